@@ -0,0 +1,342 @@
+//! Audit of the permissions an owner granted on an ERC-20 token, i.e., who set, read, and spent
+//! its allowances during the execution, as asked when debugging drained approvals.
+//!
+//! The calls to the token (`approve`, `permit`, `transferFrom`, ...) and its `Approval` events
+//! are decoded with the standard ABI. The storage slots of the allowances are recovered from the
+//! `KECCAK256` steps hashing the owner and then the spender, i.e., the layout of a Solidity
+//! `mapping(address => mapping(address => uint256))`, so that the reads and writes of the token
+//! itself are listed as well.
+
+use std::{collections::HashMap, fmt};
+
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::{sol, SolCall, SolEvent};
+use revm::interpreter::opcode;
+
+use crate::{
+    analysis::{
+        events::emitted_events,
+        shadow::stack_usize,
+        storage::{storage_accesses, StorageAccessKind},
+    },
+    artifact::debug::DebugArtifact,
+    export::calltree::CallFrame,
+};
+
+sol! {
+    function approve(address spender, uint256 amount) external returns (bool);
+    function increaseAllowance(address spender, uint256 addedValue) external returns (bool);
+    function decreaseAllowance(address spender, uint256 subtractedValue) external returns (bool);
+    function permit(
+        address owner,
+        address spender,
+        uint256 value,
+        uint256 deadline,
+        uint8 v,
+        bytes32 r,
+        bytes32 s
+    ) external;
+    function transferFrom(address from, address to, uint256 amount) external returns (bool);
+    function allowance(address owner, address spender) external view returns (uint256);
+
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+}
+
+/// What is done with an allowance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllowanceAction {
+    /// A call to `allowance(owner, spender)`, with the allowance returned.
+    Query,
+    /// A call setting the allowance, i.e., `approve`, `increaseAllowance`, `decreaseAllowance`,
+    /// or `permit`, with the amount given.
+    Approve(&'static str),
+    /// A call to `transferFrom` spending the allowance.
+    TransferFrom { to: Address },
+    /// An `Approval` event, emitted whenever the allowance is set, and by some tokens whenever
+    /// it is spent, with the new allowance.
+    Event,
+    /// A read or write of the storage slot holding the allowance, with the value.
+    Storage(StorageAccessKind),
+}
+
+impl fmt::Display for AllowanceAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Query => f.write_str("allowance"),
+            Self::Approve(function) => f.write_str(function),
+            Self::TransferFrom { .. } => f.write_str("transferFrom"),
+            Self::Event => f.write_str("Approval event"),
+            Self::Storage(StorageAccessKind::Read) => f.write_str("SLOAD"),
+            Self::Storage(StorageAccessKind::Write) => f.write_str("SSTORE"),
+        }
+    }
+}
+
+/// A use of the allowance of the owner to a spender.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AllowanceUse {
+    /// The index of the step in the whole execution, i.e., the first step of the call for calls.
+    pub step: usize,
+    pub action: AllowanceAction,
+    /// The account acting on the allowance, i.e., the `msg.sender` of the call to the token.
+    pub caller: Address,
+    pub spender: Address,
+    /// The amount involved, or `None` if it is unknown, e.g., the result of a reverted query.
+    pub amount: Option<U256>,
+    /// Whether the use is undone, i.e., made in a reverted call.
+    pub reverted: bool,
+}
+
+/// Lists the uses of the allowances granted by the owner on the token, in the order of
+/// execution. The sender of the transaction is the caller of the outermost frame.
+pub fn allowance_audit(
+    artifact: &DebugArtifact,
+    frames: &[CallFrame],
+    sender: Address,
+    token: Address,
+    owner: Address,
+) -> Vec<AllowanceUse> {
+    let mut uses = Vec::new();
+    let reverted = |step: usize| {
+        frames.iter().any(|frame| frame.steps.contains(&step) && !frame.outcome.is_success())
+    };
+
+    // the calls to the token, whose storage is the one of the frame
+    for frame in frames {
+        if frame.address != token || frame.kind.is_delegate() || frame.kind.is_any_create() {
+            continue;
+        }
+        let caller = frame.caller.unwrap_or(sender);
+        let Some((action, spender, amount)) = decode_call(frame, caller, owner) else { continue };
+        uses.push(AllowanceUse {
+            step: frame.steps.start,
+            action,
+            caller,
+            spender,
+            amount,
+            reverted: !frame.outcome.is_success() || reverted(frame.steps.start),
+        });
+    }
+
+    for event in emitted_events(artifact) {
+        let is_approval = event.address == token &&
+            event.topics.len() == 3 &&
+            event.topics[0] == Approval::SIGNATURE_HASH &&
+            event.topics[1] == owner.into_word();
+        if !is_approval {
+            continue;
+        }
+        uses.push(AllowanceUse {
+            step: event.step,
+            action: AllowanceAction::Event,
+            caller: token_caller(frames, event.step, sender),
+            spender: Address::from_word(event.topics[2]),
+            amount: event.data.get(..32).map(U256::from_be_slice),
+            reverted: reverted(event.step),
+        });
+    }
+
+    let slots = allowance_slots(artifact, token, owner);
+    for access in storage_accesses(artifact) {
+        if access.address != token {
+            continue;
+        }
+        let Some(spender) = slots.get(&access.slot) else { continue };
+        uses.push(AllowanceUse {
+            step: access.step,
+            action: AllowanceAction::Storage(access.kind),
+            caller: token_caller(frames, access.step, sender),
+            spender: *spender,
+            amount: access.value,
+            reverted: reverted(access.step),
+        });
+    }
+
+    uses.sort_by_key(|allowance_use| allowance_use.step);
+    uses
+}
+
+/// Decodes a call to the token involving the allowances of the owner.
+fn decode_call(
+    frame: &CallFrame,
+    caller: Address,
+    owner: Address,
+) -> Option<(AllowanceAction, Address, Option<U256>)> {
+    let input = &frame.input;
+    let selector: [u8; 4] = input.get(..4)?.try_into().ok()?;
+    let decoded = match selector {
+        approveCall::SELECTOR if caller == owner => {
+            let call = approveCall::abi_decode(input, false).ok()?;
+            (AllowanceAction::Approve("approve"), call.spender, Some(call.amount))
+        }
+        increaseAllowanceCall::SELECTOR if caller == owner => {
+            let call = increaseAllowanceCall::abi_decode(input, false).ok()?;
+            (AllowanceAction::Approve("increaseAllowance"), call.spender, Some(call.addedValue))
+        }
+        decreaseAllowanceCall::SELECTOR if caller == owner => {
+            let call = decreaseAllowanceCall::abi_decode(input, false).ok()?;
+            let action = AllowanceAction::Approve("decreaseAllowance");
+            (action, call.spender, Some(call.subtractedValue))
+        }
+        permitCall::SELECTOR => {
+            let call = permitCall::abi_decode(input, false).ok()?;
+            if call.owner != owner {
+                return None;
+            }
+            (AllowanceAction::Approve("permit"), call.spender, Some(call.value))
+        }
+        transferFromCall::SELECTOR => {
+            let call = transferFromCall::abi_decode(input, false).ok()?;
+            if call.from != owner {
+                return None;
+            }
+            (AllowanceAction::TransferFrom { to: call.to }, caller, Some(call.amount))
+        }
+        allowanceCall::SELECTOR => {
+            let call = allowanceCall::abi_decode(input, false).ok()?;
+            if call.owner != owner {
+                return None;
+            }
+            let returned = frame
+                .outcome
+                .is_success()
+                .then(|| allowanceCall::abi_decode_returns(&frame.output, false).ok())
+                .flatten()
+                .map(|returned| returned._0);
+            (AllowanceAction::Query, call.spender, returned)
+        }
+        _ => return None,
+    };
+    Some(decoded)
+}
+
+/// Returns the `msg.sender` of the innermost call to the token making the step, skipping the
+/// delegate calls to its implementation.
+fn token_caller(frames: &[CallFrame], step: usize, sender: Address) -> Address {
+    frames
+        .iter()
+        .rev()
+        .find(|frame| frame.steps.contains(&step) && !frame.kind.is_delegate())
+        .and_then(|frame| frame.caller)
+        .unwrap_or(sender)
+}
+
+/// Recovers the storage slots of the allowances of the owner from the hashes computed by the
+/// token, mapped to their spender: `keccak256(spender . keccak256(owner . slot))`.
+///
+/// The hash of a step is read from the stack of the next one, so that slots whose hash is not
+/// recorded, e.g., at a coarse snapshot granularity, are missed.
+fn allowance_slots(
+    artifact: &DebugArtifact,
+    token: Address,
+    owner: Address,
+) -> HashMap<U256, Address> {
+    // the preimages of the hashes of the token, i.e., the two words of each 64-byte input
+    let mut hashes: Vec<(B256, B256, U256)> = Vec::new();
+    for (node, debug_node) in artifact.debug_arena.iter().enumerate() {
+        if artifact.context_address(node) != token {
+            continue;
+        }
+        for (i, step) in debug_node.steps.iter().enumerate() {
+            if step.instruction != opcode::KECCAK256 || stack_usize(step, 1) != Some(64) {
+                continue;
+            }
+            let Some(offset) = stack_usize(step, 0) else { continue };
+            let Some(input) = step.memory.get(offset..offset + 64) else { continue };
+            // `KECCAK256` never leaves the frame, so the hash is on top of the next step's stack
+            let Some(hash) = debug_node.steps.get(i + 1).and_then(|next| next.stack.last()) else {
+                continue;
+            };
+            hashes.push((B256::from_slice(&input[..32]), B256::from_slice(&input[32..]), *hash));
+        }
+    }
+
+    // the inner mappings of the owner, whichever the slot of the outer mapping
+    let inner: Vec<B256> = hashes
+        .iter()
+        .filter(|(key, _, _)| *key == owner.into_word())
+        .map(|(_, _, hash)| B256::from(*hash))
+        .collect();
+    hashes
+        .iter()
+        .filter(|(key, base, _)| inner.contains(base) && key[..12].iter().all(|b| *b == 0))
+        .map(|(key, _, hash)| (*hash, Address::from_word(*key)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, keccak256};
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::{
+        artifact::debug::{DebugNodeFlat, DebugStep},
+        export::calltree::call_frames,
+    };
+
+    #[test]
+    fn test_allowance_audit() {
+        let (token, owner, spender, to) = (
+            address!("00000000000000000000000000000000000000aa"),
+            address!("00000000000000000000000000000000000000bb"),
+            address!("00000000000000000000000000000000000000cc"),
+            address!("00000000000000000000000000000000000000dd"),
+        );
+
+        // keccak256(owner . 1), then keccak256(spender . inner), then SLOAD of the slot
+        let inner = keccak256([owner.into_word().0, B256::with_last_byte(1).0].concat());
+        let slot = U256::from_be_bytes(keccak256([spender.into_word().0, inner.0].concat()).0);
+        let hash = |key: Address, base: B256| DebugStep {
+            instruction: opcode::KECCAK256,
+            stack: vec![U256::from(64), U256::ZERO],
+            memory: [key.into_word().0, base.0].concat().into(),
+            ..Default::default()
+        };
+        let push = |value: U256| DebugStep {
+            instruction: opcode::PUSH0,
+            stack: vec![value],
+            ..Default::default()
+        };
+        let input = transferFromCall { from: owner, to, amount: U256::from(5) }.abi_encode();
+        let token_steps = vec![
+            DebugStep { calldata: input.into(), ..hash(owner, B256::with_last_byte(1)) },
+            push(inner.into()),
+            hash(spender, inner),
+            push(slot),
+            DebugStep { instruction: opcode::SLOAD, stack: vec![slot], ..Default::default() },
+            push(U256::from(7)),
+            DebugStep { instruction: opcode::STOP, ..Default::default() },
+        ];
+        let call = DebugStep {
+            instruction: opcode::CALL,
+            stack: vec![U256::ZERO; 7],
+            ..Default::default()
+        };
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(spender, CallKind::Call, 0, vec![call]),
+                DebugNodeFlat::new(token, CallKind::Call, 1, token_steps),
+                DebugNodeFlat::new(
+                    spender,
+                    CallKind::Call,
+                    0,
+                    vec![DebugStep { instruction: opcode::STOP, ..Default::default() }],
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let frames = call_frames(&artifact);
+        let uses = allowance_audit(&artifact, &frames, Address::ZERO, token, owner);
+        assert_eq!(uses.len(), 2);
+        assert_eq!(uses[0].action, AllowanceAction::TransferFrom { to });
+        assert_eq!((uses[0].caller, uses[0].amount), (spender, Some(U256::from(5))));
+        assert_eq!(uses[1].action, AllowanceAction::Storage(StorageAccessKind::Read));
+        assert_eq!((uses[1].spender, uses[1].amount), (spender, Some(U256::from(7))));
+        assert!(uses.iter().all(|allowance_use| !allowance_use.reverted));
+
+        // the allowances of other owners are left out
+        assert!(allowance_audit(&artifact, &frames, Address::ZERO, token, to).is_empty());
+    }
+}
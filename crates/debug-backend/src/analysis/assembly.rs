@@ -0,0 +1,117 @@
+//! Inline assembly blocks of the sources, along with their Yul statements, so that the steps of
+//! an assembly block can be attributed to its statements rather than to the block as a whole.
+
+use std::ops::Range;
+
+use foundry_compilers::artifacts::{
+    visitor::{Visitor, Walk},
+    Statement,
+};
+
+use crate::{
+    analysis::source_map::{PrimativeStmtVisitor, ValidSourceLocation},
+    artifact::compilation::CompilationArtifact,
+};
+
+/// An inline assembly block of a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssemblyBlock {
+    /// The index of the source file.
+    pub index: usize,
+    /// The byte range of the block in the source file.
+    pub range: Range<usize>,
+    /// The byte ranges of the primitive Yul statements of the block, in source order.
+    pub statements: Vec<Range<usize>>,
+}
+
+impl AssemblyBlock {
+    /// Returns whether the source range is within the block.
+    pub fn contains(&self, index: usize, offset: usize, length: usize) -> bool {
+        self.index == index && self.range.start <= offset && offset + length <= self.range.end
+    }
+
+    /// Returns the statement containing the source range, if any. Steps mapped to the whole block,
+    /// e.g., the stack shuffling around it, belong to no statement.
+    pub fn statement_at(&self, offset: usize, length: usize) -> Option<usize> {
+        self.statements
+            .iter()
+            .position(|range| range.start <= offset && offset + length <= range.end)
+    }
+}
+
+/// The inline assembly blocks of a compilation, in source order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AssemblyBlocks(pub Vec<AssemblyBlock>);
+
+impl AssemblyBlocks {
+    pub fn new(artifact: &CompilationArtifact) -> Self {
+        let mut visitor = AssemblyVisitor::default();
+        for source in artifact.sources.values() {
+            source.ast.walk(&mut visitor);
+        }
+        visitor.0.sort_by_key(|block| (block.index, block.range.start));
+        Self(visitor.0)
+    }
+
+    /// Returns the innermost block containing the source range, along with its index.
+    pub fn locate(
+        &self,
+        index: usize,
+        offset: usize,
+        length: usize,
+    ) -> Option<(usize, &AssemblyBlock)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.contains(index, offset, length))
+            .min_by_key(|(_, block)| block.range.len())
+    }
+}
+
+#[derive(Debug, Default)]
+struct AssemblyVisitor(Vec<AssemblyBlock>);
+
+impl Visitor for AssemblyVisitor {
+    fn visit_statement(&mut self, statement: &Statement) {
+        let Statement::InlineAssembly(stmt) = statement else { return };
+        let Ok(src) = ValidSourceLocation::try_from(&stmt.src) else { return };
+
+        // Blocks of old compilers come without a Yul AST, and thus without statements
+        let mut yul = PrimativeStmtVisitor::new();
+        for yul_stmt in &stmt.ast.statements {
+            yul.visit_yul_statment(yul_stmt);
+        }
+        let statements = yul
+            .remove(&src.index)
+            .unwrap_or_default()
+            .into_values()
+            .map(|location| location.start..location.start + location.length)
+            .collect();
+        self.0.push(AssemblyBlock {
+            index: src.index,
+            range: src.start..src.start + src.length,
+            statements,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate_statement() {
+        let blocks = AssemblyBlocks(vec![
+            AssemblyBlock { index: 0, range: 10..100, statements: vec![20..30, 40..60] },
+            AssemblyBlock { index: 1, range: 10..100, statements: vec![] },
+        ]);
+
+        let (i, block) = blocks.locate(0, 42, 5).unwrap();
+        assert_eq!(i, 0);
+        assert_eq!(block.statement_at(42, 5), Some(1));
+        // the whole block is mapped to no statement
+        assert_eq!(block.statement_at(10, 90), None);
+        assert!(blocks.locate(0, 5, 2).is_none());
+        assert_eq!(blocks.locate(1, 20, 1).map(|(i, _)| i), Some(1));
+    }
+}
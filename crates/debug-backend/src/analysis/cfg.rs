@@ -0,0 +1,285 @@
+//! Control-flow graph of runtime bytecode, for reverse engineering unverified contracts at the
+//! bytecode level.
+//!
+//! The code is disassembled by a linear sweep, except for the trailing CBOR metadata appended by
+//! solc, and the blocks which cannot be reached from the entry point by a recursive traversal
+//! are marked as such, since they usually hold embedded data (e.g., constants) rather than code.
+//!
+//! Jump targets are only resolved when they are pushed right before the jump, which covers most
+//! of the code emitted by solc. Other jumps, e.g., internal function returns, are marked as
+//! dynamic.
+
+use std::{collections::BTreeMap, ops::Range};
+
+use alloy_primitives::{Bytes, Selector};
+use revm::interpreter::{opcode, OpCode};
+use rustc_hash::FxHashSet;
+
+use crate::utils::opcode::disassemble;
+
+/// An instruction of a basic block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockInstruction {
+    pub pc: usize,
+    pub opcode: u8,
+    /// The immediate bytes, e.g., the bytes pushed by `PUSHn`.
+    pub immediate: Bytes,
+}
+
+impl BlockInstruction {
+    /// Returns the name of the opcode, followed by its immediate if any, e.g., `PUSH1(0x80)`.
+    pub fn pretty(&self) -> String {
+        let name = OpCode::new(self.opcode).map_or("INVALID", |op| op.as_str());
+        if self.immediate.is_empty() {
+            name.to_string()
+        } else {
+            format!("{name}({})", self.immediate)
+        }
+    }
+}
+
+/// A maximal sequence of instructions which are always executed one after the other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub instructions: Vec<BlockInstruction>,
+    /// The first program counter of the blocks which may be executed next: the jump target
+    /// first, then the fall-through block.
+    pub successors: Vec<usize>,
+    /// Whether the block ends with a jump whose target is not known statically.
+    pub dynamic_jump: bool,
+    /// Whether the block can be reached from the entry point. Unreachable blocks are usually
+    /// embedded data mistaken for code.
+    pub reachable: bool,
+}
+
+impl BasicBlock {
+    /// Returns the program counter of the first instruction.
+    pub fn start(&self) -> usize {
+        self.instructions[0].pc
+    }
+
+    /// Returns the program counter of the last instruction.
+    pub fn end(&self) -> usize {
+        self.instructions[self.instructions.len() - 1].pc
+    }
+}
+
+/// The control-flow graph of a bytecode, whose blocks are sorted by program counter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    /// The entry points of the public functions, found in the dispatcher, with their selector.
+    pub functions: BTreeMap<usize, Selector>,
+    /// The range of the CBOR metadata appended by solc, which is not disassembled.
+    pub metadata: Option<Range<usize>>,
+    jumpdests: FxHashSet<usize>,
+}
+
+impl ControlFlowGraph {
+    /// Builds the control-flow graph of the given bytecode.
+    pub fn new(code: &[u8]) -> Self {
+        let metadata = metadata_range(code);
+        let instructions = disassemble(&code[..metadata.as_ref().map_or(code.len(), |m| m.start)]);
+        let jumpdests: FxHashSet<usize> = instructions
+            .iter()
+            .filter(|instruction| instruction.opcode == opcode::JUMPDEST)
+            .map(|instruction| instruction.pc)
+            .collect();
+
+        // the dispatcher compares the selector with each function's, e.g.,
+        // `DUP1 PUSH4 <selector> EQ PUSH2 <entry> JUMPI`, or `PUSH4 <selector> DUP2 EQ ...`
+        let mut functions = BTreeMap::new();
+        for (i, push) in instructions.iter().enumerate() {
+            if push.opcode != opcode::PUSH4 || push.immediate.len() != 4 {
+                continue;
+            }
+            let j = if instructions.get(i + 1).map(|i| i.opcode) == Some(opcode::DUP2) {
+                i + 2
+            } else {
+                i + 1
+            };
+            let Some([eq, target, jumpi]) = instructions.get(j..j + 3) else { continue };
+            if eq.opcode != opcode::EQ || jumpi.opcode != opcode::JUMPI {
+                continue;
+            }
+            if let Some(entry) = push_value(target.opcode, target.immediate) {
+                if jumpdests.contains(&entry) {
+                    functions.insert(entry, Selector::from_slice(push.immediate));
+                }
+            }
+        }
+
+        // split the code before each jump destination and after each terminating instruction
+        let mut blocks: Vec<Vec<BlockInstruction>> = Vec::new();
+        let mut current: Vec<BlockInstruction> = Vec::new();
+        for instruction in instructions {
+            if instruction.opcode == opcode::JUMPDEST && !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+            let terminates = is_terminator(instruction.opcode);
+            current.push(BlockInstruction {
+                pc: instruction.pc,
+                opcode: instruction.opcode,
+                immediate: Bytes::copy_from_slice(instruction.immediate),
+            });
+            if terminates {
+                blocks.push(std::mem::take(&mut current));
+            }
+        }
+        if !current.is_empty() {
+            blocks.push(current);
+        }
+
+        let starts: Vec<usize> = blocks.iter().map(|block| block[0].pc).collect();
+        let blocks = blocks
+            .into_iter()
+            .enumerate()
+            .map(|(index, instructions)| {
+                let last = &instructions[instructions.len() - 1];
+                let mut successors = Vec::new();
+                let mut dynamic_jump = false;
+                if matches!(last.opcode, opcode::JUMP | opcode::JUMPI) {
+                    let target = instructions
+                        .len()
+                        .checked_sub(2)
+                        .and_then(|i| {
+                            push_value(instructions[i].opcode, &instructions[i].immediate)
+                        })
+                        .filter(|target| jumpdests.contains(target));
+                    match target {
+                        Some(target) => successors.push(target),
+                        None => dynamic_jump = true,
+                    }
+                }
+                let falls_through = last.opcode == opcode::JUMPI || !is_terminator(last.opcode);
+                if falls_through {
+                    successors.extend(starts.get(index + 1));
+                }
+                BasicBlock { instructions, successors, dynamic_jump, reachable: false }
+            })
+            .collect::<Vec<_>>();
+
+        let mut cfg = Self { blocks, functions, metadata, jumpdests };
+        cfg.mark_reachable();
+        cfg
+    }
+
+    /// Marks the blocks reachable from the entry point. Any jump destination may be the target
+    /// of a dynamic jump, so that they are all reachable once a dynamic jump is.
+    fn mark_reachable(&mut self) {
+        let mut pending = vec![0];
+        let mut dynamic = false;
+        while let Some(index) = pending.pop() {
+            let Some(block) = self.blocks.get_mut(index) else { continue };
+            if block.reachable {
+                continue;
+            }
+            block.reachable = true;
+            if block.dynamic_jump && !dynamic {
+                dynamic = true;
+                pending.extend(
+                    (0..self.blocks.len())
+                        .filter(|i| self.blocks[*i].instructions[0].opcode == opcode::JUMPDEST),
+                );
+            }
+            let successors = self.blocks[index].successors.clone();
+            pending.extend(successors.into_iter().filter_map(|pc| self.block_at(pc)));
+        }
+    }
+
+    /// Returns the symbolic label of the jump destination, i.e., the function it is the entry
+    /// point of, e.g., `fn_a9059cbb`, or `block_0123` otherwise.
+    pub fn label(&self, pc: usize) -> Option<String> {
+        if let Some(selector) = self.functions.get(&pc) {
+            return Some(format!("fn_{}", alloy_primitives::hex::encode(selector)));
+        }
+        self.jumpdests.contains(&pc).then(|| format!("block_{pc:04x}"))
+    }
+
+    /// Returns the label of the jump destination pushed by the instruction, if it pushes one.
+    pub fn push_target_label(&self, opcode: u8, immediate: &[u8]) -> Option<String> {
+        push_value(opcode, immediate).and_then(|pc| self.label(pc))
+    }
+
+    /// Returns the index of the block containing the given program counter.
+    pub fn block_at(&self, pc: usize) -> Option<usize> {
+        let index = self.blocks.partition_point(|block| block.start() <= pc).checked_sub(1)?;
+        (self.blocks[index].end() >= pc).then_some(index)
+    }
+}
+
+/// Returns the value pushed by a `PUSHn` instruction, if it fits in a program counter.
+fn push_value(op: u8, immediate: &[u8]) -> Option<usize> {
+    if !(opcode::PUSH1..=opcode::PUSH32).contains(&op) {
+        return None;
+    }
+    immediate
+        .iter()
+        .skip_while(|b| **b == 0)
+        .try_fold(0usize, |value, b| value.checked_mul(256).map(|v| v + *b as usize))
+}
+
+/// Returns the range of the CBOR metadata appended by solc, whose length is encoded in the last
+/// two bytes of the code.
+fn metadata_range(code: &[u8]) -> Option<Range<usize>> {
+    let [.., hi, lo] = code else { return None };
+    let start = code.len().checked_sub(2 + u16::from_be_bytes([*hi, *lo]) as usize)?;
+    // the metadata is a CBOR map, e.g., `a2 64 "ipfs" ...`
+    matches!(code[start], 0xa1..=0xa7).then_some(start..code.len())
+}
+
+/// Returns whether the execution never continues with the next instruction.
+fn is_terminator(op: u8) -> bool {
+    matches!(
+        op,
+        opcode::JUMP |
+            opcode::JUMPI |
+            opcode::STOP |
+            opcode::RETURN |
+            opcode::REVERT |
+            opcode::INVALID |
+            opcode::SELFDESTRUCT
+    ) || OpCode::new(op).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cfg() {
+        // PUSH1 4 JUMP STOP JUMPDEST PUSH1 0 CALLDATALOAD PUSH1 12 JUMPI STOP JUMPDEST JUMP
+        let code =
+            [0x60, 0x04, 0x56, 0x00, 0x5b, 0x60, 0x00, 0x35, 0x60, 0x0c, 0x57, 0x00, 0x5b, 0x56];
+        let cfg = ControlFlowGraph::new(&code);
+        let starts: Vec<_> = cfg.blocks.iter().map(|block| block.start()).collect();
+        assert_eq!(starts, vec![0, 3, 4, 11, 12]);
+        assert_eq!(cfg.blocks[0].successors, vec![4]);
+        assert!(cfg.blocks[1].successors.is_empty());
+        assert_eq!(cfg.blocks[2].successors, vec![12, 11]);
+        assert!(cfg.blocks[4].dynamic_jump);
+        assert_eq!(cfg.blocks[2].instructions[1].pretty(), "PUSH1(0x00)");
+
+        assert_eq!(cfg.block_at(7), Some(2));
+        assert_eq!(cfg.block_at(12), Some(4));
+        assert_eq!(cfg.block_at(20), None);
+        // the STOP after the unconditional jump is dead code
+        assert!(!cfg.blocks[1].reachable);
+        assert!(cfg.blocks[3].reachable);
+    }
+
+    #[test]
+    fn test_dispatcher_and_metadata() {
+        // DUP1 PUSH4 a9059cbb EQ PUSH1 0x0c JUMPI STOP JUMPDEST STOP, then metadata `a1 00` + len
+        let code = [
+            0x80, 0x63, 0xa9, 0x05, 0x9c, 0xbb, 0x14, 0x60, 0x0c, 0x57, 0x00, 0x00, 0x5b, 0x00,
+            0xa1, 0x00, 0x00, 0x02,
+        ];
+        let cfg = ControlFlowGraph::new(&code);
+        assert_eq!(cfg.metadata, Some(14..18));
+        assert_eq!(cfg.blocks.last().unwrap().end(), 13);
+        assert_eq!(cfg.label(12).as_deref(), Some("fn_a9059cbb"));
+        assert_eq!(cfg.push_target_label(0x60, &[0x0c]).as_deref(), Some("fn_a9059cbb"));
+        assert_eq!(cfg.push_target_label(0x60, &[0x0d]), None);
+    }
+}
@@ -0,0 +1,65 @@
+//! Detect minimal proxies (EIP-1167), i.e., clones forwarding every call to an implementation by
+//! `DELEGATECALL`, which factories deploy by the thousands and which have no source code.
+
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, Bytes};
+
+/// The code of a minimal proxy up to the address of its implementation.
+const PREFIX: &[u8] = &[0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d];
+
+/// The code of a minimal proxy following the address of its implementation, up to the jump
+/// destination, which depends on the length of the address.
+const SUFFIX: &[u8] = &[0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60];
+
+/// The end of the code of a minimal proxy, following its jump destination.
+const END: &[u8] = &[0x57, 0xfd, 0x5b, 0xf3];
+
+/// Returns the implementation of the minimal proxy with the given runtime code, if it is one.
+/// Vanity implementation addresses with leading zero bytes are pushed with shorter `PUSH`es.
+pub fn minimal_proxy_target(code: &[u8]) -> Option<Address> {
+    let rest = code.strip_prefix(PREFIX)?;
+    let (push, rest) = rest.split_first()?;
+    let len = push.checked_sub(0x5f).filter(|len| (1..=20).contains(len))? as usize;
+    let (address, rest) = (rest.get(..len)?, rest.get(len..)?);
+    let rest = rest.strip_prefix(SUFFIX)?;
+    (rest.len() == END.len() + 1 && rest[1..] == *END).then(|| {
+        let mut target = [0u8; 20];
+        target[20 - len..].copy_from_slice(address);
+        Address::from(target)
+    })
+}
+
+/// Returns the implementation of each minimal proxy among the given contracts, by address.
+pub fn detect_clones(codes: &HashMap<Address, Bytes>) -> HashMap<Address, Address> {
+    codes
+        .iter()
+        .filter_map(|(address, code)| Some((*address, minimal_proxy_target(code)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, hex};
+
+    use super::*;
+
+    #[test]
+    fn test_minimal_proxy_target() {
+        let code = hex!(
+            "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3"
+        );
+        let target = address!("bebebebebebebebebebebebebebebebebebebebe");
+        assert_eq!(minimal_proxy_target(&code), Some(target));
+
+        // a vanity address with 4 leading zero bytes
+        let code = hex!(
+            "363d3d373d3d3d363d6fbebebebebebebebebebebebebebebebe5af43d82803e903d91602757fd5bf3"
+        );
+        let target = address!("00000000bebebebebebebebebebebebebebebebe");
+        assert_eq!(minimal_proxy_target(&code), Some(target));
+
+        assert_eq!(minimal_proxy_target(&code[..code.len() - 1]), None);
+        assert_eq!(minimal_proxy_target(&hex!("6080604052")), None);
+    }
+}
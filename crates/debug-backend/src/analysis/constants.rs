@@ -0,0 +1,164 @@
+//! Symbolic names of well-known constants, e.g., `type(uint256).max`, function selectors, event
+//! topics, role hashes and the addresses of popular contracts.
+
+use alloy_primitives::{address, keccak256, Address, U256};
+use rustc_hash::FxHashMap;
+
+use crate::artifact::debug::DebugArtifact;
+
+/// Well-known function signatures, in addition to the ones in the known ABIs.
+const FUNCTIONS: &[&str] = &[
+    "transfer(address,uint256)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "balanceOf(address)",
+    "allowance(address,address)",
+    "totalSupply()",
+    "decimals()",
+    "symbol()",
+    "name()",
+    "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)",
+    "safeTransferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256,bytes)",
+    "ownerOf(uint256)",
+    "supportsInterface(bytes4)",
+    "deposit()",
+    "withdraw(uint256)",
+    "Error(string)",
+    "Panic(uint256)",
+];
+
+/// Well-known event signatures, in addition to the ones in the known ABIs.
+const EVENTS: &[&str] = &[
+    "Transfer(address,address,uint256)",
+    "Approval(address,address,uint256)",
+    "ApprovalForAll(address,address,bool)",
+    "OwnershipTransferred(address,address)",
+    "RoleGranted(bytes32,address,address)",
+    "RoleRevoked(bytes32,address,address)",
+    "Upgraded(address)",
+];
+
+/// Well-known roles of `AccessControl`. Note that `DEFAULT_ADMIN_ROLE` is zero and is therefore
+/// not annotated.
+const ROLES: &[&str] = &[
+    "MINTER_ROLE",
+    "BURNER_ROLE",
+    "PAUSER_ROLE",
+    "UPGRADER_ROLE",
+    "OPERATOR_ROLE",
+    "ADMIN_ROLE",
+    "GOVERNOR_ROLE",
+    "EXECUTOR_ROLE",
+    "PROPOSER_ROLE",
+    "CANCELLER_ROLE",
+    "TIMELOCK_ADMIN_ROLE",
+];
+
+/// Well-known storage slots, given as the preimage of the hash minus one (EIP-1967).
+const EIP1967_SLOTS: &[(&str, &str)] = &[
+    ("eip1967.proxy.implementation", "EIP-1967 implementation slot"),
+    ("eip1967.proxy.admin", "EIP-1967 admin slot"),
+    ("eip1967.proxy.beacon", "EIP-1967 beacon slot"),
+];
+
+/// Addresses of popular contracts on Ethereum mainnet.
+const ADDRESSES: &[(Address, &str)] = &[
+    (address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"), "WETH"),
+    (address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"), "USDC"),
+    (address!("dAC17F958D2ee523a2206206994597C13D831ec7"), "USDT"),
+    (address!("6B175474E89094C44Da98b954EedeAC495271d0F"), "DAI"),
+    (address!("2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"), "WBTC"),
+    (address!("000000000022D473030F116dDEE9F6B43aC78BA3"), "Permit2"),
+    (address!("cA11bde05977b3631167028862bE2a173976CA11"), "Multicall3"),
+    (address!("5FF137D4b0FDCD49DcA30c7CF57E578a026d2789"), "EntryPoint v0.6"),
+    (address!("0000000071727De22E5E9d8BAf0edAc6f37da032"), "EntryPoint v0.7"),
+];
+
+/// A dictionary of the symbolic names of constants.
+#[derive(Clone, Debug, Default)]
+pub struct ConstantNames {
+    names: FxHashMap<U256, String>,
+}
+
+impl ConstantNames {
+    /// Builds the dictionary from the well-known constants and the ABIs of the artifact.
+    pub fn new(artifact: &DebugArtifact) -> Self {
+        let mut names = FxHashMap::default();
+
+        names.insert(U256::MAX, "type(uint256).max".to_string());
+        names.insert(U256::from(u128::MAX), "type(uint128).max".to_string());
+        names.insert(U256::from(u64::MAX), "type(uint64).max".to_string());
+        names.insert(U256::from(1) << 255, "type(int256).min".to_string());
+        names.insert(U256::MAX >> 1, "type(int256).max".to_string());
+        names.insert(U256::from(10).pow(U256::from(18)), "1 ether".to_string());
+
+        let mut selector = |signature: &str, name: String| {
+            let selector = U256::from_be_slice(&keccak256(signature)[..4]);
+            // Selectors show up both as integers and left-aligned in a word
+            names.entry(selector << 224).or_insert_with(|| name.clone());
+            names.entry(selector).or_insert(name);
+        };
+        for artifact in artifact.compilation_artifacts.values() {
+            for function in artifact.abi.functions() {
+                let signature = function.signature();
+                selector(&signature, format!("{}.{signature}", artifact.contract_name));
+            }
+            for error in artifact.abi.errors() {
+                let signature = error.signature();
+                selector(&signature, format!("{}.{signature}", artifact.contract_name));
+            }
+        }
+        for signature in FUNCTIONS {
+            selector(signature, signature.to_string());
+        }
+
+        for artifact in artifact.compilation_artifacts.values() {
+            for event in artifact.abi.events() {
+                let name = format!("{}.{} topic", artifact.contract_name, event.signature());
+                names.entry(U256::from_be_bytes(event.selector().0)).or_insert(name);
+            }
+        }
+        for signature in EVENTS {
+            names
+                .entry(U256::from_be_bytes(keccak256(signature).0))
+                .or_insert_with(|| format!("{signature} topic"));
+        }
+
+        for role in ROLES {
+            names.entry(U256::from_be_bytes(keccak256(role).0)).or_insert_with(|| role.to_string());
+        }
+        for (preimage, name) in EIP1967_SLOTS {
+            let slot = U256::from_be_bytes(keccak256(preimage).0) - U256::from(1);
+            names.insert(slot, name.to_string());
+        }
+        for (address, name) in ADDRESSES {
+            names.insert(U256::from_be_bytes(address.into_word().0), name.to_string());
+        }
+
+        Self { names }
+    }
+
+    /// Returns the symbolic name of the given value, if it is a known constant.
+    pub fn get(&self, value: &U256) -> Option<&str> {
+        self.names.get(value).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_names() {
+        let names = ConstantNames::new(&DebugArtifact::default());
+
+        assert_eq!(names.get(&U256::MAX), Some("type(uint256).max"));
+        assert_eq!(names.get(&U256::from(0xa9059cbbu32)), Some("transfer(address,uint256)"));
+        assert_eq!(
+            names.get(&(U256::from(0xa9059cbbu32) << 224)),
+            Some("transfer(address,uint256)")
+        );
+        assert_eq!(names.get(&U256::from(1)), None);
+    }
+}
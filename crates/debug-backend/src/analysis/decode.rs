@@ -0,0 +1,142 @@
+//! Decoding of the calls and events of the execution in a background thread, so that a session
+//! opens before all of them are decoded, e.g., on transactions touching hundreds of contracts.
+//! The decoded calls and events are streamed as they are decoded, in the order of execution.
+
+use std::{
+    collections::HashMap,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use alloy_dyn_abi::JsonAbiExt;
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::{Address, Bytes};
+
+use crate::{
+    analysis::events::EmittedEvent,
+    artifact::debug::DebugArtifact,
+    export::calltree::{format_value, CallFrame},
+};
+
+/// A call or an event, decoded with the ABI of its contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Decoded {
+    /// The call of the frame with the given index, e.g., `transfer(0x…, 1)`.
+    Call { frame: usize, call: String },
+    /// The event with the given index, e.g., `Transfer(from: 0x…, to: 0x…, value: 1)`.
+    Event { index: usize, event: String },
+}
+
+/// The calls and events to decode, along with the ABIs of their contracts, which are copied so
+/// that the session is not borrowed while they are decoded.
+#[derive(Clone, Debug, Default)]
+pub struct DecodeJob {
+    abis: HashMap<Address, JsonAbi>,
+    calls: Vec<(usize, Address, Bytes)>,
+    events: Vec<(usize, EmittedEvent)>,
+}
+
+impl DecodeJob {
+    /// Collects the calls of the frames and the events whose contracts have a known ABI.
+    pub fn new(artifact: &DebugArtifact, frames: &[CallFrame], events: &[EmittedEvent]) -> Self {
+        let mut abis = HashMap::new();
+        let mut known = |address: Address| {
+            if !abis.contains_key(&address) {
+                let Some(abi) = artifact.abi(&address) else { return false };
+                abis.insert(address, abi.clone());
+            }
+            true
+        };
+
+        let calls = frames
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| !frame.kind.is_any_create() && frame.input.len() >= 4)
+            .filter(|(_, frame)| known(frame.address))
+            .map(|(index, frame)| (index, frame.address, frame.input.clone()))
+            .collect();
+        let events = events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| known(event.address))
+            .map(|(index, event)| (index, event.clone()))
+            .collect();
+        Self { abis, calls, events }
+    }
+
+    /// Decodes the calls, then the events, sending each as soon as it is decoded. Stops early if
+    /// the receiver is dropped.
+    pub fn run(self, sender: Sender<Decoded>) {
+        for (frame, address, input) in &self.calls {
+            let Some(call) = decode_call(&self.abis[address], input) else { continue };
+            if sender.send(Decoded::Call { frame: *frame, call }).is_err() {
+                return;
+            }
+        }
+        for (index, event) in &self.events {
+            let Some(decoded) = event.decode(&self.abis[&event.address]) else { continue };
+            if sender.send(Decoded::Event { index: *index, event: decoded }).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Runs the job in a background thread, and returns the channel the results are streamed on.
+    /// The channel is disconnected once everything is decoded.
+    pub fn spawn(self) -> Receiver<Decoded> {
+        let (sender, receiver) = mpsc::channel();
+        let spawned = thread::Builder::new().name("decoder".into()).spawn(move || self.run(sender));
+        if let Err(e) = spawned {
+            warn!("failed to spawn the decoder thread: {e}");
+        }
+        receiver
+    }
+}
+
+/// Decodes a call with the ABI of the called contract, e.g., `transfer(0x…, 1)`.
+fn decode_call(abi: &JsonAbi, input: &[u8]) -> Option<String> {
+    let function = abi.functions().find(|function| function.selector()[..] == input[..4])?;
+    let args = function.abi_decode_input(&input[4..], false).ok()?;
+    let args = args.iter().map(format_value).collect::<Vec<_>>();
+    Some(format!("{}({})", function.name, args.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, U256};
+
+    use super::*;
+
+    #[test]
+    fn test_decode_call() {
+        let abi: JsonAbi = serde_json::from_str(
+            r#"[{"type":"function","name":"transfer","stateMutability":"nonpayable",
+                "inputs":[{"name":"to","type":"address"},{"name":"amount","type":"uint256"}],
+                "outputs":[{"name":"","type":"bool"}]}]"#,
+        )
+        .unwrap();
+        let to = address!("00000000000000000000000000000000000000bb");
+        let function = abi.functions().next().unwrap();
+        let input = function.abi_encode_input(&[to.into(), U256::from(7).into()]).unwrap();
+
+        assert_eq!(
+            decode_call(&abi, &input),
+            Some(format!("transfer({}, 7)", to.to_checksum(None)))
+        );
+        assert_eq!(decode_call(&abi, &[0; 4]), None);
+
+        let job = DecodeJob {
+            abis: HashMap::from([(to, abi)]),
+            calls: vec![(3, to, input.into())],
+            events: Vec::new(),
+        };
+        let decoded = job.spawn().iter().collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            vec![Decoded::Call {
+                frame: 3,
+                call: format!("transfer({}, 7)", to.to_checksum(None))
+            }]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Reads of the environment of the transaction, i.e., the external factors, such as the block or
+//! the state of other accounts, which could change its outcome if it were included elsewhere.
+
+use std::fmt;
+
+use alloy_primitives::{Address, U256};
+use revm::interpreter::opcode;
+
+use crate::artifact::debug::DebugArtifact;
+
+/// Well-known read functions of price oracles and pools, by selector.
+const ORACLE_SELECTORS: &[([u8; 4], &str)] = &[
+    ([0xfe, 0xaf, 0x96, 0x8c], "latestRoundData()"),
+    ([0x50, 0xd2, 0x5b, 0xcd], "latestAnswer()"),
+    ([0x9a, 0x6f, 0xc8, 0xf5], "getRoundData(uint80)"),
+    ([0x09, 0x02, 0xf1, 0xac], "getReserves()"),
+    ([0x38, 0x50, 0xc7, 0xbd], "slot0()"),
+    ([0x88, 0x3b, 0xdb, 0xfd], "observe(uint32[])"),
+];
+
+/// The kind of an external dependency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DependencyKind {
+    Timestamp,
+    BlockNumber,
+    Prevrandao,
+    Coinbase,
+    BaseFee,
+    BlobBaseFee,
+    GasLimit,
+    GasPrice,
+    ChainId,
+    BlockHash,
+    /// The balance of another account than the one executing.
+    Balance,
+    /// An `EXTCODESIZE` check, e.g., whether an account is a contract.
+    CodeSize,
+    CodeHash,
+    /// A call to a well-known read function of a price oracle or a pool.
+    OracleCall(&'static str),
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timestamp => write!(f, "block timestamp"),
+            Self::BlockNumber => write!(f, "block number"),
+            Self::Prevrandao => write!(f, "prevrandao"),
+            Self::Coinbase => write!(f, "coinbase"),
+            Self::BaseFee => write!(f, "base fee"),
+            Self::BlobBaseFee => write!(f, "blob base fee"),
+            Self::GasLimit => write!(f, "block gas limit"),
+            Self::GasPrice => write!(f, "gas price"),
+            Self::ChainId => write!(f, "chain id"),
+            Self::BlockHash => write!(f, "block hash"),
+            Self::Balance => write!(f, "balance of another account"),
+            Self::CodeSize => write!(f, "code size check"),
+            Self::CodeHash => write!(f, "code hash check"),
+            Self::OracleCall(function) => write!(f, "oracle call {function}"),
+        }
+    }
+}
+
+/// A read of the environment by the transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExternalDependency {
+    /// The index of the step making the read in the whole execution, which is the first step of
+    /// the callee for oracle calls.
+    pub step: usize,
+    pub kind: DependencyKind,
+    /// The account reading the environment, in whose context the step is executed.
+    pub reader: Address,
+    /// The account whose balance or code is read, or the oracle called.
+    pub target: Option<Address>,
+    /// The value read, which is only known from the stack of the next step, so that it is
+    /// `None` if the execution halts right after the read, and for oracle calls.
+    pub value: Option<U256>,
+}
+
+/// Collects the reads of the environment in the order of execution.
+pub fn external_dependencies(artifact: &DebugArtifact) -> Vec<ExternalDependency> {
+    let mut dependencies = Vec::new();
+    let mut previous_depth = None;
+    for (index, (node, i, step)) in artifact.steps().enumerate() {
+        let flat = &artifact.debug_arena[node];
+        let reader = artifact.context_address(node);
+
+        // The first step of a new call frame tells the function called from its calldata
+        if i == 0 && previous_depth.map_or(false, |depth| flat.depth > depth) {
+            let function = step.calldata.get(..4).and_then(|selector| {
+                ORACLE_SELECTORS.iter().find(|(known, _)| known == selector).map(|(_, f)| *f)
+            });
+            if let Some(function) = function {
+                dependencies.push(ExternalDependency {
+                    step: index,
+                    kind: DependencyKind::OracleCall(function),
+                    reader: artifact.context_address(node - 1),
+                    target: Some(flat.address),
+                    value: None,
+                });
+            }
+        }
+        previous_depth = Some(flat.depth);
+
+        let top = step.stack.last().copied();
+        let target = top.map(|word| Address::from_word(word.to_be_bytes::<32>().into()));
+        let (kind, target) = match step.instruction {
+            opcode::TIMESTAMP => (DependencyKind::Timestamp, None),
+            opcode::NUMBER => (DependencyKind::BlockNumber, None),
+            opcode::DIFFICULTY => (DependencyKind::Prevrandao, None),
+            opcode::COINBASE => (DependencyKind::Coinbase, None),
+            opcode::BASEFEE => (DependencyKind::BaseFee, None),
+            opcode::BLOBBASEFEE => (DependencyKind::BlobBaseFee, None),
+            opcode::GASLIMIT => (DependencyKind::GasLimit, None),
+            opcode::GASPRICE => (DependencyKind::GasPrice, None),
+            opcode::CHAINID => (DependencyKind::ChainId, None),
+            opcode::BLOCKHASH => (DependencyKind::BlockHash, None),
+            opcode::BALANCE if target != Some(reader) => (DependencyKind::Balance, target),
+            opcode::EXTCODESIZE => (DependencyKind::CodeSize, target),
+            opcode::EXTCODEHASH => (DependencyKind::CodeHash, target),
+            _ => continue,
+        };
+        // These instructions never leave the frame, so the value is on top of the next step
+        let value = flat.steps.get(i + 1).and_then(|next| next.stack.last().copied());
+        dependencies.push(ExternalDependency { step: index, kind, reader, target, value });
+    }
+    dependencies
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    fn step(instruction: u8, stack: &[U256]) -> DebugStep {
+        DebugStep { instruction, stack: stack.to_vec(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_external_dependencies() {
+        let (reader, oracle) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let word = |address: Address| U256::from_be_slice(address.as_slice());
+        let callee = DebugStep {
+            calldata: Bytes::from_static(&[0xfe, 0xaf, 0x96, 0x8c]),
+            ..Default::default()
+        };
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(
+                    reader,
+                    CallKind::Call,
+                    0,
+                    vec![
+                        step(opcode::TIMESTAMP, &[]),
+                        step(opcode::POP, &[U256::from(1_700_000_000)]),
+                        // its own balance is not a dependency
+                        step(opcode::BALANCE, &[word(reader)]),
+                        step(opcode::EXTCODESIZE, &[U256::from(5), word(oracle)]),
+                        step(opcode::STATICCALL, &[U256::ZERO]),
+                    ],
+                ),
+                DebugNodeFlat::new(oracle, CallKind::StaticCall, 1, vec![callee]),
+                DebugNodeFlat::new(reader, CallKind::Call, 0, vec![step(opcode::STOP, &[])]),
+            ],
+            ..Default::default()
+        };
+
+        let dependencies = external_dependencies(&artifact);
+        let kinds: Vec<_> = dependencies.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DependencyKind::Timestamp,
+                DependencyKind::CodeSize,
+                DependencyKind::OracleCall("latestRoundData()")
+            ]
+        );
+        assert_eq!(dependencies[0].value, Some(U256::from(1_700_000_000)));
+        assert_eq!((dependencies[1].step, dependencies[1].target), (3, Some(oracle)));
+        assert_eq!((dependencies[2].reader, dependencies[2].target), (reader, Some(oracle)));
+    }
+}
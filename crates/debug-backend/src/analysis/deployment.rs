@@ -0,0 +1,255 @@
+//! Derive the addresses of the contracts deployed during the execution, so that the address
+//! each creation frame ended up at can be checked against the one its deployer meant.
+//!
+//! `CREATE2` addresses only depend on the deployer, the salt, and the hash of the init code,
+//! which are read from the creating step. `CREATE` addresses depend on the nonce of the
+//! deployer instead, which is counted from its nonce before the transaction along the creations
+//! it makes, rolling back the ones made within reverted frames.
+
+use std::collections::HashMap;
+
+use alloy_primitives::{keccak256, Address, Bytes, B256};
+use revm_inspectors::tracing::types::CallKind;
+
+use crate::{
+    analysis::shadow::stack_usize, artifact::debug::DebugArtifact, export::calltree::CallFrame,
+};
+
+/// The init code of a `CREATE2` deployment, or only its hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InitCode {
+    Code(Bytes),
+    Hash(B256),
+}
+
+impl InitCode {
+    /// Parses init code from hex, which is taken for its hash if it is 32 bytes long.
+    pub fn parse(input: &str) -> Option<Self> {
+        let bytes: Bytes = input.parse().ok()?;
+        Some(match bytes.len() {
+            32 => Self::Hash(B256::from_slice(&bytes)),
+            _ => Self::Code(bytes),
+        })
+    }
+
+    pub fn hash(&self) -> B256 {
+        match self {
+            Self::Code(code) => keccak256(code),
+            Self::Hash(hash) => *hash,
+        }
+    }
+}
+
+/// Derives the address of a contract deployed by `CREATE`, i.e., from the nonce of the deployer.
+pub fn create_address(deployer: Address, nonce: u64) -> Address {
+    deployer.create(nonce)
+}
+
+/// Derives the address of a contract deployed by `CREATE2`.
+pub fn create2_address(deployer: Address, salt: B256, init_code: &InitCode) -> Address {
+    deployer.create2(salt, init_code.hash())
+}
+
+/// A contract deployed by `CREATE2`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Create2Deployment {
+    /// The index of the creation frame.
+    pub frame: usize,
+    pub deployer: Address,
+    pub salt: B256,
+    pub init_code_hash: B256,
+    /// The address derived from the deployer, the salt, and the init code.
+    pub derived: Address,
+    /// The address the contract was actually created at.
+    pub created: Address,
+}
+
+impl Create2Deployment {
+    /// Returns whether the contract was created at the derived address.
+    pub fn is_verified(&self) -> bool {
+        self.derived == self.created
+    }
+}
+
+/// A contract deployed by `CREATE`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreateDeployment {
+    /// The index of the creation frame.
+    pub frame: usize,
+    pub deployer: Address,
+    /// The nonce the deployer created the contract with, if its nonce before the transaction is
+    /// known and derives the created address.
+    pub nonce: Option<u64>,
+    pub created: Address,
+}
+
+/// Finds the `CREATE` deployments among the given call frames, in the order of execution, along
+/// with the nonces of their deployers.
+pub fn create_deployments(artifact: &DebugArtifact, frames: &[CallFrame]) -> Vec<CreateDeployment> {
+    let mut deployments = Vec::new();
+    if !frames.is_empty() {
+        let mut nonces = artifact.nonces.clone();
+        walk_creations(frames, 0, &mut nonces, &mut deployments);
+    }
+    deployments
+}
+
+/// Walks the frame and its sub-calls in the order of execution, bumping the nonce of the deployer
+/// of each creation, and rolling back the nonces bumped within the frame if it reverted.
+fn walk_creations(
+    frames: &[CallFrame],
+    index: usize,
+    nonces: &mut HashMap<Address, u64>,
+    deployments: &mut Vec<CreateDeployment>,
+) {
+    let frame = &frames[index];
+    let checkpoint = frame.is_reverted().then(|| nonces.clone());
+    if frame.kind.is_any_create() {
+        // contracts start with a nonce of one (EIP-161)
+        nonces.insert(frame.address, 1);
+    }
+    for &child in &frame.children {
+        let created = &frames[child];
+        if let (true, Some(deployer)) = (created.kind.is_any_create(), created.caller) {
+            // the nonce is bumped before the init code runs, and stays bumped if it fails
+            let nonce = nonces.get_mut(&deployer).map(|nonce| {
+                *nonce += 1;
+                *nonce - 1
+            });
+            if created.kind == CallKind::Create {
+                deployments.push(CreateDeployment {
+                    frame: child,
+                    deployer,
+                    nonce: nonce.filter(|nonce| deployer.create(*nonce) == created.address),
+                    created: created.address,
+                });
+            }
+        }
+        walk_creations(frames, child, nonces, deployments);
+    }
+    if let Some(checkpoint) = checkpoint {
+        *nonces = checkpoint;
+    }
+}
+
+/// Finds the `CREATE2` deployments among the given call frames, in the order of execution.
+pub fn create2_deployments(
+    artifact: &DebugArtifact,
+    frames: &[CallFrame],
+) -> Vec<Create2Deployment> {
+    let mut deployments = Vec::new();
+    for (index, frame) in frames.iter().enumerate() {
+        if frame.kind != CallKind::Create2 {
+            continue;
+        }
+        let Some(deployer) = frame.caller else { continue };
+        // the instruction which made the call is the last step of the previous node
+        let Some(step) =
+            frame.node.checked_sub(1).and_then(|node| artifact.debug_arena[node].steps.last())
+        else {
+            continue;
+        };
+        let Some(salt) = step.stack.len().checked_sub(4).map(|i| B256::from(step.stack[i])) else {
+            continue;
+        };
+        // the init code is read from the memory of the creating step, and is otherwise the
+        // input of the frame
+        let init_code = match (stack_usize(step, 1), stack_usize(step, 2)) {
+            (Some(offset), Some(size)) => {
+                step.memory.get(offset..offset.saturating_add(size)).unwrap_or(&frame.input[..])
+            }
+            _ => &frame.input[..],
+        };
+        let init_code_hash = keccak256(init_code);
+
+        deployments.push(Create2Deployment {
+            frame: index,
+            deployer,
+            salt,
+            init_code_hash,
+            derived: deployer.create2(salt, init_code_hash),
+            created: frame.address,
+        });
+    }
+    deployments
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, U256};
+    use revm::interpreter::opcode;
+
+    use super::*;
+    use crate::{
+        artifact::debug::{DebugNodeFlat, DebugStep},
+        export::calltree::call_frames,
+    };
+
+    #[test]
+    fn test_create2_deployments() {
+        // the first example of EIP-1014
+        let (factory, deployed) =
+            (Address::ZERO, address!("4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38"));
+        let init_code = InitCode::parse("0x00").unwrap();
+        assert_eq!(create2_address(factory, B256::ZERO, &init_code), deployed);
+        assert_eq!(
+            InitCode::parse(&init_code.hash().to_string()),
+            Some(InitCode::Hash(init_code.hash()))
+        );
+
+        // create2(value, offset, size, salt), with the init code at the memory offset 0x1f
+        let create2 = DebugStep {
+            instruction: opcode::CREATE2,
+            stack: [0u64, 1, 0x1f, 0].map(U256::from).to_vec(),
+            memory: vec![0u8; 0x20].into(),
+            ..Default::default()
+        };
+        let stop = DebugStep { instruction: opcode::STOP, ..Default::default() };
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(factory, CallKind::Call, 0, vec![create2]),
+                DebugNodeFlat::new(deployed, CallKind::Create2, 1, vec![stop.clone()]),
+                DebugNodeFlat::new(factory, CallKind::Call, 0, vec![stop]),
+            ],
+            ..Default::default()
+        };
+
+        let deployments = create2_deployments(&artifact, &call_frames(&artifact));
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].frame, 1);
+        assert_eq!(deployments[0].deployer, factory);
+        assert!(deployments[0].is_verified());
+    }
+
+    #[test]
+    fn test_create_deployments() {
+        let factory = address!("b20a608c624Ca5003905aA834De7156C68b2E1d0");
+        let (first, second) = (factory.create(1), factory.create(2));
+        assert_eq!(create_address(factory, 1), first);
+
+        let stop = DebugStep { instruction: opcode::STOP, ..Default::default() };
+        let create = DebugStep { instruction: opcode::CREATE, ..Default::default() };
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(factory, CallKind::Call, 0, vec![create.clone()]),
+                DebugNodeFlat::new(first, CallKind::Create, 1, vec![stop.clone()]),
+                DebugNodeFlat::new(factory, CallKind::Call, 0, vec![create]),
+                DebugNodeFlat::new(second, CallKind::Create, 1, vec![stop.clone()]),
+                DebugNodeFlat::new(factory, CallKind::Call, 0, vec![stop]),
+            ],
+            ..Default::default()
+        };
+
+        let nonces = |artifact: &DebugArtifact| {
+            let deployments = create_deployments(artifact, &call_frames(artifact));
+            deployments.iter().map(|d| (d.frame, d.nonce)).collect::<Vec<_>>()
+        };
+        // the nonce of the factory before the transaction is unknown
+        assert_eq!(nonces(&artifact), vec![(1, None), (2, None)]);
+        let artifact = DebugArtifact { nonces: HashMap::from([(factory, 1)]), ..artifact };
+        assert_eq!(nonces(&artifact), vec![(1, Some(1)), (2, Some(2))]);
+        // a wrong nonce does not derive the created addresses
+        let artifact = DebugArtifact { nonces: HashMap::from([(factory, 5)]), ..artifact };
+        assert_eq!(nonces(&artifact), vec![(1, None), (2, None)]);
+    }
+}
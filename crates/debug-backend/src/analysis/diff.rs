@@ -0,0 +1,120 @@
+//! Comparison of two executions, e.g., the original transaction and a replay with patched code.
+//!
+//! The stacks are compared step by step, so that the first diverging step can be shown side by
+//! side. Storage writes are instead compared in the order they are made, since patched code
+//! usually takes a different number of steps to make the same writes.
+
+use alloy_primitives::{Address, U256};
+use revm::interpreter::opcode;
+
+use crate::artifact::debug::DebugArtifact;
+
+/// A storage write made by `SSTORE`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageWrite {
+    /// The index of the step making the write in the whole execution.
+    pub step: usize,
+    pub address: Address,
+    pub slot: U256,
+    pub value: U256,
+}
+
+/// The first divergence between two executions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Divergence {
+    /// The first step (in the whole execution) at which the executed opcodes or the stacks
+    /// differ, including the end of the shorter execution.
+    pub stack: Option<usize>,
+    /// The first pair of storage writes which differ. A side is `None` if that execution makes
+    /// fewer writes.
+    pub storage: Option<(Option<StorageWrite>, Option<StorageWrite>)>,
+}
+
+impl Divergence {
+    /// Compares the two executions.
+    pub fn new(left: &DebugArtifact, right: &DebugArtifact) -> Self {
+        let mut left_steps = left.steps();
+        let mut right_steps = right.steps();
+        let mut stack = None;
+        for index in 0.. {
+            match (left_steps.next(), right_steps.next()) {
+                (None, None) => break,
+                (Some((_, _, l)), Some((_, _, r)))
+                    if l.instruction == r.instruction && l.stack == r.stack => {}
+                _ => {
+                    stack = Some(index);
+                    break;
+                }
+            }
+        }
+
+        let left_writes = storage_writes(left);
+        let right_writes = storage_writes(right);
+        let n = left_writes.len().max(right_writes.len());
+        let storage = (0..n)
+            .map(|i| (left_writes.get(i).copied(), right_writes.get(i).copied()))
+            .find(|(l, r)| match (l, r) {
+                (Some(l), Some(r)) => (l.address, l.slot, l.value) != (r.address, r.slot, r.value),
+                _ => true,
+            });
+
+        Self { stack, storage }
+    }
+}
+
+/// Collects the storage writes in the order of execution.
+pub fn storage_writes(artifact: &DebugArtifact) -> Vec<StorageWrite> {
+    artifact
+        .steps()
+        .enumerate()
+        .filter(|(_, (_, _, step))| step.instruction == opcode::SSTORE && step.stack.len() >= 2)
+        .map(|(index, (node, _, step))| {
+            let n = step.stack.len();
+            StorageWrite {
+                step: index,
+                address: artifact.context_address(node),
+                slot: step.stack[n - 1],
+                value: step.stack[n - 2],
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+    use revm_inspectors::tracing::types::CallKind;
+
+    fn artifact(steps: Vec<DebugStep>) -> DebugArtifact {
+        let node = DebugNodeFlat::new(Address::ZERO, CallKind::Call, 0, steps);
+        DebugArtifact { debug_arena: vec![node], ..Default::default() }
+    }
+
+    fn step(instruction: u8, stack: &[u64]) -> DebugStep {
+        let stack = stack.iter().map(|v| U256::from(*v)).collect();
+        DebugStep { instruction, stack, ..Default::default() }
+    }
+
+    #[test]
+    fn test_divergence() {
+        let left = artifact(vec![
+            step(opcode::PUSH1, &[]),
+            step(opcode::PUSH1, &[1]),
+            step(opcode::SSTORE, &[1, 0]),
+        ]);
+        let right = artifact(vec![
+            step(opcode::PUSH1, &[]),
+            step(opcode::PUSH1, &[2]),
+            step(opcode::SSTORE, &[2, 0]),
+        ]);
+
+        let divergence = Divergence::new(&left, &right);
+        assert_eq!(divergence.stack, Some(1));
+        let (l, r) = divergence.storage.unwrap();
+        assert_eq!(l.unwrap().value, U256::from(1));
+        assert_eq!(r.unwrap().value, U256::from(2));
+
+        assert_eq!(Divergence::new(&left, &left), Divergence::default());
+    }
+}
@@ -0,0 +1,335 @@
+//! Reconstruct the EIP-712 typed data behind the digests computed during the execution, from the
+//! `KECCAK256` steps hashing the struct encodings, so that the signed fields can be shown in
+//! decoded form rather than as a bare digest.
+//!
+//! The type of a struct is told by its type hash, which is matched against the types declared as
+//! string literals in the sources, along with common types such as EIP-2612 permits.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+use alloy_dyn_abi::DynSolType;
+use alloy_primitives::{hex, keccak256, B256};
+
+use crate::{
+    analysis::signature::TypedDigest, artifact::debug::DebugArtifact,
+    export::calltree::format_value,
+};
+
+/// Types commonly signed, as encoded by `encodeType`, in addition to the ones found in sources.
+const COMMON_TYPES: &[&str] = &[
+    "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)",
+    "Delegation(address delegatee,uint256 nonce,uint256 expiry)",
+    "Ballot(uint256 proposalId,uint8 support)",
+    "PermitDetails(address token,uint160 amount,uint48 expiration,uint48 nonce)",
+    "PermitSingle(PermitDetails details,address spender,uint256 sigDeadline)PermitDetails(address \
+     token,uint160 amount,uint48 expiration,uint48 nonce)",
+    "PermitBatch(PermitDetails[] details,address spender,uint256 sigDeadline)PermitDetails(address \
+     token,uint160 amount,uint48 expiration,uint48 nonce)",
+    "TransferWithAuthorization(address from,address to,uint256 value,uint256 validAfter,uint256 \
+     validBefore,bytes32 nonce)",
+    "ReceiveWithAuthorization(address from,address to,uint256 value,uint256 validAfter,uint256 \
+     validBefore,bytes32 nonce)",
+];
+
+/// The fields of the EIP-712 domain, in the order of the standard. Domains have any subset of
+/// them.
+const DOMAIN_FIELDS: &[&str] = &[
+    "string name",
+    "string version",
+    "uint256 chainId",
+    "address verifyingContract",
+    "bytes32 salt",
+];
+
+/// A value of a field of a struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypedValue {
+    /// A value encoded in place, formatted.
+    Atomic(String),
+    /// A dynamic value or an array, encoded as its hash, along with its content, formatted, if
+    /// it was hashed during the execution.
+    Hashed(B256, Option<String>),
+    Struct(TypedStruct),
+}
+
+/// A field of a struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedField {
+    pub name: String,
+    pub ty: String,
+    pub value: TypedValue,
+}
+
+/// A struct hashed during the execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedStruct {
+    /// The name of the type, if it is known.
+    pub name: Option<String>,
+    pub hash: B256,
+    /// The fields of the struct, if its encoding was hashed during the execution. The fields of
+    /// an unknown type are the words of its encoding.
+    pub fields: Option<Vec<TypedField>>,
+}
+
+/// The typed data behind an EIP-712 digest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedData {
+    pub domain: TypedStruct,
+    pub message: TypedStruct,
+}
+
+/// The struct types known, by the hash of their encoding, along with the fields of each type
+/// taking part in the encoding.
+#[derive(Clone, Debug, Default)]
+pub struct TypeRegistry {
+    /// The primary type of each encoding, by type hash.
+    primary: HashMap<B256, String>,
+    /// The fields of each type, as types and names.
+    fields: HashMap<String, Vec<(String, String)>>,
+}
+
+impl TypeRegistry {
+    /// Collects the types declared in the sources of the artifact, along with the common ones.
+    pub fn new(artifact: &DebugArtifact) -> Self {
+        let mut registry = Self::default();
+        for encoding in COMMON_TYPES {
+            registry.insert(encoding);
+        }
+        for fields in domain_variants() {
+            registry.insert(&format!("EIP712Domain({fields})"));
+        }
+        let mut literals = BTreeSet::new();
+        for compilation in artifact.compilation_artifacts.values() {
+            for source in compilation.sources.values() {
+                literals.extend(type_literals(&source.code));
+            }
+        }
+        for literal in literals {
+            registry.insert(literal);
+        }
+        registry
+    }
+
+    /// Registers the types of an encoding, e.g., `Mail(Person from,string contents)Person(...)`,
+    /// each of which is hashed along with the types it references.
+    pub fn insert(&mut self, encoding: &str) {
+        // the declaration of each type, e.g., `Person(string name,address wallet)`
+        let mut declarations = HashMap::new();
+        let mut rest = encoding;
+        while let Some((name, tail)) = rest.split_once('(') {
+            let Some((fields, tail)) = tail.split_once(')') else { return };
+            let declaration = &rest[..rest.len() - tail.len()];
+            let fields = fields
+                .split(',')
+                .filter(|field| !field.is_empty())
+                .filter_map(|field| {
+                    let (ty, name) = field.split_once(' ')?;
+                    Some((ty.to_string(), name.to_string()))
+                })
+                .collect();
+            self.fields.insert(name.to_string(), fields);
+            declarations.insert(name, declaration);
+            rest = tail;
+        }
+
+        for (name, declaration) in &declarations {
+            // the referenced types are appended to the declaration, sorted by name
+            let mut referenced = BTreeSet::new();
+            let mut pending = vec![*name];
+            while let Some(ty) = pending.pop() {
+                for (field_ty, _) in self.fields.get(ty).into_iter().flatten() {
+                    let field_ty = field_ty.split('[').next().unwrap_or_default();
+                    if let Some((referenced_ty, _)) = declarations.get_key_value(field_ty) {
+                        if referenced_ty != name && referenced.insert(*referenced_ty) {
+                            pending.push(*referenced_ty);
+                        }
+                    }
+                }
+            }
+            let mut type_encoding = declaration.to_string();
+            type_encoding.extend(referenced.iter().map(|ty| declarations[ty]));
+            self.primary.insert(keccak256(type_encoding), name.to_string());
+        }
+    }
+
+    /// Reconstructs the typed data behind the given digest, from the hashes computed during the
+    /// execution, i.e., the preimages by hash.
+    pub fn typed_data(&self, digest: &TypedDigest, preimages: &HashMap<B256, &[u8]>) -> TypedData {
+        TypedData {
+            domain: self.typed_struct(digest.domain_separator, preimages),
+            message: self.typed_struct(digest.struct_hash, preimages),
+        }
+    }
+
+    /// Reconstructs the struct of the given hash, whose type is told by its type hash.
+    fn typed_struct(&self, hash: B256, preimages: &HashMap<B256, &[u8]>) -> TypedStruct {
+        let Some(encoding) = preimages.get(&hash).filter(|encoding| encoding.len() % 32 == 0)
+        else {
+            return TypedStruct { name: None, hash, fields: None };
+        };
+        let mut words = encoding.chunks_exact(32).map(B256::from_slice);
+        let Some(type_hash) = words.next() else {
+            return TypedStruct { name: None, hash, fields: None };
+        };
+
+        let Some(name) = self.primary.get(&type_hash) else {
+            let fields = words
+                .enumerate()
+                .map(|(i, word)| TypedField {
+                    name: i.to_string(),
+                    ty: "bytes32".to_string(),
+                    value: TypedValue::Atomic(word.to_string()),
+                })
+                .collect();
+            return TypedStruct { name: None, hash, fields: Some(fields) };
+        };
+        let fields = self.fields[name]
+            .iter()
+            .zip(words)
+            .map(|((ty, field), word)| TypedField {
+                name: field.clone(),
+                ty: ty.clone(),
+                value: self.typed_value(ty, word, preimages),
+            })
+            .collect();
+        TypedStruct { name: Some(name.clone()), hash, fields: Some(fields) }
+    }
+
+    /// Decodes the encoding of a field of the given type.
+    fn typed_value(&self, ty: &str, word: B256, preimages: &HashMap<B256, &[u8]>) -> TypedValue {
+        if self.fields.contains_key(ty) {
+            return TypedValue::Struct(self.typed_struct(word, preimages));
+        }
+        let content = preimages.get(&word);
+        match ty {
+            "string" => TypedValue::Hashed(
+                word,
+                content.map(|content| format!("{:?}", String::from_utf8_lossy(content))),
+            ),
+            _ if ty == "bytes" || ty.ends_with(']') => {
+                TypedValue::Hashed(word, content.map(|content| hex::encode_prefixed(content)))
+            }
+            _ => match DynSolType::parse(ty).and_then(|ty| ty.abi_decode(&word[..])) {
+                Ok(value) => TypedValue::Atomic(format_value(&value)),
+                Err(_) => TypedValue::Atomic(word.to_string()),
+            },
+        }
+    }
+}
+
+/// Returns the fields of every variant of the EIP-712 domain, e.g., `string name,uint256 chainId`.
+fn domain_variants() -> Vec<String> {
+    (1..1usize << DOMAIN_FIELDS.len())
+        .map(|mask| {
+            let fields = DOMAIN_FIELDS
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, field)| *field);
+            fields.collect::<Vec<_>>().join(",")
+        })
+        .collect()
+}
+
+/// Returns the string literals of the given source which look like struct type encodings, e.g.,
+/// `"Permit(address owner,...)"`.
+fn type_literals(code: &str) -> impl Iterator<Item = &str> {
+    code.split('"').skip(1).step_by(2).filter(|literal| {
+        let Some((name, rest)) = literal.split_once('(') else { return false };
+        !name.is_empty() &&
+            name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') &&
+            name.starts_with(|c: char| c.is_ascii_uppercase()) &&
+            rest.ends_with(')')
+    })
+}
+
+impl fmt::Display for TypedData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "domain: {}", self.domain)?;
+        write!(f, "message: {}", self.message)
+    }
+}
+
+impl fmt::Display for TypedStruct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+impl TypedStruct {
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        let name = self.name.as_deref().unwrap_or("unknown type");
+        let Some(fields) = &self.fields else { return write!(f, "{name} {}", self.hash) };
+        writeln!(f, "{name} {{")?;
+        for field in fields {
+            write!(f, "{:w$}{} {}: ", "", field.ty, field.name, w = indent + 2)?;
+            match &field.value {
+                TypedValue::Atomic(value) => write!(f, "{value}")?,
+                TypedValue::Hashed(_, Some(content)) => write!(f, "{content}")?,
+                TypedValue::Hashed(hash, None) => write!(f, "hash {hash}")?,
+                TypedValue::Struct(inner) => inner.fmt_indented(f, indent + 2)?,
+            }
+            writeln!(f)?;
+        }
+        write!(f, "{:indent$}}}", "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+
+    use super::*;
+
+    #[test]
+    fn test_typed_data() {
+        let mut registry = TypeRegistry::default();
+        let mail = "Mail(Person from,string contents)Person(string name,address wallet)";
+        registry.insert(mail);
+        for fields in domain_variants() {
+            registry.insert(&format!("EIP712Domain({fields})"));
+        }
+
+        let word = |word: B256| word.to_vec();
+        let name = b"Bob".to_vec();
+        let person = [
+            word(keccak256("Person(string name,address wallet)")),
+            word(keccak256(&name)),
+            word(Address::with_last_byte(2).into_word()),
+        ]
+        .concat();
+        let contents = b"Hello".to_vec();
+        let message =
+            [word(keccak256(mail)), word(keccak256(&person)), word(keccak256(&contents))].concat();
+        let domain = [
+            word(keccak256("EIP712Domain(string name,uint256 chainId)")),
+            word(keccak256("Mail")),
+            word(U256::from(1).into()),
+        ]
+        .concat();
+        let preimages: HashMap<_, _> = [&name[..], &person, &contents, &message, &domain, b"Mail"]
+            .into_iter()
+            .map(|preimage| (keccak256(preimage), preimage))
+            .collect();
+
+        let digest = TypedDigest {
+            step: 0,
+            domain_separator: keccak256(&domain),
+            struct_hash: keccak256(&message),
+            digest: B256::ZERO,
+        };
+        let typed = registry.typed_data(&digest, &preimages);
+        assert_eq!(typed.domain.name.as_deref(), Some("EIP712Domain"));
+        assert_eq!(typed.message.name.as_deref(), Some("Mail"));
+        assert_eq!(
+            typed.to_string(),
+            "domain: EIP712Domain {\n  string name: \"Mail\"\n  uint256 chainId: 1\n}\n\
+             message: Mail {\n  Person from: Person {\n    string name: \"Bob\"\n    address \
+             wallet: 0x0000000000000000000000000000000000000002\n  }\n  string contents: \
+             \"Hello\"\n}"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Breakpoints on the entry of functions, which are resolved to the targets of the dispatchers of
+//! the touched contracts, so that they also apply to unverified contracts by selector.
+
+use std::{fmt, str::FromStr};
+
+use alloy_primitives::{hex, keccak256, Address, Selector};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::debug::DebugArtifact;
+
+/// The function a breakpoint is set on, e.g., `Vault.withdraw`, `withdraw(uint256)` or
+/// `0x2e1a7d4d`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FunctionTarget {
+    /// Any function with the selector, in any contract.
+    Selector(Selector),
+    /// The functions with the name, in the contracts with the name if given. The selector is
+    /// known if the name is a full signature, so that unverified contracts match too.
+    Name { contract: Option<String>, name: String, selector: Option<Selector> },
+}
+
+impl FromStr for FunctionTarget {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(selector) = s.strip_prefix("0x") {
+            let selector = hex::decode(selector).map_err(|e| eyre!("invalid selector: {e}"))?;
+            return Selector::try_from(selector.as_slice())
+                .map(Self::Selector)
+                .map_err(|_| eyre!("a selector is 4 bytes long, e.g., 0x2e1a7d4d"));
+        }
+
+        // the contract is separated by the last dot before the parameters, if any
+        let head = s.split('(').next().unwrap_or(s);
+        let (contract, name) = match head.rfind('.') {
+            Some(dot) => (Some(s[..dot].to_string()), &s[dot + 1..]),
+            None => (None, s),
+        };
+        let function = name.split('(').next().unwrap_or(name);
+        eyre::ensure!(
+            !function.is_empty() && contract.as_ref().map_or(true, |c| !c.is_empty()),
+            "expected a function, e.g., Vault.withdraw, withdraw(uint256) or 0x2e1a7d4d"
+        );
+        let selector = name
+            .contains('(')
+            .then(|| Selector::from_slice(&keccak256(name.replace(' ', ""))[..4]));
+        Ok(Self::Name { contract, name: function.to_string(), selector })
+    }
+}
+
+impl fmt::Display for FunctionTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Selector(selector) => write!(f, "{selector}"),
+            Self::Name { contract: Some(contract), name, .. } => write!(f, "{contract}.{name}"),
+            Self::Name { contract: None, name, .. } => f.write_str(name),
+        }
+    }
+}
+
+/// The entry of a function in the code of a contract, i.e., the target its dispatcher jumps to.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FunctionEntry {
+    /// The address of the code.
+    pub address: Address,
+    pub pc: usize,
+    pub selector: Selector,
+}
+
+impl FunctionTarget {
+    /// Resolves the entries of the matching functions in the touched contracts.
+    pub fn resolve(&self, artifact: &DebugArtifact) -> Vec<FunctionEntry> {
+        let mut entries = Vec::new();
+        for (address, cfg) in &artifact.cfgs {
+            let selectors = match self {
+                Self::Selector(selector) => vec![*selector],
+                Self::Name { contract, name, selector } => {
+                    if contract.as_ref().is_some_and(|contract| {
+                        artifact.contract_name(address) != Some(contract.as_str())
+                    }) {
+                        continue;
+                    }
+                    match (selector, artifact.abi(address)) {
+                        (Some(selector), _) => vec![*selector],
+                        // the overloads of the function
+                        (None, Some(abi)) => abi
+                            .functions()
+                            .filter(|function| function.name == *name)
+                            .map(|function| function.selector())
+                            .collect(),
+                        (None, None) => continue,
+                    }
+                }
+            };
+            entries.extend(cfg.functions.iter().filter(|(_, s)| selectors.contains(s)).map(
+                |(pc, selector)| FunctionEntry { address: *address, pc: *pc, selector: *selector },
+            ));
+        }
+        entries.sort();
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::analysis::cfg::ControlFlowGraph;
+
+    use super::*;
+
+    #[test]
+    fn test_function_entries() {
+        let withdraw = FunctionTarget::from_str("withdraw(uint256)").unwrap();
+        let selector = Selector::from(hex!("2e1a7d4d"));
+        assert_eq!(
+            withdraw,
+            FunctionTarget::Name {
+                contract: None,
+                name: "withdraw".into(),
+                selector: Some(selector)
+            }
+        );
+        assert_eq!(
+            FunctionTarget::from_str("Vault.withdraw").unwrap(),
+            FunctionTarget::Name {
+                contract: Some("Vault".into()),
+                name: "withdraw".into(),
+                selector: None
+            }
+        );
+        assert_eq!(
+            FunctionTarget::from_str("0x2e1a7d4d").unwrap(),
+            FunctionTarget::Selector(selector)
+        );
+        assert!(FunctionTarget::from_str("0x2e1a").is_err());
+        assert!(FunctionTarget::from_str(".withdraw").is_err());
+
+        // DUP1 PUSH4 2e1a7d4d EQ PUSH1 0x0c JUMPI STOP STOP JUMPDEST STOP
+        let code =
+            [0x80, 0x63, 0x2e, 0x1a, 0x7d, 0x4d, 0x14, 0x60, 0x0c, 0x57, 0x00, 0x00, 0x5b, 0x00];
+        let address = Address::with_last_byte(1);
+        let artifact = DebugArtifact {
+            cfgs: [(address, Arc::new(ControlFlowGraph::new(&code)))].into(),
+            ..Default::default()
+        };
+        let entry = FunctionEntry { address, pc: 12, selector };
+        assert_eq!(FunctionTarget::Selector(selector).resolve(&artifact), vec![entry.clone()]);
+        assert_eq!(withdraw.resolve(&artifact), vec![entry]);
+        // the name alone needs the ABI, and the contract name needs the sources
+        assert!(FunctionTarget::from_str("withdraw").unwrap().resolve(&artifact).is_empty());
+        assert!(FunctionTarget::from_str("Vault.withdraw(uint256)")
+            .unwrap()
+            .resolve(&artifact)
+            .is_empty());
+    }
+}
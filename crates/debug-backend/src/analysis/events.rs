@@ -0,0 +1,359 @@
+//! The events emitted during the execution, read from the `LOG` steps, their decoding with the
+//! ABI of their emitters, and the filters to continue until one of them is emitted.
+
+use std::{fmt, str::FromStr};
+
+use alloy_dyn_abi::{DynSolValue, EventExt, Specifier};
+use alloy_json_abi::{Event, JsonAbi};
+use alloy_primitives::{keccak256, Address, Bytes, B256};
+use eyre::{eyre, Result};
+use revm::interpreter::opcode;
+
+use crate::{
+    analysis::shadow::stack_usize,
+    artifact::debug::{DebugArtifact, DebugStep},
+    export::calltree::format_value,
+};
+
+/// An event emitted by a `LOG` step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmittedEvent {
+    /// The index of the step in the whole execution.
+    pub step: usize,
+    /// The contract emitting the event, i.e., the storage context of the step.
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+impl EmittedEvent {
+    /// Decodes the event with the given ABI, e.g., `Transfer(from: 0x…, to: 0x…, value: 1)`.
+    pub fn decode(&self, abi: &JsonAbi) -> Option<String> {
+        let event = self.abi_event(abi)?;
+        let values = self.decode_values(event)?;
+        let args = event
+            .inputs
+            .iter()
+            .zip(&values)
+            .map(|(input, value)| match input.name.as_str() {
+                "" => format_value(value),
+                name => format!("{name}: {}", format_value(value)),
+            })
+            .collect::<Vec<_>>();
+        Some(format!("{}({})", event.name, args.join(", ")))
+    }
+
+    /// Returns the event of the ABI with the selector of the first topic.
+    fn abi_event<'a>(&self, abi: &'a JsonAbi) -> Option<&'a Event> {
+        let selector = self.topics.first()?;
+        abi.events().find(|event| !event.anonymous && event.selector() == *selector)
+    }
+
+    /// Decodes the values of the parameters of the event, in order.
+    fn decode_values(&self, event: &Event) -> Option<Vec<DynSolValue>> {
+        let decoded =
+            event.decode_log_parts(self.topics.iter().copied(), &self.data, false).ok()?;
+
+        // the indexed and non-indexed values are decoded apart, and interleaved back in the order
+        // of the parameters
+        let (mut indexed, mut body) = (decoded.indexed.into_iter(), decoded.body.into_iter());
+        event
+            .inputs
+            .iter()
+            .map(|input| if input.indexed { indexed.next() } else { body.next() })
+            .collect()
+    }
+}
+
+/// A parameter of an event, referred to by name or by position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ParamRef {
+    Name(String),
+    Index(usize),
+}
+
+impl ParamRef {
+    fn position(&self, event: &Event) -> Option<usize> {
+        match self {
+            Self::Name(name) => event.inputs.iter().position(|input| input.name == *name),
+            Self::Index(index) => (*index < event.inputs.len()).then_some(*index),
+        }
+    }
+}
+
+/// The events to continue until, given as a signature followed by conditions on the values of
+/// the parameters, e.g., `Transfer(address,address,uint256) to=0x… 2=100`.
+///
+/// Parameters are referred to by name or by position. The names and the indexed parameters are
+/// taken from the ABI of the emitter when it is known, so that they may be left out of the
+/// signature; otherwise, the leading parameters are assumed to be the indexed ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventFilter {
+    event: Event,
+    conditions: Vec<(ParamRef, String)>,
+}
+
+impl EventFilter {
+    /// Returns whether the event matches the signature and the conditions of the filter.
+    pub fn matches(&self, emitted: &EmittedEvent, abi: Option<&JsonAbi>) -> bool {
+        if emitted.topics.first() != Some(&self.event.selector()) {
+            return false;
+        }
+
+        let inferred;
+        let event = match abi.and_then(|abi| emitted.abi_event(abi)) {
+            Some(event) => event,
+            None if self.event.inputs.iter().any(|input| input.indexed) => &self.event,
+            None => {
+                let mut event = self.event.clone();
+                let num_indexed = emitted.topics.len() - 1;
+                event.inputs.iter_mut().take(num_indexed).for_each(|input| input.indexed = true);
+                inferred = event;
+                &inferred
+            }
+        };
+        let Some(values) = emitted.decode_values(event) else { return false };
+
+        self.conditions.iter().all(|(param, expected)| {
+            let Some(position) = param.position(event) else { return false };
+            let input = &event.inputs[position];
+            let Ok(ty) = input.resolve() else { return false };
+            ty.coerce_str(expected).is_ok_and(|expected| {
+                let expected = if input.indexed { topic_value(expected) } else { expected };
+                expected == values[position]
+            })
+        })
+    }
+}
+
+/// Returns the value of an indexed parameter as it is logged: strings and bytes are only logged
+/// as the hash of their content.
+fn topic_value(value: DynSolValue) -> DynSolValue {
+    match value {
+        DynSolValue::String(s) => DynSolValue::FixedBytes(keccak256(s), 32),
+        DynSolValue::Bytes(b) => DynSolValue::FixedBytes(keccak256(b), 32),
+        value => value,
+    }
+}
+
+impl FromStr for EventFilter {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix("event ").unwrap_or(s);
+
+        // the signature ends with the parenthesis closing its parameters, which may be tuples
+        let mut depth = 0usize;
+        let end = s
+            .char_indices()
+            .find_map(|(i, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => {
+                        depth = depth.checked_sub(1)?;
+                        if depth == 0 {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                None
+            })
+            .ok_or_else(|| eyre!("expected an event signature, e.g. Transfer(address,uint256)"))?;
+        let event = Event::parse(&s[..end]).map_err(|e| eyre!("invalid event signature: {e}"))?;
+
+        let conditions = s[end..]
+            .split_whitespace()
+            .map(|condition| {
+                let (param, value) = condition
+                    .split_once('=')
+                    .map(|(param, value)| (param, value.trim_start_matches('=')))
+                    .filter(|(param, value)| !param.is_empty() && !value.is_empty())
+                    .ok_or_else(|| eyre!("expected `param=value`, got: {condition}"))?;
+                let param = match param.parse() {
+                    Ok(index) => ParamRef::Index(index),
+                    Err(_) => ParamRef::Name(param.to_string()),
+                };
+
+                // the conditions are checked against the signature when possible, while the
+                // names may only be known from the ABI of the emitter
+                match param.position(&event) {
+                    Some(position) => {
+                        let ty = event.inputs[position].resolve()?;
+                        ty.coerce_str(value)
+                            .map_err(|e| eyre!("invalid value for {param}: {e}"))?;
+                    }
+                    None if matches!(param, ParamRef::Index(_)) => {
+                        return Err(eyre!("{} has no parameter {param}", event.name))
+                    }
+                    None => {}
+                }
+                Ok((param, value.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { event, conditions })
+    }
+}
+
+impl fmt::Display for ParamRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => f.write_str(name),
+            Self::Index(index) => write!(f, "{index}"),
+        }
+    }
+}
+
+/// Finds the events emitted during the execution, in order, including the ones of reverted calls.
+pub fn emitted_events(artifact: &DebugArtifact) -> Vec<EmittedEvent> {
+    let mut events = Vec::new();
+    let mut index = 0;
+    for (node, debug_node) in artifact.debug_arena.iter().enumerate() {
+        for step in &debug_node.steps {
+            if let Some(event) = emitted_event(step, index, || artifact.context_address(node)) {
+                events.push(event);
+            }
+            index += 1;
+        }
+    }
+    events
+}
+
+/// Reads the event emitted by the step, if it is a `LOG` step.
+fn emitted_event(
+    step: &DebugStep,
+    index: usize,
+    address: impl FnOnce() -> Address,
+) -> Option<EmittedEvent> {
+    if !(opcode::LOG0..=opcode::LOG4).contains(&step.instruction) {
+        return None;
+    }
+    let num_topics = (step.instruction - opcode::LOG0) as usize;
+    let (offset, size) = (stack_usize(step, 0)?, stack_usize(step, 1)?);
+    let topics = (2..2 + num_topics)
+        .map(|n| step.stack.len().checked_sub(n + 1).map(|i| B256::from(step.stack[i])))
+        .collect::<Option<Vec<_>>>()?;
+    // memory is only expanded by the step itself, so that the bytes past it are zero
+    let mut data = vec![0u8; size];
+    if let Some(available) = step.memory.get(offset.min(step.memory.len())..) {
+        let n = available.len().min(size);
+        data[..n].copy_from_slice(&available[..n]);
+    }
+    Some(EmittedEvent { step: index, address: address(), topics, data: data.into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, U256};
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::DebugNodeFlat;
+
+    #[test]
+    fn test_emitted_events() {
+        let abi: JsonAbi = serde_json::from_str(
+            r#"[{"type":"event","name":"Transfer","anonymous":false,"inputs":[
+                {"name":"from","type":"address","indexed":true},
+                {"name":"to","type":"address","indexed":true},
+                {"name":"value","type":"uint256","indexed":false}]}]"#,
+        )
+        .unwrap();
+        let selector = abi.events().next().unwrap().selector();
+        let (token, to) = (
+            address!("00000000000000000000000000000000000000aa"),
+            address!("00000000000000000000000000000000000000bb"),
+        );
+
+        // log3(offset, size, selector, from, to), with the value in memory
+        let log = DebugStep {
+            instruction: opcode::LOG3,
+            stack: vec![
+                to.into_word().into(),
+                U256::ZERO,
+                selector.into(),
+                U256::from(32),
+                U256::ZERO,
+            ],
+            memory: U256::from(100).to_be_bytes_vec().into(),
+            ..Default::default()
+        };
+        let stop = DebugStep { instruction: opcode::STOP, ..Default::default() };
+        let artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(token, CallKind::Call, 0, vec![stop, log])],
+            ..Default::default()
+        };
+
+        let events = emitted_events(&artifact);
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].step, events[0].address), (1, token));
+        assert_eq!(events[0].topics.len(), 3);
+        assert_eq!(
+            events[0].decode(&abi).unwrap(),
+            format!(
+                "Transfer(from: {}, to: {}, value: 100)",
+                Address::ZERO.to_checksum(None),
+                to.to_checksum(None)
+            )
+        );
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let (to, other) = (
+            address!("00000000000000000000000000000000000000bb"),
+            address!("00000000000000000000000000000000000000cc"),
+        );
+        let event = Event::parse("Transfer(address indexed, address indexed, uint256)").unwrap();
+        let emitted = EmittedEvent {
+            step: 0,
+            address: Address::ZERO,
+            topics: vec![event.selector(), Address::ZERO.into_word(), to.into_word()],
+            data: U256::from(100).to_be_bytes_vec().into(),
+        };
+
+        // without an ABI, the parameters are only known by position
+        let filter: EventFilter =
+            format!("Transfer(address,address,uint256) 1={to}").parse().unwrap();
+        assert!(filter.matches(&emitted, None));
+        let filter: EventFilter = "Transfer(address,address,uint256) 2==99".parse().unwrap();
+        assert!(!filter.matches(&emitted, None));
+        let filter: EventFilter = "Approval(address,address,uint256)".parse().unwrap();
+        assert!(!filter.matches(&emitted, None));
+
+        // the names are taken from the ABI of the emitter
+        let abi: JsonAbi = serde_json::from_str(
+            r#"[{"type":"event","name":"Transfer","anonymous":false,"inputs":[
+                {"name":"from","type":"address","indexed":true},
+                {"name":"to","type":"address","indexed":true},
+                {"name":"value","type":"uint256","indexed":false}]}]"#,
+        )
+        .unwrap();
+        let filter: EventFilter =
+            format!("Transfer(address,address,uint256) to={to} value==100").parse().unwrap();
+        assert!(filter.matches(&emitted, Some(&abi)));
+        assert!(!filter.matches(&emitted, None));
+        let filter: EventFilter =
+            format!("Transfer(address,address,uint256) to={other}").parse().unwrap();
+        assert!(!filter.matches(&emitted, Some(&abi)));
+
+        // indexed strings are matched by the hash of their content
+        let event = Event::parse("Registered(string indexed name)").unwrap();
+        let emitted = EmittedEvent {
+            step: 0,
+            address: Address::ZERO,
+            topics: vec![event.selector(), keccak256("alice")],
+            data: Bytes::new(),
+        };
+        let filter: EventFilter = "Registered(string indexed) 0=alice".parse().unwrap();
+        assert!(filter.matches(&emitted, None));
+        let filter: EventFilter = "Registered(string indexed) 0=bob".parse().unwrap();
+        assert!(!filter.matches(&emitted, None));
+
+        assert!("Transfer".parse::<EventFilter>().is_err());
+        assert!("Transfer(address) 1=0x0".parse::<EventFilter>().is_err());
+        assert!("Transfer(uint256) 0=abc".parse::<EventFilter>().is_err());
+        assert!("Transfer(uint256) value".parse::<EventFilter>().is_err());
+    }
+}
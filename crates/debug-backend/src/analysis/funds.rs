@@ -0,0 +1,316 @@
+//! Funds flow of the transaction, i.e., the ether and ERC-20 tokens moved between accounts.
+//!
+//! Ether transfers are recovered from the value of the transaction, of the `CALL`s and of the
+//! `CREATE`s, and from the balances sent by `SELFDESTRUCT`s. Token transfers are recovered from
+//! the `Transfer` events emitted by token contracts, so that tokens moving funds without emitting
+//! them are missed. Transfers made in calls which were later reverted are left out.
+
+use std::{collections::BTreeMap, fmt};
+
+use alloy_primitives::{b256, Address, B256, U256};
+use revm::interpreter::opcode;
+use revm_inspectors::tracing::types::CallKind;
+
+use crate::{
+    analysis::shadow::stack_usize,
+    artifact::debug::DebugArtifact,
+    export::calltree::{call_frames, reverted_steps},
+};
+
+/// `keccak256("Transfer(address,address,uint256)")`
+const TRANSFER_TOPIC: B256 =
+    b256!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+
+/// An asset which can be moved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Asset {
+    Ether,
+    Token(Address),
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ether => write!(f, "ETH"),
+            Self::Token(address) => write!(f, "{address}"),
+        }
+    }
+}
+
+/// A movement of funds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    /// The index of the step making the transfer in the whole execution.
+    pub step: usize,
+    pub asset: Asset,
+    pub from: Address,
+    pub to: Address,
+    pub amount: U256,
+}
+
+/// A payment for the inclusion of transactions in the block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuilderPayment {
+    /// A payment to the beneficiary of the block on top of the priority fee, e.g., a direct
+    /// ether transfer (`block.coinbase.transfer(...)`), a self-destruct or wrapped ether sent to
+    /// it.
+    Coinbase,
+    /// A payment of ether by the beneficiary of the block, i.e., the builder, to the proposer,
+    /// usually made by the last transaction of the block.
+    Proposer,
+}
+
+impl fmt::Display for BuilderPayment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Coinbase => write!(f, "coinbase payment"),
+            Self::Proposer => write!(f, "proposer payment"),
+        }
+    }
+}
+
+/// The funds flow of the transaction.
+#[derive(Clone, Debug, Default)]
+pub struct FundsFlow {
+    /// The transfers, in the order of execution.
+    pub transfers: Vec<Transfer>,
+}
+
+impl FundsFlow {
+    /// Collects the funds flow from the trace.
+    pub fn new(artifact: &DebugArtifact) -> Self {
+        let frames = call_frames(artifact);
+        // the steps of the frames whose changes were rolled back, including their sub-calls
+        let reverted = reverted_steps(&frames);
+        let is_rolled_back = |step: usize| reverted.iter().any(|steps| steps.contains(&step));
+
+        let mut transfers = Vec::new();
+
+        // the value sent with the transaction, the calls and the creations, at the step making
+        // the call, or at the first step for the transaction
+        for frame in &frames {
+            if frame.value.is_zero() ||
+                !(frame.kind == CallKind::Call || frame.kind.is_any_create()) ||
+                frame.is_reverted()
+            {
+                continue;
+            }
+            let (step, from) = match frame.caller {
+                Some(caller) => (frame.steps.start.saturating_sub(1), caller),
+                None => match artifact.sender {
+                    Some(sender) => (0, sender),
+                    None => continue,
+                },
+            };
+            if !is_rolled_back(step) {
+                let (asset, to, amount) = (Asset::Ether, frame.address, frame.value);
+                transfers.push(Transfer { step, asset, from, to, amount });
+            }
+        }
+
+        for (i, node) in artifact.debug_arena.iter().enumerate() {
+            if let Some((to, amount)) = artifact.selfdestructs.get(&i) {
+                let step = artifact.step_index(i, node.steps.len().saturating_sub(1));
+                if !amount.is_zero() && !is_rolled_back(step) {
+                    let from = artifact.context_address(i);
+                    transfers.push(Transfer {
+                        step,
+                        asset: Asset::Ether,
+                        from,
+                        to: *to,
+                        amount: *amount,
+                    });
+                }
+            }
+
+            for (j, step) in node.steps.iter().enumerate() {
+                let top = |n: usize| step.stack.len().checked_sub(n + 1).map(|k| step.stack[k]);
+                let address = |n: usize| {
+                    top(n).map(|v| Address::from_word(B256::from(v.to_be_bytes::<32>())))
+                };
+
+                let transfer = match step.instruction {
+                    opcode::LOG3
+                        if top(2).map(|topic| B256::from(topic.to_be_bytes::<32>())) ==
+                            Some(TRANSFER_TOPIC) =>
+                    {
+                        let amount = stack_usize(step, 0)
+                            .filter(|_| stack_usize(step, 1).map_or(false, |size| size >= 32))
+                            .and_then(|offset| step.memory.get(offset..offset + 32))
+                            .map(U256::from_be_slice);
+                        match (address(3), address(4), amount) {
+                            (Some(from), Some(to), Some(amount)) => {
+                                let token = artifact.context_address(i);
+                                Some((Asset::Token(token), from, to, amount))
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+
+                if let Some((asset, from, to, amount)) = transfer {
+                    let step = artifact.step_index(i, j);
+                    if !is_rolled_back(step) {
+                        transfers.push(Transfer { step, asset, from, to, amount });
+                    }
+                }
+            }
+        }
+
+        transfers.sort_by_key(|transfer| transfer.step);
+        Self { transfers }
+    }
+
+    /// Returns the transfers paying for the inclusion of transactions, i.e., the ones searchers
+    /// make to the beneficiary of the block, and the ones of the beneficiary to the proposer.
+    pub fn builder_payments(
+        &self,
+        coinbase: Address,
+    ) -> impl Iterator<Item = (BuilderPayment, &Transfer)> {
+        self.transfers.iter().filter_map(move |transfer| {
+            if transfer.to == coinbase {
+                Some((BuilderPayment::Coinbase, transfer))
+            } else if transfer.from == coinbase && transfer.asset == Asset::Ether {
+                Some((BuilderPayment::Proposer, transfer))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the net balance change of each account and asset.
+    pub fn net_changes(&self) -> BTreeMap<(Address, Asset), (U256, U256)> {
+        // (incoming, outgoing) amounts
+        let mut changes: BTreeMap<(Address, Asset), (U256, U256)> = BTreeMap::new();
+        for transfer in &self.transfers {
+            let incoming = &mut changes.entry((transfer.to, transfer.asset)).or_default().0;
+            *incoming = incoming.saturating_add(transfer.amount);
+            let outgoing = &mut changes.entry((transfer.from, transfer.asset)).or_default().1;
+            *outgoing = outgoing.saturating_add(transfer.amount);
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    use super::*;
+
+    fn step(instruction: u8, stack: &[U256]) -> DebugStep {
+        DebugStep { instruction, stack: stack.to_vec(), ..Default::default() }
+    }
+
+    fn word(address: Address) -> U256 {
+        address.into_word().into()
+    }
+
+    #[test]
+    fn test_funds_flow() {
+        let [sender, a, b, c, d, x, y, beneficiary] =
+            [1, 2, 3, 4, 5, 6, 7, 8].map(Address::with_last_byte);
+        let call = |to, value: u64| {
+            step(opcode::CALL, &[U256::ZERO, U256::from(value), word(to), U256::ZERO])
+        };
+        let log = DebugStep {
+            memory: U256::from(100).to_be_bytes_vec().into(),
+            ..step(
+                opcode::LOG3,
+                &[word(y), word(x), TRANSFER_TOPIC.into(), U256::from(32), U256::ZERO],
+            )
+        };
+        let node = |address, kind, depth, steps| DebugNodeFlat::new(address, kind, depth, steps);
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                node(a, CallKind::Call, 0, vec![call(b, 5)]),
+                // reverted, along with the value sent to it
+                node(b, CallKind::Call, 1, vec![log.clone(), step(opcode::REVERT, &[])]),
+                node(a, CallKind::Call, 0, vec![call(c, 7)]),
+                node(c, CallKind::Call, 1, vec![log, step(opcode::STOP, &[])]),
+                node(
+                    a,
+                    CallKind::Call,
+                    0,
+                    vec![step(opcode::CREATE, &[U256::ZERO, U256::ZERO, U256::from(3)])],
+                ),
+                node(
+                    d,
+                    CallKind::Create,
+                    1,
+                    vec![step(opcode::SELFDESTRUCT, &[word(beneficiary)])],
+                ),
+                node(a, CallKind::Call, 0, vec![step(opcode::STOP, &[])]),
+            ],
+            sender: Some(sender),
+            value: U256::from(1),
+            selfdestructs: [(5, (beneficiary, U256::from(3)))].into(),
+            ..Default::default()
+        };
+
+        let transfers = FundsFlow::new(&artifact)
+            .transfers
+            .into_iter()
+            .map(|t| (t.step, t.asset, t.from, t.to, t.amount.to::<u64>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transfers,
+            vec![
+                (0, Asset::Ether, sender, a, 1),
+                (3, Asset::Ether, a, c, 7),
+                (4, Asset::Token(c), x, y, 100),
+                (6, Asset::Ether, a, d, 3),
+                (7, Asset::Ether, d, beneficiary, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builder_payments() {
+        let [coinbase, searcher, proposer, weth, other] =
+            [1, 2, 3, 4, 5].map(Address::with_last_byte);
+        let transfer =
+            |step, asset, from, to| Transfer { step, asset, from, to, amount: U256::from(1) };
+        let flow = FundsFlow {
+            transfers: vec![
+                transfer(0, Asset::Ether, searcher, coinbase),
+                transfer(1, Asset::Token(weth), searcher, coinbase),
+                transfer(2, Asset::Ether, searcher, other),
+                transfer(3, Asset::Ether, coinbase, proposer),
+                // tokens of the builder are not paid to the proposer
+                transfer(4, Asset::Token(weth), coinbase, other),
+            ],
+        };
+
+        let payments: Vec<_> = flow
+            .builder_payments(coinbase)
+            .map(|(payment, transfer)| (payment, transfer.step))
+            .collect();
+        assert_eq!(
+            payments,
+            vec![
+                (BuilderPayment::Coinbase, 0),
+                (BuilderPayment::Coinbase, 1),
+                (BuilderPayment::Proposer, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reverted_transaction() {
+        let (sender, a) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(
+                a,
+                CallKind::Call,
+                0,
+                vec![DebugStep { instruction: opcode::REVERT, ..Default::default() }],
+            )],
+            sender: Some(sender),
+            value: U256::from(1),
+            ..Default::default()
+        };
+        assert!(FundsFlow::new(&artifact).transfers.is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+//! Calls to simple getters, e.g., `balanceOf` or `decimals`, which stepping treats as single
+//! steps by default, since they rarely matter to what is being debugged.
+
+use std::collections::BTreeSet;
+
+use revm_inspectors::tracing::types::CallKind;
+
+use crate::export::calltree::CallFrame;
+
+/// The most gas a call may use to be treated as a simple getter.
+pub const MAX_GETTER_GAS: u64 = 10_000;
+
+/// Returns whether the frame is a call to a simple getter, i.e., a successful `STATICCALL`, which
+/// cannot write state, making no sub-call and using little gas.
+pub fn is_getter_call(frame: &CallFrame) -> bool {
+    frame.kind == CallKind::StaticCall &&
+        frame.children.is_empty() &&
+        frame.gas_used <= MAX_GETTER_GAS &&
+        frame.outcome.is_success()
+}
+
+/// Returns the nodes of the arena of the calls to simple getters. Each of them has a single node,
+/// since it makes no sub-call.
+pub fn getter_nodes(frames: &[CallFrame]) -> BTreeSet<usize> {
+    frames.iter().filter(|frame| is_getter_call(frame)).map(|frame| frame.node).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+    use revm::interpreter::opcode;
+
+    use super::*;
+    use crate::{
+        artifact::debug::{DebugArtifact, DebugNodeFlat, DebugStep},
+        export::calltree::call_frames,
+    };
+
+    fn node(kind: CallKind, depth: usize, instruction: u8, gas: u64) -> DebugNodeFlat {
+        let steps = vec![
+            DebugStep { instruction: opcode::PUSH0, total_gas_used: 0, ..Default::default() },
+            DebugStep { instruction, total_gas_used: gas, ..Default::default() },
+        ];
+        DebugNodeFlat::new(Address::with_last_byte(depth as u8), kind, depth, steps)
+    }
+
+    #[test]
+    fn test_getter_nodes() {
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                node(CallKind::Call, 0, opcode::STATICCALL, 100),
+                // a getter
+                node(CallKind::StaticCall, 1, opcode::RETURN, 2_000),
+                node(CallKind::Call, 0, opcode::STATICCALL, 3_000),
+                // too expensive to be a getter
+                node(CallKind::StaticCall, 1, opcode::RETURN, 50_000),
+                node(CallKind::Call, 0, opcode::CALL, 60_000),
+                // not static
+                node(CallKind::Call, 1, opcode::RETURN, 61_000),
+                node(CallKind::Call, 0, opcode::STOP, 62_000),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(getter_nodes(&call_frames(&artifact)), BTreeSet::from([1]));
+    }
+}
@@ -0,0 +1,220 @@
+//! Decode the executions of governance proposals, i.e., the `execute` calls to Governor and
+//! timelock contracts, into the individual actions they carry out.
+//!
+//! `GovernorBravo` proposals are executed from their id only, but each of their actions goes
+//! through a call to the Compound timelock, which is decoded on its own.
+
+use std::{fmt, ops::Range};
+
+use alloy_primitives::{keccak256, Address, Bytes, U256};
+use alloy_sol_types::{sol, SolCall};
+
+use crate::export::calltree::CallFrame;
+
+sol! {
+    // OpenZeppelin Governor
+    function execute(address[] targets, uint256[] values, bytes[] calldatas, bytes32 descriptionHash);
+}
+
+mod timelock {
+    alloy_sol_types::sol! {
+        // OpenZeppelin TimelockController
+        function execute(address target, uint256 value, bytes payload, bytes32 predecessor, bytes32 salt);
+        function executeBatch(address[] targets, uint256[] values, bytes[] payloads, bytes32 predecessor, bytes32 salt);
+        // Compound Timelock
+        function executeTransaction(address target, uint256 value, string signature, bytes data, uint256 eta);
+    }
+}
+
+/// The kind of governance contract executing the actions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GovernanceKind {
+    Governor,
+    TimelockController,
+    CompoundTimelock,
+}
+
+impl fmt::Display for GovernanceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Governor => write!(f, "Governor"),
+            Self::TimelockController => write!(f, "TimelockController"),
+            Self::CompoundTimelock => write!(f, "Timelock"),
+        }
+    }
+}
+
+/// An action of a proposal, i.e., a call the governance contract makes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GovernanceAction {
+    pub target: Address,
+    pub value: U256,
+    pub calldata: Bytes,
+    /// The index of the frame of the call carrying out the action, if it was made.
+    pub frame: Option<usize>,
+    /// The steps of the call carrying out the action in the whole execution, if it was made.
+    pub steps: Option<Range<usize>>,
+}
+
+/// An `execute` call to a governance contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GovernanceExecution {
+    pub kind: GovernanceKind,
+    pub contract: Address,
+    /// The index of the `execute` frame.
+    pub frame: usize,
+    /// The steps of the `execute` call in the whole execution.
+    pub steps: Range<usize>,
+    pub actions: Vec<GovernanceAction>,
+}
+
+/// Decodes the executions of governance proposals among the given call frames, in the order of
+/// execution.
+pub fn governance_executions(frames: &[CallFrame]) -> Vec<GovernanceExecution> {
+    let mut executions = Vec::new();
+    for (index, frame) in frames.iter().enumerate() {
+        let Some((kind, actions)) = decode_actions(&frame.input) else { continue };
+        // the implementation behind a proxy decodes the same call
+        if frame.is_forwarded(frames) {
+            continue;
+        }
+        let contract = match (frame.kind.is_delegate(), frame.caller) {
+            (true, Some(caller)) => caller,
+            _ => frame.address,
+        };
+
+        // Actions are carried out in order, possibly through a timelock, so that each of them is
+        // matched with the first call to its target, with its calldata, following the previous
+        // action
+        let mut next = index + 1;
+        let actions = actions
+            .into_iter()
+            .map(|(target, value, calldata)| {
+                let found = frames
+                    .iter()
+                    .enumerate()
+                    .skip(next)
+                    .take_while(|(_, inner)| inner.steps.end <= frame.steps.end)
+                    .find(|(_, inner)| {
+                        !inner.kind.is_delegate() &&
+                            inner.address == target &&
+                            inner.input == calldata
+                    });
+                if let Some((i, _)) = found {
+                    next = i + 1;
+                }
+                GovernanceAction {
+                    target,
+                    value,
+                    calldata,
+                    frame: found.map(|(i, _)| i),
+                    steps: found.map(|(_, inner)| inner.steps.clone()),
+                }
+            })
+            .collect();
+
+        executions.push(GovernanceExecution {
+            kind,
+            contract,
+            frame: index,
+            steps: frame.steps.clone(),
+            actions,
+        });
+    }
+    executions
+}
+
+/// Decodes the actions (target, value, and calldata) of an `execute` call.
+fn decode_actions(input: &[u8]) -> Option<(GovernanceKind, Vec<(Address, U256, Bytes)>)> {
+    let zip = |targets: Vec<Address>, values: Vec<U256>, calldatas: Vec<Bytes>| {
+        targets
+            .into_iter()
+            .zip(values)
+            .zip(calldatas)
+            .map(|((target, value), calldata)| (target, value, calldata))
+            .collect()
+    };
+
+    if let Ok(call) = executeCall::abi_decode(input, false) {
+        Some((GovernanceKind::Governor, zip(call.targets, call.values, call.calldatas)))
+    } else if let Ok(call) = timelock::executeBatchCall::abi_decode(input, false) {
+        Some((GovernanceKind::TimelockController, zip(call.targets, call.values, call.payloads)))
+    } else if let Ok(call) = timelock::executeCall::abi_decode(input, false) {
+        Some((GovernanceKind::TimelockController, vec![(call.target, call.value, call.payload)]))
+    } else if let Ok(call) = timelock::executeTransactionCall::abi_decode(input, false) {
+        // the selector of the signature, if given, is prepended to the data
+        let calldata = if call.signature.is_empty() {
+            call.data
+        } else {
+            [&keccak256(call.signature.as_bytes())[..4], &call.data[..]].concat().into()
+        };
+        Some((GovernanceKind::CompoundTimelock, vec![(call.target, call.value, calldata)]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::interpreter::opcode;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::{
+        artifact::debug::{DebugArtifact, DebugNodeFlat, DebugStep},
+        export::calltree::call_frames,
+    };
+
+    #[test]
+    fn test_governance_executions() {
+        let (controller, first, second) =
+            (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let (first_data, second_data) =
+            (Bytes::from_static(&[0x01, 0x02, 0x03, 0x04]), Bytes::from_static(&[0x05]));
+        let input: Bytes = timelock::executeBatchCall {
+            targets: vec![first, second],
+            values: vec![U256::ZERO, U256::from(1)],
+            payloads: vec![first_data.clone(), second_data],
+            predecessor: Default::default(),
+            salt: Default::default(),
+        }
+        .abi_encode()
+        .into();
+
+        // the first action is carried out, then the execution reverts
+        let step = |calldata: &Bytes, instruction| DebugStep {
+            calldata: calldata.clone(),
+            instruction,
+            ..Default::default()
+        };
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(controller, CallKind::Call, 0, vec![step(&input, opcode::CALL)]),
+                DebugNodeFlat::new(
+                    first,
+                    CallKind::Call,
+                    1,
+                    vec![step(&first_data, opcode::PUSH0), step(&first_data, opcode::STOP)],
+                ),
+                DebugNodeFlat::new(
+                    controller,
+                    CallKind::Call,
+                    0,
+                    vec![step(&input, opcode::REVERT)],
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let executions = governance_executions(&call_frames(&artifact));
+        assert_eq!(executions.len(), 1);
+        let execution = &executions[0];
+        assert_eq!(execution.kind, GovernanceKind::TimelockController);
+        assert_eq!(execution.contract, controller);
+        assert_eq!(execution.actions.len(), 2);
+        assert_eq!(execution.actions[0].frame, Some(1));
+        assert_eq!(execution.actions[0].steps, Some(1..3));
+        assert_eq!(execution.actions[1].value, U256::from(1));
+        assert_eq!(execution.actions[1].frame, None);
+    }
+}
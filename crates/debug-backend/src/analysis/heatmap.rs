@@ -0,0 +1,105 @@
+//! Execution heat map of a code, i.e., how many times each of its instructions and source lines
+//! was executed over the whole transaction.
+
+use std::path::Path;
+
+use alloy_primitives::Address;
+use rustc_hash::FxHashMap;
+
+use crate::artifact::{compilation::CompilationArtifact, debug::DebugArtifact};
+
+/// The number of executions of each instruction of a code, keyed by program counter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeatMap {
+    pub counts: FxHashMap<usize, usize>,
+    /// The largest count, which is the hottest instruction.
+    pub max: usize,
+}
+
+impl HeatMap {
+    /// Counts the executions of the code at `address` across all its calls, or of its init code
+    /// if `is_create`.
+    pub fn new(artifact: &DebugArtifact, address: Address, is_create: bool) -> Self {
+        let mut counts = FxHashMap::default();
+        for node in artifact
+            .debug_arena
+            .iter()
+            .filter(|node| node.address == address && node.kind.is_any_create() == is_create)
+        {
+            for step in &node.steps {
+                *counts.entry(step.pc).or_default() += 1;
+            }
+        }
+        let max = counts.values().copied().max().unwrap_or(0);
+        Self { counts, max }
+    }
+
+    /// Returns the number of executions of the instruction at `pc`.
+    pub fn count(&self, pc: usize) -> usize {
+        self.counts.get(&pc).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of executions of each (1-based) line of the given source file. A line
+    /// counts as executed as many times as its hottest instruction, since a statement spans
+    /// several instructions.
+    pub fn line_counts(
+        &self,
+        artifact: &CompilationArtifact,
+        is_create: bool,
+        path: &Path,
+    ) -> FxHashMap<usize, usize> {
+        let mut lines = FxHashMap::default();
+        for (pc, count) in &self.counts {
+            let Some((element, file)) = artifact.source_element(*pc, is_create) else { continue };
+            if file.path == path {
+                let line = lines.entry(file.line_of(element.offset() as usize)).or_default();
+                *line = (*line).max(*count);
+            }
+        }
+        lines
+    }
+
+    /// Returns the heat of the given count between 0 (cold) and 1 (hottest), on a logarithmic
+    /// scale, since loops are executed orders of magnitude more often than the rest.
+    pub fn heat(&self, count: usize) -> f64 {
+        if count == 0 || self.max <= 1 {
+            return count.min(1) as f64;
+        }
+        (count as f64).ln() / (self.max as f64).ln()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    #[test]
+    fn test_heat_map() {
+        let steps = |pcs: &[usize]| {
+            pcs.iter().map(|pc| DebugStep { pc: *pc, ..Default::default() }).collect::<Vec<_>>()
+        };
+        let a = Address::with_last_byte(1);
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(a, CallKind::Call, 0, steps(&[0, 2, 4, 2, 4, 2, 4])),
+                DebugNodeFlat::new(Address::with_last_byte(2), CallKind::Call, 1, steps(&[0])),
+                DebugNodeFlat::new(a, CallKind::Call, 0, steps(&[2, 5])),
+                DebugNodeFlat::new(a, CallKind::Create, 1, steps(&[0, 1])),
+            ],
+            ..Default::default()
+        };
+
+        let heat_map = HeatMap::new(&artifact, a, false);
+        assert_eq!(heat_map.count(0), 1);
+        assert_eq!(heat_map.count(2), 4);
+        assert_eq!(heat_map.count(1), 0);
+        assert_eq!(heat_map.max, 4);
+        assert_eq!(heat_map.heat(4), 1.0);
+        assert_eq!(heat_map.heat(1), 0.0);
+        assert!(heat_map.heat(2) > 0.0 && heat_map.heat(2) < 1.0);
+    }
+}
@@ -0,0 +1,97 @@
+//! Detect the standard interfaces implemented by the contracts touched by the transaction.
+//!
+//! Contracts are probed with read-only calls against the state prior to the transaction.
+//! Standards with an ERC-165 interface id are detected through `supportsInterface`, while
+//! ERC-20 and ERC-4626 are detected by calling their view functions.
+
+use std::fmt;
+
+use alloy_primitives::{Address, FixedBytes, U256};
+use alloy_sol_types::{sol, SolCall};
+use revm::{primitives::EnvWithHandlerCfg, DatabaseRef};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::evm::static_call;
+
+sol! {
+    function supportsInterface(bytes4 interfaceId) external view returns (bool);
+    function totalSupply() external view returns (uint256);
+    function balanceOf(address owner) external view returns (uint256);
+    function allowance(address owner, address spender) external view returns (uint256);
+    function asset() external view returns (address);
+    function convertToShares(uint256 assets) external view returns (uint256);
+}
+
+/// A standard interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum InterfaceStandard {
+    Erc20,
+    Erc721,
+    Erc1155,
+    Erc4626,
+    AccessControl,
+}
+
+impl fmt::Display for InterfaceStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Erc20 => write!(f, "ERC-20"),
+            Self::Erc721 => write!(f, "ERC-721"),
+            Self::Erc1155 => write!(f, "ERC-1155"),
+            Self::Erc4626 => write!(f, "ERC-4626"),
+            Self::AccessControl => write!(f, "AccessControl"),
+        }
+    }
+}
+
+/// Interface ids of the standards detected through ERC-165.
+const ERC165_STANDARDS: &[(InterfaceStandard, [u8; 4])] = &[
+    (InterfaceStandard::Erc721, [0x80, 0xac, 0x58, 0xcd]),
+    (InterfaceStandard::Erc1155, [0xd9, 0xb6, 0x7a, 0x26]),
+    (InterfaceStandard::AccessControl, [0x79, 0x65, 0xdb, 0x0b]),
+];
+
+/// Detects the standard interfaces implemented by the contract at the given address.
+pub(crate) fn detect_interfaces<DBRef: DatabaseRef + Copy>(
+    db: DBRef,
+    env: &EnvWithHandlerCfg,
+    address: Address,
+) -> Vec<InterfaceStandard> {
+    let call = |input: Vec<u8>| static_call(db, env, address, input.into());
+    let supports = |id: [u8; 4]| {
+        call(supportsInterfaceCall { interfaceId: FixedBytes(id) }.abi_encode())
+            .and_then(|output| supportsInterfaceCall::abi_decode_returns(&output, false).ok())
+            .map_or(false, |ret| ret._0)
+    };
+    let returns_word = |input: Vec<u8>| call(input).map_or(false, |output| output.len() == 32);
+
+    let mut standards = Vec::new();
+
+    // ERC-165 itself requires `supportsInterface(0xffffffff)` to be false
+    if supports([0x01, 0xff, 0xc9, 0xa7]) && !supports([0xff; 4]) {
+        for (standard, id) in ERC165_STANDARDS {
+            if supports(*id) {
+                standards.push(*standard);
+            }
+        }
+    }
+
+    // ERC-721 has `balanceOf` and `totalSupply` as well, but not `allowance`
+    let is_erc20 = returns_word(totalSupplyCall {}.abi_encode()) &&
+        returns_word(balanceOfCall { owner: Address::ZERO }.abi_encode()) &&
+        returns_word(
+            allowanceCall { owner: Address::ZERO, spender: Address::ZERO }.abi_encode(),
+        );
+    if is_erc20 && !standards.contains(&InterfaceStandard::Erc721) {
+        standards.push(InterfaceStandard::Erc20);
+
+        if returns_word(assetCall {}.abi_encode()) &&
+            returns_word(convertToSharesCall { assets: U256::ZERO }.abi_encode())
+        {
+            standards.push(InterfaceStandard::Erc4626);
+        }
+    }
+
+    standards.sort();
+    standards
+}
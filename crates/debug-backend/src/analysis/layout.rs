@@ -0,0 +1,118 @@
+//! The size and layout of the code of the touched contracts: how much of it is executable code,
+//! embedded data, and solc metadata, and how close it is to the size limit of EIP-170.
+
+use std::collections::BTreeMap;
+
+use alloy_primitives::Address;
+use revm::primitives::MAX_CODE_SIZE;
+use serde::Serialize;
+
+use crate::{
+    analysis::cfg::ControlFlowGraph, artifact::debug::DebugArtifact, export::calltree::CallFrame,
+    utils::compilation::bytecode_bytes,
+};
+
+/// The layout of the runtime code of a contract, in bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CodeLayout {
+    pub runtime_size: usize,
+    /// The size of the init code the contract was created with during the execution, or of
+    /// its compiled init code without the constructor arguments otherwise, if known.
+    pub init_size: Option<usize>,
+    /// The bytes of the blocks reachable from the entry point.
+    pub code_size: usize,
+    /// The bytes of the blocks which cannot be reached, usually embedded data, e.g., constants.
+    pub data_size: usize,
+    /// The CBOR metadata appended by solc.
+    pub metadata_size: usize,
+    /// The number of immutable variables, known for verified contracts.
+    pub immutables: Option<usize>,
+    /// The number of places the immutable variables are inlined at.
+    pub immutable_references: Option<usize>,
+}
+
+impl CodeLayout {
+    /// Computes the layout of the runtime code from its control-flow graph.
+    pub fn new(code: &[u8], cfg: &ControlFlowGraph) -> Self {
+        let end = cfg.metadata.as_ref().map_or(code.len(), |metadata| metadata.start);
+        let (mut code_size, mut data_size) = (0, 0);
+        for (index, block) in cfg.blocks.iter().enumerate() {
+            let next = cfg.blocks.get(index + 1).map_or(end, |next| next.start());
+            let size = next.min(end).saturating_sub(block.start());
+            if block.reachable {
+                code_size += size;
+            } else {
+                data_size += size;
+            }
+        }
+        Self {
+            runtime_size: code.len(),
+            code_size,
+            data_size,
+            metadata_size: code.len() - end,
+            ..Default::default()
+        }
+    }
+
+    /// Returns how many bytes the runtime code may still grow before reaching the size limit of
+    /// EIP-170, which is negative if it exceeds it.
+    pub fn headroom(&self) -> isize {
+        MAX_CODE_SIZE as isize - self.runtime_size as isize
+    }
+}
+
+/// Computes the layout of the code of every touched contract with code.
+pub fn code_layouts(
+    artifact: &DebugArtifact,
+    frames: &[CallFrame],
+) -> BTreeMap<Address, CodeLayout> {
+    artifact
+        .codes
+        .iter()
+        .filter(|(_, code)| !code.is_empty())
+        .map(|(address, code)| {
+            let mut layout = match artifact.cfgs.get(address) {
+                Some(cfg) => CodeLayout::new(code, cfg),
+                None => CodeLayout::new(code, &ControlFlowGraph::new(code)),
+            };
+
+            let compilation = artifact.compilation_artifacts.get(address);
+            let created =
+                frames.iter().find(|frame| frame.kind.is_any_create() && frame.address == *address);
+            layout.init_size = match created {
+                Some(frame) => Some(frame.input.len()),
+                None => compilation
+                    .and_then(|compilation| compilation.evm.bytecode.as_ref())
+                    .and_then(bytecode_bytes)
+                    .map(|code| code.len()),
+            };
+
+            let references = compilation
+                .and_then(|compilation| compilation.evm.deployed_bytecode.as_ref())
+                .map(|deployed| &deployed.immutable_references);
+            layout.immutables = references.map(|references| references.len());
+            layout.immutable_references =
+                references.map(|references| references.values().map(Vec::len).sum());
+            (*address, layout)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::hex;
+
+    use super::*;
+
+    #[test]
+    fn test_code_layout() {
+        // push1 5, jump, (data) 0xaaaa, jumpdest, stop, followed by a one-entry CBOR map
+        let code = hex!("600556" "aaaa" "5b00" "a16161" "0003");
+        let layout = CodeLayout::new(&code, &ControlFlowGraph::new(&code));
+
+        assert_eq!(layout.runtime_size, code.len());
+        assert_eq!(layout.metadata_size, 5);
+        assert_eq!((layout.code_size, layout.data_size), (5, 2));
+        assert_eq!(layout.headroom(), (MAX_CODE_SIZE - code.len()) as isize);
+    }
+}
@@ -0,0 +1,140 @@
+//! Cross-contract interaction matrix, i.e., which account caused writes to which account's
+//! storage and sent value to whom, aggregated over the whole transaction. This maps the trust
+//! relationships exercised by the transaction, e.g., a router allowed to move funds of a pool.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_primitives::{Address, U256};
+use revm::interpreter::opcode;
+use revm_inspectors::tracing::types::CallKind;
+
+use crate::{
+    analysis::funds::{Asset, FundsFlow},
+    artifact::debug::DebugArtifact,
+    export::calltree::{call_frames, reverted_steps},
+};
+
+/// The interactions between the accounts touched by the transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InteractionMatrix {
+    /// The number of storage writes, keyed by the sender of the writing call (`None` for the
+    /// sender of the transaction) and the account owning the storage.
+    pub storage_writes: BTreeMap<(Option<Address>, Address), usize>,
+    /// The ether sent, keyed by sender and recipient.
+    pub ether: BTreeMap<(Address, Address), U256>,
+    /// The number of token transfers, keyed by sender and recipient.
+    pub token_transfers: BTreeMap<(Address, Address), usize>,
+}
+
+impl InteractionMatrix {
+    pub fn new(artifact: &DebugArtifact) -> Self {
+        let mut matrix = Self::default();
+
+        // The storage context and the sender of each open call frame, by depth. Delegate calls
+        // keep both the context and the sender of their caller.
+        let mut frames: Vec<(Address, Option<Address>)> = Vec::new();
+        // the writes of the reverted frames are rolled back
+        let reverted = reverted_steps(&call_frames(artifact));
+        let mut first_step = 0;
+        for node in &artifact.debug_arena {
+            if node.depth < frames.len() {
+                frames.truncate(node.depth + 1);
+            } else {
+                let frame = match frames.last() {
+                    None => (node.address, None),
+                    Some((context, sender)) => match node.kind {
+                        CallKind::DelegateCall => (*context, *sender),
+                        CallKind::CallCode => (*context, Some(*context)),
+                        _ => (node.address, Some(*context)),
+                    },
+                };
+                frames.resize(node.depth + 1, frame);
+            }
+
+            let (context, sender) = frames[node.depth];
+            let count = (first_step..)
+                .zip(&node.steps)
+                .filter(|(step, debug_step)| {
+                    debug_step.instruction == opcode::SSTORE &&
+                        !reverted.iter().any(|steps| steps.contains(step))
+                })
+                .count();
+            first_step += node.steps.len();
+            if count > 0 {
+                *matrix.storage_writes.entry((sender, context)).or_default() += count;
+            }
+        }
+
+        for transfer in FundsFlow::new(artifact).transfers {
+            let key = (transfer.from, transfer.to);
+            match transfer.asset {
+                Asset::Ether => {
+                    let total = matrix.ether.entry(key).or_default();
+                    *total = total.saturating_add(transfer.amount);
+                }
+                Asset::Token(_) => *matrix.token_transfers.entry(key).or_default() += 1,
+            }
+        }
+
+        matrix
+    }
+
+    /// Returns the accounts of the matrix, sorted by address. The sender of the transaction is
+    /// left out unless it moved funds.
+    pub fn accounts(&self) -> Vec<Address> {
+        let mut accounts = BTreeSet::new();
+        for (sender, owner) in self.storage_writes.keys() {
+            accounts.extend(*sender);
+            accounts.insert(*owner);
+        }
+        for (from, to) in self.ether.keys().chain(self.token_transfers.keys()) {
+            accounts.insert(*from);
+            accounts.insert(*to);
+        }
+        accounts.into_iter().collect()
+    }
+
+    /// Returns whether the transaction wrote no storage and moved no funds.
+    pub fn is_empty(&self) -> bool {
+        self.storage_writes.is_empty() && self.ether.is_empty() && self.token_transfers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    fn node(byte: u8, kind: CallKind, depth: usize, instructions: &[u8]) -> DebugNodeFlat {
+        let steps = instructions
+            .iter()
+            .map(|instruction| DebugStep { instruction: *instruction, ..Default::default() })
+            .collect();
+        DebugNodeFlat::new(Address::with_last_byte(byte), kind, depth, steps)
+    }
+
+    #[test]
+    fn test_storage_writes() {
+        let (router, pool, implementation) =
+            (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                node(1, CallKind::Call, 0, &[opcode::SSTORE, opcode::CALL]),
+                node(2, CallKind::Call, 1, &[opcode::SSTORE, opcode::DELEGATECALL]),
+                node(3, CallKind::DelegateCall, 2, &[opcode::SSTORE, opcode::SSTORE]),
+                node(2, CallKind::Call, 1, &[opcode::CALL]),
+                // reverted, so that its write is rolled back
+                node(4, CallKind::Call, 2, &[opcode::SSTORE, opcode::REVERT]),
+                node(2, CallKind::Call, 1, &[opcode::STOP]),
+                node(1, CallKind::Call, 0, &[opcode::STOP]),
+            ],
+            ..Default::default()
+        };
+
+        let matrix = InteractionMatrix::new(&artifact);
+        let expected = BTreeMap::from([((None, router), 1), ((Some(router), pool), 3)]);
+        assert_eq!(matrix.storage_writes, expected);
+        assert_eq!(matrix.accounts(), vec![router, pool]);
+        assert!(!matrix.accounts().contains(&implementation));
+    }
+}
@@ -0,0 +1,201 @@
+//! Label the memory regions of a call by how they are produced or consumed.
+//!
+//! Solidity lays memory out in a very regular way: the first four words are reserved, and most
+//! of the remaining regions are built for a single consumer (e.g., the arguments of a call or
+//! the data of an event). Labeling them turns the memory hexdump into a structured view.
+
+use alloy_primitives::{Address, U256};
+use revm::interpreter::opcode;
+
+use crate::{
+    analysis::shadow::stack_usize,
+    artifact::debug::{DebugArtifact, DebugStep},
+};
+
+/// Selector of `Error(string)`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// A labeled region of memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryLabel {
+    /// The offset of the region.
+    pub offset: usize,
+    /// The size of the region.
+    pub size: usize,
+    /// The label of the region.
+    pub label: String,
+}
+
+/// Labels the memory of the `step`-th step of the `node`-th node in the debug arena.
+///
+/// Labels are returned by priority: string literals first, then regions consumed by an
+/// instruction of the call (the nearest consumer after the current step first), and finally the
+/// reserved slots. Regions overlapping a higher-priority one of the same kind are skipped.
+pub fn memory_labels(artifact: &DebugArtifact, node: usize, step: usize) -> Vec<MemoryLabel> {
+    let steps = &artifact.debug_arena[node].steps;
+    let memory_len = steps[step].memory.len();
+
+    let mut labels: Vec<MemoryLabel> = Vec::new();
+    let push = |labels: &mut Vec<MemoryLabel>, start: usize, label: MemoryLabel| {
+        let overlaps = labels[start..].iter().any(|other| {
+            label.offset < other.offset + other.size && other.offset < label.offset + label.size
+        });
+        if label.size > 0 && label.offset < memory_len && !overlaps {
+            labels.push(label);
+        }
+    };
+
+    // String literals stored so far
+    for step in steps[..step].iter().rev() {
+        if let Some(label) = string_literal(step) {
+            push(&mut labels, 0, label);
+        }
+    }
+
+    // Regions consumed by the instructions of the call
+    let start = labels.len();
+    let consumers = steps[step..].iter().chain(steps[..step].iter().rev());
+    for step in consumers {
+        if let Some(label) = consumed_region(artifact, step) {
+            push(&mut labels, start, label);
+        }
+    }
+
+    // Reserved slots
+    let start = labels.len();
+    for (offset, label) in
+        [(0x00, "scratch space"), (0x40, "free memory pointer"), (0x60, "zero slot")]
+    {
+        let size = if offset == 0 { 0x40 } else { 0x20 };
+        push(&mut labels, start, MemoryLabel { offset, size, label: label.to_string() });
+    }
+
+    labels
+}
+
+/// Returns the name of the ERC-20 function with the given selector which moves (or approves)
+/// tokens, as well as the offset of the amount in the calldata.
+fn token_function(selector: &[u8]) -> Option<(&'static str, usize)> {
+    match selector {
+        [0xa9, 0x05, 0x9c, 0xbb] => Some(("transfer", 4 + 32)),
+        [0x09, 0x5e, 0xa7, 0xb3] => Some(("approve", 4 + 32)),
+        [0x23, 0xb8, 0x72, 0xdd] => Some(("transferFrom", 4 + 64)),
+        _ => None,
+    }
+}
+
+/// Labels the word written by an `MSTORE` if it looks like a short string literal, i.e.,
+/// printable ASCII padded with zeros on the right.
+fn string_literal(step: &DebugStep) -> Option<MemoryLabel> {
+    if step.instruction != opcode::MSTORE {
+        return None;
+    }
+    let offset = stack_usize(step, 0)?;
+    let value = step.stack[step.stack.len().checked_sub(2)?].to_be_bytes::<32>();
+
+    let len = value.iter().position(|b| *b == 0).unwrap_or(32);
+    if len < 4 || value[len..].iter().any(|b| *b != 0) {
+        return None;
+    }
+    let text = std::str::from_utf8(&value[..len]).ok()?;
+    if !text.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        return None;
+    }
+
+    Some(MemoryLabel { offset, size: 32, label: format!("string literal {text:?}") })
+}
+
+/// Labels the region consumed (or produced, for `CODECOPY`) by the instruction of the step.
+fn consumed_region(artifact: &DebugArtifact, step: &DebugStep) -> Option<MemoryLabel> {
+    let (offset, size, label) = match step.instruction {
+        opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL => {
+            let (offset, size) = if matches!(step.instruction, opcode::CALL | opcode::CALLCODE) {
+                (stack_usize(step, 3)?, stack_usize(step, 4)?)
+            } else {
+                (stack_usize(step, 2)?, stack_usize(step, 3)?)
+            };
+            let target = step.stack[step.stack.len() - 2].to_be_bytes::<32>();
+            let target = Address::from_slice(&target[12..]);
+
+            let selector = step.memory.get(offset..offset + 4).filter(|_| size >= 4);
+            let mut function = selector
+                .and_then(|selector| {
+                    artifact.abi(&target)?.functions().find(|f| f.selector().as_slice() == selector)
+                })
+                .map(|f| format!(".{}", f.name))
+                .unwrap_or_default();
+
+            // Render the amount of token transfers and approvals
+            if let Some((name, amount_offset)) = selector.and_then(token_function) {
+                let amount = step
+                    .memory
+                    .get(offset + amount_offset..offset + amount_offset + 32)
+                    .filter(|_| size >= amount_offset + 32)
+                    .and_then(|word| {
+                        artifact.format_token_amount(&target, U256::from_be_slice(word))
+                    });
+                if let Some(amount) = amount {
+                    if function.is_empty() {
+                        function = format!(".{name}");
+                    }
+                    function = format!("{function} ({amount})");
+                }
+            }
+            (
+                offset,
+                size,
+                format!("abi.encode of call to {}{function}", artifact.address_label(&target)),
+            )
+        }
+        opcode::RETURN => (stack_usize(step, 0)?, stack_usize(step, 1)?, "return data".to_string()),
+        opcode::REVERT => {
+            let (offset, size) = (stack_usize(step, 0)?, stack_usize(step, 1)?);
+            let is_error =
+                size >= 4 && step.memory.get(offset..offset + 4) == Some(&ERROR_SELECTOR[..]);
+            let label = if is_error { "revert data of Error(string)" } else { "revert data" };
+            (offset, size, label.to_string())
+        }
+        opcode::LOG0 | opcode::LOG1 | opcode::LOG2 | opcode::LOG3 | opcode::LOG4 => {
+            (stack_usize(step, 0)?, stack_usize(step, 1)?, "event data".to_string())
+        }
+        opcode::KECCAK256 => {
+            (stack_usize(step, 0)?, stack_usize(step, 1)?, "keccak256 input".to_string())
+        }
+        opcode::CREATE | opcode::CREATE2 => {
+            (stack_usize(step, 1)?, stack_usize(step, 2)?, "init code".to_string())
+        }
+        opcode::CODECOPY => {
+            (stack_usize(step, 0)?, stack_usize(step, 2)?, "code constant".to_string())
+        }
+        _ => return None,
+    };
+
+    Some(MemoryLabel { offset, size, label })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_literal() {
+        let mut value = [0u8; 32];
+        value[..5].copy_from_slice(b"hello");
+        let step = DebugStep {
+            instruction: opcode::MSTORE,
+            stack: vec![U256::from_be_bytes(value), U256::from(0x80)],
+            ..Default::default()
+        };
+        assert_eq!(
+            string_literal(&step),
+            Some(MemoryLabel { offset: 0x80, size: 32, label: "string literal \"hello\"".into() })
+        );
+
+        let step = DebugStep {
+            instruction: opcode::MSTORE,
+            stack: vec![U256::from(0x1234), U256::from(0x80)],
+            ..Default::default()
+        };
+        assert_eq!(string_literal(&step), None);
+    }
+}
@@ -1,3 +1,35 @@
-pub mod prune;
-pub mod scope;
-pub mod source_map;
+pub mod allowance;
+pub mod assembly;
+pub mod cfg;
+pub mod clone;
+pub mod constants;
+pub mod decode;
+pub mod dependency;
+pub mod deployment;
+pub mod diff;
+pub mod eip712;
+pub mod entry;
+pub mod events;
+pub mod funds;
+pub mod getter;
+pub mod governance;
+pub mod heatmap;
+pub mod interface;
+pub mod layout;
+pub mod matrix;
+pub mod memory;
+pub mod multicall;
+pub mod price;
+pub mod provenance;
+pub(crate) mod prune;
+pub mod safe;
+pub(crate) mod scope;
+pub(crate) mod shadow;
+pub mod signature;
+pub(crate) mod source_map;
+pub mod storage;
+pub mod suggest;
+pub mod taint;
+pub mod timeline;
+pub mod token;
+pub mod userop;
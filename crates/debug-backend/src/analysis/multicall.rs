@@ -0,0 +1,270 @@
+//! Recognize the batches of calls made through Multicall contracts (and the `multicall`
+//! functions of contracts batching calls to themselves), so that each call of a batch can be
+//! presented on its own, with its own outcome, rather than as part of an opaque aggregate call.
+
+use std::fmt;
+
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::{sol, SolCall};
+
+use crate::export::calltree::CallFrame;
+
+sol! {
+    struct Call {
+        address target;
+        bytes callData;
+    }
+
+    struct Call3 {
+        address target;
+        bool allowFailure;
+        bytes callData;
+    }
+
+    struct Call3Value {
+        address target;
+        bool allowFailure;
+        uint256 value;
+        bytes callData;
+    }
+
+    // Multicall and Multicall2
+    function aggregate(Call[] calls);
+    function tryAggregate(bool requireSuccess, Call[] calls);
+    function blockAndAggregate(Call[] calls);
+    function tryBlockAndAggregate(bool requireSuccess, Call[] calls);
+    // Multicall3
+    function aggregate3(Call3[] calls);
+    function aggregate3Value(Call3Value[] calls);
+    // self-batching contracts, e.g., the Uniswap routers
+    function multicall(bytes[] data);
+    function multicall(uint256 deadline, bytes[] data);
+    function multicall(bytes32 previousBlockhash, bytes[] data);
+}
+
+/// The kind of batching contract.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchKind {
+    /// Multicall or Multicall2, the latter allowing failures with `tryAggregate`.
+    Multicall,
+    Multicall3,
+    /// A contract delegate-calling itself with each of the calls.
+    SelfMulticall,
+}
+
+impl fmt::Display for BatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Multicall => write!(f, "Multicall"),
+            Self::Multicall3 => write!(f, "Multicall3"),
+            Self::SelfMulticall => write!(f, "multicall"),
+        }
+    }
+}
+
+/// A call of a batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchedCall {
+    pub target: Address,
+    pub value: U256,
+    pub calldata: Bytes,
+    /// Whether the batch goes on if the call fails.
+    pub allow_failure: bool,
+    /// The index of the frame of the call, if it was made.
+    pub frame: Option<usize>,
+    /// Whether the call succeeded, if it was made.
+    pub success: Option<bool>,
+}
+
+/// A batch of calls, i.e., an aggregate call to a batching contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Batch {
+    pub kind: BatchKind,
+    pub contract: Address,
+    /// The index of the aggregate frame.
+    pub frame: usize,
+    pub calls: Vec<BatchedCall>,
+}
+
+impl Batch {
+    /// Returns the number of calls which failed.
+    pub fn num_failed(&self) -> usize {
+        self.calls.iter().filter(|call| call.success == Some(false)).count()
+    }
+
+    /// Returns the index of the call made in the given frame, if it is one of the batch.
+    pub fn call_of(&self, frame: usize) -> Option<usize> {
+        self.calls.iter().position(|call| call.frame == Some(frame))
+    }
+}
+
+/// Recognizes the batches of calls among the given call frames, in the order of execution.
+pub fn batches(frames: &[CallFrame]) -> Vec<Batch> {
+    let mut batches = Vec::new();
+    for (index, frame) in frames.iter().enumerate() {
+        let Some((kind, calls)) = decode_calls(&frame.input) else { continue };
+        if frame.is_forwarded(frames) {
+            continue;
+        }
+        let contract = match (frame.kind.is_delegate(), frame.caller) {
+            (true, Some(caller)) => caller,
+            _ => frame.address,
+        };
+
+        // the calls are made in order, directly by the aggregate frame
+        let mut children = frame.children.iter().copied();
+        let calls = calls
+            .into_iter()
+            .map(|(target, allow_failure, value, calldata)| {
+                let target = target.unwrap_or(contract);
+                let child = children.by_ref().find(|child| {
+                    frames[*child].address == target && frames[*child].input == calldata
+                });
+                BatchedCall {
+                    target,
+                    value,
+                    calldata,
+                    allow_failure,
+                    frame: child,
+                    success: child.map(|child| frames[child].outcome.is_success()),
+                }
+            })
+            .collect();
+
+        batches.push(Batch { kind, contract, frame: index, calls });
+    }
+    batches
+}
+
+/// A call to decode, i.e., its target (`None` for the batching contract itself), whether it may
+/// fail, its value, and its calldata.
+type DecodedCall = (Option<Address>, bool, U256, Bytes);
+
+/// Decodes the calls of an aggregate call.
+fn decode_calls(input: &[u8]) -> Option<(BatchKind, Vec<DecodedCall>)> {
+    let calls = |calls: Vec<Call>, allow_failure: bool| {
+        calls
+            .into_iter()
+            .map(|call| (Some(call.target), allow_failure, U256::ZERO, call.callData))
+            .collect()
+    };
+    let own =
+        |data: Vec<Bytes>| data.into_iter().map(|data| (None, false, U256::ZERO, data)).collect();
+
+    if let Ok(call) = aggregateCall::abi_decode(input, false) {
+        Some((BatchKind::Multicall, calls(call.calls, false)))
+    } else if let Ok(call) = blockAndAggregateCall::abi_decode(input, false) {
+        Some((BatchKind::Multicall, calls(call.calls, false)))
+    } else if let Ok(call) = tryAggregateCall::abi_decode(input, false) {
+        Some((BatchKind::Multicall, calls(call.calls, !call.requireSuccess)))
+    } else if let Ok(call) = tryBlockAndAggregateCall::abi_decode(input, false) {
+        Some((BatchKind::Multicall, calls(call.calls, !call.requireSuccess)))
+    } else if let Ok(call) = aggregate3Call::abi_decode(input, false) {
+        let calls = call
+            .calls
+            .into_iter()
+            .map(|call| (Some(call.target), call.allowFailure, U256::ZERO, call.callData))
+            .collect();
+        Some((BatchKind::Multicall3, calls))
+    } else if let Ok(call) = aggregate3ValueCall::abi_decode(input, false) {
+        let calls = call
+            .calls
+            .into_iter()
+            .map(|call| (Some(call.target), call.allowFailure, call.value, call.callData))
+            .collect();
+        Some((BatchKind::Multicall3, calls))
+    } else if let Ok(call) = multicall_0Call::abi_decode(input, false) {
+        Some((BatchKind::SelfMulticall, own(call.data)))
+    } else if let Ok(call) = multicall_1Call::abi_decode(input, false) {
+        Some((BatchKind::SelfMulticall, own(call.data)))
+    } else if let Ok(call) = multicall_2Call::abi_decode(input, false) {
+        Some((BatchKind::SelfMulticall, own(call.data)))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::interpreter::opcode;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::{
+        artifact::debug::{DebugArtifact, DebugNodeFlat, DebugStep},
+        export::calltree::call_frames,
+    };
+
+    #[test]
+    fn test_batches() {
+        let (multicall, token, pool) =
+            (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let (balance_of, slot0) = (
+            Bytes::from_static(&[0x70, 0xa0, 0x82, 0x31]),
+            Bytes::from_static(&[0x38, 0x50, 0xc7, 0xbd]),
+        );
+        let input: Bytes = aggregate3Call {
+            calls: vec![
+                Call3 { target: token, allowFailure: true, callData: balance_of.clone() },
+                Call3 { target: pool, allowFailure: true, callData: slot0.clone() },
+            ],
+        }
+        .abi_encode()
+        .into();
+
+        // the first call reverts, which the batch allows
+        let step = |calldata: &Bytes, instruction| DebugStep {
+            calldata: calldata.clone(),
+            instruction,
+            ..Default::default()
+        };
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(
+                    multicall,
+                    CallKind::Call,
+                    0,
+                    vec![step(&input, opcode::STATICCALL)],
+                ),
+                DebugNodeFlat::new(
+                    token,
+                    CallKind::StaticCall,
+                    1,
+                    vec![step(&balance_of, opcode::REVERT)],
+                ),
+                DebugNodeFlat::new(
+                    multicall,
+                    CallKind::Call,
+                    0,
+                    vec![step(&input, opcode::STATICCALL)],
+                ),
+                DebugNodeFlat::new(
+                    pool,
+                    CallKind::StaticCall,
+                    1,
+                    vec![step(&slot0, opcode::RETURN)],
+                ),
+                DebugNodeFlat::new(
+                    multicall,
+                    CallKind::Call,
+                    0,
+                    vec![step(&input, opcode::RETURN)],
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let batches = batches(&call_frames(&artifact));
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.kind, BatchKind::Multicall3);
+        assert_eq!(
+            batch.calls.iter().map(|call| call.frame).collect::<Vec<_>>(),
+            vec![Some(1), Some(2)]
+        );
+        assert_eq!(batch.calls[0].success, Some(false));
+        assert_eq!(batch.calls[1].success, Some(true));
+        assert_eq!(batch.num_failed(), 1);
+        assert_eq!(batch.call_of(2), Some(1));
+    }
+}
@@ -0,0 +1,194 @@
+//! Approximate USD prices of assets at the replayed block on Ethereum mainnet.
+//!
+//! Prices are read from the Chainlink price feeds of a handful of assets. The other tokens are
+//! priced in ether by the time-weighted average price (TWAP) of their most liquid Uniswap V3 pool
+//! with WETH, over the half hour before the block. Prices are meant to give an idea of the value
+//! moved by a transaction, not to be accurate.
+
+use std::{cell::OnceCell, collections::HashMap};
+
+use alloy_primitives::{address, Address};
+use alloy_sol_types::{sol, SolCall};
+use revm::{primitives::EnvWithHandlerCfg, DatabaseRef};
+
+use crate::{analysis::funds::Asset, utils::evm::static_call};
+
+sol! {
+    function decimals() external view returns (uint8);
+    function latestRoundData() external view returns (
+        uint80 roundId,
+        int256 answer,
+        uint256 startedAt,
+        uint256 updatedAt,
+        uint80 answeredInRound
+    );
+
+    function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool);
+    function liquidity() external view returns (uint128);
+    function observe(uint32[] secondsAgos) external view returns (
+        int56[] tickCumulatives,
+        uint160[] secondsPerLiquidityCumulativeX128s
+    );
+}
+
+/// Chainlink USD price feed of ether on Ethereum mainnet.
+const ETH_USD_FEED: Address = address!("5f4eC3Df9cbd43714FE2740f5E3616155c5b8419");
+
+/// Wrapped ether on Ethereum mainnet, which the other tokens are priced against on Uniswap.
+const WETH: Address = address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+
+/// The Uniswap V3 factory on Ethereum mainnet.
+const UNISWAP_V3_FACTORY: Address = address!("1F98431c8aD98523631AE4a59f267346ea31F984");
+
+/// The fee tiers of the Uniswap V3 pools considered, in hundredths of a basis point.
+const UNISWAP_V3_FEES: [u32; 3] = [500, 3_000, 10_000];
+
+/// The period the Uniswap prices are averaged over, in seconds.
+const TWAP_PERIOD: u32 = 1_800;
+
+/// Chainlink USD price feeds of tokens on Ethereum mainnet.
+const TOKEN_USD_FEEDS: &[(Address, Address)] = &[
+    // WETH
+    (address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"), ETH_USD_FEED),
+    // WBTC (BTC / USD)
+    (
+        address!("2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"),
+        address!("F4030086522a5bEEa4988F8cA5B36dbC97BeE88c"),
+    ),
+    // USDC
+    (
+        address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+        address!("8fFfFfd4AfB6115b954Bd326cbe7B4BA576818f6"),
+    ),
+    // USDT
+    (
+        address!("dAC17F958D2ee523a2206206994597C13D831ec7"),
+        address!("3E7d1eAB13ad0104d2750B8863b489D65364e32D"),
+    ),
+    // DAI
+    (
+        address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+        address!("Aed0c38402a5d19df6E4c03F4E2DceD6e29c1ee9"),
+    ),
+    // LINK
+    (
+        address!("514910771AF9Ca656af840dff83E8264EcF986CA"),
+        address!("2c1d072e956AFFC0D435Cb7AC38EF18d24d9127c"),
+    ),
+];
+
+/// Reads the USD price of the given assets from Chainlink, or from Uniswap for the tokens
+/// without a known feed. Assets which can be priced by neither are left out.
+///
+/// The database and environment are expected to be the ones of the replayed block on Ethereum
+/// mainnet.
+pub fn usd_prices<DBRef: DatabaseRef + Copy>(
+    db: DBRef,
+    env: &EnvWithHandlerCfg,
+    assets: impl IntoIterator<Item = Asset>,
+) -> HashMap<Asset, f64> {
+    let eth_usd = OnceCell::new();
+    assets
+        .into_iter()
+        .filter_map(|asset| {
+            let price = match asset {
+                Asset::Ether => read_feed(db, env, ETH_USD_FEED),
+                Asset::Token(token) => match TOKEN_USD_FEEDS.iter().find(|(t, _)| *t == token) {
+                    Some((_, feed)) => read_feed(db, env, *feed),
+                    None => {
+                        let eth_usd = *eth_usd.get_or_init(|| read_feed(db, env, ETH_USD_FEED));
+                        uniswap_eth_price(db, env, token).zip(eth_usd).map(|(eth, usd)| eth * usd)
+                    }
+                },
+            };
+            Some((asset, price?))
+        })
+        .collect()
+}
+
+/// Reads the price of a token in ether from the most liquid of its Uniswap V3 pools with WETH
+/// which can be averaged over [`TWAP_PERIOD`].
+fn uniswap_eth_price<DBRef: DatabaseRef + Copy>(
+    db: DBRef,
+    env: &EnvWithHandlerCfg,
+    token: Address,
+) -> Option<f64> {
+    let call = |to: Address, input: Vec<u8>| static_call(db, env, to, input.into());
+
+    let output = call(token, decimalsCall {}.abi_encode())?;
+    let decimals = decimalsCall::abi_decode_returns(&output, false).ok()?._0;
+
+    let observe = observeCall { secondsAgos: vec![TWAP_PERIOD, 0] }.abi_encode();
+    UNISWAP_V3_FEES
+        .into_iter()
+        .filter_map(|fee| {
+            let get_pool = getPoolCall { tokenA: token, tokenB: WETH, fee: fee.try_into().ok()? };
+            let output = call(UNISWAP_V3_FACTORY, get_pool.abi_encode())?;
+            let pool = getPoolCall::abi_decode_returns(&output, false).ok()?.pool;
+            if pool.is_zero() {
+                return None;
+            }
+
+            let output = call(pool, liquidityCall {}.abi_encode())?;
+            let liquidity = liquidityCall::abi_decode_returns(&output, false).ok()?._0;
+            // the pool must have recorded enough observations to be averaged
+            let output = call(pool, observe.clone())?;
+            let ticks = observeCall::abi_decode_returns(&output, false).ok()?.tickCumulatives;
+            let [start, end] = ticks[..] else { return None };
+            let price = twap_eth_price(end - start, token < WETH, decimals);
+            Some((liquidity, price))
+        })
+        .max_by_key(|(liquidity, _)| *liquidity)
+        .map(|(_, price)| price)
+}
+
+/// Converts the change of the cumulative tick of a token/WETH pool over [`TWAP_PERIOD`] into the
+/// price of the token in ether. `is_token0` is whether the token is the first token of the pool,
+/// i.e., has the lower address.
+fn twap_eth_price(tick_change: i64, is_token0: bool, decimals: u8) -> f64 {
+    let tick = tick_change as f64 / TWAP_PERIOD as f64;
+    // the amount of the second token per unit of the first one, in their smallest units
+    let ratio = 1.0001f64.powf(tick);
+    let raw = if is_token0 { ratio } else { 1.0 / ratio };
+    raw * 10f64.powi(decimals as i32 - 18)
+}
+
+/// Reads the latest answer of a Chainlink feed.
+fn read_feed<DBRef: DatabaseRef + Copy>(
+    db: DBRef,
+    env: &EnvWithHandlerCfg,
+    feed: Address,
+) -> Option<f64> {
+    let output = static_call(db, env, feed, decimalsCall {}.abi_encode().into())?;
+    let decimals = decimalsCall::abi_decode_returns(&output, false).ok()?._0;
+
+    let output = static_call(db, env, feed, latestRoundDataCall {}.abi_encode().into())?;
+    let answer = latestRoundDataCall::abi_decode_returns(&output, false).ok()?.answer;
+    if answer.is_negative() {
+        return None;
+    }
+
+    let answer: f64 = answer.to_string().parse().ok()?;
+    Some(answer / 10f64.powi(decimals as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_twap_eth_price() {
+        let close = |a: f64, b: f64| (a - b).abs() / b < 1e-6;
+        // a tick of 0 is a price of 1 in the smallest units
+        assert!(close(twap_eth_price(0, true, 18), 1.0));
+        assert!(close(twap_eth_price(0, false, 6), 1e-12));
+        // a token with 6 decimals worth 1/2000 ether, e.g., USDC, is the first token of its pool,
+        // and is worth 5e8 wei per smallest unit, i.e., at tick ln(5e8) / ln(1.0001)
+        let tick = (5e8f64.ln() / 1.0001f64.ln()).round() as i64;
+        let price = twap_eth_price(tick * TWAP_PERIOD as i64, true, 6);
+        assert!((price - 0.0005).abs() < 0.0005 * 1e-4);
+        // and the inverse as the second token
+        let price = twap_eth_price(-tick * TWAP_PERIOD as i64, false, 6);
+        assert!((price - 0.0005).abs() < 0.0005 * 1e-4);
+    }
+}
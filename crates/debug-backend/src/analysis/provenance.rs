@@ -0,0 +1,116 @@
+//! Track where the return data of sub-calls ends up in the stack and memory of the caller.
+//!
+//! Return data enters the memory of the caller either through the output area of the call
+//! itself or through `RETURNDATACOPY`. From there, it is followed through `MLOAD`, `MSTORE`,
+//! `MCOPY`, `DUP` and `SWAP`. Any other instruction produces a new value without provenance.
+
+use alloy_primitives::Address;
+use revm::interpreter::opcode;
+
+use crate::{
+    analysis::shadow::{stack_usize, Shadow, Tag},
+    artifact::debug::{DebugArtifact, DebugStep},
+};
+
+/// The sub-call which returned a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReturndataOrigin {
+    /// The callee, or `None` for a contract creation.
+    pub target: Option<Address>,
+    /// The index of the call instruction in the whole execution.
+    pub step: usize,
+}
+
+impl Tag for Option<ReturndataOrigin> {
+    fn join(&self, other: &Self) -> Self {
+        self.or(*other)
+    }
+
+    fn compute(_inputs: &[Self]) -> Self {
+        None
+    }
+}
+
+/// The provenance of the stack items and memory bytes at a step.
+#[derive(Clone, Debug, Default)]
+pub struct Provenance {
+    /// The origin of each stack item, from the bottom to the top.
+    pub stack: Vec<Option<ReturndataOrigin>>,
+    /// Memory regions, i.e., `(offset, size, origin)`, holding return data.
+    pub memory: Vec<(usize, usize, ReturndataOrigin)>,
+}
+
+/// Computes the provenance of the stack and memory prior to the `step`-th step of the `node`-th
+/// node in the debug arena.
+pub fn returndata_provenance(artifact: &DebugArtifact, node: usize, step: usize) -> Provenance {
+    let mut shadow = Shadow::<Option<ReturndataOrigin>>::default();
+    // The most recent sub-call, whose return data is the current return data buffer
+    let mut last_call = None;
+
+    for i in artifact.frame_nodes(node) {
+        let steps = &artifact.debug_arena[i].steps;
+        let end = if i == node { step } else { steps.len() };
+        for (j, s) in steps[..end].iter().enumerate() {
+            shadow.execute(s);
+
+            match s.instruction {
+                opcode::CALL |
+                opcode::CALLCODE |
+                opcode::DELEGATECALL |
+                opcode::STATICCALL |
+                opcode::CREATE |
+                opcode::CREATE2 => {
+                    let origin = ReturndataOrigin {
+                        target: call_target(s),
+                        step: artifact.step_index(i, j),
+                    };
+                    last_call = Some(origin);
+
+                    let output = match s.instruction {
+                        opcode::CALL | opcode::CALLCODE => Some((5, 6)),
+                        opcode::DELEGATECALL | opcode::STATICCALL => Some((4, 5)),
+                        _ => None,
+                    };
+                    if let Some((offset, size)) = output {
+                        if let (Some(offset), Some(size)) =
+                            (stack_usize(s, offset), stack_usize(s, size))
+                        {
+                            shadow.set_memory(offset, size, Some(origin));
+                        }
+                    }
+                }
+                opcode::RETURNDATACOPY => {
+                    if let (Some(offset), Some(size)) = (stack_usize(s, 0), stack_usize(s, 2)) {
+                        shadow.set_memory(offset, size, last_call);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let current = &artifact.debug_arena[node].steps[step];
+    shadow.align(current.stack.len());
+
+    // Merge consecutive bytes with the same origin into regions
+    let mut bytes: Vec<_> = shadow.memory.iter().filter_map(|(i, o)| Some((*i, (*o)?))).collect();
+    bytes.sort_unstable_by_key(|(i, _)| *i);
+    let mut memory: Vec<(usize, usize, ReturndataOrigin)> = Vec::new();
+    for (i, origin) in bytes {
+        match memory.last_mut() {
+            Some((offset, size, last)) if *offset + *size == i && *last == origin => *size += 1,
+            _ => memory.push((i, 1, origin)),
+        }
+    }
+
+    Provenance { stack: shadow.stack, memory }
+}
+
+/// Returns the callee of a call instruction.
+fn call_target(step: &DebugStep) -> Option<Address> {
+    if matches!(step.instruction, opcode::CREATE | opcode::CREATE2) {
+        return None;
+    }
+    let target = step.stack.get(step.stack.len().checked_sub(2)?)?;
+    Some(Address::from_word(target.to_be_bytes::<32>().into()))
+}
@@ -164,11 +164,11 @@ mod tests {
     use foundry_block_explorers::Client;
     use foundry_compilers::{
         artifacts::{output_selection::OutputSelection, SolcInput, Source},
-        solc::{Solc, SolcLanguage},
+        solc::SolcLanguage,
     };
     use serial_test::serial;
 
-    use crate::etherscan_rate_limit_guard;
+    use crate::{etherscan_rate_limit_guard, solc::SolcManager};
 
     use super::*;
 
@@ -195,7 +195,7 @@ mod tests {
 
         // prepare the compiler
         let version = meta.compiler_version()?;
-        let compiler = Solc::find_or_install(&version)?;
+        let compiler = SolcManager::default().get(&version).await?;
 
         let mut output = compiler.compile_exact(&input)?;
         for (_, contract) in output.sources.iter_mut() {
@@ -0,0 +1,261 @@
+//! Decode the transactions executed by Safe (formerly Gnosis Safe) multisig wallets, i.e., their
+//! `execTransaction` calls, along with the owners who signed them. The call made by the wallet
+//! is the logical transaction the owners meant, the outer one merely relaying it.
+
+use std::fmt;
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_sol_types::{sol, SolCall};
+
+use crate::{
+    analysis::signature::{ecrecover_calls, Ecrecover, ECRECOVER},
+    artifact::debug::DebugArtifact,
+    export::calltree::CallFrame,
+};
+
+sol! {
+    function execTransaction(
+        address to,
+        uint256 value,
+        bytes data,
+        uint8 operation,
+        uint256 safeTxGas,
+        uint256 baseGas,
+        uint256 gasPrice,
+        address gasToken,
+        address refundReceiver,
+        bytes signatures
+    ) returns (bool success);
+}
+
+/// How a signature of a Safe transaction was made, which is told by its `v` byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureKind {
+    /// An EIP-1271 signature of a contract owner.
+    Contract,
+    /// A hash approved on-chain beforehand, or the sender of the transaction being an owner.
+    ApprovedHash,
+    /// A signature of the hash prefixed by `eth_sign`.
+    EthSign,
+    Ecdsa,
+}
+
+impl fmt::Display for SignatureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contract => write!(f, "contract signature"),
+            Self::ApprovedHash => write!(f, "approved hash"),
+            Self::EthSign => write!(f, "eth_sign"),
+            Self::Ecdsa => write!(f, "ECDSA"),
+        }
+    }
+}
+
+/// A signature of a Safe transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SafeSignature {
+    pub kind: SignatureKind,
+    /// The owner who signed, which is unknown if the signature was not checked, e.g., because
+    /// the transaction reverted before.
+    pub signer: Option<Address>,
+}
+
+/// A transaction executed by a Safe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SafeTransaction {
+    pub safe: Address,
+    /// The index of the `execTransaction` frame, run by the singleton of the Safe.
+    pub frame: usize,
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    /// Whether the Safe delegate-calls the target rather than calling it.
+    pub delegate: bool,
+    pub signatures: Vec<SafeSignature>,
+    /// The index of the frame of the call made by the Safe, if it was made.
+    pub inner: Option<usize>,
+}
+
+impl SafeTransaction {
+    /// Returns the owners known to have signed, in the order of the signatures.
+    pub fn signers(&self) -> impl Iterator<Item = Address> + '_ {
+        self.signatures.iter().filter_map(|signature| signature.signer)
+    }
+}
+
+/// Decodes the Safe transactions of the given call frames, in the order of execution.
+pub fn safe_transactions(artifact: &DebugArtifact, frames: &[CallFrame]) -> Vec<SafeTransaction> {
+    let mut transactions = Vec::new();
+    let calls = ecrecover_calls(artifact);
+    for (index, frame) in frames.iter().enumerate() {
+        let Ok(call) = execTransactionCall::abi_decode(&frame.input, false) else { continue };
+
+        // Safes are proxies delegating to a singleton, whose frame is the one of interest
+        if frame.is_forwarded(frames) {
+            continue;
+        }
+        let safe = match (frame.kind.is_delegate(), frame.caller) {
+            (true, Some(caller)) => caller,
+            _ => frame.address,
+        };
+
+        let delegate = call.operation == 1;
+        let inner = frame.children.iter().copied().find(|child| {
+            let child = &frames[*child];
+            child.address == call.to &&
+                child.input == call.data &&
+                child.kind.is_delegate() == delegate
+        });
+
+        let mut recovered = recovered_signers(artifact, frame, &calls).into_iter();
+        let signatures = call
+            .signatures
+            .chunks_exact(65)
+            .map(|signature| {
+                let owner = Address::from_word(B256::from_slice(&signature[..32]));
+                match signature[64] {
+                    0 => SafeSignature { kind: SignatureKind::Contract, signer: Some(owner) },
+                    1 => SafeSignature { kind: SignatureKind::ApprovedHash, signer: Some(owner) },
+                    v => SafeSignature {
+                        kind: if v > 30 { SignatureKind::EthSign } else { SignatureKind::Ecdsa },
+                        signer: recovered.next(),
+                    },
+                }
+            })
+            .collect();
+
+        transactions.push(SafeTransaction {
+            safe,
+            frame: index,
+            to: call.to,
+            value: call.value,
+            data: call.data,
+            delegate,
+            signatures,
+            inner,
+        });
+    }
+    transactions
+}
+
+/// Returns the addresses recovered by the given `ecrecover` calls which the frame makes itself,
+/// in order, rather than the contracts it calls.
+fn recovered_signers(
+    artifact: &DebugArtifact,
+    frame: &CallFrame,
+    calls: &[Ecrecover],
+) -> Vec<Address> {
+    let depth = artifact.debug_arena[frame.node].depth;
+    calls
+        .iter()
+        .filter(|call| frame.steps.contains(&call.step))
+        .filter(|call| {
+            artifact
+                .locate_step(call.step)
+                .is_some_and(|(node, _)| artifact.debug_arena[node].depth == depth)
+        })
+        .filter_map(|call| call.recovered)
+        // an invalid signature recovers nothing, which makes the Safe revert anyway
+        .filter(|signer| *signer != Address::ZERO && *signer != ECRECOVER)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::interpreter::opcode;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::{
+        artifact::debug::{DebugNodeFlat, DebugStep},
+        export::calltree::call_frames,
+    };
+
+    #[test]
+    fn test_safe_transaction() {
+        let (proxy, singleton, target) =
+            (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let (approver, signer) = (Address::with_last_byte(4), Address::with_last_byte(5));
+
+        let mut approved = [0u8; 65];
+        approved[12..32].copy_from_slice(approver.as_slice());
+        approved[64] = 1;
+        let mut ecdsa = [0u8; 65];
+        ecdsa[64] = 27;
+        let data = Bytes::from_static(&[0xa9, 0x05, 0x9c, 0xbb]);
+        let input: Bytes = execTransactionCall {
+            to: target,
+            value: U256::ZERO,
+            data: data.clone(),
+            operation: 0,
+            safeTxGas: U256::ZERO,
+            baseGas: U256::ZERO,
+            gasPrice: U256::ZERO,
+            gasToken: Address::ZERO,
+            refundReceiver: Address::ZERO,
+            signatures: [approved, ecdsa].concat().into(),
+        }
+        .abi_encode()
+        .into();
+
+        // ecrecover(hash, v, r, s) returning the signer to the memory at 0x80
+        let recover = DebugStep {
+            instruction: opcode::STATICCALL,
+            stack: [0x20u64, 0x80, 0x80, 0x00, 1, 10_000].map(U256::from).to_vec(),
+            memory: vec![0u8; 0x80].into(),
+            calldata: input.clone(),
+            ..Default::default()
+        };
+        let mut memory = vec![0u8; 0xa0];
+        memory[0x80 + 12..].copy_from_slice(signer.as_slice());
+        let recovered = DebugStep { memory: memory.into(), ..Default::default() };
+        let call = DebugStep { instruction: opcode::CALL, ..Default::default() };
+        let at_call = |steps| {
+            let mut steps: Vec<DebugStep> = steps;
+            for step in &mut steps {
+                step.calldata = input.clone();
+            }
+            steps
+        };
+
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(
+                    proxy,
+                    CallKind::Call,
+                    0,
+                    at_call(vec![DebugStep {
+                        instruction: opcode::DELEGATECALL,
+                        ..Default::default()
+                    }]),
+                ),
+                DebugNodeFlat::new(
+                    singleton,
+                    CallKind::DelegateCall,
+                    1,
+                    at_call(vec![recover, recovered, call]),
+                ),
+                DebugNodeFlat::new(
+                    target,
+                    CallKind::Call,
+                    2,
+                    vec![DebugStep {
+                        calldata: data,
+                        instruction: opcode::STOP,
+                        ..Default::default()
+                    }],
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let frames = call_frames(&artifact);
+        let transactions = safe_transactions(&artifact, &frames);
+        assert_eq!(transactions.len(), 1);
+        let transaction = &transactions[0];
+        assert_eq!((transaction.safe, transaction.to), (proxy, target));
+        assert_eq!(transaction.inner, Some(2));
+        assert_eq!(transaction.signers().collect::<Vec<_>>(), vec![approver, signer]);
+        assert_eq!(transaction.signatures[1].kind, SignatureKind::Ecdsa);
+    }
+}
@@ -0,0 +1,187 @@
+//! A shadow stack and memory, which replay the recorded steps of a call frame while
+//! propagating a tag attached to each stack item and memory byte.
+//!
+//! The shadow machine only knows how values move around (e.g., `DUP`, `SWAP`, `MLOAD`,
+//! `MSTORE`, `MCOPY`); what a tag means and how the tags of the inputs of other instructions
+//! combine is up to the analysis built on top of it.
+
+use alloy_primitives::U256;
+use revm::interpreter::{opcode, OpCode};
+use rustc_hash::FxHashMap;
+
+use crate::artifact::debug::DebugStep;
+
+/// Memory regions larger than this are not tracked.
+const MAX_TRACKED_REGION: usize = 1 << 20;
+
+/// A tag attached to a value.
+pub(crate) trait Tag: Clone + Default + PartialEq {
+    /// Joins the tags of the bytes loaded together as a word.
+    fn join(&self, other: &Self) -> Self;
+
+    /// Computes the tag of the outputs of an instruction from the tags of its inputs.
+    fn compute(inputs: &[Self]) -> Self;
+}
+
+/// The shadow stack and memory.
+#[derive(Debug, Default)]
+pub(crate) struct Shadow<T> {
+    /// Tags of the stack items, from the bottom to the top.
+    pub(crate) stack: Vec<T>,
+    /// Tags of the memory bytes. Bytes without an entry have the default tag.
+    pub(crate) memory: FxHashMap<usize, T>,
+}
+
+/// Returns the `n`-th item from the top of the recorded stack, as a memory offset or size.
+pub(crate) fn stack_usize(step: &DebugStep, n: usize) -> Option<usize> {
+    let item = step.stack.len().checked_sub(n + 1).map(|i| step.stack[i])?;
+    (item <= U256::from(u32::MAX)).then(|| item.to::<usize>())
+}
+
+impl<T: Tag> Shadow<T> {
+    /// Aligns the shadow stack with the recorded stack of the given length, in case they get
+    /// out of sync (e.g., at the beginning of a frame).
+    pub(crate) fn align(&mut self, len: usize) {
+        if self.stack.len() > len {
+            self.stack.drain(..self.stack.len() - len);
+        } else if self.stack.len() < len {
+            let missing = len - self.stack.len();
+            self.stack.splice(0..0, std::iter::repeat(T::default()).take(missing));
+        }
+    }
+
+    /// Returns the tag of the given memory region.
+    pub(crate) fn memory_tag(&self, offset: usize, size: usize) -> T {
+        (offset..offset + size)
+            .filter_map(|i| self.memory.get(&i))
+            .fold(T::default(), |acc, tag| acc.join(tag))
+    }
+
+    /// Sets the tag of the given memory region.
+    pub(crate) fn set_memory(&mut self, offset: usize, size: usize, tag: T) {
+        if size > MAX_TRACKED_REGION {
+            return;
+        }
+        for i in offset..offset + size {
+            if tag == T::default() {
+                self.memory.remove(&i);
+            } else {
+                self.memory.insert(i, tag.clone());
+            }
+        }
+    }
+
+    fn pop(&mut self, n: usize) -> Vec<T> {
+        let n = n.min(self.stack.len());
+        let mut inputs = self.stack.split_off(self.stack.len() - n);
+        inputs.reverse();
+        inputs
+    }
+
+    /// Executes the given step on the shadow stack and memory.
+    pub(crate) fn execute(&mut self, step: &DebugStep) {
+        self.align(step.stack.len());
+
+        let op = step.instruction;
+        match op {
+            opcode::DUP1..=opcode::DUP16 => {
+                let n = (op - opcode::DUP1 + 1) as usize;
+                let tag = self.stack.len().checked_sub(n).map(|i| self.stack[i].clone());
+                self.stack.push(tag.unwrap_or_default());
+                return;
+            }
+            opcode::SWAP1..=opcode::SWAP16 => {
+                let n = (op - opcode::SWAP1 + 1) as usize;
+                let len = self.stack.len();
+                if len > n {
+                    self.stack.swap(len - 1, len - 1 - n);
+                }
+                return;
+            }
+            opcode::MLOAD => {
+                let tag = stack_usize(step, 0)
+                    .map(|offset| self.memory_tag(offset, 32))
+                    .unwrap_or_default();
+                self.pop(1);
+                self.stack.push(tag);
+                return;
+            }
+            opcode::MSTORE | opcode::MSTORE8 => {
+                let inputs = self.pop(2);
+                if let Some(offset) = stack_usize(step, 0) {
+                    let size = if op == opcode::MSTORE { 32 } else { 1 };
+                    self.set_memory(offset, size, inputs.get(1).cloned().unwrap_or_default());
+                }
+                return;
+            }
+            opcode::MCOPY => {
+                if let (Some(dest), Some(src), Some(size)) =
+                    (stack_usize(step, 0), stack_usize(step, 1), stack_usize(step, 2))
+                {
+                    let tags: Vec<_> =
+                        (src..src + size).map(|i| self.memory.get(&i).cloned()).collect();
+                    for (i, tag) in tags.into_iter().enumerate() {
+                        self.set_memory(dest + i, 1, tag.unwrap_or_default());
+                    }
+                }
+                self.pop(3);
+                return;
+            }
+            _ => {}
+        }
+
+        // Memory written by the instruction is cleared, and can be overridden by the analysis
+        let written = match op {
+            opcode::CALLDATACOPY | opcode::CODECOPY | opcode::RETURNDATACOPY => {
+                Some((stack_usize(step, 0), stack_usize(step, 2)))
+            }
+            opcode::EXTCODECOPY => Some((stack_usize(step, 1), stack_usize(step, 3))),
+            opcode::CALL | opcode::CALLCODE => Some((stack_usize(step, 5), stack_usize(step, 6))),
+            opcode::DELEGATECALL | opcode::STATICCALL => {
+                Some((stack_usize(step, 4), stack_usize(step, 5)))
+            }
+            _ => None,
+        };
+        if let Some((Some(offset), Some(size))) = written {
+            self.set_memory(offset, size, T::default());
+        }
+
+        let (inputs, outputs) =
+            OpCode::new(op).map_or((0, 0), |op| (op.inputs() as usize, op.outputs() as usize));
+        let inputs = self.pop(inputs);
+        let tag = T::compute(&inputs);
+        self.stack.extend(std::iter::repeat(tag).take(outputs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(instruction: u8, stack: &[u64]) -> DebugStep {
+        DebugStep {
+            instruction,
+            stack: stack.iter().map(|item| U256::from(*item)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_shadow() {
+        let mut shadow = Shadow::<bool> { stack: vec![true, false], ..Default::default() };
+
+        // MSTORE(0x80, tagged)
+        shadow.execute(&step(opcode::DUP2, &[7, 0]));
+        shadow.execute(&step(opcode::PUSH1, &[7, 0, 7]));
+        shadow.execute(&step(opcode::MSTORE, &[7, 0, 7, 0x80]));
+        assert_eq!(shadow.stack, vec![true, false]);
+        assert!(shadow.memory_tag(0x80, 32));
+        assert!(!shadow.memory_tag(0xa0, 32));
+
+        // ADD(MLOAD(0x80), untagged)
+        shadow.execute(&step(opcode::PUSH1, &[7, 0]));
+        shadow.execute(&step(opcode::MLOAD, &[7, 0, 0x80]));
+        shadow.execute(&step(opcode::ADD, &[7, 0, 7]));
+        assert_eq!(shadow.stack, vec![true, true]);
+    }
+}
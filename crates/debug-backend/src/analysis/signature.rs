@@ -0,0 +1,328 @@
+//! Check the signatures verified during the execution, i.e., the calls to the `ecrecover`
+//! precompile, by recomputing their digests and signers, and explain why a signature does not
+//! recover the expected signer, e.g., the owner of an EIP-2612 permit.
+
+use std::collections::HashMap;
+
+use alloy_primitives::{keccak256, uint, Address, Signature, B256, U256};
+use alloy_sol_types::{sol, SolCall};
+use revm::interpreter::opcode;
+
+use crate::{
+    analysis::{
+        eip712::{TypeRegistry, TypedData},
+        shadow::stack_usize,
+    },
+    artifact::debug::DebugArtifact,
+    export::calltree::CallFrame,
+};
+
+sol! {
+    // EIP-2612
+    function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s);
+}
+
+/// The `ecrecover` precompile.
+pub(crate) const ECRECOVER: Address = Address::with_last_byte(1);
+
+/// The struct type signed by the owners of EIP-2612 permits.
+const PERMIT_TYPE: &str =
+    "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+
+/// Half the order of the secp256k1 curve, above which `s` values are malleable.
+const HALF_ORDER: U256 =
+    uint!(0x7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a0_U256);
+
+/// A call to the `ecrecover` precompile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ecrecover {
+    /// The step making the call, in the whole execution.
+    pub step: usize,
+    pub hash: B256,
+    pub v: U256,
+    pub r: B256,
+    pub s: B256,
+    /// The address returned by the precompile, which is zero for invalid signatures, if known.
+    pub recovered: Option<Address>,
+}
+
+/// An EIP-712 digest computed during the execution, i.e., the hash of `0x1901`, the domain
+/// separator, and the hash of the signed struct.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypedDigest {
+    /// The `KECCAK256` step computing the digest, in the whole execution.
+    pub step: usize,
+    pub domain_separator: B256,
+    pub struct_hash: B256,
+    pub digest: B256,
+}
+
+/// The checks of a signature verified by `ecrecover`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureReport {
+    pub ecrecover: Ecrecover,
+    /// The signer recomputed from the inputs of the call, if the signature is valid.
+    pub signer: Option<Address>,
+    /// The signer the contract expects, if known, e.g., the owner of a permit.
+    pub expected: Option<Address>,
+    /// The last EIP-712 digest computed before the call, if any.
+    pub typed_digest: Option<TypedDigest>,
+    /// The typed data behind the digest, as far as it can be reconstructed.
+    pub typed_data: Option<TypedData>,
+    /// The nonce of the permit being verified, if any, as hashed by the token.
+    pub nonce: Option<U256>,
+    /// The problems found with the signature, explained. There are none for valid signatures
+    /// recovering the expected signer.
+    pub findings: Vec<String>,
+}
+
+/// Returns the calls to the `ecrecover` precompile, in the order of execution.
+pub fn ecrecover_calls(artifact: &DebugArtifact) -> Vec<Ecrecover> {
+    let mut calls: Vec<Ecrecover> = Vec::new();
+    // the call waiting for its result, which is read from the memory of the next step of the
+    // caller, along with the depth of the caller and the return offset
+    let mut pending: Option<(usize, usize)> = None;
+    for (index, (node, _, step)) in artifact.steps().enumerate() {
+        let depth = artifact.debug_arena[node].depth;
+        if let Some((caller_depth, offset)) = pending.take() {
+            if caller_depth == depth {
+                let word = step.memory.get(offset..offset + 32);
+                if let Some(call) = calls.last_mut() {
+                    call.recovered = word.map(|word| Address::from_word(B256::from_slice(word)));
+                }
+            }
+        }
+
+        let target = step.stack.len().checked_sub(2).map(|i| step.stack[i]);
+        let is_ecrecover = target == Some(ECRECOVER.into_word().into());
+        if step.instruction != opcode::STATICCALL || !is_ecrecover {
+            continue;
+        }
+        let (Some(args), Some(ret)) = (stack_usize(step, 2), stack_usize(step, 4)) else {
+            continue;
+        };
+        let Some(input) = step.memory.get(args..args + 128) else { continue };
+        let word = |i: usize| B256::from_slice(&input[i * 32..(i + 1) * 32]);
+        calls.push(Ecrecover {
+            step: index,
+            hash: word(0),
+            v: word(1).into(),
+            r: word(2),
+            s: word(3),
+            recovered: None,
+        });
+        pending = Some((depth, ret));
+    }
+    calls
+}
+
+/// Recovers the signer of the given hash, as the `ecrecover` precompile does, if the signature
+/// is valid.
+pub fn recover_signer(hash: &B256, v: U256, r: &B256, s: &B256) -> Option<Address> {
+    let parity = match u64::try_from(v) {
+        Ok(27) => false,
+        Ok(28) => true,
+        _ => return None,
+    };
+    let signature = Signature::from_scalars_and_parity(*r, *s, parity).ok()?;
+    signature.recover_address_from_prehash(hash).ok()
+}
+
+/// Checks the signature verified by the given call, against the EIP-712 digest and the permit
+/// being verified, if any. The typed data behind the digest is reconstructed with the types of
+/// the given registry.
+pub fn verify_signature(
+    artifact: &DebugArtifact,
+    frames: &[CallFrame],
+    registry: &TypeRegistry,
+    ecrecover: &Ecrecover,
+) -> SignatureReport {
+    let Ecrecover { step, hash, v, r, s, recovered } = *ecrecover;
+    let signer = recover_signer(&hash, v, &r, &s);
+    let mut findings = encoding_findings(v, &s);
+    if signer.is_none() && findings.is_empty() {
+        findings.push(
+            "r and s do not form a valid signature, so that no signer is recovered (the \
+             precompile returns zero)"
+                .to_string(),
+        );
+    }
+    if let (Some(signer), Some(recovered)) = (signer, recovered) {
+        if signer != recovered && recovered != Address::ZERO {
+            findings.push(format!(
+                "the precompile returned {recovered}, while the signature recovers {signer}"
+            ));
+        }
+    }
+
+    // the frames running the call, from the innermost to the outermost
+    let enclosing: Vec<&CallFrame> =
+        frames.iter().rev().filter(|frame| frame.steps.contains(&step)).collect();
+    let start = enclosing.first().map_or(0, |frame| frame.steps.start);
+    let preimages = keccak_preimages(artifact, start..step);
+
+    let typed_digest = preimages
+        .iter()
+        .filter(|(_, preimage)| preimage.len() == 66 && preimage[..2] == [0x19, 0x01])
+        .map(|(step, preimage)| TypedDigest {
+            step: *step,
+            domain_separator: B256::from_slice(&preimage[2..34]),
+            struct_hash: B256::from_slice(&preimage[34..]),
+            digest: keccak256(preimage),
+        })
+        .last();
+    if let Some(typed) = &typed_digest {
+        if typed.digest != hash {
+            findings.push(format!(
+                "the hash being checked is not the EIP-712 digest {} computed at step {}",
+                typed.digest, typed.step
+            ));
+        }
+    }
+
+    // the domain separator is often hashed before, e.g., when the token is first called
+    let typed_data = typed_digest.as_ref().map(|typed| {
+        let known: HashMap<_, _> = keccak_preimages(artifact, 0..step)
+            .into_iter()
+            .map(|(_, preimage)| (keccak256(preimage), preimage))
+            .collect();
+        registry.typed_data(typed, &known)
+    });
+
+    let permit =
+        enclosing.iter().find_map(|frame| permitCall::abi_decode(&frame.input, false).ok());
+    let expected = permit.as_ref().map(|permit| permit.owner);
+    let mut nonce = None;
+    if let (Some(permit), Some(typed)) = (&permit, &typed_digest) {
+        // the struct hash of the permit, i.e., the hash of its type hash and fields
+        let fields = preimages
+            .iter()
+            .rev()
+            .find(|(_, preimage)| {
+                preimage.len() == 6 * 32 && keccak256(preimage) == typed.struct_hash
+            })
+            .map(|(_, preimage)| preimage);
+        if let Some(fields) = fields {
+            let word = |i: usize| B256::from_slice(&fields[i * 32..(i + 1) * 32]);
+            if word(0) != keccak256(PERMIT_TYPE) {
+                findings.push(format!(
+                    "the token hashes the type {} rather than the standard `{PERMIT_TYPE}`",
+                    word(0)
+                ));
+            }
+            let arguments = [
+                ("owner", permit.owner.into_word()),
+                ("spender", permit.spender.into_word()),
+                ("value", permit.value.into()),
+                ("deadline", permit.deadline.into()),
+            ];
+            for ((name, argument), i) in arguments.into_iter().zip([1, 2, 3, 5]) {
+                if word(i) != argument {
+                    findings.push(format!(
+                        "the {name} hashed by the token ({}) differs from the argument ({argument})",
+                        word(i)
+                    ));
+                }
+            }
+            nonce = Some(word(4).into());
+
+            // Signatures recovering someone else are often made for a stale nonce
+            if signer.is_some() && signer != expected {
+                let current = U256::from_be_bytes(word(4).0);
+                let candidates =
+                    [current.checked_sub(U256::from(1)), current.checked_add(U256::from(1))];
+                for candidate in candidates.into_iter().flatten() {
+                    let mut fields = fields.to_vec();
+                    fields[4 * 32..5 * 32].copy_from_slice(&candidate.to_be_bytes::<32>());
+                    let digest = keccak256(
+                        [&[0x19, 0x01][..], &typed.domain_separator[..], &keccak256(&fields)[..]]
+                            .concat(),
+                    );
+                    if recover_signer(&digest, v, &r, &s) == expected {
+                        findings.push(format!(
+                            "the permit was signed for the nonce {candidate}, while the current \
+                             nonce of the owner is {current}"
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    if let (Some(signer), Some(expected)) = (signer, expected) {
+        if signer != expected {
+            findings.push(format!(
+                "the signature recovers {signer}, not the owner {expected}: it was signed over \
+                 other data, e.g., another domain (name, version, chain id, or verifying \
+                 contract), or by another account"
+            ));
+        }
+    }
+
+    SignatureReport {
+        ecrecover: *ecrecover,
+        signer,
+        expected,
+        typed_digest,
+        typed_data,
+        nonce,
+        findings,
+    }
+}
+
+/// Checks the encoding of a signature, which the precompile or the libraries calling it reject.
+fn encoding_findings(v: U256, s: &B256) -> Vec<String> {
+    let mut findings = Vec::new();
+    if v != U256::from(27) && v != U256::from(28) {
+        let hint = if v < U256::from(2) { " (add 27 to it)" } else { "" };
+        findings.push(format!("v is {v}, while ecrecover only accepts 27 or 28{hint}"));
+    }
+    if U256::from_be_bytes(s.0) > HALF_ORDER {
+        findings.push(
+            "s is in the upper half of the curve order, which is malleable and rejected by \
+             libraries such as OpenZeppelin's ECDSA"
+                .to_string(),
+        );
+    }
+    findings
+}
+
+/// Returns the inputs of the `KECCAK256` steps in the given range of the whole execution, along
+/// with their steps.
+fn keccak_preimages(
+    artifact: &DebugArtifact,
+    steps: std::ops::Range<usize>,
+) -> Vec<(usize, &[u8])> {
+    artifact
+        .steps()
+        .enumerate()
+        .skip(steps.start)
+        .take(steps.len())
+        .filter(|(_, (_, _, step))| step.instruction == opcode::KECCAK256)
+        .filter_map(|(index, (_, _, step))| {
+            let (offset, size) = (stack_usize(step, 0)?, stack_usize(step, 1)?);
+            Some((index, step.memory.get(offset..offset + size)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, b256, eip191_hash_message};
+
+    use super::*;
+
+    #[test]
+    fn test_recover_signer() {
+        // https://web3js.readthedocs.io/en/v1.2.2/web3-eth-accounts.html#sign
+        let hash = eip191_hash_message("Some data");
+        let r = b256!("b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd");
+        let s = b256!("6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a029");
+        let signer = address!("2c7536E3605D9C16a7a3D7b1898e529396a65c23");
+        assert_eq!(recover_signer(&hash, U256::from(28), &r, &s), Some(signer));
+        assert_ne!(recover_signer(&hash, U256::from(27), &r, &s), Some(signer));
+        assert_eq!(recover_signer(&hash, U256::from(1), &r, &s), None);
+
+        assert!(encoding_findings(U256::from(28), &s).is_empty());
+        assert_eq!(encoding_findings(U256::from(1), &B256::repeat_byte(0xff)).len(), 2);
+    }
+}
@@ -7,14 +7,20 @@ use std::{
 use eyre::{eyre, Result};
 use foundry_compilers::artifacts::{
     ast::SourceLocation,
+    sourcemap::{Jump, SourceElement},
     visitor::{Visitor, Walk},
     yul::{YulExpression, YulStatement},
     ExpressionOrVariableDeclarationStatement, SourceUnitPart, Statement,
 };
+use rayon::prelude::*;
+use revm::interpreter::opcode;
 
 use crate::{
-    artifact::compilation::CompilationArtifact,
-    utils::ast::{get_source_location_for_expression, source_with_primative_statements},
+    artifact::compilation::{CompilationArtifact, PcSourceMap},
+    utils::{
+        ast::{get_source_location_for_expression, source_with_primative_statements},
+        compilation::bytecode_bytes,
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -54,7 +60,7 @@ pub type PrimitiveStmts = BTreeMap<usize, BTreeMap<usize, ValidSourceLocation>>;
 /// Primative statements are the basic stepping blocks for debugging.
 /// This visitor will collect all primative statements and their locations.
 #[derive(Clone, Debug)]
-struct PrimativeStmtVisitor(pub PrimitiveStmts);
+pub(super) struct PrimativeStmtVisitor(pub PrimitiveStmts);
 
 impl PrimativeStmtVisitor {
     pub fn new() -> Self {
@@ -177,7 +183,7 @@ impl PrimativeStmtVisitor {
         }
     }
 
-    fn visit_yul_statment(&mut self, stmt: &YulStatement) {
+    pub(super) fn visit_yul_statment(&mut self, stmt: &YulStatement) {
         // node_group! {
         //     YulStatement;
 
@@ -269,6 +275,14 @@ impl PrimativeStmtVisitor {
         Ok(())
     }
 
+    /// Merges the statements collected by another visitor into this one.
+    fn merge(mut self, other: Self) -> Self {
+        for (index, stmts) in other.0 {
+            self.0.entry(index).or_default().extend(stmts);
+        }
+        self
+    }
+
     /// Produce the PrimativeStmts.
     fn produce(self) -> Result<PrimitiveStmts> {
         self.check_integrity()?;
@@ -280,12 +294,20 @@ impl PrimativeStmtVisitor {
 pub struct SourceMapAnalysis {}
 
 impl SourceMapAnalysis {
-    /// Analyze the source map of a compilation artifact.
-    pub fn analyze(artifact: &CompilationArtifact) -> Result<PrimitiveStmts> {
-        let mut visitor = PrimativeStmtVisitor::new();
-        for (_, source) in artifact.sources.iter() {
-            source.ast.walk(&mut visitor);
-        }
+    /// Analyze the source map of a compilation artifact, and repair the misattributions of its
+    /// source maps against the primitive statements.
+    pub fn analyze(artifact: &mut CompilationArtifact) -> Result<PrimitiveStmts> {
+        // the sources are walked apart, and their statements merged, since each of them is
+        // indexed by its own file id
+        let visitor = artifact
+            .sources
+            .par_iter()
+            .map(|(_, source)| {
+                let mut visitor = PrimativeStmtVisitor::new();
+                source.ast.walk(&mut visitor);
+                visitor
+            })
+            .reduce(PrimativeStmtVisitor::new, PrimativeStmtVisitor::merge);
 
         let units = visitor.produce()?;
 
@@ -300,6 +322,98 @@ impl SourceMapAnalysis {
             println!("{}", source_with_primative_statements(source, stmts));
         }
 
+        let runtime = artifact.evm.deployed_bytecode.as_ref().and_then(|b| b.bytecode.as_ref());
+        let runtime = runtime.and_then(bytecode_bytes).unwrap_or_default();
+        let creation = artifact.evm.bytecode.as_ref().and_then(bytecode_bytes).unwrap_or_default();
+        repair_source_map(Arc::make_mut(&mut artifact.runtime_source_map), &runtime, &units);
+        repair_source_map(Arc::make_mut(&mut artifact.creation_source_map), &creation, &units);
+
         Ok(units)
     }
 }
+
+/// Returns whether the source element lies within a primitive statement.
+fn within_statement(element: &SourceElement, stmts: &PrimitiveStmts) -> bool {
+    let Some(stmts) = element.index().and_then(|index| stmts.get(&(index as usize))) else {
+        return false;
+    };
+    let (offset, length) = (element.offset() as usize, element.length() as usize);
+    stmts
+        .range(..=offset)
+        .next_back()
+        .map_or(false, |(start, stmt)| offset + length <= start + stmt.length)
+}
+
+/// Repairs the misattributions of a source map, which are common in optimized code: the
+/// optimizer moves and merges instructions across statements and attributes them to an
+/// enclosing node, e.g., the whole function or contract, so that the current line jumps back
+/// and forth.
+///
+/// An instruction which does not lie within any statement is attributed to the statement
+/// executed right before it in the same basic block, if any. Instructions entering or leaving a
+/// function are kept, since they legitimately move to the function. Returns the number of
+/// repaired instructions.
+pub fn repair_source_map(map: &mut PcSourceMap, code: &[u8], stmts: &PrimitiveStmts) -> usize {
+    let mut pcs: Vec<usize> = map.keys().copied().collect();
+    pcs.sort_unstable();
+
+    let mut repaired = 0;
+    let mut last: Option<SourceElement> = None;
+    for pc in pcs {
+        // A basic block may be entered from anywhere
+        if code.get(pc) == Some(&opcode::JUMPDEST) {
+            last = None;
+        }
+        let element = &map[&pc];
+        if within_statement(element, stmts) {
+            last = Some(element.clone());
+        } else if element.jump() == Jump::Regular {
+            if let Some(last) = &last {
+                map.insert(pc, last.clone());
+                repaired += 1;
+            }
+        }
+    }
+    repaired
+}
+
+#[cfg(test)]
+mod tests {
+    use foundry_compilers::artifacts::sourcemap::parse;
+
+    use super::*;
+
+    #[test]
+    fn test_repair_source_map() {
+        // a statement at 10..20, and the function enclosing it at 0..100
+        let stmts = PrimitiveStmts::from([(
+            0,
+            BTreeMap::from([(10, ValidSourceLocation { start: 10, length: 10, index: 0 })]),
+        )]);
+        let elements = parse("0:100:0:-;12:3:0:-;0:100:0:-;0:100:0:o;0:100:0:-;12:3:0:-").unwrap();
+        let code = [opcode::JUMPDEST, opcode::ADD, opcode::POP, opcode::JUMP, opcode::JUMPDEST];
+        let mut map: PcSourceMap = elements.into_iter().enumerate().collect();
+
+        assert_eq!(repair_source_map(&mut map, &code, &stmts), 1);
+        // the function prologue precedes any statement, and is kept
+        assert_eq!(map[&0].offset(), 0);
+        // the instruction moved by the optimizer stays on the statement
+        assert_eq!(map[&2].offset(), 12);
+        // leaving the function, and entering a new basic block, are kept
+        assert_eq!(map[&3].offset(), 0);
+        assert_eq!(map[&4].offset(), 0);
+    }
+
+    #[test]
+    fn test_merge_visitors() {
+        let visitor = |index: usize, start: usize| {
+            let location = ValidSourceLocation { start, length: 1, index };
+            PrimativeStmtVisitor(BTreeMap::from([(index, BTreeMap::from([(start, location)]))]))
+        };
+
+        let merged = visitor(0, 1).merge(visitor(1, 2)).merge(visitor(0, 3));
+        assert_eq!(merged[&0].keys().copied().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(merged[&1].len(), 1);
+        assert!(merged.produce().is_ok());
+    }
+}
@@ -0,0 +1,181 @@
+//! Storage accesses over the execution, e.g., to follow how a single slot evolves rather than
+//! only seeing its current value.
+
+use alloy_primitives::{Address, U256};
+use revm::interpreter::opcode;
+
+use crate::artifact::debug::DebugArtifact;
+
+/// Whether a storage slot is read or written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageAccessKind {
+    Read,
+    Write,
+}
+
+/// A read (`SLOAD`) or a write (`SSTORE`) of a storage slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageAccess {
+    /// The index of the step making the access in the whole execution.
+    pub step: usize,
+    /// The account whose storage is accessed, which differs from the code address in delegate
+    /// calls.
+    pub address: Address,
+    pub slot: U256,
+    pub kind: StorageAccessKind,
+    /// The value read or written. The value read is only known from the stack of the next step,
+    /// so that it is `None` if the execution halts right after the read.
+    pub value: Option<U256>,
+}
+
+/// Collects the storage accesses in the order of execution.
+pub fn storage_accesses(artifact: &DebugArtifact) -> Vec<StorageAccess> {
+    let mut accesses = Vec::new();
+    let mut offset = 0;
+    for node in 0..artifact.debug_arena.len() {
+        accesses.extend(node_accesses(artifact, node, offset));
+        offset += artifact.debug_arena[node].steps.len();
+    }
+    accesses
+}
+
+/// Collects the accesses to the given slot of the given account in the order of execution.
+pub fn slot_history(artifact: &DebugArtifact, address: Address, slot: U256) -> Vec<StorageAccess> {
+    let mut history = Vec::new();
+    SlotScan::new(address, slot).advance(artifact, usize::MAX, &mut history);
+    history
+}
+
+/// A scan of the execution for the accesses to a storage slot, which is advanced a few nodes at
+/// a time, so that watching a slot of a long execution does not block the interface.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlotScan {
+    address: Address,
+    slot: U256,
+    /// The next node to scan.
+    node: usize,
+    /// The index of the first step of the next node in the whole execution.
+    offset: usize,
+}
+
+impl SlotScan {
+    pub fn new(address: Address, slot: U256) -> Self {
+        Self { address, slot, node: 0, offset: 0 }
+    }
+
+    /// Scans whole nodes until at least `budget` steps are scanned, appending the accesses to the
+    /// slot to `history`. Returns whether the whole execution is scanned.
+    pub fn advance(
+        &mut self,
+        artifact: &DebugArtifact,
+        budget: usize,
+        history: &mut Vec<StorageAccess>,
+    ) -> bool {
+        let mut scanned = 0;
+        while scanned < budget {
+            let Some(node) = artifact.debug_arena.get(self.node) else { break };
+            history.extend(
+                node_accesses(artifact, self.node, self.offset)
+                    .filter(|access| access.address == self.address && access.slot == self.slot),
+            );
+            scanned += node.steps.len();
+            self.offset += node.steps.len();
+            self.node += 1;
+        }
+        self.is_finished(artifact)
+    }
+
+    pub fn is_finished(&self, artifact: &DebugArtifact) -> bool {
+        self.node >= artifact.debug_arena.len()
+    }
+}
+
+/// Collects the storage accesses of a node, whose first step has the given index in the whole
+/// execution.
+fn node_accesses(
+    artifact: &DebugArtifact,
+    node: usize,
+    offset: usize,
+) -> impl Iterator<Item = StorageAccess> + '_ {
+    let address = artifact.context_address(node);
+    let steps = &artifact.debug_arena[node].steps;
+    steps.iter().enumerate().filter_map(move |(i, step)| {
+        let slot = step.stack.last().copied()?;
+        let (kind, value) = match step.instruction {
+            opcode::SSTORE if step.stack.len() >= 2 => {
+                (StorageAccessKind::Write, Some(step.stack[step.stack.len() - 2]))
+            }
+            // `SLOAD` never leaves the frame, so the value is on top of the next step's stack
+            opcode::SLOAD => (
+                StorageAccessKind::Read,
+                steps.get(i + 1).and_then(|next| next.stack.last().copied()),
+            ),
+            _ => return None,
+        };
+        Some(StorageAccess { step: offset + i, address, slot, kind, value })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    fn step(instruction: u8, stack: &[u64]) -> DebugStep {
+        let stack = stack.iter().map(|v| U256::from(*v)).collect();
+        DebugStep { instruction, stack, ..Default::default() }
+    }
+
+    #[test]
+    fn test_slot_history() {
+        let steps = vec![
+            step(opcode::SLOAD, &[1]),
+            step(opcode::SSTORE, &[7, 5, 1]),
+            step(opcode::SSTORE, &[9, 2]),
+            step(opcode::SLOAD, &[1]),
+        ];
+        let artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(Address::ZERO, CallKind::Call, 0, steps)],
+            ..Default::default()
+        };
+
+        let history = slot_history(&artifact, Address::ZERO, U256::from(1));
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].kind, StorageAccessKind::Read);
+        assert_eq!(history[0].value, Some(U256::from(1)));
+        assert_eq!((history[1].step, history[1].value), (1, Some(U256::from(5))));
+        // the last read halts the execution, so its value is unknown
+        assert_eq!(history[2].value, None);
+    }
+
+    #[test]
+    fn test_slot_scan() {
+        let node = |steps| DebugNodeFlat::new(Address::ZERO, CallKind::Call, 0, steps);
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                node(vec![step(opcode::SSTORE, &[7, 5, 1]), step(opcode::POP, &[])]),
+                node(vec![step(opcode::SLOAD, &[2])]),
+                node(vec![
+                    step(opcode::PUSH0, &[]),
+                    step(opcode::SLOAD, &[1]),
+                    step(opcode::STOP, &[5]),
+                ]),
+            ],
+            ..Default::default()
+        };
+
+        let mut history = Vec::new();
+        let mut scan = SlotScan::new(Address::ZERO, U256::from(1));
+        // the budget is spent by the first node
+        assert!(!scan.advance(&artifact, 1, &mut history));
+        assert_eq!(history.iter().map(|access| access.step).collect::<Vec<_>>(), [0]);
+        assert!(!scan.advance(&artifact, 1, &mut history));
+        assert_eq!(history.len(), 1);
+        assert!(scan.advance(&artifact, 1, &mut history));
+        assert_eq!(history.iter().map(|access| access.step).collect::<Vec<_>>(), [0, 4]);
+        assert_eq!(history[1].value, Some(U256::from(5)));
+        assert_eq!(history, slot_history(&artifact, Address::ZERO, U256::from(1)));
+    }
+}
@@ -0,0 +1,173 @@
+//! Heuristics suggesting where to start debugging, e.g., the first revert or the largest ether
+//! transfer, so that users do not have to step through the whole execution to find them.
+
+use std::{collections::HashSet, fmt};
+
+use alloy_primitives::utils::format_ether;
+use revm::interpreter::opcode;
+
+use crate::{
+    analysis::{
+        diff::storage_writes,
+        funds::{Asset, FundsFlow},
+    },
+    artifact::debug::DebugArtifact,
+};
+
+/// Why a step is worth looking at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SuggestionKind {
+    FirstRevert,
+    LargestEtherTransfer,
+    FirstUnverifiedCall,
+    FirstExternalStorageWrite,
+}
+
+impl fmt::Display for SuggestionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::FirstRevert => "first revert",
+            Self::LargestEtherTransfer => "largest ether transfer",
+            Self::FirstUnverifiedCall => "first call into an unverified contract",
+            Self::FirstExternalStorageWrite => "first storage write to another contract",
+        };
+        f.write_str(s)
+    }
+}
+
+/// A suggested starting point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    /// The index of the step in the whole execution.
+    pub step: usize,
+    pub description: String,
+}
+
+/// Suggests starting points of the execution, in the order of execution.
+///
+/// The victim of an exploit is usually not the contract called by the transaction (which the
+/// attacker controls), so the storage writes to other contracts, except the ones created in the
+/// transaction, are the interesting ones.
+pub fn suggest_steps(artifact: &DebugArtifact) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+
+    let revert = artifact.steps().position(|(_, _, step)| step.instruction == opcode::REVERT);
+    if let Some(step) = revert {
+        let (node, _) = artifact.locate_step(step).expect("step exists");
+        let address = artifact.address_label(&artifact.debug_arena[node].address);
+        suggestions.push(Suggestion {
+            kind: SuggestionKind::FirstRevert,
+            step,
+            description: format!("in {address}"),
+        });
+    }
+
+    let largest = FundsFlow::new(artifact)
+        .transfers
+        .into_iter()
+        .filter(|transfer| transfer.asset == Asset::Ether)
+        .max_by_key(|transfer| transfer.amount);
+    if let Some(transfer) = largest {
+        suggestions.push(Suggestion {
+            kind: SuggestionKind::LargestEtherTransfer,
+            step: transfer.step,
+            description: format!(
+                "{} ETH from {} to {}",
+                format_ether(transfer.amount),
+                artifact.address_label(&transfer.from),
+                artifact.address_label(&transfer.to)
+            ),
+        });
+    }
+
+    // the entry contract is where the session starts anyway
+    let unverified = artifact.debug_arena.iter().enumerate().find(|(_, node)| {
+        node.depth > 0 &&
+            !node.steps.is_empty() &&
+            !artifact.compilation_artifacts.contains_key(&node.address)
+    });
+    if let Some((node, debug_node)) = unverified {
+        suggestions.push(Suggestion {
+            kind: SuggestionKind::FirstUnverifiedCall,
+            step: artifact.step_index(node, 0),
+            description: format!("to {}", artifact.address_label(&debug_node.address)),
+        });
+    }
+
+    if let Some(entry) = artifact.debug_arena.first() {
+        let created: HashSet<_> = artifact
+            .debug_arena
+            .iter()
+            .filter(|node| node.kind.is_any_create())
+            .map(|node| node.address)
+            .collect();
+        let write = storage_writes(artifact)
+            .into_iter()
+            .find(|write| write.address != entry.address && !created.contains(&write.address));
+        if let Some(write) = write {
+            suggestions.push(Suggestion {
+                kind: SuggestionKind::FirstExternalStorageWrite,
+                step: write.step,
+                description: format!(
+                    "slot {:#x} of {}",
+                    write.slot,
+                    artifact.address_label(&write.address)
+                ),
+            });
+        }
+    }
+
+    suggestions.sort_by_key(|suggestion| suggestion.step);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+    use alloy_primitives::{Address, U256};
+    use revm_inspectors::tracing::types::CallKind;
+
+    fn step(instruction: u8, stack: &[u64]) -> DebugStep {
+        let stack = stack.iter().map(|v| U256::from(*v)).collect();
+        DebugStep { instruction, stack, ..Default::default() }
+    }
+
+    #[test]
+    fn test_suggest_steps() {
+        let attacker = Address::with_last_byte(1);
+        let victim = Address::with_last_byte(2);
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                // the attacker writes to its own storage, then sends 5 wei to the victim
+                DebugNodeFlat::new(
+                    attacker,
+                    CallKind::Call,
+                    0,
+                    vec![step(opcode::SSTORE, &[1, 0]), step(opcode::CALL, &[0, 0, 0, 0, 5, 2, 0])],
+                ),
+                // the victim writes to its storage and reverts
+                DebugNodeFlat::new(
+                    victim,
+                    CallKind::Call,
+                    1,
+                    vec![step(opcode::SSTORE, &[1, 7]), step(opcode::REVERT, &[0, 0])],
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let suggestions = suggest_steps(&artifact);
+        let found: Vec<_> = suggestions.iter().map(|s| (s.kind, s.step)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (SuggestionKind::LargestEtherTransfer, 1),
+                (SuggestionKind::FirstUnverifiedCall, 2),
+                (SuggestionKind::FirstExternalStorageWrite, 2),
+                (SuggestionKind::FirstRevert, 3),
+            ]
+        );
+    }
+}
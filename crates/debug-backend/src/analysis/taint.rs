@@ -0,0 +1,342 @@
+//! Taint analysis from the calldata of the transaction to state writes and calls.
+//!
+//! The calldata of the transaction is controlled by the sender, so every value derived from it
+//! is tainted. Taint is propagated through the stack and memory of each call frame, into the
+//! calldata of sub-calls and back through their return data, as well as through storage. Every
+//! state write (`SSTORE`) and call whose inputs are tainted is reported as a sink.
+
+use alloy_primitives::{Address, U256};
+use revm::interpreter::opcode;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    analysis::shadow::{stack_usize, Shadow, Tag},
+    artifact::debug::{DebugArtifact, DebugStep},
+};
+
+impl Tag for bool {
+    fn join(&self, other: &Self) -> Self {
+        *self || *other
+    }
+
+    fn compute(inputs: &[Self]) -> Self {
+        inputs.iter().any(|tag| *tag)
+    }
+}
+
+/// A state write or call with tainted inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaintedSink {
+    /// The tainted stack inputs, bit `i` being the `i`-th item from the top.
+    pub inputs: u64,
+    /// Whether the memory input (e.g., the arguments of a call) is tainted.
+    pub memory: bool,
+}
+
+/// The result of the taint analysis.
+#[derive(Clone, Debug, Default)]
+pub struct TaintAnalysis {
+    /// For each node and step, the tainted stack items (up to 64 from the top), bit `i` being the
+    /// `i`-th item from the top.
+    pub stack: Vec<Vec<u64>>,
+    /// Sinks with tainted inputs, keyed by node and step.
+    pub sinks: FxHashMap<(usize, usize), TaintedSink>,
+}
+
+impl TaintAnalysis {
+    /// Returns whether the `n`-th stack item from the top is tainted prior to the given step.
+    pub fn is_tainted(&self, node: usize, step: usize, n: usize) -> bool {
+        n < 64 && self.stack[node][step] & (1 << n) != 0
+    }
+
+    /// Returns the sink at the given step, if its inputs are tainted.
+    pub fn sink(&self, node: usize, step: usize) -> Option<&TaintedSink> {
+        self.sinks.get(&(node, step))
+    }
+}
+
+/// A sub-call in progress.
+#[derive(Debug)]
+struct PendingCall {
+    is_create: bool,
+    /// Taint of the arguments.
+    args: Vec<bool>,
+    /// The output area, i.e., `(offset, size)`, in the memory of the caller.
+    output: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Default)]
+struct Frame {
+    shadow: Shadow<bool>,
+    calldata: Vec<bool>,
+    returndata: Vec<bool>,
+    /// Taint of the return data of this frame, once it returns.
+    output: Vec<bool>,
+    pending: Option<PendingCall>,
+}
+
+impl Frame {
+    fn memory_taint(&self, offset: usize, size: usize) -> Vec<bool> {
+        (offset..offset + size)
+            .map(|i| self.shadow.memory.get(&i).copied().unwrap_or_default())
+            .collect()
+    }
+
+    /// Resolves the pending sub-call with the given return data taint.
+    fn resolve(&mut self, output: Vec<bool>) {
+        let Some(pending) = self.pending.take() else { return };
+        if let Some((offset, size)) = pending.output {
+            for i in 0..size {
+                let tag = output.get(i).copied().unwrap_or_default();
+                self.shadow.set_memory(offset + i, 1, tag);
+            }
+        }
+        self.returndata = output;
+    }
+
+    /// Resolves the pending sub-call which did not execute any code (e.g., a call to a
+    /// precompile), whose return data is assumed to be derived from its arguments.
+    fn resolve_without_code(&mut self) {
+        let Some(pending) = &self.pending else { return };
+        let tainted = pending.args.iter().any(|tag| *tag);
+        let size = pending.output.map_or(0, |(_, size)| size);
+        self.resolve(vec![tainted; size]);
+    }
+}
+
+/// Runs the taint analysis on the whole execution.
+pub fn taint_analysis(artifact: &DebugArtifact) -> TaintAnalysis {
+    let mut analysis = TaintAnalysis::default();
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut storage: FxHashSet<(Address, U256)> = FxHashSet::default();
+
+    for (i, node) in artifact.debug_arena.iter().enumerate() {
+        // Return from sub-calls
+        while frames.len() > node.depth + 1 {
+            let child = frames.pop().expect("non-empty frames");
+            if let Some(parent) = frames.last_mut() {
+                parent.resolve(child.output);
+            }
+        }
+        if frames.len() == node.depth + 1 {
+            // Continue the frame, in which a sub-call may have returned without executing code
+            let frame = frames.last_mut().expect("non-empty frames");
+            frame.resolve_without_code();
+        }
+        // Enter sub-calls
+        while frames.len() < node.depth + 1 {
+            let calldata = match frames.last() {
+                Some(parent) => match &parent.pending {
+                    Some(pending) if !pending.is_create => pending.args.clone(),
+                    _ => Vec::new(),
+                },
+                // The calldata of the transaction is controlled by the sender
+                None => vec![true; node.steps.first().map_or(0, |step| step.calldata.len())],
+            };
+            frames.push(Frame { calldata, ..Default::default() });
+        }
+
+        let frame = frames.last_mut().expect("non-empty frames");
+        let mut stack = Vec::with_capacity(node.steps.len());
+        for (j, step) in node.steps.iter().enumerate() {
+            frame.shadow.align(step.stack.len());
+            let inputs = frame
+                .shadow
+                .stack
+                .iter()
+                .rev()
+                .take(64)
+                .enumerate()
+                .fold(0u64, |acc, (n, tag)| if *tag { acc | (1 << n) } else { acc });
+            stack.push(inputs);
+
+            if let Some(sink) = check_sink(frame, step, inputs) {
+                analysis.sinks.insert((i, j), sink);
+            }
+            execute(frame, step, node.address, &mut storage);
+        }
+        analysis.stack.push(stack);
+    }
+
+    analysis
+}
+
+/// Checks whether the step is a sink with tainted inputs.
+fn check_sink(frame: &Frame, step: &DebugStep, inputs: u64) -> Option<TaintedSink> {
+    let (n_inputs, memory) = match step.instruction {
+        opcode::SSTORE => (2, None),
+        opcode::CALL | opcode::CALLCODE => (7, Some((3, 4))),
+        opcode::DELEGATECALL | opcode::STATICCALL => (6, Some((2, 3))),
+        opcode::CREATE => (3, Some((1, 2))),
+        opcode::CREATE2 => (4, Some((1, 2))),
+        opcode::SELFDESTRUCT => (1, None),
+        _ => return None,
+    };
+
+    let inputs = inputs & ((1 << n_inputs) - 1);
+    let memory = memory
+        .and_then(|(offset, size)| Some((stack_usize(step, offset)?, stack_usize(step, size)?)))
+        .map_or(false, |(offset, size)| frame.memory_taint(offset, size).contains(&true));
+    (inputs != 0 || memory).then_some(TaintedSink { inputs, memory })
+}
+
+/// Executes the step on the shadow stack and memory of the frame, with the sources of taint.
+fn execute(
+    frame: &mut Frame,
+    step: &DebugStep,
+    address: Address,
+    storage: &mut FxHashSet<(Address, U256)>,
+) {
+    let top = |n: usize| step.stack.len().checked_sub(n + 1).map(|i| step.stack[i]);
+
+    // Capture what the generic execution would lose
+    match step.instruction {
+        opcode::CALL |
+        opcode::CALLCODE |
+        opcode::DELEGATECALL |
+        opcode::STATICCALL |
+        opcode::CREATE |
+        opcode::CREATE2 => {
+            let (args, output) = match step.instruction {
+                opcode::CALL | opcode::CALLCODE => ((3, 4), Some((5, 6))),
+                opcode::DELEGATECALL | opcode::STATICCALL => ((2, 3), Some((4, 5))),
+                _ => ((1, 2), None),
+            };
+            let args = match (stack_usize(step, args.0), stack_usize(step, args.1)) {
+                (Some(offset), Some(size)) => frame.memory_taint(offset, size),
+                _ => Vec::new(),
+            };
+            let output = output.and_then(|(offset, size)| {
+                Some((stack_usize(step, offset)?, stack_usize(step, size)?))
+            });
+            let is_create = matches!(step.instruction, opcode::CREATE | opcode::CREATE2);
+            frame.pending = Some(PendingCall { is_create, args, output });
+        }
+        opcode::RETURN | opcode::REVERT => {
+            if let (Some(offset), Some(size)) = (stack_usize(step, 0), stack_usize(step, 1)) {
+                frame.output = frame.memory_taint(offset, size);
+            }
+        }
+        opcode::SSTORE => {
+            if let Some(key) = top(0) {
+                let len = frame.shadow.stack.len();
+                if frame.shadow.stack[len.saturating_sub(2)..].contains(&true) {
+                    storage.insert((address, key));
+                } else {
+                    storage.remove(&(address, key));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    frame.shadow.execute(step);
+
+    // Sources of taint
+    let copy = |frame: &mut Frame, source: &[bool]| {
+        if let (Some(dest), Some(offset), Some(size)) =
+            (stack_usize(step, 0), stack_usize(step, 1), stack_usize(step, 2))
+        {
+            for i in 0..size {
+                let tag = source.get(offset + i).copied().unwrap_or_default();
+                frame.shadow.set_memory(dest + i, 1, tag);
+            }
+        }
+    };
+    match step.instruction {
+        opcode::CALLDATALOAD => {
+            let tainted = stack_usize(step, 0).map_or(false, |offset| {
+                frame.calldata.iter().skip(offset).take(32).any(|tag| *tag)
+            });
+            if let Some(last) = frame.shadow.stack.last_mut() {
+                *last |= tainted;
+            }
+        }
+        opcode::CALLDATASIZE | opcode::RETURNDATASIZE => {
+            let source = if step.instruction == opcode::CALLDATASIZE {
+                &frame.calldata
+            } else {
+                &frame.returndata
+            };
+            let tainted = source.contains(&true);
+            if let Some(last) = frame.shadow.stack.last_mut() {
+                *last = tainted;
+            }
+        }
+        opcode::CALLDATACOPY => {
+            let calldata = std::mem::take(&mut frame.calldata);
+            copy(frame, &calldata);
+            frame.calldata = calldata;
+        }
+        opcode::RETURNDATACOPY => {
+            let returndata = std::mem::take(&mut frame.returndata);
+            copy(frame, &returndata);
+            frame.returndata = returndata;
+        }
+        opcode::SLOAD => {
+            if let (Some(key), Some(last)) = (top(0), frame.shadow.stack.last_mut()) {
+                *last |= storage.contains(&(address, key));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::DebugNodeFlat;
+
+    fn step(instruction: u8, stack: &[u64]) -> DebugStep {
+        DebugStep {
+            instruction,
+            stack: stack.iter().map(|item| U256::from(*item)).collect(),
+            calldata: Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_taint_through_storage() {
+        let steps = vec![
+            // SSTORE(1, CALLDATALOAD(0))
+            step(opcode::PUSH1, &[]),
+            step(opcode::CALLDATALOAD, &[0]),
+            step(opcode::PUSH1, &[7]),
+            step(opcode::SSTORE, &[7, 1]),
+            // SSTORE(2, SLOAD(1))
+            step(opcode::PUSH1, &[]),
+            step(opcode::SLOAD, &[1]),
+            step(opcode::PUSH1, &[7]),
+            step(opcode::SSTORE, &[7, 2]),
+            // SSTORE(3, 5)
+            step(opcode::PUSH1, &[]),
+            step(opcode::PUSH1, &[5]),
+            step(opcode::SSTORE, &[5, 3]),
+        ];
+        let artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(
+                Address::with_last_byte(1),
+                CallKind::Call,
+                0,
+                steps,
+            )],
+            ..Default::default()
+        };
+
+        let analysis = taint_analysis(&artifact);
+        // the stored value is tainted, but not the slot
+        assert!(analysis.is_tainted(0, 3, 1));
+        assert!(!analysis.is_tainted(0, 3, 0));
+        let sink = TaintedSink { inputs: 0b10, memory: false };
+        assert_eq!(analysis.sink(0, 3), Some(&sink));
+        // the taint is carried through storage
+        assert!(analysis.is_tainted(0, 6, 0));
+        assert_eq!(analysis.sink(0, 7), Some(&sink));
+        assert_eq!(analysis.sink(0, 10), None);
+        assert_eq!(analysis.sinks.len(), 2);
+    }
+}
@@ -0,0 +1,121 @@
+//! Call depth and gas over the steps of the whole execution, e.g., to spot deep recursions and
+//! gas-hungry loops at a glance.
+
+use crate::artifact::debug::DebugArtifact;
+
+/// The call depth and the gas used so far at each step of the execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Timeline {
+    pub depths: Vec<usize>,
+    /// The gas used by the transaction so far, including the gas used by the parent calls up to
+    /// the current call.
+    pub gas_used: Vec<u64>,
+}
+
+/// A column of the timeline, which summarizes consecutive steps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimelineBucket {
+    /// The first step of the bucket.
+    pub start: usize,
+    /// The deepest call of the bucket.
+    pub depth: usize,
+    /// The gas left to be used by the transaction after the bucket.
+    pub gas_left: u64,
+}
+
+impl Timeline {
+    pub fn new(artifact: &DebugArtifact) -> Self {
+        let len = artifact.debug_arena.iter().map(|node| node.steps.len()).sum();
+        let mut depths = Vec::with_capacity(len);
+        let mut gas_used = Vec::with_capacity(len);
+
+        // The gas used by each frame is cumulative within the frame, so the gas used by the
+        // transaction is the one of the frame plus the one used before the frame was entered
+        let mut bases: Vec<u64> = Vec::new();
+        for node in &artifact.debug_arena {
+            if node.depth >= bases.len() {
+                let base = gas_used.last().copied().unwrap_or(0);
+                bases.resize(node.depth + 1, base);
+            } else {
+                bases.truncate(node.depth + 1);
+            }
+            let base = bases[node.depth];
+            for step in &node.steps {
+                depths.push(node.depth);
+                gas_used.push(base + step.total_gas_used);
+            }
+        }
+
+        Self { depths, gas_used }
+    }
+
+    /// Returns the number of steps.
+    pub fn len(&self) -> usize {
+        self.depths.len()
+    }
+
+    /// Returns whether the execution has no step.
+    pub fn is_empty(&self) -> bool {
+        self.depths.is_empty()
+    }
+
+    /// Returns the bucket of the given step when the timeline is split into `width` buckets.
+    pub fn bucket_of(&self, step: usize, width: usize) -> usize {
+        if self.is_empty() || width == 0 {
+            return 0;
+        }
+        (step * width / self.len()).min(width - 1)
+    }
+
+    /// Splits the timeline into at most `width` buckets of consecutive steps.
+    pub fn buckets(&self, width: usize) -> Vec<TimelineBucket> {
+        if self.is_empty() || width == 0 {
+            return Vec::new();
+        }
+        let total = self.gas_used.iter().copied().max().unwrap_or(0);
+        let width = width.min(self.len());
+        (0..width)
+            .map(|bucket| {
+                let start = bucket * self.len() / width;
+                let end = (bucket + 1) * self.len() / width;
+                TimelineBucket {
+                    start,
+                    depth: self.depths[start..end].iter().copied().max().unwrap_or(0),
+                    gas_left: total.saturating_sub(self.gas_used[end - 1]),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    #[test]
+    fn test_timeline() {
+        let node = |depth: usize, gas: &[u64]| {
+            let steps =
+                gas.iter().map(|gas| DebugStep { total_gas_used: *gas, ..Default::default() });
+            DebugNodeFlat::new(Address::ZERO, CallKind::Call, depth, steps.collect())
+        };
+        let artifact = DebugArtifact {
+            debug_arena: vec![node(0, &[3, 10]), node(1, &[2, 5]), node(0, &[16, 20])],
+            ..Default::default()
+        };
+
+        let timeline = Timeline::new(&artifact);
+        assert_eq!(timeline.depths, vec![0, 0, 1, 1, 0, 0]);
+        assert_eq!(timeline.gas_used, vec![3, 10, 12, 15, 16, 20]);
+
+        let buckets = timeline.buckets(3);
+        assert_eq!(buckets.iter().map(|b| b.start).collect::<Vec<_>>(), vec![0, 2, 4]);
+        assert_eq!(buckets.iter().map(|b| b.depth).collect::<Vec<_>>(), vec![0, 1, 0]);
+        assert_eq!(buckets.iter().map(|b| b.gas_left).collect::<Vec<_>>(), vec![10, 5, 0]);
+        assert_eq!(timeline.bucket_of(3, 3), 1);
+    }
+}
@@ -0,0 +1,142 @@
+//! Metadata (i.e., symbol and decimals) of the ERC-20 tokens touched by the transaction, so that
+//! token amounts can be rendered in a human-readable way.
+//!
+//! Metadata is fetched from the chain once and cached on disk, so that later sessions work
+//! offline. A user-maintained file can override the fetched metadata, e.g., for tokens with a
+//! non-standard `symbol()`.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::{sol, SolCall};
+use eyre::Result;
+use revm::{primitives::EnvWithHandlerCfg, DatabaseRef};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::evm::static_call;
+
+sol! {
+    function symbol() external view returns (string);
+    function decimals() external view returns (uint8);
+}
+
+/// Metadata of an ERC-20 token.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    /// The symbol of the token.
+    pub symbol: String,
+    /// The number of decimals of the token.
+    pub decimals: u8,
+}
+
+impl TokenMetadata {
+    /// Formats the given amount of the token, e.g., `1,250.5 USDC`.
+    pub fn format_amount(&self, amount: U256) -> String {
+        // Units of 78 decimals and more overflow, and every amount is below them
+        let (integer, fraction) = match U256::from(10).checked_pow(U256::from(self.decimals)) {
+            Some(unit) => amount.div_rem(unit),
+            None => (U256::ZERO, amount),
+        };
+
+        let integer = integer.to_string();
+        let mut grouped = String::with_capacity(integer.len() * 4 / 3);
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+
+        if fraction.is_zero() {
+            format!("{grouped} {}", self.symbol)
+        } else {
+            let fraction = format!("{fraction:0>width$}", width = self.decimals as usize);
+            format!("{grouped}.{} {}", fraction.trim_end_matches('0'), self.symbol)
+        }
+    }
+}
+
+/// Fetches the metadata of the token at the given address, if it has any.
+pub(crate) fn fetch_token_metadata<DBRef: DatabaseRef + Copy>(
+    db: DBRef,
+    env: &EnvWithHandlerCfg,
+    address: Address,
+) -> Option<TokenMetadata> {
+    let output = static_call(db, env, address, symbolCall {}.abi_encode().into())?;
+    let symbol = match symbolCall::abi_decode_returns(&output, false) {
+        Ok(ret) => ret._0,
+        // Some old tokens (e.g., MKR) return `bytes32` instead of `string`
+        Err(_) if output.len() == 32 => {
+            let len = output.iter().position(|b| *b == 0).unwrap_or(32);
+            String::from_utf8(output[..len].to_vec()).ok()?
+        }
+        Err(_) => return None,
+    };
+
+    let output = static_call(db, env, address, decimalsCall {}.abi_encode().into())?;
+    let decimals = decimalsCall::abi_decode_returns(&output, false).ok()?._0;
+
+    Some(TokenMetadata { symbol, decimals })
+}
+
+/// Token metadata stored in a JSON file, keyed by the address of the token.
+#[derive(Debug, Default)]
+pub(crate) struct TokenFile {
+    path: Option<PathBuf>,
+    pub(crate) tokens: BTreeMap<Address, TokenMetadata>,
+}
+
+impl TokenFile {
+    /// Loads the token file at the given path. A missing or malformed file is treated as empty.
+    pub(crate) fn load(path: Option<PathBuf>) -> Self {
+        let tokens = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(tokens) => Some(tokens),
+                Err(e) => {
+                    warn!("ignoring malformed token file {path:?}: {e}");
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self { path, tokens }
+    }
+
+    /// Saves the token file.
+    pub(crate) fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.tokens)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_amount() {
+        let usdc = TokenMetadata { symbol: "USDC".to_string(), decimals: 6 };
+        assert_eq!(usdc.format_amount(U256::from(1_250_500_000u64)), "1,250.5 USDC");
+        assert_eq!(usdc.format_amount(U256::from(1_000_000u64)), "1 USDC");
+        assert_eq!(usdc.format_amount(U256::from(1u64)), "0.000001 USDC");
+        assert_eq!(usdc.format_amount(U256::from(123_456_789_000_000u64)), "123,456,789 USDC");
+    }
+
+    #[test]
+    fn test_format_amount_many_decimals() {
+        let token = TokenMetadata { symbol: "XYZ".to_string(), decimals: 80 };
+        let expected = format!("0.{}1 XYZ", "0".repeat(79));
+        assert_eq!(token.format_amount(U256::from(1u64)), expected);
+        assert_eq!(token.format_amount(U256::ZERO), "0 XYZ");
+
+        let token = TokenMetadata { symbol: "XYZ".to_string(), decimals: 77 };
+        let expected = format!("1.{}1 XYZ", "0".repeat(76));
+        let amount = U256::from(10).pow(U256::from(77)) + U256::from(1u64);
+        assert_eq!(token.format_amount(amount), expected);
+    }
+}
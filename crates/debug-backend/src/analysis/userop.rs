@@ -0,0 +1,423 @@
+//! Decode the bundles of ERC-4337 user operations, i.e., the `handleOps` calls to an entry point,
+//! along with the phases each user operation goes through: its validation by the account and the
+//! paymaster, then its execution and the post-operation of the paymaster.
+
+use std::{fmt, ops::Range};
+
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::SolCall;
+
+use crate::{
+    analysis::funds::{FundsFlow, Transfer},
+    artifact::debug::DebugArtifact,
+    export::calltree::{call_frames, CallFrame},
+};
+
+mod v06 {
+    alloy_sol_types::sol! {
+        struct UserOperation {
+            address sender;
+            uint256 nonce;
+            bytes initCode;
+            bytes callData;
+            uint256 callGasLimit;
+            uint256 verificationGasLimit;
+            uint256 preVerificationGas;
+            uint256 maxFeePerGas;
+            uint256 maxPriorityFeePerGas;
+            bytes paymasterAndData;
+            bytes signature;
+        }
+
+        function handleOps(UserOperation[] ops, address beneficiary);
+        function validateUserOp(UserOperation userOp, bytes32 userOpHash, uint256 missingAccountFunds);
+        function validatePaymasterUserOp(UserOperation userOp, bytes32 userOpHash, uint256 maxCost);
+        function postOp(uint8 mode, bytes context, uint256 actualGasCost);
+    }
+}
+
+mod v07 {
+    alloy_sol_types::sol! {
+        struct PackedUserOperation {
+            address sender;
+            uint256 nonce;
+            bytes initCode;
+            bytes callData;
+            bytes32 accountGasLimits;
+            uint256 preVerificationGas;
+            bytes32 gasFees;
+            bytes paymasterAndData;
+            bytes signature;
+        }
+
+        function handleOps(PackedUserOperation[] ops, address beneficiary);
+        function validateUserOp(PackedUserOperation userOp, bytes32 userOpHash, uint256 missingAccountFunds);
+        function validatePaymasterUserOp(PackedUserOperation userOp, bytes32 userOpHash, uint256 maxCost);
+        function postOp(uint8 mode, bytes context, uint256 actualGasCost, uint256 actualUserOpFeePerGas);
+        function executeUserOp(PackedUserOperation userOp, bytes32 userOpHash);
+    }
+}
+
+/// The version of the entry point handling a bundle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryPointVersion {
+    V06,
+    V07,
+}
+
+impl fmt::Display for EntryPointVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V06 => write!(f, "v0.6"),
+            Self::V07 => write!(f, "v0.7"),
+        }
+    }
+}
+
+/// A phase of the handling of a user operation by the entry point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UserOpPhase {
+    Validation,
+    PaymasterValidation,
+    Execution,
+    PostOp,
+}
+
+impl fmt::Display for UserOpPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Validation => write!(f, "validation"),
+            Self::PaymasterValidation => write!(f, "paymaster validation"),
+            Self::Execution => write!(f, "execution"),
+            Self::PostOp => write!(f, "post-operation"),
+        }
+    }
+}
+
+/// A decoded user operation, whose packed fields (in v0.7) are unpacked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserOp {
+    pub sender: Address,
+    pub nonce: U256,
+    /// Whether the account is deployed by the operation.
+    pub deploys_account: bool,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster: Option<Address>,
+}
+
+impl From<v06::UserOperation> for UserOp {
+    fn from(op: v06::UserOperation) -> Self {
+        Self {
+            sender: op.sender,
+            nonce: op.nonce,
+            deploys_account: !op.initCode.is_empty(),
+            call_data: op.callData,
+            call_gas_limit: op.callGasLimit,
+            verification_gas_limit: op.verificationGasLimit,
+            pre_verification_gas: op.preVerificationGas,
+            max_fee_per_gas: op.maxFeePerGas,
+            max_priority_fee_per_gas: op.maxPriorityFeePerGas,
+            paymaster: paymaster(&op.paymasterAndData),
+        }
+    }
+}
+
+impl From<v07::PackedUserOperation> for UserOp {
+    fn from(op: v07::PackedUserOperation) -> Self {
+        // Both halves of the packed words are 128-bit integers, the high one coming first
+        let high = |word: &[u8; 32]| U256::from_be_slice(&word[..16]);
+        let low = |word: &[u8; 32]| U256::from_be_slice(&word[16..]);
+        Self {
+            sender: op.sender,
+            nonce: op.nonce,
+            deploys_account: !op.initCode.is_empty(),
+            call_data: op.callData,
+            call_gas_limit: low(&op.accountGasLimits.0),
+            verification_gas_limit: high(&op.accountGasLimits.0),
+            pre_verification_gas: op.preVerificationGas,
+            max_fee_per_gas: low(&op.gasFees.0),
+            max_priority_fee_per_gas: high(&op.gasFees.0),
+            paymaster: paymaster(&op.paymasterAndData),
+        }
+    }
+}
+
+/// Returns the paymaster, which prefixes the paymaster data, if there is one.
+fn paymaster(paymaster_and_data: &[u8]) -> Option<Address> {
+    paymaster_and_data.get(..20).map(Address::from_slice)
+}
+
+/// A user operation, along with how it was handled in the transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserOpExecution {
+    pub op: UserOp,
+    /// The steps of each phase the operation went through, in the whole execution.
+    pub phases: Vec<(UserOpPhase, Range<usize>)>,
+    /// The transfers made during the phases of the operation.
+    pub transfers: Vec<Transfer>,
+}
+
+impl UserOpExecution {
+    /// Returns the phase the given step belongs to, if any.
+    pub fn phase_of(&self, step: usize) -> Option<UserOpPhase> {
+        self.phases.iter().find(|(_, steps)| steps.contains(&step)).map(|(phase, _)| *phase)
+    }
+}
+
+/// A `handleOps` call to an entry point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserOpBundle {
+    pub entry_point: Address,
+    pub version: EntryPointVersion,
+    /// The recipient of the gas fees paid by the operations, i.e., the bundler.
+    pub beneficiary: Address,
+    /// The steps of the `handleOps` call in the whole execution.
+    pub steps: Range<usize>,
+    pub ops: Vec<UserOpExecution>,
+}
+
+/// Decodes the bundles of user operations handled in the transaction, in the order of
+/// execution.
+pub fn user_op_bundles(artifact: &DebugArtifact) -> Vec<UserOpBundle> {
+    let mut bundles = decode_bundles(&call_frames(artifact));
+    if bundles.is_empty() {
+        return bundles;
+    }
+
+    let flow = FundsFlow::new(artifact);
+    for op in bundles.iter_mut().flat_map(|bundle| &mut bundle.ops) {
+        op.transfers = flow
+            .transfers
+            .iter()
+            .filter(|transfer| op.phase_of(transfer.step).is_some())
+            .cloned()
+            .collect();
+    }
+    bundles
+}
+
+/// Decodes the bundles of user operations among the call frames, along with the phases of each
+/// operation, but not their transfers.
+fn decode_bundles(frames: &[CallFrame]) -> Vec<UserOpBundle> {
+    let mut bundles = Vec::new();
+    for frame in frames {
+        let (version, ops, beneficiary) =
+            if let Ok(call) = v06::handleOpsCall::abi_decode(&frame.input, false) {
+                let ops = call.ops.into_iter().map(UserOp::from).collect::<Vec<_>>();
+                (EntryPointVersion::V06, ops, call.beneficiary)
+            } else if let Ok(call) = v07::handleOpsCall::abi_decode(&frame.input, false) {
+                let ops = call.ops.into_iter().map(UserOp::from).collect::<Vec<_>>();
+                (EntryPointVersion::V07, ops, call.beneficiary)
+            } else {
+                continue;
+            };
+
+        let mut ops: Vec<_> = ops
+            .into_iter()
+            .map(|op| UserOpExecution { op, phases: Vec::new(), transfers: Vec::new() })
+            .collect();
+
+        // The entry point validates all the operations first, then executes them one by one,
+        // so that each phase is attributed to the first operation of the account (or paymaster)
+        // which has not gone through it yet
+        let inner = frames.iter().filter(|inner| {
+            inner.steps.start > frame.steps.start && inner.steps.end <= frame.steps.end
+        });
+        for inner in inner {
+            let selector = inner.input.get(..4).unwrap_or_default();
+            let is = |selectors: [[u8; 4]; 2]| selectors.iter().any(|s| s[..] == *selector);
+            let (phase, by_paymaster) =
+                if is([v06::validateUserOpCall::SELECTOR, v07::validateUserOpCall::SELECTOR]) {
+                    (UserOpPhase::Validation, false)
+                } else if is([
+                    v06::validatePaymasterUserOpCall::SELECTOR,
+                    v07::validatePaymasterUserOpCall::SELECTOR,
+                ]) {
+                    (UserOpPhase::PaymasterValidation, true)
+                } else if is([v06::postOpCall::SELECTOR, v07::postOpCall::SELECTOR]) {
+                    (UserOpPhase::PostOp, true)
+                } else if !inner.input.is_empty() {
+                    (UserOpPhase::Execution, false)
+                } else {
+                    continue;
+                };
+
+            let op = ops.iter_mut().find(|execution| {
+                let account =
+                    if by_paymaster { execution.op.paymaster } else { Some(execution.op.sender) };
+                let matches_call = phase != UserOpPhase::Execution ||
+                    inner.input == execution.op.call_data ||
+                    selector == v07::executeUserOpCall::SELECTOR;
+                account == Some(inner.address) &&
+                    matches_call &&
+                    !execution.phases.iter().any(|(done, _)| *done == phase)
+            });
+            if let Some(op) = op {
+                op.phases.push((phase, inner.steps.clone()));
+            }
+        }
+
+        for op in &mut ops {
+            op.phases.sort_by_key(|(_, steps)| steps.start);
+        }
+
+        bundles.push(UserOpBundle {
+            entry_point: frame.address,
+            version,
+            beneficiary,
+            steps: frame.steps.clone(),
+            ops,
+        });
+    }
+    bundles
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::B256;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::export::calltree::FrameOutcome;
+
+    fn frame(address: Address, input: Vec<u8>, steps: Range<usize>) -> CallFrame {
+        CallFrame {
+            kind: CallKind::Call,
+            caller: None,
+            address,
+            input: input.into(),
+            output: Bytes::new(),
+            value: U256::ZERO,
+            gas_limit: 0,
+            gas_used: 0,
+            outcome: FrameOutcome::Return,
+            children: Vec::new(),
+            node: 0,
+            steps,
+        }
+    }
+
+    fn user_op(
+        sender: Address,
+        call_data: &[u8],
+        paymaster: Option<Address>,
+    ) -> v06::UserOperation {
+        v06::UserOperation {
+            sender,
+            nonce: U256::ZERO,
+            initCode: Bytes::new(),
+            callData: Bytes::copy_from_slice(call_data),
+            callGasLimit: U256::ZERO,
+            verificationGasLimit: U256::ZERO,
+            preVerificationGas: U256::ZERO,
+            maxFeePerGas: U256::ZERO,
+            maxPriorityFeePerGas: U256::ZERO,
+            paymasterAndData: paymaster
+                .map(|p| Bytes::copy_from_slice(p.as_slice()))
+                .unwrap_or_default(),
+            signature: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn test_decode_bundle_phases() {
+        let entry_point = Address::with_last_byte(0xe0);
+        let beneficiary = Address::with_last_byte(0xbe);
+        let (alice, bob) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let paymaster = Address::with_last_byte(9);
+        let (alice_call, bob_call) = ([0xaa, 0, 0, 1], [0xbb, 0, 0, 2]);
+
+        let ops = vec![user_op(alice, &alice_call, Some(paymaster)), user_op(bob, &bob_call, None)];
+        let validate = |op: &v06::UserOperation| {
+            v06::validateUserOpCall {
+                userOp: op.clone(),
+                userOpHash: B256::ZERO,
+                missingAccountFunds: U256::ZERO,
+            }
+            .abi_encode()
+        };
+        let validate_paymaster = v06::validatePaymasterUserOpCall {
+            userOp: ops[0].clone(),
+            userOpHash: B256::ZERO,
+            maxCost: U256::ZERO,
+        }
+        .abi_encode();
+        let post_op = v06::postOpCall { mode: 0, context: Bytes::new(), actualGasCost: U256::ZERO }
+            .abi_encode();
+        let handle_ops = v06::handleOpsCall { ops: ops.clone(), beneficiary }.abi_encode();
+
+        // all the operations are validated first, then executed one by one
+        let frames = vec![
+            frame(entry_point, handle_ops, 0..100),
+            frame(alice, validate(&ops[0]), 10..20),
+            frame(paymaster, validate_paymaster, 20..30),
+            frame(bob, validate(&ops[1]), 30..40),
+            frame(alice, alice_call.to_vec(), 50..60),
+            frame(paymaster, post_op, 60..70),
+            frame(bob, bob_call.to_vec(), 70..80),
+        ];
+        let bundles = decode_bundles(&frames);
+        assert_eq!(bundles.len(), 1);
+
+        let bundle = &bundles[0];
+        assert_eq!(bundle.entry_point, entry_point);
+        assert_eq!(bundle.version, EntryPointVersion::V06);
+        assert_eq!(bundle.beneficiary, beneficiary);
+        assert_eq!(bundle.steps, 0..100);
+        assert_eq!(
+            bundle.ops[0].phases,
+            vec![
+                (UserOpPhase::Validation, 10..20),
+                (UserOpPhase::PaymasterValidation, 20..30),
+                (UserOpPhase::Execution, 50..60),
+                (UserOpPhase::PostOp, 60..70),
+            ]
+        );
+        assert_eq!(
+            bundle.ops[1].phases,
+            vec![(UserOpPhase::Validation, 30..40), (UserOpPhase::Execution, 70..80)]
+        );
+        assert_eq!(bundle.ops[0].phase_of(55), Some(UserOpPhase::Execution));
+        assert_eq!(bundle.ops[0].phase_of(45), None);
+    }
+
+    #[test]
+    fn test_decode_no_bundle() {
+        let frames = vec![frame(Address::with_last_byte(1), vec![0xaa, 0, 0, 1], 0..10)];
+        assert!(decode_bundles(&frames).is_empty());
+    }
+
+    #[test]
+    fn test_unpack_user_op() {
+        let mut account_gas_limits = [0u8; 32];
+        account_gas_limits[15] = 1; // verification gas limit
+        account_gas_limits[31] = 2; // call gas limit
+        let mut gas_fees = [0u8; 32];
+        gas_fees[15] = 3; // max priority fee per gas
+        gas_fees[31] = 4; // max fee per gas
+        let paymaster = Address::with_last_byte(9);
+
+        let op = UserOp::from(v07::PackedUserOperation {
+            sender: Address::with_last_byte(1),
+            nonce: U256::from(7),
+            initCode: Bytes::new(),
+            callData: Bytes::from_static(&[0xb6, 0x1d, 0x27, 0xf6]),
+            accountGasLimits: B256::from(account_gas_limits),
+            preVerificationGas: U256::from(5),
+            gasFees: B256::from(gas_fees),
+            paymasterAndData: [paymaster.as_slice(), &[0u8; 32]].concat().into(),
+            signature: Bytes::new(),
+        });
+        assert_eq!(op.verification_gas_limit, U256::from(1));
+        assert_eq!(op.call_gas_limit, U256::from(2));
+        assert_eq!(op.max_priority_fee_per_gas, U256::from(3));
+        assert_eq!(op.max_fee_per_gas, U256::from(4));
+        assert_eq!(op.paymaster, Some(paymaster));
+        assert!(!op.deploys_account);
+    }
+}
@@ -1,13 +1,20 @@
 use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
 
 use alloy_json_abi::JsonAbi;
+use alloy_primitives::{keccak256, B256};
 use eyre::{eyre, Result};
-use foundry_compilers::artifacts::{CompilerOutput, DeployedBytecode, Evm, SourceUnit, Sources};
+use foundry_compilers::artifacts::{
+    sourcemap::SourceElement, Bytecode, CompilerOutput, Evm, SourceUnit, Sources,
+};
 use revm::primitives::Bytecode as RevmBytecode;
+use rustc_hash::FxHashMap;
 
 use crate::{
     analysis::prune::ASTPruner,
-    utils::compilation::{bytecode_similarity, link_contracts_fakely},
+    utils::{
+        compilation::{bytecode_bytes, bytecode_similarity, link_libraries},
+        opcode::PcIcMap,
+    },
 };
 
 const SIMILARITY_THRESHOLD: f64 = 0.7;
@@ -17,18 +24,202 @@ pub struct SourceFile {
     pub path: PathBuf,
     pub code: Arc<String>,
     pub ast: SourceUnit,
+    // Byte offsets of the beginning of each line
+    line_starts: Arc<Vec<usize>>,
+}
+
+impl SourceFile {
+    pub fn new(path: PathBuf, code: Arc<String>, ast: SourceUnit) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+            .collect::<Vec<_>>();
+        Self { path, code, ast, line_starts: Arc::new(line_starts) }
+    }
+
+    /// Returns the (1-based) line number of the given byte offset.
+    pub fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|start| *start <= offset)
+    }
+
+    /// Returns the number of lines of the source file.
+    pub fn num_lines(&self) -> usize {
+        self.line_starts.len()
+    }
 }
 
+/// Source elements of each instruction in a bytecode, indexed by program counter.
+pub type PcSourceMap = FxHashMap<usize, SourceElement>;
+
 #[derive(Clone, Debug)]
 pub struct CompilationArtifact {
     // The following fields exclusively belongs to a specific contract
     pub contract_name: String,
+    /// The hash of the on-chain runtime bytecode, which identifies the contract across
+    /// transactions and addresses.
+    pub code_hash: B256,
     pub file_id: u32, // the file id of the
     pub abi: JsonAbi,
     pub evm: Evm,
 
     // Other contract's source code may also get involved in the compilation process
     pub sources: BTreeMap<u32, SourceFile>,
+
+    // Source elements of the deployed and creation bytecode, indexed by program counter
+    pub runtime_source_map: Arc<PcSourceMap>,
+    pub creation_source_map: Arc<PcSourceMap>,
+
+    // Sources generated by the compiler for the deployed and creation bytecode, indexed by file id
+    pub runtime_generated_sources: BTreeMap<u32, GeneratedSource>,
+    pub creation_generated_sources: BTreeMap<u32, GeneratedSource>,
+}
+
+/// A source generated by the compiler, e.g., the Yul utility functions of the via-IR pipeline,
+/// which the source maps refer to along with the actual sources.
+#[derive(Clone, Debug)]
+pub struct GeneratedSource {
+    /// The name of the source, e.g., `#utility.yul`.
+    pub name: String,
+    pub code: Arc<String>,
+}
+
+impl CompilationArtifact {
+    /// Returns the source map of the runtime bytecode, or the creation bytecode if `is_create`.
+    pub fn source_map(&self, is_create: bool) -> &PcSourceMap {
+        if is_create {
+            &self.creation_source_map
+        } else {
+            &self.runtime_source_map
+        }
+    }
+
+    /// Returns the source element, as well as the source file, of the instruction at `pc`.
+    pub fn source_element(
+        &self,
+        pc: usize,
+        is_create: bool,
+    ) -> Option<(&SourceElement, &SourceFile)> {
+        let element = self.source_map(is_create).get(&pc)?;
+        let file = self.sources.get(&element.index()?)?;
+        Some((element, file))
+    }
+
+    /// Returns the source element, as well as the generated source, of the instruction at `pc`,
+    /// if it is mapped to code generated by the compiler rather than to the actual sources.
+    pub fn generated_element(
+        &self,
+        pc: usize,
+        is_create: bool,
+    ) -> Option<(&SourceElement, &GeneratedSource)> {
+        let element = self.source_map(is_create).get(&pc)?;
+        let generated = if is_create {
+            &self.creation_generated_sources
+        } else {
+            &self.runtime_generated_sources
+        };
+        Some((element, generated.get(&element.index()?)?))
+    }
+
+    /// Tells how the sources of `other`, e.g., fetched from the explorer, disagree with these
+    /// ones, e.g., of a local artifact, for the same contract, if they do.
+    pub fn disagreement(&self, other: &Self) -> Option<String> {
+        if self.contract_name != other.contract_name {
+            return Some(format!(
+                "the contract is named {} here but {} there",
+                self.contract_name, other.contract_name
+            ));
+        }
+        let files = |artifact: &Self| {
+            artifact.sources.values().map(|file| (file.path.clone(), file.code.clone())).collect()
+        };
+        let differing = differing_files(&files(self), &files(other));
+        (!differing.is_empty()).then(|| {
+            let names: Vec<_> = differing.iter().map(|path| path.display().to_string()).collect();
+            format!("the sources differ in {}", names.join(", "))
+        })
+    }
+}
+
+/// Returns the files of `ours` whose content differs from the file of `theirs` at the same path.
+/// Files are matched by their full path, so that files sharing a name in different directories,
+/// e.g., two `IERC20.sol`, are not mixed up, and line endings and trailing whitespace are ignored.
+fn differing_files(
+    ours: &[(PathBuf, Arc<String>)],
+    theirs: &[(PathBuf, Arc<String>)],
+) -> Vec<PathBuf> {
+    let normalize = |code: &str| code.lines().map(str::trim_end).collect::<Vec<_>>().join("\n");
+    ours.iter()
+        .filter(|(path, code)| {
+            theirs.iter().any(|(other_path, other_code)| {
+                path == other_path && normalize(code) != normalize(other_code)
+            })
+        })
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// Collects the sources generated by the compiler for the given bytecode, which come with
+/// via-IR compilations and the ABI coders of recent compilers.
+fn generated_sources(bytecode: Option<&Bytecode>) -> BTreeMap<u32, GeneratedSource> {
+    bytecode
+        .into_iter()
+        .flat_map(|bytecode| &bytecode.generated_sources)
+        .map(|source| {
+            let code = Arc::new(source.contents.clone());
+            (source.id, GeneratedSource { name: source.name.clone(), code })
+        })
+        .collect()
+}
+
+/// Returns the location in the actual sources which the generated Yul code at `offset` comes
+/// from, i.e., the one of the last `@src` annotation preceding it, as an index, an offset and a
+/// length.
+fn ir_source_location(code: &str, offset: usize) -> Option<(u32, u32, u32)> {
+    let annotation = code.get(..offset)?.rfind("@src ")?;
+    let location = code[annotation + "@src ".len()..].split_whitespace().next()?;
+    let mut parts = location.split(':').map(|part| part.parse::<i64>().ok());
+    let (index, start, end) = (parts.next()??, parts.next()??, parts.next()??);
+    // `-1:-1:-1` annotates code without any counterpart in the sources
+    if index < 0 || start < 0 || end < start {
+        return None;
+    }
+    Some((index as u32, start as u32, (end - start) as u32))
+}
+
+/// Maps the instructions attributed to the Yul code generated by the compiler back to the actual
+/// sources, through the `@src` annotations of the via-IR pipeline. Instructions without any
+/// annotation keep pointing to the generated code, which is shown instead.
+fn resolve_generated_locations(map: &mut PcSourceMap, generated: &BTreeMap<u32, GeneratedSource>) {
+    for element in map.values_mut() {
+        let Some(source) = element.index().and_then(|index| generated.get(&index)) else {
+            continue;
+        };
+        let Some((index, offset, length)) =
+            ir_source_location(&source.code, element.offset() as usize)
+        else {
+            continue;
+        };
+        if generated.contains_key(&index) {
+            continue;
+        }
+        element.set_index(Some(index));
+        element.set_offset(offset);
+        element.set_length(length);
+    }
+}
+
+/// Build the program-counter-indexed source map of the given bytecode.
+fn pc_source_map(bytecode: Option<&Bytecode>) -> Result<PcSourceMap> {
+    let Some(bytecode) = bytecode else { return Ok(PcSourceMap::default()) };
+    let Some(source_map) = bytecode.source_map() else { return Ok(PcSourceMap::default()) };
+    let source_map = source_map.map_err(|e| eyre!("failed to parse source map: {e}"))?;
+    let code = bytecode_bytes(bytecode).ok_or(eyre!("invalid bytecode object"))?;
+
+    let pc_ic_map = PcIcMap::new(&code);
+    Ok(pc_ic_map
+        .inner
+        .iter()
+        .filter_map(|(pc, ic)| Some((*pc, source_map.get(*ic)?.clone())))
+        .collect())
 }
 
 pub trait AsCompilationArtifact {
@@ -42,47 +233,38 @@ pub trait AsCompilationArtifact {
 /// The bytecode is the on-chain bytecode of the subject contract.
 /// The source map is the source code of all contracts involved in the compilation process.
 /// The compiler output is the output of the compiler.
-impl AsCompilationArtifact for (&str, RevmBytecode, &Sources, CompilerOutput) {
+impl AsCompilationArtifact for (&str, RevmBytecode, &Sources, &CompilerOutput) {
     fn as_artifact(self) -> Result<CompilationArtifact> {
-        let (contract_name, bytecode, input_sources, mut output) = self;
+        let (contract_name, bytecode, input_sources, output) = self;
         let bytecode = bytecode.original_byte_slice();
+        let code_hash = keccak256(bytecode);
 
-        // let first link the contracts, to have a more accurate similarity check
-        for (_, contracts) in output.contracts.iter_mut() {
-            for (_, contract) in contracts.iter_mut() {
-                if let Some(Evm { deployed_bytecode: Some(ref mut deployed_bytecode), .. }) =
-                    contract.evm
-                {
-                    link_contracts_fakely(deployed_bytecode, None)?;
-                }
-            }
-        }
-
-        // let first find the correct compiler artifact for the specific contract
+        // let first find the correct compiler artifact for the specific contract, whose libraries
+        // are linked to have a more accurate similarity check
         let mut selected = None;
         let mut max_similarity = 0.0;
         for (path, contracts) in output.contracts.iter() {
             for (_, contract) in contracts.iter().filter(|(name, _)| name.as_str() == contract_name)
             {
-                if let Some(Evm {
-                    deployed_bytecode:
-                        Some(DeployedBytecode { bytecode: Some(bytecode_to_check), .. }),
-                    ..
-                }) = &contract.evm
-                {
-                    let bytecod_to_check = bytecode_to_check
-                        .object
-                        .as_bytes()
-                        .ok_or(eyre!("missing bytecode object"))?
-                        .as_ref();
-
-                    let similarity = bytecode_similarity(bytecode, bytecod_to_check);
-                    println!("similarity: {}", similarity);
-
-                    if similarity > max_similarity {
-                        max_similarity = similarity;
-                        selected = Some((contract, path));
-                    }
+                let Some(mut evm) = contract.evm.clone() else { continue };
+                let Some(deployed_bytecode) = evm.deployed_bytecode.as_mut() else { continue };
+                if deployed_bytecode.bytecode.is_none() {
+                    continue;
+                }
+                link_libraries(deployed_bytecode, bytecode)?;
+
+                let bytecod_to_check = deployed_bytecode
+                    .bytecode
+                    .as_ref()
+                    .and_then(|bytecode_to_check| bytecode_to_check.object.as_bytes())
+                    .ok_or(eyre!("missing bytecode object"))?;
+
+                let similarity = bytecode_similarity(bytecode, bytecod_to_check);
+                println!("similarity: {}", similarity);
+
+                if similarity > max_similarity {
+                    max_similarity = similarity;
+                    selected = Some((contract, evm, path));
                 }
             }
         }
@@ -91,7 +273,7 @@ impl AsCompilationArtifact for (&str, RevmBytecode, &Sources, CompilerOutput) {
             return Err(eyre!("no similar contract found"));
         }
 
-        let (compilation_ref, path_ref) =
+        let (compilation_ref, evm, path_ref) =
             selected.ok_or(eyre!("no compilation reference found"))?;
 
         // get file id
@@ -103,21 +285,89 @@ impl AsCompilationArtifact for (&str, RevmBytecode, &Sources, CompilerOutput) {
 
         // collect all repated source
         let mut sources = BTreeMap::new();
-        for (path, source) in output.sources.iter_mut() {
-            let ast = ASTPruner::convert(source.ast.as_mut().ok_or(eyre!("AST does not exist"))?)?;
+        for (path, source) in output.sources.iter() {
+            let mut ast = source.ast.clone().ok_or(eyre!("AST does not exist"))?;
+            let ast = ASTPruner::convert(&mut ast)?;
             let source_code = &input_sources.get(path).ok_or(eyre!("missing source code"))?.content;
-            sources.insert(
-                source.id,
-                SourceFile { path: path.clone(), code: Arc::clone(&source_code), ast: ast.clone() },
-            );
+            sources.insert(source.id, SourceFile::new(path.clone(), Arc::clone(source_code), ast));
         }
 
+        let deployed_bytecode =
+            evm.deployed_bytecode.as_ref().and_then(|bytecode| bytecode.bytecode.as_ref());
+        let (runtime_source_map, creation_source_map) = rayon::join(
+            || pc_source_map(deployed_bytecode),
+            || pc_source_map(evm.bytecode.as_ref()),
+        );
+        let (mut runtime_source_map, mut creation_source_map) =
+            (runtime_source_map?, creation_source_map?);
+        let runtime_generated_sources = generated_sources(deployed_bytecode);
+        let creation_generated_sources = generated_sources(evm.bytecode.as_ref());
+        resolve_generated_locations(&mut runtime_source_map, &runtime_generated_sources);
+        resolve_generated_locations(&mut creation_source_map, &creation_generated_sources);
+
         Ok(CompilationArtifact {
             contract_name: contract_name.to_string(),
+            code_hash,
             file_id,
             abi: compilation_ref.abi.as_ref().ok_or(eyre!("missing abi"))?.clone(),
-            evm: compilation_ref.evm.as_ref().ok_or(eyre!("missing evm"))?.clone(),
+            evm,
             sources,
+            runtime_source_map: Arc::new(runtime_source_map),
+            creation_source_map: Arc::new(creation_source_map),
+            runtime_generated_sources,
+            creation_generated_sources,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use foundry_compilers::artifacts::sourcemap::{parse, Jump};
+
+    use super::*;
+
+    #[test]
+    fn test_differing_files() {
+        let file = |path: &str, code: &str| (PathBuf::from(path), Arc::new(code.to_string()));
+        let ours = vec![
+            file("src/Token.sol", "contract Token {}\n"),
+            file("src/Vault.sol", "contract Vault { uint a; }"),
+            file("lib/Math.sol", "library Math {}"),
+            file("src/interfaces/IERC20.sol", "interface IERC20 {}"),
+        ];
+        let theirs = vec![
+            file("src/Token.sol", "contract Token {}  \r\n"),
+            file("src/Vault.sol", "contract Vault { uint b; }"),
+            // another file of the same name
+            file("lib/forge-std/IERC20.sol", "interface IERC20 { function f(); }"),
+        ];
+        assert_eq!(differing_files(&ours, &theirs), vec![PathBuf::from("src/Vault.sol")]);
+    }
+
+    #[test]
+    fn test_resolve_generated_locations() {
+        let code = concat!(
+            "/// @src 0:100:150  \"function f() {...\"\n",
+            "function fun_f() {\n    sstore(0, 1)\n}\n",
+            "/// @src -1:-1:-1\n",
+            "function abi_decode() {}\n",
+        );
+        let generated = BTreeMap::from([(
+            3,
+            GeneratedSource { name: "#utility.yul".to_string(), code: Arc::new(code.to_string()) },
+        )]);
+        let (annotated, unannotated) = (code.find("sstore").unwrap(), code.find("abi").unwrap());
+        let elements = parse(&format!("{annotated}:12:3:-;{unannotated}:23:3:-;7:4:0:i")).unwrap();
+        let mut map: PcSourceMap = elements.into_iter().enumerate().collect();
+
+        resolve_generated_locations(&mut map, &generated);
+        // the annotated Yul code is mapped back to the sources
+        assert_eq!((map[&0].index(), map[&0].offset(), map[&0].length()), (Some(0), 100, 50));
+        // the code without counterpart keeps pointing to the generated source
+        assert_eq!((map[&1].index(), map[&1].offset()), (Some(3), unannotated as u32));
+        // as does the code of the actual sources
+        assert_eq!((map[&2].index(), map[&2].offset(), map[&2].jump()), (Some(0), 7, Jump::In));
+
+        assert_eq!(ir_source_location(code, 4), None);
+    }
+}
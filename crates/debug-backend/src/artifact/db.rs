@@ -0,0 +1,191 @@
+//! In-memory database of the compilation artifacts, indexed by both address and code hash, so
+//! that identical implementations deployed at many addresses, e.g., thousands of clones of a
+//! factory, share a single artifact.
+
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, B256};
+use rayon::prelude::*;
+
+use crate::artifact::compilation::CompilationArtifact;
+
+#[derive(Clone, Debug, Default)]
+pub struct ArtifactDb {
+    artifacts: Vec<CompilationArtifact>,
+    by_address: HashMap<Address, usize>,
+    by_code_hash: HashMap<B256, usize>,
+}
+
+impl ArtifactDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts the artifact of the contract at the given address. The artifact already stored
+    /// for the same code is kept, if any, so that the given one is dropped.
+    pub fn insert(&mut self, address: Address, artifact: CompilationArtifact) {
+        let index = match self.by_code_hash.get(&artifact.code_hash) {
+            Some(index) => *index,
+            None => {
+                let index = self.artifacts.len();
+                self.by_code_hash.insert(artifact.code_hash, index);
+                self.artifacts.push(artifact);
+                index
+            }
+        };
+        self.by_address.insert(address, index);
+    }
+
+    /// Attributes the artifact of the given code to the address, if there is one. Returns
+    /// whether there was.
+    pub fn link(&mut self, address: Address, code_hash: B256) -> bool {
+        let Some(index) = self.by_code_hash.get(&code_hash) else { return false };
+        self.by_address.insert(address, *index);
+        true
+    }
+
+    /// Returns the artifact of the contract at the given address.
+    pub fn get(&self, address: &Address) -> Option<&CompilationArtifact> {
+        self.by_address.get(address).map(|index| &self.artifacts[*index])
+    }
+
+    /// Returns the artifact of the given runtime code.
+    pub fn get_by_code_hash(&self, code_hash: &B256) -> Option<&CompilationArtifact> {
+        self.by_code_hash.get(code_hash).map(|index| &self.artifacts[*index])
+    }
+
+    pub fn contains_key(&self, address: &Address) -> bool {
+        self.by_address.contains_key(address)
+    }
+
+    /// Returns the addresses sharing the given code, sorted.
+    pub fn addresses_of(&self, code_hash: &B256) -> Vec<Address> {
+        let Some(index) = self.by_code_hash.get(code_hash) else { return Vec::new() };
+        let mut addresses: Vec<_> = self
+            .by_address
+            .iter()
+            .filter(|(_, i)| *i == index)
+            .map(|(address, _)| *address)
+            .collect();
+        addresses.sort();
+        addresses
+    }
+
+    /// Returns the addresses with an artifact, in no particular order.
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        self.by_address.keys()
+    }
+
+    /// Returns every address along with its artifact, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, &CompilationArtifact)> {
+        self.by_address.iter().map(|(address, index)| (address, &self.artifacts[*index]))
+    }
+
+    /// Returns the distinct artifacts, each of them once however many addresses share it.
+    pub fn values(&self) -> impl Iterator<Item = &CompilationArtifact> {
+        self.artifacts.iter()
+    }
+
+    /// Returns the distinct artifacts mutably, e.g., to analyze each of them once.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut CompilationArtifact> {
+        self.artifacts.iter_mut()
+    }
+
+    /// Returns the distinct artifacts mutably, to be processed in parallel.
+    pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut CompilationArtifact> {
+        self.artifacts.par_iter_mut()
+    }
+
+    /// Returns the number of addresses with an artifact.
+    pub fn len(&self) -> usize {
+        self.by_address.len()
+    }
+
+    /// Returns the number of distinct artifacts.
+    pub fn num_distinct(&self) -> usize {
+        self.artifacts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_address.is_empty()
+    }
+}
+
+impl FromIterator<(Address, CompilationArtifact)> for ArtifactDb {
+    fn from_iter<I: IntoIterator<Item = (Address, CompilationArtifact)>>(iter: I) -> Self {
+        let mut db = Self::new();
+        for (address, artifact) in iter {
+            db.insert(address, artifact);
+        }
+        db
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_json_abi::JsonAbi;
+    use alloy_primitives::{address, b256};
+    use foundry_compilers::artifacts::Evm;
+
+    const CLONE: B256 = b256!("00000000000000000000000000000000000000000000000000000000000000c1");
+    const TOKEN: B256 = b256!("00000000000000000000000000000000000000000000000000000000000000ee");
+
+    fn artifact(contract_name: &str, code_hash: B256) -> CompilationArtifact {
+        CompilationArtifact {
+            contract_name: contract_name.to_string(),
+            code_hash,
+            file_id: 0,
+            abi: JsonAbi::new(),
+            evm: Evm::default(),
+            sources: Default::default(),
+            runtime_source_map: Default::default(),
+            creation_source_map: Default::default(),
+            runtime_generated_sources: Default::default(),
+            creation_generated_sources: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_insert_dedups_by_code_hash() {
+        let (a, b, c) = (
+            address!("000000000000000000000000000000000000000a"),
+            address!("000000000000000000000000000000000000000b"),
+            address!("000000000000000000000000000000000000000c"),
+        );
+        let mut db = ArtifactDb::new();
+        db.insert(a, artifact("Clone", CLONE));
+        // the artifact of the same code is dropped in favor of the stored one
+        db.insert(b, artifact("Duplicate", CLONE));
+        db.insert(c, artifact("Token", TOKEN));
+
+        assert_eq!(db.len(), 3);
+        assert_eq!(db.num_distinct(), 2);
+        assert_eq!(db.get(&a).unwrap().contract_name, "Clone");
+        assert_eq!(db.get(&b).unwrap().contract_name, "Clone");
+        assert_eq!(db.get(&c).unwrap().contract_name, "Token");
+        assert_eq!(db.get_by_code_hash(&CLONE).unwrap().contract_name, "Clone");
+        assert_eq!(db.addresses_of(&CLONE), vec![a, b]);
+        assert_eq!(db.values().count(), 2);
+    }
+
+    #[test]
+    fn test_link() {
+        let (a, b) = (
+            address!("000000000000000000000000000000000000000a"),
+            address!("000000000000000000000000000000000000000b"),
+        );
+        let mut db: ArtifactDb = [(a, artifact("Clone", CLONE))].into_iter().collect();
+
+        // there is no artifact of an unknown code to attribute
+        assert!(!db.link(b, TOKEN));
+        assert!(!db.contains_key(&b));
+        assert!(db.get_by_code_hash(&TOKEN).is_none());
+
+        assert!(db.link(b, CLONE));
+        assert_eq!(db.get(&b).unwrap().contract_name, "Clone");
+        assert_eq!(db.len(), 2);
+        assert_eq!(db.num_distinct(), 1);
+        assert!(db.addresses_of(&TOKEN).is_empty());
+    }
+}
@@ -1,13 +1,24 @@
+use alloy_json_abi::JsonAbi;
 use alloy_primitives::{Address, Bytes, U256};
 use arrayvec::ArrayVec;
 use revm::interpreter::OpCode;
 use revm_inspectors::tracing::types::CallKind;
+use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use crate::utils::opcode;
 
-use crate::artifact::compilation::CompilationArtifact;
+use crate::{
+    analysis::{cfg::ControlFlowGraph, interface::InterfaceStandard, token::TokenMetadata},
+    artifact::{
+        db::ArtifactDb, flag::AddressFlag, verification::VerificationStatus, warning::Warning,
+    },
+    snapshot::Recording,
+};
 
 /// An arena of [DebugNode]s
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -119,6 +130,8 @@ pub struct DebugNode {
     pub kind: CallKind,
     /// Depth of the call.
     pub depth: usize,
+    /// The gas passed in to the call.
+    pub gas_limit: u64,
     /// The debug steps.
     pub steps: Vec<DebugStep>,
 }
@@ -145,12 +158,24 @@ impl DebugNode {
 
     /// Flattens this node into a [`DebugNodeFlat`].
     pub fn flat(&self) -> DebugNodeFlat {
-        DebugNodeFlat { address: self.address, kind: self.kind, steps: self.steps.clone() }
+        DebugNodeFlat {
+            address: self.address,
+            kind: self.kind,
+            depth: self.depth,
+            gas_limit: self.gas_limit,
+            steps: self.steps.clone(),
+        }
     }
 
     /// Flattens this node into a [`DebugNodeFlat`].
     pub fn into_flat(self) -> DebugNodeFlat {
-        DebugNodeFlat { address: self.address, kind: self.kind, steps: self.steps }
+        DebugNodeFlat {
+            address: self.address,
+            kind: self.kind,
+            depth: self.depth,
+            gas_limit: self.gas_limit,
+            steps: self.steps,
+        }
     }
 }
 
@@ -163,15 +188,101 @@ pub struct DebugNodeFlat {
     pub address: Address,
     /// The kind of call this is.
     pub kind: CallKind,
+    /// Depth of the call.
+    ///
+    /// Note that a call frame may be split into several consecutive nodes (one before and one
+    /// after each of its sub-calls), all of which share the same depth.
+    pub depth: usize,
+    /// The gas passed in to the call, which is unknown (and zero) for imported traces.
+    #[serde(default)]
+    pub gas_limit: u64,
     /// The debug steps.
     pub steps: Vec<DebugStep>,
 }
 
 impl DebugNodeFlat {
     /// Creates a new debug node flat.
-    pub fn new(address: Address, kind: CallKind, steps: Vec<DebugStep>) -> Self {
-        Self { address, kind, steps }
+    pub fn new(address: Address, kind: CallKind, depth: usize, steps: Vec<DebugStep>) -> Self {
+        Self { address, kind, depth, gas_limit: 0, steps }
     }
+
+    /// Returns the gas consumed by each step of this node alone, i.e., excluding the gas
+    /// consumed by sub-calls.
+    ///
+    /// The cost of the last step is unknown (the next step, if any, lives in another node) and
+    /// is reported as zero.
+    pub fn step_gas_costs(&self) -> impl Iterator<Item = u64> + '_ {
+        self.steps
+            .windows(2)
+            .map(|w| w[1].total_gas_used.saturating_sub(w[0].total_gas_used))
+            .chain((!self.steps.is_empty()).then_some(0))
+    }
+
+    /// Detects the loops executed in this node, i.e., runs of consecutive iterations which jump
+    /// back to the same instruction (the loop head): a `JUMPDEST` in legacy code, or the target
+    /// of a relative jump in EOF code.
+    ///
+    /// Only loops with at least `min_iterations` iterations are returned, sorted by their first
+    /// step. Nested loops are reported separately, with the outer loop coming first.
+    pub fn loops(&self, min_iterations: usize) -> Vec<LoopSummary> {
+        use revm::interpreter::opcode::{JUMP, JUMPDEST, JUMPI, RJUMP, RJUMPI, RJUMPV};
+
+        let mut loops = Vec::new();
+        let mut active: FxHashMap<usize, LoopSummary> = FxHashMap::default();
+        let mut last_visit: FxHashMap<usize, usize> = FxHashMap::default();
+
+        // EOF code has no `JUMPDEST`, so that any instruction may be a loop head
+        let is_eof =
+            self.steps.iter().any(|step| matches!(step.instruction, RJUMP | RJUMPI | RJUMPV));
+        for (i, step) in self.steps.iter().enumerate() {
+            if step.instruction != JUMPDEST && !is_eof {
+                continue;
+            }
+
+            let is_back_edge = i > 0 && {
+                let prev = &self.steps[i - 1];
+                matches!(prev.instruction, JUMP | JUMPI | RJUMP | RJUMPI | RJUMPV) &&
+                    prev.pc > step.pc
+            };
+            if is_back_edge {
+                if let Some(&start) = last_visit.get(&step.pc) {
+                    match active.get_mut(&step.pc) {
+                        // another iteration right after the previous one
+                        Some(summary) if summary.end == start => {
+                            summary.end = i;
+                            summary.iterations += 1;
+                        }
+                        _ => {
+                            let summary =
+                                LoopSummary { head_pc: step.pc, start, end: i, iterations: 1 };
+                            if let Some(done) = active.insert(step.pc, summary) {
+                                loops.push(done);
+                            }
+                        }
+                    }
+                }
+            }
+            last_visit.insert(step.pc, i);
+        }
+
+        loops.extend(active.into_values());
+        loops.retain(|summary| summary.iterations >= min_iterations);
+        loops.sort_by_key(|summary| (summary.start, std::cmp::Reverse(summary.end)));
+        loops
+    }
+}
+
+/// A loop detected in the steps of a [`DebugNodeFlat`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LoopSummary {
+    /// The program counter of the loop head.
+    pub head_pc: usize,
+    /// The index of the first step of the first iteration.
+    pub start: usize,
+    /// The index of the step right after the last iteration (exclusive).
+    pub end: usize,
+    /// The number of iterations.
+    pub iterations: usize,
 }
 
 /// A `DebugStep` is a snapshot of the EVM's runtime state.
@@ -191,11 +302,13 @@ pub struct DebugStep {
     pub returndata: Bytes,
     /// Opcode to be executed
     pub instruction: u8,
-    /// Optional bytes that are being pushed onto the stack.
-    /// Empty if the opcode is not a push or PUSH0.
+    /// The immediate bytes of the opcode, e.g., the bytes being pushed onto the stack by `PUSHn`
+    /// or the offset of `RJUMP` in EOF code, truncated to 32 bytes. Empty if the opcode takes no
+    /// immediate.
     #[serde(serialize_with = "hex::serialize", deserialize_with = "deserialize_arrayvec_hex")]
     pub push_bytes: ArrayVec<u8, 32>,
-    /// The program counter at this step.
+    /// The program counter at this step. In EOF code, this is the offset in the whole container
+    /// rather than in the current code section, so that it is unique across code sections.
     ///
     /// Note: To map this step onto source code using a source map, you must convert the program
     /// counter to an instruction counter.
@@ -234,6 +347,20 @@ impl DebugStep {
     pub fn opcode_modifies_memory(&self) -> bool {
         OpCode::new(self.instruction).map_or(false, opcode::is_memory_modifying_opcode)
     }
+
+    /// Returns whether the branch is taken if this step is a `JUMPI` (or `RJUMPI` in EOF code),
+    /// or `None` otherwise.
+    pub fn branch_taken(&self) -> Option<bool> {
+        use revm::interpreter::opcode::{JUMPI, RJUMPI};
+        // the condition is the second item of `JUMPI`, after the destination, and the only item
+        // of `RJUMPI`, whose destination is an immediate
+        let condition = match self.instruction {
+            JUMPI => self.stack.len().checked_sub(2)?,
+            RJUMPI => self.stack.len().checked_sub(1)?,
+            _ => return None,
+        };
+        Some(!self.stack[condition].is_zero())
+    }
 }
 
 fn deserialize_arrayvec_hex<'de, D: serde::Deserializer<'de>>(
@@ -245,10 +372,190 @@ fn deserialize_arrayvec_hex<'de, D: serde::Deserializer<'de>>(
     Ok(array)
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct DebugArtifact {
     /// Debug traces returned from the EVM execution.
     pub debug_arena: Vec<DebugNodeFlat>,
-    /// Map of source files. Note that each address will have a compilation artifact.
-    pub compilation_artifacts: HashMap<Address, CompilationArtifact>,
+    /// Compilation artifacts of the verified contracts, indexed by address and code hash.
+    pub compilation_artifacts: ArtifactDb,
+    /// Standard interfaces implemented by the touched contracts.
+    pub interfaces: HashMap<Address, Vec<InterfaceStandard>>,
+    /// Metadata of the touched ERC-20 tokens.
+    pub tokens: HashMap<Address, TokenMetadata>,
+    /// Issues found while preparing the session, e.g., unverified contracts.
+    pub warnings: Vec<Warning>,
+    /// Addresses flagged by the imported security lists, e.g., known attackers and mixers.
+    pub flags: HashMap<Address, AddressFlag>,
+    /// The runtime bytecode of the touched contracts, as deployed after the transaction.
+    pub codes: HashMap<Address, Bytes>,
+    /// How faithfully the recompiled sources of the verified contracts reproduce their code.
+    pub verification: HashMap<Address, VerificationStatus>,
+    /// The implementation of the minimal proxies (EIP-1167) among the touched contracts.
+    pub clones: HashMap<Address, Address>,
+    /// The beneficiary of the block, i.e., the builder or validator collecting its fees.
+    pub coinbase: Option<Address>,
+    /// The sender of the transaction.
+    pub sender: Option<Address>,
+    /// The value sent by the transaction to the outermost call.
+    pub value: U256,
+    /// The nonces of the touched accounts before the transaction, from which the nonces their
+    /// `CREATE`s derive addresses with are counted.
+    pub nonces: HashMap<Address, u64>,
+    /// The beneficiary of each `SELFDESTRUCT` and the balance sent to it, by the index of the
+    /// node ending with it.
+    pub selfdestructs: HashMap<usize, (Address, U256)>,
+    /// The nodes whose stack and memory are only recorded at the steps of a coarse granularity,
+    /// see [`SnapshotGranularity`](crate::snapshot::SnapshotGranularity).
+    pub coarse_nodes: HashSet<usize>,
+    /// The state the transaction read, against which the coarse nodes are re-executed.
+    pub recording: Option<Arc<Recording>>,
+    /// The control-flow graph of the runtime code of the touched contracts.
+    pub cfgs: HashMap<Address, Arc<ControlFlowGraph>>,
+}
+
+impl DebugArtifact {
+    /// Returns the name of the contract deployed at the given address, if it is known.
+    pub fn contract_name(&self, address: &Address) -> Option<&str> {
+        self.compilation_artifacts.get(address).map(|artifact| artifact.contract_name.as_str())
+    }
+
+    /// Returns the ABI of the contract at the given address, which is the one of its
+    /// implementation for minimal proxies.
+    pub fn abi(&self, address: &Address) -> Option<&JsonAbi> {
+        let address = self.clones.get(address).unwrap_or(address);
+        self.compilation_artifacts.get(address).map(|artifact| &artifact.abi)
+    }
+
+    /// Returns a human-readable label of the given address, i.e., the contract name if it is
+    /// known, or the address itself otherwise, followed by its flag if it is flagged.
+    pub fn address_label(&self, address: &Address) -> String {
+        let mut label = match self.contract_name(address) {
+            Some(name) => format!("{name}@{address}"),
+            None => address.to_string(),
+        };
+        if let Some(implementation) = self.clones.get(address) {
+            let implementation = match self.contract_name(implementation) {
+                Some(name) => format!("{name}@{implementation}"),
+                None => implementation.to_string(),
+            };
+            label.push_str(&format!(" [clone of {implementation}]"));
+        }
+        if self.coinbase == Some(*address) {
+            label.push_str(" [coinbase]");
+        }
+        match self.flags.get(address) {
+            Some(flag) => format!("{label} [{flag}]"),
+            None => label,
+        }
+    }
+
+    /// Formats an amount of the given token, e.g., `1,250.5 USDC`, if the token is known.
+    pub fn format_token_amount(&self, token: &Address, amount: U256) -> Option<String> {
+        self.tokens.get(token).map(|metadata| metadata.format_amount(amount))
+    }
+
+    /// Returns the addresses of the contracts executed in the transaction, in the order of their
+    /// first execution.
+    pub fn touched_addresses(&self) -> Vec<Address> {
+        let mut seen = std::collections::HashSet::new();
+        self.debug_arena
+            .iter()
+            .map(|node| node.address)
+            .filter(|address| seen.insert(*address))
+            .collect()
+    }
+
+    /// Returns the first node of the call frame which the given node belongs to.
+    ///
+    /// A call frame is split into several nodes around its sub-calls, all of which are at the
+    /// same depth and are not separated by any shallower node.
+    pub fn frame_start(&self, node: usize) -> usize {
+        let depth = self.debug_arena[node].depth;
+        let mut start = node;
+        for (i, other) in self.debug_arena[..node].iter().enumerate().rev() {
+            if other.depth < depth {
+                break;
+            }
+            if other.depth == depth {
+                start = i;
+            }
+        }
+        start
+    }
+
+    /// Returns the address whose storage and balance are used by the given node, which differs
+    /// from the address of the code for `DELEGATECALL` and `CALLCODE`.
+    pub fn context_address(&self, node: usize) -> Address {
+        let mut node = node;
+        loop {
+            let current = &self.debug_arena[node];
+            if !matches!(current.kind, CallKind::DelegateCall | CallKind::CallCode) {
+                return current.address;
+            }
+            // The node right before the frame belongs to the caller
+            match self.frame_start(node).checked_sub(1) {
+                Some(parent) => node = parent,
+                None => return current.address,
+            }
+        }
+    }
+
+    /// Returns the nodes of the call frame which the given node belongs to, up to (and
+    /// including) the given node.
+    pub fn frame_nodes(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        let depth = self.debug_arena[node].depth;
+        (self.frame_start(node)..=node).filter(move |i| self.debug_arena[*i].depth == depth)
+    }
+
+    /// Returns the index of the given step in the whole execution.
+    pub fn step_index(&self, node: usize, step: usize) -> usize {
+        self.debug_arena[..node].iter().map(|node| node.steps.len()).sum::<usize>() + step
+    }
+
+    /// Returns the node and the step within the node of the given step in the whole execution,
+    /// or `None` if the execution has fewer steps.
+    pub fn locate_step(&self, mut index: usize) -> Option<(usize, usize)> {
+        for (node, debug_node) in self.debug_arena.iter().enumerate() {
+            if index < debug_node.steps.len() {
+                return Some((node, index));
+            }
+            index -= debug_node.steps.len();
+        }
+        None
+    }
+
+    /// Returns all steps in the order of execution, along with their nodes and indices within
+    /// the nodes.
+    pub fn steps(&self) -> impl Iterator<Item = (usize, usize, &DebugStep)> + '_ {
+        self.debug_arena
+            .iter()
+            .enumerate()
+            .flat_map(|(i, node)| node.steps.iter().enumerate().map(move |(j, step)| (i, j, step)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::interpreter::opcode::{JUMP, JUMPDEST, PUSH1, STOP};
+
+    fn step(pc: usize, instruction: u8) -> DebugStep {
+        DebugStep { pc, instruction, ..Default::default() }
+    }
+
+    #[test]
+    fn test_loops() {
+        let mut steps = vec![step(0, PUSH1)];
+        for _ in 0..3 {
+            steps.extend([step(2, JUMPDEST), step(3, PUSH1), step(5, JUMP)]);
+        }
+        steps.extend([step(2, JUMPDEST), step(6, STOP)]);
+        let node = DebugNodeFlat::new(Address::ZERO, CallKind::Call, 0, steps);
+
+        assert_eq!(
+            node.loops(2),
+            vec![LoopSummary { head_pc: 2, start: 1, end: 10, iterations: 3 }]
+        );
+        assert!(node.loops(4).is_empty());
+    }
 }
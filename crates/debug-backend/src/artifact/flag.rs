@@ -0,0 +1,111 @@
+//! Flagged addresses imported from security lists, e.g., known attackers and mixers.
+//!
+//! A list is a JSON array of flagged addresses:
+//!
+//! ```json
+//! [
+//!   {
+//!     "address": "0xd90e2f925DA726b50C4Ed8D0Fb90Ad053324F31b",
+//!     "category": "mixer",
+//!     "label": "Tornado Cash: Router",
+//!     "source": "OFAC"
+//!   }
+//! ]
+//! ```
+
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+use alloy_primitives::Address;
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+/// The category of a flagged address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagCategory {
+    /// An address involved in a known exploit.
+    Attacker,
+    /// A mixer, e.g., Tornado Cash.
+    Mixer,
+    /// A sanctioned address.
+    Sanctioned,
+    /// A phishing or scam address.
+    Scam,
+    Other,
+}
+
+impl fmt::Display for FlagCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Attacker => "attacker",
+            Self::Mixer => "mixer",
+            Self::Sanctioned => "sanctioned",
+            Self::Scam => "scam",
+            Self::Other => "flagged",
+        };
+        f.write_str(s)
+    }
+}
+
+/// An address flagged by a security list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressFlag {
+    pub category: FlagCategory,
+    pub label: String,
+    /// Where the flag comes from, e.g., the name of the list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl fmt::Display for AddressFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.category, self.label)
+    }
+}
+
+#[derive(Deserialize)]
+struct FlagEntry {
+    address: Address,
+    #[serde(flatten)]
+    flag: AddressFlag,
+}
+
+/// Parses a list of flagged addresses. Later entries of the same address take precedence.
+pub fn parse_flags(content: &str) -> Result<HashMap<Address, AddressFlag>> {
+    let entries: Vec<FlagEntry> = serde_json::from_str(content)?;
+    Ok(entries.into_iter().map(|entry| (entry.address, entry.flag)).collect())
+}
+
+/// Loads a list of flagged addresses from a file.
+pub fn load_flags(path: &Path) -> Result<HashMap<Address, AddressFlag>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| eyre!("failed to read the flag list {}: {e}", path.display()))?;
+    parse_flags(&content).map_err(|e| eyre!("invalid flag list {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[test]
+    fn test_parse_flags() {
+        let content = r#"[
+            {"address": "0xd90e2f925DA726b50C4Ed8D0Fb90Ad053324F31b", "category": "mixer", "label": "Tornado Cash: Router", "source": "OFAC"},
+            {"address": "0x0000000000000000000000000000000000000001", "category": "attacker", "label": "Exploiter 1"},
+            {"address": "0x0000000000000000000000000000000000000001", "category": "attacker", "label": "Exploiter 2"}
+        ]"#;
+        let flags = parse_flags(content).unwrap();
+        assert_eq!(flags.len(), 2);
+
+        let router = &flags[&address!("d90e2f925DA726b50C4Ed8D0Fb90Ad053324F31b")];
+        assert_eq!(router.category, FlagCategory::Mixer);
+        assert_eq!(router.source.as_deref(), Some("OFAC"));
+        assert_eq!(router.to_string(), "mixer: Tornado Cash: Router");
+
+        let exploiter = &flags[&address!("0000000000000000000000000000000000000001")];
+        assert_eq!(exploiter.label, "Exploiter 2");
+
+        assert!(parse_flags(r#"[{"address": "0x01", "category": "mixer", "label": "x"}]"#).is_err());
+    }
+}
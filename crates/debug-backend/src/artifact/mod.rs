@@ -1,2 +1,7 @@
 pub mod compilation;
+pub mod db;
 pub mod debug;
+pub mod flag;
+pub mod usage;
+pub mod verification;
+pub mod warning;
@@ -0,0 +1,149 @@
+//! Estimates of the memory held by debug artifacts, which is dominated by the snapshots of the
+//! execution state recorded at every step, and the compaction of these snapshots.
+//!
+//! The memory of a call, its calldata, and its return data buffer seldom change from one step to
+//! the next, yet each step records its own copy of them. Compacting shares the identical copies
+//! between consecutive steps, which loses nothing.
+
+use std::{collections::HashSet, iter::Sum, mem::size_of, ops::Add};
+
+use alloy_primitives::{Bytes, U256};
+
+use crate::artifact::debug::{DebugArtifact, DebugStep};
+
+/// The memory held by a debug artifact, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The snapshots of the steps, i.e., their stacks, memory, calldata, and return data.
+    pub snapshots: usize,
+    /// The source code of the verified contracts.
+    pub sources: usize,
+    /// The state recorded to re-execute the transaction, see
+    /// [`Recording`](crate::snapshot::Recording).
+    pub recording: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.snapshots + self.sources + self.recording
+    }
+}
+
+impl Add for MemoryUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            snapshots: self.snapshots + other.snapshots,
+            sources: self.sources + other.sources,
+            recording: self.recording + other.recording,
+        }
+    }
+}
+
+impl Sum for MemoryUsage {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// Formats a number of bytes with a binary unit, e.g., `1.5 GiB`.
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+impl DebugArtifact {
+    /// Estimates the memory held by the artifact. Buffers shared by several steps are counted
+    /// once.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut buffers = HashSet::new();
+        let mut snapshots = 0;
+        for (_, _, step) in self.steps() {
+            snapshots += size_of::<DebugStep>() + step.stack.capacity() * size_of::<U256>();
+            for buffer in [&step.memory, &step.calldata, &step.returndata] {
+                if !buffer.is_empty() && buffers.insert((buffer.as_ptr(), buffer.len())) {
+                    snapshots += buffer.len();
+                }
+            }
+        }
+
+        let mut codes = HashSet::new();
+        let sources = self
+            .compilation_artifacts
+            .values()
+            .flat_map(|artifact| artifact.sources.values())
+            .filter(|source| codes.insert(source.code.as_ptr()))
+            .map(|source| source.code.len())
+            .sum();
+
+        let recording = self.recording.as_ref().map_or(0, |recording| recording.size());
+
+        MemoryUsage { snapshots, sources, recording }
+    }
+
+    /// Shares the buffers of each step which are identical to the ones of the previous step of
+    /// the same node, so that the copies are freed. Returns the number of copies freed.
+    pub fn compact_snapshots(&mut self) -> usize {
+        let mut freed = 0;
+        for node in &mut self.debug_arena {
+            let mut previous: Option<[Bytes; 3]> = None;
+            for step in &mut node.steps {
+                let buffers = [&mut step.memory, &mut step.calldata, &mut step.returndata];
+                if let Some(previous) = &previous {
+                    for (buffer, shared) in buffers.into_iter().zip(previous) {
+                        if !buffer.is_empty() &&
+                            buffer.as_ptr() != shared.as_ptr() &&
+                            **buffer == *shared
+                        {
+                            *buffer = shared.clone();
+                            freed += 1;
+                        }
+                    }
+                }
+                previous =
+                    Some([step.memory.clone(), step.calldata.clone(), step.returndata.clone()]);
+            }
+        }
+        freed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::DebugNodeFlat;
+
+    #[test]
+    fn test_compact_snapshots() {
+        // every step records its own copy of the same memory
+        let steps = (0..3)
+            .map(|pc| DebugStep { pc, memory: vec![0xab; 0x40].into(), ..Default::default() })
+            .collect();
+        let mut artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(Address::ZERO, CallKind::Call, 0, steps)],
+            ..Default::default()
+        };
+
+        let before = artifact.memory_usage();
+        assert_eq!(artifact.compact_snapshots(), 2);
+        let after = artifact.memory_usage();
+        assert_eq!(before.snapshots - after.snapshots, 2 * 0x40);
+        assert_eq!(artifact.compact_snapshots(), 0);
+
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 << 29), "1.5 GiB");
+    }
+}
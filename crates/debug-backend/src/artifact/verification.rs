@@ -0,0 +1,186 @@
+//! Verification of recompiled sources against the on-chain code, so that users know whether the
+//! sources they step through are really the ones being executed.
+
+use std::{fmt, ops::Range};
+
+use foundry_compilers::{
+    artifacts::{CompilerOutput, DeployedBytecode, Evm, Settings, SolcInput},
+    solc::Solc,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::compilation::bytecode_bytes;
+
+/// Optimizer runs commonly used by deployers, tried when the ones reported by the explorer do
+/// not reproduce the on-chain code.
+const COMMON_OPTIMIZER_RUNS: &[usize] = &[200, 1_000_000];
+
+/// How faithfully the recompiled sources reproduce the on-chain code of a contract, from the
+/// least to the most faithful.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum VerificationStatus {
+    /// The recompiled code differs from the on-chain code, so that the source mapping is likely
+    /// wrong.
+    Mismatch,
+    /// The recompiled code matches, but its metadata hash differs, e.g., because of different
+    /// comments or file paths, which does not affect the execution.
+    Partial,
+    /// The recompiled code, including its metadata hash, matches the on-chain code.
+    Full,
+}
+
+impl fmt::Display for VerificationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Mismatch => "bytecode mismatch",
+            Self::Partial => "partial match",
+            Self::Full => "full match",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Splits the CBOR-encoded metadata appended by solc off the runtime code, if there is any.
+pub fn split_metadata(code: &[u8]) -> (&[u8], &[u8]) {
+    let Some(len) = code.len().checked_sub(2) else { return (code, &[]) };
+    let metadata_len = u16::from_be_bytes([code[len], code[len + 1]]) as usize;
+    match len.checked_sub(metadata_len) {
+        // the metadata is a CBOR map of at most a few entries
+        Some(start) if metadata_len > 0 && code[start] & 0xf0 == 0xa0 => code.split_at(start),
+        _ => (code, &[]),
+    }
+}
+
+/// Verifies the on-chain runtime code against the compiled one. Immutables and linked libraries
+/// are only known at deployment, so that they are ignored.
+pub fn verify_deployed_bytecode(onchain: &[u8], compiled: &DeployedBytecode) -> VerificationStatus {
+    let Some(bytecode) = compiled.bytecode.as_ref() else { return VerificationStatus::Mismatch };
+    let Some(expected) = bytecode_bytes(bytecode) else { return VerificationStatus::Mismatch };
+
+    let mut ignored: Vec<Range<usize>> = compiled
+        .immutable_references
+        .values()
+        .flatten()
+        .chain(bytecode.link_references.values().flat_map(|libraries| libraries.values().flatten()))
+        .map(|offsets| offsets.start as usize..(offsets.start + offsets.length) as usize)
+        .collect();
+    // Libraries start by pushing their own address, to forbid being called other than by
+    // delegate calls
+    if expected.len() > 21 && expected[0] == 0x73 && expected[1..21].iter().all(|b| *b == 0) {
+        ignored.push(1..21);
+    }
+
+    let mut actual = onchain.to_vec();
+    for range in ignored {
+        if let Some(bytes) = actual.get_mut(range) {
+            bytes.fill(0);
+        }
+    }
+
+    let (actual_code, actual_metadata) = split_metadata(&actual);
+    let (expected_code, expected_metadata) = split_metadata(&expected);
+    if actual_code != expected_code {
+        VerificationStatus::Mismatch
+    } else if actual_metadata != expected_metadata {
+        VerificationStatus::Partial
+    } else {
+        VerificationStatus::Full
+    }
+}
+
+/// Verifies the on-chain runtime code against the best matching contract of the given name in
+/// the compiler output.
+pub fn verify_output(
+    onchain: &[u8],
+    output: &CompilerOutput,
+    contract_name: &str,
+) -> VerificationStatus {
+    output
+        .contracts
+        .values()
+        .filter_map(|contracts| contracts.get(contract_name))
+        .filter_map(|contract| match &contract.evm {
+            Some(Evm { deployed_bytecode: Some(deployed), .. }) => {
+                Some(verify_deployed_bytecode(onchain, deployed))
+            }
+            _ => None,
+        })
+        .max()
+        .unwrap_or(VerificationStatus::Mismatch)
+}
+
+/// Returns alternatives to the compiler settings reported by the explorer, which are sometimes
+/// mislabeled: the default EVM version of the compiler, the optimizer toggled, and commonly used
+/// optimizer runs, from the most to the least likely.
+pub fn alternative_settings(settings: &Settings) -> Vec<Settings> {
+    let mut alternatives = Vec::new();
+    if settings.evm_version.is_some() {
+        let mut alternative = settings.clone();
+        alternative.evm_version = None;
+        alternatives.push(alternative);
+    }
+
+    let enabled = settings.optimizer.enabled.unwrap_or_default();
+    let mut toggled = settings.clone();
+    toggled.optimizer.enabled = Some(!enabled);
+    alternatives.push(toggled);
+
+    for runs in COMMON_OPTIMIZER_RUNS {
+        if enabled && settings.optimizer.runs != Some(*runs) {
+            let mut alternative = settings.clone();
+            alternative.optimizer.runs = Some(*runs);
+            alternatives.push(alternative);
+        }
+    }
+    alternatives
+}
+
+/// Tries the alternatives to the compiler settings of the input until one reproduces the
+/// on-chain code, if the given output of the input does not. Returns the input reproducing the
+/// code the most faithfully, along with its output.
+pub fn replicate_settings(
+    compiler: &Solc,
+    input: SolcInput,
+    output: CompilerOutput,
+    onchain: &[u8],
+    contract_name: &str,
+) -> (SolcInput, CompilerOutput, VerificationStatus) {
+    let status = verify_output(onchain, &output, contract_name);
+    if status != VerificationStatus::Mismatch {
+        return (input, output, status);
+    }
+    for settings in alternative_settings(&input.settings) {
+        let alternative = SolcInput { settings, ..input.clone() };
+        let Ok(alternative_output) = compiler.compile_exact(&alternative) else { continue };
+        let alternative_status = verify_output(onchain, &alternative_output, contract_name);
+        if alternative_status > status {
+            return (alternative, alternative_output, alternative_status);
+        }
+    }
+    (input, output, status)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::hex;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_deployed_bytecode() {
+        // PUSH2 <immutable> STOP followed by a one-entry CBOR map of 3 bytes
+        let compiled: DeployedBytecode = serde_json::from_value(json!({
+            "object": "6100000000a161610003",
+            "immutableReferences": { "1": [{ "start": 1, "length": 2 }] },
+        }))
+        .unwrap();
+
+        let onchain = hex!("61" "1234" "00" "a1" "6161" "0003");
+        assert_eq!(verify_deployed_bytecode(&onchain, &compiled), VerificationStatus::Full);
+        let onchain = hex!("61" "1234" "00" "a1" "6162" "0003");
+        assert_eq!(verify_deployed_bytecode(&onchain, &compiled), VerificationStatus::Partial);
+        let onchain = hex!("61" "1234" "01" "a1" "6161" "0003");
+        assert_eq!(verify_deployed_bytecode(&onchain, &compiled), VerificationStatus::Mismatch);
+    }
+}
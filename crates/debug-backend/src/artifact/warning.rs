@@ -0,0 +1,72 @@
+use std::fmt;
+
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+
+/// The kind of an issue which makes the debugging session less trustworthy or less complete.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum WarningKind {
+    /// The replayed transaction does not match its on-chain receipt.
+    ReplayMismatch,
+    /// The source code of a contract is not verified on the explorer.
+    UnverifiedContract,
+    /// The source code of a contract is verified, but cannot be compiled by EDB (e.g., Vyper).
+    UnsupportedSource,
+    /// The sources of a local artifact disagree with the explorer's for the same contract.
+    SourceMismatch,
+    /// The recompiled sources of a contract do not reproduce its on-chain code.
+    BytecodeMismatch,
+    /// The explorer rate-limited the requests, which slowed down fetching the source code.
+    RateLimited,
+    /// Nearby transactions of the block touched the same contracts, e.g., a suspected sandwich.
+    MevContext,
+    /// The block of the transaction is not finalized yet, so that it may still be reorged.
+    UnfinalizedBlock,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::ReplayMismatch => "replay mismatch",
+            Self::UnverifiedContract => "unverified contract",
+            Self::UnsupportedSource => "unsupported source",
+            Self::SourceMismatch => "source mismatch",
+            Self::BytecodeMismatch => "bytecode mismatch",
+            Self::RateLimited => "rate limited",
+            Self::MevContext => "MEV context",
+            Self::UnfinalizedBlock => "unfinalized block",
+        };
+        f.write_str(s)
+    }
+}
+
+/// An issue found while preparing the debugging session.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// The contract the warning is about, if any.
+    pub address: Option<Address>,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: impl Into<String>) -> Self {
+        Self { kind, address: None, message: message.into() }
+    }
+
+    /// Attaches the contract the warning is about.
+    pub fn with_address(mut self, address: Address) -> Self {
+        self.address = Some(address);
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.kind)?;
+        if let Some(address) = self.address {
+            write!(f, "{address}: ")?;
+        }
+        f.write_str(&self.message)
+    }
+}
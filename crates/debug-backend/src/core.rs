@@ -2,21 +2,28 @@ use std::{
     collections::{HashMap, HashSet},
     fmt::Debug,
     path::PathBuf,
+    sync::Arc,
     time::Duration,
 };
 
 use alloy_chains::Chain;
-use alloy_primitives::{Address, Bytes};
-use edb_utils::{cache::CachePath, init_progress, update_progress};
+use alloy_primitives::{keccak256, Address, Bytes, U256};
+use edb_utils::{
+    cache::CachePath,
+    init_progress,
+    profile::{span, Phase},
+    update_progress,
+};
 use eyre::{eyre, Result};
 use foundry_block_explorers::{contract::Metadata, errors::EtherscanError, Client};
 use foundry_compilers::{
-    artifacts::{output_selection::OutputSelection, SolcInput, Source},
-    solc::{Solc, SolcLanguage},
+    artifacts::{output_selection::OutputSelection, CompilerOutput, SolcInput, Source, Sources},
+    solc::SolcLanguage,
 };
+use rayon::prelude::*;
 use revm::{
     db::CacheDB,
-    primitives::{CreateScheme, EnvWithHandlerCfg},
+    primitives::{Bytecode as RevmBytecode, CreateScheme, EnvWithHandlerCfg},
     DatabaseRef,
 };
 
@@ -25,27 +32,50 @@ use revm::{
 const DEFAULT_CACHE_TTL: u64 = 86400;
 
 use crate::{
-    analysis::source_map::SourceMapAnalysis,
+    analysis::{
+        cfg::ControlFlowGraph,
+        clone::detect_clones,
+        interface::{detect_interfaces, InterfaceStandard},
+        source_map::SourceMapAnalysis,
+        token::{fetch_token_metadata, TokenFile, TokenMetadata},
+    },
     artifact::{
         compilation::{AsCompilationArtifact, CompilationArtifact},
+        db::ArtifactDb,
         debug::{DebugArtifact, DebugNodeFlat},
+        flag::{load_flags, AddressFlag},
+        verification::{replicate_settings, verify_output, VerificationStatus},
+        warning::{Warning, WarningKind},
     },
     etherscan_rate_limit_guard,
+    event::{emit, EngineEvent, EventSender, Stage},
     inspector::{CollectInspector, DebugInspector},
-    utils::evm::new_evm_with_inspector,
+    snapshot::{Recording, SnapshotGranularity},
+    solc::SolcManager,
+    utils::{
+        compilation::linked_libraries,
+        evm::{new_evm_with_inspector, ProfiledDb},
+    },
 };
 
 #[derive(Debug, Default)]
 pub struct DebugBackendBuilder {
     chain: Option<Chain>,
     api_key: Option<String>,
+    api_url: Option<String>,
     cache_root: Option<PathBuf>,
     cache_ttl: Option<Duration>,
+    no_cache: bool,
+    token_override_file: Option<PathBuf>,
+    flag_files: Vec<PathBuf>,
+    events: Option<EventSender>,
+    granularity: SnapshotGranularity,
+    solc: SolcManager,
 
     // Compilation artifact from local file system
     // XXX (ZZ): let's support them later
     local_compilation_artifact: Option<CompilationArtifact>,
-    compilation_artifacts: Option<HashMap<Address, CompilationArtifact>>,
+    compilation_artifacts: Option<ArtifactDb>,
 }
 
 impl DebugBackendBuilder {
@@ -70,6 +100,21 @@ impl DebugBackendBuilder {
         self
     }
 
+    /// Disable the cache of the explorer responses, e.g., so that every response is fetched
+    /// while they are recorded as fixtures.
+    /// If not set, the cache is used.
+    pub fn no_cache(mut self) -> Self {
+        self.no_cache = true;
+        self
+    }
+
+    /// Set the file of user-maintained token metadata, which overrides the fetched metadata.
+    /// If not set, `~/.edb/tokens.json` will be used.
+    pub fn token_override_file(mut self, path: PathBuf) -> Self {
+        self.token_override_file = Some(path);
+        self
+    }
+
     /// Set the etherscan API key.
     /// If not set, a blank API key will be used.
     pub fn etherscan_api_key(mut self, etherscan_api_key: String) -> Self {
@@ -77,6 +122,14 @@ impl DebugBackendBuilder {
         self
     }
 
+    /// Set the URL of the etherscan-compatible API, e.g., for explorers of chains unknown to
+    /// `alloy-chains` or self-hosted instances.
+    /// If not set, the URL of the chain's default explorer will be used.
+    pub fn etherscan_api_url(mut self, api_url: String) -> Self {
+        self.api_url = Some(api_url);
+        self
+    }
+
     // XXX (ZZ): let's support them later
     /// Set the local compilation artifact.
     /// If not set, the local compilation artifact will not be used.
@@ -95,7 +148,7 @@ impl DebugBackendBuilder {
         mut self,
         compilation_artifacts: HashMap<Address, impl AsCompilationArtifact>,
     ) -> Result<Self> {
-        let result: Result<HashMap<Address, CompilationArtifact>, _> = compilation_artifacts
+        let result: Result<ArtifactDb, _> = compilation_artifacts
             .into_iter()
             .map(|(k, v)| {
                 let artifact = v.as_artifact()?;
@@ -107,6 +160,34 @@ impl DebugBackendBuilder {
         Ok(self)
     }
 
+    /// Add a list of flagged addresses, e.g., known attackers and mixers, which are marked in
+    /// the debug artifact. `~/.edb/flags.json` is always loaded if it exists.
+    pub fn flag_file(mut self, path: PathBuf) -> Self {
+        self.flag_files.push(path);
+        self
+    }
+
+    /// Set the channel on which the progress of the analysis is streamed.
+    /// If not set, no event is sent.
+    pub fn events(mut self, sender: EventSender) -> Self {
+        self.events = Some(sender);
+        self
+    }
+
+    /// Set the granularity at which the stack and memory are recorded.
+    /// If not set, they are recorded at every step.
+    pub fn snapshot_granularity(mut self, granularity: SnapshotGranularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
+
+    /// Set the solc installations to compile the fetched sources with.
+    /// If not set, the compilers under `~/.edb/solc` will be used, and downloaded if missing.
+    pub fn solc_manager(mut self, manager: SolcManager) -> Self {
+        self.solc = manager;
+        self
+    }
+
     /// Build the debug backend.
     pub fn build<DBRef>(self, db: &DBRef, env: EnvWithHandlerCfg) -> Result<DebugBackend<&DBRef>>
     where
@@ -114,16 +195,30 @@ impl DebugBackendBuilder {
         DBRef::Error: std::error::Error,
     {
         // XXX: the following code looks bad and needs to be refactored
+        let cache_root = self
+            .cache_root
+            .or(CachePath::edb_etherscan_chain_cache_dir(self.chain.unwrap_or(Chain::default())))
+            .filter(|_| !self.no_cache);
         let cb = Client::builder().with_cache(
-            self.cache_root.or(CachePath::edb_etherscan_chain_cache_dir(
-                self.chain.unwrap_or(Chain::default()),
-            )),
+            cache_root,
             self.cache_ttl.unwrap_or(Duration::from_secs(DEFAULT_CACHE_TTL)),
         );
         let cb = if let Some(chain) = self.chain { cb.chain(chain)? } else { cb };
         let cb = if let Some(api_key) = self.api_key { cb.with_api_key(api_key) } else { cb };
+        let cb = if let Some(api_url) = self.api_url { cb.with_api_url(api_url)? } else { cb };
         let client = cb.build()?;
 
+        let token_cache_file =
+            CachePath::edb_token_cache_file(self.chain.unwrap_or(Chain::default()));
+        let token_override_file = self.token_override_file.or(CachePath::edb_token_override_file());
+
+        // The flags of the later lists take precedence
+        let mut flags = HashMap::new();
+        let default_flag_file = CachePath::edb_flags_file().filter(|path| path.exists());
+        for path in default_flag_file.iter().chain(&self.flag_files) {
+            flags.extend(load_flags(path)?);
+        }
+
         let local_compilation_artifact = self.local_compilation_artifact;
 
         let compilation_artifacts = self.compilation_artifacts.unwrap_or_default();
@@ -134,8 +229,18 @@ impl DebugBackendBuilder {
             addresses: HashSet::new(),
             metadata: HashMap::new(),
             creation_codes: HashMap::new(),
+            codes: HashMap::new(),
+            verification: HashMap::new(),
+            clones: HashMap::new(),
+            warnings: Vec::new(),
             etherscan: client,
-            base_db: CacheDB::new(db),
+            token_cache_file,
+            token_override_file,
+            flags,
+            events: self.events,
+            granularity: self.granularity,
+            solc: self.solc,
+            base_db: CacheDB::new(ProfiledDb(db)),
             env,
         })
     }
@@ -149,11 +254,23 @@ pub struct DebugBackend<DBRef> {
     // Creation code of contracts that are deployed during the transaction
     pub creation_codes: HashMap<Address, (Bytes, CreateScheme)>,
 
+    /// Runtime bytecode of the visited contracts, after the transaction.
+    pub codes: HashMap<Address, Bytes>,
+
     /// Metadata of each contract.
     pub metadata: HashMap<Address, Metadata>,
 
-    /// Map of source files. Note that each address will have a compilation artifact.
-    pub compilation_artifacts: HashMap<Address, CompilationArtifact>,
+    /// Compilation artifacts of the verified contracts, indexed by address and code hash.
+    pub compilation_artifacts: ArtifactDb,
+
+    /// How faithfully the recompiled sources of each verified contract reproduce its code.
+    pub verification: HashMap<Address, VerificationStatus>,
+
+    /// The implementation of each minimal proxy (EIP-1167) among the visited contracts.
+    pub clones: HashMap<Address, Address>,
+
+    /// Issues found while analyzing the transaction, which are reported to the user.
+    pub warnings: Vec<Warning>,
 
     // Compilation artifact from local file system
     // TODO: support local compilation artifact later
@@ -163,9 +280,25 @@ pub struct DebugBackend<DBRef> {
     // Etherscan client
     etherscan: Client,
 
+    // Token metadata files
+    token_cache_file: Option<PathBuf>,
+    token_override_file: Option<PathBuf>,
+
+    // Flagged addresses
+    flags: HashMap<Address, AddressFlag>,
+
+    // Channel of the progress events
+    events: Option<EventSender>,
+
+    // Granularity of the recorded snapshots
+    granularity: SnapshotGranularity,
+
+    // Compilers of the fetched sources
+    solc: SolcManager,
+
     // Transaction information
     // The base database
-    base_db: CacheDB<DBRef>,
+    base_db: CacheDB<ProfiledDb<DBRef>>,
     // EVM evnironment
     env: EnvWithHandlerCfg,
 }
@@ -183,19 +316,135 @@ where
     /// Analyze the transaction and return the debug artifact.
     pub async fn analyze(mut self) -> Result<DebugArtifact> {
         self.collect_compilation_artifacts().await?;
+        let analysis = span(Phase::Analyze);
         self.analyze_source_map()?;
+        drop(analysis);
+        self.emit_warnings(0);
+        let num_warnings = self.warnings.len();
+
+        emit(&self.events, EngineEvent::StageStarted { stage: Stage::Trace });
+        let (debug_arena, selfdestructs) = self.collect_debug_trace()?;
+        emit(&self.events, EngineEvent::StageStarted { stage: Stage::Decorate });
+        let analysis = span(Phase::Analyze);
+        let interfaces = self.detect_interfaces();
+        let tokens = self.collect_token_metadata(&interfaces);
+        let cfgs = self.build_cfgs();
+        drop(analysis);
+        self.emit_warnings(num_warnings);
+
+        // the base database is left untouched by the executions, i.e., holds the state before the
+        // transaction
+        let nonces = self
+            .addresses
+            .iter()
+            .filter_map(|address| Some((*address, self.base_db.basic_ref(*address).ok()??.nonce)))
+            .collect();
 
-        let debug_arena = self.collect_debug_trace()?;
+        let steps = debug_arena.iter().map(|node| node.steps.len()).sum();
+        // the state read by the execution is kept to record the coarse nodes again on demand
+        let (coarse_nodes, recording) = match self.granularity {
+            SnapshotGranularity::Opcode => (HashSet::new(), None),
+            granularity => (
+                (0..debug_arena.len()).collect(),
+                Some(Arc::new(Recording::new(&self.base_db, self.env.clone(), granularity))),
+            ),
+        };
+        emit(&self.events, EngineEvent::Finished { steps });
+
+        Ok(DebugArtifact {
+            debug_arena,
+            compilation_artifacts: self.compilation_artifacts,
+            interfaces,
+            tokens,
+            warnings: self.warnings,
+            flags: self.flags,
+            codes: self.codes,
+            verification: self.verification,
+            clones: self.clones,
+            coinbase: Some(self.env.block.coinbase),
+            sender: Some(self.env.tx.caller),
+            value: self.env.tx.value,
+            nonces,
+            selfdestructs,
+            coarse_nodes,
+            recording,
+            cfgs,
+        })
+    }
 
-        Ok(DebugArtifact { debug_arena, compilation_artifacts: self.compilation_artifacts })
+    /// Stream the warnings raised since the first `from` ones were streamed.
+    fn emit_warnings(&self, from: usize) {
+        for warning in &self.warnings[from..] {
+            emit(&self.events, EngineEvent::Warning { warning: warning.clone() });
+        }
     }
 
-    fn analyze_source_map(&mut self) -> Result<()> {
-        for (_, artifact) in &self.compilation_artifacts {
-            SourceMapAnalysis::analyze(artifact)?;
+    /// Collect the metadata of the touched tokens, from the override file, the cache, or the
+    /// chain (in this order). Newly fetched metadata is added to the cache.
+    fn collect_token_metadata(
+        &self,
+        interfaces: &HashMap<Address, Vec<InterfaceStandard>>,
+    ) -> HashMap<Address, TokenMetadata> {
+        let overrides = TokenFile::load(self.token_override_file.clone());
+        let mut cache = TokenFile::load(self.token_cache_file.clone());
+        let mut updated = false;
+
+        let mut tokens = HashMap::new();
+        for address in &self.addresses {
+            let is_erc20 = interfaces
+                .get(address)
+                .map_or(false, |standards| standards.contains(&InterfaceStandard::Erc20));
+
+            let metadata = if let Some(metadata) = overrides.tokens.get(address) {
+                metadata.clone()
+            } else if !is_erc20 {
+                continue;
+            } else if let Some(metadata) = cache.tokens.get(address) {
+                metadata.clone()
+            } else if let Some(metadata) = fetch_token_metadata(&self.base_db, &self.env, *address)
+            {
+                cache.tokens.insert(*address, metadata.clone());
+                updated = true;
+                metadata
+            } else {
+                continue;
+            };
+            tokens.insert(*address, metadata);
         }
 
-        Ok(())
+        if updated {
+            if let Err(e) = cache.save() {
+                warn!("failed to save the token metadata cache: {e}");
+            }
+        }
+        tokens
+    }
+
+    /// Detect the standard interfaces implemented by the touched contracts, against the state
+    /// prior to the transaction.
+    fn detect_interfaces(&self) -> HashMap<Address, Vec<InterfaceStandard>> {
+        self.addresses
+            .iter()
+            .map(|address| (*address, detect_interfaces(&self.base_db, &self.env, *address)))
+            .filter(|(_, standards)| !standards.is_empty())
+            .collect()
+    }
+
+    /// Builds the control-flow graph of every touched contract on all cores, rather than
+    /// disassembling its code each time a call to it is looked at.
+    fn build_cfgs(&self) -> HashMap<Address, Arc<ControlFlowGraph>> {
+        self.codes
+            .par_iter()
+            .filter(|(_, code)| !code.is_empty())
+            .map(|(address, code)| (*address, Arc::new(ControlFlowGraph::new(code))))
+            .collect()
+    }
+
+    fn analyze_source_map(&mut self) -> Result<()> {
+        // the artifacts are independent, and analyzed on all cores
+        self.compilation_artifacts
+            .par_values_mut()
+            .try_for_each(|artifact| SourceMapAnalysis::analyze(artifact).map(drop))
     }
 
     async fn collect_compilation_artifacts(&mut self) -> Result<()> {
@@ -205,35 +454,132 @@ where
         // The major reason is that, since the transaction may create/deploy new contracts, without
         // actually committing the transaction, we cannot know the deployed code of the new
         // contracts.
+        emit(&self.events, EngineEvent::StageStarted { stage: Stage::CollectContracts });
         let mut db = CacheDB::new(&self.base_db);
 
         // Step 1. collect addresses of contracts that are visited during the transaction,
         // as well as the creation codes of contracts that are deployed during the transaction
         let mut inspect = CollectInspector::new(&mut self.addresses, &mut self.creation_codes);
+        let execution = span(Phase::Execute);
         let mut evm = new_evm_with_inspector(&mut db, self.env.clone(), &mut inspect);
         evm.transact_commit().map_err(|err| eyre!("failed to transact: {}", err))?;
         drop(evm);
+        drop(execution);
+
+        // The runtime bytecode is kept for bytecode-level analyses, e.g., of unverified contracts
+        for addr in &self.addresses {
+            let Some(info) =
+                db.basic_ref(*addr).map_err(|e| eyre!("failed to load {addr}: {e}"))?
+            else {
+                continue;
+            };
+            let code = match info.code {
+                Some(code) => code,
+                None => db
+                    .code_by_hash_ref(info.code_hash)
+                    .map_err(|e| eyre!("failed to load the code of {addr}: {e}"))?,
+            };
+            if !code.is_empty() {
+                self.codes.insert(*addr, code.original_bytes());
+            }
+        }
+
+        self.clones = detect_clones(&self.codes);
 
         // Step 2. collect source code from etherscan
+        emit(&self.events, EngineEvent::StageStarted { stage: Stage::FetchSources });
+        let mut rate_limited = false;
+        let total = self.addresses.len();
+        let fetched = |index: usize, address: Address, verified: bool| EngineEvent::FetchProgress {
+            done: index + 1,
+            total,
+            address,
+            verified,
+        };
+        // The compilations linking each visited library, to fall back on when the library is
+        // not verified on its own. Each compilation is kept once, however many libraries it links
+        let mut linking: Vec<(Sources, CompilerOutput)> = Vec::new();
+        let mut linked: HashMap<Address, (String, usize)> = HashMap::new();
         let pb = init_progress!(self.addresses, "Compiling source code from etherscan");
         for (index, addr) in self.addresses.iter().enumerate() {
             println!("{:#?} {}", addr, self.creation_codes.contains_key(addr));
 
-            let mut meta =
-                match etherscan_rate_limit_guard!(self.etherscan.contract_source_code(*addr).await)
+            // Minimal proxies have no source code of their own, and calls to them are decoded with
+            // the artifact of their implementation, which is visited as well
+            if self.clones.contains_key(addr) {
+                update_progress!(pb, index);
+                emit(&self.events, fetched(index, *addr, false));
+                continue;
+            }
+
+            // Clones of an implementation compiled already share its artifact, which saves
+            // fetching and compiling the same sources again
+            if let Some(code_hash) = self.codes.get(addr).map(keccak256) {
+                if !self.compilation_artifacts.contains_key(addr) &&
+                    self.compilation_artifacts.link(*addr, code_hash)
                 {
-                    Ok(meta) => meta,
-                    Err(EtherscanError::ContractCodeNotVerified(_)) => {
-                        update_progress!(pb, index);
-                        continue;
+                    let status = self
+                        .compilation_artifacts
+                        .addresses_of(&code_hash)
+                        .iter()
+                        .find_map(|address| self.verification.get(address).copied());
+                    if let Some(status) = status {
+                        self.verification.insert(*addr, status);
                     }
-                    Err(e) => return Err(e.into()),
-                };
+                    update_progress!(pb, index);
+                    emit(&self.events, fetched(index, *addr, true));
+                    continue;
+                }
+            }
+
+            // The sources of a contract are resolved in this order of precedence:
+            //  1. a local artifact compiled to the deployed code, which is used as it is without
+            //     fetching anything;
+            //  2. a local artifact compiled to other code, which is still used, but is checked
+            //     against the explorer's sources;
+            //  3. the explorer's sources.
+            // Sourcify is not supported as a provider yet.
+            let local = self.compilation_artifacts.get(addr);
+            if local.is_some_and(|local| {
+                self.codes.get(addr).is_some_and(|code| keccak256(code) == local.code_hash)
+            }) {
+                update_progress!(pb, index);
+                emit(&self.events, fetched(index, *addr, true));
+                continue;
+            }
+
+            let explorer = span(Phase::Explorer);
+            let source_code = etherscan_rate_limit_guard!(
+                self.etherscan.contract_source_code(*addr).await,
+                limited = rate_limited
+            );
+            drop(explorer);
+            let mut meta = match source_code {
+                Ok(meta) => meta,
+                Err(EtherscanError::ContractCodeNotVerified(_)) => {
+                    self.warnings.push(
+                        Warning::new(
+                            WarningKind::UnverifiedContract,
+                            "no source code is available, only opcodes can be debugged",
+                        )
+                        .with_address(*addr),
+                    );
+                    update_progress!(pb, index);
+                    emit(&self.events, fetched(index, *addr, false));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
             eyre::ensure!(meta.items.len() == 1, "contract not found or ill-formed");
             let meta = meta.items.remove(0);
             if meta.is_vyper() {
                 // TODO: support Vyper later
+                self.warnings.push(
+                    Warning::new(WarningKind::UnsupportedSource, "Vyper is not supported yet")
+                        .with_address(*addr),
+                );
                 update_progress!(pb, index);
+                emit(&self.events, fetched(index, *addr, true));
                 continue;
             }
 
@@ -276,20 +622,29 @@ where
                 .into_iter()
                 .map(|(k, v)| (k.into(), Source::new(v.content)))
                 .collect();
-            let input = SolcInput::new(SolcLanguage::Solidity, sources, settings);
+            let mut input = SolcInput::new(SolcLanguage::Solidity, sources, settings);
 
             // prepare the compiler
             let version = meta.compiler_version()?;
-            let compiler = Solc::find_or_install(&version)?;
+            let compilation = span(Phase::Compile);
+            let compiler = self.solc.get(&version).await?;
 
             // compile the source code
-            let output = match compiler.compile_exact(&input) {
+            let mut output = match compiler.compile_exact(&input) {
                 Ok(compiler_output) => compiler_output,
                 Err(_) if version.major == 0 && version.minor == 4 => {
                     // check compiler version
                     // it is known that Solc 0.4.x does not support --standard-json
                     warn!("Solc 0.4.x does not support --standard-json, skipping");
+                    self.warnings.push(
+                        Warning::new(
+                            WarningKind::UnsupportedSource,
+                            format!("solc {version} does not support --standard-json"),
+                        )
+                        .with_address(*addr),
+                    );
                     update_progress!(pb, index);
+                    emit(&self.events, fetched(index, *addr, true));
                     continue;
                 }
                 Err(e) => {
@@ -297,24 +652,111 @@ where
                 }
             };
 
+            // The explorer sometimes reports the compiler settings wrongly, so that alternatives
+            // are tried until one reproduces the on-chain code
+            let onchain = deployed_bytecode.original_byte_slice();
+            let reported = verify_output(onchain, &output, contract_name);
+            let status;
+            (input, output, status) =
+                replicate_settings(&compiler, input, output, onchain, contract_name);
+            if reported == VerificationStatus::Mismatch && status != reported {
+                debug!("reproduced the code of {addr} with alternative compiler settings");
+            }
+            drop(compilation);
+            if status == VerificationStatus::Mismatch {
+                self.warnings.push(
+                    Warning::new(
+                        WarningKind::BytecodeMismatch,
+                        "the recompiled sources do not reproduce the on-chain code, the source \
+                         mapping may be wrong",
+                    )
+                    .with_address(*addr),
+                );
+            }
+            self.verification.insert(*addr, status);
+
             let artifact =
-                (contract_name, deployed_bytecode, &input.sources, output).as_artifact()?;
+                (contract_name, deployed_bytecode, &input.sources, &output).as_artifact()?;
 
-            self.compilation_artifacts.insert(*addr, artifact);
-            self.metadata.insert(*addr, meta);
+            let libraries: Vec<_> = linked_libraries(&input.settings.libraries)
+                .into_iter()
+                .filter(|(_, library)| {
+                    self.codes.contains_key(library) && !linked.contains_key(library)
+                })
+                .collect();
+            if !libraries.is_empty() {
+                for (name, library) in libraries {
+                    linked.insert(library, (name, linking.len()));
+                }
+                linking.push((input.sources, output));
+            }
+
+            // A local artifact takes precedence, along with its metadata, but a disagreement with
+            // the explorer likely means that one of them is attributed to the wrong contract
+            match self.compilation_artifacts.get(addr) {
+                Some(local) => {
+                    if let Some(reason) = local.disagreement(&artifact) {
+                        warn!("the local sources of {addr} disagree with the explorer: {reason}");
+                        self.warnings.push(
+                            Warning::new(
+                                WarningKind::SourceMismatch,
+                                format!("local sources disagree with the explorer: {reason}"),
+                            )
+                            .with_address(*addr),
+                        );
+                    }
+                }
+                None => {
+                    self.compilation_artifacts.insert(*addr, artifact);
+                    self.metadata.insert(*addr, meta);
+                }
+            }
 
             update_progress!(pb, index);
+            emit(&self.events, fetched(index, *addr, true));
+        }
+
+        // Step 3. attribute the code of the libraries without verified sources to the sources
+        // of a contract linking them, so that delegate calls into them can be stepped through
+        for (library, (name, index)) in linked {
+            if self.compilation_artifacts.contains_key(&library) {
+                continue;
+            }
+            let code = RevmBytecode::new_raw(self.codes[&library].clone());
+            let (sources, output) = &linking[index];
+            match (name.as_str(), code, sources, output).as_artifact() {
+                Ok(artifact) => {
+                    self.warnings.retain(|warning| {
+                        warning.kind != WarningKind::UnverifiedContract ||
+                            warning.address != Some(library)
+                    });
+                    self.compilation_artifacts.insert(library, artifact);
+                }
+                Err(e) => debug!("failed to attribute library {name} at {library}: {e}"),
+            }
+        }
+
+        if rate_limited {
+            self.warnings.push(Warning::new(
+                WarningKind::RateLimited,
+                "the explorer API rate limit was reached, consider setting an API key",
+            ));
         }
 
         Ok(())
     }
 
-    fn collect_debug_trace(&mut self) -> Result<Vec<DebugNodeFlat>> {
-        let mut inspector = DebugInspector::new();
+    /// Executes the transaction, returning its trace along with the `SELFDESTRUCT`s it made.
+    fn collect_debug_trace(
+        &mut self,
+    ) -> Result<(Vec<DebugNodeFlat>, HashMap<usize, (Address, U256)>)> {
+        let _execution = span(Phase::Execute);
+        let mut inspector = DebugInspector::new().with_granularity(self.granularity);
         let mut evm = new_evm_with_inspector(&mut self.base_db, self.env.clone(), &mut inspector);
         evm.transact().map_err(|err| eyre!("failed to transact: {}", err))?;
         drop(evm);
 
-        Ok(inspector.arena.arena.into_iter().map(|n| n.into_flat()).collect())
+        let debug_arena = inspector.arena.arena.into_iter().map(|n| n.into_flat()).collect();
+        Ok((debug_arena, inspector.selfdestructs))
     }
 }
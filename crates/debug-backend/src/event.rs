@@ -0,0 +1,106 @@
+//! Events streamed while the engine works, so that the interfaces built on top of it (e.g., GUIs
+//! talking to a server or DAP mode) can show live progress.
+//!
+//! Events are sent on the channel given to [`DebugBackendBuilder::events`], and are serialized
+//! as JSON objects tagged by `event`, one per line when streamed, e.g.:
+//!
+//! ```json
+//! {"event":"stage_started","stage":"fetch_sources"}
+//! {"event":"fetch_progress","done":1,"total":4,"address":"0x…","verified":true}
+//! {"event":"warning","warning":{"kind":"UnverifiedContract","address":"0x…","message":"…"}}
+//! {"event":"finished","steps":1234}
+//! ```
+//!
+//! `edb replay --machine-interface` streams them on stdout, along with the events of the machine
+//! interface.
+//!
+//! [`DebugBackendBuilder::events`]: crate::DebugBackendBuilder::events
+
+use std::sync::mpsc::Sender;
+
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::warning::Warning;
+
+/// The sending half of an event channel.
+pub type EventSender = Sender<EngineEvent>;
+
+/// A stage of the analysis of a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Executing the transaction to collect the touched contracts.
+    CollectContracts,
+    /// Fetching and compiling the source code of the touched contracts.
+    FetchSources,
+    /// Recording the execution, step by step.
+    Trace,
+    /// Detecting interfaces and token metadata.
+    Decorate,
+}
+
+/// An event of the engine.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EngineEvent {
+    /// `done` out of `total` transactions of the block, the debugged one being the last, were
+    /// replayed to rebuild the state it ran on. Sent by `edb replay` before each transaction.
+    ReplayProgress { done: usize, total: usize },
+    /// A stage of the analysis started.
+    StageStarted { stage: Stage },
+    /// The source code of `done` out of `total` touched contracts was fetched, the last one
+    /// being `address`, which is unverified if `verified` is false.
+    FetchProgress { done: usize, total: usize, address: Address, verified: bool },
+    /// An issue was found. Warnings are sent at the end of the stage which raised them.
+    Warning { warning: Warning },
+    /// The analysis finished and the trace has `steps` steps.
+    Finished { steps: usize },
+    /// A breakpoint was hit at the given step. Sent by the machine interface when `continue`
+    /// stops at a breakpoint.
+    BreakpointHit { step: usize },
+}
+
+/// Sends the event, if anyone is listening.
+pub(crate) fn emit(sender: &Option<EventSender>, event: EngineEvent) {
+    if let Some(sender) = sender {
+        // the listener going away must not interrupt the analysis
+        let _ = sender.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_schema() {
+        let event = EngineEvent::FetchProgress {
+            done: 1,
+            total: 4,
+            address: Address::with_last_byte(1),
+            verified: false,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"fetch_progress","done":1,"total":4,"address":"0x0000000000000000000000000000000000000001","verified":false}"#
+        );
+
+        let event = EngineEvent::StageStarted { stage: Stage::FetchSources };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"stage_started","stage":"fetch_sources"}"#
+        );
+
+        let event = EngineEvent::ReplayProgress { done: 2, total: 5 };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"replay_progress","done":2,"total":5}"#
+        );
+        let event = EngineEvent::BreakpointHit { step: 7 };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"breakpoint_hit","step":7}"#
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Export the allowance audit of an owner on a token as a plain-text report, i.e., every use of
+//! its allowances by spender, with the callers and the amounts involved.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use alloy_primitives::{Address, U256};
+
+use crate::{
+    analysis::allowance::{AllowanceAction, AllowanceUse},
+    artifact::debug::DebugArtifact,
+};
+
+/// Renders the uses of the allowances of the owner on the token: a summary by spender, followed
+/// by every use in the order of execution.
+pub fn allowance_report(
+    artifact: &DebugArtifact,
+    token: Address,
+    owner: Address,
+    uses: &[AllowanceUse],
+) -> String {
+    let format_amount = |amount: U256| match artifact.tokens.get(&token) {
+        _ if amount == U256::MAX => "unlimited".to_string(),
+        Some(metadata) => metadata.format_amount(amount),
+        None => amount.to_string(),
+    };
+
+    let mut report = String::new();
+    let (token_label, owner_label) =
+        (artifact.address_label(&token), artifact.address_label(&owner));
+    if uses.is_empty() {
+        writeln!(report, "The allowances of {owner_label} on {token_label} are never used.")
+            .unwrap();
+        return report;
+    }
+
+    // (approvals, transfers, amount transferred) by spender, leaving out the reverted uses
+    let mut spenders: BTreeMap<Address, (usize, usize, U256)> = BTreeMap::new();
+    for allowance_use in uses.iter().filter(|allowance_use| !allowance_use.reverted) {
+        let (approvals, transfers, spent) = spenders.entry(allowance_use.spender).or_default();
+        match allowance_use.action {
+            AllowanceAction::Approve(_) => *approvals += 1,
+            AllowanceAction::TransferFrom { .. } => {
+                *transfers += 1;
+                *spent = spent.saturating_add(allowance_use.amount.unwrap_or_default());
+            }
+            _ => {}
+        }
+    }
+
+    writeln!(report, "Allowances of {owner_label} on {token_label}").unwrap();
+    writeln!(report, "Summary").unwrap();
+    writeln!(report, "=======").unwrap();
+    for (spender, (approvals, transfers, spent)) in &spenders {
+        writeln!(
+            report,
+            "{}: {approvals} approval(s), {transfers} transferFrom(s) moving {}",
+            artifact.address_label(spender),
+            format_amount(*spent)
+        )
+        .unwrap();
+    }
+
+    writeln!(report).unwrap();
+    writeln!(report, "Uses").unwrap();
+    writeln!(report, "====").unwrap();
+    for allowance_use in uses {
+        write!(
+            report,
+            "step {}: {} by {} for spender {}",
+            allowance_use.step,
+            allowance_use.action,
+            artifact.address_label(&allowance_use.caller),
+            artifact.address_label(&allowance_use.spender)
+        )
+        .unwrap();
+        if let AllowanceAction::TransferFrom { to } = allowance_use.action {
+            write!(report, " to {}", artifact.address_label(&to)).unwrap();
+        }
+        if let Some(amount) = allowance_use.amount {
+            write!(report, ", amount {}", format_amount(amount)).unwrap();
+        }
+        if allowance_use.reverted {
+            report.push_str(" (reverted)");
+        }
+        writeln!(report).unwrap();
+    }
+
+    report
+}
@@ -0,0 +1,296 @@
+//! Reconstruction of the call frames from the flattened debug arena, on which the trace
+//! exporters are built.
+
+use std::ops::Range;
+
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::Function;
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::decode_revert_reason;
+use revm::interpreter::opcode;
+use revm_inspectors::tracing::types::CallKind;
+
+use crate::artifact::debug::{DebugArtifact, DebugStep};
+
+/// How a call frame ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameOutcome {
+    Stop,
+    Return,
+    SelfDestruct,
+    Revert,
+    /// Any other exceptional halt, e.g., running out of gas or an invalid opcode.
+    Halt,
+}
+
+impl FrameOutcome {
+    pub fn is_success(self) -> bool {
+        matches!(self, Self::Stop | Self::Return | Self::SelfDestruct)
+    }
+}
+
+/// A call frame, i.e., all the consecutive nodes of the arena with the same depth.
+#[derive(Clone, Debug)]
+pub struct CallFrame {
+    pub kind: CallKind,
+    /// The caller, i.e., the context of the parent frame, or `None` for the outermost frame.
+    pub caller: Option<Address>,
+    pub address: Address,
+    pub input: Bytes,
+    pub output: Bytes,
+    /// The value sent with the call, or the endowment of the created contract.
+    pub value: U256,
+    /// The gas passed in to the frame, which is unknown (and zero) for imported traces.
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub outcome: FrameOutcome,
+    /// The indices of the sub-calls in the frame list.
+    pub children: Vec<usize>,
+    /// The first node of the frame in the arena.
+    pub node: usize,
+    /// The steps of the frame, including the ones of its sub-calls, in the whole execution.
+    pub steps: Range<usize>,
+}
+
+impl CallFrame {
+    /// Returns whether the changes made by the frame were rolled back. A frame without any step,
+    /// e.g., a call to an account without code, cannot fail but for the checks of the call
+    /// itself.
+    pub fn is_reverted(&self) -> bool {
+        !self.steps.is_empty() && !self.outcome.is_success()
+    }
+
+    /// Returns the function of the ABI of the callee which is called, if known.
+    pub fn function<'a>(&self, artifact: &'a DebugArtifact) -> Option<&'a Function> {
+        if self.kind.is_any_create() || self.input.len() < 4 {
+            return None;
+        }
+        artifact
+            .abi(&self.address)?
+            .functions()
+            .find(|function| function.selector()[..] == self.input[..4])
+    }
+
+    /// Decodes the reason of the revert, if the frame reverted with an `Error(string)` or a
+    /// `Panic(uint256)`.
+    pub fn revert_reason(&self) -> Option<String> {
+        (self.outcome == FrameOutcome::Revert).then(|| decode_revert_reason(&self.output)).flatten()
+    }
+
+    /// Returns whether the frame merely forwards its call to an implementation, like proxies do,
+    /// i.e., delegate-calls it with the same input.
+    pub fn is_forwarded(&self, frames: &[CallFrame]) -> bool {
+        self.children
+            .iter()
+            .any(|child| frames[*child].kind.is_delegate() && frames[*child].input == self.input)
+    }
+
+    /// Returns the gas and the value the frame was given, and how it used them.
+    pub fn budget(&self, frames: &[CallFrame]) -> FrameBudget {
+        let children_gas = self.children.iter().map(|child| frames[*child].gas_used).sum::<u64>();
+        FrameBudget {
+            gas_limit: self.gas_limit,
+            self_gas: self.gas_used.saturating_sub(children_gas),
+            children_gas,
+            value: self.value,
+            returned: self.output.len(),
+        }
+    }
+
+    /// Decodes the arguments of the call, if the function is known.
+    pub fn decoded_input(&self, artifact: &DebugArtifact) -> Option<Vec<DynSolValue>> {
+        self.function(artifact)?.abi_decode_input(&self.input[4..], false).ok()
+    }
+}
+
+/// The gas and value budget of a call frame, which otherwise has to be worked out from the raw
+/// trace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameBudget {
+    /// The gas passed in to the frame.
+    pub gas_limit: u64,
+    /// The gas consumed by the frame alone, including the cost of making its sub-calls.
+    pub self_gas: u64,
+    /// The gas consumed by the sub-calls of the frame.
+    pub children_gas: u64,
+    /// The value sent with the call.
+    pub value: U256,
+    /// The size of the returned (or reverted) data, in bytes.
+    pub returned: usize,
+}
+
+impl FrameBudget {
+    /// Returns the gas consumed by the frame, including its sub-calls.
+    pub fn gas_used(&self) -> u64 {
+        self.self_gas + self.children_gas
+    }
+
+    /// Returns the gas left unused when the frame ended, if the gas passed in is known.
+    pub fn gas_left(&self) -> Option<u64> {
+        (self.gas_limit > 0).then(|| self.gas_limit.saturating_sub(self.gas_used()))
+    }
+}
+
+/// Returns the steps, in the whole execution, whose changes were rolled back, i.e., the steps of
+/// the reverted frames, including the ones of their sub-calls.
+pub fn reverted_steps(frames: &[CallFrame]) -> Vec<Range<usize>> {
+    frames.iter().filter(|frame| frame.is_reverted()).map(|frame| frame.steps.clone()).collect()
+}
+
+/// Reconstructs the call frames of the artifact, in the order they are entered. The outermost
+/// frame comes first.
+pub fn call_frames(artifact: &DebugArtifact) -> Vec<CallFrame> {
+    let mut frames: Vec<CallFrame> = Vec::new();
+    // indices of the open frames, from the outermost to the innermost
+    let mut open: Vec<usize> = Vec::new();
+    // the depth of each open frame
+    let mut depths: Vec<usize> = Vec::new();
+    let mut last_step: Option<&DebugStep> = None;
+    // the number of steps before the current node
+    let mut total = 0;
+
+    for (index, node) in artifact.debug_arena.iter().enumerate() {
+        while depths.last().is_some_and(|depth| *depth > node.depth) {
+            open.pop();
+            depths.pop();
+        }
+
+        if depths.last() != Some(&node.depth) {
+            // the instruction which made the call is the last step of the previous node
+            let value = last_step
+                .filter(|_| !open.is_empty())
+                .and_then(|step| {
+                    let n = match step.instruction {
+                        opcode::CALL | opcode::CALLCODE => 2,
+                        opcode::CREATE | opcode::CREATE2 => 0,
+                        _ => return None,
+                    };
+                    step.stack.len().checked_sub(n + 1).map(|i| step.stack[i])
+                })
+                // the outermost frame is sent the value of the transaction
+                .unwrap_or(if open.is_empty() { artifact.value } else { U256::ZERO });
+            let caller = open.last().map(|_| {
+                // the context of the caller is the one of the node right before this one
+                artifact.context_address(index - 1)
+            });
+            let frame = CallFrame {
+                kind: node.kind,
+                caller,
+                address: node.address,
+                input: node.steps.first().map(|step| step.calldata.clone()).unwrap_or_default(),
+                output: Bytes::new(),
+                value,
+                gas_limit: node.gas_limit,
+                gas_used: 0,
+                outcome: FrameOutcome::Halt,
+                children: Vec::new(),
+                node: index,
+                steps: total..total,
+            };
+            if let Some(parent) = open.last() {
+                frames[*parent].children.push(frames.len());
+            }
+            open.push(frames.len());
+            depths.push(node.depth);
+            frames.push(frame);
+        }
+
+        // the last node of a frame tells how it ended
+        if let Some(step) = node.steps.last() {
+            let frame = &mut frames[*open.last().expect("a frame is open")];
+            frame.gas_used = step.total_gas_used;
+            frame.outcome = match step.instruction {
+                opcode::STOP => FrameOutcome::Stop,
+                opcode::RETURN => FrameOutcome::Return,
+                opcode::SELFDESTRUCT => FrameOutcome::SelfDestruct,
+                opcode::REVERT => FrameOutcome::Revert,
+                _ => FrameOutcome::Halt,
+            };
+            frame.output = match step.instruction {
+                opcode::RETURN | opcode::REVERT => returned_data(step),
+                _ => Bytes::new(),
+            };
+        }
+        last_step = node.steps.last();
+        total += node.steps.len();
+        for frame in &open {
+            frames[*frame].steps.end = total;
+        }
+    }
+
+    frames
+}
+
+/// Returns the data returned by a `RETURN` or `REVERT` step, read from its memory.
+fn returned_data(step: &DebugStep) -> Bytes {
+    let top = |n: usize| step.stack.len().checked_sub(n + 1).map(|i| step.stack[i]);
+    let (Some(offset), Some(size)) = (top(0), top(1)) else { return Bytes::new() };
+    let (Ok(offset), Ok(size)) = (usize::try_from(offset), usize::try_from(size)) else {
+        return Bytes::new();
+    };
+    // memory is only expanded by the step itself, so that the bytes past it are zero
+    let mut data = vec![0u8; size];
+    if let Some(available) = step.memory.get(offset.min(step.memory.len())..) {
+        let n = available.len().min(size);
+        data[..n].copy_from_slice(&available[..n]);
+    }
+    data.into()
+}
+
+/// Formats a decoded value like `cast` does, e.g., addresses are checksummed and strings are
+/// quoted.
+pub fn format_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Address(address) => address.to_checksum(None),
+        DynSolValue::Bool(b) => b.to_string(),
+        DynSolValue::Int(v, _) => v.to_string(),
+        DynSolValue::Uint(v, _) => v.to_string(),
+        DynSolValue::FixedBytes(word, size) => {
+            format!("0x{}", alloy_primitives::hex::encode(&word[..*size]))
+        }
+        DynSolValue::Bytes(bytes) => format!("0x{}", alloy_primitives::hex::encode(bytes)),
+        DynSolValue::String(s) => format!("{s:?}"),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            format!("[{}]", values.iter().map(format_value).collect::<Vec<_>>().join(", "))
+        }
+        DynSolValue::Tuple(values) => {
+            format!("({})", values.iter().map(format_value).collect::<Vec<_>>().join(", "))
+        }
+        DynSolValue::Function(function) => function.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::artifact::debug::DebugNodeFlat;
+
+    use super::*;
+
+    #[test]
+    fn test_frame_budget() {
+        let step = |instruction, total_gas_used| DebugStep {
+            instruction,
+            total_gas_used,
+            ..Default::default()
+        };
+        let node = |byte, depth, gas_limit, steps| DebugNodeFlat {
+            gas_limit,
+            ..DebugNodeFlat::new(Address::with_last_byte(byte), CallKind::Call, depth, steps)
+        };
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                node(1, 0, 1000, vec![step(opcode::PUSH0, 0), step(opcode::CALL, 20)]),
+                node(2, 1, 500, vec![step(opcode::PUSH0, 0), step(opcode::STOP, 30)]),
+                node(1, 0, 1000, vec![step(opcode::PUSH0, 55), step(opcode::STOP, 60)]),
+            ],
+            ..Default::default()
+        };
+
+        let frames = call_frames(&artifact);
+        let budget = frames[0].budget(&frames);
+        assert_eq!((budget.gas_limit, budget.self_gas, budget.children_gas), (1000, 30, 30));
+        assert_eq!(budget.gas_left(), Some(940));
+        assert_eq!(frames[1].budget(&frames).gas_left(), Some(470));
+        assert_eq!(budget.returned, 0);
+    }
+}
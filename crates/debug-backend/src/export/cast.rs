@@ -0,0 +1,162 @@
+//! Export the call trace in the text format of `cast run --trace`.
+//!
+//! Calls to known contracts are decoded with their ABI, and the others are printed as raw
+//! calldata, as `cast` does. Events are not part of the debug artifact, so they are not printed.
+
+use std::{fmt::Write as _, io::Write};
+
+use alloy_primitives::hex;
+use eyre::Result;
+
+use super::calltree::{call_frames, format_value, CallFrame, FrameOutcome};
+use crate::{analysis::funds::BuilderPayment, artifact::debug::DebugArtifact};
+
+/// Render the call trace of the given artifact like `cast run --trace` does.
+pub fn cast_trace(artifact: &DebugArtifact) -> String {
+    let frames = call_frames(artifact);
+    let mut trace = String::from("Traces:\n");
+    if !frames.is_empty() {
+        render(artifact, &frames, 0, "  ", "  ", &mut trace);
+    }
+    trace
+}
+
+/// Write the call trace of the given artifact in the `cast run --trace` format.
+pub fn write_cast_trace(artifact: &DebugArtifact, mut writer: impl Write) -> Result<()> {
+    writer.write_all(cast_trace(artifact).as_bytes())?;
+    Ok(())
+}
+
+/// Render a frame, whose first line is prefixed with `first` and the others with `rest`.
+fn render(
+    artifact: &DebugArtifact,
+    frames: &[CallFrame],
+    index: usize,
+    first: &str,
+    rest: &str,
+    out: &mut String,
+) {
+    let frame = &frames[index];
+    let _ = writeln!(out, "{first}[{}] {}", frame.gas_used, call_line(artifact, frame));
+    for child in &frame.children {
+        render(artifact, frames, *child, &format!("{rest}├─ "), &format!("{rest}│   "), out);
+    }
+    let _ = writeln!(out, "{rest}└─ ← {}", result_line(artifact, frame));
+}
+
+fn call_line(artifact: &DebugArtifact, frame: &CallFrame) -> String {
+    let label = artifact.contract_name(&frame.address);
+    if frame.kind.is_any_create() {
+        return format!("→ new {}@{}", label.unwrap_or("<unknown>"), frame.address);
+    }
+
+    let callee = label.map(str::to_string).unwrap_or_else(|| frame.address.to_string());
+    let call = match (frame.function(artifact), frame.decoded_input(artifact)) {
+        (Some(function), Some(args)) => {
+            let args = args.iter().map(format_value).collect::<Vec<_>>().join(", ");
+            format!("{}({args})", function.name)
+        }
+        _ if frame.input.len() >= 4 => {
+            format!(
+                "{}({})",
+                hex::encode_prefixed(&frame.input[..4]),
+                hex::encode_prefixed(&frame.input[4..])
+            )
+        }
+        _ if frame.input.is_empty() => "fallback()".to_string(),
+        _ => format!("fallback({})", hex::encode_prefixed(&frame.input)),
+    };
+
+    let mut line = format!("{callee}::{call}");
+    if !frame.value.is_zero() {
+        let _ = write!(line, "{{value: {}}}", frame.value);
+    }
+    let payment = match artifact.coinbase {
+        _ if frame.value.is_zero() => None,
+        Some(coinbase) if frame.address == coinbase => Some(BuilderPayment::Coinbase),
+        Some(coinbase) if frame.caller.or(artifact.sender) == Some(coinbase) => {
+            Some(BuilderPayment::Proposer)
+        }
+        _ => None,
+    };
+    if let Some(payment) = payment {
+        let _ = write!(line, " [{payment}]");
+    }
+    if frame.kind.is_static_call() {
+        line.push_str(" [staticcall]");
+    } else if frame.kind.is_delegate() {
+        line.push_str(" [delegatecall]");
+    }
+    line
+}
+
+fn result_line(artifact: &DebugArtifact, frame: &CallFrame) -> String {
+    let status = match frame.outcome {
+        FrameOutcome::Stop => "[Stop]",
+        FrameOutcome::Return => "[Return]",
+        FrameOutcome::SelfDestruct => "[SelfDestruct]",
+        FrameOutcome::Revert => "[Revert]",
+        FrameOutcome::Halt => "[Halt]",
+    };
+    if frame.output.is_empty() {
+        return status.to_string();
+    }
+
+    let output = match frame.outcome {
+        FrameOutcome::Return if frame.kind.is_any_create() => {
+            format!("{} bytes of code", frame.output.len())
+        }
+        FrameOutcome::Return => frame
+            .function(artifact)
+            .and_then(|function| function.abi_decode_output(&frame.output, false).ok())
+            .map(|values| values.iter().map(format_value).collect::<Vec<_>>().join(", "))
+            .unwrap_or_else(|| hex::encode_prefixed(&frame.output)),
+        _ => frame.revert_reason().unwrap_or_else(|| hex::encode_prefixed(&frame.output)),
+    };
+    format!("{status} {output}")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+    use revm::interpreter::opcode;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    fn node(byte: u8, kind: CallKind, depth: usize, instruction: u8, gas: u64) -> DebugNodeFlat {
+        let step = DebugStep {
+            instruction,
+            stack: vec![U256::ZERO; 7],
+            calldata: vec![0x12, 0x34, 0x56, 0x78, 0x9a].into(),
+            total_gas_used: gas,
+            ..Default::default()
+        };
+        DebugNodeFlat::new(Address::with_last_byte(byte), kind, depth, vec![step])
+    }
+
+    #[test]
+    fn test_cast_trace() {
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                node(1, CallKind::Call, 0, opcode::STATICCALL, 10),
+                node(2, CallKind::StaticCall, 1, opcode::REVERT, 5),
+                node(1, CallKind::Call, 0, opcode::STOP, 30),
+            ],
+            ..Default::default()
+        };
+
+        let a = Address::with_last_byte(1);
+        let b = Address::with_last_byte(2);
+        let expected = format!(
+            "Traces:
+  [30] {a}::0x12345678(0x9a)
+    ├─ [5] {b}::0x12345678(0x9a) [staticcall]
+    │   └─ ← [Revert]
+    └─ ← [Stop]
+"
+        );
+        assert_eq!(cast_trace(&artifact), expected);
+    }
+}
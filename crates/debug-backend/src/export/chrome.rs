@@ -0,0 +1,104 @@
+//! Export the call timeline in the Chrome `trace_event` format.
+//!
+//! The output can be loaded into `chrome://tracing` or <https://ui.perfetto.dev>. Each call frame
+//! becomes a span, and the timeline is measured in gas rather than in time: one microsecond in
+//! the viewer corresponds to one unit of gas.
+
+use std::io::Write;
+
+use eyre::Result;
+use serde_json::{json, Value};
+
+use crate::artifact::debug::DebugArtifact;
+
+/// Build the Chrome `trace_event` JSON object of the given artifact.
+pub fn chrome_trace(artifact: &DebugArtifact) -> Value {
+    let mut events = Vec::new();
+    // Depths of the currently open frames, from the outermost to the innermost.
+    let mut open: Vec<usize> = Vec::new();
+    let mut clock = 0u64;
+
+    let event = |ph: &str, name: String, ts: u64, args: Value| json!({ "name": name, "cat": "call", "ph": ph, "ts": ts, "pid": 1, "tid": 1, "args": args });
+
+    for (index, node) in artifact.debug_arena.iter().enumerate() {
+        // Returning to a shallower depth closes all the frames in between.
+        while open.last().is_some_and(|depth| *depth > node.depth) {
+            open.pop();
+            events.push(event("E", String::new(), clock, Value::Null));
+        }
+
+        // A node at the same depth as the innermost open frame continues that frame.
+        if open.last() != Some(&node.depth) {
+            open.push(node.depth);
+            let name = format!("{} {}", node.kind, artifact.address_label(&node.address));
+            let args = json!({
+                "address": node.address,
+                "kind": node.kind.to_string(),
+                "depth": node.depth,
+                "node": index,
+            });
+            events.push(event("B", name, clock, args));
+        }
+
+        clock += node.step_gas_costs().sum::<u64>();
+    }
+    events.extend(open.iter().map(|_| event("E", String::new(), clock, Value::Null)));
+
+    json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ns",
+        "otherData": { "unit": "gas" },
+    })
+}
+
+/// Write the Chrome `trace_event` JSON of the given artifact.
+pub fn write_chrome_trace(artifact: &DebugArtifact, writer: impl Write) -> Result<()> {
+    serde_json::to_writer(writer, &chrome_trace(artifact))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    fn steps(gas: &[u64]) -> Vec<DebugStep> {
+        gas.iter().map(|gas| DebugStep { total_gas_used: *gas, ..Default::default() }).collect()
+    }
+
+    #[test]
+    fn test_chrome_trace() {
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        // the gas used by a step is counted in its own frame, i.e., from the gas limit of the
+        // frame, so that it starts over in the sub-call
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                // the last step is the call
+                DebugNodeFlat::new(a, CallKind::Call, 0, steps(&[0, 3, 5])),
+                DebugNodeFlat::new(b, CallKind::StaticCall, 1, steps(&[0, 2, 7])),
+                // the caller resumes after the sub-call, whose cost includes the gas it forwarded
+                DebugNodeFlat::new(a, CallKind::Call, 0, steps(&[120, 122])),
+            ],
+            ..Default::default()
+        };
+
+        let trace = chrome_trace(&artifact);
+        let events: Vec<_> = trace["traceEvents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| (event["ph"].as_str().unwrap(), event["ts"].as_u64().unwrap()))
+            .collect();
+        // one span per frame, the clock advancing by the gas used by each step within its node:
+        // the cost of the last step of a node is unknown, so that neither the call nor the
+        // return of the sub-call advance the clock
+        assert_eq!(events, vec![("B", 0), ("B", 5), ("E", 12), ("E", 14)]);
+        // the sub-call spans the gas used by its own steps
+        assert_eq!(trace["traceEvents"][1]["args"]["depth"], 1);
+        assert_eq!(trace["traceEvents"][1]["args"]["node"], 1);
+        assert_eq!((events[1].1, events[2].1), (5, 5 + 7));
+    }
+}
@@ -0,0 +1,62 @@
+//! Export the reads of the environment performed by the transaction as a plain-text report, so
+//! that users understand which external factors could change its outcome.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use alloy_primitives::Address;
+
+use crate::{
+    analysis::dependency::{external_dependencies, DependencyKind},
+    artifact::debug::DebugArtifact,
+};
+
+/// Renders the external dependencies of the given artifact: a summary by kind, followed by every
+/// read in the order of execution.
+pub fn external_dependency_report(artifact: &DebugArtifact) -> String {
+    let dependencies = external_dependencies(artifact);
+    let mut report = String::new();
+    if dependencies.is_empty() {
+        writeln!(report, "The transaction read nothing from its environment.").unwrap();
+        return report;
+    }
+
+    let mut readers: BTreeMap<DependencyKind, (usize, BTreeSet<Address>)> = BTreeMap::new();
+    for dependency in &dependencies {
+        let (count, accounts) = readers.entry(dependency.kind).or_default();
+        *count += 1;
+        accounts.insert(dependency.reader);
+    }
+
+    writeln!(report, "Summary").unwrap();
+    writeln!(report, "=======").unwrap();
+    for (kind, (count, accounts)) in &readers {
+        let accounts: Vec<_> = accounts.iter().map(|a| artifact.address_label(a)).collect();
+        writeln!(report, "{kind}: {count} read(s) by {}", accounts.join(", ")).unwrap();
+    }
+
+    writeln!(report).unwrap();
+    writeln!(report, "Reads").unwrap();
+    writeln!(report, "=====").unwrap();
+    for dependency in &dependencies {
+        write!(
+            report,
+            "step {}: {} reads {}",
+            dependency.step,
+            artifact.address_label(&dependency.reader),
+            dependency.kind
+        )
+        .unwrap();
+        if let Some(target) = &dependency.target {
+            write!(report, " of {}", artifact.address_label(target)).unwrap();
+        }
+        if let Some(value) = &dependency.value {
+            write!(report, " = {value:#x}").unwrap();
+        }
+        writeln!(report).unwrap();
+    }
+
+    report
+}
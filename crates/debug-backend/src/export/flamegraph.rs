@@ -0,0 +1,331 @@
+//! Export the gas consumption of the transaction as a flamegraph.
+//!
+//! Each call frame is a box whose width is proportional to the gas it consumed, including the
+//! gas consumed by its sub-calls. Internal (Solidity-level) function calls are boxes of their own
+//! within the call frame, entered and left at the jumps the source maps mark as going into and
+//! out of a function, so that only contracts with sources are split any further.
+
+use std::{collections::BTreeMap, fmt::Write as _, io::Write};
+
+use eyre::Result;
+use foundry_compilers::artifacts::sourcemap::Jump;
+use revm::interpreter::opcode::JUMP;
+
+use crate::artifact::debug::{DebugArtifact, DebugNodeFlat};
+
+const IMAGE_WIDTH: f64 = 1200.0;
+const FRAME_HEIGHT: f64 = 16.0;
+const FONT_SIZE: f64 = 12.0;
+const FONT_WIDTH: f64 = 0.59;
+const PADDING: f64 = 10.0;
+const TITLE_HEIGHT: f64 = 32.0;
+
+/// Collapse the call frames of the given artifact, and the internal function calls within them,
+/// into folded stacks, i.e., `a;b;c` paths with the gas consumed by the innermost frame alone.
+///
+/// Stacks are returned in execution order and identical stacks are merged.
+pub fn folded_stacks(artifact: &DebugArtifact) -> Vec<(String, u64)> {
+    fold(artifact, |node, step| internal_jump(artifact, node, step))
+}
+
+/// A jump into or out of an internal function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum InternalJump {
+    /// Enters the function of the given name.
+    In(String),
+    Out,
+}
+
+/// Returns the internal function call the given step of the node makes or returns from, if any.
+fn internal_jump(
+    artifact: &DebugArtifact,
+    node: &DebugNodeFlat,
+    step: usize,
+) -> Option<InternalJump> {
+    if node.steps[step].instruction != JUMP {
+        return None;
+    }
+    let compilation = artifact.compilation_artifacts.get(&node.address)?;
+    let is_create = node.kind.is_any_create();
+    let (element, _) = compilation.source_element(node.steps[step].pc, is_create)?;
+    match element.jump() {
+        Jump::Out => Some(InternalJump::Out),
+        Jump::In => {
+            // the function is named after the definition its first instruction is mapped to
+            let target = node.steps.get(step + 1)?;
+            let (element, source) = compilation.source_element(target.pc, is_create)?;
+            let start = element.offset() as usize;
+            let name = source
+                .code
+                .get(start..start + element.length() as usize)
+                .and_then(|code| code.strip_prefix("function"))
+                .map(|code| {
+                    let code = code.trim_start();
+                    let end = code
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                        .unwrap_or(code.len());
+                    code[..end].to_string()
+                })
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("internal@{}", source.line_of(start)));
+            Some(InternalJump::In(name))
+        }
+        Jump::Regular => None,
+    }
+}
+
+/// Collapse the call frames into folded stacks, splitting them on the internal jumps of each
+/// step.
+fn fold(
+    artifact: &DebugArtifact,
+    jumps: impl Fn(&DebugNodeFlat, usize) -> Option<InternalJump>,
+) -> Vec<(String, u64)> {
+    let mut folded: Vec<(String, u64)> = Vec::new();
+    let mut index: BTreeMap<String, usize> = BTreeMap::new();
+    // (depth, name, internal functions entered) of the currently open frames
+    let mut open: Vec<(usize, String, Vec<String>)> = Vec::new();
+
+    let mut add = |open: &[(usize, String, Vec<String>)], gas: u64| {
+        if gas == 0 {
+            return;
+        }
+        let stack = open
+            .iter()
+            .flat_map(|(_, name, internal)| std::iter::once(name).chain(internal))
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(";");
+        match index.get(&stack) {
+            Some(i) => folded[*i].1 += gas,
+            None => {
+                index.insert(stack.clone(), folded.len());
+                folded.push((stack, gas));
+            }
+        }
+    };
+
+    for node in &artifact.debug_arena {
+        while open.last().is_some_and(|(depth, ..)| *depth > node.depth) {
+            open.pop();
+        }
+        if open.last().map(|(depth, ..)| *depth) != Some(node.depth) {
+            let name = artifact
+                .contract_name(&node.address)
+                .map(|name| format!("{name} ({})", node.kind))
+                .unwrap_or_else(|| format!("{} ({})", node.address, node.kind));
+            open.push((node.depth, name, Vec::new()));
+        }
+
+        // the gas is added up until the internal functions entered change
+        let mut gas = 0;
+        for (step, cost) in node.step_gas_costs().enumerate() {
+            gas += cost;
+            let Some(jump) = jumps(node, step) else { continue };
+            add(&open, std::mem::take(&mut gas));
+            let internal = &mut open.last_mut().expect("the frame of the node is open").2;
+            match jump {
+                InternalJump::In(name) => internal.push(name),
+                InternalJump::Out => {
+                    internal.pop();
+                }
+            }
+        }
+        add(&open, gas);
+    }
+
+    folded
+}
+
+#[derive(Debug, Default)]
+struct Frame {
+    total: u64,
+    children: Vec<(String, Frame)>,
+}
+
+impl Frame {
+    fn insert(&mut self, path: &[&str], gas: u64) {
+        self.total += gas;
+        if let Some((name, rest)) = path.split_first() {
+            let child = match self.children.iter().position(|(n, _)| n == name) {
+                Some(i) => &mut self.children[i].1,
+                None => {
+                    self.children.push((name.to_string(), Self::default()));
+                    &mut self.children.last_mut().unwrap().1
+                }
+            };
+            child.insert(rest, gas);
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.children.iter().map(|(_, c)| c.depth() + 1).max().unwrap_or(0)
+    }
+}
+
+/// Render the flamegraph of the given artifact as a standalone SVG image.
+pub fn flamegraph_svg(artifact: &DebugArtifact, title: &str) -> String {
+    let mut root = Frame::default();
+    for (stack, gas) in folded_stacks(artifact) {
+        root.insert(&stack.split(';').collect::<Vec<_>>(), gas);
+    }
+
+    let depth = root.depth();
+    let height = TITLE_HEIGHT + FRAME_HEIGHT * depth as f64 + PADDING * 2.0;
+    let scale =
+        if root.total == 0 { 0.0 } else { (IMAGE_WIDTH - PADDING * 2.0) / root.total as f64 };
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<?xml version="1.0" standalone="no"?>
+<svg version="1.1" width="{IMAGE_WIDTH}" height="{height}" xmlns="http://www.w3.org/2000/svg">
+<rect x="0" y="0" width="100%" height="100%" fill="#f8f8f8"/>
+<text x="{}" y="{}" font-size="16" font-family="Verdana" text-anchor="middle">{} ({} gas)</text>"#,
+        IMAGE_WIDTH / 2.0,
+        TITLE_HEIGHT / 2.0 + 4.0,
+        escape(title),
+        root.total,
+    );
+
+    fn draw(
+        svg: &mut String,
+        frame: &Frame,
+        x: f64,
+        level: usize,
+        total: u64,
+        height: f64,
+        scale: f64,
+    ) {
+        let mut x = x;
+        for (name, child) in &frame.children {
+            let width = child.total as f64 * scale;
+            // frames grow upwards from the bottom of the image
+            let y = height - PADDING - FRAME_HEIGHT * (level + 1) as f64;
+            let percent = child.total as f64 * 100.0 / total as f64;
+            let max_chars = (width / (FONT_SIZE * FONT_WIDTH)) as usize;
+            let label = if max_chars < 3 {
+                String::new()
+            } else if name.chars().count() > max_chars {
+                format!("{}..", name.chars().take(max_chars - 2).collect::<String>())
+            } else {
+                name.clone()
+            };
+            let _ = writeln!(
+                svg,
+                r#"<g><title>{} ({} gas, {percent:.2}%)</title><rect x="{x:.2}" y="{y:.2}" width="{width:.2}" height="{}" fill="{}" rx="2" ry="2"/><text x="{:.2}" y="{:.2}" font-size="{FONT_SIZE}" font-family="Verdana">{}</text></g>"#,
+                escape(name),
+                child.total,
+                FRAME_HEIGHT - 1.0,
+                color(name),
+                x + 3.0,
+                y + FRAME_HEIGHT - 4.0,
+                escape(&label),
+            );
+            draw(svg, child, x, level + 1, total, height, scale);
+            x += width;
+        }
+    }
+    if root.total > 0 {
+        draw(&mut svg, &root, PADDING, 0, root.total, height, scale);
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Write the flamegraph of the given artifact as an SVG image.
+pub fn write_flamegraph(
+    artifact: &DebugArtifact,
+    title: &str,
+    mut writer: impl Write,
+) -> Result<()> {
+    writer.write_all(flamegraph_svg(artifact, title).as_bytes())?;
+    Ok(())
+}
+
+/// Pick a warm color for the frame, stable across runs.
+fn color(name: &str) -> String {
+    let hash = name.bytes().fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32));
+    let r = 205 + (hash % 50);
+    let g = (hash / 50) % 230;
+    let b = (hash / 50 / 230) % 55;
+    format!("rgb({r},{g},{b})")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    fn node(byte: u8, depth: usize, gas: &[u64]) -> DebugNodeFlat {
+        let steps = gas
+            .iter()
+            .map(|total_gas_used| DebugStep {
+                total_gas_used: *total_gas_used,
+                ..Default::default()
+            })
+            .collect();
+        DebugNodeFlat::new(Address::with_last_byte(byte), CallKind::Call, depth, steps)
+    }
+
+    #[test]
+    fn test_folded_stacks() {
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                node(1, 0, &[0, 3, 10]),
+                node(2, 1, &[0, 5]),
+                node(1, 0, &[20, 22]),
+                node(2, 1, &[0, 1]),
+                node(1, 0, &[30, 33]),
+            ],
+            ..Default::default()
+        };
+
+        let a = format!("{} (CALL)", Address::with_last_byte(1));
+        let b = format!("{} (CALL)", Address::with_last_byte(2));
+        assert_eq!(folded_stacks(&artifact), vec![(a.clone(), 15), (format!("{a};{b}"), 6)]);
+
+        let svg = flamegraph_svg(&artifact, "test");
+        assert!(svg.contains("21 gas"));
+    }
+
+    #[test]
+    fn test_internal_functions() {
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                node(1, 0, &[0, 1, 4, 8, 9, 20]),
+                node(2, 1, &[0, 5]),
+                node(1, 0, &[20, 22, 30]),
+            ],
+            ..Default::default()
+        };
+
+        // the first node enters `f` at step 1 and leaves it at step 3, and the last one enters
+        // `g` at step 0 without leaving it. The cost of a jump is paid by the frame it jumps from.
+        let folded = fold(&artifact, |node, step| match (node.steps.len(), step) {
+            (6, 1) => Some(InternalJump::In("f".to_string())),
+            (6, 3) => Some(InternalJump::Out),
+            (3, 0) => Some(InternalJump::In("g".to_string())),
+            _ => None,
+        });
+
+        let a = format!("{} (CALL)", Address::with_last_byte(1));
+        let b = format!("{} (CALL)", Address::with_last_byte(2));
+        assert_eq!(
+            folded,
+            vec![
+                (a.clone(), 1 + 3 + 11 + 2),
+                (format!("{a};f"), 4 + 1),
+                (format!("{a};{b}"), 5),
+                (format!("{a};g"), 8),
+            ]
+        );
+    }
+}
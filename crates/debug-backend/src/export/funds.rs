@@ -0,0 +1,149 @@
+//! Export the funds flow of the transaction as a plain-text report, optionally annotated with
+//! approximate USD values.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    io::Write,
+};
+
+use alloy_primitives::U256;
+use eyre::Result;
+
+use crate::{
+    analysis::{
+        funds::{Asset, BuilderPayment, FundsFlow},
+        token::TokenMetadata,
+    },
+    artifact::debug::DebugArtifact,
+};
+
+/// Returns the metadata of the asset, if it is known.
+fn metadata(artifact: &DebugArtifact, asset: &Asset) -> Option<TokenMetadata> {
+    match asset {
+        Asset::Ether => Some(TokenMetadata { symbol: "ETH".to_string(), decimals: 18 }),
+        Asset::Token(token) => artifact.tokens.get(token).cloned(),
+    }
+}
+
+/// Formats an amount of the asset, with its USD value if the price is known.
+fn format_amount(
+    artifact: &DebugArtifact,
+    prices: &HashMap<Asset, f64>,
+    asset: &Asset,
+    amount: U256,
+) -> String {
+    let Some(metadata) = metadata(artifact, asset) else {
+        return format!("{amount} of token {asset}");
+    };
+
+    let mut formatted = metadata.format_amount(amount);
+    if let Some(price) = prices.get(asset) {
+        let amount: f64 = amount.to_string().parse().unwrap_or_default();
+        let value = amount / 10f64.powi(metadata.decimals as i32) * price;
+        write!(formatted, " (≈ ${value:.2})").unwrap();
+    }
+    formatted
+}
+
+/// Builds the funds flow report of the given artifact.
+pub fn funds_flow_report(artifact: &DebugArtifact, prices: &HashMap<Asset, f64>) -> String {
+    let flow = FundsFlow::new(artifact);
+    let mut report = String::new();
+
+    writeln!(report, "Transfers").unwrap();
+    writeln!(report, "=========").unwrap();
+    if flow.transfers.is_empty() {
+        writeln!(report, "(none)").unwrap();
+    }
+    for transfer in &flow.transfers {
+        writeln!(
+            report,
+            "[step {}] {} -> {}: {}",
+            transfer.step,
+            artifact.address_label(&transfer.from),
+            artifact.address_label(&transfer.to),
+            format_amount(artifact, prices, &transfer.asset, transfer.amount),
+        )
+        .unwrap();
+    }
+
+    let payments = artifact
+        .coinbase
+        .map(|coinbase| flow.builder_payments(coinbase).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if !payments.is_empty() {
+        writeln!(report).unwrap();
+        writeln!(report, "Builder payments").unwrap();
+        writeln!(report, "================").unwrap();
+        let mut totals: BTreeMap<Asset, U256> = BTreeMap::new();
+        for (payment, transfer) in &payments {
+            let paid = match payment {
+                BuilderPayment::Coinbase => "the block beneficiary".to_string(),
+                BuilderPayment::Proposer => {
+                    format!("the proposer {}", artifact.address_label(&transfer.to))
+                }
+            };
+            writeln!(
+                report,
+                "[step {}] {} paid {paid} {}",
+                transfer.step,
+                artifact.address_label(&transfer.from),
+                format_amount(artifact, prices, &transfer.asset, transfer.amount),
+            )
+            .unwrap();
+            if *payment == BuilderPayment::Coinbase {
+                let total = totals.entry(transfer.asset).or_default();
+                *total = total.saturating_add(transfer.amount);
+            }
+        }
+        for (asset, total) in totals {
+            writeln!(report, "Total: {}", format_amount(artifact, prices, &asset, total)).unwrap();
+        }
+    }
+
+    writeln!(report).unwrap();
+    writeln!(report, "Net balance changes").unwrap();
+    writeln!(report, "===================").unwrap();
+    for ((account, asset), (incoming, outgoing)) in flow.net_changes() {
+        let change = if incoming >= outgoing {
+            format!("+{}", format_amount(artifact, prices, &asset, incoming - outgoing))
+        } else {
+            format!("-{}", format_amount(artifact, prices, &asset, outgoing - incoming))
+        };
+        writeln!(report, "{}: {change}", artifact.address_label(&account)).unwrap();
+    }
+
+    // Funds sent to or received from flagged addresses are usually what the report is read for
+    let flagged: BTreeMap<_, _> = flow
+        .transfers
+        .iter()
+        .flat_map(|transfer| [transfer.from, transfer.to])
+        .filter_map(|address| Some((address, artifact.flags.get(&address)?)))
+        .collect();
+    if !flagged.is_empty() {
+        writeln!(report).unwrap();
+        writeln!(report, "Flagged addresses").unwrap();
+        writeln!(report, "=================").unwrap();
+        for (address, flag) in flagged {
+            write!(report, "{address}: {flag}").unwrap();
+            if let Some(source) = &flag.source {
+                write!(report, " (source: {source})").unwrap();
+            }
+            writeln!(report).unwrap();
+        }
+    }
+
+    report
+}
+
+/// Writes the funds flow report of the given artifact.
+pub fn write_funds_flow(
+    artifact: &DebugArtifact,
+    prices: &HashMap<Asset, f64>,
+    mut writer: impl Write,
+) -> Result<()> {
+    writer.write_all(funds_flow_report(artifact, prices).as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
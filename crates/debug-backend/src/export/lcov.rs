@@ -0,0 +1,181 @@
+//! Export the source lines and branches exercised by the transaction as an lcov report.
+//!
+//! Coverage is reported per source file (merging all contracts compiled from the same file),
+//! so that the report can be opened by the usual coverage tooling (e.g. `genhtml` or editor
+//! extensions) to see which code paths the transaction actually exercised.
+
+use std::{collections::BTreeMap, fmt::Write as _, io::Write, path::PathBuf};
+
+use eyre::Result;
+
+use crate::artifact::debug::DebugArtifact;
+
+#[derive(Debug, Default)]
+struct FileCoverage {
+    /// Hit count of each line.
+    lines: BTreeMap<usize, u64>,
+    /// Branches, keyed by (line, pc of the JUMPI), with the (taken, not taken) counts.
+    branches: BTreeMap<(usize, usize), [u64; 2]>,
+}
+
+fn collect(artifact: &DebugArtifact) -> BTreeMap<PathBuf, FileCoverage> {
+    let mut files: BTreeMap<PathBuf, FileCoverage> = BTreeMap::new();
+
+    // All lines which are covered by any instruction, so that lines which are never reached
+    // show up with zero hits.
+    for compilation in artifact.compilation_artifacts.values() {
+        for element in
+            compilation.runtime_source_map.values().chain(compilation.creation_source_map.values())
+        {
+            let Some(file) = element.index().and_then(|index| compilation.sources.get(&index))
+            else {
+                continue;
+            };
+            let line = file.line_of(element.offset() as usize);
+            files.entry(file.path.clone()).or_default().lines.entry(line).or_default();
+        }
+    }
+
+    for node in &artifact.debug_arena {
+        let Some(compilation) = artifact.compilation_artifacts.get(&node.address) else {
+            continue;
+        };
+        let is_create = node.kind.is_any_create();
+
+        let mut last = None;
+        for step in &node.steps {
+            let Some((element, file)) = compilation.source_element(step.pc, is_create) else {
+                last = None;
+                continue;
+            };
+            let line = file.line_of(element.offset() as usize);
+            let coverage = files.entry(file.path.clone()).or_default();
+
+            // consecutive instructions of the same line count as a single hit
+            if last != Some((element.index(), line)) {
+                *coverage.lines.entry(line).or_default() += 1;
+                last = Some((element.index(), line));
+            }
+
+            if let Some(taken) = step.branch_taken() {
+                let counts = coverage.branches.entry((line, step.pc)).or_default();
+                counts[if taken { 0 } else { 1 }] += 1;
+            }
+        }
+    }
+
+    files
+}
+
+/// Build the lcov report of the given artifact.
+pub fn lcov(artifact: &DebugArtifact) -> String {
+    let mut out = String::new();
+    for (path, coverage) in collect(artifact) {
+        let _ = writeln!(out, "TN:");
+        let _ = writeln!(out, "SF:{}", path.display());
+
+        for (block, ((line, _), [taken, not_taken])) in coverage.branches.iter().enumerate() {
+            let _ = writeln!(out, "BRDA:{line},{block},0,{taken}");
+            let _ = writeln!(out, "BRDA:{line},{block},1,{not_taken}");
+        }
+        let branches_hit = coverage.branches.values().flatten().filter(|hits| **hits > 0).count();
+        let _ = writeln!(out, "BRF:{}", coverage.branches.len() * 2);
+        let _ = writeln!(out, "BRH:{branches_hit}");
+
+        for (line, hits) in &coverage.lines {
+            let _ = writeln!(out, "DA:{line},{hits}");
+        }
+        let lines_hit = coverage.lines.values().filter(|hits| **hits > 0).count();
+        let _ = writeln!(out, "LF:{}", coverage.lines.len());
+        let _ = writeln!(out, "LH:{lines_hit}");
+
+        let _ = writeln!(out, "end_of_record");
+    }
+    out
+}
+
+/// Write the lcov report of the given artifact.
+pub fn write_lcov(artifact: &DebugArtifact, mut writer: impl Write) -> Result<()> {
+    writer.write_all(lcov(artifact).as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy_json_abi::JsonAbi;
+    use alloy_primitives::{Address, B256, U256};
+    use foundry_compilers::artifacts::{sourcemap::parse, Evm};
+    use revm::interpreter::opcode;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::{
+        compilation::{CompilationArtifact, PcSourceMap, SourceFile},
+        debug::{DebugNodeFlat, DebugStep},
+    };
+
+    #[test]
+    fn test_lcov() {
+        let code = "contract A {\n    function f(bool x) public {\n        if (x) {}\n    }\n}\n";
+        let ast = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "src": "0:0:0",
+            "absolutePath": "src/A.sol",
+            "exportedSymbols": {},
+            "nodes": [],
+        }))
+        .unwrap();
+        let file = SourceFile::new(PathBuf::from("src/A.sol"), Arc::new(code.to_string()), ast);
+
+        // the function, the condition and the end of the function, which is never reached
+        let (function, condition, end) =
+            (code.find("function").unwrap(), code.find("if").unwrap(), code.find("}\n}").unwrap());
+        let elements =
+            parse(&format!("{function}:5:0:-;{function}:5:0:-;{condition}:9:0:-;{end}:1:0:-"))
+                .unwrap();
+        let source_map: PcSourceMap = elements.into_iter().enumerate().collect();
+
+        let address = Address::with_last_byte(1);
+        let compilation = CompilationArtifact {
+            contract_name: "A".to_string(),
+            code_hash: B256::ZERO,
+            file_id: 0,
+            abi: JsonAbi::new(),
+            evm: Evm::default(),
+            sources: BTreeMap::from([(0, file)]),
+            runtime_source_map: Arc::new(source_map),
+            creation_source_map: Default::default(),
+            runtime_generated_sources: Default::default(),
+            creation_generated_sources: Default::default(),
+        };
+        let step = |pc, instruction, stack: &[u64]| DebugStep {
+            pc,
+            instruction,
+            stack: stack.iter().map(|v| U256::from(*v)).collect(),
+            ..Default::default()
+        };
+        let artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(
+                address,
+                CallKind::Call,
+                0,
+                vec![
+                    step(0, opcode::PUSH0, &[]),
+                    step(1, opcode::PUSH0, &[0]),
+                    // the branch is taken
+                    step(2, opcode::JUMPI, &[0, 1]),
+                ],
+            )],
+            compilation_artifacts: [(address, compilation)].into_iter().collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            lcov(&artifact),
+            "TN:\nSF:src/A.sol\nBRDA:3,0,0,1\nBRDA:3,0,1,0\nBRF:2\nBRH:1\n\
+             DA:2,1\nDA:3,1\nDA:4,0\nLF:3\nLH:2\nend_of_record\n"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Export the cross-contract interaction matrix of the transaction as a plain-text table, or as
+//! CSV for spreadsheets.
+
+use std::fmt::Write;
+
+use alloy_primitives::Address;
+
+use crate::{
+    analysis::{matrix::InteractionMatrix, token::TokenMetadata},
+    artifact::debug::DebugArtifact,
+};
+
+/// Renders the interaction matrix of the given artifact as plain-text tables, in which the
+/// accounts are numbered to keep the columns narrow.
+pub fn interaction_matrix_table(artifact: &DebugArtifact) -> String {
+    let matrix = InteractionMatrix::new(artifact);
+    let accounts = matrix.accounts();
+    let mut report = String::new();
+    if matrix.is_empty() {
+        writeln!(report, "The transaction wrote no storage and moved no funds.").unwrap();
+        return report;
+    }
+
+    let name = |address: &Address| {
+        let index = accounts.iter().position(|account| account == address).unwrap_or_default();
+        format!("[{}]", index + 1)
+    };
+
+    writeln!(report, "Accounts").unwrap();
+    writeln!(report, "========").unwrap();
+    for address in &accounts {
+        writeln!(report, "{} {}", name(address), artifact.address_label(address)).unwrap();
+    }
+
+    // Rows are the senders, including the sender of the transaction, and columns the owners
+    writeln!(report).unwrap();
+    writeln!(report, "Storage writes (row: sender, column: storage owner)").unwrap();
+    writeln!(report, "====================================================").unwrap();
+    let mut senders: Vec<Option<Address>> =
+        matrix.storage_writes.keys().map(|(sender, _)| *sender).collect();
+    senders.dedup();
+    let mut owners: Vec<Address> = matrix.storage_writes.keys().map(|(_, owner)| *owner).collect();
+    owners.sort();
+    owners.dedup();
+    let row_header = |sender: &Option<Address>| sender.as_ref().map_or("tx sender".into(), name);
+    let first = senders.iter().map(|sender| row_header(sender).len()).max().unwrap_or(0);
+    let width = owners
+        .iter()
+        .map(|owner| name(owner).len())
+        .chain(matrix.storage_writes.values().map(|count| count.to_string().len()))
+        .max()
+        .unwrap_or(0);
+    write!(report, "{:first$}", "").unwrap();
+    for owner in &owners {
+        write!(report, "  {:>width$}", name(owner)).unwrap();
+    }
+    writeln!(report).unwrap();
+    for sender in &senders {
+        write!(report, "{:first$}", row_header(sender)).unwrap();
+        for owner in &owners {
+            let cell = matrix
+                .storage_writes
+                .get(&(*sender, *owner))
+                .map_or(".".to_string(), |count| count.to_string());
+            write!(report, "  {cell:>width$}").unwrap();
+        }
+        writeln!(report).unwrap();
+    }
+
+    let ether = TokenMetadata { symbol: "ETH".to_string(), decimals: 18 };
+    writeln!(report).unwrap();
+    writeln!(report, "Value sent").unwrap();
+    writeln!(report, "==========").unwrap();
+    if matrix.ether.is_empty() && matrix.token_transfers.is_empty() {
+        writeln!(report, "(none)").unwrap();
+    }
+    for ((from, to), amount) in &matrix.ether {
+        writeln!(report, "{} -> {}: {}", name(from), name(to), ether.format_amount(*amount))
+            .unwrap();
+    }
+    for ((from, to), count) in &matrix.token_transfers {
+        writeln!(report, "{} -> {}: {count} token transfer(s)", name(from), name(to)).unwrap();
+    }
+
+    report
+}
+
+/// Renders the interaction matrix of the given artifact as CSV, one row per pair of accounts
+/// and kind of interaction: `kind,from,to,count,amount`.
+pub fn interaction_matrix_csv(artifact: &DebugArtifact) -> String {
+    let matrix = InteractionMatrix::new(artifact);
+    let mut csv = String::from("kind,from,to,count,amount\n");
+    for ((sender, owner), count) in &matrix.storage_writes {
+        let sender = sender.map_or("tx sender".to_string(), |sender| sender.to_string());
+        writeln!(csv, "storage_write,{sender},{owner},{count},").unwrap();
+    }
+    for ((from, to), amount) in &matrix.ether {
+        writeln!(csv, "ether,{from},{to},,{amount}").unwrap();
+    }
+    for ((from, to), count) in &matrix.token_transfers {
+        writeln!(csv, "token_transfer,{from},{to},{count},").unwrap();
+    }
+    csv
+}
@@ -0,0 +1,13 @@
+//! Exporters of the debug artifact into formats understood by third-party tools.
+
+pub mod allowance;
+pub mod calltree;
+pub mod cast;
+pub mod chrome;
+pub mod dependency;
+pub mod flamegraph;
+pub mod funds;
+pub mod lcov;
+pub mod matrix;
+pub mod signature;
+pub mod tenderly;
@@ -0,0 +1,44 @@
+//! Export the checks of a signature verified by `ecrecover` as a plain-text report, i.e., the
+//! inputs of the call, the recomputed digest and signer, and the problems found.
+
+use std::fmt::Write;
+
+use crate::{analysis::signature::SignatureReport, artifact::debug::DebugArtifact};
+
+/// Renders the checks of a signature: the inputs of the `ecrecover` call and the signer, the
+/// EIP-712 digest and typed data it was computed from, if any, followed by the problems found.
+pub fn signature_report(artifact: &DebugArtifact, report: &SignatureReport) -> String {
+    let call = &report.ecrecover;
+    let mut text = String::new();
+    writeln!(text, "ecrecover at step {}", call.step).unwrap();
+    writeln!(text, "  hash: {}", call.hash).unwrap();
+    writeln!(text, "  v: {}\n  r: {}\n  s: {}", call.v, call.r, call.s).unwrap();
+    match report.signer {
+        Some(signer) => writeln!(text, "  signer: {}", artifact.address_label(&signer)).unwrap(),
+        None => text.push_str("  signer: none (invalid signature)\n"),
+    }
+    if let Some(expected) = &report.expected {
+        writeln!(text, "  expected: {}", artifact.address_label(expected)).unwrap();
+    }
+    if let Some(typed) = &report.typed_digest {
+        writeln!(text, "\nEIP-712 digest computed at step {}", typed.step).unwrap();
+        writeln!(text, "  domain separator: {}", typed.domain_separator).unwrap();
+        writeln!(text, "  struct hash: {}", typed.struct_hash).unwrap();
+        writeln!(text, "  digest: {}", typed.digest).unwrap();
+    }
+    if let Some(nonce) = report.nonce {
+        writeln!(text, "  permit nonce: {nonce}").unwrap();
+    }
+    if let Some(typed_data) = &report.typed_data {
+        writeln!(text, "\nTyped data\n{typed_data}").unwrap();
+    }
+    if report.findings.is_empty() {
+        text.push_str("\nThe signature is valid.\n");
+    } else {
+        text.push_str("\nProblems:\n");
+        for finding in &report.findings {
+            writeln!(text, "  • {finding}").unwrap();
+        }
+    }
+    text
+}
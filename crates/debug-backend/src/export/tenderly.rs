@@ -0,0 +1,111 @@
+//! Export the call trace in the JSON schema of Tenderly's `call_trace`.
+//!
+//! Only the fields which can be recovered from the debug artifact are filled: there are no
+//! balances, and the gas forwarded to each call is not recorded.
+
+use std::io::Write;
+
+use eyre::Result;
+use serde_json::{json, Value};
+
+use super::calltree::{call_frames, format_value, CallFrame, FrameOutcome};
+use crate::artifact::debug::DebugArtifact;
+
+/// Build the Tenderly call trace of the given artifact, or `null` if it has no call.
+pub fn tenderly_trace(artifact: &DebugArtifact) -> Value {
+    let frames = call_frames(artifact);
+    if frames.is_empty() {
+        return Value::Null;
+    }
+    call(artifact, &frames, 0)
+}
+
+/// Write the Tenderly call trace of the given artifact as JSON.
+pub fn write_tenderly_trace(artifact: &DebugArtifact, mut writer: impl Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut writer, &tenderly_trace(artifact))?;
+    Ok(())
+}
+
+fn call(artifact: &DebugArtifact, frames: &[CallFrame], index: usize) -> Value {
+    let frame = &frames[index];
+    let function = frame.function(artifact);
+    let decoded_input = frame.decoded_input(artifact).map(|values| {
+        function
+            .into_iter()
+            .flat_map(|function| &function.inputs)
+            .zip(&values)
+            .map(|(param, value)| {
+                json!({
+                    "soltype": { "name": param.name, "type": param.ty },
+                    "value": format_value(value),
+                })
+            })
+            .collect::<Vec<_>>()
+    });
+    let error = match frame.outcome {
+        FrameOutcome::Revert => Some("execution reverted"),
+        FrameOutcome::Halt => Some("execution halted"),
+        _ => None,
+    };
+
+    json!({
+        "call_type": frame.kind.to_string(),
+        "from": frame.caller,
+        "to": frame.address,
+        "contract_name": artifact.contract_name(&frame.address),
+        "function_name": function.map(|function| &function.name),
+        "value": frame.value.to_string(),
+        "gas_used": frame.gas_used,
+        "input": frame.input,
+        "decoded_input": decoded_input,
+        "output": frame.output,
+        "error": error,
+        "error_reason": frame.revert_reason(),
+        "calls": frame.children.iter().map(|child| call(artifact, frames, *child)).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+    use revm::interpreter::opcode;
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::{DebugNodeFlat, DebugStep};
+
+    #[test]
+    fn test_tenderly_trace() {
+        let step = |instruction, stack: Vec<u64>, gas| DebugStep {
+            instruction,
+            stack: stack.into_iter().map(U256::from).collect(),
+            total_gas_used: gas,
+            ..Default::default()
+        };
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let artifact = DebugArtifact {
+            debug_arena: vec![
+                DebugNodeFlat::new(
+                    a,
+                    CallKind::Call,
+                    0,
+                    vec![step(opcode::CALL, vec![0, 0, 0, 0, 5, 2, 0], 10)],
+                ),
+                DebugNodeFlat::new(b, CallKind::Call, 1, vec![step(opcode::INVALID, vec![], 3)]),
+                DebugNodeFlat::new(a, CallKind::Call, 0, vec![step(opcode::STOP, vec![], 40)]),
+            ],
+            ..Default::default()
+        };
+
+        let trace = tenderly_trace(&artifact);
+        assert_eq!(trace["call_type"], "CALL");
+        assert_eq!(trace["from"], Value::Null);
+        assert_eq!(trace["gas_used"], 40);
+        assert_eq!(trace["error"], Value::Null);
+        let sub = &trace["calls"][0];
+        assert_eq!(sub["from"], json!(a));
+        assert_eq!(sub["to"], json!(b));
+        assert_eq!(sub["value"], "5");
+        assert_eq!(sub["error"], "execution halted");
+    }
+}
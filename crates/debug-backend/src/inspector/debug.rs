@@ -1,10 +1,12 @@
-use alloy_primitives::Address;
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, U256};
 use alloy_sol_types::SolError;
 use arrayvec::ArrayVec;
 use revm::{
     interpreter::{
-        opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Gas, InstructionResult,
-        Interpreter, InterpreterResult,
+        CallInputs, CallOutcome, CreateInputs, CreateOutcome, Gas, InstructionResult, Interpreter,
+        InterpreterResult,
     },
     Database, EvmContext, Inspector,
 };
@@ -12,7 +14,8 @@ use revm_inspectors::tracing::types::CallKind;
 
 use crate::{
     artifact::debug::{DebugArena, DebugNode, DebugStep},
-    utils::evm,
+    snapshot::SnapshotGranularity,
+    utils::{evm, opcode},
 };
 
 #[derive(Debug)]
@@ -23,6 +26,13 @@ pub struct DebugInspector<DB> {
     pub head: usize,
     /// The current execution address.
     pub context: Address,
+    /// The beneficiary of each `SELFDESTRUCT` and the balance sent to it, by the index of the
+    /// node ending with it.
+    pub selfdestructs: HashMap<usize, (Address, U256)>,
+    /// The steps at which the stack and memory are recorded.
+    granularity: SnapshotGranularity,
+    /// The node whose stack and memory are recorded at every step regardless of the granularity.
+    full_node: Option<usize>,
 
     phantom: std::marker::PhantomData<DB>,
 }
@@ -37,21 +47,48 @@ where
             arena: DebugArena::default(),
             head: 0,
             context: Address::default(),
+            selfdestructs: HashMap::new(),
+            granularity: SnapshotGranularity::default(),
+            full_node: None,
             phantom: Default::default(),
         }
     }
 
+    /// Records the stack and memory only at the steps of the given granularity.
+    pub fn with_granularity(mut self, granularity: SnapshotGranularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
+
+    /// Records the stack and memory at every step of the given node.
+    pub fn with_full_snapshots(mut self, node: usize) -> Self {
+        self.full_node = Some(node);
+        self
+    }
+
+    /// Returns whether the stack and memory of the `index`-th step of the current node are
+    /// recorded.
+    fn keeps(&self, instruction: u8, index: usize) -> bool {
+        self.full_node == Some(self.head) || self.granularity.keeps(instruction, index == 0)
+    }
+
     /// Enters a new execution context.
-    pub fn enter(&mut self, depth: usize, address: Address, kind: CallKind) {
+    pub fn enter(&mut self, depth: usize, address: Address, kind: CallKind, gas_limit: u64) {
         self.context = address;
-        self.head = self.arena.push_node(DebugNode { depth, address, kind, ..Default::default() });
+        self.head = self.arena.push_node(DebugNode {
+            depth,
+            address,
+            kind,
+            gas_limit,
+            ..Default::default()
+        });
     }
 
     /// Exits the current execution context, replacing it with the previous one.
     pub fn exit(&mut self) {
         if let Some(parent_id) = self.arena.arena[self.head].parent {
-            let DebugNode { depth, address, kind, .. } = self.arena.arena[parent_id];
-            self.enter(depth, address, kind);
+            let DebugNode { depth, address, kind, gas_limit, .. } = self.arena.arena[parent_id];
+            self.enter(depth, address, kind, gas_limit);
         }
     }
 }
@@ -62,22 +99,25 @@ where
     DB::Error: std::error::Error,
 {
     fn step(&mut self, interp: &mut Interpreter, ecx: &mut EvmContext<DB>) {
-        let pc = interp.program_counter();
         let op = interp.current_opcode();
-
-        // Extract the push bytes
-        let push_size = if (opcode::PUSH1..=opcode::PUSH32).contains(&op) {
-            (op - opcode::PUSH0) as usize
-        } else {
-            0
+        // In EOF code, the program counter is relative to the current code section
+        let section_pc = interp.program_counter();
+        let pc = match interp.contract.bytecode.eof() {
+            Some(eof) if interp.is_eof => {
+                let section = interp.function_stack.current_code_idx;
+                opcode::eof_code_section_offset(&eof.header, section) + section_pc
+            }
+            _ => section_pc,
         };
-        let push_bytes = (push_size > 0).then(|| {
-            let start = pc + 1;
-            let end = start + push_size;
-            let slice = &interp.contract.bytecode.bytecode()[start..end];
-            debug_assert!(slice.len() <= 32);
+
+        // Extract the immediate bytes, e.g., the push bytes
+        let code = &interp.bytecode[section_pc..];
+        let immediate_size = opcode::immediate_size(code, interp.is_eof);
+        let push_bytes = (immediate_size > 0).then(|| {
+            // the jump table of `RJUMPV` may be longer than 32 bytes, and is truncated
+            let end = (1 + immediate_size).min(code.len()).min(33);
             let mut array = ArrayVec::new();
-            array.try_extend_from_slice(slice).unwrap();
+            array.try_extend_from_slice(&code[1..end]).unwrap();
             array
         });
 
@@ -87,17 +127,24 @@ where
             interp.gas.refunded() as u64,
         );
 
-        // Reuse the memory from the previous step if the previous opcode did not modify it.
-        let memory = self.arena.arena[self.head]
-            .steps
-            .last()
-            .filter(|step| !step.opcode_modifies_memory())
-            .map(|step| step.memory.clone())
-            .unwrap_or_else(|| interp.shared_memory.context_memory().to_vec().into());
+        // Only record the stack and memory at the steps of the granularity.
+        let steps = &self.arena.arena[self.head].steps;
+        let (stack, memory) = if self.keeps(op, steps.len()) {
+            // Reuse the memory from the previous step if the previous opcode did not modify it.
+            let memory = steps
+                .last()
+                .filter(|step| !step.opcode_modifies_memory())
+                .filter(|step| self.keeps(step.instruction, steps.len() - 1))
+                .map(|step| step.memory.clone())
+                .unwrap_or_else(|| interp.shared_memory.context_memory().to_vec().into());
+            (interp.stack().data().clone(), memory)
+        } else {
+            Default::default()
+        };
 
         self.arena.arena[self.head].steps.push(DebugStep {
             pc,
-            stack: interp.stack().data().clone(),
+            stack,
             memory,
             calldata: interp.contract().input.clone(),
             returndata: interp.return_data_buffer.clone(),
@@ -107,11 +154,16 @@ where
         });
     }
 
+    fn selfdestruct(&mut self, _contract: Address, target: Address, value: U256) {
+        self.selfdestructs.insert(self.head, (target, value));
+    }
+
     fn call(&mut self, ecx: &mut EvmContext<DB>, inputs: &mut CallInputs) -> Option<CallOutcome> {
         self.enter(
             ecx.journaled_state.depth() as usize,
             inputs.bytecode_address,
             inputs.scheme.into(),
+            inputs.gas_limit,
         );
 
         None
@@ -150,7 +202,8 @@ where
         self.enter(
             ecx.journaled_state.depth() as usize,
             inputs.created_address(nonce),
-            CallKind::Create,
+            inputs.scheme.into(),
+            inputs.gas_limit,
         );
 
         None
@@ -5,11 +5,16 @@
 #[macro_use]
 extern crate tracing;
 
-mod analysis;
+pub mod analysis;
 pub mod artifact;
 mod core;
+pub mod event;
+pub mod export;
 mod handler;
 mod inspector;
+pub mod reference;
+pub mod snapshot;
+pub mod solc;
 mod utils;
 
-pub use core::DebugBackend;
+pub use core::{DebugBackend, DebugBackendBuilder};
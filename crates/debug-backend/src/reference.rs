@@ -0,0 +1,260 @@
+//! A built-in reference of the EVM opcodes: their semantics, gas costs and stack effects.
+//!
+//! Gas costs follow the Cancun hard fork. Dynamic costs are given as formulas, where `words` is
+//! the size in 32-byte words and `mem` is the cost of expanding the memory.
+
+use revm::interpreter::{opcode, OpCode};
+
+/// The reference entry of an opcode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpcodeDoc {
+    pub opcode: u8,
+    pub name: &'static str,
+    /// What the opcode does.
+    pub description: String,
+    /// The gas cost, or its formula if it is dynamic.
+    pub gas: String,
+    /// The stack items popped by the opcode, the top of the stack first.
+    pub inputs: Vec<String>,
+    /// The stack items pushed by the opcode, the top of the stack first.
+    pub outputs: Vec<String>,
+}
+
+impl OpcodeDoc {
+    /// Returns the reference entry of the opcode, or `None` if the opcode is not defined.
+    pub fn of(op: u8) -> Option<Self> {
+        let name = OpCode::new(op)?.as_str();
+        let (description, gas, inputs, outputs) = match op {
+            opcode::PUSH0 => {
+                ("Pushes 0 onto the stack.".to_string(), "2".to_string(), vec![], vals(&["0"]))
+            }
+            opcode::PUSH1..=opcode::PUSH32 => {
+                let n = op - opcode::PUSH0;
+                (
+                    format!("Pushes the {n}-byte immediate following the opcode onto the stack."),
+                    "3".to_string(),
+                    vec![],
+                    vals(&["value"]),
+                )
+            }
+            opcode::DUP1..=opcode::DUP16 => {
+                let n = (op - opcode::DUP1 + 1) as usize;
+                let inputs: Vec<_> = (1..=n).map(|i| format!("a{i}")).collect();
+                let mut outputs = vec![format!("a{n}")];
+                outputs.extend(inputs.iter().cloned());
+                (
+                    format!("Duplicates the {} stack item.", ordinal(n)),
+                    "3".to_string(),
+                    inputs,
+                    outputs,
+                )
+            }
+            opcode::SWAP1..=opcode::SWAP16 => {
+                let n = (op - opcode::SWAP1 + 1) as usize;
+                let inputs: Vec<_> = (0..=n).map(|i| format!("a{i}")).collect();
+                let mut outputs = inputs.clone();
+                outputs.swap(0, n);
+                (
+                    format!("Exchanges the top stack item with the {} one.", ordinal(n + 1)),
+                    "3".to_string(),
+                    inputs,
+                    outputs,
+                )
+            }
+            opcode::LOG0..=opcode::LOG4 => {
+                let n = (op - opcode::LOG0) as usize;
+                let mut inputs = vals(&["offset", "size"]);
+                inputs.extend((0..n).map(|i| format!("topic{i}")));
+                (
+                    format!("Emits an event with {n} topics and the data in memory[offset:offset+size]."),
+                    format!("375 + 375 * {n} + 8 * size + mem"),
+                    inputs,
+                    vec![],
+                )
+            }
+            _ => match fixed(op) {
+                Some((description, gas, inputs, outputs)) => {
+                    (description.to_string(), gas.to_string(), vals(inputs), vals(outputs))
+                }
+                // the EOF instructions, which are defined by revm but not live on any chain yet
+                None => {
+                    let info = OpCode::new(op)?.info();
+                    (
+                        "EVM Object Format (EIP-7692) instruction, only valid in EOF contracts."
+                            .to_string(),
+                        "see EIP-7692".to_string(),
+                        (0..info.inputs()).map(|i| format!("a{i}")).collect(),
+                        (0..info.outputs()).map(|i| format!("b{i}")).collect(),
+                    )
+                }
+            },
+        };
+        Some(Self { opcode: op, name, description, gas, inputs, outputs })
+    }
+
+    /// Looks up an opcode by its mnemonic (e.g. `sstore`, case-insensitive) or its hex value
+    /// (e.g. `0x55`).
+    pub fn find(query: &str) -> Option<Self> {
+        let query = query.trim();
+        if let Some(hex) = query.strip_prefix("0x").or_else(|| query.strip_prefix("0X")) {
+            return u8::from_str_radix(hex, 16).ok().and_then(Self::of);
+        }
+        // `SHA3` is the name of `KECCAK256` before it was renamed
+        let query = query.to_ascii_uppercase();
+        let query = if query == "SHA3" { "KECCAK256" } else { query.as_str() };
+        OpCode::parse(query).and_then(|op| Self::of(op.get()))
+    }
+}
+
+impl std::fmt::Display for OpcodeDoc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} (0x{:02x})", self.name, self.opcode)?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.description)?;
+        writeln!(f)?;
+        writeln!(f, "Gas:     {}", self.gas)?;
+        writeln!(f, "Inputs:  {}", stack(&self.inputs))?;
+        write!(f, "Outputs: {}", stack(&self.outputs))
+    }
+}
+
+fn stack(items: &[String]) -> String {
+    if items.is_empty() {
+        "-".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+fn vals(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (1, 11) | (2, 12) | (3, 13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+type Fixed = (&'static str, &'static str, &'static [&'static str], &'static [&'static str]);
+
+/// The entries of the opcodes which are not part of a family (`PUSH`, `DUP`, `SWAP`, `LOG`), or
+/// `None` for the EOF instructions.
+#[rustfmt::skip]
+fn fixed(op: u8) -> Option<Fixed> {
+    use opcode::*;
+    let doc: Fixed = match op {
+        STOP => ("Halts the execution successfully, without returning data.", "0", &[], &[]),
+        ADD => ("Addition modulo 2^256.", "3", &["a", "b"], &["a + b"]),
+        MUL => ("Multiplication modulo 2^256.", "5", &["a", "b"], &["a * b"]),
+        SUB => ("Subtraction modulo 2^256.", "3", &["a", "b"], &["a - b"]),
+        DIV => ("Unsigned integer division; division by zero gives 0.", "5", &["a", "b"], &["a / b"]),
+        SDIV => ("Signed integer division; division by zero gives 0.", "5", &["a", "b"], &["a / b"]),
+        MOD => ("Unsigned modulo; modulo zero gives 0.", "5", &["a", "b"], &["a % b"]),
+        SMOD => ("Signed modulo, with the sign of a; modulo zero gives 0.", "5", &["a", "b"], &["a % b"]),
+        ADDMOD => ("Addition modulo N, without overflowing 2^256.", "8", &["a", "b", "N"], &["(a + b) % N"]),
+        MULMOD => ("Multiplication modulo N, without overflowing 2^256.", "8", &["a", "b", "N"], &["(a * b) % N"]),
+        EXP => ("Exponentiation modulo 2^256.", "10 + 50 * byte_len(exponent)", &["a", "exponent"], &["a ** exponent"]),
+        SIGNEXTEND => ("Extends the sign of the (b+1)-byte signed integer x to 32 bytes.", "5", &["b", "x"], &["y"]),
+        LT => ("Unsigned less-than comparison.", "3", &["a", "b"], &["a < b"]),
+        GT => ("Unsigned greater-than comparison.", "3", &["a", "b"], &["a > b"]),
+        SLT => ("Signed less-than comparison.", "3", &["a", "b"], &["a < b"]),
+        SGT => ("Signed greater-than comparison.", "3", &["a", "b"], &["a > b"]),
+        EQ => ("Equality comparison.", "3", &["a", "b"], &["a == b"]),
+        ISZERO => ("Pushes 1 if a is zero, 0 otherwise.", "3", &["a"], &["a == 0"]),
+        AND => ("Bitwise AND.", "3", &["a", "b"], &["a & b"]),
+        OR => ("Bitwise OR.", "3", &["a", "b"], &["a | b"]),
+        XOR => ("Bitwise XOR.", "3", &["a", "b"], &["a ^ b"]),
+        NOT => ("Bitwise NOT.", "3", &["a"], &["~a"]),
+        BYTE => ("Retrieves the i-th byte of x, counting from the most significant byte.", "3", &["i", "x"], &["y"]),
+        SHL => ("Left shift.", "3", &["shift", "value"], &["value << shift"]),
+        SHR => ("Logical right shift.", "3", &["shift", "value"], &["value >> shift"]),
+        SAR => ("Arithmetic (signed) right shift.", "3", &["shift", "value"], &["value >> shift"]),
+        KECCAK256 => ("Computes the Keccak-256 hash of memory[offset:offset+size].", "30 + 6 * words + mem", &["offset", "size"], &["hash"]),
+        ADDRESS => ("Pushes the address of the executing account.", "2", &[], &["address"]),
+        BALANCE => ("Pushes the balance of the account, in wei.", "100 (warm) or 2600 (cold)", &["address"], &["balance"]),
+        ORIGIN => ("Pushes the sender of the transaction.", "2", &[], &["address"]),
+        CALLER => ("Pushes the address which called the current context.", "2", &[], &["address"]),
+        CALLVALUE => ("Pushes the value sent with the call, in wei.", "2", &[], &["value"]),
+        CALLDATALOAD => ("Loads 32 bytes of the call data at the offset, padded with zeros.", "3", &["offset"], &["data"]),
+        CALLDATASIZE => ("Pushes the size of the call data in bytes.", "2", &[], &["size"]),
+        CALLDATACOPY => ("Copies call data to memory.", "3 + 3 * words + mem", &["destOffset", "offset", "size"], &[]),
+        CODESIZE => ("Pushes the size of the executing code in bytes.", "2", &[], &["size"]),
+        CODECOPY => ("Copies the executing code to memory.", "3 + 3 * words + mem", &["destOffset", "offset", "size"], &[]),
+        GASPRICE => ("Pushes the effective gas price of the transaction.", "2", &[], &["price"]),
+        EXTCODESIZE => ("Pushes the code size of the account.", "100 (warm) or 2600 (cold)", &["address"], &["size"]),
+        EXTCODECOPY => ("Copies the code of the account to memory.", "100 (warm) or 2600 (cold) + 3 * words + mem", &["address", "destOffset", "offset", "size"], &[]),
+        RETURNDATASIZE => ("Pushes the size of the data returned by the last call.", "2", &[], &["size"]),
+        RETURNDATACOPY => ("Copies the data returned by the last call to memory; reverts when reading out of bounds.", "3 + 3 * words + mem", &["destOffset", "offset", "size"], &[]),
+        EXTCODEHASH => ("Pushes the Keccak-256 hash of the account's code, or 0 for an empty account.", "100 (warm) or 2600 (cold)", &["address"], &["hash"]),
+        BLOCKHASH => ("Pushes the hash of one of the 256 most recent blocks, or 0.", "20", &["blockNumber"], &["hash"]),
+        COINBASE => ("Pushes the beneficiary address of the block.", "2", &[], &["address"]),
+        TIMESTAMP => ("Pushes the timestamp of the block.", "2", &[], &["timestamp"]),
+        NUMBER => ("Pushes the number of the block.", "2", &[], &["blockNumber"]),
+        DIFFICULTY => ("Pushes the randomness of the beacon chain (the difficulty before the merge).", "2", &[], &["prevrandao"]),
+        GASLIMIT => ("Pushes the gas limit of the block.", "2", &[], &["gasLimit"]),
+        CHAINID => ("Pushes the chain id.", "2", &[], &["chainId"]),
+        SELFBALANCE => ("Pushes the balance of the executing account, in wei.", "5", &[], &["balance"]),
+        BASEFEE => ("Pushes the base fee of the block.", "2", &[], &["baseFee"]),
+        BLOBHASH => ("Pushes the versioned hash of the i-th blob of the transaction, or 0.", "3", &["index"], &["blobHash"]),
+        BLOBBASEFEE => ("Pushes the blob base fee of the block.", "2", &[], &["blobBaseFee"]),
+        POP => ("Removes the top stack item.", "2", &["a"], &[]),
+        MLOAD => ("Loads 32 bytes from memory.", "3 + mem", &["offset"], &["value"]),
+        MSTORE => ("Stores 32 bytes to memory.", "3 + mem", &["offset", "value"], &[]),
+        MSTORE8 => ("Stores the least significant byte of the value to memory.", "3 + mem", &["offset", "value"], &[]),
+        SLOAD => ("Loads a word from the storage of the executing account.", "100 (warm) or 2100 (cold)", &["key"], &["value"]),
+        SSTORE => ("Stores a word to the storage of the executing account.", "100 (unchanged or dirty), 2900 (reset) or 20000 (set) + 2100 if cold; refunds per EIP-3529", &["key", "value"], &[]),
+        JUMP => ("Jumps to the destination, which must be a JUMPDEST.", "8", &["counter"], &[]),
+        JUMPI => ("Jumps to the destination if the condition is not zero.", "10", &["counter", "b"], &[]),
+        PC => ("Pushes the program counter of this instruction.", "2", &[], &["counter"]),
+        MSIZE => ("Pushes the size of the active memory in bytes.", "2", &[], &["size"]),
+        GAS => ("Pushes the remaining gas, after paying for this instruction.", "2", &[], &["gas"]),
+        JUMPDEST => ("Marks a valid jump destination.", "1", &[], &[]),
+        TLOAD => ("Loads a word from the transient storage of the executing account.", "100", &["key"], &["value"]),
+        TSTORE => ("Stores a word to the transient storage of the executing account.", "100", &["key", "value"], &[]),
+        MCOPY => ("Copies memory to memory; the areas may overlap.", "3 + 3 * words + mem", &["destOffset", "offset", "size"], &[]),
+        CREATE => ("Creates a contract with the init code in memory, at an address derived from the sender and its nonce.", "32000 + 2 * words + mem + execution", &["value", "offset", "size"], &["address"]),
+        CALL => ("Calls the account, forwarding at most 63/64 of the remaining gas.", "100 (warm) or 2600 (cold) + 9000 if value > 0 + 25000 if the account is new + mem", &["gas", "address", "value", "argsOffset", "argsSize", "retOffset", "retSize"], &["success"]),
+        CALLCODE => ("Calls the account's code in the context of the executing account (deprecated).", "100 (warm) or 2600 (cold) + 9000 if value > 0 + mem", &["gas", "address", "value", "argsOffset", "argsSize", "retOffset", "retSize"], &["success"]),
+        RETURN => ("Halts the execution, returning memory[offset:offset+size].", "mem", &["offset", "size"], &[]),
+        DELEGATECALL => ("Calls the account's code in the current context, keeping the caller and value.", "100 (warm) or 2600 (cold) + mem", &["gas", "address", "argsOffset", "argsSize", "retOffset", "retSize"], &["success"]),
+        CREATE2 => ("Creates a contract at an address derived from the sender, the salt and the init code hash.", "32000 + 8 * words + mem + execution", &["value", "offset", "size", "salt"], &["address"]),
+        STATICCALL => ("Calls the account, disallowing any state modification.", "100 (warm) or 2600 (cold) + mem", &["gas", "address", "argsOffset", "argsSize", "retOffset", "retSize"], &["success"]),
+        REVERT => ("Halts the execution, reverting the state changes and returning memory[offset:offset+size].", "mem", &["offset", "size"], &[]),
+        INVALID => ("Designated invalid instruction, which consumes all the remaining gas.", "all remaining gas", &[], &[]),
+        SELFDESTRUCT => ("Sends the balance to the beneficiary; the account is only deleted if it was created in the same transaction (EIP-6780).", "5000 + 2600 if cold + 25000 if the beneficiary is new", &["address"], &[]),
+        _ => return None,
+    };
+    Some(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opcode_doc() {
+        let doc = OpcodeDoc::find("sstore").unwrap();
+        assert_eq!(doc.opcode, opcode::SSTORE);
+        assert_eq!(doc.inputs, ["key", "value"]);
+        assert_eq!(OpcodeDoc::find("0x55"), Some(doc));
+        assert_eq!(OpcodeDoc::find("sha3").unwrap().opcode, opcode::KECCAK256);
+        assert!(OpcodeDoc::find("FOO").is_none());
+
+        let swap = OpcodeDoc::of(opcode::SWAP2).unwrap();
+        assert_eq!(swap.inputs, ["a0", "a1", "a2"]);
+        assert_eq!(swap.outputs, ["a2", "a1", "a0"]);
+        let dup = OpcodeDoc::of(opcode::DUP2).unwrap();
+        assert_eq!(dup.outputs, ["a2", "a1", "a2"]);
+
+        // every opcode defined by revm is documented
+        assert!((0..=u8::MAX)
+            .filter(|op| OpCode::new(*op).is_some())
+            .all(|op| OpcodeDoc::of(op).is_some()));
+    }
+}
@@ -0,0 +1,293 @@
+//! The granularity at which the execution state is recorded.
+//!
+//! Recording the stack and memory at every step dominates the memory and the time taken to start
+//! a session on long transactions. With a coarser granularity, the stack and memory are only
+//! recorded at the steps the analyses rely on (e.g., calls, hashes, and logs) and at the chosen
+//! boundaries. The other steps keep their program counter, gas, calldata, and return data.
+//!
+//! The execution is deterministic once the state it reads is known, so the state fetched from
+//! the RPC endpoint during the first execution is kept as a [`Recording`]. The stack and memory of
+//! a node are recorded on demand by re-executing the transaction against it, and dropped again
+//! once the node is no longer looked at, which bounds the memory held by giant transactions.
+
+use std::{fmt, mem::size_of, str::FromStr};
+
+use alloy_primitives::{Bytes, U256};
+use eyre::{eyre, Result};
+use revm::{
+    db::{CacheDB, DbAccount, EmptyDB},
+    interpreter::opcode,
+    primitives::EnvWithHandlerCfg,
+    DatabaseRef,
+};
+
+use crate::{
+    artifact::debug::{DebugArtifact, DebugStep},
+    inspector::DebugInspector,
+    utils::evm::new_evm_with_inspector,
+};
+
+/// The steps at which the stack and memory are recorded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SnapshotGranularity {
+    /// Every step.
+    #[default]
+    Opcode,
+    /// The first step of each node, and the calls, returns, hashes, and logs.
+    Call,
+    /// As [`Self::Call`], and the storage accesses.
+    Sstore,
+    /// As [`Self::Call`], and the jump destinations, where the statements of the source code
+    /// usually start.
+    Line,
+}
+
+impl SnapshotGranularity {
+    pub const ALL: [Self; 4] = [Self::Opcode, Self::Call, Self::Sstore, Self::Line];
+
+    /// Returns whether the stack and memory of a step executing the given instruction are
+    /// recorded. `first` is whether the step is the first one of its node.
+    pub fn keeps(self, instruction: u8, first: bool) -> bool {
+        if self == Self::Opcode || first {
+            return true;
+        }
+        match instruction {
+            // the steps the analyses read the state of, e.g., the call frames, the hashed
+            // preimages, and the events
+            opcode::CALL |
+            opcode::CALLCODE |
+            opcode::DELEGATECALL |
+            opcode::STATICCALL |
+            opcode::CREATE |
+            opcode::CREATE2 |
+            opcode::RETURN |
+            opcode::REVERT |
+            opcode::STOP |
+            opcode::SELFDESTRUCT |
+            opcode::KECCAK256 |
+            opcode::LOG0..=opcode::LOG4 => true,
+            opcode::SLOAD | opcode::SSTORE | opcode::TLOAD | opcode::TSTORE => self == Self::Sstore,
+            opcode::JUMPDEST => self == Self::Line,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for SnapshotGranularity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Opcode => write!(f, "opcode"),
+            Self::Call => write!(f, "call"),
+            Self::Sstore => write!(f, "sstore"),
+            Self::Line => write!(f, "line"),
+        }
+    }
+}
+
+impl FromStr for SnapshotGranularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|granularity| granularity.to_string() == s).ok_or_else(|| {
+            format!("unknown snapshot granularity `{s}`, expected opcode, call, sstore, or line")
+        })
+    }
+}
+
+/// The state read by a transaction during its first execution, i.e., its only inputs which are
+/// not deterministic, against which it is re-executed without fetching anything again.
+#[derive(Clone, Debug)]
+pub struct Recording {
+    db: CacheDB<EmptyDB>,
+    env: EnvWithHandlerCfg,
+    granularity: SnapshotGranularity,
+}
+
+impl Recording {
+    /// Keeps the state loaded into the cache of the database the transaction was executed on.
+    pub(crate) fn new<ExtDB>(
+        db: &CacheDB<ExtDB>,
+        env: EnvWithHandlerCfg,
+        granularity: SnapshotGranularity,
+    ) -> Self {
+        let mut state = CacheDB::new(EmptyDB::default());
+        state.accounts = db.accounts.clone();
+        state.contracts = db.contracts.clone();
+        state.block_hashes = db.block_hashes.clone();
+        Self { db: state, env, granularity }
+    }
+
+    /// The granularity the stack and memory were first recorded at.
+    pub fn granularity(&self) -> SnapshotGranularity {
+        self.granularity
+    }
+
+    /// Re-executes the transaction, and returns the steps of the given node with the stack and
+    /// memory recorded at every step.
+    pub fn record_node(&self, node: usize) -> Result<Vec<DebugStep>> {
+        record_node_snapshots(&self.db, self.env.clone(), node)
+    }
+
+    /// Estimates the memory held by the recorded state, in bytes.
+    pub fn size(&self) -> usize {
+        let accounts: usize = self
+            .db
+            .accounts
+            .values()
+            .map(|account| size_of::<DbAccount>() + account.storage.len() * 2 * size_of::<U256>())
+            .sum();
+        let codes: usize = self.db.contracts.values().map(|code| code.len()).sum();
+        accounts + codes + self.db.block_hashes.len() * 2 * size_of::<U256>()
+    }
+}
+
+impl DebugArtifact {
+    /// Returns whether the stack and memory of the node are only recorded at some of its steps.
+    pub fn is_coarse(&self, node: usize) -> bool {
+        self.coarse_nodes.contains(&node)
+    }
+
+    /// Records the stack and memory of every step of the node by re-executing the transaction, if
+    /// they were only recorded at some of them. Returns whether they were recorded.
+    pub fn load_snapshots(&mut self, node: usize) -> Result<bool> {
+        let Some(recording) = self.recording.clone().filter(|_| self.is_coarse(node)) else {
+            return Ok(false);
+        };
+        self.fill_snapshots(node, recording.record_node(node)?)?;
+        Ok(true)
+    }
+
+    /// Drops the stack and memory of the steps of the node which are not at the recorded
+    /// granularity, once they were loaded with [`Self::load_snapshots`].
+    pub fn unload_snapshots(&mut self, node: usize) {
+        let Some(recording) = &self.recording else { return };
+        let granularity = recording.granularity;
+        for (index, step) in self.debug_arena[node].steps.iter_mut().enumerate() {
+            if !granularity.keeps(step.instruction, index == 0) {
+                step.stack = Vec::new();
+                step.memory = Bytes::new();
+            }
+        }
+        self.coarse_nodes.insert(node);
+    }
+
+    /// Replaces the steps of a coarse node with the given ones, recorded at every step by
+    /// [`record_node_snapshots`]. The steps must be the same as the recorded ones.
+    pub fn fill_snapshots(&mut self, node: usize, steps: Vec<DebugStep>) -> Result<()> {
+        let recorded = &mut self.debug_arena[node].steps;
+        eyre::ensure!(
+            recorded.len() == steps.len() && recorded.iter().zip(&steps).all(|(a, b)| a.pc == b.pc),
+            "the re-execution of node {node} diverged from the recorded one"
+        );
+        *recorded = steps;
+        self.coarse_nodes.remove(&node);
+        Ok(())
+    }
+}
+
+/// Re-executes the transaction against the state it was first executed on, and returns the steps
+/// of the given node with the stack and memory recorded at every step.
+///
+/// The node is identified by its index in the debug arena, which is the same across executions
+/// since they are deterministic.
+pub fn record_node_snapshots<DBRef>(
+    db: &DBRef,
+    env: EnvWithHandlerCfg,
+    node: usize,
+) -> Result<Vec<DebugStep>>
+where
+    DBRef: DatabaseRef,
+    DBRef::Error: std::error::Error,
+{
+    let mut inspector =
+        DebugInspector::new().with_granularity(SnapshotGranularity::Call).with_full_snapshots(node);
+    let mut db = CacheDB::new(db);
+    let mut evm = new_evm_with_inspector(&mut db, env, &mut inspector);
+    evm.transact().map_err(|err| eyre!("failed to transact: {}", err))?;
+    drop(evm);
+
+    let mut arena = inspector.arena.arena;
+    eyre::ensure!(node < arena.len(), "the re-execution has no node {node}");
+    Ok(std::mem::take(&mut arena[node].steps))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use alloy_primitives::{address, bytes, Address, TxKind};
+    use revm::primitives::{AccountInfo, Bytecode, SpecId};
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+    use crate::artifact::debug::DebugNodeFlat;
+
+    #[test]
+    fn test_granularity() {
+        for granularity in SnapshotGranularity::ALL {
+            assert_eq!(granularity.to_string().parse(), Ok(granularity));
+            assert!(granularity.keeps(opcode::ADD, true));
+            assert!(granularity.keeps(opcode::CALL, false));
+        }
+        assert!("byte".parse::<SnapshotGranularity>().is_err());
+
+        assert!(SnapshotGranularity::Opcode.keeps(opcode::ADD, false));
+        assert!(!SnapshotGranularity::Call.keeps(opcode::ADD, false));
+        assert!(SnapshotGranularity::Sstore.keeps(opcode::SSTORE, false));
+        assert!(!SnapshotGranularity::Line.keeps(opcode::SSTORE, false));
+        assert!(SnapshotGranularity::Line.keeps(opcode::JUMPDEST, false));
+    }
+
+    #[test]
+    fn test_fill_snapshots() {
+        let steps = |stack: usize| {
+            (0..2)
+                .map(|pc| DebugStep { pc, stack: vec![U256::ZERO; stack], ..Default::default() })
+                .collect::<Vec<_>>()
+        };
+        let mut artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(Address::ZERO, CallKind::Call, 0, steps(0))],
+            coarse_nodes: HashSet::from([0]),
+            ..Default::default()
+        };
+
+        assert!(artifact.fill_snapshots(0, steps(1)[..1].to_vec()).is_err());
+        assert!(artifact.is_coarse(0));
+        artifact.fill_snapshots(0, steps(1)).unwrap();
+        assert!(!artifact.is_coarse(0));
+        assert_eq!(artifact.debug_arena[0].steps[1].stack.len(), 1);
+    }
+
+    #[test]
+    fn test_recording() {
+        // push1 1, push1 2, add, pop, stop
+        let contract = address!("00000000000000000000000000000000000000aa");
+        let code = Bytecode::new_raw(bytes!("6001600201500000"));
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(contract, AccountInfo { code: Some(code), ..Default::default() });
+
+        let mut env = EnvWithHandlerCfg::new_with_spec_id(Box::default(), SpecId::CANCUN);
+        env.tx.transact_to = TxKind::Call(contract);
+        let mut inspector = DebugInspector::new().with_granularity(SnapshotGranularity::Call);
+        let mut evm = new_evm_with_inspector(&mut db, env.clone(), &mut inspector);
+        evm.transact().unwrap();
+        drop(evm);
+
+        let steps = std::mem::take(&mut inspector.arena.arena[0].steps);
+        let mut artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(contract, CallKind::Call, 0, steps)],
+            coarse_nodes: HashSet::from([0]),
+            recording: Some(Arc::new(Recording::new(&db, env, SnapshotGranularity::Call))),
+            ..Default::default()
+        };
+        // the stack after the two pushes is only known once re-executed
+        assert!(artifact.debug_arena[0].steps[2].stack.is_empty());
+        assert!(artifact.load_snapshots(0).unwrap());
+        assert_eq!(artifact.debug_arena[0].steps[2].stack, vec![U256::from(1), U256::from(2)]);
+        assert!(!artifact.load_snapshots(0).unwrap());
+
+        artifact.unload_snapshots(0);
+        assert!(artifact.is_coarse(0));
+        assert!(artifact.debug_arena[0].steps[2].stack.is_empty());
+    }
+}
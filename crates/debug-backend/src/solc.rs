@@ -0,0 +1,227 @@
+//! The solc binaries managed by EDB, shared by every compilation.
+//!
+//! Compilers are downloaded from the official builds at <https://binaries.soliditylang.org> by
+//! version, verified against the checksum of the build list, and kept under `~/.edb/solc`, so
+//! that each version is only downloaded once. In offline mode, only the compilers downloaded
+//! already are used.
+//!
+//! There are no official builds for some platforms, e.g., Linux on ARM, on which the compilers
+//! are installed with `svm` as before, from its own builds.
+
+use std::{
+    collections::HashMap,
+    env::consts::{ARCH, EXE_SUFFIX, OS},
+    path::{Path, PathBuf},
+};
+
+use alloy_primitives::{keccak256, B256};
+use edb_utils::cache::CachePath;
+use eyre::{eyre, Result};
+use foundry_compilers::solc::Solc;
+use semver::Version;
+use serde::Deserialize;
+
+/// The base URL of the official solc builds.
+const BINARIES_URL: &str = "https://binaries.soliditylang.org";
+
+/// The list of the builds of a platform, as published in `<platform>/list.json`.
+#[derive(Clone, Debug, Deserialize)]
+struct BuildList {
+    builds: Vec<Build>,
+    /// The file name of the build of each release, by version.
+    releases: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Build {
+    path: String,
+    keccak256: B256,
+}
+
+impl BuildList {
+    /// Returns the release build of the given version, whatever its build metadata.
+    fn release(&self, version: &Version) -> Option<&Build> {
+        let version = Version::new(version.major, version.minor, version.patch);
+        let path = self.releases.get(&version.to_string())?;
+        self.builds.iter().find(|build| &build.path == path)
+    }
+}
+
+/// The solc installations under a directory, `~/.edb/solc` by default.
+#[derive(Clone, Debug)]
+pub struct SolcManager {
+    dir: PathBuf,
+    offline: bool,
+}
+
+impl Default for SolcManager {
+    fn default() -> Self {
+        let dir =
+            CachePath::edb_solc_dir().unwrap_or_else(|| std::env::temp_dir().join("edb-solc"));
+        Self::new(dir)
+    }
+}
+
+impl SolcManager {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, offline: false }
+    }
+
+    /// Only uses the compilers downloaded already, and never downloads any.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Returns the path of the binary of the given version: `<dir>/<version>/solc-<version>`.
+    pub fn path(&self, version: &Version) -> PathBuf {
+        let version = Version::new(version.major, version.minor, version.patch);
+        self.dir.join(version.to_string()).join(format!("solc-{version}{EXE_SUFFIX}"))
+    }
+
+    /// Returns the versions downloaded already, sorted.
+    pub fn installed(&self) -> Vec<Version> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else { return Vec::new() };
+        let mut versions: Vec<_> = entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<Version>().ok())
+            .filter(|version| self.path(version).is_file())
+            .collect();
+        versions.sort();
+        versions
+    }
+
+    /// Returns the compiler of the given version, downloading it first if it is missing.
+    pub async fn get(&self, version: &Version) -> Result<Solc> {
+        if let Err(e) = platform() {
+            debug!("{e}, falling back to svm");
+            return self.get_with_svm(version);
+        }
+        let path = self.path(version);
+        if !path.is_file() {
+            eyre::ensure!(
+                !self.offline,
+                "solc {version} is not installed under {}, and cannot be downloaded offline",
+                self.dir.display()
+            );
+            self.install(version).await?;
+        }
+        let version = Version::new(version.major, version.minor, version.patch);
+        Ok(Solc::new_with_version(path, version))
+    }
+
+    /// Returns the compiler of the given version installed by `svm`, installing it first if it is
+    /// missing, for the platforms without official builds.
+    fn get_with_svm(&self, version: &Version) -> Result<Solc> {
+        if self.offline {
+            return Solc::find_svm_installed_version(version)?.ok_or_else(|| {
+                eyre!("solc {version} is not installed by svm, and cannot be installed offline")
+            });
+        }
+        Ok(Solc::find_or_install(version)?)
+    }
+
+    /// Downloads the given version, and verifies it against the checksum of the build list.
+    pub async fn install(&self, version: &Version) -> Result<PathBuf> {
+        let platform = platform()?;
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("edb/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        info!("downloading solc {version}");
+
+        let list: BuildList = client
+            .get(format!("{BINARIES_URL}/{platform}/list.json"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let build = list
+            .release(version)
+            .ok_or_else(|| eyre!("solc {version} is not released for {platform}"))?;
+        eyre::ensure!(
+            !build.path.ends_with(".zip"),
+            "solc {version} is only released as an archive for {platform}, which is not supported"
+        );
+
+        let binary = client
+            .get(format!("{BINARIES_URL}/{platform}/{}", build.path))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        verify_checksum(&binary, build.keccak256)
+            .map_err(|e| eyre!("the download of solc {version} is corrupted: {e}"))?;
+
+        let path = self.path(version);
+        write_binary(&path, &binary)?;
+        Ok(path)
+    }
+}
+
+/// Returns the platform of the official builds for the current one.
+fn platform() -> Result<&'static str> {
+    match (OS, ARCH) {
+        ("linux", "x86_64") => Ok("linux-amd64"),
+        // the builds for macOS are universal binaries since 0.8.24, and run under Rosetta before
+        ("macos", _) => Ok("macosx-amd64"),
+        ("windows", "x86_64") => Ok("windows-amd64"),
+        _ => Err(eyre!("there are no official solc builds for {OS}-{ARCH}")),
+    }
+}
+
+fn verify_checksum(binary: &[u8], expected: B256) -> Result<()> {
+    let actual = keccak256(binary);
+    eyre::ensure!(actual == expected, "expected keccak256 {expected}, got {actual}");
+    Ok(())
+}
+
+/// Writes the binary next to its path and renames it, so that an interrupted download never
+/// leaves a broken compiler behind, e.g., when several sessions download the same version.
+fn write_binary(path: &Path, binary: &[u8]) -> Result<()> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(eyre!("invalid solc path {}", path.display()));
+    };
+    std::fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!("{}.{}.tmp", name.to_string_lossy(), std::process::id()));
+    std::fs::write(&tmp, binary)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_solc_manager() {
+        let list: BuildList = serde_json::from_str(
+            r#"{"builds":[{"path":"solc-linux-amd64-v0.8.20+commit.a1b79de6","version":"0.8.20",
+                "keccak256":"0x0000000000000000000000000000000000000000000000000000000000000001"}],
+                "releases":{"0.8.20":"solc-linux-amd64-v0.8.20+commit.a1b79de6"}}"#,
+        )
+        .unwrap();
+        assert!(list.release(&Version::parse("0.8.20+commit.a1b79de6").unwrap()).is_some());
+        assert!(list.release(&Version::new(0, 8, 21)).is_none());
+        assert!(verify_checksum(b"solc", keccak256(b"solc")).is_ok());
+        assert!(verify_checksum(b"solc", B256::with_last_byte(1)).is_err());
+
+        let dir = std::env::temp_dir().join(format!("edb-solc-test-{}", std::process::id()));
+        let manager = SolcManager::new(dir.clone()).offline(true);
+        let version = Version::parse("0.8.20+commit.a1b79de6").unwrap();
+        assert!(manager.get(&version).await.is_err());
+
+        write_binary(&manager.path(&version), b"solc").unwrap();
+        assert_eq!(manager.installed(), vec![Version::new(0, 8, 20)]);
+        let solc = manager.get(&version).await.unwrap();
+        assert_eq!(solc.solc, dir.join("0.8.20").join(format!("solc-0.8.20{EXE_SUFFIX}")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,21 +1,36 @@
 use alloy_primitives::Address;
 use eyre::{eyre, Result};
-use foundry_compilers::artifacts::DeployedBytecode;
+use foundry_compilers::artifacts::{Bytecode, BytecodeObject, DeployedBytecode, Libraries};
 
-pub fn link_contracts_fakely(contract: &mut DeployedBytecode, addr: Option<Address>) -> Result<()> {
-    let addr = addr.unwrap_or_default();
+/// Links the libraries of the compiled bytecode to the addresses found at their placeholders in
+/// the deployed code. When the two do not line up, the placeholders are replaced with the zero
+/// address instead, which still keeps the instruction layout intact.
+pub fn link_libraries(contract: &mut DeployedBytecode, deployed: &[u8]) -> Result<()> {
     let bytecode = contract.bytecode.as_mut().ok_or(eyre!("missing bytecode"))?;
+    let aligned = bytecode_bytes(bytecode).is_some_and(|bytes| bytes.len() == deployed.len());
 
     let references: Vec<_> = bytecode
         .link_references
         .iter()
         .flat_map(|(file, libraries)| {
-            libraries.iter().map(move |(library, _)| (file.clone(), library.clone()))
+            libraries.iter().map(move |(library, offsets)| {
+                let address = offsets
+                    .first()
+                    .filter(|_| aligned)
+                    .and_then(|offset| {
+                        let start = offset.start as usize;
+                        deployed.get(start..start + offset.length as usize)
+                    })
+                    .filter(|address| address.len() == 20)
+                    .map(Address::from_slice)
+                    .unwrap_or_default();
+                (file.clone(), library.clone(), address)
+            })
         })
         .collect();
 
-    for (file, library) in references {
-        bytecode.link(&file, &library, addr);
+    for (file, library, address) in references {
+        bytecode.link(&file, &library, address);
     }
 
     bytecode.object.resolve().ok_or(eyre!("object linking failed"))?;
@@ -23,6 +38,37 @@ pub fn link_contracts_fakely(contract: &mut DeployedBytecode, addr: Option<Addre
     Ok(())
 }
 
+/// Returns the name and the address of the libraries linked by the compiler settings.
+pub fn linked_libraries(libraries: &Libraries) -> Vec<(String, Address)> {
+    libraries
+        .libs
+        .values()
+        .flatten()
+        .filter_map(|(name, address)| Some((name.clone(), address.parse().ok()?)))
+        .collect()
+}
+
+/// Returns the raw bytes of the bytecode. Library placeholders of unlinked bytecode are replaced
+/// with the zero address, which keeps the instruction layout intact.
+pub fn bytecode_bytes(bytecode: &Bytecode) -> Option<Vec<u8>> {
+    match &bytecode.object {
+        BytecodeObject::Bytecode(bytes) => Some(bytes.to_vec()),
+        BytecodeObject::Unlinked(object) => {
+            let object = object.strip_prefix("0x").unwrap_or(object);
+            let mut resolved = String::with_capacity(object.len());
+            let mut rest = object;
+            while let Some(start) = rest.find("__") {
+                resolved.push_str(&rest[..start]);
+                // a placeholder is 40 characters long, just like the address it stands for
+                resolved.push_str(&"0".repeat(40));
+                rest = rest.get(start + 40..)?;
+            }
+            resolved.push_str(rest);
+            hex::decode(resolved).ok()
+        }
+    }
+}
+
 pub fn bytecode_similarity(bytecode1: &[u8], bytecode2: &[u8]) -> f64 {
     let len_s1 = bytecode1.len();
     let len_s2 = bytecode2.len();
@@ -43,3 +89,44 @@ pub fn bytecode_similarity(bytecode1: &[u8], bytecode2: &[u8]) -> f64 {
 
     return lcs_table[len_s1][len_s2] as f64 / len_s1.max(len_s2) as f64;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, keccak256};
+    use serde_json::json;
+
+    /// A `PUSH20 <library> STOP` snippet linking `lib/L.sol:L`.
+    fn unlinked() -> DeployedBytecode {
+        let placeholder = format!("__${}$__", &hex::encode(keccak256("lib/L.sol:L"))[..34]);
+        serde_json::from_value(json!({
+            "object": format!("73{placeholder}00"),
+            "linkReferences": { "lib/L.sol": { "L": [{ "start": 1, "length": 20 }] } },
+        }))
+        .unwrap()
+    }
+
+    fn linked_bytes(contract: &DeployedBytecode) -> Vec<u8> {
+        contract.bytecode.as_ref().unwrap().object.as_bytes().unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_link_libraries() {
+        let library = address!("00000000000000000000000000000000000000aa");
+        let mut deployed = vec![0x73];
+        deployed.extend_from_slice(library.as_slice());
+        deployed.push(0x00);
+
+        let mut contract = unlinked();
+        link_libraries(&mut contract, &deployed).unwrap();
+        assert_eq!(linked_bytes(&contract), deployed);
+
+        // the deployed code does not line up with the compiled one
+        let mut contract = unlinked();
+        link_libraries(&mut contract, &deployed[..21]).unwrap();
+        let mut expected = vec![0x73];
+        expected.extend_from_slice(Address::ZERO.as_slice());
+        expected.push(0x00);
+        assert_eq!(linked_bytes(&contract), expected);
+    }
+}
@@ -15,6 +15,21 @@ macro_rules! etherscan_rate_limit_guard {
         }
     };
 
+    // Sets the given flag whenever the rate limit is reached
+    ($request:expr, limited = $limited:expr) => {
+        loop {
+            match $request {
+                Ok(response) => break Ok(response),
+                Err(foundry_block_explorers::errors::EtherscanError::RateLimitExceeded) => {
+                    $limited = true;
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    continue
+                }
+                Err(e) => break Err(e),
+            }
+        }
+    };
+
     ($request:expr, $secs:expr) => {
         loop {
             match $request {
@@ -1,11 +1,18 @@
 //! Utils
 
+use alloy_primitives::{Address, Bytes, TxKind, B256, U256};
+use edb_utils::profile::{span, Phase};
 use revm::{
+    db::CacheDB,
     inspector_handle_register,
-    primitives::{EnvWithHandlerCfg, SpecId},
-    Context, Database, Evm, EvmContext, Handler, Inspector,
+    inspectors::NoOpInspector,
+    primitives::{AccountInfo, Bytecode, EnvWithHandlerCfg, ExecutionResult, SpecId},
+    Context, Database, DatabaseRef, Evm, EvmContext, Handler, Inspector,
 };
 
+/// Gas limit of read-only calls made by the debugger itself.
+const STATIC_CALL_GAS_LIMIT: u64 = 1_000_000;
+
 /// Get the gas used, accounting for refunds
 #[inline]
 pub fn gas_used(spec: SpecId, spent: u64, refunded: u64) -> u64 {
@@ -13,6 +20,35 @@ pub fn gas_used(spec: SpecId, spent: u64, refunded: u64) -> u64 {
     spent - (refunded).min(spent / refund_quotient)
 }
 
+/// A database timing the accesses to the underlying one, i.e., the fetches from the RPC endpoint
+/// of a fork, for the profiler.
+#[derive(Debug)]
+pub struct ProfiledDb<DBRef>(pub DBRef);
+
+impl<DBRef: DatabaseRef> DatabaseRef for ProfiledDb<DBRef> {
+    type Error = DBRef::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let _span = span(Phase::Rpc);
+        self.0.basic_ref(address)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        let _span = span(Phase::Rpc);
+        self.0.code_by_hash_ref(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let _span = span(Phase::Rpc);
+        self.0.storage_ref(address, index)
+    }
+
+    fn block_hash_ref(&self, number: U256) -> Result<B256, Self::Error> {
+        let _span = span(Phase::Rpc);
+        self.0.block_hash_ref(number)
+    }
+}
+
 /// Creates a new EVM with the given inspector.
 #[inline]
 pub fn new_evm_with_inspector<'a, DB, I>(
@@ -31,3 +67,34 @@ where
     handler.append_handler_register_plain(inspector_handle_register);
     Evm::new(context, handler)
 }
+
+/// Executes a read-only call on top of the given database, in the block of the given
+/// environment, and returns the output if the call succeeds.
+pub fn static_call<DBRef: DatabaseRef>(
+    db: DBRef,
+    env: &EnvWithHandlerCfg,
+    to: Address,
+    input: Bytes,
+) -> Option<Bytes> {
+    let mut env = env.clone();
+    env.tx.caller = Address::ZERO;
+    env.tx.transact_to = TxKind::Call(to);
+    env.tx.data = input;
+    env.tx.value = U256::ZERO;
+    env.tx.gas_limit = STATIC_CALL_GAS_LIMIT;
+    env.tx.gas_price = U256::ZERO;
+    env.tx.gas_priority_fee = None;
+    env.tx.nonce = None;
+    env.tx.access_list.clear();
+    env.tx.blob_hashes.clear();
+    env.tx.max_fee_per_blob_gas = None;
+    // the caller pays nothing for the call
+    env.block.basefee = U256::ZERO;
+
+    let mut db = CacheDB::new(db);
+    let mut evm = new_evm_with_inspector(&mut db, env, NoOpInspector);
+    match evm.transact().ok()?.result {
+        ExecutionResult::Success { output, .. } => Some(output.into_data()),
+        _ => None,
+    }
+}
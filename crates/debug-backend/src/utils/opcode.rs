@@ -1,6 +1,12 @@
-use revm::interpreter::{
-    opcode::{PUSH0, PUSH1, PUSH32},
-    OpCode,
+use std::ops::Range;
+
+use alloy_primitives::Bytes;
+use revm::{
+    interpreter::{
+        opcode::{PUSH0, PUSH1, PUSH32, RJUMPV},
+        OpCode,
+    },
+    primitives::{eof::EofHeader, Eof, EOF_MAGIC_BYTES},
 };
 use rustc_hash::FxHashMap;
 
@@ -84,27 +90,109 @@ impl IcPcMap {
     }
 }
 
-fn make_map<const PC_FIRST: bool>(code: &[u8]) -> FxHashMap<usize, usize> {
-    let mut map = FxHashMap::default();
-
-    let mut pc = 0;
-    let mut cumulative_push_size = 0;
-    while pc < code.len() {
-        let ic = pc - cumulative_push_size;
-        if PC_FIRST {
-            map.insert(pc, ic);
-        } else {
-            map.insert(ic, pc);
-        }
+/// An instruction of the bytecode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction<'a> {
+    /// The offset of the instruction in the bytecode. In EOF code, this is the offset in the
+    /// whole container.
+    pub pc: usize,
+    pub opcode: u8,
+    /// The immediate bytes, e.g., the bytes pushed by `PUSHn`.
+    pub immediate: &'a [u8],
+}
+
+/// Disassembles the bytecode. Legacy code is disassembled as a whole, while only the code
+/// sections of an EOF container are, so that its header and data are not mistaken for code.
+pub fn disassemble(code: &[u8]) -> Vec<Instruction<'_>> {
+    let (sections, is_eof) = match eof_code_sections(code) {
+        Some(sections) => (sections, true),
+        None => (vec![0..code.len()], false),
+    };
 
-        if (PUSH1..=PUSH32).contains(&code[pc]) {
-            // Skip the push bytes.
-            let push_size = (code[pc] - PUSH0) as usize;
-            pc += push_size;
-            cumulative_push_size += push_size;
+    let mut instructions = Vec::new();
+    for section in sections {
+        let mut pc = section.start;
+        while pc < section.end {
+            let size = immediate_size(&code[pc..section.end], is_eof);
+            let end = (pc + 1 + size).min(section.end);
+            instructions.push(Instruction { pc, opcode: code[pc], immediate: &code[pc + 1..end] });
+            pc = end;
         }
+    }
+    instructions
+}
+
+/// Returns the size of the immediate operand of the instruction at the start of `code`.
+///
+/// The opcodes introduced by EOF, e.g., `RJUMP`, only take immediates in EOF code, since they
+/// are invalid in legacy code.
+pub fn immediate_size(code: &[u8], is_eof: bool) -> usize {
+    let Some(&op) = code.first() else { return 0 };
+    if (PUSH1..=PUSH32).contains(&op) {
+        return (op - PUSH0) as usize;
+    }
+    if !is_eof {
+        return 0;
+    }
+    let Some(info) = OpCode::new(op).map(|op| op.info()) else { return 0 };
+    let mut size = info.immediate_size() as usize;
+    if op == RJUMPV {
+        // the immediate of `RJUMPV` is followed by a jump table of `max_index + 1` offsets
+        size += code.get(1).map_or(0, |max_index| (*max_index as usize + 1) * 2);
+    }
+    size
+}
+
+/// Returns the ranges of the code sections if the bytecode is an EOF container, or `None` if it
+/// is legacy code (or a malformed container, which is then treated as legacy code).
+pub fn eof_code_sections(code: &[u8]) -> Option<Vec<Range<usize>>> {
+    if !code.starts_with(&EOF_MAGIC_BYTES) {
+        return None;
+    }
+    let eof = Eof::decode(Bytes::copy_from_slice(code)).ok()?;
+    let sections = (0..eof.header.code_sizes.len())
+        .map(|index| {
+            let start = eof_code_section_offset(&eof.header, index);
+            start..start + eof.header.code_sizes[index] as usize
+        })
+        .collect();
+    Some(sections)
+}
+
+/// Returns the offset of the code section in its EOF container.
+pub fn eof_code_section_offset(header: &EofHeader, index: usize) -> usize {
+    let preceding: usize = header.code_sizes[..index].iter().map(|size| *size as usize).sum();
+    header.size() + header.types_size as usize + preceding
+}
+
+fn make_map<const PC_FIRST: bool>(code: &[u8]) -> FxHashMap<usize, usize> {
+    disassemble(code)
+        .into_iter()
+        .enumerate()
+        .map(|(ic, instruction)| if PC_FIRST { (instruction.pc, ic) } else { (ic, instruction.pc) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::interpreter::opcode::{ADD, RJUMP, STOP};
+
+    #[test]
+    fn test_disassemble() {
+        // legacy code, in which 0xe0 (RJUMP) is an invalid opcode without immediates
+        let legacy = [PUSH1, 0x01, RJUMP, 0x00, STOP];
+        let pcs: Vec<_> = disassemble(&legacy).iter().map(|i| i.pc).collect();
+        assert_eq!(pcs, [0, 2, 3, 4]);
 
-        pc += 1;
+        // a container with a single code section `RJUMP 0, ADD, STOP` and no data
+        let mut eof = vec![0xef, 0x00, 0x01, 0x01, 0x00, 0x04, 0x02, 0x00, 0x01, 0x00, 0x05];
+        eof.extend([0x04, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00]);
+        eof.extend([RJUMP, 0x00, 0x00, ADD, STOP]);
+        let instructions = disassemble(&eof);
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(instructions[0].pc, 19);
+        assert_eq!(instructions[0].immediate, [0x00, 0x00]);
+        assert_eq!(PcIcMap::new(&eof).get(22), Some(1));
     }
-    map
 }
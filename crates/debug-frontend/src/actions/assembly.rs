@@ -0,0 +1,23 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use eyre::Result;
+
+use crate::context::FrontendContext;
+
+impl<'a> FrontendContext<'a> {
+    pub fn handle_key_event_in_assembly(&mut self, event: KeyEvent) -> Result<()> {
+        match event.code {
+            // Go to the previous Yul statement
+            KeyCode::Char('k') | KeyCode::Up => self.repeat(|this| this.step_yul(false))?,
+            // Go to the next Yul statement
+            KeyCode::Char('j') | KeyCode::Down => self.repeat(|this| this.step_yul(true))?,
+            // Step a single opcode, e.g., to get into an assembly block
+            KeyCode::Char('K') => self.repeat(Self::step_back)?,
+            KeyCode::Char('J') => self.repeat(Self::step)?,
+            // Show the reference of the current opcode
+            KeyCode::Char('i') => self.inspect_opcode()?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use eyre::Result;
+
+use crate::context::FrontendContext;
+
+impl<'a> FrontendContext<'a> {
+    pub fn handle_key_event_in_compare(&mut self, event: KeyEvent) -> Result<()> {
+        match event.code {
+            // Move up, which moves the compared session as well
+            KeyCode::Char('k') | KeyCode::Up => self.repeat(Self::step_back)?,
+            // Move down, which moves the compared session as well
+            KeyCode::Char('j') | KeyCode::Down => self.repeat(Self::step)?,
+            // Go to the first divergence
+            KeyCode::Char('d') => self.goto_divergence()?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,60 @@
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
+use eyre::Result;
 
-use crate::context::FrontendContext;
+use crate::{
+    context::{FrontendContext, GovernanceRow, UserOpRow},
+    window::PaneView,
+};
 
 impl<'a> FrontendContext<'a> {
-    pub fn handle_key_even_in_data(&mut self, event: KeyEvent) {
-        // todo
+    pub fn handle_key_even_in_data(&mut self, event: KeyEvent) -> Result<()> {
+        let view = self.window.get_focused_view()?;
+        // List views move the cursor, while the other ones scroll. Either way, the state is
+        // clamped to the content while drawing.
+        match event.code {
+            KeyCode::Char('k') | KeyCode::Up => self.repeat(|this| this.scroll_focused(true, 1))?,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.repeat(|this| this.scroll_focused(false, 1))?
+            }
+            // Switch to the selected session
+            KeyCode::Enter if view == PaneView::Sessions => {
+                self.switch_session(self.view_state(view).cursor)
+            }
+            // Jump to the selected storage access
+            KeyCode::Enter if view == PaneView::Storage => {
+                if let Some(access) = self.session.storage_history.get(self.view_state(view).cursor)
+                {
+                    self.goto_step(access.step)?;
+                }
+            }
+            // Jump to the selected user operation, phase, or transfer
+            KeyCode::Enter if view == PaneView::UserOps => {
+                let cursor = self.view_state(view).cursor;
+                if let Some(step) = self.user_op_rows().get(cursor).map(UserOpRow::first_step) {
+                    self.goto_step(step)?;
+                }
+            }
+            // Jump to the selected proposal action, if it was carried out
+            KeyCode::Enter if view == PaneView::Governance => {
+                let cursor = self.view_state(view).cursor;
+                if let Some(step) =
+                    self.governance_rows().get(cursor).and_then(GovernanceRow::first_step)
+                {
+                    self.goto_step(step)?;
+                }
+            }
+            // Jump to the step emitting the selected event
+            KeyCode::Enter if view == PaneView::Events => {
+                let cursor = self.view_state(view).cursor;
+                if let Some(step) = self.session.events().get(cursor).map(|event| event.step) {
+                    self.goto_step(step)?;
+                }
+            }
+            // Watch the slot accessed by the current step
+            KeyCode::Char('s') if view == PaneView::Storage => self.watch_current_slot()?,
+            _ => {}
+        }
+
+        Ok(())
     }
 }
@@ -1,4 +1,7 @@
+mod assembly;
+mod compare;
 mod data;
+mod motion;
 mod opcode;
 mod source;
 mod trace;
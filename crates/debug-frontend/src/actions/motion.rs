@@ -0,0 +1,76 @@
+//! Vim-like motions, which are shared by all panes but the terminal and compose with counts,
+//! e.g., `5j`, `10gg`, or `3G`.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::Result;
+
+use crate::context::{buffer_as_number, FrontendContext};
+
+impl<'a> FrontendContext<'a> {
+    /// Handles a count or a motion in the focused pane, returning whether the key is consumed.
+    ///
+    /// In the panes stepping through the execution, `gg` and `G` go to the first and the last
+    /// step, or to the step given by the count. In the other panes, they go to the top and the
+    /// bottom. `ctrl-d` and `ctrl-u` scroll half a page, in every pane.
+    pub(crate) fn handle_motion(&mut self, event: KeyEvent) -> Result<bool> {
+        let view = self.window.get_focused_view()?;
+        let stepping = view.can_step();
+        let shift = event.modifiers.contains(KeyModifiers::SHIFT);
+        let control = event.modifiers.contains(KeyModifiers::CONTROL);
+        let count = (!self.key_buffer.trim_end_matches('g').is_empty())
+            .then(|| buffer_as_number(&self.key_buffer));
+
+        match event.code {
+            // Counts, where a leading zero is not a count
+            KeyCode::Char(c @ '0'..='9')
+                if !control && !(c == '0' && self.key_buffer.is_empty()) =>
+            {
+                // A count after a pending `g` starts over
+                self.key_buffer.retain(|c| c.is_ascii_digit());
+                self.key_buffer.push(c);
+                return Ok(true);
+            }
+            // The first `g` of `gg` is pending
+            KeyCode::Char('g') if !control && !self.key_buffer.ends_with('g') => {
+                self.key_buffer.push('g');
+                return Ok(true);
+            }
+            KeyCode::Char('g') if !control && stepping => {
+                self.goto_step(count.map_or(0, |count| count - 1))?
+            }
+            KeyCode::Char('g') if !control => self.update_view_state(view, |state| {
+                state.offset = 0;
+                state.cursor = 0;
+            }),
+            KeyCode::Char('G') if shift && stepping => {
+                let last = self.session.artifact.steps().count().saturating_sub(1);
+                self.goto_step(count.map_or(last, |count| count - 1))?
+            }
+            KeyCode::Char('G') if shift => self.update_view_state(view, |state| {
+                // The state is clamped to the content while drawing
+                state.offset = usize::MAX;
+                state.cursor = usize::MAX;
+            }),
+            KeyCode::Char('d') if control => {
+                let rows = self.focused_pane_height()? / 2 * count.unwrap_or(1);
+                self.scroll_focused(false, rows.max(1))?
+            }
+            KeyCode::Char('u') if control => {
+                let rows = self.focused_pane_height()? / 2 * count.unwrap_or(1);
+                self.scroll_focused(true, rows.max(1))?
+            }
+            _ => return Ok(false),
+        }
+
+        self.key_buffer.clear();
+        Ok(true)
+    }
+
+    /// Returns the number of rows inside the borders of the focused pane.
+    fn focused_pane_height(&self) -> Result<usize> {
+        let layout = self.window.get_flattened_layout(self.window.screen_size)?;
+        // The zoomed pane comes last
+        let pane = layout.iter().rev().find(|pane| pane.focused);
+        Ok(pane.map_or(0, |pane| pane.rect.height.saturating_sub(2) as usize))
+    }
+}
@@ -1,9 +1,63 @@
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::Result;
 
 use crate::context::FrontendContext;
 
 impl<'a> FrontendContext<'a> {
-    pub fn handle_key_event_in_opcode(&mut self, event: KeyEvent) {
-        // todo
+    pub fn handle_key_event_in_opcode(&mut self, event: KeyEvent) -> Result<()> {
+        let control = event.modifiers.contains(KeyModifiers::CONTROL);
+        match event.code {
+            // Scroll manually, which stops following the execution
+            KeyCode::Char('k') | KeyCode::Up if control => {
+                self.repeat(|this| this.scroll_focused(true, 1))?
+            }
+            KeyCode::Char('j') | KeyCode::Down if control => {
+                self.repeat(|this| this.scroll_focused(false, 1))?
+            }
+            // Move up, skipping collapsed loops as a whole
+            KeyCode::Char('k') | KeyCode::Up => self.repeat(|this| {
+                this.ensure_movable()?;
+                let row = this.current_op_row();
+                if this.session.current_step > this.session.op_rows[row].first_step() {
+                    this.session.current_step = this.session.op_rows[row].first_step();
+                } else if row > 0 {
+                    this.session.current_step = this.session.op_rows[row - 1].first_step();
+                } else {
+                    this.step_back()?;
+                }
+                Ok(())
+            })?,
+            // Move down, skipping collapsed loops as a whole
+            KeyCode::Char('j') | KeyCode::Down => self.repeat(|this| {
+                this.ensure_movable()?;
+                let row = this.current_op_row();
+                match this.session.op_rows.get(row + 1) {
+                    Some(next) => this.session.current_step = next.first_step(),
+                    None => this.step()?,
+                }
+                Ok(())
+            })?,
+            // Move down, into any call
+            KeyCode::Char('J') => self.repeat(Self::step_into)?,
+            // Go to the previous branch decision
+            KeyCode::Char('[') => self.repeat(Self::prev_branch)?,
+            // Go to the next branch decision
+            KeyCode::Char(']') => self.repeat(Self::next_branch)?,
+            // Expand or collapse the loop
+            KeyCode::Char('e') => self.toggle_loop()?,
+            // Toggle following the execution
+            KeyCode::Char('f') => self.toggle_follow()?,
+            // Jump to the current execution point
+            KeyCode::Char('c') => self.jump_to_current()?,
+            // Show the reference of the current opcode
+            KeyCode::Char('i') => self.inspect_opcode()?,
+            // Set or remove a breakpoint on the current source line
+            KeyCode::Char('b') => self.toggle_breakpoint()?,
+            // Continue to the next breakpoint
+            KeyCode::Char('n') => self.repeat(Self::continue_to_breakpoint)?,
+            _ => {}
+        }
+
+        Ok(())
     }
 }
@@ -1,9 +1,42 @@
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::Result;
 
 use crate::context::FrontendContext;
 
 impl<'a> FrontendContext<'a> {
-    pub fn handle_key_event_in_source(&mut self, event: KeyEvent) {
-        // todo
+    pub fn handle_key_event_in_source(&mut self, event: KeyEvent) -> Result<()> {
+        let control = event.modifiers.contains(KeyModifiers::CONTROL);
+        match event.code {
+            // Scroll manually, which stops following the execution
+            KeyCode::Char('k') | KeyCode::Up if control => {
+                self.repeat(|this| this.scroll_focused(true, 1))?
+            }
+            KeyCode::Char('j') | KeyCode::Down if control => {
+                self.repeat(|this| this.scroll_focused(false, 1))?
+            }
+            // Move up
+            KeyCode::Char('k') | KeyCode::Up => self.repeat(Self::step_back)?,
+            // Move down
+            KeyCode::Char('j') | KeyCode::Down => self.repeat(Self::step)?,
+            // Move down, into any call
+            KeyCode::Char('J') => self.repeat(Self::step_into)?,
+            // Go to the previous branch decision
+            KeyCode::Char('[') => self.repeat(Self::prev_branch)?,
+            // Go to the next branch decision
+            KeyCode::Char(']') => self.repeat(Self::next_branch)?,
+            // Toggle following the execution
+            KeyCode::Char('f') => self.toggle_follow()?,
+            // Jump to the current execution point
+            KeyCode::Char('c') => self.jump_to_current()?,
+            // Show the reference of the current opcode
+            KeyCode::Char('i') => self.inspect_opcode()?,
+            // Set or remove a breakpoint on the current source line
+            KeyCode::Char('b') => self.toggle_breakpoint()?,
+            // Continue to the next breakpoint
+            KeyCode::Char('n') => self.repeat(Self::continue_to_breakpoint)?,
+            _ => {}
+        }
+
+        Ok(())
     }
 }
@@ -1,9 +1,26 @@
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::Result;
 
 use crate::context::FrontendContext;
 
 impl<'a> FrontendContext<'a> {
-    pub fn handle_key_event_in_trace(&mut self, event: KeyEvent) {
-        // todo
+    pub fn handle_key_event_in_trace(&mut self, event: KeyEvent) -> Result<()> {
+        let control = event.modifiers.contains(KeyModifiers::CONTROL);
+        match event.code {
+            // Scroll manually, which stops following the execution
+            KeyCode::Char('k') | KeyCode::Up if control => {
+                self.repeat(|this| this.scroll_focused(true, 1))?
+            }
+            KeyCode::Char('j') | KeyCode::Down if control => {
+                self.repeat(|this| this.scroll_focused(false, 1))?
+            }
+            // Toggle following the execution
+            KeyCode::Char('f') => self.toggle_follow()?,
+            // Jump to the current execution point
+            KeyCode::Char('c') => self.jump_to_current()?,
+            _ => {}
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,172 @@
+//! Blackboxed contracts, e.g., libraries or well-known routers, whose calls are stepped over, so
+//! that stepping stays in the code being debugged. The list is kept in `~/.edb/blackbox.json`,
+//! and starts with [`DEFAULT_BLACKBOX`] until the file is first saved.
+
+use std::{collections::BTreeSet, fmt, fs, path::PathBuf, str::FromStr};
+
+use alloy_primitives::Address;
+use edb_debug_backend::artifact::debug::DebugArtifact;
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+/// The contracts blackboxed unless the list was edited: well-known routers and helpers which are
+/// called externally, so that they get frames of their own, but are rarely what is debugged.
+/// Libraries with internal functions only, e.g., `SafeERC20`, are inlined into their callers
+/// and never get a frame, so that they cannot be blackboxed.
+pub const DEFAULT_BLACKBOX: &[&str] = &[
+    "WETH9",
+    "Multicall3",
+    "Permit2",
+    "UniswapV2Router02",
+    "SwapRouter",
+    "SwapRouter02",
+    "UniversalRouter",
+];
+
+/// A blackboxed contract, given by its address or by the name of its verified contract.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum BlackboxEntry {
+    Address(Address),
+    Contract(String),
+}
+
+impl BlackboxEntry {
+    /// Returns whether the code at the address is blackboxed by this entry.
+    pub fn matches(&self, artifact: &DebugArtifact, address: &Address) -> bool {
+        match self {
+            Self::Address(blackboxed) => blackboxed == address,
+            Self::Contract(name) => artifact.contract_name(address) == Some(name.as_str()),
+        }
+    }
+}
+
+impl FromStr for BlackboxEntry {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.starts_with("0x") {
+            return s.parse().map(Self::Address).map_err(|e| eyre!("invalid address: {e}"));
+        }
+        if s.is_empty() || !s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$') {
+            return Err(eyre!("expected an address or a contract name, e.g., UniswapV2Router02"));
+        }
+        Ok(Self::Contract(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for BlackboxEntry {
+    type Error = eyre::Report;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<BlackboxEntry> for String {
+    fn from(entry: BlackboxEntry) -> Self {
+        entry.to_string()
+    }
+}
+
+impl fmt::Display for BlackboxEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "{address}"),
+            Self::Contract(name) => f.write_str(name),
+        }
+    }
+}
+
+/// The list of blackboxed contracts, backed by a file.
+#[derive(Debug, Default)]
+pub struct BlackboxFile {
+    path: Option<PathBuf>,
+    entries: BTreeSet<BlackboxEntry>,
+}
+
+impl BlackboxFile {
+    /// Loads the blackbox file at the given path. A missing or malformed file holds the
+    /// [`DEFAULT_BLACKBOX`] contracts.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(entries) => Some(entries),
+                Err(e) => {
+                    warn!("ignoring malformed blackbox file {path:?}: {e}");
+                    None
+                }
+            })
+            .unwrap_or_else(|| {
+                DEFAULT_BLACKBOX
+                    .iter()
+                    .map(|name| BlackboxEntry::Contract(name.to_string()))
+                    .collect()
+            });
+        Self { path, entries }
+    }
+
+    /// Saves the blackbox file.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+
+    /// Adds the entry, or removes it if it is already listed. Returns whether it is listed.
+    pub fn toggle(&mut self, entry: BlackboxEntry) -> bool {
+        if self.entries.remove(&entry) {
+            false
+        } else {
+            self.entries.insert(entry);
+            true
+        }
+    }
+
+    /// Returns whether the code at the address is blackboxed.
+    pub fn contains(&self, artifact: &DebugArtifact, address: &Address) -> bool {
+        self.entries.iter().any(|entry| entry.matches(artifact, address))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blackbox_file() {
+        let dir = std::env::temp_dir().join(format!("edb-blackbox-{}", std::process::id()));
+        let path = dir.join("blackbox.json");
+        let router = Address::with_last_byte(1);
+        let artifact = DebugArtifact::default();
+
+        assert!("0x12".parse::<BlackboxEntry>().is_err());
+        assert!("Safe ERC20".parse::<BlackboxEntry>().is_err());
+        let mut blackbox = BlackboxFile::load(Some(path.clone()));
+        assert!(blackbox.entries.contains(&BlackboxEntry::Contract("WETH9".to_string())));
+        assert_eq!(blackbox.entries.len(), DEFAULT_BLACKBOX.len());
+        assert!(blackbox.toggle(router.to_string().parse().unwrap()));
+        assert!(!blackbox.toggle("WETH9".parse().unwrap()));
+        blackbox.save().unwrap();
+
+        let mut blackbox = BlackboxFile::load(Some(path));
+        assert!(blackbox.contains(&artifact, &router));
+        assert!(!blackbox.contains(&artifact, &Address::ZERO));
+        assert!(!blackbox.entries.contains(&BlackboxEntry::Contract("WETH9".to_string())));
+        assert!(!blackbox.toggle(BlackboxEntry::Address(router)));
+        assert!(!blackbox.contains(&artifact, &router));
+        assert!(!blackbox.is_empty());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,207 @@
+//! Source breakpoints, which are kept across sessions in `~/.edb/breakpoints.json`, and function
+//! breakpoints, which are kept in `~/.edb/function_breakpoints.json`.
+//!
+//! Source breakpoints are keyed by the code hash of the contract rather than its address, so that
+//! they are restored whenever the same contract is debugged again, in any transaction and at any
+//! address. Function breakpoints are resolved in the contracts of each session instead.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alloy_primitives::B256;
+use edb_debug_backend::analysis::entry::FunctionTarget;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+/// A breakpoint on a line of a source file of a contract.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub code_hash: B256,
+    pub path: PathBuf,
+    /// The 1-based line number.
+    pub line: usize,
+}
+
+/// The set of breakpoints, backed by a file.
+#[derive(Debug, Default)]
+pub struct BreakpointFile {
+    path: Option<PathBuf>,
+    breakpoints: BTreeSet<Breakpoint>,
+}
+
+impl BreakpointFile {
+    /// Loads the breakpoint file at the given path. A missing or malformed file is treated as
+    /// empty.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let breakpoints = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(breakpoints) => Some(breakpoints),
+                Err(e) => {
+                    warn!("ignoring malformed breakpoint file {path:?}: {e}");
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self { path, breakpoints }
+    }
+
+    /// Saves the breakpoint file.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.breakpoints)?)?;
+        Ok(())
+    }
+
+    /// Adds the breakpoint, or removes it if it is already set. Returns whether it is set.
+    pub fn toggle(&mut self, breakpoint: Breakpoint) -> bool {
+        if self.breakpoints.remove(&breakpoint) {
+            false
+        } else {
+            self.breakpoints.insert(breakpoint);
+            true
+        }
+    }
+
+    /// Sets or removes the breakpoint.
+    pub fn set(&mut self, breakpoint: Breakpoint, enabled: bool) {
+        if enabled {
+            self.breakpoints.insert(breakpoint);
+        } else {
+            self.breakpoints.remove(&breakpoint);
+        }
+    }
+
+    /// Returns whether a breakpoint is set on the given line.
+    pub fn contains(&self, code_hash: B256, path: &Path, line: usize) -> bool {
+        self.lines(code_hash, path).any(|l| l == line)
+    }
+
+    /// Returns the lines with a breakpoint in the given source file of a contract.
+    pub fn lines(&self, code_hash: B256, path: &Path) -> impl Iterator<Item = usize> + '_ {
+        let path = path.to_path_buf();
+        let first = Breakpoint { code_hash, path: path.clone(), line: 0 };
+        let last = Breakpoint { code_hash, path, line: usize::MAX };
+        self.breakpoints.range(first..=last).map(|bp| bp.line)
+    }
+
+    /// Returns all the breakpoints, ordered by contract, file and line.
+    pub fn iter(&self) -> impl Iterator<Item = &Breakpoint> + '_ {
+        self.breakpoints.iter()
+    }
+
+    /// Returns the number of breakpoints.
+    pub fn count(&self) -> usize {
+        self.breakpoints.len()
+    }
+}
+
+/// The breakpoints on the entry of functions, in the order they were set, backed by a file.
+#[derive(Debug, Default)]
+pub struct FunctionBreakpointFile {
+    path: Option<PathBuf>,
+    targets: Vec<FunctionTarget>,
+}
+
+impl FunctionBreakpointFile {
+    /// Loads the function breakpoint file at the given path. A missing or malformed file is
+    /// treated as empty.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let targets = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(targets) => Some(targets),
+                Err(e) => {
+                    warn!("ignoring malformed function breakpoint file {path:?}: {e}");
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self { path, targets }
+    }
+
+    /// Saves the function breakpoint file.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.targets)?)?;
+        Ok(())
+    }
+
+    /// Adds the breakpoint, or removes it if it is already set. Returns whether it is set.
+    pub fn toggle(&mut self, target: FunctionTarget) -> bool {
+        if let Some(index) = self.targets.iter().position(|t| *t == target) {
+            self.targets.remove(index);
+            false
+        } else {
+            self.targets.push(target);
+            true
+        }
+    }
+
+    /// Returns the functions with a breakpoint.
+    pub fn targets(&self) -> &[FunctionTarget] {
+        &self.targets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakpoint_file() {
+        let dir = std::env::temp_dir().join(format!("edb-breakpoints-{}", std::process::id()));
+        let path = dir.join("breakpoints.json");
+        let code_hash = B256::with_last_byte(1);
+        let file = Path::new("src/Token.sol");
+
+        let mut breakpoints = BreakpointFile::load(Some(path.clone()));
+        assert!(breakpoints.toggle(Breakpoint { code_hash, path: file.into(), line: 42 }));
+        assert!(breakpoints.toggle(Breakpoint { code_hash, path: file.into(), line: 7 }));
+        breakpoints.save().unwrap();
+
+        let mut breakpoints = BreakpointFile::load(Some(path));
+        assert!(breakpoints.contains(code_hash, file, 42));
+        assert!(!breakpoints.contains(B256::ZERO, file, 42));
+        assert!(!breakpoints.contains(code_hash, Path::new("src/Other.sol"), 42));
+        assert_eq!(breakpoints.lines(code_hash, file).collect::<Vec<_>>(), vec![7, 42]);
+        assert!(!breakpoints.toggle(Breakpoint { code_hash, path: file.into(), line: 42 }));
+        assert_eq!(breakpoints.count(), 1);
+        assert_eq!(breakpoints.iter().map(|bp| bp.line).collect::<Vec<_>>(), vec![7]);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_function_breakpoint_file() {
+        let dir =
+            std::env::temp_dir().join(format!("edb-function-breakpoints-{}", std::process::id()));
+        let path = dir.join("function_breakpoints.json");
+        let withdraw: FunctionTarget = "Vault.withdraw(uint256)".parse().unwrap();
+        let selector: FunctionTarget = "0xa9059cbb".parse().unwrap();
+
+        let mut breakpoints = FunctionBreakpointFile::load(Some(path.clone()));
+        assert!(breakpoints.targets().is_empty());
+        assert!(breakpoints.toggle(withdraw.clone()));
+        assert!(breakpoints.toggle(selector.clone()));
+        breakpoints.save().unwrap();
+
+        let mut breakpoints = FunctionBreakpointFile::load(Some(path));
+        assert_eq!(breakpoints.targets(), [withdraw.clone(), selector.clone()]);
+        assert!(!breakpoints.toggle(withdraw));
+        assert_eq!(breakpoints.targets(), [selector]);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
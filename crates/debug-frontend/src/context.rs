@@ -1,27 +1,121 @@
 //! Debugger context and event handler implementation.
 
-use alloy_primitives::Address;
+use alloy_primitives::{Address, B256, U256};
 use crossterm::event::{
     Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
-use edb_debug_backend::artifact::debug::{DebugArtifact, DebugNodeFlat, DebugStep};
+use edb_debug_backend::{
+    analysis::{
+        deployment::{create2_address, create_address, InitCode},
+        diff::Divergence,
+        entry::FunctionTarget,
+        events::EventFilter,
+        funds::Transfer,
+        governance::GovernanceExecution,
+        heatmap::HeatMap,
+        signature::verify_signature,
+        suggest::suggest_steps,
+        taint::taint_analysis,
+        userop::{UserOpBundle, UserOpPhase},
+    },
+    artifact::{
+        debug::{DebugNodeFlat, DebugStep, LoopSummary},
+        usage::{format_bytes, MemoryUsage},
+    },
+    export::signature::signature_report,
+    reference::OpcodeDoc,
+};
+use edb_utils::cache::CachePath;
 use eyre::Result;
-use ratatui::layout::{Direction, Rect};
+use ratatui::layout::{Direction, Position, Rect};
+use revm::interpreter::opcode;
 use revm_inspectors::tracing::types::CallKind;
 use serde::de;
-use std::ops::ControlFlow;
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Write,
+    ops::{ControlFlow, Range},
+    sync::mpsc::{Receiver, Sender, TryRecvError},
+};
 
 use crate::{
+    blackbox::{BlackboxEntry, BlackboxFile},
+    breakpoint::{Breakpoint, BreakpointFile, FunctionBreakpointFile},
     core::ExitReason,
-    window::{PaneView, TerminalMode, VirtCoord, Window},
+    draw::PaneCache,
+    machine::Location,
+    session::{Session, SessionState},
+    stepping::{LineTracker, SkippedCalls},
+    sync::{SyncCommand, SyncDir, SyncLocation},
+    theme::Theme,
+    utils::key::normalize_key_event,
+    window::{
+        parse_create2, parse_deployer, parse_slot, DialogAction, PaneView, PopupOutcome,
+        ScreenManager, TerminalMode, VirtCoord, Window,
+    },
 };
 
-/// This is currently used to remember last scroll position so screen doesn't wiggle as much.
+/// The state of the debugger set by the user, which is carried over when the frontend is rebuilt,
+/// e.g., after re-running the transaction.
+#[derive(Clone, Debug, Default)]
+pub struct FrontendState {
+    view_states: BTreeMap<PaneView, ViewState>,
+    event_breakpoint: Option<EventFilter>,
+    stack_labels: bool,
+    buf_utf: bool,
+    show_shortcuts: bool,
+    /// The state of each session, in tab order.
+    sessions: Vec<SessionState>,
+}
+
+/// The call currently being shown.
 #[derive(Default)]
 pub struct DrawMemory {
     pub inner_call_index: usize,
-    pub current_buf_startline: usize,
-    pub current_stack_startline: usize,
+}
+
+/// The scroll offset and the cursor of a view, which are kept while the view is not shown, e.g.,
+/// when its pane loses focus, when it is swapped out, or when the layout profile changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ViewState {
+    /// The first visible row.
+    pub offset: usize,
+    /// The selected row, for views with selectable rows.
+    pub cursor: usize,
+    /// Whether the view scrolls to the current execution point on the next draw, even if its
+    /// pane does not follow the execution.
+    pub recenter: bool,
+}
+
+/// The position of the execution the panes are drawn at. Each view reads only part of it, so that
+/// moving the position only draws again the panes whose part moved, see [`Self::read_by`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionPosition {
+    /// The tab index of the active session.
+    pub session: usize,
+    /// The deepest call frame containing the step, and the number of frames entered so far.
+    pub frame: (Option<usize>, usize),
+    /// The index of the current node.
+    pub call: usize,
+    /// The index of the current step in the node.
+    pub step: usize,
+}
+
+impl ExecutionPosition {
+    /// Returns the part of the position the view is drawn from, with the rest left out. The
+    /// warnings only depend on the session, the trace on the call frames, and the contracts on
+    /// the current call.
+    pub fn read_by(self, view: PaneView) -> Self {
+        let session = Self { session: self.session, ..Default::default() };
+        match view {
+            PaneView::Warnings => session,
+            PaneView::Trace => Self { frame: self.frame, ..session },
+            PaneView::Contracts => Self { call: self.call, ..session },
+            _ => self,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -43,21 +137,139 @@ impl std::fmt::Display for RecoverableError {
 
 impl std::error::Error for RecoverableError {}
 
+/// Loops with fewer iterations are not worth collapsing.
+const MIN_LOOP_ITERATIONS: usize = 3;
+
+/// The number of addresses predicted for a deployer.
+const PREDICTED_ADDRESSES: u64 = 5;
+
+/// A row in the opcode list, which is either a single step or a collapsed loop.
+#[derive(Clone, Copy, Debug)]
+pub enum OpRow {
+    Step(usize),
+    Loop(LoopSummary),
+}
+
+impl OpRow {
+    /// Returns the first step of this row.
+    pub fn first_step(&self) -> usize {
+        match self {
+            Self::Step(step) => *step,
+            Self::Loop(summary) => summary.start,
+        }
+    }
+}
+
+/// A row of the user operation pane, which jumps to the step it starts at when selected.
+#[derive(Clone, Copy, Debug)]
+pub enum UserOpRow<'a> {
+    Bundle(&'a UserOpBundle),
+    /// An operation of a bundle, by index.
+    Op(&'a UserOpBundle, usize),
+    Phase(UserOpPhase, &'a Range<usize>),
+    Transfer(&'a Transfer),
+}
+
+impl UserOpRow<'_> {
+    /// Returns the step this row starts at.
+    pub fn first_step(&self) -> usize {
+        match self {
+            Self::Bundle(bundle) => bundle.steps.start,
+            Self::Op(bundle, i) => {
+                bundle.ops[*i].phases.first().map_or(bundle.steps.start, |(_, steps)| steps.start)
+            }
+            Self::Phase(_, steps) => steps.start,
+            Self::Transfer(transfer) => transfer.step,
+        }
+    }
+}
+
+/// A row of the governance pane, which jumps to the step it starts at when selected.
+#[derive(Clone, Copy, Debug)]
+pub enum GovernanceRow<'a> {
+    Execution(&'a GovernanceExecution),
+    /// An action of an execution, by index.
+    Action(&'a GovernanceExecution, usize),
+}
+
+impl GovernanceRow<'_> {
+    /// Returns the step this row starts at, which is unknown for actions never carried out.
+    pub fn first_step(&self) -> Option<usize> {
+        match self {
+            Self::Execution(execution) => Some(execution.steps.start),
+            Self::Action(execution, i) => {
+                execution.actions[*i].steps.as_ref().map(|steps| steps.start)
+            }
+        }
+    }
+}
+
+/// Two sessions compared step by step.
+pub struct Comparison {
+    /// The tab indices of the compared sessions.
+    pub sessions: [usize; 2],
+    pub divergence: Divergence,
+}
+
+impl Comparison {
+    /// Returns the tab index of the session compared with the given one, if it is compared.
+    pub fn peer(&self, session: usize) -> Option<usize> {
+        match self.sessions {
+            [left, right] if left == session => Some(right),
+            [left, right] if right == session => Some(left),
+            _ => None,
+        }
+    }
+}
+
 pub struct FrontendContext<'a> {
-    pub artifact: &'a mut DebugArtifact,
+    /// The active debugging session.
+    pub session: Session<'a>,
+    /// The inactive debugging sessions, in tab order with the active session left out.
+    pub sessions: Vec<Session<'a>>,
+    /// The tab index of the active session.
+    pub session_index: usize,
+    /// The sessions being compared, if any.
+    pub comparison: Option<Comparison>,
+    /// The source breakpoints, shared by all sessions and kept across runs of the debugger.
+    pub breakpoints: BreakpointFile,
+    /// The breakpoints on the entry of functions, shared by all sessions and kept across runs of
+    /// the debugger.
+    pub function_breakpoints: FunctionBreakpointFile,
+    /// The events continuing stops at, set when continuing until an event, shared by all
+    /// sessions.
+    pub event_breakpoint: Option<EventFilter>,
+    /// The contracts whose calls are stepped over, kept across runs of the debugger.
+    pub blackbox: BlackboxFile,
+    /// Whether stepping treats the calls to simple getters as single steps.
+    pub skip_getters: bool,
+    /// Whether the transaction can be modified and re-run.
+    pub rerunnable: bool,
+    /// The directory the position is synchronized with an editor through, if any.
+    pub sync: Option<SyncDir>,
+    /// The position of the driver of the session, which the session follows read-only.
+    pub follow: Option<Receiver<usize>>,
+    /// Where the position of the first session is sent, for others to follow it read-only.
+    pub share: Option<Sender<Location>>,
 
     /// Buffer for keys prior to execution, i.e. '10' + 'k' => move up 10 operations.
     pub key_buffer: String,
-    /// Current step in the debug steps.
-    pub current_step: usize,
-    pub draw_memory: DrawMemory,
-    pub opcode_list: Vec<String>,
-    pub last_index: usize,
+    /// The state of each view, which is updated while drawing as well.
+    pub view_states: RefCell<BTreeMap<PaneView, ViewState>>,
+    /// Bumped by every change of the state other than a move of the position, so that the panes
+    /// drawn at an older generation are drawn again, see [`Self::touch`].
+    pub generation: u64,
+    /// The panes drawn in the previous frame, which are copied instead of being drawn again if
+    /// nothing they are drawn from has changed since.
+    pub pane_cache: RefCell<PaneCache>,
+    /// The area of the graphs of the timeline when it was last drawn, to map clicks onto steps.
+    pub timeline_area: Cell<Rect>,
 
     pub stack_labels: bool,
     /// Whether to decode active buffer as utf8 or not.
     pub buf_utf: bool,
     pub show_shortcuts: bool,
+    pub theme: Theme,
 
     /// The display window (which is only aware of the layout,
     /// without any actual data)
@@ -65,34 +277,300 @@ pub struct FrontendContext<'a> {
 }
 
 impl<'a> FrontendContext<'a> {
-    pub(crate) fn new(artifact: &'a mut DebugArtifact) -> Result<Self> {
+    pub(crate) fn new(mut sessions: Vec<Session<'a>>, theme: Theme) -> Result<Self> {
+        eyre::ensure!(!sessions.is_empty(), "no debugging session");
+        let session = sessions.remove(0);
         Ok(FrontendContext {
-            artifact,
+            session,
+            sessions,
+            session_index: 0,
+            comparison: None,
+            breakpoints: BreakpointFile::load(CachePath::edb_breakpoints_file()),
+            function_breakpoints: FunctionBreakpointFile::load(
+                CachePath::edb_function_breakpoints_file(),
+            ),
+            event_breakpoint: None,
+            blackbox: BlackboxFile::load(CachePath::edb_blackbox_file()),
+            skip_getters: true,
+            rerunnable: false,
+            sync: None,
+            follow: None,
+            share: None,
 
             key_buffer: String::with_capacity(64),
-            current_step: 0,
-            draw_memory: DrawMemory::default(),
-            opcode_list: Vec::new(),
-            last_index: 0,
+            view_states: RefCell::new(BTreeMap::new()),
+            generation: 0,
+            pane_cache: RefCell::new(PaneCache::default()),
+            timeline_area: Cell::new(Rect::default()),
 
             stack_labels: false,
             buf_utf: false,
             show_shortcuts: true,
+            theme,
 
             window: Window::new()?,
         })
     }
 
     pub(crate) fn init(&mut self) {
+        for _ in 0..self.num_sessions() {
+            self.gen_opcode_list();
+            self.next_session();
+        }
+
+        // Offer the suggested steps as starting points, if there are any
+        let suggestions = self.suggestions();
+        if !suggestions.is_empty() {
+            self.window.pop_suggestions(suggestions);
+        }
+    }
+
+    /// Returns the state set by the user, to carry it over to the next frontend.
+    pub(crate) fn state(&self) -> FrontendState {
+        let mut sessions: Vec<_> = self.sessions.iter().map(Session::state).collect();
+        sessions.insert(self.session_index, self.session.state());
+        FrontendState {
+            view_states: self.view_states.borrow().clone(),
+            event_breakpoint: self.event_breakpoint.clone(),
+            stack_labels: self.stack_labels,
+            buf_utf: self.buf_utf,
+            show_shortcuts: self.show_shortcuts,
+            sessions,
+        }
+    }
+
+    /// Restores the state carried over from a previous frontend. Sessions are matched by tab
+    /// index, so that the sessions added since keep their initial state.
+    pub(crate) fn restore(&mut self, state: FrontendState) {
+        *self.view_states.get_mut() = state.view_states;
+        self.event_breakpoint = state.event_breakpoint;
+        self.stack_labels = state.stack_labels;
+        self.buf_utf = state.buf_utf;
+        self.show_shortcuts = state.show_shortcuts;
+
+        let active = self.session_index;
+        for (index, session) in state.sessions.into_iter().enumerate().take(self.num_sessions()) {
+            self.switch_session(index);
+            self.restore_session(session);
+        }
+        self.switch_session(active);
+    }
+
+    /// Restores the state of the active session, recomputing the analyses it shows.
+    fn restore_session(&mut self, state: SessionState) {
+        // The sessions keep their artifacts, so that the step is still in the execution
+        let _ = self.locate(state.step);
+        self.session.expanded_loops = state.expanded_loops;
         self.gen_opcode_list();
+        self.session.last_index = self.session.draw_memory.inner_call_index;
+
+        self.session.skip_blackboxed = state.skip_blackboxed;
+        self.session.taint = state.taint.then(|| taint_analysis(self.session.artifact));
+        let heat_map = state.heat_map.then(|| self.heat_map());
+        self.session.heat_map = heat_map;
+        if let Some((address, slot)) = state.storage_slot {
+            self.session.watch_slot(address, slot);
+        }
+    }
+
+    /// Returns the suggested steps of the current session, as their descriptions and indices.
+    fn suggestions(&self) -> Vec<(String, usize)> {
+        suggest_steps(self.session.artifact)
+            .into_iter()
+            .map(|s| (format!("{} ({})", s.kind, s.description), s.step))
+            .collect()
+    }
+
+    /// Pops up the suggested steps of the current session.
+    pub(crate) fn pop_suggestions(&mut self) {
+        let suggestions = self.suggestions();
+        if suggestions.is_empty() {
+            self.window.pop_info(
+                " Suggested Steps ".to_string(),
+                "Nothing stands out in this execution.".to_string(),
+            );
+        } else {
+            self.window.pop_suggestions(suggestions);
+        }
+    }
+
+    /// Returns the number of debugging sessions.
+    pub(crate) fn num_sessions(&self) -> usize {
+        self.sessions.len() + 1
+    }
+
+    /// Returns the names of all sessions in tab order.
+    pub(crate) fn session_names(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.sessions.iter().map(|s| s.name.as_str()).collect();
+        names.insert(self.session_index, self.session.name.as_str());
+        names
+    }
+
+    /// Returns the state of the given view.
+    pub(crate) fn view_state(&self, view: PaneView) -> ViewState {
+        self.view_states.borrow().get(&view).copied().unwrap_or_default()
+    }
+
+    /// Updates the state of the given view.
+    pub(crate) fn update_view_state(&self, view: PaneView, f: impl FnOnce(&mut ViewState)) {
+        f(self.view_states.borrow_mut().entry(view).or_default());
+    }
+
+    /// Clamps the state of the given view to a content of `len` rows, of which `height` rows are
+    /// visible, and returns it.
+    ///
+    /// The content changes with every step, so the state is clamped while drawing rather than
+    /// when scrolling.
+    pub(crate) fn clamp_view_state(&self, view: PaneView, len: usize, height: usize) -> ViewState {
+        let mut states = self.view_states.borrow_mut();
+        let state = states.entry(view).or_default();
+        state.offset = state.offset.min(len.saturating_sub(height.max(1)));
+        state.cursor = state.cursor.min(len.saturating_sub(1));
+        *state
+    }
+
+    /// Summarizes the debugging state for crash reports.
+    pub(crate) fn crash_context(&self) -> String {
+        let step = self.current_step();
+        let mut context = String::new();
+        let _ = writeln!(
+            context,
+            "session: {} ({}/{})",
+            self.session.name,
+            self.session_index + 1,
+            self.num_sessions()
+        );
+        let _ = writeln!(
+            context,
+            "call: #{} at {}",
+            self.session.draw_memory.inner_call_index,
+            self.address()
+        );
+        let _ = writeln!(
+            context,
+            "step: {} (pc {:#x}, {})",
+            self.session.current_step,
+            step.pc,
+            step.pretty_opcode()
+        );
+        let _ = writeln!(context, "focused view: {:?}", self.window.get_focused_view().ok());
+        let _ = writeln!(context, "key buffer: {:?}", self.key_buffer);
+        context
+    }
+
+    /// Toggles whether the focused pane follows the execution.
+    pub(crate) fn toggle_follow(&mut self) -> Result<()> {
+        let pane = self.window.get_focused_pane_mut()?;
+        pane.follow = !pane.follow;
+        Ok(())
+    }
+
+    /// Scrolls the focused view by the given number of rows, or moves the cursor for views with
+    /// selectable rows. Manual scrolling stops the pane from following the execution, so that
+    /// the next step does not scroll it back.
+    pub(crate) fn scroll_focused(&mut self, up: bool, rows: usize) -> Result<()> {
+        let pane = self.window.get_focused_pane_mut()?;
+        let view = pane.get_current_view();
+        if view.can_follow() {
+            pane.follow = false;
+        }
+        self.update_view_state(view, |state| {
+            let value = if view.has_cursor() { &mut state.cursor } else { &mut state.offset };
+            *value = if up { value.saturating_sub(rows) } else { value.saturating_add(rows) };
+        });
+        Ok(())
+    }
+
+    /// Scrolls the focused view to the current execution point once, without changing whether
+    /// its pane follows the execution.
+    pub(crate) fn jump_to_current(&mut self) -> Result<()> {
+        let view = self.window.get_focused_view()?;
+        self.update_view_state(view, |state| state.recenter = true);
+        Ok(())
+    }
+
+    /// Shows the reference of the opcode at the current step.
+    pub(crate) fn inspect_opcode(&mut self) -> Result<()> {
+        let op = self.session.step().instruction;
+        let message = match OpcodeDoc::of(op) {
+            Some(doc) => doc.to_string(),
+            None => format!("Undefined opcode 0x{op:02x}, which aborts the execution."),
+        };
+        self.window.pop_info(" Opcode Reference ".to_string(), message);
+        Ok(())
+    }
+
+    /// Explains the signature checked by the `ecrecover` call at or after the current step, or
+    /// by the last one before it.
+    pub(crate) fn explain_signature(&mut self) -> Result<()> {
+        let step = self.session.step_index();
+        let calls = self.session.ecrecover_calls();
+        let call =
+            calls.iter().find(|call| call.step >= step).or_else(|| calls.last()).ok_or_else(
+                || RecoverableError::new("No signature is checked in the transaction."),
+            )?;
+        let report = verify_signature(
+            self.session.artifact,
+            self.session.call_frames(),
+            self.session.type_registry(),
+            call,
+        );
+        let message = signature_report(self.session.artifact, &report);
+        self.window.pop_info(" Signature Check ".to_string(), message.trim_end().to_string());
+        Ok(())
+    }
+
+    /// Returns the session at the given tab index.
+    pub(crate) fn session_at(&self, index: usize) -> &Session<'a> {
+        match index.cmp(&self.session_index) {
+            Ordering::Less => &self.sessions[index],
+            Ordering::Equal => &self.session,
+            Ordering::Greater => &self.sessions[index - 1],
+        }
+    }
+
+    /// Returns the session at the given tab index.
+    pub(crate) fn session_at_mut(&mut self, index: usize) -> &mut Session<'a> {
+        match index.cmp(&self.session_index) {
+            Ordering::Less => &mut self.sessions[index],
+            Ordering::Equal => &mut self.session,
+            Ordering::Greater => &mut self.sessions[index - 1],
+        }
+    }
+
+    /// Activates the session at the given tab index.
+    pub(crate) fn switch_session(&mut self, index: usize) {
+        if index == self.session_index || index >= self.num_sessions() {
+            return;
+        }
+
+        // The inactive sessions before the active one keep their tab index, while the ones
+        // after it are shifted by one.
+        let target = if index < self.session_index { index } else { index - 1 };
+        let previous = std::mem::replace(&mut self.session, self.sessions.remove(target));
+        let slot =
+            if index < self.session_index { self.session_index - 1 } else { self.session_index };
+        self.sessions.insert(slot, previous);
+        self.session_index = index;
+    }
+
+    /// Activates the next session, wrapping around at the last one.
+    pub(crate) fn next_session(&mut self) {
+        self.switch_session((self.session_index + 1) % self.num_sessions());
+    }
+
+    /// Activates the previous session, wrapping around at the first one.
+    pub(crate) fn prev_session(&mut self) {
+        let n = self.num_sessions();
+        self.switch_session((self.session_index + n - 1) % n);
     }
 
     pub(crate) fn debug_arena(&self) -> &[DebugNodeFlat] {
-        &self.artifact.debug_arena
+        &self.session.artifact.debug_arena
     }
 
     pub(crate) fn debug_call(&self) -> &DebugNodeFlat {
-        &self.debug_arena()[self.draw_memory.inner_call_index]
+        &self.debug_arena()[self.session.draw_memory.inner_call_index]
     }
 
     /// Returns the current call address.
@@ -112,19 +590,95 @@ impl<'a> FrontendContext<'a> {
 
     /// Returns the current debug step.
     pub(crate) fn current_step(&self) -> &DebugStep {
-        &self.debug_steps()[self.current_step]
+        &self.debug_steps()[self.session.current_step]
     }
 
     fn gen_opcode_list(&mut self) {
-        self.opcode_list.clear();
-        let debug_steps = &self.artifact.debug_arena[self.draw_memory.inner_call_index].steps;
-        self.opcode_list.extend(debug_steps.iter().map(DebugStep::pretty_opcode));
+        // Opcodes are only formatted while drawing, for the visible rows
+        let debug_steps =
+            &self.session.artifact.debug_arena[self.session.draw_memory.inner_call_index].steps;
+        self.session.max_pc = debug_steps.iter().map(|step| step.pc).max().unwrap_or(0);
+
+        self.session.loops = self.debug_call().loops(MIN_LOOP_ITERATIONS);
+        self.gen_op_rows();
+
+        if self.session.heat_map.is_some() {
+            self.session.heat_map = Some(self.heat_map());
+        }
+
+        // Creations execute the init code, which is not deployed
+        let call = self.debug_call();
+        self.session.cfg = (!call.kind.is_any_create())
+            .then(|| self.session.artifact.cfgs.get(&call.address).cloned())
+            .flatten();
+    }
+
+    /// Generates the rows of the opcode list, collapsing the loops which are not expanded.
+    pub(crate) fn gen_op_rows(&mut self) {
+        let call = self.session.draw_memory.inner_call_index;
+        let n_steps = self.n_steps();
+
+        self.session.op_rows.clear();
+        let mut step = 0;
+        while step < n_steps {
+            // Outer loops come first, so that nested loops show up once the outer one is expanded
+            let collapsed = self.session.loops.iter().find(|summary| {
+                summary.start == step &&
+                    !self.session.expanded_loops.contains(&(call, summary.start))
+            });
+            match collapsed {
+                Some(summary) => {
+                    self.session.op_rows.push(OpRow::Loop(*summary));
+                    step = summary.end;
+                }
+                None => {
+                    self.session.op_rows.push(OpRow::Step(step));
+                    step += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the opcode row containing the current step.
+    pub(crate) fn current_op_row(&self) -> usize {
+        self.session
+            .op_rows
+            .partition_point(|row| row.first_step() <= self.session.current_step)
+            .saturating_sub(1)
+    }
+
+    /// Expands the loop collapsed at the current row, or collapses the innermost expanded loop
+    /// containing the current step.
+    pub(crate) fn toggle_loop(&mut self) -> Result<()> {
+        let call = self.session.draw_memory.inner_call_index;
+        let key = match self.session.op_rows.get(self.current_op_row()) {
+            Some(OpRow::Loop(summary)) => (call, summary.start),
+            _ => {
+                let summary = self
+                    .session
+                    .loops
+                    .iter()
+                    .rev()
+                    .find(|summary| {
+                        (summary.start..summary.end).contains(&self.session.current_step) &&
+                            self.session.expanded_loops.contains(&(call, summary.start))
+                    })
+                    .ok_or_else(|| RecoverableError::new("The current step is not in a loop."))?;
+                (call, summary.start)
+            }
+        };
+
+        if !self.session.expanded_loops.remove(&key) {
+            self.session.expanded_loops.insert(key);
+        }
+        self.gen_op_rows();
+        Ok(())
     }
 
     fn gen_opcode_list_if_necessary(&mut self) {
-        if self.last_index != self.draw_memory.inner_call_index {
+        if self.session.last_index != self.session.draw_memory.inner_call_index {
             self.gen_opcode_list();
-            self.last_index = self.draw_memory.inner_call_index;
+            self.session.last_index = self.session.draw_memory.inner_call_index;
         }
     }
 
@@ -141,12 +695,22 @@ impl<'a> FrontendContext<'a> {
 impl FrontendContext<'_> {
     pub(crate) fn handle_event(&mut self, event: Event) -> ControlFlow<ExitReason> {
         let ret = match event {
-            Event::Key(event) => self.handle_key_event(event),
+            Event::Key(event) => match normalize_key_event(event) {
+                Some(event) => self.handle_key_event(event),
+                None => ControlFlow::Continue(()),
+            },
             Event::Mouse(event) => self.handle_mouse_event(event),
+            Event::Paste(text) => {
+                self.window.handle_paste(&text);
+                ControlFlow::Continue(())
+            }
             _ => ControlFlow::Continue(()),
         };
+        // Keep the compared session at the same step.
+        self.sync_comparison();
         // Generate the list after the event has been handled.
         self.gen_opcode_list_if_necessary();
+        self.publish_location();
         ret
     }
 
@@ -182,13 +746,25 @@ impl FrontendContext<'_> {
             if event.code == KeyCode::Esc {
                 self.window.exit_popup();
             } else {
-                self.window.handle_key_event_in_popup(event)?;
+                match self.window.handle_key_event_in_popup(event)? {
+                    // Commands from the palette go through the same dispatcher as key bindings
+                    Some(PopupOutcome::Command(command)) => {
+                        return self.try_handle_key_event(command)
+                    }
+                    Some(PopupOutcome::Dialog(action, input)) => {
+                        return self.handle_dialog(action, input.trim())
+                    }
+                    None => {}
+                }
             }
         } else if focused_pane == PaneView::Terminal &&
             self.window.editor_mode == TerminalMode::Insert
         {
             // Insert mode is a special case
             self.window.handle_input(event);
+        } else if focused_pane != PaneView::Terminal && self.handle_motion(event)? {
+            // Counts and pending motions are kept in the key buffer
+            return Ok(ControlFlow::Continue(()));
         } else {
             // Handle common key events
             match event.code {
@@ -218,9 +794,31 @@ impl FrontendContext<'_> {
                 // Move focus to the up pane
                 KeyCode::Up if shift && !self.window.full_screen => self.window.focus_up()?,
 
+                // Pop up the command palette
+                KeyCode::Char('p') if control => self.window.pop_command_palette(),
+
+                // Compact the snapshots, like a garbage collection
+                KeyCode::Char('g') if control => self.collect_garbage(),
+
+                // Pop up the view picker
+                KeyCode::Char('P') if shift => self.window.pop_view_picker(),
+
                 // Pop up the assignment window
                 KeyCode::Char('C') if shift => self.window.pop_assignment(),
 
+                // Toggle the taint mode
+                KeyCode::Char('T') if shift => self.toggle_taint(),
+
+                // Toggle the heat-map mode
+                KeyCode::Char('H') if shift => self.toggle_heat_map(),
+
+                // Compare the current session with the next one
+                KeyCode::Char('V') if shift => self.toggle_comparison()?,
+
+                // Cycle the debugging sessions
+                KeyCode::Tab => self.next_session(),
+                KeyCode::BackTab => self.prev_session(),
+
                 // Shortcut to enter the terminal
                 KeyCode::Char('I') if shift => {
                     // We do not want to exit the full screen mode when we are in
@@ -234,11 +832,32 @@ impl FrontendContext<'_> {
                     self.window.set_editor_insert_mode();
                 }
 
+                // Zoom the focused pane
+                KeyCode::Char('Z') if shift && !self.window.full_screen => {
+                    self.window.toggle_zoom()
+                }
+
                 // Esc
+                KeyCode::Esc if self.window.zoomed && !self.window.full_screen => {
+                    self.window.toggle_zoom()
+                }
                 KeyCode::Esc if self.window.full_screen => self.window.toggle_full_screen(),
 
-                // Enter
-                KeyCode::Enter if !self.window.full_screen => self.window.toggle_full_screen(),
+                // Enter, which selects the session in the session list or the access in the
+                // storage timeline
+                KeyCode::Enter
+                    if !self.window.full_screen &&
+                        !matches!(
+                            focused_pane,
+                            PaneView::Sessions |
+                                PaneView::Storage |
+                                PaneView::UserOps |
+                                PaneView::Governance |
+                                PaneView::Events
+                        ) =>
+                {
+                    self.window.toggle_full_screen()
+                }
 
                 // Cycle left the current focused pane
                 KeyCode::Left if focused_pane != PaneView::Terminal => self.repeat(|this| {
@@ -253,7 +872,55 @@ impl FrontendContext<'_> {
                 })?,
 
                 // Quit
-                KeyCode::Char('Q') if shift => return Ok(ControlFlow::Break(ExitReason::CharExit)),
+                KeyCode::Char('Q') if shift => self.window.pop_confirmation(DialogAction::Quit),
+
+                // Go to a step
+                KeyCode::Char(':') => self.window.pop_input(DialogAction::GotoStep),
+
+                // Show the suggested steps to start from
+                KeyCode::Char('!') => self.pop_suggestions(),
+
+                // Go to the first call to an address
+                KeyCode::Char('A') if shift => self.window.pop_input(DialogAction::GotoCall),
+
+                // Run to a source line
+                KeyCode::Char('L') if shift => self.window.pop_input(DialogAction::RunToLine),
+
+                // Watch a storage slot
+                KeyCode::Char('W') if shift => self.window.pop_input(DialogAction::WatchSlot),
+
+                // Derive a CREATE2 address
+                KeyCode::Char('@') => self.window.pop_input(DialogAction::Create2),
+
+                // Predict the next CREATE addresses of a deployer
+                KeyCode::Char('#') => self.window.pop_input(DialogAction::PredictCreate),
+
+                // Break on an event, and continue until it is emitted
+                KeyCode::Char('N') if shift => self.window.pop_input(DialogAction::ContinueToEvent),
+
+                // Set or remove a breakpoint on a function entry
+                KeyCode::Char('B') if shift => self.window.pop_input(DialogAction::BreakOnFunction),
+
+                // List the breakpoints
+                KeyCode::Char('l') if control => self.list_breakpoints(),
+
+                // Blackbox or unblackbox a contract, and toggle skipping them in this session
+                KeyCode::Char('K') if shift => self.window.pop_input(DialogAction::Blackbox),
+                KeyCode::Char('b') if control => self.toggle_skip_blackboxed(),
+
+                // Explain the next signature check
+                KeyCode::Char('E') if shift => self.explain_signature()?,
+
+                // Modify and re-run the transaction
+                KeyCode::Char('R') if shift => {
+                    if !self.rerunnable {
+                        return Err(RecoverableError::new(
+                            "The transaction cannot be re-run from this command.",
+                        )
+                        .into());
+                    }
+                    self.window.pop_input(DialogAction::EditTx)
+                }
 
                 // Shortcut to split the screen: (s)plit and (d)ivide
                 KeyCode::Char('D') if shift && !self.window.full_screen => {
@@ -264,41 +931,48 @@ impl FrontendContext<'_> {
                 }
 
                 // Shortcut to unregister the current view
-                KeyCode::Char('X') if shift => {
-                    let view = self.window.get_focused_view()?;
+                KeyCode::Char('X') if shift => self.window.record(|screen| {
+                    let view = screen.get_focused_view()?;
 
                     if view.is_valid() {
-                        self.window.get_pane_manager_mut()?.unassign(view)?;
+                        screen.get_pane_manager_mut()?.unassign(view)?;
                     }
 
                     // try to merge the pane if it is empty
-                    if !self.window.get_focused_view()?.is_valid() && !self.window.full_screen {
-                        if self.window.get_pane_manager()?.pane_num() == 1 {
+                    if !screen.get_focused_view()?.is_valid() && !screen.full_screen {
+                        if screen.get_pane_manager()?.pane_num() == 1 {
                             return Err(RecoverableError::new("Cannot close the last pane.").into());
                         }
-                        self.window.close_focused_pane()?;
+                        screen.close_focused_pane()?;
                     }
-                }
+                    Ok(())
+                })?,
+
+                // Undo and redo layout operations
+                KeyCode::Char('z') if control => self.window.undo_layout()?,
+                KeyCode::Char('y') if control => self.window.redo_layout()?,
 
                 // Other view-specific key events
                 _ => match focused_pane {
                     PaneView::Terminal => self.window.handle_input(event),
-                    PaneView::Source => self.handle_key_event_in_source(event),
-                    PaneView::Trace => self.handle_key_event_in_trace(event),
-                    PaneView::Opcode => self.handle_key_event_in_opcode(event),
-                    _ => self.handle_key_even_in_data(event),
+                    PaneView::Source => self.handle_key_event_in_source(event)?,
+                    PaneView::Trace => self.handle_key_event_in_trace(event)?,
+                    PaneView::Opcode => self.handle_key_event_in_opcode(event)?,
+                    PaneView::Compare => self.handle_key_event_in_compare(event)?,
+                    PaneView::Assembly => self.handle_key_event_in_assembly(event)?,
+                    _ => self.handle_key_even_in_data(event)?,
                 },
                 // // Scroll up the memory buffer
                 // KeyCode::Char('k') | KeyCode::Up if control => self.repeat(|this| {
-                //     this.draw_memory.current_buf_startline =
-                //         this.draw_memory.current_buf_startline.saturating_sub(1);
+                //     this.session.draw_memory.current_buf_startline =
+                //         this.session.draw_memory.current_buf_startline.saturating_sub(1);
                 // }),
                 // // Scroll down the memory buffer
                 // // KeyCode::Char('j') | KeyCode::Down if control =>
                 // self.repeat(|this| { //     let max_buf =
                 // this.data_pane_height().saturating_sub(1);
-                // //     if this.draw_memory.current_buf_startline < max_buf {
-                // //         this.draw_memory.current_buf_startline += 1;
+                // //     if this.session.draw_memory.current_buf_startline < max_buf {
+                // //         this.session.draw_memory.current_buf_startline += 1;
                 // //     }
                 // // }),
 
@@ -309,35 +983,35 @@ impl FrontendContext<'_> {
 
                 // // Go to top of file
                 // KeyCode::Char('g') => {
-                //     self.draw_memory.inner_call_index = 0;
-                //     self.current_step = 0;
+                //     self.session.draw_memory.inner_call_index = 0;
+                //     self.session.current_step = 0;
                 // }
 
                 // // Go to bottom of file
                 // KeyCode::Char('G') => {
-                //     self.draw_memory.inner_call_index = self.debug_arena().len() - 1;
-                //     self.current_step = self.n_steps() - 1;
+                //     self.session.draw_memory.inner_call_index = self.debug_arena().len() - 1;
+                //     self.session.current_step = self.n_steps() - 1;
                 // }
 
                 // // Go to previous call
                 // KeyCode::Char('c') => {
-                //     self.draw_memory.inner_call_index =
-                //         self.draw_memory.inner_call_index.saturating_sub(1);
-                //     self.current_step = self.n_steps() - 1;
+                //     self.session.draw_memory.inner_call_index =
+                //         self.session.draw_memory.inner_call_index.saturating_sub(1);
+                //     self.session.current_step = self.n_steps() - 1;
                 // }
 
                 // // Go to next call
                 // KeyCode::Char('C') => {
-                //     if self.debug_arena().len() > self.draw_memory.inner_call_index +
+                //     if self.debug_arena().len() > self.session.draw_memory.inner_call_index +
                 // 1     {
-                //         self.draw_memory.inner_call_index += 1;
-                //         self.current_step = 0;
+                //         self.session.draw_memory.inner_call_index += 1;
+                //         self.session.current_step = 0;
                 //     }
                 // }
 
                 // // Step forward
                 // KeyCode::Char('s') => self.repeat(|this| {
-                //     let remaining_ops = &this.opcode_list[this.current_step..];
+                //     let remaining_ops = &this.session.opcode_list[this.session.current_step..];
                 //     if let Some((i, _)) =
                 //         remaining_ops.iter().enumerate().skip(1).find(|&(i, op)| {
                 //             let prev = &remaining_ops[i - 1];
@@ -347,14 +1021,14 @@ impl FrontendContext<'_> {
                 //             prev_is_jump && is_jumpdest
                 //         })
                 //     {
-                //         this.current_step += i;
+                //         this.session.current_step += i;
                 //     }
                 // }),
 
                 // // Step backwards
                 // KeyCode::Char('a') => self.repeat(|this| {
-                //     let ops = &this.opcode_list[..this.current_step];
-                //     this.current_step = ops
+                //     let ops = &this.session.opcode_list[..this.session.current_step];
+                //     this.session.current_step = ops
                 //         .iter()
                 //         .enumerate()
                 //         .skip(1)
@@ -398,6 +1072,175 @@ impl FrontendContext<'_> {
         Ok(ControlFlow::Continue(()))
     }
 
+    /// Returns the memory held by the artifacts of all sessions.
+    pub(crate) fn memory_usage(&self) -> MemoryUsage {
+        std::iter::once(&self.session).chain(&self.sessions).map(Session::memory_usage).sum()
+    }
+
+    /// Compacts the snapshots of all sessions, sharing the identical ones between consecutive
+    /// steps and dropping the fine-grained ones of the calls already inspected, and tells how much
+    /// memory was freed.
+    fn collect_garbage(&mut self) {
+        let freed: usize = std::iter::once(&mut self.session)
+            .chain(&mut self.sessions)
+            .map(Session::compact)
+            .sum();
+        let message = format!(
+            "Freed {} of duplicate and already inspected snapshots, {} are held now.",
+            format_bytes(freed),
+            format_bytes(self.memory_usage().total())
+        );
+        self.window.pop_info(" Memory ".to_string(), message);
+    }
+
+    /// Shows the `CREATE2` address derived from the given deployment, and where the contract is
+    /// created in the transaction, if it is.
+    fn explain_create2(&mut self, deployer: Address, salt: B256, init_code: &InitCode) {
+        let artifact = &*self.session.artifact;
+        let address = create2_address(deployer, salt, init_code);
+        let mut message = format!(
+            "deployer: {}\nsalt: {salt}\ninit code hash: {}\n\naddress: {}",
+            artifact.address_label(&deployer),
+            init_code.hash(),
+            address.to_checksum(None)
+        );
+        let created = self
+            .session
+            .call_frames()
+            .iter()
+            .find(|frame| frame.kind.is_any_create() && frame.address == address);
+        match created {
+            Some(frame) => {
+                let _ =
+                    write!(message, "\n\nThe contract is created at step {}.", frame.steps.start);
+            }
+            None => message.push_str("\n\nThe contract is not created in the transaction."),
+        }
+        self.window.pop_info(" CREATE2 Address ".to_string(), message);
+    }
+
+    /// Shows the next addresses the deployer would create contracts at with `CREATE`, from the
+    /// given nonce or, by default, the one following its last deployment in the transaction.
+    fn predict_create(&mut self, deployer: Address, nonce: Option<u64>) -> Result<()> {
+        let deployments = self.session.create_deployments();
+        let last = deployments
+            .iter()
+            .filter(|deployment| deployment.deployer == deployer)
+            .filter_map(|deployment| deployment.nonce)
+            .max();
+        let nonce = nonce.or(last.map(|nonce| nonce + 1)).ok_or_else(|| {
+            RecoverableError::new(format!(
+                "{deployer} creates no contract in the transaction, so that its nonce must be \
+                 given, e.g., `{deployer} 1`."
+            ))
+        })?;
+
+        let artifact = &*self.session.artifact;
+        let mut message = format!("deployer: {}\n", artifact.address_label(&deployer));
+        for nonce in nonce..nonce.saturating_add(PREDICTED_ADDRESSES) {
+            let address = create_address(deployer, nonce);
+            let _ = write!(message, "\nnonce {nonce}: {}", address.to_checksum(None));
+            if let Some(deployment) =
+                deployments.iter().find(|deployment| deployment.created == address)
+            {
+                let step = self.session.call_frames()[deployment.frame].steps.start;
+                let _ = write!(message, " (created at step {step})");
+            }
+        }
+        self.window.pop_info(" CREATE Addresses ".to_string(), message);
+        Ok(())
+    }
+
+    /// Handles a confirmed or submitted dialog.
+    fn handle_dialog(
+        &mut self,
+        action: DialogAction,
+        input: &str,
+    ) -> Result<ControlFlow<ExitReason>> {
+        match action {
+            DialogAction::Quit => return Ok(ControlFlow::Break(ExitReason::CharExit)),
+            DialogAction::GotoStep => {
+                let index = input
+                    .parse()
+                    .map_err(|_| RecoverableError::new(format!("Invalid step: {input}")))?;
+                self.goto_step(index)?;
+            }
+            DialogAction::GotoCall => {
+                let address: Address = input
+                    .parse()
+                    .map_err(|_| RecoverableError::new(format!("Invalid address: {input}")))?;
+                let node =
+                    self.debug_arena().iter().position(|node| node.address == address).ok_or_else(
+                        || RecoverableError::new(format!("No call to {address} is made.")),
+                    )?;
+                self.ensure_movable()?;
+                self.session.draw_memory.inner_call_index = node;
+                self.session.current_step = 0;
+            }
+            DialogAction::EditTx => {
+                let edit = input.parse().map_err(RecoverableError::new)?;
+                return Ok(ControlFlow::Break(ExitReason::Rerun {
+                    session: self.session_index,
+                    edit,
+                }));
+            }
+            DialogAction::RunToLine => {
+                let (file, line) = input
+                    .rsplit_once(':')
+                    .and_then(|(file, line)| Some((file, line.parse().ok()?)))
+                    .ok_or_else(|| {
+                        RecoverableError::new(format!("Expected `file:line`, got: {input}"))
+                    })?;
+                self.run_to_line(file, line)?;
+            }
+            DialogAction::WatchSlot => {
+                let (address, slot) = parse_slot(input).map_err(RecoverableError::new)?;
+                let address = address.unwrap_or_else(|| {
+                    self.session.artifact.context_address(self.session.draw_memory.inner_call_index)
+                });
+                self.watch_slot(address, slot);
+            }
+            DialogAction::Create2 => {
+                let (deployer, salt, init_code) =
+                    parse_create2(input).map_err(RecoverableError::new)?;
+                self.explain_create2(deployer, salt, &init_code);
+            }
+            DialogAction::PredictCreate => {
+                let (deployer, nonce) = parse_deployer(input).map_err(RecoverableError::new)?;
+                self.predict_create(deployer, nonce)?;
+            }
+            DialogAction::ContinueToEvent if input.is_empty() => {
+                self.event_breakpoint = None;
+                self.window.pop_info(
+                    "Event breakpoint".to_string(),
+                    "Removed the breakpoint on events.".to_string(),
+                );
+            }
+            DialogAction::ContinueToEvent => {
+                let filter: EventFilter = input
+                    .parse()
+                    .map_err(|e: eyre::Report| RecoverableError::new(e.to_string()))?;
+                // the filter is kept, so that continuing stops at the next matching events too
+                self.event_breakpoint = Some(filter.clone());
+                self.continue_to_event(&filter)?;
+            }
+            DialogAction::BreakOnFunction => {
+                let target: FunctionTarget = input
+                    .parse()
+                    .map_err(|e: eyre::Report| RecoverableError::new(e.to_string()))?;
+                self.toggle_function_breakpoint(target)?;
+            }
+            DialogAction::Blackbox => {
+                let entry: BlackboxEntry = input
+                    .parse()
+                    .map_err(|e: eyre::Report| RecoverableError::new(e.to_string()))?;
+                self.toggle_blackbox(entry);
+            }
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+
     fn handle_breakpoint(&mut self, _c: char) {
         // // Find the location of the called breakpoint in the whole debug arena (at this address
         // with // this pc)
@@ -405,8 +1248,8 @@ impl FrontendContext<'_> {
         //     for (i, node) in self.debug_arena().iter().enumerate() {
         //         if node.address == *caller {
         //             if let Some(step) = node.steps.iter().position(|step| step.pc == *pc) {
-        //                 self.draw_memory.inner_call_index = i;
-        //                 self.current_step = step;
+        //                 self.session.draw_memory.inner_call_index = i;
+        //                 self.session.current_step = step;
         //                 break;
         //             }
         //         }
@@ -428,11 +1271,31 @@ impl FrontendContext<'_> {
             MouseEventKind::ScrollUp => self.window.get_focused_pane_mut()?.prev_view(),
             MouseEventKind::ScrollDown => self.window.get_focused_pane_mut()?.next_view(),
             MouseEventKind::Down(MouseButton::Left) => {
-                if !self.window.full_screen {
-                    let v_point =
-                        VirtCoord::project(event.column, event.row, self.window.screen_size);
+                // Clicks on the zoomed pane keep the focus, while the ones around it move the
+                // focus (and thus the zoom) to another pane
+                let in_overlay = self.window.zoomed &&
+                    ScreenManager::zoomed_rect(self.window.screen_size)
+                        .contains(Position::new(event.column, event.row));
+                if !self.window.full_screen && !in_overlay {
+                    // The app area may not start at the top, e.g., when the session tabs are shown
+                    let screen = self.window.screen_size;
+                    let row = event.row.saturating_sub(screen.y);
+                    let v_point = VirtCoord::project(event.column, row, screen);
                     self.window.get_pane_manager_mut().unwrap().force_goto(v_point);
                 }
+
+                // Clicks on the timeline jump to the steps of the column
+                let area = self.timeline_area.get();
+                if self.window.get_focused_view()? == PaneView::Timeline &&
+                    area.contains(Position::new(event.column, event.row))
+                {
+                    let len = self.session.timeline().len();
+                    let width = (area.width as usize).min(len);
+                    let column = (event.column - area.x) as usize;
+                    if column < width {
+                        self.goto_step(column * len / width)?;
+                    }
+                }
             }
             _ => {}
         }
@@ -440,28 +1303,128 @@ impl FrontendContext<'_> {
         Ok(ControlFlow::Continue(()))
     }
 
-    fn step_back(&mut self) {
-        if self.current_step > 0 {
-            self.current_step -= 1;
-        } else if self.draw_memory.inner_call_index > 0 {
-            self.draw_memory.inner_call_index -= 1;
-            self.current_step = self.n_steps() - 1;
+    /// Marks the state as changed, so that the next frame draws every pane again.
+    pub(crate) fn touch(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Marks the state as changed after an event, unless the event moved the position away from
+    /// `before`. Moving only changes the position, so that the next frame then only draws again
+    /// the panes whose view reads the part of the position which moved.
+    pub(crate) fn touch_unless_moved(&mut self, before: ExecutionPosition) {
+        if self.position() == before {
+            self.touch();
         }
     }
 
-    fn step(&mut self) {
-        if self.current_step < self.n_steps() - 1 {
-            self.current_step += 1;
-        } else if self.draw_memory.inner_call_index < self.debug_arena().len() - 1 {
-            self.draw_memory.inner_call_index += 1;
-            self.current_step = 0;
+    /// Returns the position of the execution the panes are drawn at.
+    pub(crate) fn position(&self) -> ExecutionPosition {
+        let step = self.session.step_index();
+        let entered = self.session.call_frames().partition_point(|frame| frame.steps.start <= step);
+        ExecutionPosition {
+            session: self.session_index,
+            frame: (self.session.current_frame(), entered),
+            call: self.session.draw_memory.inner_call_index,
+            step: self.session.current_step,
+        }
+    }
+
+    /// Fails if the session follows a driver, in which case it only moves with it. Every command
+    /// moving the position checks it before doing anything.
+    pub(crate) fn ensure_movable(&self) -> Result<()> {
+        if self.follow.is_some() {
+            return Err(RecoverableError::new(
+                "This session follows its driver, and only moves with it.",
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn step_back(&mut self) -> Result<()> {
+        self.ensure_movable()?;
+        if self.session.current_step > 0 {
+            self.session.current_step -= 1;
+        } else if let Some(index) = self.adjacent_call(false, true) {
+            self.session.draw_memory.inner_call_index = index;
+            self.session.current_step = self.n_steps() - 1;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn step(&mut self) -> Result<()> {
+        self.step_forward(true)
+    }
+
+    /// Steps forward into any call, even to a simple getter or into a blackboxed contract.
+    pub(crate) fn step_into(&mut self) -> Result<()> {
+        self.step_forward(false)
+    }
+
+    fn step_forward(&mut self, step_over: bool) -> Result<()> {
+        self.ensure_movable()?;
+        if self.session.current_step < self.n_steps() - 1 {
+            self.session.current_step += 1;
+        } else if let Some(index) = self.adjacent_call(true, step_over) {
+            self.session.draw_memory.inner_call_index = index;
+            self.session.current_step = 0;
+        }
+        Ok(())
+    }
+
+    /// Returns the node stepped to from the current one. If `step_over` is set, the nodes of
+    /// blackboxed contracts and of calls to simple getters are skipped, unless the current one
+    /// is skipped too, e.g., when it was jumped to.
+    fn adjacent_call(&self, forward: bool, step_over: bool) -> Option<usize> {
+        let artifact = &*self.session.artifact;
+        let current = self.session.draw_memory.inner_call_index;
+        let skipped = SkippedCalls {
+            getters: self.skip_getters.then(|| self.session.getter_nodes()),
+            blackbox: self.session.skip_blackboxed.then_some(&self.blackbox),
+        };
+        let is_skipped = |index: usize| step_over && skipped.contains(artifact, index);
+        let skip = !is_skipped(current);
+        let stops = |index: &usize| !skip || !is_skipped(*index);
+        if forward {
+            (current + 1..artifact.debug_arena.len()).find(stops)
+        } else {
+            (0..current).rev().find(stops)
+        }
+    }
+
+    /// Toggles skipping the calls into blackboxed contracts in the current session.
+    pub(crate) fn toggle_skip_blackboxed(&mut self) {
+        self.session.skip_blackboxed = !self.session.skip_blackboxed;
+        let message = match (self.session.skip_blackboxed, self.blackbox.is_empty()) {
+            (true, false) => "Stepping skips the calls into blackboxed contracts in this session.",
+            (true, true) => {
+                "Stepping skips the calls into blackboxed contracts in this session, \
+                             but none is blackboxed yet (Shift+K)."
+            }
+            (false, _) => "Stepping goes into blackboxed contracts in this session.",
+        };
+        self.window.pop_info("Blackbox".to_string(), message.to_string());
+    }
+
+    /// Adds the contract to the blackbox list, or removes it, and saves the list.
+    pub(crate) fn toggle_blackbox(&mut self, entry: BlackboxEntry) {
+        let message = if self.blackbox.toggle(entry.clone()) {
+            format!("Stepping skips the calls into {entry}.")
+        } else {
+            format!("Stepping goes into {entry} again.")
+        };
+        if let Err(e) = self.blackbox.save() {
+            warn!("failed to save the blackbox list: {e}");
         }
+        self.window.pop_info("Blackbox".to_string(), message);
     }
 
     /// Calls a closure `f` the number of times specified in the key buffer, and at least once.
-    fn repeat(&mut self, mut f: impl FnMut(&mut Self) -> Result<()>) -> Result<()> {
+    pub(crate) fn repeat(&mut self, mut f: impl FnMut(&mut Self) -> Result<()>) -> Result<()> {
         for _ in 0..buffer_as_number(&self.key_buffer) {
             f(self)?;
+            // The call may have changed in between
+            self.gen_opcode_list_if_necessary();
         }
 
         Ok(())
@@ -470,11 +1433,568 @@ impl FrontendContext<'_> {
     fn n_steps(&self) -> usize {
         self.debug_steps().len()
     }
+
+    /// Toggles the taint mode, which highlights values derived from the calldata.
+    pub(crate) fn toggle_taint(&mut self) {
+        self.session.taint = match self.session.taint {
+            Some(_) => None,
+            None => Some(taint_analysis(self.session.artifact)),
+        };
+    }
+
+    /// Toggles the heat-map mode, which colors the gutters of the opcode and source panes by
+    /// how many times each instruction and line was executed.
+    pub(crate) fn toggle_heat_map(&mut self) {
+        self.session.heat_map = match self.session.heat_map {
+            Some(_) => None,
+            None => Some(self.heat_map()),
+        };
+    }
+
+    /// Returns the heat map of the code of the current call.
+    fn heat_map(&self) -> HeatMap {
+        let call = self.debug_call();
+        HeatMap::new(self.session.artifact, call.address, call.kind.is_any_create())
+    }
+
+    /// Starts comparing the active session with the next one step by step, or stops the
+    /// comparison.
+    pub(crate) fn toggle_comparison(&mut self) -> Result<()> {
+        if self.comparison.take().is_some() {
+            return Ok(());
+        }
+        if self.num_sessions() < 2 {
+            return Err(RecoverableError::new("There is no other session to compare with.").into());
+        }
+
+        self.compare_with((self.session_index + 1) % self.num_sessions())
+    }
+
+    /// Starts comparing the active session with the session at the given tab index.
+    pub(crate) fn compare_with(&mut self, peer: usize) -> Result<()> {
+        if peer == self.session_index || peer >= self.num_sessions() {
+            return Err(RecoverableError::new("There is no such session to compare with.").into());
+        }
+        let divergence = Divergence::new(self.session.artifact, self.session_at(peer).artifact);
+        self.comparison = Some(Comparison { sessions: [self.session_index, peer], divergence });
+        self.sync_comparison();
+        Ok(())
+    }
+
+    /// Moves the session compared with the active one to the same step in the whole execution,
+    /// or to its last step if it is shorter.
+    fn sync_comparison(&mut self) {
+        let Some(peer) = self.comparison.as_ref().and_then(|c| c.peer(self.session_index)) else {
+            return;
+        };
+
+        let index = self.session.step_index();
+        let peer = self.session_at_mut(peer);
+        let total = peer.artifact.steps().count();
+        if let Some((node, step)) = peer.artifact.locate_step(index.min(total.saturating_sub(1))) {
+            peer.draw_memory.inner_call_index = node;
+            peer.current_step = step;
+        }
+    }
+
+    /// Jumps to the first step at which the compared sessions diverge.
+    pub(crate) fn goto_divergence(&mut self) -> Result<()> {
+        self.ensure_movable()?;
+        let index = self
+            .comparison
+            .as_ref()
+            .filter(|c| c.peer(self.session_index).is_some())
+            .ok_or_else(|| RecoverableError::new("The current session is not being compared."))?
+            .divergence
+            .stack
+            .ok_or_else(|| RecoverableError::new("The executions do not diverge."))?;
+
+        // The divergence may be right after the end of the shorter execution.
+        let total = self.session.artifact.steps().count();
+        if let Some((node, step)) = self.session.artifact.locate_step(index.min(total - 1)) {
+            self.session.draw_memory.inner_call_index = node;
+            self.session.current_step = step;
+        }
+        Ok(())
+    }
+
+    /// Returns the rows of the user operation pane: each bundle, followed by its operations,
+    /// each of which is followed by its phases and its transfers.
+    pub(crate) fn user_op_rows(&self) -> Vec<UserOpRow<'_>> {
+        let mut rows = Vec::new();
+        for bundle in self.session.user_op_bundles() {
+            rows.push(UserOpRow::Bundle(bundle));
+            for (i, execution) in bundle.ops.iter().enumerate() {
+                rows.push(UserOpRow::Op(bundle, i));
+                rows.extend(
+                    execution.phases.iter().map(|(phase, steps)| UserOpRow::Phase(*phase, steps)),
+                );
+                rows.extend(execution.transfers.iter().map(UserOpRow::Transfer));
+            }
+        }
+        rows
+    }
+
+    /// Returns the rows of the governance pane: each execution, followed by its actions.
+    pub(crate) fn governance_rows(&self) -> Vec<GovernanceRow<'_>> {
+        let mut rows = Vec::new();
+        for execution in self.session.governance_executions() {
+            rows.push(GovernanceRow::Execution(execution));
+            rows.extend((0..execution.actions.len()).map(|i| GovernanceRow::Action(execution, i)));
+        }
+        rows
+    }
+
+    /// Watches the accesses to the given storage slot, which are shown in the storage timeline.
+    pub(crate) fn watch_slot(&mut self, address: Address, slot: U256) {
+        self.session.watch_slot(address, slot);
+
+        // Select the latest access so far, so that the current value shows up first
+        let step = self.session.step_index();
+        let latest = self.session.storage_history.partition_point(|access| access.step <= step);
+        self.update_view_state(PaneView::Storage, |state| state.cursor = latest.saturating_sub(1));
+
+        // Show the timeline, unless its view has been closed
+        if let Ok(manager) = self.window.get_pane_manager_mut() {
+            let _ = manager.force_goto_by_view(PaneView::Storage);
+        }
+    }
+
+    /// Watches the storage slot accessed by the current step, if it is an `SLOAD` or `SSTORE`.
+    pub(crate) fn watch_current_slot(&mut self) -> Result<()> {
+        let step = self.current_step();
+        let slot = match step.instruction {
+            opcode::SLOAD | opcode::SSTORE => step.stack.last().copied(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            RecoverableError::new("The current step does not access storage (SLOAD or SSTORE).")
+        })?;
+        let address =
+            self.session.artifact.context_address(self.session.draw_memory.inner_call_index);
+        self.watch_slot(address, slot);
+        Ok(())
+    }
+
+    /// Jumps to the given step in the whole execution.
+    pub(crate) fn goto_step(&mut self, index: usize) -> Result<()> {
+        self.ensure_movable()?;
+        self.locate(index)
+    }
+
+    /// Moves to the given step in the whole execution, even if the session is followed.
+    fn locate(&mut self, index: usize) -> Result<()> {
+        let (node, step) = self.session.artifact.locate_step(index).ok_or_else(|| {
+            RecoverableError::new(format!("The execution has fewer than {} steps.", index + 1))
+        })?;
+        self.session.draw_memory.inner_call_index = node;
+        self.session.current_step = step;
+        Ok(())
+    }
+
+    /// Continues until the first event emitted after the current step which matches the filter.
+    pub(crate) fn continue_to_event(&mut self, filter: &EventFilter) -> Result<()> {
+        self.ensure_movable()?;
+        let current = self.session.step_index();
+        let artifact = &*self.session.artifact;
+        let step = self
+            .session
+            .events()
+            .iter()
+            .filter(|event| event.step > current)
+            .find(|event| filter.matches(event, artifact.abi(&event.address)))
+            .map(|event| event.step)
+            .ok_or_else(|| RecoverableError::new("No matching event is emitted afterwards."))?;
+        self.goto_step(step)
+    }
+
+    /// Continues until the first step after the current one which is mapped to the given line
+    /// of a source file, matched by the suffix of its path.
+    pub(crate) fn run_to_line(&mut self, file: &str, line: usize) -> Result<()> {
+        self.ensure_movable()?;
+        let artifact = &*self.session.artifact;
+        let current = self.session.step_index();
+        let found = artifact.steps().enumerate().skip(current + 1).find_map(|(_, (i, j, step))| {
+            let node = &artifact.debug_arena[i];
+            let compilation = artifact.compilation_artifacts.get(&node.address)?;
+            let (element, source) =
+                compilation.source_element(step.pc, node.kind.is_any_create())?;
+            (source.path.ends_with(file) && source.line_of(element.offset() as usize) == line)
+                .then_some((i, j))
+        });
+
+        let (node, step) = found.ok_or_else(|| {
+            RecoverableError::new(format!("Line {line} of {file} is not reached anymore."))
+        })?;
+        self.session.draw_memory.inner_call_index = node;
+        self.session.current_step = step;
+        Ok(())
+    }
+
+    /// Returns the inline assembly block of the given step of the current call, as an index in
+    /// the blocks of its code, along with the Yul statement the step is mapped to, if any.
+    pub(crate) fn yul_position(&self, step: usize) -> Option<(usize, Option<usize>)> {
+        let node = self.debug_call();
+        let compilation = self.session.artifact.compilation_artifacts.get(&node.address)?;
+        let pc = node.steps.get(step)?.pc;
+        let (element, _) = compilation.source_element(pc, node.kind.is_any_create())?;
+        let (offset, length) = (element.offset() as usize, element.length() as usize);
+        let (index, block) = self.session.assembly(&node.address)?.locate(
+            element.index()? as usize,
+            offset,
+            length,
+        )?;
+        Some((index, block.statement_at(offset, length)))
+    }
+
+    /// Steps to the first step of the next (or previous) Yul statement of the current call, or
+    /// out of the inline assembly block. Steps between statements, e.g., the stack shuffling
+    /// of the block, are skipped.
+    pub(crate) fn step_yul(&mut self, forward: bool) -> Result<()> {
+        self.ensure_movable()?;
+        let current = self.session.current_step;
+        let start = self.yul_position(current).ok_or_else(|| {
+            RecoverableError::new("The current step is not in an inline assembly block.")
+        })?;
+        let is_target = |position: Option<(usize, Option<usize>)>| match position {
+            None => true,
+            Some(position) => position != start && position.1.is_some(),
+        };
+        let found = if forward {
+            (current + 1..self.n_steps()).find(|step| is_target(self.yul_position(*step)))
+        } else {
+            (0..current).rev().find(|step| is_target(self.yul_position(*step))).map(|step| {
+                // Go back to the first step of the statement, if not leaving the block
+                let position = self.yul_position(step);
+                let first = (0..step).rev().take_while(|s| self.yul_position(*s) == position);
+                match position {
+                    Some(_) => first.last().unwrap_or(step),
+                    None => step,
+                }
+            })
+        };
+
+        self.session.current_step = found.ok_or_else(|| {
+            RecoverableError::new("No other Yul statement is executed in this call.")
+        })?;
+        Ok(())
+    }
+
+    /// Sets a breakpoint on the source line of the current step, or removes it if it is set.
+    pub(crate) fn toggle_breakpoint(&mut self) -> Result<()> {
+        let node = self.debug_call();
+        let compilation =
+            self.session.artifact.compilation_artifacts.get(&node.address).ok_or_else(|| {
+                RecoverableError::new("Breakpoints can only be set in contracts with source code.")
+            })?;
+        let (element, source) = compilation
+            .source_element(self.current_step().pc, node.kind.is_any_create())
+            .ok_or_else(|| {
+                RecoverableError::new("The current step is not mapped to a source line.")
+            })?;
+        let breakpoint = Breakpoint {
+            code_hash: compilation.code_hash,
+            path: source.path.clone(),
+            line: source.line_of(element.offset() as usize),
+        };
+
+        self.breakpoints.toggle(breakpoint);
+        if let Err(e) = self.breakpoints.save() {
+            warn!("failed to save the breakpoints: {e}");
+        }
+        Ok(())
+    }
+
+    /// Publishes the current position to the editor and to the followers of the session, if any.
+    pub(crate) fn publish_location(&mut self) {
+        self.write_sync_location();
+        let Some(share) = &self.share else { return };
+        // the followers debug the first session, not the re-runs
+        if self.session_index != 0 {
+            return;
+        }
+        let location = Location::new(
+            self.session.artifact,
+            self.session.draw_memory.inner_call_index,
+            self.session.current_step,
+            self.session.step_index(),
+        );
+        if share.send(location).is_err() {
+            self.share = None;
+        }
+    }
+
+    /// Writes the current position to the directory shared with the editor, if any.
+    fn write_sync_location(&mut self) {
+        let node = self.debug_call();
+        let source = self
+            .session
+            .artifact
+            .compilation_artifacts
+            .get(&node.address)
+            .and_then(|compilation| {
+                compilation.source_element(self.current_step().pc, node.kind.is_any_create())
+            })
+            .map(|(element, source)| {
+                (source.path.clone(), source.line_of(element.offset() as usize))
+            });
+        let location = SyncLocation {
+            session: self.session.name.clone(),
+            call: self.session.draw_memory.inner_call_index,
+            step: self.session.current_step,
+            address: node.address,
+            depth: node.depth,
+            line: source.as_ref().map(|(_, line)| *line),
+            file: source.map(|(path, _)| path),
+        };
+        let Some(sync) = &mut self.sync else { return };
+        if let Err(e) = sync.write_location(location) {
+            warn!("failed to write the location for the editor: {e}");
+        }
+    }
+
+    /// Handles the commands sent by the editor since the last poll. Returns whether any was
+    /// received.
+    pub(crate) fn poll_sync(&mut self) -> bool {
+        let Some(sync) = &mut self.sync else { return false };
+        let commands = sync.read_commands();
+        for command in &commands {
+            if let Err(e) = self.handle_sync_command(command) {
+                self.window.pop_error_message(e.to_string());
+            }
+        }
+        if !commands.is_empty() {
+            self.sync_comparison();
+            self.gen_opcode_list_if_necessary();
+            self.publish_location();
+        }
+        !commands.is_empty()
+    }
+
+    /// Moves to the latest position of the driver since the last poll. Returns whether it moved,
+    /// or whether the driver left, in which case the session is not followed anymore.
+    pub(crate) fn poll_follow(&mut self) -> bool {
+        let Some(driver) = &self.follow else { return false };
+        let mut latest = None;
+        loop {
+            match driver.try_recv() {
+                Ok(step) => latest = Some(step),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.follow = None;
+                    self.window.pop_error_message(
+                        "The driver left the session, which can be moved freely now.".to_string(),
+                    );
+                    break;
+                }
+            }
+        }
+
+        let Some(step) = latest else { return self.follow.is_none() };
+        if let Err(e) = self.locate(step) {
+            self.window.pop_error_message(e.to_string());
+        }
+        self.sync_comparison();
+        self.gen_opcode_list_if_necessary();
+        self.publish_location();
+        true
+    }
+
+    fn handle_sync_command(&mut self, command: &SyncCommand) -> Result<()> {
+        let (file, line, enabled) = match command {
+            SyncCommand::Jump { file, line } => return self.run_to_line(file, *line),
+            SyncCommand::Break { file, line } => (file, *line, true),
+            SyncCommand::Clear { file, line } => (file, *line, false),
+        };
+
+        // the breakpoint is set in every contract of the session with a matching source file
+        let compilations = &self.session.artifact.compilation_artifacts;
+        let breakpoints = compilations
+            .addresses()
+            .filter_map(|address| compilations.get(address))
+            .flat_map(|compilation| {
+                compilation.sources.values().filter(|source| source.path.ends_with(file)).map(
+                    |source| Breakpoint {
+                        code_hash: compilation.code_hash,
+                        path: source.path.clone(),
+                        line,
+                    },
+                )
+            })
+            .collect::<BTreeSet<_>>();
+        if breakpoints.is_empty() {
+            return Err(
+                RecoverableError::new(format!("No contract is compiled from {file}.")).into()
+            );
+        }
+        for breakpoint in breakpoints {
+            self.breakpoints.set(breakpoint, enabled);
+        }
+        if let Err(e) = self.breakpoints.save() {
+            warn!("failed to save the breakpoints: {e}");
+        }
+        Ok(())
+    }
+
+    /// Sets a breakpoint on the entry of the function, or removes it if it is set. The function
+    /// must be found in the dispatcher of a contract of the current session.
+    pub(crate) fn toggle_function_breakpoint(&mut self, target: FunctionTarget) -> Result<()> {
+        if self.function_breakpoints.targets().contains(&target) {
+            self.function_breakpoints.toggle(target.clone());
+            if let Err(e) = self.function_breakpoints.save() {
+                warn!("failed to save the function breakpoints: {e}");
+            }
+            self.window.pop_info(
+                "Function breakpoint".to_string(),
+                format!("Removed the breakpoint on {target}."),
+            );
+            return Ok(());
+        }
+
+        let artifact = &*self.session.artifact;
+        let entries = target.resolve(artifact);
+        if entries.is_empty() {
+            return Err(RecoverableError::new(format!(
+                "No dispatcher of the touched contracts jumps to {target}."
+            ))
+            .into());
+        }
+        let mut message = format!("Breaking on the entry of {target} in:");
+        for entry in &entries {
+            let _ = write!(
+                message,
+                "\n  {} {} (pc {:#x})",
+                artifact.address_label(&entry.address),
+                entry.selector,
+                entry.pc
+            );
+        }
+        self.function_breakpoints.toggle(target);
+        if let Err(e) = self.function_breakpoints.save() {
+            warn!("failed to save the function breakpoints: {e}");
+        }
+        self.window.pop_info("Function breakpoint".to_string(), message);
+        Ok(())
+    }
+
+    /// Lists the function breakpoints, with the number of entries each one is resolved to in the
+    /// current session, and the source breakpoints in the contracts of the current session.
+    pub(crate) fn list_breakpoints(&mut self) {
+        let artifact = &*self.session.artifact;
+        let mut message = String::from("Functions (Shift+B)\n");
+        if self.function_breakpoints.targets().is_empty() {
+            message.push_str("  none\n");
+        }
+        for target in self.function_breakpoints.targets() {
+            let entries = target.resolve(artifact).len();
+            let _ = writeln!(message, "  {target} ({entries} entries in this session)");
+        }
+
+        message.push_str("\nSource lines (b)\n");
+        let code_hashes = artifact
+            .compilation_artifacts
+            .values()
+            .map(|compilation| compilation.code_hash)
+            .collect::<HashSet<_>>();
+        let mut shown = 0;
+        for breakpoint in self.breakpoints.iter().filter(|bp| code_hashes.contains(&bp.code_hash)) {
+            let _ = writeln!(message, "  {}:{}", breakpoint.path.display(), breakpoint.line);
+            shown += 1;
+        }
+        if shown == 0 {
+            message.push_str("  none\n");
+        }
+        let others = self.breakpoints.count() - shown;
+        if others > 0 {
+            let _ = writeln!(message, "  and {others} in contracts not touched in this session");
+        }
+        self.window.pop_info("Breakpoints".to_string(), message);
+    }
+
+    /// Continues until the next step which enters a line with a breakpoint, in any contract
+    /// sharing the code of the one the breakpoint was set in, the entry of a function with a
+    /// breakpoint, or the emission of an event matching the event breakpoint.
+    pub(crate) fn continue_to_breakpoint(&mut self) -> Result<()> {
+        self.ensure_movable()?;
+        let artifact = &*self.session.artifact;
+        let current = self.session.step_index();
+        let entries = self
+            .function_breakpoints
+            .targets()
+            .iter()
+            .flat_map(|target| target.resolve(artifact))
+            .map(|entry| (entry.address, entry.pc))
+            .collect::<HashSet<_>>();
+        let event = self.event_breakpoint.as_ref().and_then(|filter| {
+            self.session
+                .events()
+                .iter()
+                .filter(|event| event.step > current)
+                .find(|event| filter.matches(event, artifact.abi(&event.address)))
+                .map(|event| event.step)
+        });
+        // the steps past the matching event, if any, are not searched
+        let end = event.map_or(usize::MAX, |event| event - current);
+        let mut lines = LineTracker::default();
+        let found =
+            artifact.steps().skip(current).enumerate().take(end).find_map(|(k, (i, j, step))| {
+                let node = &artifact.debug_arena[i];
+                if k > 0 && entries.contains(&(node.address, step.pc)) {
+                    return Some(current + k);
+                }
+                // Stop only when the line is entered, rather than at each of its steps
+                let (path, line) = lines.enter(artifact, i, j)?;
+                let code_hash = artifact.compilation_artifacts.get(&node.address)?.code_hash;
+                (k > 0 && self.breakpoints.contains(code_hash, path, line)).then_some(current + k)
+            });
+
+        let index = found
+            .or(event)
+            .ok_or_else(|| RecoverableError::new("No breakpoint is hit anymore."))?;
+        self.locate(index)
+    }
+
+    /// Jumps to the next branch decision (i.e., `JUMPI`) in the execution.
+    pub(crate) fn next_branch(&mut self) -> Result<()> {
+        self.ensure_movable()?;
+        let (node, step) = (self.session.draw_memory.inner_call_index, self.session.current_step);
+        let found = self.debug_arena().iter().enumerate().skip(node).find_map(|(i, n)| {
+            let from = if i == node { step + 1 } else { 0 };
+            n.steps
+                .iter()
+                .enumerate()
+                .skip(from)
+                .find(|(_, s)| s.branch_taken().is_some())
+                .map(|(j, _)| (i, j))
+        });
+
+        let (node, step) =
+            found.ok_or_else(|| RecoverableError::new("There is no next branch decision."))?;
+        self.session.draw_memory.inner_call_index = node;
+        self.session.current_step = step;
+        Ok(())
+    }
+
+    /// Jumps to the previous branch decision (i.e., `JUMPI`) in the execution.
+    pub(crate) fn prev_branch(&mut self) -> Result<()> {
+        self.ensure_movable()?;
+        let (node, step) = (self.session.draw_memory.inner_call_index, self.session.current_step);
+        let found = self.debug_arena()[..=node].iter().enumerate().rev().find_map(|(i, n)| {
+            let to = if i == node { step } else { n.steps.len() };
+            n.steps[..to].iter().rposition(|s| s.branch_taken().is_some()).map(|j| (i, j))
+        });
+
+        let (node, step) =
+            found.ok_or_else(|| RecoverableError::new("There is no previous branch decision."))?;
+        self.session.draw_memory.inner_call_index = node;
+        self.session.current_step = step;
+        Ok(())
+    }
 }
 
 /// Grab number from buffer. Used for something like '10k' to move up 10 operations
-fn buffer_as_number(s: &str) -> usize {
+pub(crate) fn buffer_as_number(s: &str) -> usize {
     const MIN: usize = 1;
     const MAX: usize = 100_000;
-    s.parse().unwrap_or(MIN).clamp(MIN, MAX)
+    s.trim_end_matches('g').parse().unwrap_or(MIN).clamp(MIN, MAX)
 }
@@ -1,45 +1,169 @@
 use std::{
+    backtrace::Backtrace,
     io,
     ops::ControlFlow,
-    sync::{mpsc, Arc},
+    path::PathBuf,
+    sync::{mpsc, Arc, Mutex},
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyEventKind, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use edb_debug_backend::artifact::debug::DebugArtifact;
+use edb_utils::cache::CachePath;
 use eyre::Result;
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
 
-use crate::{context::FrontendContext, FrontendTerminal};
+use crate::{
+    context::{FrontendContext, FrontendState},
+    edit::TxEdit,
+    machine::Location,
+    session::Session,
+    sync::SyncDir,
+    theme::Theme,
+    FrontendTerminal,
+};
 
 /// Debugger exit reason.
 #[derive(Debug)]
 pub enum ExitReason {
     /// Exit using 'q'.
     CharExit,
+    /// The transaction of the session with the given tab index is to be re-run with the changes.
+    Rerun { session: usize, edit: TxEdit },
 }
 
+/// The minimum interval between two frames, i.e., about 60 frames per second.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// The interval between two redraws while calls and events are decoded in the background.
+const DECODING_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The interval between two reads of the commands sent by the editor, or of the position of the
+/// followed driver.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Default)]
-pub struct DebugFrountendBuilder {}
+pub struct DebugFrountendBuilder {
+    theme: Option<Theme>,
+    rerunnable: bool,
+    comparison: Option<[usize; 2]>,
+    sync_dir: Option<PathBuf>,
+    follow: Option<mpsc::Receiver<usize>>,
+    share: Option<mpsc::Sender<Location>>,
+    step_into_getters: bool,
+    state: Option<FrontendState>,
+}
 
 impl DebugFrountendBuilder {
+    /// Sets the theme, which defaults to [`Theme::from_env`].
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Offers to modify and re-run the transaction, in which case the frontend exits with
+    /// [`ExitReason::Rerun`].
+    pub fn rerunnable(mut self, rerunnable: bool) -> Self {
+        self.rerunnable = rerunnable;
+        self
+    }
+
+    /// Opens the session with the first tab index, compared with the session with the second
+    /// one.
+    pub fn compare(mut self, session: usize, peer: usize) -> Self {
+        self.comparison = Some([session, peer]);
+        self
+    }
+
+    /// Synchronizes the current position with an editor through the given directory, see
+    /// [`LOCATION_FILE`](crate::LOCATION_FILE) and [`COMMANDS_FILE`](crate::COMMANDS_FILE).
+    pub fn sync_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.sync_dir = dir;
+        self
+    }
+
+    /// Follows the position of another client driving the same execution, which is received as
+    /// the index of the step in the whole execution. The position cannot be moved otherwise
+    /// until the driver leaves.
+    pub fn follow(mut self, driver: mpsc::Receiver<usize>) -> Self {
+        self.follow = Some(driver);
+        self
+    }
+
+    /// Sends the position of the first session whenever it moves, so that others can follow it
+    /// read-only.
+    pub fn share(mut self, followers: Option<mpsc::Sender<Location>>) -> Self {
+        self.share = followers;
+        self
+    }
+
+    /// Steps into the calls to simple getters, rather than over them as single steps.
+    pub fn step_into_getters(mut self, step_into_getters: bool) -> Self {
+        self.step_into_getters = step_into_getters;
+        self
+    }
+
+    /// Restores the state of a previous frontend, see [`DebugFrontend::state`].
+    pub fn state(mut self, state: Option<FrontendState>) -> Self {
+        self.state = state;
+        self
+    }
+
     pub fn build(self, artifact: DebugArtifact) -> DebugFrontend {
-        DebugFrontend { artifact }
+        let name = match artifact.debug_arena.first() {
+            Some(node) => artifact.address_label(&node.address),
+            None => "transaction".to_string(),
+        };
+        self.build_sessions(vec![(name, artifact)])
+    }
+
+    /// Builds a frontend debugging multiple transactions, each in its own session tab.
+    pub fn build_sessions(self, artifacts: Vec<(String, DebugArtifact)>) -> DebugFrontend {
+        DebugFrontend {
+            artifacts,
+            theme: self.theme.unwrap_or_else(Theme::from_env),
+            rerunnable: self.rerunnable,
+            comparison: self.comparison,
+            sync_dir: self.sync_dir,
+            follow: self.follow,
+            share: self.share,
+            step_into_getters: self.step_into_getters,
+            state: self.state,
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct DebugFrontend {
-    /// The backend.
-    pub artifact: DebugArtifact,
+    /// The artifacts to debug, each of which is opened as a session named by the first element.
+    pub artifacts: Vec<(String, DebugArtifact)>,
+    pub theme: Theme,
+    /// Whether the transaction can be modified and re-run.
+    pub rerunnable: bool,
+    /// The sessions compared when the frontend starts, if any.
+    pub comparison: Option<[usize; 2]>,
+    /// The directory the position is synchronized with an editor through, if any.
+    pub sync_dir: Option<PathBuf>,
+    /// The position of the driver of the session, if it is only followed.
+    pub follow: Option<mpsc::Receiver<usize>>,
+    /// Where the position of the first session is sent whenever it moves, if it is shared.
+    pub share: Option<mpsc::Sender<Location>>,
+    /// Whether stepping goes into the calls to simple getters.
+    pub step_into_getters: bool,
+    /// The state set by the user, which is restored when the frontend starts and saved when it
+    /// exits, so that it can be carried over to the next frontend.
+    pub state: Option<FrontendState>,
 }
 
 impl DebugFrontend {
@@ -48,16 +172,20 @@ impl DebugFrontend {
         DebugFrountendBuilder::default()
     }
 
+    /// Adds another transaction to debug, which is opened as a new session tab.
+    pub fn add_session(&mut self, name: impl Into<String>, artifact: DebugArtifact) {
+        self.artifacts.push((name.into(), artifact));
+    }
+
     /// Run the frontend.
-    pub async fn render(&mut self) -> Result<()> {
-        self.try_run()?;
-        Ok(())
+    pub async fn render(&mut self) -> Result<ExitReason> {
+        self.try_run()
     }
 
     /// Starts the debugger TUI. Terminates the current process on failure or user exit.
     pub fn run_exit(mut self) -> ! {
         let code = match self.try_run() {
-            Ok(ExitReason::CharExit) => 0,
+            Ok(ExitReason::CharExit | ExitReason::Rerun { .. }) => 0,
             Err(e) => {
                 println!("{e}");
                 1
@@ -68,7 +196,10 @@ impl DebugFrontend {
 
     /// Starts the debugger TUI.
     pub fn try_run(&mut self) -> Result<ExitReason> {
-        eyre::ensure!(!self.artifact.debug_arena.is_empty(), "debug arena is empty");
+        eyre::ensure!(!self.artifacts.is_empty(), "nothing to debug");
+        for (name, artifact) in &self.artifacts {
+            eyre::ensure!(!artifact.debug_arena.is_empty(), "debug arena of {name} is empty");
+        }
 
         let backend = CrosstermBackend::new(io::stdout());
         let terminal = Terminal::new(backend)?;
@@ -78,9 +209,29 @@ impl DebugFrontend {
     #[instrument(target = "debugger", name = "run", skip_all, ret)]
     fn try_run_real(&mut self, terminal: &mut FrontendTerminal) -> Result<ExitReason> {
         // Create the context.
-        let mut cx = FrontendContext::new(&mut self.artifact)?;
+        let sessions = self
+            .artifacts
+            .iter_mut()
+            .map(|(name, artifact)| Session::new(name.clone(), artifact))
+            .collect();
+        let mut cx = FrontendContext::new(sessions, self.theme)?;
+        cx.rerunnable = self.rerunnable;
+        cx.skip_getters = !self.step_into_getters;
 
         cx.init();
+        if let Some(state) = self.state.take() {
+            cx.restore(state);
+        }
+        if let Some([session, peer]) = self.comparison {
+            cx.switch_session(session);
+            cx.compare_with(peer)?;
+        }
+        if let Some(dir) = &self.sync_dir {
+            cx.sync = Some(SyncDir::open(dir.clone())?);
+        }
+        cx.follow = self.follow.take();
+        cx.share = self.share.take();
+        cx.publish_location();
 
         // Create an event listener in a different thread.
         let (tx, rx) = mpsc::channel();
@@ -89,13 +240,71 @@ impl DebugFrontend {
             .spawn(move || Self::event_listener(tx))
             .expect("failed to spawn thread");
 
-        // Start the event loop.
+        *CRASH_CONTEXT.lock().unwrap_or_else(|e| e.into_inner()) = cx.crash_context();
+
+        // Start the event loop. Redraws are capped at the frame rate, so that events arriving
+        // in bursts (e.g., while holding a key) are handled together before the next frame.
+        let mut dirty = true;
+        let mut last_draw: Option<Instant> = None;
         loop {
-            cx.draw(terminal)?;
-            match cx.handle_event(rx.recv()?) {
+            // handle the commands sent by the editor and follow the driver on every iteration,
+            // whatever else is going on
+            let position = cx.position();
+            if cx.poll_sync() | cx.poll_follow() {
+                cx.touch_unless_moved(position);
+                dirty = true;
+            }
+
+            let wait = last_draw
+                .map_or(Duration::ZERO, |last| FRAME_INTERVAL.saturating_sub(last.elapsed()));
+            if dirty && wait.is_zero() {
+                cx.draw(terminal)?;
+                last_draw = Some(Instant::now());
+                dirty = false;
+            }
+
+            // redraw periodically while the decoded calls and events, and the accesses to the
+            // watched storage slot, stream in, and wake up periodically to poll the editor and
+            // the driver
+            let refreshing = cx.session.is_decoding() || cx.session.is_scanning_storage();
+            let polling = cx.sync.is_some() || cx.follow.is_some();
+            let timeout = if dirty {
+                Some(wait)
+            } else {
+                [refreshing.then_some(DECODING_REFRESH_INTERVAL), polling.then_some(POLL_INTERVAL)]
+                    .into_iter()
+                    .flatten()
+                    .min()
+            };
+            let event = match timeout {
+                Some(timeout) => match rx.recv_timeout(timeout) {
+                    Ok(event) => event,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        dirty |= refreshing;
+                        continue;
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        eyre::bail!("the event listener has stopped")
+                    }
+                },
+                None => rx.recv()?,
+            };
+            // stepping only draws again the panes whose view reads the part of the position
+            // which moved, any other change draws all of them
+            let redraw = changes_display(&event);
+            let position = cx.position();
+            match cx.handle_event(event) {
                 ControlFlow::Continue(()) => {}
-                ControlFlow::Break(reason) => return Ok(reason),
+                ControlFlow::Break(reason) => {
+                    self.state = Some(cx.state());
+                    return Ok(reason);
+                }
             }
+            if redraw {
+                cx.touch_unless_moved(position);
+                dirty = true;
+            }
+            *CRASH_CONTEXT.lock().unwrap_or_else(|e| e.into_inner()) = cx.crash_context();
         }
     }
 
@@ -123,6 +332,43 @@ impl DebugFrontend {
     }
 }
 
+/// Returns whether the event may change what is displayed. Mouse moves and focus changes of the
+/// terminal are not handled, so they do not need a redraw.
+fn changes_display(event: &Event) -> bool {
+    match event {
+        Event::Key(event) => event.kind != KeyEventKind::Release,
+        Event::Mouse(event) => !matches!(event.kind, MouseEventKind::Moved),
+        Event::FocusGained | Event::FocusLost => false,
+        _ => true,
+    }
+}
+
+/// A summary of the debugging state, which is included in the crash report if the frontend
+/// panics. The panic hook cannot reach the frontend context, so the summary is kept up to date
+/// by the event loop.
+static CRASH_CONTEXT: Mutex<String> = Mutex::new(String::new());
+
+/// Writes a crash report with the panic, a backtrace, and the debugging state, returning its
+/// path.
+fn write_crash_report(info: &std::panic::PanicInfo<'_>) -> Result<PathBuf> {
+    let dir = CachePath::edb_crash_report_dir()
+        .ok_or_else(|| eyre::eyre!("cannot find the home directory"))?;
+    std::fs::create_dir_all(&dir)?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let path = dir.join(format!("crash-{timestamp}.txt"));
+
+    let context = CRASH_CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let report = format!(
+        "edb {} ({} {})\ntimestamp: {timestamp}\n\n{info}\n\n## Debugging state\n{context}\n## Backtrace\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        Backtrace::force_capture(),
+    );
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
 type PanicHandler = Box<dyn Fn(&std::panic::PanicInfo<'_>) + 'static + Sync + Send>;
 
 /// Handles terminal state.
@@ -146,11 +392,20 @@ impl<B: Backend + io::Write> TerminalGuard<B> {
         // TODO: Use `std::panic::update_hook` when it's stable
         std::panic::set_hook(Box::new(move |info| {
             Self::half_restore(&mut std::io::stdout());
-            (previous)(info)
+            (previous)(info);
+            match write_crash_report(info) {
+                Ok(path) => eprintln!("A crash report has been written to {}", path.display()),
+                Err(e) => eprintln!("Failed to write a crash report: {e}"),
+            }
         }));
 
         let _ = enable_raw_mode();
-        let _ = execute!(*self.terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture);
+        let _ = execute!(
+            *self.terminal.backend_mut(),
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableBracketedPaste
+        );
         let _ = self.terminal.hide_cursor();
         let _ = self.terminal.clear();
     }
@@ -177,7 +432,7 @@ impl<B: Backend + io::Write> TerminalGuard<B> {
 
     fn half_restore(w: &mut impl io::Write) {
         let _ = disable_raw_mode();
-        let _ = execute!(*w, LeaveAlternateScreen, DisableMouseCapture);
+        let _ = execute!(*w, LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste);
     }
 }
 
@@ -1,36 +1,112 @@
 //! TUI draw implementation.
 
 use alloy_primitives::U256;
+use edb_debug_backend::{
+    analysis::{
+        diff::StorageWrite,
+        funds::Asset,
+        memory::{memory_labels, MemoryLabel},
+        provenance::ReturndataOrigin,
+        storage::StorageAccessKind,
+        token::TokenMetadata,
+        userop::UserOpPhase,
+    },
+    artifact::{compilation::SourceFile, usage::format_bytes, verification::VerificationStatus},
+    export::calltree::FrameBudget,
+};
+use edb_utils::profile::{span, Phase};
 use foundry_compilers::artifacts::sourcemap::SourceElement;
 use ratatui::{
+    buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     symbols::border,
     terminal::Frame,
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Sparkline, Tabs, Wrap},
 };
 use revm::interpreter::opcode;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{BTreeMap, HashSet, VecDeque},
     fmt::Write,
     io,
 };
 
 const POPUP_WIDTH: u16 = 60;
 const MIN_POPUP_HEIGHT: u16 = 10;
+/// The memory the sessions may hold before the status bar warns about it.
+const MEMORY_BUDGET: usize = 4 << 30;
 
 use crate::{
-    context::FrontendContext,
+    context::{ExecutionPosition, FrontendContext, GovernanceRow, OpRow, UserOpRow, ViewState},
     utils::opcode::OpcodeParam,
-    window::{PaneFlattened, PaneView, PopupMessage, TerminalMode},
+    window::{PaneFlattened, PaneId, PaneView, PopupMessage, TerminalMode},
     FrontendTerminal,
 };
 
+/// What a pane is drawn from, which is compared with the pane drawn in the previous frame to
+/// reuse it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PaneKey {
+    /// The generation of the state, see [`FrontendContext::touch`].
+    pub generation: u64,
+    pub area: Rect,
+    pub view: PaneView,
+    /// The scroll offset and the cursor of the view.
+    pub state: ViewState,
+    /// Whether the border of the pane is highlighted as focused.
+    pub highlighted: bool,
+    /// The part of the position of the execution the view reads.
+    pub position: ExecutionPosition,
+}
+
+/// The panes drawn in the previous frame, identified by their id and whether they are overlays.
+#[derive(Default)]
+pub(crate) struct PaneCache {
+    panes: FxHashMap<(PaneId, bool), (PaneKey, Buffer)>,
+}
+
+impl PaneCache {
+    /// Returns the pane drawn in the previous frame, if it was drawn from the same key.
+    pub(crate) fn get(&self, id: (PaneId, bool), key: &PaneKey) -> Option<&Buffer> {
+        self.panes.get(&id).filter(|(cached, _)| cached == key).map(|(_, buffer)| buffer)
+    }
+
+    pub(crate) fn insert(&mut self, id: (PaneId, bool), key: PaneKey, buffer: Buffer) {
+        self.panes.insert(id, (key, buffer));
+    }
+
+    /// Drops the panes which are no longer drawn.
+    pub(crate) fn retain(&mut self, drawn: &[(PaneId, bool)]) {
+        self.panes.retain(|id, _| drawn.contains(id));
+    }
+}
+
 impl FrontendContext<'_> {
     /// Draws the TUI layout and subcomponents to the given terminal.
     pub(crate) fn draw(&mut self, terminal: &mut FrontendTerminal) -> io::Result<()> {
-        terminal.draw(|f| self.draw_layout(f)).map(drop)
+        let _render = span(Phase::Render);
+        let mut changed = self.session.poll_decoded();
+        match self.session.load_snapshots() {
+            Ok(loaded) => changed |= loaded,
+            Err(e) => {
+                warn!("failed to record the snapshots of the current call: {e}");
+                self.window.pop_error_message(format!(
+                    "The stack and memory of this call could not be recorded: {e}"
+                ));
+                changed = true;
+            }
+        }
+        if changed {
+            self.touch();
+        }
+        terminal
+            .draw(|f| {
+                self.draw_layout(f);
+                self.theme.apply(f.buffer_mut());
+            })
+            .map(drop)
     }
 
     #[inline]
@@ -99,6 +175,29 @@ impl FrontendContext<'_> {
             unreachable!()
         };
 
+        // Split off the session tabs, which are only shown when there are multiple sessions.
+        let app = if self.num_sessions() > 1 {
+            let [tabs, app] =
+                Layout::new(Direction::Vertical, [Constraint::Length(1), Constraint::Min(0)])
+                    .split(app)[..]
+            else {
+                unreachable!()
+            };
+            self.draw_session_tabs(f, tabs);
+            app
+        } else {
+            app
+        };
+
+        // Split off the status bar
+        let [app, status] =
+            Layout::new(Direction::Vertical, [Constraint::Min(0), Constraint::Length(1)])
+                .split(app)[..]
+        else {
+            unreachable!()
+        };
+        self.draw_status_bar(f, status);
+
         // update screen size
         self.window.screen_size = app;
 
@@ -108,7 +207,35 @@ impl FrontendContext<'_> {
             self.draw_footer(f, footer);
         }
 
+        let position = self.position();
+        let mut drawn = Vec::with_capacity(layout.len());
         for pane in layout {
+            if pane.overlay {
+                f.render_widget(Clear, pane.rect);
+            }
+
+            // Copy the pane from the previous frame if nothing it is drawn from changed since. The
+            // terminal and the comparison depend on more than the state, so they are drawn in
+            // every frame.
+            let cache_id = (pane.id, pane.overlay);
+            let cacheable = !matches!(pane.view, PaneView::Terminal | PaneView::Compare);
+            let rect = pane.rect.intersection(f.size());
+            let key = PaneKey {
+                generation: self.generation,
+                area: rect,
+                view: pane.view,
+                state: self.view_state(pane.view),
+                highlighted: pane.focused && !self.window.has_popup(),
+                position: position.read_by(pane.view),
+            };
+            drawn.push(cache_id);
+            if cacheable {
+                if let Some(buffer) = self.pane_cache.borrow().get(cache_id, &key) {
+                    f.buffer_mut().merge(buffer);
+                    continue;
+                }
+            }
+
             match pane.view {
                 PaneView::Memory | PaneView::Calldata | PaneView::Returndata => {
                     self.draw_buffer(f, pane)
@@ -116,13 +243,37 @@ impl FrontendContext<'_> {
                 PaneView::Expression => self.draw_expressions(f, pane),
                 PaneView::Variable => self.draw_variables(f, pane),
                 PaneView::Stack => self.draw_stack(f, pane),
+                PaneView::Compare => self.draw_compare(f, pane),
                 PaneView::Source => self.draw_src(f, pane),
                 PaneView::Trace => self.draw_trace(f, pane),
                 PaneView::Opcode => self.draw_op_list(f, pane),
                 PaneView::Terminal => self.draw_terminal(f, pane),
+                PaneView::Contracts => self.draw_contracts(f, pane),
+                PaneView::Sessions => self.draw_sessions(f, pane),
+                PaneView::Warnings => self.draw_warnings(f, pane),
+                PaneView::Cfg => self.draw_cfg(f, pane),
+                PaneView::Timeline => self.draw_timeline(f, pane),
+                PaneView::UserOps => self.draw_user_ops(f, pane),
+                PaneView::Governance => self.draw_governance(f, pane),
+                PaneView::Events => self.draw_events(f, pane),
+                PaneView::Storage => self.draw_storage(f, pane),
+                PaneView::Assembly => self.draw_assembly(f, pane),
                 PaneView::Null => self.draw_null(f, pane),
             }
+
+            if cacheable {
+                let mut buffer = Buffer::empty(rect);
+                for y in rect.top()..rect.bottom() {
+                    for x in rect.left()..rect.right() {
+                        *buffer.get_mut(x, y) = f.buffer_mut().get(x, y).clone();
+                    }
+                }
+                // drawing may scroll the view, e.g., to follow the execution
+                let key = PaneKey { state: self.view_state(key.view), ..key };
+                self.pane_cache.borrow_mut().insert(cache_id, key, buffer);
+            }
         }
+        self.pane_cache.borrow_mut().retain(&drawn);
 
         if let Ok(message) = self.window.get_popup_message() {
             // the background of the popup will take up 4 more columns and 4 more rows than the
@@ -133,7 +284,8 @@ impl FrontendContext<'_> {
 
     fn get_focused_block<'a>(&'a self, pane: &PaneFlattened<'a>) -> Block<'static> {
         // prepare the style
-        let (border_style, border_set) = if pane.focused {
+        // Popups capture the focus, so no pane is highlighted while one is shown
+        let (border_style, border_set) = if pane.focused && !self.window.has_popup() {
             if self.window.editor_mode == TerminalMode::Insert && pane.view == PaneView::Terminal {
                 (Style::default().fg(Color::LightGreen), border::DOUBLE)
             } else if pane.view == PaneView::Null {
@@ -186,6 +338,11 @@ impl FrontendContext<'_> {
             .border_set(border_set)
             .title(Line::from(spans));
 
+        // show that the pane does not scroll with the execution
+        if !pane.follow && pane.view.can_follow() {
+            block = block.title_bottom(Line::from(" [ Not Following ] ").right_aligned());
+        }
+
         // update bottom right corner with the terminal mode
         if pane.view == PaneView::Terminal {
             match self.window.editor_mode {
@@ -223,6 +380,10 @@ impl FrontendContext<'_> {
         };
 
         let mut editor_mut = self.window.editor.borrow_mut();
+        // Show the cursor position, which helps to enter long inputs such as calldata
+        let (row, col) = editor_mut.cursor();
+        let position = format!(" Ln {}, Col {} ", row + 1, col + 1);
+        let block = block.title_bottom(Line::from(position).right_aligned());
         editor_mut.set_cursor_line_style(cursor_line_style);
         editor_mut.set_cursor_style(cursor_style);
         editor_mut.set_block(block);
@@ -291,12 +452,174 @@ impl FrontendContext<'_> {
         f.render_widget(paragraph, pane.rect);
     }
 
-    // TODO
+    /// Draws the call frames of the whole execution as a tree, with the current one marked.
+    /// The calls relayed by Safe multisig wallets are marked as the transactions they really
+    /// are, the `execTransaction` calls being annotated with their signers. The calls of a
+    /// Multicall batch are flattened to the level of the aggregate call, each with its outcome.
     fn draw_trace<'a>(&self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
         let block = self.get_focused_block(&pane);
-        let paragraph = Paragraph::new(Text::from(format!("trace displaying under construction")))
-            .block(block)
-            .wrap(Wrap { trim: false });
+        let artifact = &*self.session.artifact;
+        let frames = self.session.call_frames();
+        let safe_transactions = self.session.safe_transactions();
+        let governance = self.session.governance_executions();
+        let batches = self.session.batches();
+        let deployments = self.session.create2_deployments();
+        let created = self.session.create_deployments();
+        let step = self.session.step_index();
+        let current = self.session.current_frame();
+
+        let height = pane.rect.height.saturating_sub(2) as usize;
+        let row = current.unwrap_or(0);
+        let view_state = self.clamp_view_state(pane.view, frames.len(), height);
+        let offset = if view_state.recenter {
+            row.saturating_sub(height / 2)
+        } else if pane.follow {
+            scroll_window(view_state.offset, height, frames.len(), row, 3)
+        } else {
+            view_state.offset
+        };
+        self.update_view_state(pane.view, |view_state| {
+            view_state.offset = offset;
+            view_state.recenter = false;
+        });
+
+        let mut lines = Vec::with_capacity(height);
+        for (index, frame) in frames.iter().enumerate().skip(offset).take(height) {
+            // the frames within batches are outdented, as if the calls were made by the caller of
+            // the aggregate call
+            let batched = batches
+                .iter()
+                .filter(|batch| {
+                    let aggregate = &frames[batch.frame].steps;
+                    batch.frame < index && frame.steps.end <= aggregate.end
+                })
+                .count();
+            let depth = artifact.debug_arena[frame.node].depth - batched;
+            let function = if frame.kind.is_any_create() {
+                "new".to_string()
+            } else if let Some(call) = self.session.decoded_calls.get(&index) {
+                call.clone()
+            } else if let Some(function) = frame.function(artifact) {
+                function.name.clone()
+            } else if let Some(selector) = frame.input.get(..4) {
+                alloy_primitives::hex::encode_prefixed(selector)
+            } else {
+                "fallback".to_string()
+            };
+            let relayed = safe_transactions.iter().find(|tx| tx.inner == Some(index));
+            let marker = match (Some(index) == current, relayed.is_some()) {
+                (true, _) => "▶ ",
+                (false, true) => "★ ",
+                (false, false) => "  ",
+            };
+            let mut content = format!(
+                "{marker}{:indent$}[{}] {}::{function}",
+                "",
+                frame.kind,
+                artifact.address_label(&frame.address),
+                indent = depth * 2,
+            );
+            if !frame.value.is_zero() {
+                write!(content, " {{value: {}}}", frame.value).unwrap();
+            }
+            if !frame.outcome.is_success() {
+                write!(content, " ({:?})", frame.outcome).unwrap();
+            }
+            if relayed.is_some() {
+                content.push_str(" [real transaction]");
+            }
+            let action = governance.iter().find_map(|execution| {
+                execution.actions.iter().position(|action| action.frame == Some(index))
+            });
+            if let Some(action) = action {
+                write!(content, " [proposal action #{action}]").unwrap();
+            }
+            let batch = batches.iter().find(|batch| batch.frame == index);
+            if let Some(batch) = batch {
+                write!(
+                    content,
+                    " [{} batch of {} call(s), {} failed]",
+                    batch.kind,
+                    batch.calls.len(),
+                    batch.num_failed()
+                )
+                .unwrap();
+            }
+            let batched_call = batches.iter().find_map(|batch| {
+                batch.call_of(index).map(|i| (batch.calls.len(), i, &batch.calls[i]))
+            });
+            if let Some((len, i, call)) = batched_call {
+                let status = match (call.success, call.allow_failure) {
+                    (Some(true), _) => "✓",
+                    (_, true) => "✗ allowed",
+                    (_, false) => "✗",
+                };
+                write!(content, " [call {}/{len} {status}]", i + 1).unwrap();
+            }
+
+            let style = if Some(index) == current {
+                Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            } else if relayed.is_some() {
+                Style::new().fg(Color::Green).add_modifier(Modifier::BOLD)
+            } else if batch.is_some() {
+                Style::new().fg(Color::DarkGray)
+            } else if !frame.outcome.is_success() {
+                Style::new().fg(Color::Red)
+            } else if frame.steps.start > step {
+                Style::new().add_modifier(Modifier::DIM)
+            } else {
+                Style::new()
+            };
+            let mut spans = vec![Span::styled(content, style)];
+
+            if let Some(tx) = safe_transactions.iter().find(|tx| tx.frame == index) {
+                let signers: Vec<_> =
+                    tx.signers().map(|signer| artifact.address_label(&signer)).collect();
+                let mut annotation = format!(
+                    "  [Safe {} tx → {}",
+                    artifact.address_label(&tx.safe),
+                    artifact.address_label(&tx.to)
+                );
+                if tx.delegate {
+                    annotation.push_str(" (delegate)");
+                }
+                if !tx.value.is_zero() {
+                    write!(annotation, ", value {}", tx.value).unwrap();
+                }
+                write!(annotation, ", {} signature(s)", tx.signatures.len()).unwrap();
+                if !signers.is_empty() {
+                    write!(annotation, " by {}", signers.join(", ")).unwrap();
+                }
+                annotation.push(']');
+                spans.push(Span::styled(annotation, Style::new().fg(Color::Magenta)));
+            }
+            if let Some(deployment) = deployments.iter().find(|d| d.frame == index) {
+                let (annotation, color) = if deployment.is_verified() {
+                    (format!("  [salt {} ✓]", deployment.salt), Color::Blue)
+                } else {
+                    let annotation = format!(
+                        "  [salt {}, expected at {} ✗]",
+                        deployment.salt, deployment.derived
+                    );
+                    (annotation, Color::Red)
+                };
+                spans.push(Span::styled(annotation, Style::new().fg(color)));
+            }
+            let nonce = created.iter().find(|d| d.frame == index).and_then(|d| d.nonce);
+            if let Some(nonce) = nonce {
+                spans
+                    .push(Span::styled(format!("  [nonce {nonce}]"), Style::new().fg(Color::Blue)));
+            }
+            if Some(index) == current {
+                spans.push(Span::styled(
+                    format!("  [{}]", format_budget(&frame.budget(frames))),
+                    Style::new().fg(Color::Yellow),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let paragraph = Paragraph::new(lines).block(block);
         f.render_widget(paragraph, pane.rect);
     }
 
@@ -320,9 +643,235 @@ impl FrontendContext<'_> {
         f.render_widget(paragraph, pane.rect);
     }
 
+    fn draw_contracts<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let current = self.address();
+        let items = self
+            .session
+            .artifact
+            .touched_addresses()
+            .into_iter()
+            .map(|address| {
+                let mut spans = vec![Span::raw(address.to_string())];
+                if let Some(compilation) = self.session.artifact.compilation_artifacts.get(&address)
+                {
+                    spans.push(Span::styled(
+                        format!(" {}", compilation.contract_name),
+                        Style::new().fg(Color::Green),
+                    ));
+                    let clones = self
+                        .session
+                        .artifact
+                        .compilation_artifacts
+                        .addresses_of(&compilation.code_hash)
+                        .len();
+                    if clones > 1 {
+                        spans.push(Span::styled(
+                            format!(" (same code as {} others)", clones - 1),
+                            Style::new().fg(Color::DarkGray),
+                        ));
+                    }
+                }
+                if let Some(implementation) = self.session.artifact.clones.get(&address) {
+                    spans.push(Span::styled(
+                        format!(
+                            " → clone of {}",
+                            self.session.artifact.address_label(implementation)
+                        ),
+                        Style::new().fg(Color::Magenta),
+                    ));
+                }
+                match self.session.artifact.verification.get(&address) {
+                    Some(VerificationStatus::Mismatch) => spans
+                        .push(Span::styled(" (bytecode mismatch)", Style::new().fg(Color::Red))),
+                    Some(VerificationStatus::Partial) => {
+                        spans.push(Span::styled(" (partial match)", Style::new().fg(Color::Yellow)))
+                    }
+                    _ => {}
+                }
+                if let Some(flag) = self.session.artifact.flags.get(&address) {
+                    spans.push(Span::styled(
+                        format!(" [{flag}]"),
+                        Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    ));
+                }
+                if let Some(token) = self.session.artifact.tokens.get(&address) {
+                    spans.push(Span::styled(
+                        format!(" {} ({} decimals)", token.symbol, token.decimals),
+                        Style::new().fg(Color::Cyan),
+                    ));
+                }
+                if let Some(standards) = self.session.artifact.interfaces.get(&address) {
+                    let standards: Vec<_> = standards.iter().map(ToString::to_string).collect();
+                    spans.push(Span::styled(
+                        format!(" [{}]", standards.join(", ")),
+                        Style::new().fg(Color::Yellow),
+                    ));
+                }
+                if let Some(layout) = self.session.code_layouts().get(&address) {
+                    let mut parts = vec![
+                        format!("code {}", format_bytes(layout.code_size)),
+                        format!("data {}", format_bytes(layout.data_size)),
+                        format!("metadata {}", format_bytes(layout.metadata_size)),
+                    ];
+                    if let Some(immutables) = layout.immutables.filter(|n| *n > 0) {
+                        parts.push(format!("{immutables} immutables"));
+                    }
+                    if let Some(init_size) = layout.init_size {
+                        parts.push(format!("init {}", format_bytes(init_size)));
+                    }
+                    // code over the limit of EIP-170 only deploys on chains without it
+                    let color = if layout.headroom() < 0 { Color::Red } else { Color::DarkGray };
+                    spans.push(Span::styled(
+                        format!(" {} ({})", format_bytes(layout.runtime_size), parts.join(", ")),
+                        Style::new().fg(color),
+                    ));
+                }
+
+                let style = if address == *current {
+                    Style::new().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::new().add_modifier(Modifier::DIM)
+                };
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect::<Vec<_>>();
+
+        self.render_cursor_list(f, pane, items);
+    }
+
+    /// Renders a list whose cursor and scroll offset are kept in the view state.
+    fn render_cursor_list(
+        &self,
+        f: &mut Frame<'_>,
+        pane: PaneFlattened<'_>,
+        items: Vec<ListItem<'_>>,
+    ) {
+        let block = self.get_focused_block(&pane);
+        self.render_cursor_list_in(f, pane, block, items);
+    }
+
+    /// Draws a list with a cursor like [`Self::render_cursor_list`], in the given block.
+    fn render_cursor_list_in(
+        &self,
+        f: &mut Frame<'_>,
+        pane: PaneFlattened<'_>,
+        block: Block<'_>,
+        items: Vec<ListItem<'_>>,
+    ) {
+        let height = pane.rect.height.saturating_sub(2) as usize;
+        let view_state = self.clamp_view_state(pane.view, items.len(), height);
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().bg(Color::DarkGray))
+            .scroll_padding(1);
+        let mut state = ListState::default()
+            .with_selected(Some(view_state.cursor))
+            .with_offset(view_state.offset);
+        f.render_stateful_widget(list, pane.rect, &mut state);
+        self.update_view_state(pane.view, |view_state| view_state.offset = state.offset());
+    }
+
+    fn draw_session_tabs(&self, f: &mut Frame<'_>, area: Rect) {
+        let titles: Vec<_> = self
+            .session_names()
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| format!("{}: {name}", i + 1))
+            .collect();
+        let tabs = Tabs::new(titles)
+            .select(self.session_index)
+            .highlight_style(Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+        f.render_widget(tabs, area);
+    }
+
+    fn draw_sessions<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let items = self
+            .session_names()
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                if i == self.session_index {
+                    let content = format!("▶ {name} (step {})", self.session.current_step);
+                    ListItem::new(content).style(Style::new().add_modifier(Modifier::BOLD))
+                } else {
+                    ListItem::new(format!("  {name}"))
+                        .style(Style::new().add_modifier(Modifier::DIM))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.render_cursor_list(f, pane, items);
+    }
+
+    fn draw_warnings<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let items = self
+            .session
+            .artifact
+            .warnings
+            .iter()
+            .map(|warning| {
+                let mut spans = vec![Span::styled(
+                    format!("[{}] ", warning.kind),
+                    Style::new().fg(Color::Yellow),
+                )];
+                if let Some(address) = &warning.address {
+                    spans.push(Span::styled(
+                        format!("{}: ", self.session.artifact.address_label(address)),
+                        Style::new().fg(Color::Cyan),
+                    ));
+                }
+                spans.push(Span::raw(warning.message.as_str()));
+                ListItem::new(Line::from(spans))
+            })
+            .collect::<Vec<_>>();
+
+        self.render_cursor_list(f, pane, items);
+    }
+
+    /// Draws the number of warnings of each kind, so that they are not missed, and the memory
+    /// held by the sessions, which turns red past the budget.
+    fn draw_status_bar(&self, f: &mut Frame<'_>, area: Rect) {
+        let usage = self.memory_usage().total();
+        let (memory, memory_style) = if usage > MEMORY_BUDGET {
+            let text = format!(
+                " ⚠ {} held, over the {} budget, Ctrl+G to compact ",
+                format_bytes(usage),
+                format_bytes(MEMORY_BUDGET)
+            );
+            (text, Style::new().fg(Color::White).bg(Color::Red))
+        } else {
+            (format!(" {} held ", format_bytes(usage)), Style::new().fg(Color::Gray))
+        };
+        let [warnings, memory_area] = Layout::new(
+            Direction::Horizontal,
+            [Constraint::Min(0), Constraint::Length(memory.chars().count() as u16)],
+        )
+        .split(area)[..] else {
+            unreachable!()
+        };
+        f.render_widget(Paragraph::new(memory).style(memory_style), memory_area);
+
+        if self.session.artifact.warnings.is_empty() {
+            return;
+        }
+        let mut counts = BTreeMap::new();
+        for warning in &self.session.artifact.warnings {
+            *counts.entry(warning.kind).or_insert(0) += 1;
+        }
+        let counts: Vec<_> =
+            counts.into_iter().map(|(kind, count)| format!("{count} × {kind}")).collect();
+        let text = format!(
+            " ⚠ {} warning(s): {} (see the Warnings pane)",
+            self.session.artifact.warnings.len(),
+            counts.join(", ")
+        );
+        let paragraph = Paragraph::new(text).style(Style::new().fg(Color::Black).bg(Color::Yellow));
+        f.render_widget(paragraph, warnings);
+    }
+
     fn draw_footer(&self, f: &mut Frame<'_>, area: Rect) {
-        let l1 = "[q]: quit | [k/j]: prev/next op | [a/s]: prev/next jump | [c/C]: prev/next call | [g/G]: start/end | [b]: cycle variable/watcher/memory/calldata/returndata/stack";
-        let l2 = "[t]: stack labels | [m]: buffer decoding | [shift + k]: cycle trace/source | [ctrl + j/k]: scroll data | ['<char>]: goto breakpoint | [h] toggle help";
+        let l1 = "[q]: quit | [k/j]: prev/next op | [a/s]: prev/next jump | [e]: expand/collapse loop | [c/C]: prev/next call | [g/G]: start/end | [b]: cycle variable/watcher/memory/calldata/returndata/stack";
+        let l2 = "[t]: stack labels | [m]: buffer decoding | [shift + k]: cycle trace/source | [ctrl + j/k]: scroll data | ['<char>]: goto breakpoint | [[/]]: prev/next branch | [tab]: cycle sessions | [V]: compare sessions | [Z]: zoom pane | [P]: set view | [ctrl + p]: commands | [ctrl + z/y]: undo/redo layout | [:]: go to step | [!]: suggested steps | [gg/G]: top/bottom | [ctrl + d/u]: half page | [L]: run to line | [A]: go to address | [R]: modify & re-run | [f]: follow execution | [c]: jump to current | [i]: explain opcode | [b/n]: toggle/continue to breakpoint | [ctrl + k/u/w, ctrl + y]: kill/yank in terminal | [h] toggle help";
         let dimmed = Style::new().add_modifier(Modifier::DIM);
         let lines =
             vec![Line::from(Span::styled(l1, dimmed)), Line::from(Span::styled(l2, dimmed))];
@@ -332,7 +881,20 @@ impl FrontendContext<'_> {
     }
 
     fn draw_src<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
-        let (text_output, _) = self.src_text(pane.rect);
+        let view_state = self.view_state(pane.view);
+        let follow = pane.follow || view_state.recenter;
+        let (text_output, _, start_line) = self.src_text(pane.rect, follow);
+        // Without following, the whole file is shown and scrolled to the kept offset
+        let scroll = if follow {
+            self.update_view_state(pane.view, |state| {
+                state.offset = start_line;
+                state.recenter = false;
+            });
+            0
+        } else {
+            let height = pane.rect.height.saturating_sub(2) as usize;
+            self.clamp_view_state(pane.view, text_output.lines.len(), height).offset
+        };
         // let call_kind_text = match self.call_kind() {
         //     CallKind::Create | CallKind::Create2 => "Contract creation",
         //     CallKind::Call => "Contract call",
@@ -342,15 +904,21 @@ impl FrontendContext<'_> {
         //     CallKind::AuthCall => "Contract authcall",
         // };
         let block = self.get_focused_block(&pane);
-        let paragraph = Paragraph::new(text_output).block(block).wrap(Wrap { trim: false });
+        let paragraph = Paragraph::new(text_output)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll as u16, 0));
         f.render_widget(paragraph, pane.rect);
     }
 
-    fn src_text(&self, area: Rect) -> (Text<'_>, Option<&str>) {
-        let (source_element, source_code, source_file) = match self.src_map() {
+    /// Returns the source code around the current execution point, its path, and the first line
+    /// shown. If `follow` is false, the whole source code is returned instead.
+    fn src_text(&self, area: Rect, follow: bool) -> (Text<'_>, Option<&str>, usize) {
+        let (source_element, source_file) = match self.src_map() {
             Ok(r) => r,
-            Err(e) => return (Text::from(e), None),
+            Err(e) => return (self.generated_src_text().unwrap_or_else(|| Text::from(e)), None, 0),
         };
+        let source_code = source_file.code.as_str();
 
         // We are handed a vector of SourceElements that give us a span of sourcecode that is
         // currently being executed. This includes an offset and length.
@@ -374,7 +942,9 @@ impl FrontendContext<'_> {
         let mid_len = before.len() + actual.len();
 
         // adjust what text we show of the source code
-        let (start_line, end_line) = if needed_highlight > height {
+        let (start_line, end_line) = if !follow {
+            (0, num_lines)
+        } else if needed_highlight > height {
             // highlighted section is more lines than we have available
             let start_line = before.len().saturating_sub(1);
             (start_line, before.len() + needed_highlight)
@@ -413,7 +983,13 @@ impl FrontendContext<'_> {
         // Highlighted text: cyan, bold.
         let h_text = Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD);
 
-        let mut lines = SourceLines::new(decimal_digits(num_lines));
+        let mut lines = SourceLines::new(
+            start_line,
+            decimal_digits(num_lines),
+            self.branch_decisions(source_file),
+            self.breakpoint_lines(source_file),
+            self.line_heat(source_file),
+        );
 
         // We check if there is other text on the same line before the highlight starts.
         if let Some(last) = before.pop() {
@@ -471,93 +1047,183 @@ impl FrontendContext<'_> {
             }
         }
 
-        (Text::from(lines.lines), Some(source_file))
+        (Text::from(lines.lines), source_file.path.to_str(), start_line)
     }
 
-    /// Returns source map, source code and source name of the current line.
-    fn src_map(&self) -> Result<(SourceElement, &str, &str), String> {
-        Err(format!("source code displaying under construction"))
+    /// Returns the source element and the source file of the current step.
+    fn src_map(&self) -> Result<(&SourceElement, &SourceFile), String> {
+        let address = self.address();
+        let Some(artifact) = self.session.artifact.compilation_artifacts.get(address) else {
+            return Err(format!("Unknown contract at address {address}"));
+        };
 
-        // let address = self.address();
-        // let Some(contract_name) = self.debugger.identified_contracts.get(address) else {
-        //     return Err(format!("Unknown contract at address {address}"));
-        // };
+        let is_create = self.call_kind().is_any_create();
+        let pc = self.current_step().pc;
+        artifact.source_element(pc, is_create).ok_or_else(|| {
+            format!("No source map for contract {} at pc {pc}", artifact.contract_name)
+        })
+    }
 
-        // let Some(mut files_source_code) =
-        //     self.debugger.contracts_sources.get_sources(contract_name)
-        // else {
-        //     return Err(format!("No source map index for contract {contract_name}"));
-        // };
+    /// Returns the compiler-generated code the current step is mapped to, e.g., a Yul utility
+    /// function of a via-IR compilation, which is shown instead of the actual sources.
+    fn generated_src_text(&self) -> Option<Text<'_>> {
+        let artifact = self.session.artifact.compilation_artifacts.get(self.address())?;
+        let is_create = self.call_kind().is_any_create();
+        let (element, source) = artifact.generated_element(self.current_step().pc, is_create)?;
+
+        // Generated sources are huge and unknown to the user, so only the lines being executed
+        // are shown
+        let code = source.code.as_str();
+        let start = (element.offset() as usize).min(code.len());
+        let end = (start + element.length() as usize).min(code.len());
+        let first = code[..start].rfind('\n').map_or(0, |i| i + 1);
+        let last = code[end..].find('\n').map_or(code.len(), |i| end + i);
+
+        let mut lines = vec![
+            Line::styled(
+                format!("Compiler-generated code ({})", source.name),
+                Style::new().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            ),
+            Line::default(),
+        ];
+        let style = Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+        lines.extend(code[first..last].lines().map(|line| Line::styled(line, style)));
+        Some(Text::from(lines))
+    }
 
-        // let Some((create_map, rt_map)) = self.debugger.pc_ic_maps.get(contract_name) else {
-        //     return Err(format!("No PC-IC maps for contract {contract_name}"));
-        // };
+    /// Returns the lines of the given file with a breakpoint in the current contract.
+    fn breakpoint_lines(&self, source_file: &SourceFile) -> FxHashSet<usize> {
+        let Some(artifact) = self.session.artifact.compilation_artifacts.get(self.address()) else {
+            return FxHashSet::default();
+        };
+        self.breakpoints.lines(artifact.code_hash, &source_file.path).collect()
+    }
 
-        // let is_create = matches!(self.call_kind(), CallKind::Create | CallKind::Create2);
-        // let pc = self.current_step().pc;
-        // let Some((source_element, source_code, source_file)) =
-        //     files_source_code.find_map(|(artifact, source)| {
-        //         let bytecode = if is_create {
-        //             &artifact.bytecode.bytecode
-        //         } else {
-        //             artifact.bytecode.deployed_bytecode.bytecode.as_ref()?
-        //         };
-        //         let source_map = bytecode.source_map()?.expect("failed to parse");
-
-        //         let pc_ic_map = if is_create { create_map } else { rt_map };
-        //         let ic = pc_ic_map.get(pc)?;
-
-        //         // Solc indexes source maps by instruction counter, but Vyper indexes by program
-        //         // counter.
-        //         let source_element = if matches!(source.language, MultiCompilerLanguage::Solc(_))
-        // {             source_map.get(ic)?
-        //         } else {
-        //             source_map.get(pc)?
-        //         };
-        //         // if the source element has an index, find the sourcemap for that index
-        //         let res = source_element
-        //             .index()
-        //             // if index matches current file_id, return current source code
-        //             .and_then(|index| {
-        //                 (index == artifact.file_id)
-        //                     .then(|| (source_element.clone(), source.source.as_str(),
-        // &source.name))             })
-        //             .or_else(|| {
-        //                 // otherwise find the source code for the element's index
-        //                 self.debugger
-        //                     .contracts_sources
-        //                     .sources_by_id
-        //                     .get(&artifact.build_id)?
-        //                     .get(&source_element.index()?)
-        //                     .map(|source| {
-        //                         (source_element.clone(), source.source.as_str(), &source.name)
-        //                     })
-        //             });
-
-        //         res
-        //     })
-        // else {
-        //     return Err(format!("No source map for contract {contract_name}"));
-        // };
+    /// Returns the latest decision of each branch in the given file made so far in the current
+    /// call, keyed by the (1-based) line number of the branch.
+    fn branch_decisions(&self, source_file: &SourceFile) -> FxHashMap<usize, bool> {
+        let Some(artifact) = self.session.artifact.compilation_artifacts.get(self.address()) else {
+            return FxHashMap::default();
+        };
+        let is_create = self.call_kind().is_any_create();
 
-        // Ok((source_element, source_code, source_file))
+        let mut decisions = FxHashMap::default();
+        for step in &self.debug_steps()[..=self.session.current_step] {
+            let Some(taken) = step.branch_taken() else { continue };
+            let Some((element, file)) = artifact.source_element(step.pc, is_create) else {
+                continue;
+            };
+            if file.path == source_file.path {
+                decisions.insert(source_file.line_of(element.offset() as usize), taken);
+            }
+        }
+        decisions
+    }
+
+    /// Returns the number of executions of each line of the given file, with its heat color, if
+    /// the heat-map mode is enabled.
+    fn line_heat(&self, source_file: &SourceFile) -> Option<LineHeat> {
+        let heat_map = self.session.heat_map.as_ref()?;
+        let artifact = self.session.artifact.compilation_artifacts.get(self.address())?;
+        let counts =
+            heat_map.line_counts(artifact, self.call_kind().is_any_create(), &source_file.path);
+        let width = decimal_digits(counts.values().copied().max().unwrap_or(0));
+        let lines = counts
+            .into_iter()
+            .map(|(line, count)| (line, (count, heat_color(heat_map.heat(count)))))
+            .collect();
+        Some(LineHeat { lines, width })
     }
 
     fn draw_op_list<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
         let debug_steps = self.debug_steps();
-        let max_pc = debug_steps.iter().map(|step| step.pc).max().unwrap_or(0);
-        let max_pc_len = hex_digits(max_pc);
+        let max_pc_len = hex_digits(self.session.max_pc);
+
+        // The offset is kept across frames so that the list does not jump around. When following
+        // the execution, the list scrolls just enough to show the current row, which is otherwise
+        // only highlighted if it is visible.
+        let row = self.current_op_row();
+        let len = self.session.op_rows.len();
+        let height = pane.rect.height.saturating_sub(2) as usize;
+        let view_state = self.clamp_view_state(pane.view, len, height);
+        let offset = if view_state.recenter {
+            row.saturating_sub(height / 2)
+        } else if pane.follow {
+            scroll_window(view_state.offset, height, len, row, 1)
+        } else {
+            view_state.offset
+        };
+        self.update_view_state(pane.view, |view_state| {
+            view_state.offset = offset;
+            view_state.recenter = false;
+        });
+
+        // In the heat-map mode, each row starts with the number of executions of its instruction
+        let heat_width =
+            self.session.heat_map.as_ref().map(|heat_map| decimal_digits(heat_map.max));
+        let gutter = |pc: usize| {
+            let (heat_map, width) = self.session.heat_map.as_ref().zip(heat_width)?;
+            let count = heat_map.count(pc);
+            Some(Span::styled(
+                format!("{count:>width$} "),
+                Style::new().fg(heat_color(heat_map.heat(count))),
+            ))
+        };
 
-        let items = debug_steps
+        // Only the visible rows are rendered, since a call may execute millions of steps
+        let end = (offset + height).min(len);
+        let items = self.session.op_rows[offset..end]
             .iter()
-            .enumerate()
-            .map(|(i, step)| {
-                let mut content = String::with_capacity(64);
-                write!(content, "{:0>max_pc_len$x}|", step.pc).unwrap();
-                if let Some(op) = self.opcode_list.get(i) {
-                    content.push_str(op);
+            .map(|row| match row {
+                OpRow::Step(i) => {
+                    let step = &debug_steps[*i];
+                    let mut content = String::with_capacity(64);
+                    write!(content, "{:0>max_pc_len$x}|{}", step.pc, step.pretty_opcode()).unwrap();
+                    if let Some(label) =
+                        self.session.cfg.as_ref().and_then(|cfg| {
+                            cfg.push_target_label(step.instruction, &step.push_bytes)
+                        })
+                    {
+                        write!(content, " → {label}").unwrap();
+                    }
+
+                    let node = self.session.draw_memory.inner_call_index;
+                    match self.session.taint.as_ref().and_then(|taint| taint.sink(node, *i)) {
+                        Some(sink) => {
+                            let mut inputs: Vec<_> = OpcodeParam::of(step.instruction)
+                                .iter()
+                                .filter(|param| sink.inputs & (1 << param.index) != 0)
+                                .map(|param| param.name)
+                                .collect();
+                            if sink.memory {
+                                inputs.push("memory");
+                            }
+                            write!(content, " ⚠ tainted: {}", inputs.join(", ")).unwrap();
+                            let content = Span::styled(content, Style::new().fg(Color::Red));
+                            ListItem::new(Line::from_iter(
+                                gutter(step.pc).into_iter().chain([content]),
+                            ))
+                        }
+                        None => {
+                            let content = Span::styled(content, Style::new().fg(Color::White));
+                            ListItem::new(Line::from_iter(
+                                gutter(step.pc).into_iter().chain([content]),
+                            ))
+                        }
+                    }
+                }
+                OpRow::Loop(summary) => {
+                    let content = format!(
+                        "{:0>max_pc_len$x}|↻ ×{} iterations ({} steps, [e] to expand)",
+                        summary.head_pc,
+                        summary.iterations,
+                        summary.end - summary.start,
+                    );
+                    let content = Span::styled(content, Style::new().fg(Color::Yellow));
+                    ListItem::new(Line::from_iter(
+                        gutter(summary.head_pc).into_iter().chain([content]),
+                    ))
                 }
-                ListItem::new(Span::styled(content, Style::new().fg(Color::White)))
             })
             .collect::<Vec<_>>();
 
@@ -565,32 +1231,533 @@ impl FrontendContext<'_> {
         let list = List::new(items)
             .block(block)
             .highlight_symbol("▶")
-            .highlight_style(Style::new().fg(Color::White).bg(Color::DarkGray))
-            .scroll_padding(1);
-        let mut state = ListState::default().with_selected(Some(self.current_step));
+            .highlight_style(Style::new().fg(Color::White).bg(Color::DarkGray));
+        let selected = (offset..end).contains(&row).then(|| row - offset);
+        let mut state = ListState::default().with_selected(selected);
         f.render_stateful_widget(list, pane.rect, &mut state);
     }
 
+    /// Draws the basic blocks of the current code one below the other, each followed by its
+    /// successors, with the block being executed highlighted.
+    fn draw_cfg<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let block = self.get_focused_block(&pane);
+        let Some(cfg) = &self.session.cfg else {
+            let paragraph = Paragraph::new("The bytecode of the current call is not available.")
+                .block(block)
+                .wrap(Wrap { trim: false });
+            f.render_widget(paragraph, pane.rect);
+            return;
+        };
+
+        // each block takes a header row, a row per instruction, and a row for its successors
+        let pc = self.current_step().pc;
+        let current = cfg.block_at(pc);
+        let mut first_rows = Vec::with_capacity(cfg.blocks.len());
+        let mut len = 0;
+        for basic_block in &cfg.blocks {
+            first_rows.push(len);
+            len += basic_block.instructions.len() + 2;
+        }
+        len += cfg.metadata.is_some() as usize;
+        let row = current
+            .map(|index| {
+                let offset = cfg.blocks[index].instructions.iter().position(|i| i.pc == pc);
+                first_rows[index] + 1 + offset.unwrap_or(0)
+            })
+            .unwrap_or(0);
+
+        let height = pane.rect.height.saturating_sub(2) as usize;
+        let view_state = self.clamp_view_state(pane.view, len, height);
+        let offset = if view_state.recenter {
+            row.saturating_sub(height / 2)
+        } else if pane.follow {
+            scroll_window(view_state.offset, height, len, row, 3)
+        } else {
+            view_state.offset
+        };
+        self.update_view_state(pane.view, |view_state| {
+            view_state.offset = offset;
+            view_state.recenter = false;
+        });
+
+        // Only the visible blocks are rendered, since contracts have thousands of blocks
+        let width = hex_digits(cfg.blocks.last().map_or(0, |block| block.end()));
+        let first = first_rows.partition_point(|first_row| *first_row <= offset).saturating_sub(1);
+        let mut lines = Vec::with_capacity(height);
+        for (index, basic_block) in cfg.blocks.iter().enumerate().skip(first) {
+            if first_rows[index] >= offset + height {
+                break;
+            }
+            let style = if Some(index) == current {
+                Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            } else if !basic_block.reachable {
+                Style::new().fg(Color::DarkGray).add_modifier(Modifier::DIM)
+            } else {
+                Style::new().add_modifier(Modifier::DIM)
+            };
+
+            let mut header = format!("┌─ block {:#0w$x}", basic_block.start(), w = width + 2);
+            if let Some(label) = cfg.label(basic_block.start()) {
+                write!(header, " {label}").unwrap();
+            }
+            if !basic_block.reachable {
+                header.push_str(" (unreachable, likely data)");
+            }
+            lines.push(Line::from(Span::styled(header, style)));
+            for instruction in &basic_block.instructions {
+                let marker =
+                    if Some(index) == current && instruction.pc == pc { "▶" } else { " " };
+                let mut content =
+                    format!("│{marker}{:0>width$x} {}", instruction.pc, instruction.pretty());
+                if let Some(label) =
+                    cfg.push_target_label(instruction.opcode, &instruction.immediate)
+                {
+                    write!(content, " → {label}").unwrap();
+                }
+                let style = if marker == "▶" { style.bg(Color::DarkGray) } else { style };
+                lines.push(Line::from(Span::styled(content, style)));
+            }
+            let mut successors: Vec<String> = basic_block
+                .successors
+                .iter()
+                .map(|successor| {
+                    cfg.label(*successor)
+                        .unwrap_or_else(|| format!("{successor:#0w$x}", w = width + 2))
+                })
+                .collect();
+            if basic_block.dynamic_jump {
+                successors.push("?".to_string());
+            }
+            let footer = if successors.is_empty() {
+                "└─ (end)".to_string()
+            } else {
+                format!("└─→ {}", successors.join(", "))
+            };
+            lines.push(Line::from(Span::styled(footer, style)));
+        }
+
+        let skip = offset - first_rows.get(first).copied().unwrap_or(0);
+        // the metadata row follows the last block, if it is visible
+        let last_visible = first_rows.last().map_or(true, |first_row| *first_row < offset + height);
+        if let Some(metadata) = cfg.metadata.as_ref().filter(|_| last_visible) {
+            lines.push(Line::from(Span::styled(
+                format!("   {} bytes of metadata at {:#x}", metadata.len(), metadata.start),
+                Style::new().fg(Color::DarkGray),
+            )));
+        }
+        let paragraph = Paragraph::new(lines).block(block).scroll((skip as u16, 0));
+        f.render_widget(paragraph, pane.rect);
+    }
+
+    /// Draws the call depth and the gas left over the whole execution, one column per bucket of
+    /// steps, with the current step marked below.
+    fn draw_timeline<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let block = self.get_focused_block(&pane);
+        let inner = block.inner(pane.rect);
+        f.render_widget(block, pane.rect);
+
+        let timeline = self.session.timeline();
+        let [legend, depth_area, gas_area, marker] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+        self.timeline_area.set(depth_area.union(gas_area));
+
+        let buckets = timeline.buckets(inner.width as usize);
+        let step = self.session.step_index();
+        let current = timeline.bucket_of(step, buckets.len());
+        let max_depth = timeline.depths.iter().copied().max().unwrap_or(0);
+        let gas_left = buckets.get(current).map_or(0, |bucket| bucket.gas_left);
+        let text = format!(
+            "step {step}/{}  depth {}/{max_depth}  gas left {gas_left}  (click to jump)",
+            timeline.len(),
+            self.debug_call().depth,
+        );
+        f.render_widget(Paragraph::new(text).style(Style::new().fg(Color::Gray)), legend);
+
+        // The root call is at depth 0, which would not show up at all
+        let depths: Vec<u64> = buckets.iter().map(|bucket| bucket.depth as u64 + 1).collect();
+        let depth = Sparkline::default()
+            .data(&depths)
+            .max(max_depth as u64 + 1)
+            .style(Style::new().fg(Color::Cyan));
+        f.render_widget(depth, depth_area);
+
+        let gas: Vec<u64> = buckets.iter().map(|bucket| bucket.gas_left).collect();
+        let gas = Sparkline::default().data(&gas).style(Style::new().fg(Color::Yellow));
+        f.render_widget(gas, gas_area);
+
+        // The phases of the user operations are marked by their initial, e.g., `E` for the
+        // execution, below the bucket they start in
+        let mut marks = vec![Span::raw(" "); buckets.len()];
+        for bundle in self.session.user_op_bundles() {
+            for (phase, steps) in bundle.ops.iter().flat_map(|execution| &execution.phases) {
+                let initial = match phase {
+                    UserOpPhase::Validation => "V",
+                    UserOpPhase::PaymasterValidation => "P",
+                    UserOpPhase::Execution => "E",
+                    UserOpPhase::PostOp => "O",
+                };
+                if let Some(mark) = marks.get_mut(timeline.bucket_of(steps.start, buckets.len())) {
+                    *mark = Span::styled(initial, Style::new().fg(Color::Magenta));
+                }
+            }
+        }
+        if let Some(mark) = marks.get_mut(current) {
+            *mark = Span::styled("▲", Style::new().fg(Color::Cyan));
+        }
+        f.render_widget(Paragraph::new(Line::from(marks)), marker);
+    }
+
+    /// Draws every access to the watched storage slot, with the latest one so far marked, and
+    /// the ones yet to come dimmed.
+    fn draw_storage<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let block = self.get_focused_block(&pane);
+        let Some((address, slot)) = self.session.storage_slot else {
+            let text = "No storage slot is watched. Press [s] on an SLOAD or SSTORE to watch its \
+                        slot, or [W] to enter one.";
+            let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+            f.render_widget(paragraph, pane.rect);
+            return;
+        };
+
+        let history = &self.session.storage_history;
+        let step = self.session.step_index();
+        let latest = history.partition_point(|access| access.step <= step).checked_sub(1);
+        let width = decimal_digits(history.last().map_or(0, |access| access.step));
+        let items = history
+            .iter()
+            .enumerate()
+            .map(|(i, access)| {
+                let (kind, color) = match access.kind {
+                    StorageAccessKind::Read => ("read ", Color::Green),
+                    StorageAccessKind::Write => ("write", Color::Yellow),
+                };
+                let value = access.value.map_or("?".to_string(), |value| format!("{value:#x}"));
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{:>width$}  ", access.step),
+                        Style::new().fg(Color::Gray),
+                    ),
+                    Span::styled(format!("{kind}  "), Style::new().fg(color)),
+                    Span::raw(value),
+                ];
+                if Some(i) == latest {
+                    spans.push(Span::styled("  ◀ current", Style::new().fg(Color::Cyan)));
+                }
+                let line = Line::from(spans);
+                if access.step > step {
+                    ListItem::new(line.patch_style(Style::new().add_modifier(Modifier::DIM)))
+                } else {
+                    ListItem::new(line)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut title = format!(
+            " slot {slot:#x} of {} · {} accesses ",
+            self.session.artifact.address_label(&address),
+            history.len()
+        );
+        if self.session.is_scanning_storage() {
+            title.push_str("(searching…) ");
+        }
+        let block = block.title_bottom(Line::from(title).style(Style::new().fg(Color::Gray)));
+        self.render_cursor_list_in(f, pane, block, items);
+    }
+
+    /// Draws the bundles of ERC-4337 user operations, with the phase of the current step
+    /// highlighted.
+    fn draw_user_ops<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let block = self.get_focused_block(&pane);
+        let rows = self.user_op_rows();
+        if rows.is_empty() {
+            let text = "The transaction handles no ERC-4337 user operation.";
+            let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+            f.render_widget(paragraph, pane.rect);
+            return;
+        }
+
+        let artifact = &*self.session.artifact;
+        let step = self.session.step_index();
+        let items = rows
+            .iter()
+            .map(|row| match row {
+                UserOpRow::Bundle(bundle) => ListItem::new(Line::styled(
+                    format!(
+                        "EntryPoint {} at {} · {} operation(s) · beneficiary {}",
+                        bundle.version,
+                        artifact.address_label(&bundle.entry_point),
+                        bundle.ops.len(),
+                        artifact.address_label(&bundle.beneficiary),
+                    ),
+                    Style::new().add_modifier(Modifier::BOLD),
+                )),
+                UserOpRow::Op(bundle, i) => {
+                    let op = &bundle.ops[*i].op;
+                    let mut header =
+                        format!("  #{i} {} nonce {}", artifact.address_label(&op.sender), op.nonce);
+                    if let Some(paymaster) = &op.paymaster {
+                        header.push_str(&format!(
+                            " · paymaster {}",
+                            artifact.address_label(paymaster)
+                        ));
+                    }
+                    if op.deploys_account {
+                        header.push_str(" · deploys the account");
+                    }
+                    let gas = format!(
+                        "     gas: call {} · verification {} · pre-verification {} · max fee {} · \
+                         max priority fee {}",
+                        op.call_gas_limit,
+                        op.verification_gas_limit,
+                        op.pre_verification_gas,
+                        op.max_fee_per_gas,
+                        op.max_priority_fee_per_gas,
+                    );
+                    ListItem::new(Text::from(vec![
+                        Line::styled(header, Style::new().fg(Color::Cyan)),
+                        Line::styled(gas, Style::new().fg(Color::Gray)),
+                    ]))
+                }
+                UserOpRow::Phase(phase, steps) => {
+                    let line = format!("     ◆ {phase}  steps {}–{}", steps.start, steps.end);
+                    let style = if steps.contains(&step) {
+                        Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                    } else if steps.start > step {
+                        Style::new().add_modifier(Modifier::DIM)
+                    } else {
+                        Style::new()
+                    };
+                    ListItem::new(Line::styled(line, style))
+                }
+                UserOpRow::Transfer(transfer) => {
+                    let amount = match &transfer.asset {
+                        Asset::Ether => TokenMetadata { symbol: "ETH".to_string(), decimals: 18 }
+                            .format_amount(transfer.amount),
+                        Asset::Token(token) => artifact
+                            .format_token_amount(token, transfer.amount)
+                            .unwrap_or_else(|| format!("{} of token {token}", transfer.amount)),
+                    };
+                    ListItem::new(Line::styled(
+                        format!(
+                            "     $ {amount}: {} → {}",
+                            artifact.address_label(&transfer.from),
+                            artifact.address_label(&transfer.to)
+                        ),
+                        Style::new().fg(Color::Green),
+                    ))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.render_cursor_list(f, pane, items);
+    }
+
+    /// Draws the events emitted during the execution, decoded as the background decoding
+    /// progresses. The events emitted before the current step are highlighted.
+    fn draw_events<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let events = self.session.events();
+        if events.is_empty() {
+            let block = self.get_focused_block(&pane);
+            let text = "The transaction emits no event.";
+            let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+            f.render_widget(paragraph, pane.rect);
+            return;
+        }
+
+        let artifact = &*self.session.artifact;
+        let step = self.session.step_index();
+        let decoding = self.session.is_decoding();
+        let items = events
+            .iter()
+            .enumerate()
+            .map(|(i, event)| {
+                let mut line =
+                    format!("#{i} step {} {}", event.step, artifact.address_label(&event.address));
+                match self.session.decoded_events.get(&i) {
+                    Some(decoded) => write!(line, "::{decoded}").unwrap(),
+                    None => {
+                        match event.topics.first() {
+                            Some(topic) => write!(line, " topic0 {topic}").unwrap(),
+                            None => line.push_str(" anonymous"),
+                        }
+                        write!(line, ", {} byte(s) of data", event.data.len()).unwrap();
+                        if decoding {
+                            line.push_str(" (decoding…)");
+                        }
+                    }
+                }
+                let style = if event.step <= step {
+                    Style::new().fg(Color::Cyan)
+                } else {
+                    Style::new().add_modifier(Modifier::DIM)
+                };
+                ListItem::new(Line::styled(line, style))
+            })
+            .collect::<Vec<_>>();
+
+        self.render_cursor_list(f, pane, items);
+    }
+
+    /// Draws the executions of governance proposals and their actions, with the action being
+    /// executed highlighted.
+    fn draw_governance<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let block = self.get_focused_block(&pane);
+        let rows = self.governance_rows();
+        if rows.is_empty() {
+            let text = "The transaction executes no governance proposal.";
+            let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+            f.render_widget(paragraph, pane.rect);
+            return;
+        }
+
+        let artifact = &*self.session.artifact;
+        let step = self.session.step_index();
+        let items = rows
+            .iter()
+            .map(|row| match row {
+                GovernanceRow::Execution(execution) => ListItem::new(Line::styled(
+                    format!(
+                        "{} {} · {} action(s) · steps {}–{}",
+                        execution.kind,
+                        artifact.address_label(&execution.contract),
+                        execution.actions.len(),
+                        execution.steps.start,
+                        execution.steps.end,
+                    ),
+                    Style::new().add_modifier(Modifier::BOLD),
+                )),
+                GovernanceRow::Action(execution, i) => {
+                    let action = &execution.actions[*i];
+                    let mut line = format!("  #{i} {}", artifact.address_label(&action.target));
+                    match artifact.abi(&action.target).and_then(|abi| {
+                        abi.functions().find(|function| {
+                            action.calldata.get(..4) == Some(&function.selector()[..])
+                        })
+                    }) {
+                        Some(function) => write!(line, "::{}", function.signature()).unwrap(),
+                        None => write!(line, " calldata {}", action.calldata).unwrap(),
+                    }
+                    if !action.value.is_zero() {
+                        write!(line, " {{value: {}}}", action.value).unwrap();
+                    }
+                    let style = match &action.steps {
+                        None => {
+                            line.push_str(" (not executed)");
+                            Style::new().fg(Color::DarkGray)
+                        }
+                        Some(steps) if steps.contains(&step) => {
+                            Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                        }
+                        Some(steps) if steps.start > step => {
+                            Style::new().add_modifier(Modifier::DIM)
+                        }
+                        Some(_) => Style::new().fg(Color::Cyan),
+                    };
+                    ListItem::new(Line::styled(line, style))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.render_cursor_list(f, pane, items);
+    }
+
+    fn draw_assembly<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let block = self.get_focused_block(&pane);
+        let node = self.debug_call();
+        let located =
+            self.yul_position(self.session.current_step).and_then(|(index, statement)| {
+                let assembly = &self.session.assembly(&node.address)?.0[index];
+                let compilation = self.session.artifact.compilation_artifacts.get(&node.address)?;
+                let file = compilation.sources.get(&(assembly.index as u32))?;
+                Some((assembly, statement, file))
+            });
+        let Some((assembly, statement, file)) = located else {
+            let text = "The current step is not in an inline assembly block. Press [J/K] to step \
+                        a single opcode.";
+            let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+            f.render_widget(paragraph, pane.rect);
+            return;
+        };
+
+        // One row per statement, showing its first line, around the current statement
+        let height = pane.rect.height.saturating_sub(2) as usize;
+        let offset = statement.unwrap_or(0).saturating_sub(height / 2);
+        let width = decimal_digits(file.line_of(assembly.range.end));
+        let lines = assembly
+            .statements
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .map(|(i, range)| {
+                let code = &file.code[range.clone()];
+                let first = code.lines().next().unwrap_or_default().trim();
+                let more = if code.contains('\n') { " …" } else { "" };
+                let current = Some(i) == statement;
+                let marker = if current { "▶" } else { " " };
+                let content =
+                    format!("{marker}{:>width$}  {first}{more}", file.line_of(range.start));
+                if current {
+                    Line::styled(
+                        content,
+                        Style::new()
+                            .fg(Color::Cyan)
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    Line::raw(content)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let position = match statement {
+            Some(i) => format!("statement {}/{}", i + 1, assembly.statements.len()),
+            None => "between statements".to_string(),
+        };
+        let title =
+            format!(" assembly at line {} · {position} ", file.line_of(assembly.range.start));
+        let block = block.title_bottom(Line::from(title).style(Style::new().fg(Color::Gray)));
+        f.render_widget(Paragraph::new(lines).block(block), pane.rect);
+    }
+
     fn draw_stack<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
         let step = self.current_step();
         let stack = &step.stack;
 
         let min_len = decimal_digits(stack.len()).max(2);
+        let height = pane.rect.height.saturating_sub(2) as usize;
+        let start_line = self.clamp_view_state(pane.view, stack.len(), height).offset;
 
         let params = OpcodeParam::of(step.instruction);
+        let provenance = self.session.provenance();
 
         let text: Vec<Line<'_>> = stack
             .iter()
             .rev()
             .enumerate()
-            .skip(self.draw_memory.current_stack_startline)
+            .skip(start_line)
+            .take(pane.rect.height as usize)
             .map(|(i, stack_item)| {
                 let param = params.iter().find(|param| param.index == i);
 
                 let mut spans = Vec::with_capacity(1 + 32 * 2 + 3);
 
-                // Stack index.
-                spans.push(Span::styled(format!("{i:0min_len$}| "), Style::new().fg(Color::White)));
+                // Stack index, in red if the item is tainted.
+                let tainted = self.session.taint.as_ref().map_or(false, |taint| {
+                    taint.is_tainted(
+                        self.session.draw_memory.inner_call_index,
+                        self.session.current_step,
+                        i,
+                    )
+                });
+                let index_color = if tainted { Color::Red } else { Color::White };
+                spans.push(Span::styled(format!("{i:0min_len$}| "), Style::new().fg(index_color)));
 
                 // Item hex bytes.
                 hex_bytes_spans(&stack_item.to_be_bytes::<32>(), &mut spans, |_, _| {
@@ -608,6 +1775,20 @@ impl FrontendContext<'_> {
                     }
                 }
 
+                if let Some(name) = self.session.constant_names.get(stack_item) {
+                    spans.push(Span::styled(
+                        format!(" = {name}"),
+                        Style::new().fg(Color::Cyan).add_modifier(Modifier::DIM),
+                    ));
+                }
+
+                if let Some(origin) = provenance.stack.get(stack.len() - 1 - i).copied().flatten() {
+                    spans.push(Span::styled(
+                        format!(" ← {}", self.origin_text(&origin)),
+                        Style::new().fg(Color::Magenta).add_modifier(Modifier::DIM),
+                    ));
+                }
+
                 spans.push(Span::raw("\n"));
 
                 Line::from(spans)
@@ -619,6 +1800,87 @@ impl FrontendContext<'_> {
         f.render_widget(paragraph, pane.rect);
     }
 
+    fn draw_compare<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
+        let block = self.get_focused_block(&pane);
+        let Some(comparison) = self.comparison.as_ref() else {
+            let paragraph = Paragraph::new(
+                "No sessions are being compared.\n\nPress [V] to compare the current session with the next one.",
+            )
+            .block(block)
+            .wrap(Wrap { trim: false });
+            f.render_widget(paragraph, pane.rect);
+            return;
+        };
+
+        let inner = block.inner(pane.rect);
+        f.render_widget(block, pane.rect);
+        let [summary, columns] =
+            Layout::new(Direction::Vertical, [Constraint::Length(3), Constraint::Min(0)])
+                .split(inner)[..]
+        else {
+            unreachable!()
+        };
+        let [left, right] =
+            Layout::new(Direction::Horizontal, [Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+                .split(columns)[..]
+        else {
+            unreachable!()
+        };
+
+        let [l, r] = comparison.sessions.map(|index| self.session_at(index));
+        let divergence = &comparison.divergence;
+
+        // Summary of the first divergences.
+        let red = Style::new().fg(Color::Red);
+        let mut lines = Vec::with_capacity(3);
+        lines.push(Line::from(match divergence.stack {
+            Some(index) if index == self.session.step_index() => {
+                Span::styled(format!("Stacks diverge at step {index} (here)"), red)
+            }
+            Some(index) => Span::styled(format!("Stacks diverge at step {index} ([d] to go)"), red),
+            None => Span::raw("Stacks never diverge"),
+        }));
+        lines.push(Line::from(match &divergence.storage {
+            Some((lw, rw)) => {
+                let describe = |write: &Option<StorageWrite>| match write {
+                    Some(w) => format!("slot {:#x} = {:#x} at step {}", w.slot, w.value, w.step),
+                    None => "no write".to_string(),
+                };
+                Span::styled(format!("Storage diverges: {} vs {}", describe(lw), describe(rw)), red)
+            }
+            None => Span::raw("Storage writes are identical"),
+        }));
+        f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), summary);
+
+        // The stacks side by side, with the differing items in red.
+        for (area, session, other) in [(left, l, r), (right, r, l)] {
+            let step = session.step();
+            let other_stack = &other.step().stack;
+            let mut lines = vec![Line::from(Span::styled(
+                format!("{} @ {}: {}", session.name, session.step_index(), step.pretty_opcode()),
+                Style::new().add_modifier(Modifier::BOLD),
+            ))];
+            lines.extend(step.stack.iter().rev().enumerate().map(|(i, item)| {
+                let same = other_stack.len() > i && other_stack[other_stack.len() - 1 - i] == *item;
+                let style = if same { Style::new() } else { red };
+                Line::from(Span::styled(format!("{i:02}| {item:#x}"), style))
+            }));
+            f.render_widget(Paragraph::new(lines), area);
+        }
+    }
+
+    /// Describes the sub-call which returned a value.
+    fn origin_text(&self, origin: &ReturndataOrigin) -> String {
+        match &origin.target {
+            Some(target) => format!(
+                "returned by the call to {} at step {}",
+                self.session.artifact.address_label(target),
+                origin.step
+            ),
+            None => format!("returned by the contract creation at step {}", origin.step),
+        }
+    }
+
     fn draw_buffer<'a>(&'a self, f: &mut Frame<'_>, pane: PaneFlattened<'a>) {
         let step = self.current_step();
         let buf = match pane.view {
@@ -657,8 +1919,8 @@ impl FrontendContext<'_> {
         // TODO: technically it's possible for this to conflict with the current op, ie, with
         // subsequent MCOPYs, but solc can't seem to generate that code even with high optimizer
         // settings
-        if self.current_step > 0 {
-            let prev_step = self.current_step - 1;
+        if self.session.current_step > 0 {
+            let prev_step = self.session.current_step - 1;
             let prev_step = &self.debug_steps()[prev_step];
             if let Some(write_access) =
                 get_buffer_accesses(prev_step.instruction, &prev_step.stack).and_then(|a| a.write)
@@ -671,15 +1933,53 @@ impl FrontendContext<'_> {
             }
         }
 
+        // Memory, calldata and returndata are scrolled independently.
         let height = pane.rect.height as usize;
-        let end_line = self.draw_memory.current_buf_startline + height;
+        let start_line = self
+            .clamp_view_state(pane.view, buf.len().div_ceil(32), height.saturating_sub(2))
+            .offset;
+        let end_line = start_line + height;
+
+        // Label the memory regions, which are shown as a header above their first word.
+        let labels = if pane.view == PaneView::Memory {
+            let provenance = self.session.provenance();
+            let mut labels: Vec<_> = provenance
+                .memory
+                .iter()
+                .map(|(offset, size, origin)| MemoryLabel {
+                    offset: *offset,
+                    size: *size,
+                    label: self.origin_text(origin),
+                })
+                .collect();
+            labels.extend(memory_labels(&*self.session.artifact, node, step));
+            labels
+        } else {
+            Vec::new()
+        };
 
         let text: Vec<Line<'_>> = buf
             .chunks(32)
             .enumerate()
-            .skip(self.draw_memory.current_buf_startline)
+            .skip(start_line)
             .take_while(|(i, _)| *i < end_line)
-            .map(|(i, buf_word)| {
+            .flat_map(|(i, buf_word)| {
+                let mut lines: Vec<_> = labels
+                    .iter()
+                    .filter(|label| label.offset / 32 == i)
+                    .map(|label| {
+                        Line::styled(
+                            format!(
+                                "── {} [{:#x}..{:#x}]",
+                                label.label,
+                                label.offset,
+                                label.offset + label.size
+                            ),
+                            Style::new().fg(Color::Yellow).add_modifier(Modifier::DIM),
+                        )
+                    })
+                    .collect();
+
                 let mut spans = Vec::with_capacity(1 + 32 * 2 + 1 + 32 / 4 + 1);
 
                 // Buffer index.
@@ -739,9 +2039,22 @@ impl FrontendContext<'_> {
                     }
                 }
 
+                if buf_word.len() == 32 {
+                    let word = U256::from_be_slice(buf_word);
+                    if let Some(name) =
+                        self.session.constant_names.get(&word).filter(|_| !word.is_zero())
+                    {
+                        spans.push(Span::styled(
+                            format!(" = {name}"),
+                            Style::new().fg(Color::Cyan).add_modifier(Modifier::DIM),
+                        ));
+                    }
+                }
+
                 spans.push(Span::raw("\n"));
 
-                Line::from(spans)
+                lines.push(Line::from(spans));
+                lines
             })
             .collect();
 
@@ -751,15 +2064,33 @@ impl FrontendContext<'_> {
     }
 }
 
-/// Wrapper around a list of [`Line`]s that prepends the line number on each new line.
+/// The number of executions of the lines of a source file, with their heat color.
+struct LineHeat {
+    lines: FxHashMap<usize, (usize, Color)>,
+    /// The width of the largest count.
+    width: usize,
+}
+
+/// Wrapper around a list of [`Line`]s that prepends the line number, as well as the branch
+/// decision marker if any, on each new line.
 struct SourceLines<'a> {
     lines: Vec<Line<'a>>,
+    start_line: usize,
     max_line_num: usize,
+    branches: FxHashMap<usize, bool>,
+    breakpoints: FxHashSet<usize>,
+    heat: Option<LineHeat>,
 }
 
 impl<'a> SourceLines<'a> {
-    fn new(max_line_num: usize) -> Self {
-        Self { lines: Vec::new(), max_line_num }
+    fn new(
+        start_line: usize,
+        max_line_num: usize,
+        branches: FxHashMap<usize, bool>,
+        breakpoints: FxHashSet<usize>,
+        heat: Option<LineHeat>,
+    ) -> Self {
+        Self { lines: Vec::new(), start_line, max_line_num, branches, breakpoints, heat }
     }
 
     fn push(&mut self, line_number_style: Style, line: &'a str, line_style: Style) {
@@ -767,14 +2098,38 @@ impl<'a> SourceLines<'a> {
     }
 
     fn push_raw(&mut self, line_number_style: Style, spans: &[Span<'a>]) {
-        let mut line_spans = Vec::with_capacity(4);
+        let mut line_spans = Vec::with_capacity(6);
 
-        let line_number =
-            format!("{number: >width$} ", number = self.lines.len() + 1, width = self.max_line_num);
+        let number = self.start_line + self.lines.len() + 1;
+        let line_number = format!("{number: >width$}", width = self.max_line_num);
         line_spans.push(Span::styled(line_number, line_number_style));
 
+        // Execution count of the line, in the heat-map mode.
+        if let Some(heat) = &self.heat {
+            let width = heat.width;
+            match heat.lines.get(&number) {
+                Some((count, color)) => line_spans
+                    .push(Span::styled(format!(" {count:>width$}"), Style::new().fg(*color))),
+                None => line_spans.push(Span::raw(" ".repeat(width + 1))),
+            }
+        }
+
+        // Breakpoint (●) marker.
+        if self.breakpoints.contains(&number) {
+            line_spans.push(Span::styled("●", Style::new().fg(Color::Red)));
+        } else {
+            line_spans.push(Span::raw(" "));
+        }
+
+        // Taken (✔) or not-taken (✘) marker of the latest branch decision on this line.
+        match self.branches.get(&number) {
+            Some(true) => line_spans.push(Span::styled("✔", Style::new().fg(Color::Green))),
+            Some(false) => line_spans.push(Span::styled("✘", Style::new().fg(Color::Red))),
+            None => line_spans.push(Span::raw(" ")),
+        }
+
         // Space between line number and line text.
-        line_spans.push(Span::raw("  "));
+        line_spans.push(Span::raw(" "));
 
         line_spans.extend_from_slice(spans);
 
@@ -928,6 +2283,31 @@ fn hex_bytes_spans(bytes: &[u8], spans: &mut Vec<Span<'_>>, f: impl Fn(usize, u8
     }
 }
 
+/// Returns the color of the given heat, from blue (cold) to red (hottest).
+fn heat_color(heat: f64) -> Color {
+    match heat {
+        h if h < 0.25 => Color::Blue,
+        h if h < 0.5 => Color::Green,
+        h if h < 0.75 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+/// Formats the gas and value budget of a frame, e.g., `gas 1000 in: 30 self + 30 sub-calls, 940
+/// left | value 0 | returned 32 B`.
+fn format_budget(budget: &FrameBudget) -> String {
+    let mut out = String::from("gas ");
+    if budget.gas_limit > 0 {
+        write!(out, "{} in: ", budget.gas_limit).unwrap();
+    }
+    write!(out, "{} self + {} sub-calls", budget.self_gas, budget.children_gas).unwrap();
+    if let Some(left) = budget.gas_left() {
+        write!(out, ", {left} left").unwrap();
+    }
+    write!(out, " | value {} | returned {} B", budget.value, budget.returned).unwrap();
+    out
+}
+
 /// Returns the number of decimal digits in the given number.
 ///
 /// This is the same as `n.to_string().len()`.
@@ -942,6 +2322,31 @@ fn hex_digits(n: usize) -> usize {
     n.checked_ilog(16).unwrap_or(0) as usize + 1
 }
 
+/// Returns the first row of a window of `height` rows over a list of `len` rows, moving the
+/// window from `offset` as little as possible so that the `selected` row is shown with `padding`
+/// rows around it.
+fn scroll_window(
+    offset: usize,
+    height: usize,
+    len: usize,
+    selected: usize,
+    padding: usize,
+) -> usize {
+    if height == 0 {
+        return offset;
+    }
+    let padding = padding.min(height.saturating_sub(1) / 2);
+    let max_offset = len.saturating_sub(height);
+    let offset = if selected < offset + padding {
+        selected.saturating_sub(padding)
+    } else if selected + padding >= offset + height {
+        selected + padding + 1 - height
+    } else {
+        offset
+    };
+    offset.min(max_offset)
+}
+
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
 fn centered_rect(len_x: u16, len_y: u16, r: Rect) -> Rect {
     // Cut the given rectangle into three vertical pieces
@@ -977,6 +2382,19 @@ mod tests {
         assert_eq!(super::decimal_digits(1001), 4);
     }
 
+    #[test]
+    fn scroll_window() {
+        // already visible
+        assert_eq!(super::scroll_window(10, 5, 100, 12, 1), 10);
+        // above and below the window
+        assert_eq!(super::scroll_window(10, 5, 100, 10, 1), 9);
+        assert_eq!(super::scroll_window(10, 5, 100, 14, 1), 11);
+        assert_eq!(super::scroll_window(10, 5, 100, 50, 0), 46);
+        // the window does not go past the end
+        assert_eq!(super::scroll_window(0, 5, 100, 99, 1), 95);
+        assert_eq!(super::scroll_window(0, 5, 3, 2, 1), 0);
+    }
+
     #[test]
     fn hex_digits() {
         assert_eq!(super::hex_digits(0), 1);
@@ -992,4 +2410,64 @@ mod tests {
         assert_eq!(super::hex_digits(0x100), 3);
         assert_eq!(super::hex_digits(0x101), 3);
     }
+
+    #[test]
+    fn pane_cache() {
+        use super::{PaneCache, PaneKey};
+        use crate::{context::ExecutionPosition, window::PaneView};
+        use ratatui::{buffer::Buffer, layout::Rect};
+
+        let area = Rect::new(0, 0, 8, 4);
+        let key = |generation, view, position: ExecutionPosition| PaneKey {
+            generation,
+            area,
+            view,
+            state: Default::default(),
+            highlighted: false,
+            position: position.read_by(view),
+        };
+        let views = [
+            PaneView::Warnings,
+            PaneView::Trace,
+            PaneView::Contracts,
+            PaneView::Opcode,
+            PaneView::Stack,
+        ];
+        let cached = |cache: &PaneCache, generation, position| -> Vec<PaneView> {
+            views
+                .into_iter()
+                .enumerate()
+                .filter(|&(id, view)| {
+                    cache.get((id, false), &key(generation, view, position)).is_some()
+                })
+                .map(|(_, view)| view)
+                .collect()
+        };
+
+        let position = ExecutionPosition { session: 0, frame: (Some(1), 2), call: 3, step: 10 };
+        let mut cache = PaneCache::default();
+        for (id, view) in views.into_iter().enumerate() {
+            cache.insert((id, false), key(1, view, position), Buffer::empty(area));
+        }
+        assert_eq!(cached(&cache, 1, position), views);
+
+        // stepping within the call keeps the panes which do not read the step
+        let step = ExecutionPosition { step: 11, ..position };
+        assert_eq!(
+            cached(&cache, 1, step),
+            [PaneView::Warnings, PaneView::Trace, PaneView::Contracts]
+        );
+        // entering a call only keeps the warnings
+        let call = ExecutionPosition { frame: (Some(2), 3), call: 4, step: 0, ..position };
+        assert_eq!(cached(&cache, 1, call), [PaneView::Warnings]);
+        // switching the session or touching the state draws every pane again
+        assert!(cached(&cache, 1, ExecutionPosition { session: 1, ..position }).is_empty());
+        assert!(cached(&cache, 2, position).is_empty());
+        // a moved pane is drawn again, and panes which are no longer drawn are dropped
+        assert!(cache
+            .get((0, false), &PaneKey { area: Rect::new(1, 0, 8, 4), ..key(1, views[0], position) })
+            .is_none());
+        cache.retain(&[(1, false)]);
+        assert_eq!(cached(&cache, 1, position), [PaneView::Trace]);
+    }
 }
@@ -0,0 +1,64 @@
+//! Changes to the debugged transaction, which is then re-simulated by the caller of the frontend.
+
+use std::str::FromStr;
+
+use alloy_primitives::{Address, Bytes, U256};
+
+/// Changes to the debugged transaction, entered as space-separated `field=value` pairs, e.g.,
+/// `value=1000 gas=300000 from=0x... data=0x...`. Fields left out are unchanged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxEdit {
+    pub data: Option<Bytes>,
+    /// The value in wei.
+    pub value: Option<U256>,
+    pub gas: Option<u64>,
+    pub from: Option<Address>,
+}
+
+impl FromStr for TxEdit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut edit = Self::default();
+        for pair in s.split_whitespace() {
+            let (field, value) =
+                pair.split_once('=').ok_or_else(|| format!("expected `field=value`: {pair}"))?;
+            let invalid = |_| format!("invalid {field}: {value}");
+            match field {
+                "data" | "calldata" => edit.data = Some(value.parse().map_err(invalid)?),
+                "value" => edit.value = Some(value.parse().map_err(invalid)?),
+                "gas" => edit.gas = Some(value.parse().map_err(invalid)?),
+                "from" => edit.from = Some(value.parse().map_err(invalid)?),
+                _ => return Err(format!("unknown field `{field}` (data, value, gas or from)")),
+            }
+        }
+        if edit == Self::default() {
+            return Err("nothing is changed".to_string());
+        }
+        Ok(edit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tx_edit() {
+        let edit: TxEdit = "value=1000  gas=300000 from=0x0000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        assert_eq!(edit.value, Some(U256::from(1000)));
+        assert_eq!(edit.gas, Some(300000));
+        assert_eq!(edit.from, Some(Address::with_last_byte(1)));
+        assert_eq!(edit.data, None);
+
+        let edit: TxEdit = "data=0xa9059cbb".parse().unwrap();
+        assert_eq!(edit.data.unwrap().len(), 4);
+
+        assert!("".parse::<TxEdit>().is_err());
+        assert!("value=abc".parse::<TxEdit>().is_err());
+        assert!("nonce=1".parse::<TxEdit>().is_err());
+        assert!("gas 100".parse::<TxEdit>().is_err());
+    }
+}
@@ -2,13 +2,27 @@
 extern crate tracing;
 
 mod actions;
+mod blackbox;
+mod breakpoint;
 mod context;
 mod core;
 mod draw;
+mod edit;
+mod machine;
+mod session;
+mod stepping;
+mod sync;
+mod theme;
 mod utils;
 mod window;
 
-pub use core::DebugFrontend;
+pub use blackbox::{BlackboxEntry, BlackboxFile, DEFAULT_BLACKBOX};
+pub use context::FrontendState;
+pub use core::{DebugFrontend, ExitReason};
+pub use edit::TxEdit;
+pub use machine::{engine_events, Command, Event, Location, MachineInterface};
+pub use sync::{SyncCommand, SyncLocation, COMMANDS_FILE, LOCATION_FILE};
+pub use theme::{ColorMode, Theme};
 
 use ratatui::{backend::CrosstermBackend, Terminal};
 
@@ -0,0 +1,461 @@
+//! A line-based JSON protocol to drive the debugger without the terminal UI, for lightweight
+//! editor plugins, e.g., for Neovim or Emacs.
+//!
+//! Each line read is a command, e.g., `{"command":"next"}`, and each line written is an event.
+//! After every command, the current position is reported with a `location` event, or an `error`
+//! event is written if the command is malformed. A `ready` event is written once the session is
+//! loaded; any output before it, e.g., progress messages, is not part of the protocol.
+//!
+//! The events of the engine (see [`edb_debug_backend::event`]) are written on the same output,
+//! e.g., the progress of the analysis before `ready` if [`engine_events`] is given to the
+//! backend, and a `breakpoint_hit` event before the location a `continue` stops at, which is
+//! either a line with a breakpoint or the entry of a function with one.
+//!
+//! Stepping by lines stops where the terminal UI does (see [`crate::stepping`]), and goes over the
+//! calls to simple getters unless told otherwise, as well as the calls into the blackboxed
+//! contracts, if any are given.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+};
+
+use alloy_primitives::Address;
+use edb_debug_backend::{
+    analysis::{entry::FunctionTarget, getter::getter_nodes},
+    artifact::debug::DebugArtifact,
+    event::{EngineEvent, EventSender},
+    export::calltree::call_frames,
+};
+use eyre::Result;
+use revm::interpreter::OpCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blackbox::BlackboxFile,
+    stepping::{source_line, LineTracker, SkippedCalls},
+};
+
+/// A command sent by the editor.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    /// Steps to the next source line, into calls.
+    Step,
+    /// Steps to the next source line of the current call or of its callers.
+    Next,
+    /// Steps out of the current call.
+    Finish,
+    /// Steps back to the previous source line.
+    Back,
+    /// Steps to the next instruction.
+    StepInstruction,
+    /// Goes to the given step of the execution.
+    Goto {
+        step: usize,
+    },
+    /// Sets a breakpoint on a line of the source files whose path ends with `file`, or on the
+    /// entry of a function, e.g., `Vault.withdraw`, `withdraw(uint256)` or `0x2e1a7d4d`.
+    Break {
+        file: Option<String>,
+        line: Option<usize>,
+        function: Option<String>,
+    },
+    /// Removes a breakpoint, or all of them if neither a file nor a function is given.
+    Clear {
+        file: Option<String>,
+        line: Option<usize>,
+        function: Option<String>,
+    },
+    /// Continues until a breakpoint is hit, or until the end of the execution.
+    Continue,
+    /// Reports the current position.
+    Location,
+    Quit,
+}
+
+/// An event written to the editor.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Ready { steps: usize },
+    Location(Location),
+    Error { message: String },
+    Exited,
+}
+
+/// The current position of the debugger.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Location {
+    /// The index of the step in the whole execution.
+    pub step: usize,
+    pub address: Address,
+    pub depth: usize,
+    pub pc: usize,
+    pub opcode: String,
+    /// The source file and the 1-based line of the step, if the contract is verified.
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    /// Whether the step is the last one of the execution.
+    pub end: bool,
+}
+
+impl Location {
+    /// Returns the position of the given step of the given node, the step being the `index`-th
+    /// one of the whole execution.
+    pub fn new(artifact: &DebugArtifact, node: usize, step: usize, index: usize) -> Self {
+        let source = source_line(artifact, node, step);
+        let end = node + 1 == artifact.debug_arena.len();
+        let node = &artifact.debug_arena[node];
+        let end = end && step + 1 == node.steps.len();
+        let step = &node.steps[step];
+        Self {
+            step: index,
+            address: node.address,
+            depth: node.depth,
+            pc: step.pc,
+            opcode: OpCode::new(step.instruction).map_or_else(
+                || format!("0x{:02x}", step.instruction),
+                |op| op.as_str().to_string(),
+            ),
+            file: source.as_ref().map(|(path, _)| path.to_path_buf()),
+            line: source.map(|(_, line)| line),
+            end,
+        }
+    }
+}
+
+/// Drives a session over the machine interface.
+#[derive(Debug)]
+pub struct MachineInterface<'a> {
+    artifact: &'a DebugArtifact,
+    /// The node and step indices of the steps of the execution, in order.
+    steps: Vec<(usize, usize)>,
+    current: usize,
+    breakpoints: BTreeSet<(String, usize)>,
+    /// The breakpoints on the entry of functions, in the order they were set.
+    function_breakpoints: Vec<FunctionTarget>,
+    /// The step of the breakpoint the last `continue` stopped at, until it is reported.
+    breakpoint_hit: Option<usize>,
+    /// The nodes of the calls to simple getters.
+    getter_nodes: BTreeSet<usize>,
+    skip_getters: bool,
+    /// The blackboxed contracts, whose calls are stepped over.
+    blackbox: Option<BlackboxFile>,
+}
+
+impl<'a> MachineInterface<'a> {
+    pub fn new(artifact: &'a DebugArtifact) -> Self {
+        let steps = artifact.steps().map(|(i, j, _)| (i, j)).collect();
+        Self {
+            artifact,
+            steps,
+            current: 0,
+            breakpoints: BTreeSet::new(),
+            function_breakpoints: Vec::new(),
+            breakpoint_hit: None,
+            getter_nodes: getter_nodes(&call_frames(artifact)),
+            skip_getters: true,
+            blackbox: None,
+        }
+    }
+
+    /// Steps into the calls to simple getters, rather than over them.
+    pub fn step_into_getters(mut self, step_into_getters: bool) -> Self {
+        self.skip_getters = !step_into_getters;
+        self
+    }
+
+    /// Steps over the calls into the given blackboxed contracts.
+    pub fn blackbox(mut self, blackbox: BlackboxFile) -> Self {
+        self.blackbox = Some(blackbox);
+        self
+    }
+
+    /// Reads commands until `quit` or the end of the input, writing the events to the output.
+    pub fn run(mut self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        eyre::ensure!(!self.steps.is_empty(), "nothing to debug");
+        write_event(&mut output, &Event::Ready { steps: self.steps.len() })?;
+        write_event(&mut output, &Event::Location(self.location()))?;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = match serde_json::from_str(&line) {
+                Ok(Command::Quit) => break,
+                Ok(command) => self.execute(command),
+                Err(e) => Event::Error { message: format!("invalid command: {e}") },
+            };
+            if let Some(step) = self.take_breakpoint_hit() {
+                write_event(&mut output, &EngineEvent::BreakpointHit { step })?;
+            }
+            write_event(&mut output, &event)?;
+        }
+        write_event(&mut output, &Event::Exited)
+    }
+
+    /// Executes a command, returning the event to report.
+    pub fn execute(&mut self, command: Command) -> Event {
+        let last = self.steps.len() - 1;
+        let depth = self.depth(self.current);
+        let forward = || self.current + 1..self.steps.len();
+        let target = match command {
+            Command::Step => self
+                .find_line(forward(), |this, index| !this.is_skipped(index), |_, _| true)
+                .unwrap_or(last),
+            Command::Next => self
+                .find_line(forward(), |this, index| this.depth(index) <= depth, |_, _| true)
+                .unwrap_or(last),
+            Command::Finish => forward().find(|index| self.depth(*index) < depth).unwrap_or(last),
+            Command::Back => self
+                .find_line(
+                    (0..self.current).rev(),
+                    |this, index| !this.is_skipped(index),
+                    |_, _| true,
+                )
+                .unwrap_or(0),
+            Command::StepInstruction => (self.current + 1).min(last),
+            Command::Goto { step } if step > last => {
+                return Event::Error { message: format!("step {step} is out of range") }
+            }
+            Command::Goto { step } => step,
+            Command::Break { file, line, function } => {
+                match (file, line, function) {
+                    (Some(file), Some(line), None) => {
+                        self.breakpoints.insert((file, line));
+                    }
+                    (None, None, Some(function)) => match function.parse::<FunctionTarget>() {
+                        Ok(target) if self.function_breakpoints.contains(&target) => {}
+                        Ok(target) => self.function_breakpoints.push(target),
+                        Err(e) => return Event::Error { message: e.to_string() },
+                    },
+                    _ => {
+                        return Event::Error {
+                            message: "expected either a file and a line, or a function".to_string(),
+                        }
+                    }
+                }
+                self.current
+            }
+            Command::Clear { file, line, function } => {
+                if let Some(function) = function {
+                    match function.parse::<FunctionTarget>() {
+                        Ok(target) => self.function_breakpoints.retain(|t| *t != target),
+                        Err(e) => return Event::Error { message: e.to_string() },
+                    }
+                } else {
+                    if file.is_none() {
+                        self.function_breakpoints.clear();
+                    }
+                    self.breakpoints.retain(|(f, l)| match (&file, line) {
+                        (None, _) => false,
+                        (Some(file), None) => f != file,
+                        (Some(file), Some(line)) => (f, *l) != (file, line),
+                    });
+                }
+                self.current
+            }
+            Command::Continue => {
+                // the entries are resolved in the contracts of the execution, as in the terminal
+                // UI, and are hit even if they do not enter a new line
+                let entries = self
+                    .function_breakpoints
+                    .iter()
+                    .flat_map(|target| target.resolve(self.artifact))
+                    .map(|entry| (entry.address, entry.pc))
+                    .collect::<HashSet<_>>();
+                let entry = forward().find(|index| {
+                    let (node, step) = self.steps[*index];
+                    let node = &self.artifact.debug_arena[node];
+                    entries.contains(&(node.address, node.steps[step].pc))
+                });
+                let line = self.find_line(forward(), |_, _| true, Self::is_breakpoint);
+                let hit = entry.into_iter().chain(line).min();
+                self.breakpoint_hit = hit;
+                hit.unwrap_or(last)
+            }
+            Command::Location | Command::Quit => self.current,
+        };
+        self.current = target;
+        Event::Location(self.location())
+    }
+
+    /// Returns the step of the breakpoint the last `continue` stopped at, if it has not been
+    /// returned yet.
+    pub fn take_breakpoint_hit(&mut self) -> Option<usize> {
+        self.breakpoint_hit.take()
+    }
+
+    /// Returns the current position.
+    pub fn location(&self) -> Location {
+        let (node, step) = self.steps[self.current];
+        Location::new(self.artifact, node, step, self.current)
+    }
+
+    /// Returns the first of the given steps which enters a source line and can be stopped at.
+    /// The line of each step is compared with the one of the step walked before it, so that each
+    /// iteration of a loop stops again, and only the steps for which `walked` holds are walked
+    /// over, e.g., those of the current call when stepping over calls.
+    fn find_line(
+        &self,
+        indices: impl Iterator<Item = usize>,
+        walked: impl Fn(&Self, usize) -> bool,
+        stops: impl Fn(&Self, usize) -> bool,
+    ) -> Option<usize> {
+        let (node, step) = self.steps[self.current];
+        let mut lines = LineTracker::new(self.artifact, node, step);
+        indices.filter(|index| walked(self, *index)).find(|index| {
+            let (node, step) = self.steps[*index];
+            lines.enter(self.artifact, node, step).is_some() && stops(self, *index)
+        })
+    }
+
+    fn depth(&self, index: usize) -> usize {
+        self.artifact.debug_arena[self.steps[index].0].depth
+    }
+
+    /// Returns whether the step is in a call which is stepped over, unless the current step is
+    /// in such a call too, e.g., after going to it.
+    fn is_skipped(&self, index: usize) -> bool {
+        let skipped = SkippedCalls {
+            getters: self.skip_getters.then_some(&self.getter_nodes),
+            blackbox: self.blackbox.as_ref(),
+        };
+        let is_skipped = |index: usize| skipped.contains(self.artifact, self.steps[index].0);
+        !is_skipped(self.current) && is_skipped(index)
+    }
+
+    fn is_breakpoint(&self, index: usize) -> bool {
+        let (node, step) = self.steps[index];
+        source_line(self.artifact, node, step).is_some_and(|(path, line)| {
+            self.breakpoints.iter().any(|(file, l)| *l == line && path.ends_with(file))
+        })
+    }
+}
+
+/// Returns a channel for the events of the engine, which are written to stdout as they are
+/// received.
+pub fn engine_events() -> EventSender {
+    let (sender, receiver) = mpsc::channel::<EngineEvent>();
+    let spawned = thread::Builder::new().name("engine-events".into()).spawn(move || {
+        for event in receiver {
+            if write_event(&mut io::stdout(), &event).is_err() {
+                return;
+            }
+        }
+    });
+    if let Err(e) = spawned {
+        warn!("failed to spawn the engine event writer: {e}");
+    }
+    sender
+}
+
+/// Writes the event as a single line, so that the lines written from several threads do not
+/// interleave.
+fn write_event(output: &mut impl Write, event: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    output.write_all(&line)?;
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use edb_debug_backend::{
+        analysis::cfg::ControlFlowGraph,
+        artifact::debug::{DebugNodeFlat, DebugStep},
+    };
+    use revm::interpreter::opcode::{ADD, PUSH1, STOP};
+    use revm_inspectors::tracing::types::CallKind;
+
+    use super::*;
+
+    fn step(pc: usize, instruction: u8) -> DebugStep {
+        DebugStep { pc, instruction, ..Default::default() }
+    }
+
+    #[test]
+    fn test_machine_interface() {
+        let caller = DebugNodeFlat::new(Address::ZERO, CallKind::Call, 0, vec![step(0, PUSH1)]);
+        let callee = DebugNodeFlat::new(
+            Address::with_last_byte(1),
+            CallKind::Call,
+            1,
+            vec![step(0, PUSH1), step(2, ADD)],
+        );
+        let back = DebugNodeFlat::new(Address::ZERO, CallKind::Call, 0, vec![step(2, STOP)]);
+        let artifact =
+            DebugArtifact { debug_arena: vec![caller, callee, back], ..Default::default() };
+
+        let input = [
+            r#"{"command":"step_instruction"}"#,
+            r#"{"command":"finish"}"#,
+            r#"{"command":"goto","step":9}"#,
+            "oops",
+            r#"{"command":"quit"}"#,
+            r#"{"command":"location"}"#,
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        MachineInterface::new(&artifact).run(input.as_bytes(), &mut output).unwrap();
+
+        let events = String::from_utf8(output).unwrap();
+        let events = events.lines().collect::<Vec<_>>();
+        assert_eq!(events.len(), 7);
+        assert_eq!(events[0], r#"{"event":"ready","steps":4}"#);
+        assert!(events[2].contains(r#""step":1,"#) && events[2].contains(r#""depth":1"#));
+        assert!(events[3].contains(r#""step":3,"#) && events[3].contains(r#""opcode":"STOP""#));
+        assert!(events[3].contains(r#""file":null"#) && events[3].contains(r#""end":true"#));
+        assert!(events[4].contains("out of range"));
+        assert!(events[5].contains("invalid command"));
+        assert_eq!(events[6], r#"{"event":"exited"}"#);
+    }
+
+    #[test]
+    fn test_function_breakpoints() {
+        // DUP1 PUSH4 2e1a7d4d EQ PUSH1 0x0c JUMPI STOP STOP JUMPDEST STOP
+        let code =
+            [0x80, 0x63, 0x2e, 0x1a, 0x7d, 0x4d, 0x14, 0x60, 0x0c, 0x57, 0x00, 0x00, 0x5b, 0x00];
+        let address = Address::with_last_byte(1);
+        let steps = Vec::from([0, 1, 6, 7, 9, 12, 13].map(|pc| step(pc, code[pc])));
+        let artifact = DebugArtifact {
+            debug_arena: vec![DebugNodeFlat::new(address, CallKind::Call, 0, steps)],
+            cfgs: [(address, Arc::new(ControlFlowGraph::new(&code)))].into(),
+            ..Default::default()
+        };
+
+        let mut interface = MachineInterface::new(&artifact);
+        let function = |function: &str| Some(function.to_string());
+        let command = Command::Break { file: None, line: None, function: function("0x2e1a") };
+        assert!(matches!(interface.execute(command), Event::Error { .. }));
+        let command = Command::Break { file: None, line: Some(3), function: function("withdraw") };
+        assert!(matches!(interface.execute(command), Event::Error { .. }));
+
+        let command =
+            Command::Break { file: None, line: None, function: function("withdraw(uint256)") };
+        interface.execute(command);
+        let Event::Location(location) = interface.execute(Command::Continue) else {
+            panic!("expected a location");
+        };
+        assert_eq!((location.step, location.pc), (5, 12));
+        assert_eq!(interface.take_breakpoint_hit(), Some(5));
+
+        interface.execute(Command::Goto { step: 0 });
+        let command = Command::Clear { file: None, line: None, function: function("0x2e1a7d4d") };
+        interface.execute(command);
+        assert!(!interface.function_breakpoints.is_empty());
+        interface.execute(Command::Clear { file: None, line: None, function: None });
+        let Event::Location(location) = interface.execute(Command::Continue) else {
+            panic!("expected a location");
+        };
+        assert!(location.end);
+        assert_eq!(interface.take_breakpoint_hit(), None);
+    }
+}
@@ -0,0 +1,446 @@
+//! Debugging sessions, each of which is bound to a single transaction.
+
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    rc::Rc,
+    sync::{
+        mpsc::{Receiver, TryRecvError},
+        Arc,
+    },
+};
+
+use alloy_primitives::{Address, B256, U256};
+use edb_debug_backend::{
+    analysis::{
+        assembly::AssemblyBlocks,
+        cfg::ControlFlowGraph,
+        constants::ConstantNames,
+        decode::{DecodeJob, Decoded},
+        deployment::{
+            create2_deployments, create_deployments, Create2Deployment, CreateDeployment,
+        },
+        eip712::TypeRegistry,
+        events::{emitted_events, EmittedEvent},
+        getter::getter_nodes,
+        governance::{governance_executions, GovernanceExecution},
+        heatmap::HeatMap,
+        layout::{code_layouts, CodeLayout},
+        multicall::{batches, Batch},
+        provenance::{returndata_provenance, Provenance},
+        safe::{safe_transactions, SafeTransaction},
+        signature::{ecrecover_calls, Ecrecover},
+        storage::{SlotScan, StorageAccess},
+        taint::TaintAnalysis,
+        timeline::Timeline,
+        userop::{user_op_bundles, UserOpBundle},
+    },
+    artifact::{
+        debug::{DebugArtifact, DebugStep, LoopSummary},
+        usage::MemoryUsage,
+    },
+    export::calltree::{call_frames, CallFrame},
+};
+use eyre::Result;
+use rustc_hash::FxHashSet;
+
+use crate::context::{DrawMemory, OpRow};
+
+/// The number of coarse nodes whose stack and memory are kept once loaded.
+const MAX_LOADED_NODES: usize = 16;
+
+/// The number of steps searched for the accesses to the watched storage slot on each poll.
+const STORAGE_SCAN_BUDGET: usize = 100_000;
+
+/// The state of a debugging session, i.e., where the user is in the execution of a transaction.
+///
+/// The layout of the window is shared by all sessions, so switching between sessions only swaps
+/// the data being displayed.
+pub struct Session<'a> {
+    /// The name shown in the session tabs.
+    pub name: String,
+    pub artifact: &'a mut DebugArtifact,
+
+    /// Current step in the debug steps.
+    pub current_step: usize,
+    pub draw_memory: DrawMemory,
+    /// The largest program counter in the current call, which sets the width of the opcode list.
+    pub max_pc: usize,
+    pub last_index: usize,
+
+    /// Loops detected in the current call.
+    pub loops: Vec<LoopSummary>,
+    /// Loops expanded by the user, identified by the call index and their first step.
+    pub expanded_loops: FxHashSet<(usize, usize)>,
+    /// Rows of the opcode list, in which non-expanded loops are collapsed.
+    pub op_rows: Vec<OpRow>,
+    /// The control-flow graph of the runtime code of the current call, if it is known.
+    pub cfg: Option<Arc<ControlFlowGraph>>,
+
+    /// Symbolic names of well-known constants.
+    pub constant_names: ConstantNames,
+    /// The taint analysis, if the taint mode is enabled.
+    pub taint: Option<TaintAnalysis>,
+    /// The execution counts of the current code, if the heat-map mode is enabled.
+    pub heat_map: Option<HeatMap>,
+    /// Whether stepping skips the calls into blackboxed contracts.
+    pub skip_blackboxed: bool,
+    /// The call depth and gas of every step, computed when the timeline is first shown.
+    timeline: OnceCell<Timeline>,
+    /// The bundles of ERC-4337 user operations, decoded when they are first shown.
+    user_ops: OnceCell<Vec<UserOpBundle>>,
+    /// The call frames of the whole execution, built when the trace is first shown.
+    call_frames: OnceCell<Vec<CallFrame>>,
+    /// The nodes of the calls to simple getters, found when stepping first needs them.
+    getter_nodes: OnceCell<BTreeSet<usize>>,
+    /// The transactions executed by Safe multisig wallets, decoded along with the call frames.
+    safe_transactions: OnceCell<Vec<SafeTransaction>>,
+    /// The executions of governance proposals, decoded along with the call frames.
+    governance: OnceCell<Vec<GovernanceExecution>>,
+    /// The batches of calls made through Multicall contracts, decoded along with the call frames.
+    batches: OnceCell<Vec<Batch>>,
+    /// The signatures checked by `ecrecover`, found when one is first explained.
+    ecrecover_calls: OnceCell<Vec<Ecrecover>>,
+    /// The contracts deployed by `CREATE2`, found when the call trace is first shown.
+    create2_deployments: OnceCell<Vec<Create2Deployment>>,
+    /// The contracts deployed by `CREATE`, along with the nonces they were created with.
+    create_deployments: OnceCell<Vec<CreateDeployment>>,
+    /// The EIP-712 struct types declared in the sources, collected along with the signatures.
+    type_registry: OnceCell<TypeRegistry>,
+    /// The provenance of the return data in the stack and memory at the last step it was
+    /// computed for, by node and step, which is shared by the panes drawn at that step.
+    provenance: RefCell<Option<((usize, usize), Rc<Provenance>)>>,
+    /// The memory held by the artifact, estimated when first shown and after compactions.
+    memory_usage: Cell<Option<MemoryUsage>>,
+    /// The events emitted during the execution, found when first shown.
+    events: OnceCell<Vec<EmittedEvent>>,
+    /// The layout of the code of the touched contracts, computed when first shown.
+    code_layouts: OnceCell<BTreeMap<Address, CodeLayout>>,
+    /// The inline assembly blocks of the sources of every compiled code, by code hash, collected
+    /// when an assembly block is first looked up.
+    assembly: OnceCell<HashMap<B256, AssemblyBlocks>>,
+    /// The calls decoded so far in the background, indexed by call frame.
+    pub decoded_calls: HashMap<usize, String>,
+    /// The events decoded so far in the background, indexed like the events.
+    pub decoded_events: HashMap<usize, String>,
+    /// The channel of the calls and events still being decoded, if the decoding was started.
+    decoder: Option<Receiver<Decoded>>,
+    decoding_started: bool,
+    /// The coarse nodes whose stack and memory were loaded by re-executing the transaction, from
+    /// the least recently loaded.
+    loaded_nodes: VecDeque<usize>,
+    /// Whether re-executing the transaction failed, in which case it is not retried.
+    replay_failed: bool,
+    /// The storage slot being watched, along with the account it belongs to.
+    pub storage_slot: Option<(Address, U256)>,
+    /// The accesses to the watched storage slot over the whole execution, found so far.
+    pub storage_history: Vec<StorageAccess>,
+    /// The scan for the accesses to the watched storage slot, while it is in progress.
+    storage_scan: Option<SlotScan>,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(name: String, artifact: &'a mut DebugArtifact) -> Self {
+        let constant_names = ConstantNames::new(artifact);
+        Self {
+            name,
+            artifact,
+
+            current_step: 0,
+            draw_memory: DrawMemory::default(),
+            max_pc: 0,
+            last_index: 0,
+
+            loops: Vec::new(),
+            expanded_loops: FxHashSet::default(),
+            op_rows: Vec::new(),
+            cfg: None,
+
+            constant_names,
+            taint: None,
+            heat_map: None,
+            skip_blackboxed: true,
+            timeline: OnceCell::new(),
+            user_ops: OnceCell::new(),
+            call_frames: OnceCell::new(),
+            getter_nodes: OnceCell::new(),
+            safe_transactions: OnceCell::new(),
+            governance: OnceCell::new(),
+            batches: OnceCell::new(),
+            ecrecover_calls: OnceCell::new(),
+            create2_deployments: OnceCell::new(),
+            create_deployments: OnceCell::new(),
+            type_registry: OnceCell::new(),
+            provenance: RefCell::new(None),
+            memory_usage: Cell::new(None),
+            events: OnceCell::new(),
+            code_layouts: OnceCell::new(),
+            assembly: OnceCell::new(),
+            decoded_calls: HashMap::new(),
+            decoded_events: HashMap::new(),
+            decoder: None,
+            decoding_started: false,
+            loaded_nodes: VecDeque::new(),
+            replay_failed: false,
+            storage_slot: None,
+            storage_history: Vec::new(),
+            storage_scan: None,
+        }
+    }
+
+    /// Records the stack and memory of every step of the current node, if they were only recorded
+    /// at some of them, by re-executing the transaction. Only the last few nodes loaded are kept,
+    /// so that the memory held stays bounded. Returns whether any snapshot was recorded.
+    pub fn load_snapshots(&mut self) -> Result<bool> {
+        if self.replay_failed {
+            return Ok(false);
+        }
+        let node = self.draw_memory.inner_call_index;
+        match self.artifact.load_snapshots(node) {
+            Ok(false) => return Ok(false),
+            Ok(true) => {}
+            Err(e) => {
+                self.replay_failed = true;
+                return Err(e);
+            }
+        }
+
+        self.loaded_nodes.push_back(node);
+        while self.loaded_nodes.len() > MAX_LOADED_NODES {
+            let node = self.loaded_nodes.pop_front().expect("more nodes than the maximum");
+            self.artifact.unload_snapshots(node);
+        }
+        self.memory_usage.set(None);
+        *self.provenance.get_mut() = None;
+        Ok(true)
+    }
+
+    /// Returns the provenance of the return data in the stack and memory at the current step,
+    /// which is only computed again once the step changes.
+    pub fn provenance(&self) -> Rc<Provenance> {
+        let key = (self.draw_memory.inner_call_index, self.current_step);
+        let mut cached = self.provenance.borrow_mut();
+        match &*cached {
+            Some((step, provenance)) if *step == key => provenance.clone(),
+            _ => {
+                let provenance = Rc::new(returndata_provenance(self.artifact, key.0, key.1));
+                *cached = Some((key, provenance.clone()));
+                provenance
+            }
+        }
+    }
+
+    /// Returns the current debug step.
+    pub fn step(&self) -> &DebugStep {
+        &self.artifact.debug_arena[self.draw_memory.inner_call_index].steps[self.current_step]
+    }
+
+    /// Returns the timeline of the whole execution.
+    pub fn timeline(&self) -> &Timeline {
+        self.timeline.get_or_init(|| Timeline::new(self.artifact))
+    }
+
+    /// Returns the bundles of user operations handled in the transaction.
+    pub fn user_op_bundles(&self) -> &[UserOpBundle] {
+        self.user_ops.get_or_init(|| user_op_bundles(self.artifact))
+    }
+
+    /// Returns the call frames of the whole execution, in the order they are entered.
+    pub fn call_frames(&self) -> &[CallFrame] {
+        self.call_frames.get_or_init(|| call_frames(self.artifact))
+    }
+
+    /// Returns the layout of the code of each touched contract with code.
+    pub fn code_layouts(&self) -> &BTreeMap<Address, CodeLayout> {
+        self.code_layouts.get_or_init(|| code_layouts(self.artifact, self.call_frames()))
+    }
+
+    /// Returns the inline assembly blocks of the sources of the code at the given address, if it
+    /// was compiled.
+    pub fn assembly(&self, address: &Address) -> Option<&AssemblyBlocks> {
+        let compilation = self.artifact.compilation_artifacts.get(address)?;
+        let blocks = self.assembly.get_or_init(|| {
+            let mut blocks = HashMap::new();
+            for (_, compilation) in self.artifact.compilation_artifacts.iter() {
+                blocks
+                    .entry(compilation.code_hash)
+                    .or_insert_with(|| AssemblyBlocks::new(compilation));
+            }
+            blocks
+        });
+        blocks.get(&compilation.code_hash)
+    }
+
+    /// Returns the events emitted during the execution, in order.
+    pub fn events(&self) -> &[EmittedEvent] {
+        self.events.get_or_init(|| emitted_events(self.artifact))
+    }
+
+    /// Collects the calls and events decoded in the background since the last poll, starting the
+    /// decoding on the first one. Returns whether anything shown changed, including the progress
+    /// of the storage scan.
+    pub fn poll_decoded(&mut self) -> bool {
+        if !self.decoding_started {
+            self.decoding_started = true;
+            let job = DecodeJob::new(self.artifact, self.call_frames(), self.events());
+            self.decoder = Some(job.spawn());
+        }
+        let mut changed = false;
+        if let Some(decoder) = &self.decoder {
+            let finished = loop {
+                match decoder.try_recv() {
+                    Ok(Decoded::Call { frame, call }) => {
+                        self.decoded_calls.insert(frame, call);
+                    }
+                    Ok(Decoded::Event { index, event }) => {
+                        self.decoded_events.insert(index, event);
+                    }
+                    Err(TryRecvError::Empty) => break false,
+                    Err(TryRecvError::Disconnected) => break true,
+                }
+                changed = true;
+            };
+            if finished {
+                self.decoder = None;
+                changed = true;
+            }
+        }
+
+        if let Some(scan) = &mut self.storage_scan {
+            changed = true;
+            if scan.advance(self.artifact, STORAGE_SCAN_BUDGET, &mut self.storage_history) {
+                self.storage_scan = None;
+            }
+        }
+        changed
+    }
+
+    /// Returns whether calls or events are still being decoded in the background.
+    pub fn is_decoding(&self) -> bool {
+        self.decoder.is_some()
+    }
+
+    /// Watches the accesses to the given storage slot, which are found a few nodes per poll.
+    pub fn watch_slot(&mut self, address: Address, slot: U256) {
+        let mut scan = SlotScan::new(address, slot);
+        self.storage_history.clear();
+        let finished = scan.advance(self.artifact, STORAGE_SCAN_BUDGET, &mut self.storage_history);
+        self.storage_scan = (!finished).then_some(scan);
+        self.storage_slot = Some((address, slot));
+    }
+
+    /// Returns whether the accesses to the watched storage slot are still being searched for.
+    pub fn is_scanning_storage(&self) -> bool {
+        self.storage_scan.is_some()
+    }
+
+    /// Returns the nodes of the calls to simple getters, which stepping treats as single steps.
+    pub fn getter_nodes(&self) -> &BTreeSet<usize> {
+        self.getter_nodes.get_or_init(|| getter_nodes(self.call_frames()))
+    }
+
+    /// Returns the transactions executed by Safe multisig wallets, in the order of execution.
+    pub fn safe_transactions(&self) -> &[SafeTransaction] {
+        self.safe_transactions.get_or_init(|| safe_transactions(self.artifact, self.call_frames()))
+    }
+
+    /// Returns the executions of governance proposals, in the order of execution.
+    pub fn governance_executions(&self) -> &[GovernanceExecution] {
+        self.governance.get_or_init(|| governance_executions(self.call_frames()))
+    }
+
+    /// Returns the batches of calls made through Multicall contracts, in the order of execution.
+    pub fn batches(&self) -> &[Batch] {
+        self.batches.get_or_init(|| batches(self.call_frames()))
+    }
+
+    /// Returns the calls to the `ecrecover` precompile, in the order of execution.
+    pub fn ecrecover_calls(&self) -> &[Ecrecover] {
+        self.ecrecover_calls.get_or_init(|| ecrecover_calls(self.artifact))
+    }
+
+    /// Returns the contracts deployed by `CREATE2`, in the order of execution.
+    pub fn create2_deployments(&self) -> &[Create2Deployment] {
+        self.create2_deployments
+            .get_or_init(|| create2_deployments(self.artifact, self.call_frames()))
+    }
+
+    /// Returns the contracts deployed by `CREATE`, in the order of execution.
+    pub fn create_deployments(&self) -> &[CreateDeployment] {
+        self.create_deployments
+            .get_or_init(|| create_deployments(self.artifact, self.call_frames()))
+    }
+
+    /// Returns the EIP-712 struct types known, to reconstruct the typed data being signed.
+    pub fn type_registry(&self) -> &TypeRegistry {
+        self.type_registry.get_or_init(|| TypeRegistry::new(self.artifact))
+    }
+
+    /// Returns the memory held by the artifact.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let usage = self.memory_usage.get().unwrap_or_else(|| self.artifact.memory_usage());
+        self.memory_usage.set(Some(usage));
+        usage
+    }
+
+    /// Compacts the snapshots of the artifact, returning the number of bytes freed. The stack and
+    /// memory recorded at every step of the nodes already inspected, but the current one, are
+    /// dropped as well, down to the recorded granularity, and recorded again if they are inspected
+    /// once more.
+    pub fn compact(&mut self) -> usize {
+        let before = self.memory_usage();
+        let current = self.draw_memory.inner_call_index;
+        for node in std::mem::take(&mut self.loaded_nodes) {
+            if node == current {
+                self.loaded_nodes.push_back(node);
+            } else {
+                self.artifact.unload_snapshots(node);
+            }
+        }
+        self.artifact.compact_snapshots();
+        self.memory_usage.set(None);
+        *self.provenance.get_mut() = None;
+        before.total().saturating_sub(self.memory_usage().total())
+    }
+
+    /// Returns the index of the current step in the whole execution.
+    pub fn step_index(&self) -> usize {
+        self.artifact.step_index(self.draw_memory.inner_call_index, self.current_step)
+    }
+
+    /// Returns the index of the deepest call frame containing the current step, if any.
+    pub fn current_frame(&self) -> Option<usize> {
+        // frames are entered in order, so that the last one containing the step is the deepest
+        let step = self.step_index();
+        self.call_frames().iter().rposition(|frame| frame.steps.contains(&step))
+    }
+
+    /// Returns the state set by the user, to carry it over to the next frontend.
+    pub fn state(&self) -> SessionState {
+        SessionState {
+            step: self.step_index(),
+            expanded_loops: self.expanded_loops.clone(),
+            taint: self.taint.is_some(),
+            heat_map: self.heat_map.is_some(),
+            skip_blackboxed: self.skip_blackboxed,
+            storage_slot: self.storage_slot,
+        }
+    }
+}
+
+/// The state of a session set by the user, which is carried over when the frontend is rebuilt,
+/// e.g., after re-running the transaction. The analyses are not kept, only whether they are
+/// shown, so that they are computed again for the new session.
+#[derive(Clone, Debug)]
+pub struct SessionState {
+    /// The index of the current step in the whole execution.
+    pub step: usize,
+    pub expanded_loops: FxHashSet<(usize, usize)>,
+    /// Whether the taint mode is enabled.
+    pub taint: bool,
+    /// Whether the heat-map mode is enabled.
+    pub heat_map: bool,
+    pub skip_blackboxed: bool,
+    /// The watched storage slot, along with the account it belongs to.
+    pub storage_slot: Option<(Address, U256)>,
+}
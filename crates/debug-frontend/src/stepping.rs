@@ -0,0 +1,70 @@
+//! Stepping through the execution by source lines, shared by the terminal UI and the machine
+//! interface, so that both stop at the same places and skip the same calls.
+
+use std::{collections::BTreeSet, path::Path};
+
+use edb_debug_backend::artifact::debug::DebugArtifact;
+
+use crate::blackbox::BlackboxFile;
+
+/// Returns the source file and the line the step of the node is mapped to, if any.
+pub(crate) fn source_line(
+    artifact: &DebugArtifact,
+    node: usize,
+    step: usize,
+) -> Option<(&Path, usize)> {
+    let node = &artifact.debug_arena[node];
+    let compilation = artifact.compilation_artifacts.get(&node.address)?;
+    let (element, source) =
+        compilation.source_element(node.steps[step].pc, node.kind.is_any_create())?;
+    Some((source.path.as_path(), source.line_of(element.offset() as usize)))
+}
+
+/// Tells the source lines entered along a walk over the execution. A line is entered by a step
+/// mapped to it right after a step mapped to another line, or at another depth, so that each
+/// iteration of a loop enters its lines again. The steps which are not mapped to any line, e.g.,
+/// of the dispatcher, neither enter nor leave a line.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LineTracker<'a> {
+    previous: Option<(usize, &'a Path, usize)>,
+}
+
+impl<'a> LineTracker<'a> {
+    /// Starts a walk at the given step, whose line is only entered again once it is left.
+    pub(crate) fn new(artifact: &'a DebugArtifact, node: usize, step: usize) -> Self {
+        let mut tracker = Self::default();
+        tracker.enter(artifact, node, step);
+        tracker
+    }
+
+    /// Walks to the given step. Returns the line it enters, if any.
+    pub(crate) fn enter(
+        &mut self,
+        artifact: &'a DebugArtifact,
+        node: usize,
+        step: usize,
+    ) -> Option<(&'a Path, usize)> {
+        let (path, line) = source_line(artifact, node, step)?;
+        let location = Some((artifact.debug_arena[node].depth, path, line));
+        (std::mem::replace(&mut self.previous, location) != location).then_some((path, line))
+    }
+}
+
+/// The calls which stepping goes over as a whole, rather than into.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SkippedCalls<'a> {
+    /// The nodes of the calls to simple getters, unless stepping goes into them.
+    pub getters: Option<&'a BTreeSet<usize>>,
+    /// The blackboxed contracts, unless stepping goes into them.
+    pub blackbox: Option<&'a BlackboxFile>,
+}
+
+impl SkippedCalls<'_> {
+    /// Returns whether the node is in a call which is stepped over.
+    pub(crate) fn contains(&self, artifact: &DebugArtifact, node: usize) -> bool {
+        self.getters.is_some_and(|getters| getters.contains(&node)) ||
+            self.blackbox.is_some_and(|blackbox| {
+                blackbox.contains(artifact, &artifact.debug_arena[node].address)
+            })
+    }
+}
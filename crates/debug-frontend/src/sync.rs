@@ -0,0 +1,160 @@
+//! Synchronization of the current position with an editor through the files of a directory, for
+//! thin integrations, e.g., a Neovim plugin, which need no debug adapter client.
+//!
+//! The debugger writes the current position to `location.json` whenever it changes, and reads
+//! the commands the editor appends to `commands`, one JSON object per line, e.g.,
+//! `{"command":"jump","file":"src/Vault.sol","line":42}`.
+
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+use alloy_primitives::Address;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+/// The file the current position is written to.
+pub const LOCATION_FILE: &str = "location.json";
+
+/// The file the editor appends its commands to.
+pub const COMMANDS_FILE: &str = "commands";
+
+/// The current position, as written for the editor.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SyncLocation {
+    pub session: String,
+    /// The index of the call in the debug arena, and of the step in the call.
+    pub call: usize,
+    pub step: usize,
+    pub address: Address,
+    pub depth: usize,
+    /// The source file and the 1-based line of the step, if the contract is verified.
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+}
+
+/// A command sent by the editor, where `file` is matched against the end of the source paths.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum SyncCommand {
+    /// Runs to the next step on the line.
+    Jump { file: String, line: usize },
+    /// Sets a breakpoint on the line.
+    Break { file: String, line: usize },
+    /// Removes the breakpoint on the line.
+    Clear { file: String, line: usize },
+}
+
+/// The directory shared with the editor.
+#[derive(Debug)]
+pub struct SyncDir {
+    dir: PathBuf,
+    /// How far the commands file has been read.
+    offset: u64,
+    last: Option<SyncLocation>,
+}
+
+impl SyncDir {
+    /// Opens the directory, creating it if needed. The commands appended before are ignored.
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let offset = fs::metadata(dir.join(COMMANDS_FILE)).map_or(0, |metadata| metadata.len());
+        Ok(Self { dir, offset, last: None })
+    }
+
+    /// Writes the current position, unless it is the one written last. The file is replaced
+    /// atomically, so that the editor never reads it half-written.
+    pub fn write_location(&mut self, location: SyncLocation) -> Result<()> {
+        if self.last.as_ref() == Some(&location) {
+            return Ok(());
+        }
+        let temp = self.dir.join(format!("{LOCATION_FILE}.tmp"));
+        fs::write(&temp, serde_json::to_string(&location)?)?;
+        fs::rename(temp, self.dir.join(LOCATION_FILE))?;
+        self.last = Some(location);
+        Ok(())
+    }
+
+    /// Reads the commands appended since the last read. A line still being written is left for
+    /// the next read, and malformed commands are skipped.
+    pub fn read_commands(&mut self) -> Vec<SyncCommand> {
+        let Ok(mut file) = fs::File::open(self.dir.join(COMMANDS_FILE)) else { return Vec::new() };
+        let len = file.metadata().map_or(0, |metadata| metadata.len());
+        if len < self.offset {
+            // the editor truncated the file
+            self.offset = 0;
+        }
+        let mut content = String::new();
+        if file.seek(SeekFrom::Start(self.offset)).is_err() ||
+            file.read_to_string(&mut content).is_err()
+        {
+            return Vec::new();
+        }
+
+        let Some(end) = content.rfind('\n') else { return Vec::new() };
+        self.offset += end as u64 + 1;
+        content[..end]
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(command) => Some(command),
+                Err(e) => {
+                    warn!("ignoring malformed editor command {line:?}: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_sync_dir() {
+        let dir = std::env::temp_dir().join(format!("edb-sync-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut sync = SyncDir::open(dir.clone()).unwrap();
+
+        let location = SyncLocation {
+            session: "tx".to_string(),
+            call: 1,
+            step: 7,
+            address: Address::ZERO,
+            depth: 1,
+            file: Some("src/Vault.sol".into()),
+            line: Some(42),
+        };
+        sync.write_location(location).unwrap();
+        let written = fs::read_to_string(dir.join(LOCATION_FILE)).unwrap();
+        assert!(written.contains(r#""file":"src/Vault.sol","line":42"#));
+
+        let mut commands =
+            fs::OpenOptions::new().create(true).append(true).open(dir.join(COMMANDS_FILE)).unwrap();
+        write!(
+            commands,
+            "{}\nnot json\n{}",
+            r#"{"command":"jump","file":"Vault.sol","line":3}"#, r#"{"command":"break""#
+        )
+        .unwrap();
+        assert_eq!(
+            sync.read_commands(),
+            vec![SyncCommand::Jump { file: "Vault.sol".to_string(), line: 3 }]
+        );
+
+        // the incomplete line is read once it is terminated
+        writeln!(commands, r#","file":"Vault.sol","line":5}}"#).unwrap();
+        assert_eq!(
+            sync.read_commands(),
+            vec![SyncCommand::Break { file: "Vault.sol".to_string(), line: 5 }]
+        );
+        assert!(sync.read_commands().is_empty());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
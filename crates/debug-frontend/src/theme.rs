@@ -0,0 +1,150 @@
+//! Themes, which adapt the output to the terminal and to accessibility needs, e.g., for screen
+//! readers or terminals without colors.
+//!
+//! The panes are drawn with the full theme, and the frame is adapted as a whole afterwards, so
+//! that the drawing code does not need to care about the theme.
+
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+
+/// How colors are rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    #[default]
+    Full,
+    /// Bright colors only, without dimmed text.
+    HighContrast,
+    /// No colors, as required by `NO_COLOR`. Highlighted backgrounds are reversed instead.
+    None,
+}
+
+/// The theme of the frontend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Theme {
+    pub colors: ColorMode,
+    /// Whether borders and symbols are drawn with ASCII characters only.
+    pub ascii: bool,
+    /// Whether the panes are rendered as plain text, without any style, which works best with
+    /// screen readers.
+    pub plain: bool,
+}
+
+impl Theme {
+    /// The accessibility theme: high contrast, and ASCII-only borders and symbols.
+    pub fn accessible() -> Self {
+        Self { colors: ColorMode::HighContrast, ascii: true, plain: false }
+    }
+
+    /// Returns the default theme, without colors if the `NO_COLOR` environment variable is set
+    /// to a non-empty value (see <https://no-color.org>).
+    pub fn from_env() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+        let colors = if no_color { ColorMode::None } else { ColorMode::Full };
+        Self { colors, ..Default::default() }
+    }
+
+    /// Adapts a drawn frame to the theme.
+    pub(crate) fn apply(&self, buffer: &mut Buffer) {
+        if *self == Self::default() {
+            return;
+        }
+
+        for cell in &mut buffer.content {
+            if self.ascii {
+                if let Some(symbol) = ascii_symbol(cell.symbol()) {
+                    cell.set_symbol(symbol);
+                }
+            }
+
+            if self.plain {
+                cell.fg = Color::Reset;
+                cell.bg = Color::Reset;
+                cell.modifier = Modifier::empty();
+                continue;
+            }
+
+            match self.colors {
+                ColorMode::Full => {}
+                ColorMode::HighContrast => {
+                    cell.modifier.remove(Modifier::DIM);
+                    cell.fg = high_contrast(cell.fg);
+                    if cell.bg != Color::Reset {
+                        cell.fg = Color::Black;
+                        cell.bg = Color::White;
+                    }
+                }
+                ColorMode::None => {
+                    if cell.bg != Color::Reset {
+                        cell.modifier.insert(Modifier::REVERSED);
+                    }
+                    cell.fg = Color::Reset;
+                    cell.bg = Color::Reset;
+                }
+            }
+        }
+    }
+}
+
+/// Maps a color to its bright variant, which reads better on both dark and light backgrounds.
+fn high_contrast(color: Color) -> Color {
+    match color {
+        Color::Gray | Color::DarkGray => Color::White,
+        Color::Red => Color::LightRed,
+        Color::Green => Color::LightGreen,
+        Color::Yellow => Color::LightYellow,
+        Color::Blue => Color::LightBlue,
+        Color::Magenta => Color::LightMagenta,
+        Color::Cyan => Color::LightCyan,
+        color => color,
+    }
+}
+
+/// Returns the ASCII replacement of a non-ASCII symbol drawn by the frontend, if any.
+fn ascii_symbol(symbol: &str) -> Option<&'static str> {
+    let ascii = match symbol {
+        "─" | "━" | "═" => "-",
+        "│" | "┃" | "║" | "▏" => "|",
+        "┌" | "┐" | "└" | "┘" | "╔" | "╗" | "╚" | "╝" | "├" | "┤" | "┬" | "┴" | "┼" | "╭" |
+        "╮" | "╰" | "╯" => "+",
+        "▶" | "→" => ">",
+        "←" => "<",
+        "↑" => "^",
+        "↓" => "v",
+        "↻" => "@",
+        "×" => "x",
+        "⚠" => "!",
+        "✗" => "X",
+        "…" => ".",
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{layout::Rect, style::Style};
+
+    #[test]
+    fn test_apply_theme() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 3, 1));
+        buffer.set_string(0, 0, "▶─a", Style::new().fg(Color::Red).bg(Color::DarkGray));
+
+        let mut plain = buffer.clone();
+        Theme { plain: true, ascii: true, ..Default::default() }.apply(&mut plain);
+        let symbols: String = plain.content.iter().map(|cell| cell.symbol()).collect();
+        assert_eq!(symbols, ">-a");
+        let cell = &plain.content[0];
+        assert_eq!(
+            (cell.fg, cell.bg, cell.modifier),
+            (Color::Reset, Color::Reset, Modifier::empty())
+        );
+
+        Theme { colors: ColorMode::None, ..Default::default() }.apply(&mut buffer);
+        assert_eq!(buffer.content[0].symbol(), "▶");
+        assert_eq!(buffer.content[0].fg, Color::Reset);
+        assert!(buffer.content[0].modifier.contains(Modifier::REVERSED));
+    }
+}
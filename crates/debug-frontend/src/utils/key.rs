@@ -0,0 +1,47 @@
+//! Normalization of key events, which are reported differently across platforms and terminals.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+/// Normalizes a key event, returning `None` if the event should be ignored.
+///
+/// - Windows reports key releases as well as presses, which would handle every key twice, so
+///   releases are ignored.
+/// - Some terminals (e.g., ConPTY on Windows) and caps lock report uppercase characters without the
+///   shift modifier, which the key bindings rely on, so it is added back.
+/// - Shift+Tab is reported as either `BackTab` or `Tab` with the shift modifier, the latter of
+///   which is turned into the former.
+pub fn normalize_key_event(mut event: KeyEvent) -> Option<KeyEvent> {
+    if event.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    match event.code {
+        KeyCode::Char(c) if c.is_ascii_uppercase() => event.modifiers |= KeyModifiers::SHIFT,
+        KeyCode::Tab if event.modifiers.contains(KeyModifiers::SHIFT) => {
+            event.code = KeyCode::BackTab
+        }
+        _ => {}
+    }
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_key_event() {
+        let press = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(normalize_key_event(press), Some(press));
+
+        let release =
+            KeyEvent::new_with_kind(KeyCode::Char('j'), KeyModifiers::NONE, KeyEventKind::Release);
+        assert_eq!(normalize_key_event(release), None);
+
+        let upper = KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::NONE);
+        assert_eq!(normalize_key_event(upper).unwrap().modifiers, KeyModifiers::SHIFT);
+
+        let back_tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::SHIFT);
+        assert_eq!(normalize_key_event(back_tab).unwrap().code, KeyCode::BackTab);
+    }
+}
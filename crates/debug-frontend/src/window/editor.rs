@@ -1,5 +1,6 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use tui_textarea::TextArea;
+use unicode_width::UnicodeWidthChar;
 
 use super::{TerminalMode, Window};
 
@@ -31,11 +32,62 @@ impl<'a> Window<'a> {
     }
 
     pub fn handle_insert_mode(&mut self, key: KeyEvent) {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let mut editor = self.editor.borrow_mut();
         match key.code {
-            KeyCode::Esc => self.set_editor_normal_mode(),
+            KeyCode::Esc => {
+                drop(editor);
+                self.set_editor_normal_mode()
+            }
+            // Kill to the start of the line, as in readline. The other kill and yank bindings
+            // (ctrl+k, ctrl+w, alt+d, and ctrl+y) are the ones of the text area.
+            KeyCode::Char('u') if control => {
+                editor.delete_line_by_head();
+            }
+            // Undo, since ctrl+u is taken by the kill above
+            KeyCode::Char('z') if control => {
+                editor.undo();
+            }
             _ => {
-                self.editor.borrow_mut().input(key);
+                editor.input(key);
             }
         }
     }
+
+    /// Handles text pasted into the terminal, which is inserted as a whole so that long hex
+    /// strings (e.g., calldata) spanning multiple lines are kept intact.
+    pub fn handle_paste(&mut self, text: &str) {
+        let text = sanitize_input(text);
+        if self.has_popup() {
+            self.paste_into_popup(&text.replace('\n', ""));
+        } else if self.editor_mode == TerminalMode::Insert {
+            self.editor.borrow_mut().insert_str(text);
+        }
+    }
+}
+
+/// Normalizes the line endings and drops the characters which have no width, e.g., control
+/// characters, since they would misplace the cursor. Tabs are expanded to spaces.
+pub fn sanitize_input(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .chars()
+        .flat_map(|c| match c {
+            '\n' => Some("\n".to_string()),
+            '\t' => Some("    ".to_string()),
+            c if c.width().unwrap_or(0) > 0 => Some(c.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_input() {
+        assert_eq!(sanitize_input("0x12\r\n34\n"), "0x12\n34\n");
+        assert_eq!(sanitize_input("a\tb\u{7}\u{200b}c"), "a    bc");
+        assert_eq!(sanitize_input("日本"), "日本");
+    }
 }
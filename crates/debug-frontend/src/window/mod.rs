@@ -1,4 +1,5 @@
 mod editor;
+mod palette;
 mod pane;
 mod popup;
 mod screen;
@@ -13,9 +14,11 @@ use eyre::Result;
 use ratatui::layout::Rect;
 use tui_textarea::TextArea;
 
-pub use pane::{PaneFlattened, PaneView, VirtCoord};
-pub use popup::{PopupMessage, PopupMode};
-use screen::ScreenManager;
+pub use pane::{PaneFlattened, PaneId, PaneView, VirtCoord};
+pub use popup::{
+    parse_create2, parse_deployer, parse_slot, DialogAction, PopupMessage, PopupMode, PopupOutcome,
+};
+pub use screen::ScreenManager;
 
 /// The focus mode of the frontend.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -0,0 +1,174 @@
+//! The command palette, which lists the debugger commands and UI actions with fuzzy search.
+//!
+//! Each entry is bound to the key event triggering the command, so that executing an entry goes
+//! through the same dispatcher as pressing the key.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::PaneView;
+
+/// The maximum number of entries shown in the palette.
+pub const MAX_PALETTE_ENTRIES: usize = 12;
+
+/// A command listed in the palette.
+#[derive(Clone, Copy, Debug)]
+pub struct PaletteEntry {
+    pub name: &'static str,
+    /// The key binding, as shown to the user.
+    pub binding: &'static str,
+    pub key: KeyEvent,
+    /// The views in which the command is available, or empty if it is available everywhere.
+    pub views: &'static [PaneView],
+}
+
+const fn global(name: &'static str, binding: &'static str, key: KeyEvent) -> PaletteEntry {
+    PaletteEntry { name, binding, key, views: &[] }
+}
+
+const fn local(
+    name: &'static str,
+    binding: &'static str,
+    key: KeyEvent,
+    views: &'static [PaneView],
+) -> PaletteEntry {
+    PaletteEntry { name, binding, key, views }
+}
+
+const fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+const fn shift(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::SHIFT)
+}
+
+const fn ctrl(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::CONTROL)
+}
+
+const STEPPING_VIEWS: &[PaneView] = &[PaneView::Source, PaneView::Opcode, PaneView::Compare];
+const BRANCH_VIEWS: &[PaneView] = &[PaneView::Source, PaneView::Opcode];
+const OPCODE_VIEWS: &[PaneView] = &[PaneView::Source, PaneView::Opcode];
+const FOLLOW_VIEWS: &[PaneView] = &[PaneView::Source, PaneView::Opcode, PaneView::Trace];
+
+pub const PALETTE_ENTRIES: &[PaletteEntry] = &[
+    // debugging
+    local("Step forward", "j", key(KeyCode::Char('j')), STEPPING_VIEWS),
+    local("Step forward into any call", "J", shift(KeyCode::Char('J')), OPCODE_VIEWS),
+    local("Step backward", "k", key(KeyCode::Char('k')), STEPPING_VIEWS),
+    local("Go to the next branch decision", "]", key(KeyCode::Char(']')), BRANCH_VIEWS),
+    local("Go to the previous branch decision", "[", key(KeyCode::Char('[')), BRANCH_VIEWS),
+    local("Go to the next Yul statement", "j", key(KeyCode::Char('j')), &[PaneView::Assembly]),
+    local("Go to the previous Yul statement", "k", key(KeyCode::Char('k')), &[PaneView::Assembly]),
+    local("Expand or collapse the loop", "e", key(KeyCode::Char('e')), &[PaneView::Opcode]),
+    local("Go to the first divergence", "d", key(KeyCode::Char('d')), &[PaneView::Compare]),
+    local("Toggle following the execution", "f", key(KeyCode::Char('f')), FOLLOW_VIEWS),
+    local("Jump to the current step", "c", key(KeyCode::Char('c')), FOLLOW_VIEWS),
+    local("Explain the current opcode", "i", key(KeyCode::Char('i')), OPCODE_VIEWS),
+    local("Toggle a breakpoint on the current line", "b", key(KeyCode::Char('b')), OPCODE_VIEWS),
+    local("Continue to the next breakpoint", "n", key(KeyCode::Char('n')), OPCODE_VIEWS),
+    global("Go to step", ":", key(KeyCode::Char(':'))),
+    global("Show the suggested steps", "!", key(KeyCode::Char('!'))),
+    local("Go to the last step", "G", shift(KeyCode::Char('G')), STEPPING_VIEWS),
+    global("Scroll half a page down", "Ctrl+D", ctrl(KeyCode::Char('d'))),
+    global("Scroll half a page up", "Ctrl+U", ctrl(KeyCode::Char('u'))),
+    global("Run to source line", "L", shift(KeyCode::Char('L'))),
+    global("Break on an event and continue to it", "N", shift(KeyCode::Char('N'))),
+    global("Break on a function entry", "B", shift(KeyCode::Char('B'))),
+    global("List the breakpoints", "Ctrl+L", ctrl(KeyCode::Char('l'))),
+    global("Blackbox or unblackbox a contract", "K", shift(KeyCode::Char('K'))),
+    global("Toggle skipping blackboxed contracts", "Ctrl+B", ctrl(KeyCode::Char('b'))),
+    global("Go to the first call to an address", "A", shift(KeyCode::Char('A'))),
+    global("Watch a storage slot", "W", shift(KeyCode::Char('W'))),
+    local("Watch the slot of the current step", "s", key(KeyCode::Char('s')), &[PaneView::Storage]),
+    local("Jump to the storage access", "Enter", key(KeyCode::Enter), &[PaneView::Storage]),
+    local("Jump to the user operation phase", "Enter", key(KeyCode::Enter), &[PaneView::UserOps]),
+    local("Jump to the proposal action", "Enter", key(KeyCode::Enter), &[PaneView::Governance]),
+    local("Jump to the emitting step", "Enter", key(KeyCode::Enter), &[PaneView::Events]),
+    global("Derive a CREATE2 address", "@", key(KeyCode::Char('@'))),
+    global("Predict the next CREATE addresses", "#", key(KeyCode::Char('#'))),
+    global("Explain the next signature check", "E", shift(KeyCode::Char('E'))),
+    global("Modify & re-run the transaction", "R", shift(KeyCode::Char('R'))),
+    global("Toggle the taint mode", "T", shift(KeyCode::Char('T'))),
+    global("Toggle the execution heat map", "H", shift(KeyCode::Char('H'))),
+    // sessions
+    global("Switch to the next session", "Tab", key(KeyCode::Tab)),
+    global("Switch to the previous session", "Shift+Tab", shift(KeyCode::BackTab)),
+    global("Compare with the next session", "V", shift(KeyCode::Char('V'))),
+    // layout
+    global("Show the next view in the pane", "→", key(KeyCode::Right)),
+    global("Show the previous view in the pane", "←", key(KeyCode::Left)),
+    global("Focus the pane on the left", "Shift+←", shift(KeyCode::Left)),
+    global("Focus the pane on the right", "Shift+→", shift(KeyCode::Right)),
+    global("Focus the pane above", "Shift+↑", shift(KeyCode::Up)),
+    global("Focus the pane below", "Shift+↓", shift(KeyCode::Down)),
+    global("Split the pane horizontally", "S", shift(KeyCode::Char('S'))),
+    global("Split the pane vertically", "D", shift(KeyCode::Char('D'))),
+    global("Close the current view", "X", shift(KeyCode::Char('X'))),
+    global("Set the view of the pane", "P", shift(KeyCode::Char('P'))),
+    global("Assign views to the pane", "C", shift(KeyCode::Char('C'))),
+    global("Compact the snapshots (gc)", "Ctrl+G", ctrl(KeyCode::Char('g'))),
+    global("Undo the layout operation", "Ctrl+Z", ctrl(KeyCode::Char('z'))),
+    global("Redo the layout operation", "Ctrl+Y", ctrl(KeyCode::Char('y'))),
+    global("Zoom the pane", "Z", shift(KeyCode::Char('Z'))),
+    global("Toggle the full screen mode", "Enter", key(KeyCode::Enter)),
+    global("Enter the terminal", "I", shift(KeyCode::Char('I'))),
+    global("Quit", "Q", shift(KeyCode::Char('Q'))),
+];
+
+impl PaletteEntry {
+    /// Returns whether the command is available in the given view.
+    pub fn is_available(&self, view: PaneView) -> bool {
+        self.views.is_empty() || self.views.contains(&view)
+    }
+}
+
+/// Returns the entries available in the given view which match the query, best match first.
+pub fn search_palette(query: &str, view: PaneView) -> Vec<&'static PaletteEntry> {
+    let mut matches: Vec<_> = PALETTE_ENTRIES
+        .iter()
+        .filter(|entry| entry.is_available(view))
+        .filter_map(|entry| fuzzy_score(query, entry.name).map(|score| (score, entry)))
+        .collect();
+    // The sort is stable, so that entries with the same score keep their order
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    matches.into_iter().map(|(_, entry)| entry).take(MAX_PALETTE_ENTRIES).collect()
+}
+
+/// Matches the query as a case-insensitive subsequence of the text, returning a score which
+/// favors consecutive characters and word starts, or `None` if the query does not match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let mut score = 0;
+    let mut chars = text.char_indices();
+    let mut prev: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let q = q.to_ascii_lowercase();
+        let (i, _) = chars.by_ref().find(|(_, c)| c.to_ascii_lowercase() == q)?;
+        score += 1;
+        if prev.map_or(false, |prev| prev + 1 == i) {
+            score += 2;
+        }
+        if i == 0 || text.as_bytes()[i - 1] == b' ' {
+            score += 3;
+        }
+        prev = Some(i);
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("stfw", "Step forward").is_some());
+        assert!(fuzzy_score("fws", "Step forward").is_none());
+        assert_eq!(fuzzy_score("", "Quit"), Some(0));
+        assert!(fuzzy_score("step", "Step forward") > fuzzy_score("step", "Split the pane"));
+
+        let entries = search_palette("next sess", PaneView::Trace);
+        assert_eq!(entries[0].name, "Switch to the next session");
+        assert!(search_palette("loop", PaneView::Trace).is_empty());
+    }
+}
@@ -25,6 +25,27 @@ pub enum PaneView {
     Calldata,
     Returndata,
     Stack,
+    Compare,
+
+    // metadata
+    Contracts,
+    Sessions,
+    Warnings,
+
+    // bytecode
+    Cfg,
+
+    // execution overview
+    Timeline,
+    Storage,
+
+    // inline assembly
+    Assembly,
+
+    // decoded transactions
+    UserOps,
+    Governance,
+    Events,
 
     // null
     Null,
@@ -43,6 +64,17 @@ impl ToString for PaneView {
             PaneView::Calldata => "Calldata".to_string(),
             PaneView::Returndata => "Returndata".to_string(),
             PaneView::Stack => "Stack".to_string(),
+            PaneView::Compare => "Compare".to_string(),
+            PaneView::Contracts => "Contracts".to_string(),
+            PaneView::Sessions => "Sessions".to_string(),
+            PaneView::Warnings => "Warnings".to_string(),
+            PaneView::Cfg => "Control Flow".to_string(),
+            PaneView::Timeline => "Timeline".to_string(),
+            PaneView::Storage => "Storage Timeline".to_string(),
+            PaneView::Assembly => "Inline Assembly".to_string(),
+            PaneView::UserOps => "User Operations".to_string(),
+            PaneView::Governance => "Governance Actions".to_string(),
+            PaneView::Events => "Events".to_string(),
             PaneView::Null => "Null".to_string(),
         }
     }
@@ -61,6 +93,17 @@ impl From<u8> for PaneView {
             7 => PaneView::Calldata,
             8 => PaneView::Returndata,
             9 => PaneView::Stack,
+            10 => PaneView::Contracts,
+            11 => PaneView::Sessions,
+            12 => PaneView::Compare,
+            13 => PaneView::Warnings,
+            14 => PaneView::Cfg,
+            15 => PaneView::Timeline,
+            16 => PaneView::Storage,
+            17 => PaneView::Assembly,
+            18 => PaneView::UserOps,
+            19 => PaneView::Governance,
+            20 => PaneView::Events,
             _ => PaneView::Null,
         }
     }
@@ -72,14 +115,39 @@ impl PaneView {
     }
 
     pub fn num_of_valid_views() -> u8 {
-        10
+        21
+    }
+
+    /// Returns whether moving in the view steps through the execution.
+    pub fn can_step(&self) -> bool {
+        matches!(self, PaneView::Source | PaneView::Opcode | PaneView::Compare | PaneView::Assembly)
+    }
+
+    /// Returns whether the view has selectable rows, in which case moving in the view moves the
+    /// cursor rather than scrolling.
+    pub fn has_cursor(&self) -> bool {
+        matches!(
+            self,
+            PaneView::Contracts |
+                PaneView::Sessions |
+                PaneView::Warnings |
+                PaneView::Storage |
+                PaneView::UserOps |
+                PaneView::Governance |
+                PaneView::Events
+        )
+    }
+
+    /// Returns whether the view shows the current execution point, and thus can follow it.
+    pub fn can_follow(&self) -> bool {
+        matches!(self, PaneView::Source | PaneView::Opcode | PaneView::Trace | PaneView::Cfg)
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct PaneLayout(BTreeMap<PaneId, Rect>);
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct VirtCoord(u16, u16);
 
 /// A virtual rect to help find the focused pane.
@@ -89,11 +157,13 @@ pub const VIRTUAL_RECT: Rect = Rect { x: 0, y: 0, width: 2048, height: 2048 };
 const MIN_SPLITABLE_PANE_SIZE: u16 = 512;
 const MIN_PANE_SIZE: u16 = MIN_SPLITABLE_PANE_SIZE / 2;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Pane {
     pub id: PaneId,
     views: Vec<PaneView>,
     current_view: usize,
+    /// Whether the pane scrolls to the current execution point on every step.
+    pub follow: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -102,12 +172,15 @@ pub struct PaneFlattened<'a> {
     pub rect: Rect,
     pub view: PaneView,
     pub focused: bool,
+    pub follow: bool,
+    /// Whether the pane is drawn above the others.
+    pub overlay: bool,
     pub id: PaneId,
 }
 
 impl Pane {
     pub fn new(id: PaneId) -> Self {
-        Pane { id, views: vec![], current_view: 0 }
+        Pane { id, views: vec![], current_view: 0, follow: true }
     }
 
     pub fn add_view(&mut self, view: PaneView) -> Result<()> {
@@ -180,31 +253,80 @@ impl Pane {
     }
 }
 
+/// A node of the layout tree, which is either a pane or a split of an area into two.
 #[derive(Debug, Clone, PartialEq)]
-struct SplitPane {
-    input_id: PaneId,
-    output_id: [PaneId; 2],
-    ratio: [u32; 2],
-    direction: Direction,
-    side_len: u16,
+enum LayoutNode {
+    Pane(PaneId),
+    Split { direction: Direction, ratio: [u32; 2], children: Box<[LayoutNode; 2]> },
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct MergePane {
-    input_id: [PaneId; 2],
-    output_id: PaneId,
-}
+impl LayoutNode {
+    /// Returns the path (i.e., the child indices from this node) to the given pane.
+    fn path_to(&self, id: PaneId) -> Option<Vec<usize>> {
+        match self {
+            Self::Pane(pane) => (*pane == id).then(Vec::new),
+            Self::Split { children, .. } => children.iter().enumerate().find_map(|(i, child)| {
+                let mut path = child.path_to(id)?;
+                path.insert(0, i);
+                Some(path)
+            }),
+        }
+    }
 
-#[derive(Debug, Clone, PartialEq)]
-struct SwitchPaneId {
-    input_id: [PaneId; 2],
+    fn node_at(&self, path: &[usize]) -> &Self {
+        match (self, path.split_first()) {
+            (Self::Split { children, .. }, Some((i, rest))) => children[*i].node_at(rest),
+            _ => self,
+        }
+    }
+
+    fn node_at_mut(&mut self, path: &[usize]) -> &mut Self {
+        match (self, path.split_first()) {
+            (Self::Split { children, .. }, Some((i, rest))) => children[*i].node_at_mut(rest),
+            (this, _) => this,
+        }
+    }
+
+    /// Returns the area of the node at the given path, if this node takes up `rect`.
+    fn rect_at(&self, path: &[usize], rect: Rect) -> Rect {
+        match (self, path.split_first()) {
+            (Self::Split { direction, ratio, children }, Some((i, rest))) => {
+                children[*i].rect_at(rest, split_rect(rect, *direction, *ratio)[*i])
+            }
+            _ => rect,
+        }
+    }
+
+    /// Returns the panes in this subtree, from the top left to the bottom right.
+    fn panes(&self) -> Vec<PaneId> {
+        match self {
+            Self::Pane(id) => vec![*id],
+            Self::Split { children, .. } => children.iter().flat_map(Self::panes).collect(),
+        }
+    }
+
+    fn layout(&self, rect: Rect, layout: &mut BTreeMap<PaneId, Rect>) {
+        match self {
+            Self::Pane(id) => {
+                layout.insert(*id, rect);
+            }
+            Self::Split { direction, ratio, children } => {
+                let rects = split_rect(rect, *direction, *ratio);
+                children[0].layout(rects[0], layout);
+                children[1].layout(rects[1], layout);
+            }
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum PaneOperation {
-    Split(SplitPane),
-    Merge(MergePane),
-    SwitchId(SwitchPaneId),
+fn split_rect(rect: Rect, direction: Direction, ratio: [u32; 2]) -> [Rect; 2] {
+    let total = ratio[0] + ratio[1];
+    let rects = Layout::new(
+        direction,
+        [Constraint::Ratio(ratio[0], total), Constraint::Ratio(ratio[1], total)],
+    )
+    .split(rect);
+    [rects[0], rects[1]]
 }
 
 #[derive(Debug, Clone)]
@@ -221,45 +343,17 @@ pub enum BorderSide {
     Right,
 }
 
-impl TryFrom<usize> for BorderSide {
-    type Error = eyre::Error;
-
-    fn try_from(value: usize) -> Result<BorderSide> {
-        match value {
-            0 => Ok(BorderSide::Top),
-            1 => Ok(BorderSide::Bottom),
-            2 => Ok(BorderSide::Left),
-            3 => Ok(BorderSide::Right),
-            _ => Err(eyre::eyre!("invalid border side")),
-        }
-    }
-}
-
-impl From<BorderSide> for usize {
-    fn from(value: BorderSide) -> usize {
-        match value {
-            BorderSide::Top => 0,
-            BorderSide::Bottom => 1,
-            BorderSide::Left => 2,
-            BorderSide::Right => 3,
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PaneManager {
     next_id: PaneId,
     panes: BTreeMap<usize, Pane>,
-    operations: Vec<PaneOperation>,
+    layout: LayoutNode,
 
     // This is used to quickly find the pane that contains a view
     view_assignment: BTreeMap<PaneView, PaneId>,
 
     // A virtual focus point to help find the focused pane
     focus: VirtCoord,
-
-    // The creator (operation id) of each border
-    borders: BTreeMap<PaneId, [Option<usize>; 4]>,
 }
 
 impl Default for PaneManager {
@@ -275,16 +369,12 @@ impl PaneManager {
         let mut panes = BTreeMap::new();
         panes.insert(pane.id, pane);
 
-        let mut borders = BTreeMap::new();
-        borders.insert(1, [None; 4]);
-
         PaneManager {
             next_id: 2,
             panes,
-            operations: Vec::new(),
+            layout: LayoutNode::Pane(pane.id),
             view_assignment: BTreeMap::new(),
             focus: VirtCoord::new(0, 0),
-            borders,
         }
     }
 
@@ -318,6 +408,15 @@ impl PaneManager {
         manager.assign(PaneView::Trace, 1)?;
         manager.assign(PaneView::Source, 2)?;
         manager.assign(PaneView::Opcode, 3)?;
+        manager.assign(PaneView::Contracts, 3)?;
+        manager.assign(PaneView::Sessions, 3)?;
+        manager.assign(PaneView::Warnings, 3)?;
+        manager.assign(PaneView::Cfg, 3)?;
+        manager.assign(PaneView::Timeline, 3)?;
+        manager.assign(PaneView::Assembly, 3)?;
+        manager.assign(PaneView::UserOps, 3)?;
+        manager.assign(PaneView::Governance, 3)?;
+        manager.assign(PaneView::Events, 3)?;
 
         manager.assign(PaneView::Variable, 5)?;
         manager.assign(PaneView::Expression, 5)?;
@@ -325,6 +424,8 @@ impl PaneManager {
         manager.assign(PaneView::Memory, 5)?;
         manager.assign(PaneView::Calldata, 5)?;
         manager.assign(PaneView::Returndata, 5)?;
+        manager.assign(PaneView::Compare, 5)?;
+        manager.assign(PaneView::Storage, 5)?;
 
         manager.assign(PaneView::Terminal, 4)?;
 
@@ -365,6 +466,15 @@ impl PaneManager {
         manager.assign(PaneView::Source, 1)?;
 
         manager.assign(PaneView::Opcode, 4)?;
+        manager.assign(PaneView::Contracts, 4)?;
+        manager.assign(PaneView::Sessions, 4)?;
+        manager.assign(PaneView::Warnings, 4)?;
+        manager.assign(PaneView::Cfg, 4)?;
+        manager.assign(PaneView::Timeline, 4)?;
+        manager.assign(PaneView::Assembly, 4)?;
+        manager.assign(PaneView::UserOps, 4)?;
+        manager.assign(PaneView::Governance, 4)?;
+        manager.assign(PaneView::Events, 4)?;
 
         manager.assign(PaneView::Variable, 2)?;
         manager.assign(PaneView::Expression, 2)?;
@@ -372,6 +482,8 @@ impl PaneManager {
         manager.assign(PaneView::Memory, 2)?;
         manager.assign(PaneView::Calldata, 2)?;
         manager.assign(PaneView::Returndata, 2)?;
+        manager.assign(PaneView::Compare, 2)?;
+        manager.assign(PaneView::Storage, 2)?;
 
         manager.assign(PaneView::Terminal, 3)?;
 
@@ -399,8 +511,29 @@ impl PaneManager {
         Ok(())
     }
 
-    pub fn get_borders(&self, id: PaneId) -> Result<[Option<usize>; 4]> {
-        self.borders.get(&id).copied().ok_or(eyre::eyre!("pane not found (get_borders)"))
+    /// Shows the given view in the target pane. If the view is shown in another pane, the
+    /// current view of the target pane is moved there, i.e., the two views are swapped.
+    pub fn set_view(&mut self, target: PaneId, view: PaneView) -> Result<()> {
+        ensure!(view.is_valid(), "invalid view");
+
+        // Work on a copy, so that the layout is left untouched if any step fails
+        let mut next = self.clone();
+        let pane = next.panes.get(&target).ok_or(eyre::eyre!("pane not found (set_view)"))?;
+        let current = pane.get_current_view();
+        match next.get_pane_id(view) {
+            Some(source) if source == target => {}
+            Some(source) if current.is_valid() => {
+                next.unassign(current)?;
+                next.assign(view, target)?;
+                next.assign(current, source)?;
+                next.panes.get_mut(&source).expect("pane exists").select_view(current);
+            }
+            _ => next.assign(view, target)?,
+        }
+        next.panes.get_mut(&target).expect("pane exists").select_view(view);
+
+        *self = next;
+        Ok(())
     }
 
     pub fn unassign(&mut self, view: PaneView) -> Result<()> {
@@ -413,104 +546,41 @@ impl PaneManager {
         Ok(())
     }
 
-    pub fn merge(&mut self, id1: PaneId, id2: PaneId) -> Result<usize> {
-        let (id1, id2) = if id1 < id2 { (id1, id2) } else { (id2, id1) };
-
-        let layout = self.get_layout(VIRTUAL_RECT)?;
-        let rect1 = layout.0.get(&id1).ok_or(eyre::eyre!("pane not found (merge)"))?;
-        let rect2 = layout.0.get(&id2).ok_or(eyre::eyre!("pane not found (merge)"))?;
-
-        // We should first check if the two panes are adjacent.
-        // We will also check the rect1 is on which side of rect2
-        let side = mergeable(rect1, rect2)?;
-
-        let target1 = self.panes.get(&id1).ok_or(eyre::eyre!("pane not found (merge)"))?;
-        let target2 = self.panes.get(&id2).ok_or(eyre::eyre!("pane not found (merge)"))?;
-
-        // We also need to make sure the two panes are from the same parent pane
-        let borders1 = self.borders.get(&id1).ok_or(eyre::eyre!("pane not found (merge)"))?;
-        let borders2 = self.borders.get(&id2).ok_or(eyre::eyre!("pane not found (merge)"))?;
-        let new_borders = match side {
-            BorderSide::Left | BorderSide::Right => {
-                if borders1[usize::from(BorderSide::Top)] !=
-                    borders2[usize::from(usize::from(BorderSide::Top))]
-                {
-                    return Err(RecoverableError::new(format!(
-                        "the two panes are not from the same parent pane"
-                    ))
-                    .into());
-                }
-                if borders1[usize::from(BorderSide::Bottom)] !=
-                    borders2[usize::from(usize::from(BorderSide::Bottom))]
-                {
-                    return Err(RecoverableError::new(format!(
-                        "the two panes are not from the same parent pane"
-                    ))
-                    .into());
-                }
-
-                let mut new_borders = borders1.clone();
-                if side == BorderSide::Left {
-                    new_borders[usize::from(BorderSide::Right)] =
-                        borders2[usize::from(BorderSide::Right)];
-                } else {
-                    new_borders[usize::from(BorderSide::Left)] =
-                        borders2[usize::from(BorderSide::Left)];
-                }
-                new_borders
-            }
-            BorderSide::Top | BorderSide::Bottom => {
-                if borders1[usize::from(BorderSide::Left)] !=
-                    borders2[usize::from(usize::from(BorderSide::Left))]
-                {
-                    return Err(RecoverableError::new(format!(
-                        "the two panes are not from the same parent pane"
-                    ))
-                    .into());
-                }
-                if borders1[usize::from(BorderSide::Right)] !=
-                    borders2[usize::from(usize::from(BorderSide::Right))]
-                {
-                    return Err(RecoverableError::new(format!(
-                        "the two panes are not from the same parent pane"
-                    ))
-                    .into());
-                }
-
-                let mut new_borders = borders1.clone();
-                if side == BorderSide::Top {
-                    new_borders[usize::from(BorderSide::Bottom)] =
-                        borders2[usize::from(BorderSide::Bottom)];
-                } else {
-                    new_borders[usize::from(BorderSide::Top)] =
-                        borders2[usize::from(BorderSide::Top)];
-                }
-                new_borders
-            }
+    /// Closes the given pane, whose area is taken up by its sibling in the layout tree (which
+    /// may consist of several panes). The views of the closed pane are moved to the first pane
+    /// of the sibling which accepts all of them.
+    pub fn close(&mut self, id: PaneId) -> Result<()> {
+        let path = self.layout.path_to(id).ok_or(eyre::eyre!("pane not found (close)"))?;
+        let Some((index, parent)) = path.split_last() else {
+            return Err(RecoverableError::new("Cannot close the last pane.").into());
         };
-
-        let new_id = id1;
-
-        let mut new_pane = Pane::new(new_id);
-        for view in target1.views.iter().chain(target2.views.iter()) {
-            new_pane.add_view(*view)?;
-        }
-
-        // merge needs to update the view assignment
-        for view in &new_pane.views {
-            self.view_assignment.insert(*view, new_id);
+        let LayoutNode::Split { children, .. } = self.layout.node_at(parent) else {
+            return Err(eyre::eyre!("the parent of a pane is not a split (close)"));
+        };
+        let sibling = children[1 - index].clone();
+
+        let closed = self.panes.get(&id).ok_or(eyre::eyre!("pane not found (close)"))?;
+        let receiver = sibling
+            .panes()
+            .into_iter()
+            .find_map(|candidate| {
+                let mut pane = self.panes.get(&candidate)?.clone();
+                closed.views.iter().all(|view| pane.add_view(*view).is_ok()).then_some(pane)
+            })
+            .ok_or_else(|| {
+                RecoverableError::new(
+                    "The views of the current pane cannot be moved to the neighboring panes, since the terminal has to be the only view in a pane.\n\nTo close this pane, you may consider unregistering some views first.",
+                )
+            })?;
+
+        for view in &receiver.views {
+            self.view_assignment.insert(*view, receiver.id);
         }
+        self.panes.insert(receiver.id, receiver);
+        self.panes.remove(&id);
+        *self.layout.node_at_mut(parent) = sibling;
 
-        self.panes.remove(&id2);
-        self.panes.insert(new_id, new_pane);
-
-        self.borders.remove(&id2);
-        self.borders.insert(new_id, new_borders);
-
-        let merge = MergePane { input_id: [id1, id2], output_id: new_id };
-        self.operations.push(PaneOperation::Merge(merge));
-
-        Ok(new_id)
+        Ok(())
     }
 
     pub fn split(&mut self, id: PaneId, direction: Direction, ratio: [u32; 2]) -> Result<usize> {
@@ -529,49 +599,24 @@ impl PaneManager {
         let new_id = self.next_id;
         self.next_id += 1;
 
-        // id is the left pane and new_id is the right pane
-        let split = SplitPane { input_id: id, output_id: [id, new_id], ratio, direction, side_len };
-
-        // update the panes
-        let new_pane = Pane::new(new_id);
-        self.panes.insert(new_id, new_pane);
-
-        // update the border info
-        let id1_borders = self.borders.get_mut(&id).ok_or(eyre::eyre!("pane not found (split)"))?;
-        let mut id2_borders = id1_borders.clone();
-
-        match direction {
-            Direction::Vertical => {
-                id1_borders[usize::from(BorderSide::Bottom)] = Some(self.operations.len());
-                id2_borders[usize::from(BorderSide::Top)] = Some(self.operations.len());
-            }
-            Direction::Horizontal => {
-                id1_borders[usize::from(BorderSide::Right)] = Some(self.operations.len());
-                id2_borders[usize::from(BorderSide::Left)] = Some(self.operations.len());
-            }
-        }
-        self.borders.insert(new_id, id2_borders);
-
-        self.operations.push(PaneOperation::Split(split));
+        // id is the left (top) pane and new_id is the right (bottom) pane
+        let path = self.layout.path_to(id).ok_or(eyre::eyre!("pane not found (split)"))?;
+        *self.layout.node_at_mut(&path) = LayoutNode::Split {
+            direction,
+            ratio,
+            children: Box::new([LayoutNode::Pane(id), LayoutNode::Pane(new_id)]),
+        };
+        self.panes.insert(new_id, Pane::new(new_id));
 
         Ok(new_id)
     }
 
     // Switch the pane ids
     pub fn switch_id(&mut self, input1: PaneId, input2: PaneId) -> Result<()> {
-        let _ = self.panes.get(&input1).ok_or(eyre::eyre!("pane not found (switch_id)"))?;
-        let _ = self.panes.get(&input2).ok_or(eyre::eyre!("pane not found (switch_id)"))?;
-
-        // remember to update borders
-        let id1_borders =
-            self.borders.remove(&input1).ok_or(eyre::eyre!("pane not found (switch_id)"))?;
-        let id2_borders =
-            self.borders.remove(&input2).ok_or(eyre::eyre!("pane not found (switch_id)"))?;
-        self.borders.insert(input1, id2_borders);
-        self.borders.insert(input2, id1_borders);
-
-        let switch = SwitchPaneId { input_id: [input1, input2] };
-        self.operations.push(PaneOperation::SwitchId(switch));
+        let path1 = self.layout.path_to(input1).ok_or(eyre::eyre!("pane not found (switch_id)"))?;
+        let path2 = self.layout.path_to(input2).ok_or(eyre::eyre!("pane not found (switch_id)"))?;
+        *self.layout.node_at_mut(&path1) = LayoutNode::Pane(input2);
+        *self.layout.node_at_mut(&path2) = LayoutNode::Pane(input1);
 
         Ok(())
     }
@@ -631,6 +676,8 @@ impl PaneManager {
         Ok(())
     }
 
+    /// Moves the border on the given side of the pane by `amount` (towards the right or the
+    /// bottom if positive), which is the border of the innermost split in that direction.
     pub fn scale_pane(
         &mut self,
         id: PaneId,
@@ -638,37 +685,49 @@ impl PaneManager {
         amount: i32,
         screen: Rect,
     ) -> Result<()> {
-        let borders = self.borders.get(&id).ok_or(eyre::eyre!("pane not found (scale_pane)"))?;
-
-        let op_id =
-            borders[side as usize].ok_or(eyre::eyre!("cannot scale the pane to this side"))?;
-        let op = self.operations.get_mut(op_id).ok_or(eyre::eyre!("operation not found"))?;
-
-        if let PaneOperation::Split(split) = op {
-            let (side_len, min_len) = match split.direction {
-                Direction::Horizontal => (
-                    (split.side_len as u32) * (screen.width as u32) / (VIRTUAL_RECT.width as u32),
-                    MIN_PANE_SIZE as u32 * screen.width as u32 / VIRTUAL_RECT.width as u32,
-                ),
-                Direction::Vertical => (
-                    (split.side_len as u32) * (screen.height as u32) / (VIRTUAL_RECT.height as u32),
-                    MIN_PANE_SIZE as u32 * screen.height as u32 / VIRTUAL_RECT.height as u32,
-                ),
-            };
-
-            let len1 = side_len * split.ratio[0] / (split.ratio[0] + split.ratio[1]);
-            let len2 = side_len - len1;
-
-            if (len1 as i32 + amount) < min_len as i32 || (len2 as i32 - amount) < min_len as i32 {
-                return Err(eyre::eyre!("pane is too small to scale"));
-            }
+        let path = self.layout.path_to(id).ok_or(eyre::eyre!("pane not found (scale_pane)"))?;
+        let (direction, child) = match side {
+            BorderSide::Left => (Direction::Horizontal, 1),
+            BorderSide::Right => (Direction::Horizontal, 0),
+            BorderSide::Top => (Direction::Vertical, 1),
+            BorderSide::Bottom => (Direction::Vertical, 0),
+        };
+        let depth = (0..path.len())
+            .rev()
+            .find(|depth| {
+                path[*depth] == child &&
+                    matches!(
+                        self.layout.node_at(&path[..*depth]),
+                        LayoutNode::Split { direction: d, .. } if *d == direction
+                    )
+            })
+            .ok_or(eyre::eyre!("cannot scale the pane to this side"))?;
+
+        let rect = self.layout.rect_at(&path[..depth], screen);
+        let (side_len, min_len) = match direction {
+            Direction::Horizontal => (
+                rect.width as u32,
+                MIN_PANE_SIZE as u32 * screen.width as u32 / VIRTUAL_RECT.width as u32,
+            ),
+            Direction::Vertical => (
+                rect.height as u32,
+                MIN_PANE_SIZE as u32 * screen.height as u32 / VIRTUAL_RECT.height as u32,
+            ),
+        };
 
-            split.ratio = [(len1 as i32 + amount) as u32, (len2 as i32 - amount) as u32];
+        let LayoutNode::Split { ratio, .. } = self.layout.node_at_mut(&path[..depth]) else {
+            return Err(eyre::eyre!("invalid operation"));
+        };
+        let len1 = side_len * ratio[0] / (ratio[0] + ratio[1]);
+        let len2 = side_len - len1;
 
-            Ok(())
-        } else {
-            Err(eyre::eyre!("invalid operation"))
+        if (len1 as i32 + amount) < min_len as i32 || (len2 as i32 - amount) < min_len as i32 {
+            return Err(eyre::eyre!("pane is too small to scale"));
         }
+
+        *ratio = [(len1 as i32 + amount) as u32, (len2 as i32 - amount) as u32];
+
+        Ok(())
     }
 
     pub fn get_focused_pane_mut(&mut self) -> Result<&mut Pane> {
@@ -719,6 +778,8 @@ impl PaneManager {
                     views: pane.get_views(),
                     view: pane.get_current_view(),
                     focused: focus_info.pane_id == *id,
+                    follow: pane.follow,
+                    overlay: false,
                     id: *id,
                 })
             })
@@ -727,57 +788,7 @@ impl PaneManager {
 
     pub fn get_layout(&self, app: Rect) -> Result<PaneLayout> {
         let mut layout = BTreeMap::new();
-        layout.insert(1usize, app);
-
-        for op in &self.operations {
-            match op {
-                PaneOperation::Split(split) => {
-                    let input = layout
-                        .get(&split.input_id)
-                        .ok_or(eyre::eyre!("pane not found (get_layout)"))?;
-                    let t_r = split.ratio[0] + split.ratio[1];
-                    let [output1, output2] = Layout::new(
-                        split.direction,
-                        [
-                            Constraint::Ratio(split.ratio[0], t_r),
-                            Constraint::Ratio(split.ratio[1], t_r),
-                        ],
-                    )
-                    .split(*input)[..] else {
-                        return Err(eyre::eyre!("unreachable code"));
-                    };
-
-                    layout.remove(&split.input_id);
-                    layout.insert(split.output_id[0], output1);
-                    layout.insert(split.output_id[1], output2);
-                }
-                PaneOperation::Merge(merge) => {
-                    let input1 = layout
-                        .get(&merge.input_id[0])
-                        .ok_or(eyre::eyre!("pane not found (get_layout)"))?;
-                    let input2 = layout
-                        .get(&merge.input_id[1])
-                        .ok_or(eyre::eyre!("pane not found (get_layout)"))?;
-                    let output = input1.union(*input2);
-
-                    layout.remove(&merge.input_id[0]);
-                    layout.remove(&merge.input_id[1]);
-                    layout.insert(merge.output_id, output);
-                }
-                PaneOperation::SwitchId(switch) => {
-                    let input1 = *layout
-                        .get(&switch.input_id[0])
-                        .ok_or(eyre::eyre!("pane not found (get_layout)"))?;
-                    let input2 = *layout
-                        .get(&switch.input_id[1])
-                        .ok_or(eyre::eyre!("pane not found (get_layout)"))?;
-
-                    layout.insert(switch.input_id[0], input2);
-                    layout.insert(switch.input_id[1], input1);
-                }
-            }
-        }
-
+        self.layout.layout(app, &mut layout);
         Ok(PaneLayout(layout))
     }
 }
@@ -802,25 +813,30 @@ impl VirtCoord {
     }
 }
 
-#[inline]
-fn mergeable(rect1: &Rect, rect2: &Rect) -> Result<BorderSide> {
-    if rect1.width == rect2.width {
-        if rect1.bottom() == rect2.top() {
-            return Ok(BorderSide::Top);
-        }
-        if rect1.top() == rect2.bottom() {
-            return Ok(BorderSide::Bottom);
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_close_pane_with_split_sibling() {
+        // +---+---+
+        // | 1 | 2 |
+        // |   +---+
+        // |   | 3 |
+        // +---+---+
+        let mut manager = PaneManager::new();
+        manager.split(1, Direction::Horizontal, [1, 1]).unwrap();
+        manager.split(2, Direction::Vertical, [1, 1]).unwrap();
+        manager.assign(PaneView::Source, 1).unwrap();
 
-    if rect1.height == rect2.height {
-        if rect1.right() == rect2.left() {
-            return Ok(BorderSide::Left);
-        }
-        if rect1.left() == rect2.right() {
-            return Ok(BorderSide::Right);
-        }
-    }
+        // Pane 1 has no single neighbor to merge with, but its sibling subtree takes its place
+        manager.close(1).unwrap();
+        let layout = manager.get_layout(VIRTUAL_RECT).unwrap();
+        assert_eq!(layout.0.keys().copied().collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(layout.0[&2].width, VIRTUAL_RECT.width);
+        assert_eq!(manager.get_pane_id(PaneView::Source), Some(2));
 
-    Err(eyre::eyre!("panes are not adjacent"))
+        manager.close(3).unwrap();
+        assert!(manager.close(2).is_err());
+    }
 }
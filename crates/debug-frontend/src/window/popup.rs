@@ -1,16 +1,188 @@
 use std::collections::HashSet;
 
+use alloy_primitives::{Address, B256, U256};
 use crossterm::event::{KeyCode, KeyEvent};
+use edb_debug_backend::analysis::{
+    deployment::InitCode, entry::FunctionTarget, events::EventFilter,
+};
 use eyre::{eyre, Result};
 
-use crate::context::RecoverableError;
+use crate::{blackbox::BlackboxEntry, context::RecoverableError, edit::TxEdit};
 
-use super::{pane::Pane, PaneView, Window};
+use super::{palette::search_palette, pane::Pane, PaneView, Window};
+
+/// An action taken once a dialog is confirmed or submitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogAction {
+    Quit,
+    /// Go to a step in the whole execution.
+    GotoStep,
+    /// Continue until a source line, given as `file:line`.
+    RunToLine,
+    /// Go to the first call to an address.
+    GotoCall,
+    /// Modify the transaction and re-run it in a new session.
+    EditTx,
+    /// Follow the accesses to a storage slot, given as `slot` or `address:slot`.
+    WatchSlot,
+    /// Derive a `CREATE2` address, given as `deployer salt initcode|hash`.
+    Create2,
+    /// Predict the next `CREATE` addresses of a deployer, given as `deployer [nonce]`.
+    PredictCreate,
+    /// Continue until an event is emitted, given as a signature and conditions on its values,
+    /// e.g., `Transfer(address,address,uint256) to=0x…`.
+    ContinueToEvent,
+    /// Set or remove a breakpoint on the entry of a function, given as `Contract.function`, a
+    /// signature, or a selector.
+    BreakOnFunction,
+    /// Add a contract to the blackbox list, or remove it, given by its address or name.
+    Blackbox,
+}
+
+impl DialogAction {
+    fn prompt(&self) -> &'static str {
+        match self {
+            Self::Quit => "Quit the debugger?",
+            Self::GotoStep => "Go to step (in the whole execution):",
+            Self::RunToLine => "Run to source line (e.g. Token.sol:42):",
+            Self::GotoCall => "Go to the first call to address (e.g. 0xdAC1...1ec7):",
+            Self::EditTx => "Modify & re-run (e.g. value=1000 gas=300000 from=0x... data=0x...):",
+            Self::WatchSlot => "Watch storage slot (e.g. 0x5, or 0xdAC1...1ec7:0x5):",
+            Self::Create2 => "Derive a CREATE2 address (deployer salt initcode|hash):",
+            Self::PredictCreate => "Predict CREATE addresses (deployer [nonce]):",
+            Self::ContinueToEvent => {
+                "Break on event, and continue until it (e.g. Transfer(address,address,uint256) \
+                 to=0x..., or nothing to remove it):"
+            }
+            Self::BreakOnFunction => {
+                "Break on function entry (e.g. Vault.withdraw, withdraw(uint256) or 0x2e1a7d4d):"
+            }
+            Self::Blackbox => {
+                "Blackbox or unblackbox a contract (address or name, e.g. UniswapV2Router02):"
+            }
+        }
+    }
+
+    /// Cleans up pasted text, which often comes with surrounding quotes (e.g., from JSON) or
+    /// whitespace and line breaks (e.g., from a block explorer page).
+    pub fn clean(&self, text: &str) -> String {
+        let text = text.trim().trim_matches(|c| matches!(c, '"' | '\'' | '`')).trim();
+        match self {
+            // Digit separators are allowed in step numbers
+            Self::GotoStep => {
+                text.chars().filter(|c| !c.is_whitespace() && !matches!(c, '_' | ',')).collect()
+            }
+            Self::GotoCall | Self::WatchSlot => {
+                text.chars().filter(|c| !c.is_whitespace()).collect()
+            }
+            Self::Quit | Self::RunToLine | Self::BreakOnFunction | Self::Blackbox => {
+                text.replace(['\r', '\n'], "")
+            }
+            Self::EditTx | Self::Create2 | Self::PredictCreate | Self::ContinueToEvent => {
+                text.replace(['\r', '\n'], " ")
+            }
+        }
+    }
+
+    /// Checks the input, returning the reason if it is invalid.
+    pub fn validate(&self, input: &str) -> Result<(), String> {
+        match self {
+            Self::Quit => Ok(()),
+            Self::GotoStep => {
+                input.parse::<usize>().map(drop).map_err(|_| "not a step number".to_string())
+            }
+            Self::RunToLine => match input.rsplit_once(':') {
+                Some((file, line)) if !file.is_empty() && line.parse::<usize>().is_ok() => Ok(()),
+                _ => Err("expected `file:line`".to_string()),
+            },
+            Self::GotoCall => {
+                input.parse::<Address>().map(drop).map_err(|_| "not an address".to_string())
+            }
+            Self::EditTx => input.parse::<TxEdit>().map(drop),
+            Self::WatchSlot => parse_slot(input).map(drop),
+            Self::Create2 => parse_create2(input).map(drop),
+            Self::PredictCreate => parse_deployer(input).map(drop),
+            Self::ContinueToEvent if input.is_empty() => Ok(()),
+            Self::ContinueToEvent => {
+                input.parse::<EventFilter>().map(drop).map_err(|e| e.to_string())
+            }
+            Self::BreakOnFunction => {
+                input.parse::<FunctionTarget>().map(drop).map_err(|e| e.to_string())
+            }
+            Self::Blackbox => input.parse::<BlackboxEntry>().map(drop).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Parses a storage slot, optionally preceded by the address of the account, e.g., `0x5` or
+/// `0xdAC1...1ec7:0x5`.
+pub fn parse_slot(input: &str) -> Result<(Option<Address>, U256), String> {
+    let (address, slot) = match input.split_once(':') {
+        Some((address, slot)) => {
+            (Some(address.parse::<Address>().map_err(|_| "not an address".to_string())?), slot)
+        }
+        None => (None, input),
+    };
+    let slot = slot.parse::<U256>().map_err(|_| "not a storage slot".to_string())?;
+    Ok((address, slot))
+}
+
+/// Parses the deployer, the salt, and the init code (or its hash) of a `CREATE2` deployment,
+/// separated by whitespace, e.g., `0x4e59...4956C 0x0 0x6080...`.
+pub fn parse_create2(input: &str) -> Result<(Address, B256, InitCode), String> {
+    let [deployer, salt, init_code] = input.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err("expected `deployer salt initcode|hash`".to_string());
+    };
+    let deployer = deployer.parse::<Address>().map_err(|_| "not an address".to_string())?;
+    let salt = salt.parse::<U256>().map_err(|_| "not a salt".to_string())?;
+    let init_code = InitCode::parse(init_code).ok_or_else(|| "not hex init code".to_string())?;
+    Ok((deployer, salt.into(), init_code))
+}
+
+/// Parses a deployer, optionally followed by its nonce, e.g., `0xb20a...E1d0 12`.
+pub fn parse_deployer(input: &str) -> Result<(Address, Option<u64>), String> {
+    let mut words = input.split_whitespace();
+    let deployer = words.next().ok_or_else(|| "expected `deployer [nonce]`".to_string())?;
+    let deployer = deployer.parse::<Address>().map_err(|_| "not an address".to_string())?;
+    let nonce = words
+        .next()
+        .map(|nonce| nonce.parse::<U256>().ok().and_then(|nonce| u64::try_from(nonce).ok()))
+        .map(|nonce| nonce.ok_or_else(|| "not a nonce".to_string()))
+        .transpose()?;
+    if words.next().is_some() {
+        return Err("expected `deployer [nonce]`".to_string());
+    }
+    Ok((deployer, nonce))
+}
+
+/// The outcome of a key event in a popup, which is handled by the frontend context.
+#[derive(Debug, Clone)]
+pub enum PopupOutcome {
+    /// A command picked from the command palette.
+    Command(KeyEvent),
+    /// A confirmed or submitted dialog, along with the input (empty for confirmations).
+    Dialog(DialogAction, String),
+}
 
 #[derive(Debug, Clone)]
 pub enum PopupMode {
     ErrorMessage(String),
-    ViewAssignment(u8),
+    /// The assignment of views to the focused pane, with the selected view and the digits of
+    /// its number typed so far.
+    ViewAssignment(u8, String),
+    /// The picker of the view shown in the focused pane, with the selected view.
+    ViewPicker(u8),
+    /// The command palette, with the search query and the selected entry.
+    CommandPalette(String, usize),
+    /// A yes/no question.
+    Confirmation(DialogAction),
+    /// A prompt for a line of input, with the input so far.
+    Input(DialogAction, String),
+    /// A read-only message, with its title.
+    Info(String, String),
+    /// The suggested steps to start from, as their descriptions and step indices, with the
+    /// selected one.
+    Suggestions(Vec<(String, usize)>, usize),
 }
 
 #[derive(Debug, Clone)]
@@ -24,16 +196,82 @@ impl PopupMode {
     pub fn title(&self) -> &str {
         match self {
             Self::ErrorMessage(_) => " Error ",
-            Self::ViewAssignment(_) => " View Assignment ",
+            Self::ViewAssignment(..) => " View Assignment ",
+            Self::ViewPicker(_) => " Set View ",
+            Self::CommandPalette(..) => " Command Palette ",
+            Self::Confirmation(_) => " Confirmation ",
+            Self::Input(..) => " Input ",
+            Self::Info(title, _) => title,
+            Self::Suggestions(..) => " Suggested Steps ",
         }
     }
 
     pub fn message(&self, pane: &Pane) -> (String, HashSet<String>) {
         let mut highlights = HashSet::new();
         match self {
-            Self::ErrorMessage(message) => (message.clone(), highlights),
-            Self::ViewAssignment(k) => {
+            Self::ErrorMessage(message) | Self::Info(_, message) => (message.clone(), highlights),
+            Self::ViewPicker(k) => {
+                let mut message = "Select the view to show in this pane\n-------------------------------------------\n".to_string();
+                for i in 0..PaneView::num_of_valid_views() {
+                    let view = PaneView::from(i);
+                    let location = if pane.get_current_view() == view {
+                        " (shown here)"
+                    } else if pane.has_view(&view) {
+                        " (in this pane)"
+                    } else {
+                        ""
+                    };
+                    let new_line =
+                        format!("({}) {}{location}\n", (i + b'a') as char, view.to_string());
+                    message.push_str(&new_line);
+                    if *k == i {
+                        highlights.insert(new_line.trim().to_string());
+                    }
+                }
+                (message, highlights)
+            }
+            Self::Suggestions(suggestions, selected) => {
+                let mut message = "Jump to a step worth looking at\n-------------------------------------------\n".to_string();
+                for (i, (description, step)) in suggestions.iter().enumerate() {
+                    let new_line = format!("#{step}: {description}\n");
+                    message.push_str(&new_line);
+                    if i == *selected {
+                        highlights.insert(new_line.trim().to_string());
+                    }
+                }
+                (message, highlights)
+            }
+            Self::Confirmation(action) => {
+                (format!("{}\n\n[y/Enter] Yes    [n] No", action.prompt()), highlights)
+            }
+            Self::Input(action, input) => {
+                let status = match action.validate(input) {
+                    Err(reason) if !input.is_empty() => format!("✗ {reason}"),
+                    _ => "[Enter] Submit".to_string(),
+                };
+                (format!("{}\n\n> {input}▏\n\n{status}", action.prompt()), highlights)
+            }
+            Self::CommandPalette(query, selected) => {
+                let mut message =
+                    format!("> {query}▏\n-------------------------------------------\n");
+                let entries = search_palette(query, pane.get_current_view());
+                if entries.is_empty() {
+                    message.push_str("No matching command\n");
+                }
+                for (i, entry) in entries.into_iter().enumerate() {
+                    let new_line = format!("{} [{}]\n", entry.name, entry.binding);
+                    message.push_str(&new_line);
+                    if i == *selected {
+                        highlights.insert(new_line.trim().to_string());
+                    }
+                }
+                (message, highlights)
+            }
+            Self::ViewAssignment(k, digits) => {
                 let mut message = "Select the following view to register\n-------------------------------------------\n".to_string();
+                if !digits.is_empty() {
+                    message = format!("> {digits}▏\n{message}");
+                }
                 let mut assign_count = 0u8;
                 for i in 0..PaneView::num_of_valid_views() {
                     let view = PaneView::from(i);
@@ -77,7 +315,43 @@ impl Window<'_> {
     }
 
     pub fn pop_assignment(&mut self) {
-        self.popup_mode = Some(PopupMode::ViewAssignment(0));
+        self.popup_mode = Some(PopupMode::ViewAssignment(0, String::new()));
+    }
+
+    pub fn pop_command_palette(&mut self) {
+        self.popup_mode = Some(PopupMode::CommandPalette(String::new(), 0));
+    }
+
+    pub fn pop_view_picker(&mut self) {
+        self.popup_mode = Some(PopupMode::ViewPicker(0));
+    }
+
+    pub fn pop_confirmation(&mut self, action: DialogAction) {
+        self.popup_mode = Some(PopupMode::Confirmation(action));
+    }
+
+    pub fn pop_info(&mut self, title: String, message: String) {
+        self.popup_mode = Some(PopupMode::Info(title, message));
+    }
+
+    pub fn pop_input(&mut self, action: DialogAction) {
+        self.popup_mode = Some(PopupMode::Input(action, String::new()));
+    }
+
+    pub fn pop_suggestions(&mut self, suggestions: Vec<(String, usize)>) {
+        self.popup_mode = Some(PopupMode::Suggestions(suggestions, 0));
+    }
+
+    /// Appends the pasted text to the input of the popup, if it takes any.
+    pub fn paste_into_popup(&mut self, text: &str) {
+        match &mut self.popup_mode {
+            Some(PopupMode::Input(action, input)) => input.push_str(&action.clean(text)),
+            Some(PopupMode::CommandPalette(query, selected)) => {
+                query.push_str(text);
+                *selected = 0;
+            }
+            _ => {}
+        }
     }
 
     pub fn exit_popup(&mut self) {
@@ -97,31 +371,168 @@ impl Window<'_> {
         Ok(PopupMessage { title: mode.title().to_string(), message, highlights })
     }
 
-    pub fn handle_key_event_in_popup(&mut self, event: KeyEvent) -> Result<()> {
+    /// Handles a key event in the popup, which captures all key events but ESC, returning the
+    /// outcome to be handled by the frontend context, if any.
+    pub fn handle_key_event_in_popup(&mut self, event: KeyEvent) -> Result<Option<PopupOutcome>> {
         match self.popup_mode.clone() {
-            Some(PopupMode::ViewAssignment(k)) => self.handle_key_event_for_assignment(event, k),
-            _ => Ok(()),
+            Some(PopupMode::ViewAssignment(k, digits)) => {
+                self.handle_key_event_for_assignment(event, k, digits).map(|_| None)
+            }
+            Some(PopupMode::ViewPicker(k)) => {
+                self.handle_key_event_for_view_picker(event, k).map(|_| None)
+            }
+            Some(PopupMode::CommandPalette(query, selected)) => Ok(self
+                .handle_key_event_for_palette(event, query, selected)?
+                .map(PopupOutcome::Command)),
+            Some(PopupMode::Confirmation(action)) => match event.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    self.exit_popup();
+                    Ok(Some(PopupOutcome::Dialog(action, String::new())))
+                }
+                KeyCode::Char('n') => {
+                    self.exit_popup();
+                    Ok(None)
+                }
+                _ => Ok(None),
+            },
+            Some(PopupMode::Input(action, mut input)) => {
+                match event.code {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    // Invalid input is not submitted, so that it can be fixed
+                    KeyCode::Enter if action.validate(&input).is_ok() => {
+                        self.exit_popup();
+                        return Ok(Some(PopupOutcome::Dialog(action, input)));
+                    }
+                    _ => {}
+                }
+                self.popup_mode = Some(PopupMode::Input(action, input));
+                Ok(None)
+            }
+            Some(PopupMode::Suggestions(suggestions, selected)) => {
+                let n = suggestions.len();
+                let selected = match event.code {
+                    KeyCode::Up | KeyCode::Char('k') if n > 0 => (selected + n - 1) % n,
+                    KeyCode::Down | KeyCode::Char('j') if n > 0 => (selected + 1) % n,
+                    KeyCode::Enter => {
+                        let step = suggestions
+                            .get(selected)
+                            .map(|(_, step)| *step)
+                            .ok_or_else(|| RecoverableError::new("No step is selected."))?;
+                        self.exit_popup();
+                        return Ok(Some(PopupOutcome::Dialog(
+                            DialogAction::GotoStep,
+                            step.to_string(),
+                        )));
+                    }
+                    _ => selected,
+                };
+                self.popup_mode = Some(PopupMode::Suggestions(suggestions, selected));
+                Ok(None)
+            }
+            _ => Ok(None),
         }
     }
 
-    fn handle_key_event_for_assignment(&mut self, event: KeyEvent, k: u8) -> Result<()> {
+    fn handle_key_event_for_view_picker(&mut self, event: KeyEvent, k: u8) -> Result<()> {
+        let n = PaneView::num_of_valid_views();
+        let selected = match event.code {
+            KeyCode::Char(c) if c.is_ascii_lowercase() && (c as u8 - b'a') < n => c as u8 - b'a',
+            KeyCode::Enter => k,
+            KeyCode::Up => {
+                self.popup_mode = Some(PopupMode::ViewPicker((k + n - 1) % n));
+                return Ok(());
+            }
+            KeyCode::Down => {
+                self.popup_mode = Some(PopupMode::ViewPicker((k + 1) % n));
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+
+        let view = PaneView::from(selected);
+        self.record(|screen| {
+            let target = screen.get_focused_pane()?.id;
+            screen.get_pane_manager_mut()?.set_view(target, view)
+        })
+        .map_err(|e| {
+            RecoverableError::new(format!(
+                "Failed to show the selected view ({})\n\nReason: {e}",
+                view.to_string()
+            ))
+        })?;
+        self.exit_popup();
+        Ok(())
+    }
+
+    fn handle_key_event_for_palette(
+        &mut self,
+        event: KeyEvent,
+        mut query: String,
+        mut selected: usize,
+    ) -> Result<Option<KeyEvent>> {
+        let entries = search_palette(&query, self.get_focused_view()?);
         match event.code {
-            KeyCode::Char(c) => match c {
-                '0'..='9' => self.handle_assignment(c as u8 - b'0')?,
-                'a'..='j' => self.handle_assignment(
-                    c as u8 - b'a' +
-                        (PaneView::num_of_valid_views() - self.get_focused_pane()?.len() as u8),
-                )?,
-                _ => {}
-            },
+            KeyCode::Char(c) => {
+                query.push(c);
+                selected = 0;
+            }
+            KeyCode::Backspace => {
+                query.pop();
+                selected = 0;
+            }
+            KeyCode::Up if !entries.is_empty() => {
+                selected = (selected + entries.len() - 1) % entries.len()
+            }
+            KeyCode::Down if !entries.is_empty() => selected = (selected + 1) % entries.len(),
+            KeyCode::Enter => {
+                let entry = entries
+                    .get(selected)
+                    .ok_or_else(|| RecoverableError::new("No command is selected."))?;
+                self.exit_popup();
+                return Ok(Some(entry.key));
+            }
+            _ => {}
+        }
+
+        self.popup_mode = Some(PopupMode::CommandPalette(query, selected));
+        Ok(None)
+    }
+
+    fn handle_key_event_for_assignment(
+        &mut self,
+        event: KeyEvent,
+        k: u8,
+        mut digits: String,
+    ) -> Result<()> {
+        let n = PaneView::num_of_valid_views();
+        let registered = self.get_focused_pane()?.len() as u8;
+        match event.code {
+            KeyCode::Char(c @ '0'..='9') => {
+                digits.push(c);
+                match assignment_number(&digits, n - registered) {
+                    Some((k, true)) => self.handle_assignment(k)?,
+                    Some((k, false)) => {
+                        self.popup_mode = Some(PopupMode::ViewAssignment(k, digits))
+                    }
+                    None => self.popup_mode = Some(PopupMode::ViewAssignment(k, String::new())),
+                }
+            }
+            KeyCode::Char(c) if c.is_ascii_lowercase() && c as u8 - b'a' < registered => {
+                self.handle_assignment(c as u8 - b'a' + (n - registered))?
+            }
+            KeyCode::Backspace => {
+                digits.pop();
+                let k = assignment_number(&digits, n - registered).map_or(k, |(k, _)| k);
+                self.popup_mode = Some(PopupMode::ViewAssignment(k, digits));
+            }
             KeyCode::Up => {
-                self.popup_mode = Some(PopupMode::ViewAssignment(
-                    (k + PaneView::num_of_valid_views() - 1) % PaneView::num_of_valid_views(),
-                ))
+                self.popup_mode = Some(PopupMode::ViewAssignment((k + n - 1) % n, String::new()))
             }
             KeyCode::Down => {
-                self.popup_mode =
-                    Some(PopupMode::ViewAssignment((k + 1) % PaneView::num_of_valid_views()))
+                self.popup_mode = Some(PopupMode::ViewAssignment((k + 1) % n, String::new()))
             }
             KeyCode::Enter => self.handle_assignment(k)?,
             _ => {}
@@ -179,3 +590,109 @@ impl Window<'_> {
         Err(RecoverableError::new("Invalid selection").into())
     }
 }
+
+/// Parses the digits typed in the view assignment into the number of a view to register, out of
+/// `count` ones. Returns the number and whether it is complete, i.e., no other number starts with
+/// it, so that views past the tenth are reached by typing several digits.
+fn assignment_number(digits: &str, count: u8) -> Option<(u8, bool)> {
+    let k = digits.parse::<u8>().ok().filter(|k| *k < count)?;
+    let complete = k == 0 || usize::from(k) * 10 >= usize::from(count);
+    Some((k, complete))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+
+    fn key(window: &mut Window<'_>, code: KeyCode) -> Option<PopupOutcome> {
+        window.handle_key_event_in_popup(KeyEvent::new(code, KeyModifiers::NONE)).unwrap()
+    }
+
+    #[test]
+    fn test_confirmation_dialog() {
+        let mut window = Window::new().unwrap();
+        window.pop_confirmation(DialogAction::Quit);
+        // other keys are captured, but ignored
+        assert!(key(&mut window, KeyCode::Char('x')).is_none());
+        assert!(window.has_popup());
+        assert!(key(&mut window, KeyCode::Char('n')).is_none());
+        assert!(!window.has_popup());
+
+        window.pop_confirmation(DialogAction::Quit);
+        let outcome = key(&mut window, KeyCode::Enter);
+        assert!(
+            matches!(outcome, Some(PopupOutcome::Dialog(DialogAction::Quit, input)) if input.is_empty())
+        );
+        assert!(!window.has_popup());
+    }
+
+    #[test]
+    fn test_input_dialog() {
+        let mut window = Window::new().unwrap();
+        window.pop_input(DialogAction::GotoStep);
+        for c in "12a".chars() {
+            assert!(key(&mut window, KeyCode::Char(c)).is_none());
+        }
+        // invalid input is kept to be fixed
+        assert!(key(&mut window, KeyCode::Enter).is_none());
+        assert!(matches!(&window.popup_mode, Some(PopupMode::Input(_, input)) if input == "12a"));
+
+        key(&mut window, KeyCode::Backspace);
+        window.paste_into_popup("_3\n");
+        let outcome = key(&mut window, KeyCode::Enter);
+        assert!(
+            matches!(outcome, Some(PopupOutcome::Dialog(DialogAction::GotoStep, input)) if input == "123")
+        );
+        assert!(!window.has_popup());
+    }
+
+    #[test]
+    fn test_assignment_number() {
+        assert_eq!(assignment_number("3", 9), Some((3, true)));
+        assert_eq!(assignment_number("1", 15), Some((1, false)));
+        assert_eq!(assignment_number("12", 15), Some((12, true)));
+        assert_eq!(assignment_number("2", 15), Some((2, true)));
+        assert_eq!(assignment_number("0", 15), Some((0, true)));
+        assert_eq!(assignment_number("16", 15), None);
+        assert_eq!(assignment_number("", 15), None);
+    }
+
+    #[test]
+    fn test_clean_pasted_input() {
+        let action = DialogAction::GotoCall;
+        let input = action.clean(" \"0xdAC17F958D2ee523a2206206994597C13D831ec7\"\n");
+        assert_eq!(input, "0xdAC17F958D2ee523a2206206994597C13D831ec7");
+        assert!(action.validate(&input).is_ok());
+        assert!(action.validate("0xdAC17F958D2ee523").is_err());
+
+        let action = DialogAction::GotoStep;
+        assert_eq!(action.clean("1_000\n"), "1000");
+
+        let action = DialogAction::WatchSlot;
+        let input = action.clean("0xdAC17F958D2ee523a2206206994597C13D831ec7: 0x5\n");
+        assert_eq!(parse_slot(&input).unwrap().1, U256::from(5));
+        assert!(action.validate("0x5").is_ok());
+        assert!(action.validate("0x12:0x5").is_err());
+        assert!(action.validate("1000").is_ok());
+        assert!(action.validate("0x10").is_err());
+
+        let action = DialogAction::RunToLine;
+        assert_eq!(action.clean("'src/Token.sol:42'"), "src/Token.sol:42");
+        assert!(action.validate("src/Token.sol:42").is_ok());
+        assert!(action.validate(":42").is_err());
+
+        let action = DialogAction::Create2;
+        let input = action.clean("0x4e59b44847b379578588920cA78FbF26c0B4956C\n 0x1 0x00");
+        assert!(
+            matches!(parse_create2(&input), Ok((_, salt, InitCode::Code(_))) if salt == B256::with_last_byte(1))
+        );
+        assert!(action.validate("0x4e59b44847b379578588920cA78FbF26c0B4956C 0x1").is_err());
+
+        let deployer = "0xb20a608c624Ca5003905aA834De7156C68b2E1d0";
+        assert_eq!(parse_deployer(deployer).unwrap().1, None);
+        assert_eq!(parse_deployer(&format!("{deployer} 0x10")).unwrap().1, Some(16));
+        assert!(DialogAction::PredictCreate.validate(&format!("{deployer} -1")).is_err());
+    }
+}
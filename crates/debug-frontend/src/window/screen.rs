@@ -1,18 +1,28 @@
 use std::collections::HashMap;
 
-use eyre::{ensure, Result};
-use ratatui::layout::{Direction, Rect};
+use eyre::Result;
+use ratatui::layout::{Direction, Margin, Rect};
 
 use crate::{
     context::RecoverableError,
     window::pane::{self, Pane, PaneFlattened, PaneManager, PaneView},
 };
 
-use super::pane::{BorderSide, PaneId};
+use super::pane::BorderSide;
 
 pub const SMALL_SCREEN_STR: &str = "Defualt (Small Screen)";
 pub const LARGE_SCREEN_STR: &str = "Defualt (Large Screen)";
 
+/// The maximum number of layout operations which can be undone.
+const MAX_LAYOUT_HISTORY: usize = 64;
+
+/// The layout of a pane profile before an operation, which can be restored.
+#[derive(Debug, Clone)]
+struct LayoutSnapshot {
+    profile: String,
+    manager: PaneManager,
+}
+
 /// A fully abstracted screen manager that manages the layout of the screen.
 /// The screen manager is not aware of the actual terminal size, but it is aware of the layout of
 /// the screen.
@@ -21,6 +31,15 @@ pub struct ScreenManager {
     pub current_pane: String,
     pub use_default_pane: bool,
     pub full_screen: bool,
+    /// Whether the focused pane is shown as an overlay above the layout. Unlike the full screen
+    /// mode, the other panes stay visible (and clickable) around the overlay.
+    pub zoomed: bool,
+
+    undo_stack: Vec<LayoutSnapshot>,
+    redo_stack: Vec<LayoutSnapshot>,
+    /// Whether a layout operation is being recorded, so that nested operations are recorded as
+    /// a whole.
+    recording: bool,
 }
 
 impl ScreenManager {
@@ -29,7 +48,11 @@ impl ScreenManager {
             panes: HashMap::new(),
             current_pane: String::new(),
             full_screen: false,
+            zoomed: false,
             use_default_pane: true,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            recording: false,
         };
 
         manager.add_pane_manager(SMALL_SCREEN_STR, PaneManager::default_small_screen()?);
@@ -66,6 +89,16 @@ impl ScreenManager {
         self.full_screen = !self.full_screen;
     }
 
+    pub fn toggle_zoom(&mut self) {
+        self.zoomed = !self.zoomed;
+    }
+
+    /// Returns the area of the zoomed pane, which leaves a margin of the layout visible.
+    pub fn zoomed_rect(app: Rect) -> Rect {
+        let margin = Margin::new((app.width / 20).max(1), (app.height / 20).max(1));
+        app.inner(margin)
+    }
+
     pub fn get_pane_manager(&self) -> Result<&PaneManager> {
         self.panes.get(&self.current_pane).ok_or(eyre::eyre!("No current pane"))
     }
@@ -113,76 +146,74 @@ impl ScreenManager {
         self.get_pane_manager_mut()?.focus_right()
     }
 
-    pub fn split_focused_pane(&mut self, direction: Direction, ratio: [u32; 2]) -> Result<()> {
-        let id = self.get_focused_pane()?.id;
-        self.get_pane_manager_mut()?.split(id, direction, ratio)?;
-        Ok(())
-    }
+    /// Runs a layout operation, recording the layout before it in the undo history if the
+    /// operation changes the layout (even if it fails halfway).
+    pub fn record<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.recording {
+            return f(self);
+        }
 
-    fn merge_pane_with_neighbor(&mut self, id: PaneId, direction: BorderSide) -> Result<()> {
-        match direction {
-            BorderSide::Left => {
-                self.focus_left()?;
-                let new_id = self.get_focused_pane()?.id;
-                if self.get_pane_manager_mut()?.merge(id, new_id).is_ok() {
-                    return Ok(());
-                }
-                self.focus_right()?;
-            }
-            BorderSide::Right => {
-                self.focus_right()?;
-                let new_id = self.get_focused_pane()?.id;
-                if self.get_pane_manager_mut()?.merge(id, new_id).is_ok() {
-                    return Ok(());
-                }
-                self.focus_left()?;
-            }
-            BorderSide::Top => {
-                self.focus_up()?;
-                let new_id = self.get_focused_pane()?.id;
-                if self.get_pane_manager_mut()?.merge(id, new_id).is_ok() {
-                    return Ok(());
-                }
-                self.focus_down()?;
-            }
-            BorderSide::Bottom => {
-                self.focus_down()?;
-                let new_id = self.get_focused_pane()?.id;
-                if self.get_pane_manager_mut()?.merge(id, new_id).is_ok() {
-                    return Ok(());
-                }
-                self.focus_up()?;
+        let before = LayoutSnapshot {
+            profile: self.current_pane.clone(),
+            manager: self.get_pane_manager()?.clone(),
+        };
+        self.recording = true;
+        let result = f(self);
+        self.recording = false;
+
+        if self.panes.get(&before.profile) != Some(&before.manager) {
+            if self.undo_stack.len() == MAX_LAYOUT_HISTORY {
+                self.undo_stack.remove(0);
             }
+            self.undo_stack.push(before);
+            self.redo_stack.clear();
         }
+        result
+    }
+
+    /// Reverts the latest layout operation.
+    pub fn undo_layout(&mut self) -> Result<()> {
+        let snapshot = self
+            .undo_stack
+            .pop()
+            .ok_or_else(|| RecoverableError::new("There is no layout operation to undo."))?;
+        let current = self.restore_layout(snapshot)?;
+        self.redo_stack.push(current);
+        Ok(())
+    }
 
-        Err(eyre::eyre!("Cannot merge the pane with its neighbor"))
+    /// Re-applies the latest reverted layout operation.
+    pub fn redo_layout(&mut self) -> Result<()> {
+        let snapshot = self
+            .redo_stack
+            .pop()
+            .ok_or_else(|| RecoverableError::new("There is no layout operation to redo."))?;
+        let current = self.restore_layout(snapshot)?;
+        self.undo_stack.push(current);
+        Ok(())
     }
 
-    pub fn close_focused_pane(&mut self) -> Result<()> {
-        let pane = self.get_focused_pane()?;
-        let id = pane.id;
-
-        // let's try to merge the pane with its neighbor.
-        // we may want to first marge the latest split pane.
-        let mut indexed_values: Vec<(usize, i32)> = self
-            .get_pane_manager()?
-            .get_borders(id)?
-            .iter()
-            .map(|x| x.map(|y| y as i32).unwrap_or(-1))
-            .enumerate()
-            .collect();
-        indexed_values.sort_by(|a, b| b.1.cmp(&a.1));
-        for (direction, v) in indexed_values {
-            if v < 0 {
-                break;
-            }
-            if self.merge_pane_with_neighbor(id, BorderSide::try_from(direction)?).is_ok() {
-                return Ok(());
-            }
-        }
+    /// Restores the given layout, returning the replaced one.
+    fn restore_layout(&mut self, snapshot: LayoutSnapshot) -> Result<LayoutSnapshot> {
+        let manager = self.panes.get_mut(&snapshot.profile).ok_or(eyre::eyre!("No such pane"))?;
+        let previous = std::mem::replace(manager, snapshot.manager);
+        self.current_pane = snapshot.profile.clone();
+        Ok(LayoutSnapshot { profile: snapshot.profile, manager: previous })
+    }
+
+    pub fn split_focused_pane(&mut self, direction: Direction, ratio: [u32; 2]) -> Result<()> {
+        self.record(|this| {
+            let id = this.get_focused_pane()?.id;
+            this.get_pane_manager_mut()?.split(id, direction, ratio)?;
+            Ok(())
+        })
+    }
 
-        ensure!(self.get_focused_pane()?.id == id, "cannot move back to the original pane");
-        Err(RecoverableError::new("The current pane cannot be merged with others due to one of the following reasons:\n\n1. The current pane cannot be merged with any adjacent panes.\n\n2. The current pane contains valid debug views but can only be merged with a Terminal Pane.\n\n3. The current pane has an adjacent pane, but these two are not directly split from the same parent pane.\n\nTo close this pane, you may consider unregistering some views or closing other panes first.").into())
+    pub fn close_focused_pane(&mut self) -> Result<()> {
+        self.record(|this| {
+            let id = this.get_focused_pane()?.id;
+            this.get_pane_manager_mut()?.close(id)
+        })
     }
 
     pub fn get_flattened_layout<'a>(&'a self, app: Rect) -> Result<Vec<PaneFlattened<'a>>> {
@@ -193,8 +224,22 @@ impl ScreenManager {
                 views: pane.get_views(),
                 id: pane.id,
                 focused: true,
+                follow: pane.follow,
+                overlay: false,
                 rect: app,
             }])
+        } else if self.zoomed {
+            // The focused pane is drawn twice, the latter of which is the overlay
+            let mut layout = self.get_pane_manager()?.get_flattened_layout(app)?;
+            let mut zoomed = layout
+                .iter()
+                .find(|pane| pane.focused)
+                .cloned()
+                .ok_or_else(|| eyre::eyre!("no focused pane"))?;
+            zoomed.rect = Self::zoomed_rect(app);
+            zoomed.overlay = true;
+            layout.push(zoomed);
+            Ok(layout)
         } else {
             Ok(self.get_pane_manager()?.get_flattened_layout(app)?)
         }
@@ -260,3 +305,48 @@ impl ScreenManager {
         Err(RecoverableError::new("The current pane cannot be scale to its top side.").into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo_layout() {
+        let mut screen = ScreenManager::new().unwrap();
+        assert!(screen.undo_layout().is_err());
+
+        let before = screen.get_pane_manager().unwrap().clone();
+        screen.split_focused_pane(Direction::Horizontal, [1, 1]).unwrap();
+        let after = screen.get_pane_manager().unwrap().clone();
+        assert_ne!(before, after);
+
+        // operations leaving the layout unchanged are not recorded
+        screen.record(|_| Ok(())).unwrap();
+
+        screen.undo_layout().unwrap();
+        assert_eq!(screen.get_pane_manager().unwrap(), &before);
+        assert!(screen.undo_layout().is_err());
+        screen.redo_layout().unwrap();
+        assert_eq!(screen.get_pane_manager().unwrap(), &after);
+
+        // a new operation discards the reverted ones
+        screen.undo_layout().unwrap();
+        screen.close_focused_pane().unwrap();
+        assert!(screen.redo_layout().is_err());
+        screen.undo_layout().unwrap();
+        assert_eq!(screen.get_pane_manager().unwrap(), &before);
+    }
+
+    #[test]
+    fn test_undo_across_profiles() {
+        let mut screen = ScreenManager::new().unwrap();
+        let small = screen.get_pane_manager().unwrap().clone();
+        screen.close_focused_pane().unwrap();
+
+        // the operation is reverted in its own profile, which is brought back
+        screen.set_large_screen();
+        screen.undo_layout().unwrap();
+        assert_eq!(screen.current_pane, SMALL_SCREEN_STR);
+        assert_eq!(screen.get_pane_manager().unwrap(), &small);
+    }
+}
@@ -1,5 +1,21 @@
-use crate::cmd::{replay::ReplayArgs, script::ScriptArgs, test::TestArgs};
+use crate::cmd::{
+    call::CallArgs,
+    completions::{CompleteArgs, CompletionsArgs},
+    explain::ExplainArgs,
+    export_test::ExportTestArgs,
+    proxy::ProxyArgs,
+    replay::ReplayArgs,
+    scan::ScanArgs,
+    script::ScriptArgs,
+    serve::ServeArgs,
+    signatures::CheckSignaturesArgs,
+    test::TestArgs,
+    trace::TraceArgs,
+    update::UpdateArgs,
+    verify_submit::VerifySubmitArgs,
+};
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 const VERSION_MESSAGE: &str = concat!(
     env!("CARGO_PKG_VERSION"),
@@ -21,6 +37,25 @@ const VERSION_MESSAGE: &str = concat!(
 pub struct EDBArgs {
     #[command(subcommand)]
     pub cmd: EDBSubcommand,
+
+    /// Disables checking for a newer release of edb on startup.
+    #[arg(long, global = true, env = "EDB_NO_UPDATE_CHECK")]
+    pub no_update_check: bool,
+
+    /// Measures where the time of the session goes (RPC fetches, compilation, execution,
+    /// rendering, ...), and prints a summary on exit.
+    #[arg(long, global = true)]
+    pub profile: bool,
+
+    /// Records the RPC and explorer responses of the session under the given directory, so that
+    /// it can be reproduced offline with `--replay-fixtures`.
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "replay_fixtures")]
+    pub record_fixtures: Option<PathBuf>,
+
+    /// Replays the RPC and explorer responses recorded with `--record-fixtures`, without any
+    /// network access, e.g., for deterministic reproductions and integration tests.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub replay_fixtures: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -30,6 +65,16 @@ pub enum EDBSubcommand {
     #[command(visible_alias = "r")]
     Replay(ReplayArgs),
 
+    /// Replay an on-chain transaction and export its trace, without opening the debugger.
+    Trace(TraceArgs),
+
+    /// Analyze many on-chain transactions in parallel and summarize their outcome, e.g., to
+    /// triage suspicious transactions after an incident.
+    Scan(ScanArgs),
+
+    /// Generate a Foundry test which reproduces an on-chain transaction on a fork.
+    ExportTest(ExportTestArgs),
+
     /// Debug a script.
     #[command(visible_alias = "s")]
     Script(ScriptArgs),
@@ -37,6 +82,40 @@ pub enum EDBSubcommand {
     /// Debug a test case.
     #[command(visible_alias = "t")]
     Test(TestArgs),
+
+    /// Debug a hypothetical call, whose calldata can be built interactively from the ABI of the
+    /// callee.
+    #[command(visible_alias = "c")]
+    Call(CallArgs),
+
+    /// Run a JSON-RPC proxy which debugs transactions instead of broadcasting them.
+    #[command(visible_alias = "p")]
+    Proxy(ProxyArgs),
+
+    /// Replay an on-chain transaction and serve a browser-based debugger for it, which any
+    /// number of browsers can follow.
+    Serve(ServeArgs),
+
+    /// Verify the sources of a contract deployed from a local Foundry project on Etherscan or
+    /// Sourcify, once they are checked to reproduce its code.
+    VerifySubmit(VerifySubmitArgs),
+
+    /// Replay an on-chain transaction and check the signatures it verifies with `ecrecover`, e.g.,
+    /// of EIP-2612 permits, recomputing their digests and signers and explaining mismatches.
+    CheckSignatures(CheckSignaturesArgs),
+
+    /// Explain the semantics, gas cost and stack effects of an EVM opcode.
+    Explain(ExplainArgs),
+
+    /// Update edb to the latest release, or check whether a newer release is available.
+    Update(UpdateArgs),
+
+    /// Generate shell completions.
+    Completions(CompletionsArgs),
+
+    /// Print the values completed at runtime by the shell completions.
+    #[command(name = "__complete", hide = true)]
+    Complete(CompleteArgs),
 }
 
 #[cfg(test)]
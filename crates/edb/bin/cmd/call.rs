@@ -0,0 +1,112 @@
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types::{TransactionInput, TransactionRequest};
+use clap::Parser;
+use edb_debug_backend::artifact::debug::DebugArtifact;
+use eyre::{ensure, eyre, Result};
+use foundry_evm::fork::database::ForkedDatabase;
+use revm::primitives::EnvWithHandlerCfg;
+
+use crate::{
+    opts::{EtherscanOpts, EvmOpts, RpcOpts, UiOpts},
+    utils::{
+        calldata::{build_interactively, encode_call},
+        evm::{fill_tx_env_with_request, setup_block_env, setup_fork_db},
+        rerun::debug_with_reruns,
+    },
+};
+
+/// CLI arguments for `edb call`.
+#[derive(Clone, Debug, Parser)]
+pub struct CallArgs {
+    /// The address of the callee.
+    pub to: Address,
+
+    /// The signature of the function to call, e.g. `transfer(address,uint256)`.
+    ///
+    /// If neither the signature nor the calldata is given, the calldata is built interactively
+    /// from the ABI of the callee, as verified on the block explorer.
+    #[arg(conflicts_with = "data")]
+    pub sig: Option<String>,
+
+    /// The arguments of the function.
+    #[arg(requires = "sig")]
+    pub args: Vec<String>,
+
+    /// The raw calldata.
+    #[arg(long, short)]
+    pub data: Option<Bytes>,
+
+    /// The sender of the call.
+    #[arg(long, default_value_t = Address::ZERO)]
+    pub from: Address,
+
+    /// The value sent with the call, in wei.
+    #[arg(long, default_value_t = U256::ZERO)]
+    pub value: U256,
+
+    /// The block to make the call on top of. Defaults to the latest block.
+    #[arg(long, short, value_name = "BLOCK")]
+    pub block: Option<u64>,
+
+    #[command(flatten)]
+    pub etherscan: EtherscanOpts,
+
+    #[command(flatten)]
+    pub rpc: RpcOpts,
+
+    #[command(flatten)]
+    pub evm: EvmOpts,
+
+    #[command(flatten)]
+    pub ui: UiOpts,
+}
+
+impl CallArgs {
+    pub async fn run(self) -> Result<()> {
+        let fork_url = self.rpc.url(true)?.unwrap().to_string();
+        let provider = self.rpc.provider()?;
+        let chain_id = provider.get_chain_id().await?;
+        ensure!(
+            self.etherscan.chain.map_or(true, |chain| chain.id() == chain_id),
+            "inconsistent chain id"
+        );
+        let block_number = match self.block {
+            Some(bn) => bn,
+            None => provider.get_block_number().await?,
+        };
+
+        let calldata = match (&self.data, &self.sig) {
+            (Some(data), _) => data.clone(),
+            (None, Some(sig)) => encode_call(sig, &self.args)?,
+            (None, None) => {
+                let abi = self.etherscan.client()?.contract_abi(self.to).await.map_err(|e| {
+                    eyre!("cannot fetch the ABI of {}, please pass the calldata ({e})", self.to)
+                })?;
+                build_interactively(&abi)?
+            }
+        };
+
+        let db = setup_fork_db(provider.clone(), &fork_url, Some(block_number), None).await?;
+        let mut env = setup_block_env(provider, Some(block_number), self.evm.spec_id()).await?;
+        // like `eth_call`, the call is free so that any sender can make it
+        env.block.basefee = U256::ZERO;
+        let request = TransactionRequest {
+            from: Some(self.from),
+            to: Some(self.to.into()),
+            value: Some(self.value),
+            input: TransactionInput::new(calldata),
+            ..Default::default()
+        };
+        fill_tx_env_with_request(&mut env, &request);
+
+        let debug_artifact = self.analyze(&db, env.clone()).await?;
+        debug_with_reruns(&self.ui, debug_artifact, env, |env| self.analyze(&db, env)).await
+    }
+
+    async fn analyze(&self, db: &ForkedDatabase, env: EnvWithHandlerCfg) -> Result<DebugArtifact> {
+        let builder = self.ui.configure(self.evm.configure(self.etherscan.backend_builder()?));
+        let backend = builder.build::<ForkedDatabase>(db, env)?;
+        backend.analyze().await
+    }
+}
@@ -0,0 +1,89 @@
+use clap::{CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use eyre::Result;
+
+use crate::{args::EDBArgs, utils::history};
+
+/// CLI arguments for `edb completions`.
+#[derive(Clone, Debug, Parser)]
+pub struct CompletionsArgs {
+    /// The shell to generate the completions for.
+    pub shell: Shell,
+}
+
+impl CompletionsArgs {
+    pub fn run(self) -> Result<()> {
+        let mut cmd = EDBArgs::command();
+        let mut out = std::io::stdout();
+        clap_complete::generate(self.shell, &mut cmd, "edb", &mut out);
+        if let Some(script) = dynamic_completion_script(self.shell) {
+            print!("{script}");
+        }
+        Ok(())
+    }
+}
+
+/// The values completed at runtime by `edb __complete`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum CompletionKind {
+    /// The hashes of recently replayed transactions.
+    TxHashes,
+}
+
+/// CLI arguments for `edb __complete`, which the generated completion scripts call.
+#[derive(Clone, Debug, Parser)]
+pub struct CompleteArgs {
+    pub kind: CompletionKind,
+}
+
+impl CompleteArgs {
+    pub fn run(self) -> Result<()> {
+        match self.kind {
+            CompletionKind::TxHashes => {
+                history::recent_txs().iter().for_each(|hash| println!("{hash}"))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the script which completes the transaction hash of `edb replay` and `edb trace` from
+/// the replay history, wrapping the static completion generated by clap.
+fn dynamic_completion_script(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(
+            r#"
+_edb_dynamic() {
+    case "${COMP_WORDS[1]}" in
+        replay|r|trace)
+            if [[ "${COMP_WORDS[COMP_CWORD]}" == 0x* ]]; then
+                COMPREPLY=($(compgen -W "$(edb __complete tx-hashes 2>/dev/null)" -- "${COMP_WORDS[COMP_CWORD]}"))
+                return 0
+            fi
+            ;;
+    esac
+    _edb "$@"
+}
+complete -F _edb_dynamic -o nosort -o bashdefault -o default edb
+"#,
+        ),
+        Shell::Zsh => Some(
+            r#"
+_edb_dynamic() {
+    if [[ "${words[2]}" == (replay|r|trace) && "$PREFIX" == 0x* ]]; then
+        compadd -- ${(f)"$(edb __complete tx-hashes 2>/dev/null)"}
+    else
+        _edb "$@"
+    fi
+}
+compdef _edb_dynamic edb
+"#,
+        ),
+        Shell::Fish => Some(
+            r#"
+complete -c edb -n "__fish_seen_subcommand_from replay r trace" -f -a "(edb __complete tx-hashes 2>/dev/null)"
+"#,
+        ),
+        _ => None,
+    }
+}
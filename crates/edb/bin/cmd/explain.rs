@@ -0,0 +1,19 @@
+use clap::Parser;
+use edb_debug_backend::reference::OpcodeDoc;
+use eyre::{eyre, Result};
+
+/// CLI arguments for `edb explain`.
+#[derive(Clone, Debug, Parser)]
+pub struct ExplainArgs {
+    /// The opcode to explain, either its mnemonic (e.g. `SSTORE`) or its value (e.g. `0x55`).
+    pub opcode: String,
+}
+
+impl ExplainArgs {
+    pub fn run(self) -> Result<()> {
+        let doc = OpcodeDoc::find(&self.opcode)
+            .ok_or_else(|| eyre!("unknown opcode: {}", self.opcode))?;
+        println!("{doc}");
+        Ok(())
+    }
+}
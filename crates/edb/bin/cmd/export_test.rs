@@ -0,0 +1,187 @@
+use std::{fmt::Write as _, path::PathBuf};
+
+use alloy_primitives::{hex, Address, Bytes, TxHash, U256};
+use alloy_provider::Provider;
+use clap::Parser;
+use eyre::{eyre, Result};
+
+use crate::opts::RpcOpts;
+
+/// CLI arguments for `edb export-test`.
+#[derive(Clone, Debug, Parser)]
+pub struct ExportTestArgs {
+    /// The hash of the transaction to reproduce.
+    pub tx_hash: TxHash,
+
+    /// The path of the generated test file.
+    ///
+    /// Defaults to `test/Replay_<hash prefix>.t.sol`.
+    #[arg(long, short, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub rpc: RpcOpts,
+}
+
+/// What the generated test reproduces and asserts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Reproduction {
+    tx_hash: TxHash,
+    from: Address,
+    /// The callee, or `None` for contract creations.
+    to: Option<Address>,
+    value: U256,
+    input: Bytes,
+    gas: u128,
+    success: bool,
+    num_logs: usize,
+}
+
+impl ExportTestArgs {
+    pub async fn run(self) -> Result<()> {
+        let provider = self.rpc.provider()?;
+        let tx = provider
+            .get_transaction_by_hash(self.tx_hash)
+            .await?
+            .ok_or(eyre!("transaction not found"))?;
+        let receipt = provider
+            .get_transaction_receipt(self.tx_hash)
+            .await?
+            .ok_or(eyre!("transaction receipt not found, it may still be pending"))?;
+
+        let reproduction = Reproduction {
+            tx_hash: self.tx_hash,
+            from: tx.from,
+            to: tx.to,
+            value: tx.value,
+            input: tx.input,
+            gas: tx.gas,
+            success: receipt.inner.inner.status(),
+            num_logs: receipt.inner.inner.logs().len(),
+        };
+
+        let out = self.out.unwrap_or_else(|| {
+            PathBuf::from("test").join(format!("{}.t.sol", reproduction.contract_name()))
+        });
+        if let Some(parent) = out.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&out, reproduction.render())?;
+        println!("Foundry test written to {}", out.display());
+        println!("Run it with: ETH_RPC_URL=<url> forge test --match-path {}", out.display());
+        Ok(())
+    }
+}
+
+impl Reproduction {
+    fn contract_name(&self) -> String {
+        format!("Replay_{}", &self.tx_hash.to_string()[2..10])
+    }
+
+    /// Renders the Foundry test, which forks right before the transaction (i.e., after the
+    /// preceding transactions of its block) and replays it from the original sender.
+    fn render(&self) -> String {
+        let mut test = String::new();
+        let name = self.contract_name();
+        writeln!(test, "// SPDX-License-Identifier: UNLICENSED").unwrap();
+        writeln!(test, "pragma solidity ^0.8.13;").unwrap();
+        writeln!(test).unwrap();
+        writeln!(test, "import {{Test, Vm}} from \"forge-std/Test.sol\";").unwrap();
+        writeln!(test).unwrap();
+        writeln!(test, "/// Reproduction of transaction {}, generated by edb.", self.tx_hash)
+            .unwrap();
+        writeln!(test, "contract {name} is Test {{").unwrap();
+        writeln!(test, "    function setUp() public {{").unwrap();
+        writeln!(
+            test,
+            "        vm.createSelectFork(vm.envString(\"ETH_RPC_URL\"), bytes32({}));",
+            self.tx_hash
+        )
+        .unwrap();
+        writeln!(test, "    }}").unwrap();
+        writeln!(test).unwrap();
+        writeln!(test, "    function test_replay() public {{").unwrap();
+        writeln!(test, "        address sender = {};", self.from.to_checksum(None)).unwrap();
+        writeln!(test, "        bytes memory data = hex\"{}\";", hex::encode(&self.input)).unwrap();
+        writeln!(test).unwrap();
+        writeln!(test, "        vm.recordLogs();").unwrap();
+        writeln!(test, "        vm.prank(sender, sender);").unwrap();
+        match self.to {
+            Some(to) => {
+                writeln!(
+                    test,
+                    "        (bool success,) = address({}).call{{value: {}, gas: {}}}(data);",
+                    to.to_checksum(None),
+                    self.value,
+                    self.gas
+                )
+                .unwrap();
+            }
+            None => {
+                writeln!(test, "        address deployed;").unwrap();
+                writeln!(test, "        uint256 value = {};", self.value).unwrap();
+                writeln!(test, "        assembly {{").unwrap();
+                writeln!(
+                    test,
+                    "            deployed := create(value, add(data, 0x20), mload(data))"
+                )
+                .unwrap();
+                writeln!(test, "        }}").unwrap();
+                writeln!(test, "        bool success = deployed != address(0);").unwrap();
+            }
+        }
+        writeln!(test).unwrap();
+        if self.success {
+            writeln!(test, "        assertTrue(success, \"the transaction succeeded on chain\");")
+                .unwrap();
+            writeln!(test, "        Vm.Log[] memory logs = vm.getRecordedLogs();").unwrap();
+            writeln!(
+                test,
+                "        assertEq(logs.length, {}, \"number of logs emitted on chain\");",
+                self.num_logs
+            )
+            .unwrap();
+        } else {
+            writeln!(test, "        assertFalse(success, \"the transaction reverted on chain\");")
+                .unwrap();
+        }
+        writeln!(test, "    }}").unwrap();
+        writeln!(test, "}}").unwrap();
+        test
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, b256, bytes};
+
+    #[test]
+    fn test_render_reproduction() {
+        let reproduction = Reproduction {
+            tx_hash: b256!("c445aa4b0f7e3fcb2b6b0d8cd2ab4dfd4cfa2c5d8eb4e5b1d2c7b8e1f2a3b4c5"),
+            from: address!("00000000000000000000000000000000000000aa"),
+            to: Some(address!("dAC17F958D2ee523a2206206994597C13D831ec7")),
+            value: U256::ZERO,
+            input: bytes!("a9059cbb"),
+            gas: 100000,
+            success: true,
+            num_logs: 1,
+        };
+        let test = reproduction.render();
+        assert!(test.contains("contract Replay_c445aa4b is Test"));
+        assert!(test.contains(
+            "bytes32(0xc445aa4b0f7e3fcb2b6b0d8cd2ab4dfd4cfa2c5d8eb4e5b1d2c7b8e1f2a3b4c5)"
+        ));
+        assert!(test.contains(
+            "address(0xdAC17F958D2ee523a2206206994597C13D831ec7).call{value: 0, gas: 100000}(data)"
+        ));
+        assert!(test.contains("hex\"a9059cbb\""));
+        assert!(test.contains("assertEq(logs.length, 1"));
+
+        let reverted = Reproduction { to: None, success: false, ..reproduction };
+        let test = reverted.render();
+        assert!(test.contains("create(value, add(data, 0x20), mload(data))"));
+        assert!(test.contains("assertFalse(success"));
+    }
+}
@@ -1,3 +1,14 @@
+pub mod call;
+pub mod completions;
+pub mod explain;
+pub mod export_test;
+pub mod proxy;
 pub mod replay;
+pub mod scan;
 pub mod script;
+pub mod serve;
+pub mod signatures;
 pub mod test;
+pub mod trace;
+pub mod update;
+pub mod verify_submit;
@@ -0,0 +1,575 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use alloy_consensus::{Transaction as _, TxEnvelope};
+use alloy_eips::eip2718::Decodable2718;
+use alloy_primitives::{keccak256, Bytes, TxHash, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types::{BlockNumberOrTag, TransactionInput, TransactionRequest};
+use clap::Parser;
+use edb_debug_frontend::{BlackboxFile, DebugFrontend, MachineInterface};
+use edb_utils::cache::CachePath;
+use eyre::{bail, ensure, eyre, Result};
+use foundry_common::provider::RetryProvider;
+use foundry_evm::{fork::database::ForkedDatabase, utils::new_evm_with_inspector};
+use revm::{
+    inspectors::NoOpInspector,
+    primitives::{EnvWithHandlerCfg, ExecutionResult, Output},
+};
+use serde_json::{json, Value};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot},
+};
+
+use crate::{
+    opts::{EtherscanOpts, EvmOpts, RpcOpts, UiOpts},
+    utils::{
+        evm::{fill_tx_env_with_request, set_pending_block_env, setup_block_env, setup_fork_db},
+        http,
+    },
+};
+
+/// CLI arguments for `edb proxy`.
+#[derive(Clone, Debug, Parser)]
+pub struct ProxyArgs {
+    /// The address the JSON-RPC proxy listens on.
+    #[arg(long, short, value_name = "ADDR", default_value = "127.0.0.1:8546")]
+    pub listen: SocketAddr,
+
+    /// The origin of the web pages allowed to call the proxy from a browser, e.g.,
+    /// `https://app.example.com`. Pages served from the local machine are always allowed.
+    #[arg(long, value_name = "ORIGIN")]
+    pub allow_origin: Option<String>,
+
+    #[command(flatten)]
+    pub etherscan: EtherscanOpts,
+
+    #[command(flatten)]
+    pub rpc: RpcOpts,
+
+    #[command(flatten)]
+    pub evm: EvmOpts,
+
+    #[command(flatten)]
+    pub ui: UiOpts,
+}
+
+/// A transaction intercepted by the proxy, waiting for a debugging session.
+struct InterceptedTx {
+    hash: TxHash,
+    request: TransactionRequest,
+    reply: oneshot::Sender<Result<SimulatedTx>>,
+}
+
+/// A simulated transaction, in the shapes returned by `eth_getTransactionByHash` and
+/// `eth_getTransactionReceipt`.
+#[derive(Clone, Debug)]
+struct SimulatedTx {
+    transaction: Value,
+    receipt: Value,
+}
+
+/// State shared by all connections of the proxy.
+#[derive(Clone)]
+struct ProxyState {
+    provider: Arc<RetryProvider>,
+    sessions: mpsc::UnboundedSender<InterceptedTx>,
+    /// The simulated transactions, so that wallets looking them up get an answer, since they
+    /// were never sent upstream.
+    simulated: Arc<Mutex<HashMap<TxHash, SimulatedTx>>>,
+    /// The origin allowed to call the proxy from a browser, besides the local pages.
+    allow_origin: Option<String>,
+}
+
+impl ProxyArgs {
+    pub async fn run(self) -> Result<()> {
+        ensure!(
+            self.ui.follow.is_none(),
+            "the sessions of the proxy cannot follow another session"
+        );
+        let fork_url = self.rpc.url(true)?.unwrap().to_string();
+        let provider = self.rpc.provider()?;
+        let chain_id = provider.get_chain_id().await?;
+        ensure!(
+            self.etherscan.chain.map_or(true, |chain| chain.id() == chain_id),
+            "inconsistent chain id"
+        );
+
+        let listener = TcpListener::bind(self.listen).await?;
+        println!("EDB proxy listening on http://{}, forwarding to {fork_url}", self.listen);
+
+        let (sessions, mut intercepted) = mpsc::unbounded_channel();
+        let state = ProxyState {
+            provider: provider.clone(),
+            sessions,
+            simulated: Arc::default(),
+            allow_origin: self.allow_origin.clone(),
+        };
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("failed to accept connection: {e}");
+                        continue;
+                    }
+                };
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, state).await {
+                        debug!("failed to handle connection: {e}");
+                    }
+                });
+            }
+        });
+
+        // Debugging sessions take over the terminal, so they are served one at a time.
+        while let Some(InterceptedTx { hash, request, reply }) = intercepted.recv().await {
+            println!("Intercepted transaction {hash}, opening a debugging session");
+            let result = self.simulate(&provider, &fork_url, hash, &request).await;
+            if let Err(e) = &result {
+                warn!("failed to simulate transaction {hash}: {e}");
+            }
+            let _ = reply.send(result);
+        }
+
+        Ok(())
+    }
+
+    /// Debug the intercepted transaction in the pending block on top of the latest one, and
+    /// return it as simulated.
+    async fn simulate(
+        &self,
+        provider: &Arc<RetryProvider>,
+        fork_url: &str,
+        hash: TxHash,
+        request: &TransactionRequest,
+    ) -> Result<SimulatedTx> {
+        let block_number = provider.get_block_number().await?;
+        let parent = provider
+            .get_block(BlockNumberOrTag::Number(block_number).into(), false.into())
+            .await?
+            .ok_or_else(|| eyre!("failed to get block {block_number}"))?;
+        let mut db = setup_fork_db(provider.clone(), fork_url, Some(block_number), None).await?;
+        let mut env =
+            setup_block_env(provider.clone(), Some(block_number), self.evm.spec_id()).await?;
+        set_pending_block_env(&mut env, &parent.header);
+        fill_tx_env_with_request(&mut env, request);
+
+        self.debug(&db, env.clone()).await?;
+
+        let mut evm = new_evm_with_inspector(&mut db, env.clone(), NoOpInspector);
+        let result = evm.transact()?.result;
+        drop(evm);
+
+        Ok(SimulatedTx {
+            transaction: simulated_transaction(hash, block_number + 1, &env),
+            receipt: simulated_receipt(hash, block_number + 1, &env, &result),
+        })
+    }
+
+    async fn debug(&self, db: &ForkedDatabase, env: EnvWithHandlerCfg) -> Result<()> {
+        let builder = self.ui.configure(self.evm.configure(self.etherscan.backend_builder()?));
+        let backend = builder.build::<ForkedDatabase>(db, env)?;
+        let debug_artifact = backend.analyze().await?;
+        if self.ui.machine_interface {
+            let interface = MachineInterface::new(&debug_artifact)
+                .step_into_getters(self.ui.step_into_getters)
+                .blackbox(BlackboxFile::load(CachePath::edb_blackbox_file()));
+            return interface.run(std::io::stdin().lock(), std::io::stdout());
+        }
+        let mut frontend = DebugFrontend::builder()
+            .theme(self.ui.theme())
+            .sync_dir(self.ui.sync_dir())
+            .step_into_getters(self.ui.step_into_getters)
+            .build(debug_artifact);
+        frontend.render().await?;
+        Ok(())
+    }
+}
+
+/// Serve a single HTTP request carrying a JSON-RPC call (or a batch of calls).
+async fn handle_connection(mut stream: TcpStream, state: ProxyState) -> Result<()> {
+    let request = http::read_request(&mut stream).await?;
+    let origin = request.header("origin");
+    if origin.is_some_and(|origin| !is_allowed_origin(origin, state.allow_origin.as_deref())) {
+        let body = b"cross-origin requests are not allowed";
+        let headers = [("Content-Type", "text/plain")];
+        return http::write_response(&mut stream, "403 Forbidden", &headers, body).await;
+    }
+    if request.method == "OPTIONS" {
+        // CORS preflight, so that the proxy can be used from a browser
+        return write_http_response(&mut stream, "204 No Content", origin, None).await;
+    }
+
+    let response = match serde_json::from_slice::<Value>(&request.body) {
+        Ok(Value::Array(calls)) => {
+            let mut responses = Vec::with_capacity(calls.len());
+            for call in calls {
+                responses.push(handle_call(call, &state).await);
+            }
+            Value::Array(responses)
+        }
+        Ok(call) => handle_call(call, &state).await,
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": Value::Null,
+            "error": { "code": -32700, "message": format!("parse error: {e}") },
+        }),
+    };
+    write_http_response(&mut stream, "200 OK", origin, Some(&response)).await
+}
+
+/// Returns whether a web page may call the proxy from a browser, i.e., whether it is served from
+/// the local machine or from the allowed origin. Other sites could otherwise send transactions to
+/// the wallet's node through the proxy, and read the answers of the node.
+fn is_allowed_origin(origin: &str, allowed: Option<&str>) -> bool {
+    if allowed.is_some_and(|allowed| origin == allowed.trim_end_matches('/')) {
+        return true;
+    }
+    let host = origin.split_once("://").map_or(origin, |(_, host)| host);
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>().is_ok() => host,
+        _ => host,
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// Handle a single JSON-RPC call, intercepting transactions and forwarding everything else to
+/// the upstream RPC endpoint.
+async fn handle_call(call: Value, state: &ProxyState) -> Value {
+    let id = call.get("id").cloned().unwrap_or(Value::Null);
+    let result = match call.get("method").and_then(Value::as_str) {
+        Some(method) => {
+            let params = call.get("params").cloned().unwrap_or(Value::Array(vec![]));
+            dispatch(method, params, state).await
+        }
+        None => Err(eyre!("missing method")),
+    };
+
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32000, "message": e.to_string() },
+        }),
+    }
+}
+
+async fn dispatch(method: &str, params: Value, state: &ProxyState) -> Result<Value> {
+    match method {
+        "eth_sendTransaction" => {
+            let request: TransactionRequest = serde_json::from_value(first_param(&params)?)?;
+            // there is no signature, so we make up a hash from the request itself
+            let hash = keccak256(serde_json::to_vec(&request)?);
+            intercept(hash, request, state).await
+        }
+        "eth_sendRawTransaction" => {
+            let raw: Bytes = serde_json::from_value(first_param(&params)?)?;
+            let request = decode_raw_transaction(&raw)?;
+            intercept(keccak256(&raw), request, state).await
+        }
+        // the hashes of simulated transactions are unknown upstream
+        "eth_getTransactionByHash" | "eth_getTransactionReceipt" => {
+            let hash: TxHash = serde_json::from_value(first_param(&params)?)?;
+            let simulated = state.simulated.lock().unwrap().get(&hash).cloned();
+            match simulated {
+                Some(tx) if method == "eth_getTransactionByHash" => Ok(tx.transaction),
+                Some(tx) => Ok(tx.receipt),
+                None => forward(method, params, state).await,
+            }
+        }
+        _ => forward(method, params, state).await,
+    }
+}
+
+/// Queue the transaction for a debugging session, and wait for it to be simulated.
+async fn intercept(hash: TxHash, request: TransactionRequest, state: &ProxyState) -> Result<Value> {
+    let (reply, simulated) = oneshot::channel();
+    state
+        .sessions
+        .send(InterceptedTx { hash, request, reply })
+        .map_err(|_| eyre!("the proxy is shutting down"))?;
+    let simulated = simulated.await??;
+    state.simulated.lock().unwrap().insert(hash, simulated);
+    Ok(Value::String(hash.to_string()))
+}
+
+async fn forward(method: &str, params: Value, state: &ProxyState) -> Result<Value> {
+    Ok(state.provider.raw_request::<_, Value>(method.to_string().into(), params).await?)
+}
+
+fn first_param(params: &Value) -> Result<Value> {
+    params.get(0).cloned().ok_or_else(|| eyre!("missing parameter"))
+}
+
+/// Decode a signed raw transaction into a request which can be simulated.
+fn decode_raw_transaction(raw: &[u8]) -> Result<TransactionRequest> {
+    let envelope = TxEnvelope::decode_2718(&mut &raw[..])
+        .map_err(|e| eyre!("invalid raw transaction: {e}"))?;
+    let from = envelope.recover_signer()?;
+
+    let mut request = TransactionRequest {
+        from: Some(from),
+        to: Some(envelope.to()),
+        value: Some(envelope.value()),
+        input: TransactionInput::new(Bytes::copy_from_slice(envelope.input())),
+        nonce: Some(envelope.nonce()),
+        gas: Some(envelope.gas_limit()),
+        chain_id: envelope.chain_id(),
+        ..Default::default()
+    };
+    match &envelope {
+        TxEnvelope::Legacy(tx) => request.gas_price = Some(tx.tx().gas_price),
+        TxEnvelope::Eip2930(tx) => {
+            request.gas_price = Some(tx.tx().gas_price);
+            request.access_list = Some(tx.tx().access_list.clone());
+        }
+        TxEnvelope::Eip1559(tx) => {
+            request.max_fee_per_gas = Some(tx.tx().max_fee_per_gas);
+            request.max_priority_fee_per_gas = Some(tx.tx().max_priority_fee_per_gas);
+            request.access_list = Some(tx.tx().access_list.clone());
+        }
+        _ => bail!("unsupported transaction type: {:?}", envelope.tx_type()),
+    }
+
+    Ok(request)
+}
+
+/// Build the simulated transaction, in the shape returned by `eth_getTransactionByHash`, as the
+/// first one of the pending block.
+fn simulated_transaction(hash: TxHash, block_number: u64, env: &EnvWithHandlerCfg) -> Value {
+    let mut transaction = json!({
+        "hash": hash,
+        "blockNumber": U256::from(block_number),
+        "transactionIndex": "0x0",
+        "from": env.tx.caller,
+        "to": env.tx.transact_to.to(),
+        "value": env.tx.value,
+        "input": env.tx.data,
+        "nonce": U256::from(env.tx.nonce.unwrap_or_default()),
+        "gas": U256::from(env.tx.gas_limit),
+        "gasPrice": env.effective_gas_price(),
+        "chainId": env.tx.chain_id.map(U256::from),
+    });
+    if let Some(priority_fee) = env.tx.gas_priority_fee {
+        transaction["maxFeePerGas"] = json!(env.tx.gas_price);
+        transaction["maxPriorityFeePerGas"] = json!(priority_fee);
+    }
+    transaction
+}
+
+/// Build a receipt for a simulated transaction, in the shape returned by
+/// `eth_getTransactionReceipt`.
+fn simulated_receipt(
+    hash: TxHash,
+    block_number: u64,
+    env: &EnvWithHandlerCfg,
+    result: &ExecutionResult,
+) -> Value {
+    let contract_address = match result {
+        ExecutionResult::Success { output: Output::Create(_, address), .. } => *address,
+        _ => None,
+    };
+    let logs = result
+        .logs()
+        .iter()
+        .enumerate()
+        .map(|(index, log)| {
+            json!({
+                "address": log.address,
+                "topics": log.topics(),
+                "data": log.data.data,
+                "blockNumber": U256::from(block_number),
+                "transactionHash": hash,
+                "transactionIndex": "0x0",
+                "logIndex": U256::from(index),
+                "removed": false,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "transactionHash": hash,
+        "transactionIndex": "0x0",
+        "blockNumber": U256::from(block_number),
+        "from": env.tx.caller,
+        "to": env.tx.transact_to.to(),
+        "contractAddress": contract_address,
+        "gasUsed": U256::from(result.gas_used()),
+        "cumulativeGasUsed": U256::from(result.gas_used()),
+        "effectiveGasPrice": env.effective_gas_price(),
+        "status": if result.is_success() { "0x1" } else { "0x0" },
+        "logs": logs,
+    })
+}
+
+/// Write the response, allowing the origin of the request to read it, which has been checked.
+async fn write_http_response(
+    stream: &mut TcpStream,
+    status: &str,
+    origin: Option<&str>,
+    body: Option<&Value>,
+) -> Result<()> {
+    let body = body.map(serde_json::to_vec).transpose()?.unwrap_or_default();
+    let mut headers = vec![("Content-Type", "application/json")];
+    if let Some(origin) = origin {
+        headers.extend([
+            ("Access-Control-Allow-Origin", origin),
+            ("Access-Control-Allow-Methods", "POST, OPTIONS"),
+            ("Access-Control-Allow-Headers", "Content-Type"),
+            ("Vary", "Origin"),
+        ]);
+    }
+    http::write_response(stream, status, &headers, &body).await
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, b256, bytes, hex, Address, Log, TxKind, B256};
+    use foundry_common::provider::ProviderBuilder;
+    use revm::primitives::SuccessReason;
+
+    use super::*;
+
+    /// The signed transaction of the example of EIP-155.
+    const RAW_TX: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    fn env() -> EnvWithHandlerCfg {
+        let mut env = EnvWithHandlerCfg::default();
+        env.block.basefee = U256::from(1_000_000_000);
+        env.tx.caller = address!("9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F");
+        env.tx.transact_to = TxKind::Call(Address::repeat_byte(0x35));
+        env.tx.gas_limit = 21_000;
+        env.tx.gas_price = U256::from(2_000_000_000);
+        env.tx.gas_priority_fee = Some(U256::from(500_000_000));
+        env.tx.nonce = Some(9);
+        env
+    }
+
+    #[test]
+    fn test_decode_raw_transaction() {
+        let request = decode_raw_transaction(&hex::decode(RAW_TX).unwrap()).unwrap();
+        assert_eq!(request.from, Some(address!("9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F")));
+        assert_eq!(request.to, Some(TxKind::Call(Address::repeat_byte(0x35))));
+        assert_eq!(request.value, Some(U256::from(1_000_000_000_000_000_000u128)));
+        assert_eq!((request.nonce, request.gas), (Some(9), Some(21_000)));
+        assert_eq!(request.gas_price, Some(20_000_000_000));
+        assert_eq!(request.chain_id, Some(1));
+        assert_eq!(request.max_fee_per_gas, None);
+
+        assert!(decode_raw_transaction(&[]).is_err());
+        assert!(decode_raw_transaction(&hex::decode(&RAW_TX[..100]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_simulated_receipt() {
+        let hash = b256!("33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788");
+        let env = env();
+        let topic = B256::repeat_byte(1);
+        let log = Log::new_unchecked(Address::repeat_byte(0x35), vec![topic], bytes!("01"));
+        let result = ExecutionResult::Success {
+            reason: SuccessReason::Stop,
+            gas_used: 21_000,
+            gas_refunded: 0,
+            logs: vec![log.clone(), log],
+            output: Output::Call(Bytes::new()),
+        };
+        let receipt = simulated_receipt(hash, 101, &env, &result);
+        assert_eq!(receipt["transactionHash"], json!(hash));
+        assert_eq!(receipt["blockNumber"], "0x65");
+        assert_eq!(receipt["from"], json!(env.tx.caller));
+        assert_eq!(receipt["to"], json!(Address::repeat_byte(0x35)));
+        assert_eq!(receipt["contractAddress"], Value::Null);
+        assert_eq!(
+            (&receipt["gasUsed"], &receipt["cumulativeGasUsed"]),
+            (&json!("0x5208"), &json!("0x5208"))
+        );
+        // the priority fee is capped by the fee cap above the base fee
+        assert_eq!(receipt["effectiveGasPrice"], json!(U256::from(1_500_000_000)));
+        assert_eq!(receipt["status"], "0x1");
+        let logs = receipt["logs"].as_array().unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1]["logIndex"], "0x1");
+        assert_eq!(logs[1]["topics"], json!([topic]));
+        assert_eq!(logs[1]["transactionHash"], json!(hash));
+
+        // a creation reports the deployed contract, and a revert has neither logs nor success
+        let deployed = Address::repeat_byte(0xcc);
+        let created = ExecutionResult::Success {
+            reason: SuccessReason::Return,
+            gas_used: 50_000,
+            gas_refunded: 0,
+            logs: vec![],
+            output: Output::Create(Bytes::new(), Some(deployed)),
+        };
+        let receipt = simulated_receipt(hash, 101, &env, &created);
+        assert_eq!(receipt["contractAddress"], json!(deployed));
+        let reverted = ExecutionResult::Revert { gas_used: 30_000, output: Bytes::new() };
+        let receipt = simulated_receipt(hash, 101, &env, &reverted);
+        assert_eq!((&receipt["status"], &receipt["logs"]), (&json!("0x0"), &json!([])));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch() {
+        let (sessions, mut intercepted) = mpsc::unbounded_channel();
+        let state = ProxyState {
+            // nothing is forwarded, so that the endpoint is never reached
+            provider: Arc::new(ProviderBuilder::new("http://127.0.0.1:1").build().unwrap()),
+            sessions,
+            simulated: Arc::default(),
+            allow_origin: None,
+        };
+        // simulate the intercepted transactions without debugging them
+        tokio::spawn(async move {
+            while let Some(InterceptedTx { hash, request, reply }) = intercepted.recv().await {
+                let mut env = env();
+                fill_tx_env_with_request(&mut env, &request);
+                let result = ExecutionResult::Revert { gas_used: 21_000, output: Bytes::new() };
+                let _ = reply.send(Ok(SimulatedTx {
+                    transaction: simulated_transaction(hash, 101, &env),
+                    receipt: simulated_receipt(hash, 101, &env, &result),
+                }));
+            }
+        });
+
+        let raw = hex::decode(RAW_TX).unwrap();
+        let params = json!([Bytes::from(raw.clone())]);
+        let hash = dispatch("eth_sendRawTransaction", params, &state).await.unwrap();
+        assert_eq!(hash, json!(keccak256(&raw)));
+
+        // the simulated transaction is looked up locally, since it was never sent upstream
+        let params = json!([hash]);
+        let transaction =
+            dispatch("eth_getTransactionByHash", params.clone(), &state).await.unwrap();
+        assert_eq!(transaction["hash"], hash);
+        assert_eq!(
+            transaction["from"],
+            json!(address!("9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F"))
+        );
+        assert_eq!(
+            (&transaction["nonce"], &transaction["blockNumber"]),
+            (&json!("0x9"), &json!("0x65"))
+        );
+        let receipt = dispatch("eth_getTransactionReceipt", params, &state).await.unwrap();
+        assert_eq!((&receipt["transactionHash"], &receipt["status"]), (&hash, &json!("0x0")));
+
+        assert!(dispatch("eth_sendRawTransaction", json!([]), &state).await.is_err());
+    }
+
+    #[test]
+    fn test_is_allowed_origin() {
+        assert!(is_allowed_origin("http://localhost:3000", None));
+        assert!(is_allowed_origin("http://127.0.0.1", None));
+        assert!(is_allowed_origin("http://[::1]:8080", None));
+        assert!(!is_allowed_origin("https://localhost.example.com", None));
+        assert!(!is_allowed_origin("https://app.example.com", None));
+        assert!(is_allowed_origin("https://app.example.com", Some("https://app.example.com/")));
+        assert!(!is_allowed_origin("https://evil.example.com", Some("https://app.example.com")));
+    }
+}
@@ -1,20 +1,46 @@
 use std::{path::PathBuf, sync::Arc};
 
-use alloy_primitives::TxHash;
+use alloy_chains::Chain;
+use alloy_primitives::{BlockHash, TxHash, U256};
 use alloy_provider::Provider;
-use alloy_rpc_types::{BlockTransactions, BlockTransactionsKind};
+use alloy_rpc_types::{
+    serde_helpers::WithOtherFields, BlockTransactions, BlockTransactionsKind, Transaction,
+};
+use anvil::{
+    eth::{pool::transactions::TransactionOrder, EthApi},
+    Hardfork, NodeConfig, NodeHandle,
+};
 use clap::Parser;
-use edb_debug_backend::DebugBackend;
-use edb_debug_frontend::DebugFrontend;
-use edb_utils::{init_progress, update_progress};
+use edb_debug_backend::{
+    artifact::{
+        debug::DebugArtifact,
+        warning::{Warning, WarningKind},
+    },
+    event::{EngineEvent, EventSender},
+};
+use edb_debug_frontend::engine_events;
+use edb_utils::{
+    init_progress,
+    profile::{span, Phase},
+    update_progress,
+};
 use eyre::{ensure, eyre, Result};
 use foundry_common::{is_known_system_sender, SYSTEM_TRANSACTION_TYPE};
 use foundry_evm::{fork::database::ForkedDatabase, utils::new_evm_with_inspector};
 use revm::{inspectors::NoOpInspector, primitives::EnvWithHandlerCfg};
+use serde_json::{json, Value};
 
 use crate::{
-    opts::{EtherscanOpts, RpcOpts},
-    utils::evm::{fill_tx_env, setup_block_env, setup_fork_db},
+    opts::{EtherscanOpts, EvmOpts, RpcOpts, UiOpts},
+    utils::{
+        chain::{format_call_trace, ChainQuirks},
+        evm::{apply_prestate, fill_tx_env, setup_block_env, setup_fork_db},
+        finality::{check_finality, ensure_canonical},
+        history,
+        mev::fetch_mev_context,
+        receipt::ReceiptDiff,
+        rerun::debug_with_reruns,
+    },
 };
 
 /// CLI arguments for `edb replay`.
@@ -30,65 +56,247 @@ pub struct ReplayArgs {
     #[arg(long, short)]
     pub quick: bool,
 
+    /// Executes the transaction on top of its exact prestate, as traced by the node with
+    /// `debug_traceTransaction`, instead of the state from the previous block. Implies `--quick`.
+    ///
+    /// This gives the same results as the live execution without replaying the preceding
+    /// transactions, but requires an RPC endpoint with the debug API.
+    #[arg(long)]
+    pub prestate: bool,
+
     /// Skips validation of transactions replayed before the target transaction.
     #[arg(long, short)]
     pub no_validation: bool,
 
+    /// The hash of the block the transaction is expected in. The replay is aborted if the
+    /// canonical block at the RPC differs, e.g., after a reorg.
+    #[arg(long, value_name = "HASH")]
+    pub block_hash: Option<BlockHash>,
+
+    /// Spawns a managed Anvil fork at the transaction's block, which mines the transactions of
+    /// the block up to (and including) the target transaction, in their original order.
+    ///
+    /// The debugging session itself replays the transaction on the RPC endpoint as usual. The
+    /// node is left running after the session, so that the post-state can be inspected with
+    /// other tools. Press Ctrl-C to stop it.
+    #[arg(long)]
+    pub spawn_anvil: bool,
+
+    /// The port the spawned Anvil node listens on.
+    #[arg(long, value_name = "PORT", default_value_t = 8545, requires = "spawn_anvil")]
+    pub anvil_port: u16,
+
+    /// Shows the transactions of the block which touched the same contracts shortly before or
+    /// after the transaction, to tell whether it was sandwiched or back-run.
+    #[arg(long)]
+    pub mev_context: bool,
+
+    /// How many transactions before and after the transaction are considered.
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "mev_context")]
+    pub mev_window: usize,
+
     #[command(flatten)]
     pub etherscan: EtherscanOpts,
 
     #[command(flatten)]
     pub rpc: RpcOpts,
+
+    #[command(flatten)]
+    pub evm: EvmOpts,
+
+    #[command(flatten)]
+    pub ui: UiOpts,
+
+    /// The channel the progress of the replay and of the analysis is streamed on, if any.
+    #[arg(skip)]
+    pub events: Option<EventSender>,
 }
 
 impl ReplayArgs {
     pub async fn run(mut self) -> Result<()> {
+        if self.prestate {
+            // the prestate makes replaying the preceding transactions unnecessary
+            self.quick = true;
+        }
         if self.quick {
             // enforce no validation when quick is enabled
             self.no_validation = true;
         }
+        if self.ui.machine_interface {
+            // the progress is reported to the editor before the session is ready
+            self.events = Some(engine_events());
+        }
+
+        let chain = self.chain().await?;
+        if !ChainQuirks::of(chain).can_reexecute() {
+            warn!("transactions on {chain} cannot be re-executed, falling back to the call trace");
+            return self.trace_only().await;
+        }
+
+        // the anvil node is kept alive until the end of this function
+        let anvil = if self.spawn_anvil { Some(self.spawn_anvil().await?) } else { None };
+
+        let (db, env, mut warnings) = self.prepare(None).await?;
+        if self.mev_context {
+            // the context only helps reading the transaction, so that the replay goes on without
+            match fetch_mev_context(self.rpc.provider()?, self.tx_hash, self.mev_window).await {
+                Ok(context) => {
+                    info!("{context}");
+                    if !context.is_empty() {
+                        warnings.push(Warning::new(WarningKind::MevContext, context.summary()));
+                    }
+                }
+                Err(e) => warn!("failed to fetch the MEV context of {}: {e}", self.tx_hash),
+            }
+        }
+        self.debug(db, env, warnings).await?;
 
-        let (db, env) = self.prepare(None).await?;
-        self.debug(db, env).await?;
+        if let Some((_api, node)) = anvil {
+            println!(
+                "Anvil is serving the post-state of {} at {} (Ctrl-C to stop)",
+                self.tx_hash,
+                node.http_endpoint()
+            );
+            tokio::signal::ctrl_c().await?;
+        }
         Ok(())
     }
 
-    pub async fn debug(&self, db: ForkedDatabase, env: EnvWithHandlerCfg) -> Result<()> {
-        let backend = DebugBackend::<ForkedDatabase>::builder()
-            .chain(self.etherscan.chain.unwrap_or_default())
-            .etherscan_api_key(self.etherscan.key().unwrap_or_default())
-            .build::<ForkedDatabase>(&db, env)?;
-        let debug_artifact = backend.analyze().await?;
-        let mut frontend = DebugFrontend::builder().build(debug_artifact);
-        todo!();
-        frontend.render().await?;
+    /// Returns the chain served by the RPC endpoint, which must be the chain of the explorer.
+    async fn chain(&self) -> Result<Chain> {
+        let chain_id = self.rpc.provider()?.get_chain_id().await?;
+        ensure!(chain_id == self.etherscan.chain.unwrap_or_default().id(), "inconsistent chain id");
+        Ok(Chain::from_id(chain_id))
+    }
+
+    /// Print the call trace of the transaction as traced by the node, for chains whose
+    /// transactions cannot be re-executed.
+    pub async fn trace_only(&self) -> Result<()> {
+        let provider = self.rpc.provider()?;
+        let frame = provider
+            .raw_request::<_, Value>(
+                "debug_traceTransaction".into(),
+                (self.tx_hash, json!({ "tracer": "callTracer" })),
+            )
+            .await
+            .map_err(|e| {
+                eyre!("failed to trace the transaction, is the debug API enabled? ({e})")
+            })?;
+        print!("{}", format_call_trace(&frame));
         Ok(())
     }
 
+    /// Spawn an Anvil node forking the state right before the transaction's block, and mine the
+    /// block up to (and including) the target transaction into it.
+    pub async fn spawn_anvil(&self) -> Result<(EthApi, NodeHandle)> {
+        let Self { tx_hash, rpc, evm, anvil_port, no_validation, .. } = self;
+        let fork_url = rpc.url(true)?.unwrap().to_string();
+        let provider = rpc.provider()?;
+
+        let tx = provider
+            .get_transaction_by_hash(*tx_hash)
+            .await?
+            .ok_or(eyre!("transaction not found"))?;
+        let tx_block_number: u64 =
+            tx.block_number.ok_or(eyre!("transaction may still be pending"))?;
+        let block = provider
+            .get_block(tx_block_number.into(), BlockTransactionsKind::Full)
+            .await?
+            .ok_or(eyre!("block not found"))?;
+        let BlockTransactions::Full(txs_in_block) = block.transactions else {
+            return Err(eyre::eyre!("block transactions not found"));
+        };
+
+        let config = anvil_config(fork_url, *anvil_port, tx_block_number, evm.hardfork);
+        let (api, node) = anvil::spawn(config).await;
+
+        // mine all replayed transactions into a single block which mirrors the original one
+        api.anvil_set_auto_mine(false).await?;
+        api.anvil_auto_impersonate_account(true).await?;
+        api.anvil_set_coinbase(block.header.miner).await?;
+        api.evm_set_next_block_timestamp(block.header.timestamp)?;
+        if let Some(base_fee) = block.header.base_fee_per_gas {
+            api.anvil_set_next_block_base_fee_per_gas(U256::from(base_fee)).await?;
+        }
+
+        let txs = anvil_replay_txs(txs_in_block, tx.inner, *tx_hash);
+        let pb = init_progress!(txs, "Replaying through Anvil");
+        pb.set_position(0);
+        let mut sent = Vec::with_capacity(txs.len());
+        for (index, tx) in txs.into_iter().enumerate() {
+            trace!("Sending transaction to Anvil: {:?}", tx.hash);
+            let hash =
+                api.send_transaction(WithOtherFields::new(tx.clone().into_request())).await?;
+            sent.push((tx.hash, hash));
+            update_progress!(pb, index);
+        }
+        api.evm_mine(None).await?;
+        api.anvil_auto_impersonate_account(false).await?;
+        api.anvil_set_auto_mine(true).await?;
+
+        // the mined block mirrors the original one only if every transaction used the same gas
+        for (original, replayed) in sent {
+            let expected = provider
+                .get_transaction_receipt(original)
+                .await?
+                .ok_or(eyre!("transaction receipt not found"))?;
+            let mined = api
+                .transaction_receipt(replayed)
+                .await?
+                .ok_or(eyre!("transaction {original} was not mined by Anvil"))?;
+            let expected = expected.inner.inner.inner.receipt.cumulative_gas_used;
+            let mined = mined.inner.inner.inner.receipt.cumulative_gas_used;
+            ensure!(
+                *no_validation || expected == mined,
+                "gas used mismatch in Anvil ({original:?}): {mined} vs {expected}"
+            );
+        }
+
+        Ok((api, node))
+    }
+
+    pub async fn debug(
+        &self,
+        db: ForkedDatabase,
+        env: EnvWithHandlerCfg,
+        warnings: Vec<Warning>,
+    ) -> Result<()> {
+        let mut debug_artifact = self.analyze(&db, env.clone()).await?;
+        debug_artifact.warnings.extend(warnings);
+        debug_with_reruns(&self.ui, debug_artifact, env, |env| self.analyze(&db, env)).await
+    }
+
+    /// Analyze the transaction and collect the debug artifact.
+    pub async fn analyze(
+        &self,
+        db: &ForkedDatabase,
+        env: EnvWithHandlerCfg,
+    ) -> Result<DebugArtifact> {
+        let mut builder = self.ui.configure(self.evm.configure(self.etherscan.backend_builder()?));
+        if let Some(events) = &self.events {
+            builder = builder.events(events.clone());
+        }
+        let backend = builder.build::<ForkedDatabase>(db, env)?;
+        backend.analyze().await
+    }
+
     /// Prepare the environment and database for the replay.
     ///  - cache_root: the path to the rpc cache directory. If not provided, the default cache
     ///    directory will be used.
     pub async fn prepare(
         &self,
         cache_root: Option<PathBuf>,
-    ) -> Result<(ForkedDatabase, EnvWithHandlerCfg)> {
-        let Self { tx_hash, quick, rpc, no_validation, etherscan: EtherscanOpts { chain, .. } } =
-            self;
+    ) -> Result<(ForkedDatabase, EnvWithHandlerCfg, Vec<Warning>)> {
+        let Self { tx_hash, quick, prestate, rpc, no_validation, block_hash, .. } = self;
         let fork_url = rpc.url(true)?.unwrap().to_string();
 
-        // step 0. prepare rpc provider
-        let compute_units_per_second =
-            if rpc.no_rate_limit { Some(u64::MAX) } else { rpc.compute_units_per_second };
-        let mut provider_builder = foundry_common::provider::ProviderBuilder::new(&fork_url)
-            .compute_units_per_second_opt(compute_units_per_second);
-        if let Some(jwt) = rpc.jwt_secret.as_deref() {
-            provider_builder = provider_builder.jwt(jwt);
-        }
-        let provider = Arc::new(provider_builder.build()?);
-        ensure!(
-            provider.get_chain_id().await? == chain.unwrap_or_default().id(),
-            "inconsistent chain id"
-        );
+        // step 0. prepare rpc provider, and check the quirks of the chain it serves
+        let provider = rpc.provider()?;
+        let chain = self.chain().await?;
+        let quirks = ChainQuirks::of(chain);
+        ensure!(quirks.can_reexecute(), "transactions on {chain} cannot be re-executed in the EVM");
+        // quick replay cannot be validated, nor can chains whose gas accounting differs
+        let no_validation = *no_validation || *quick || !quirks.gas_matches();
 
         // step 1. get the transaction and block data
         let tx = provider
@@ -101,9 +309,16 @@ impl ReplayArgs {
             .get_block(tx_block_number.into(), BlockTransactionsKind::Full)
             .await?
             .ok_or(eyre!("block not found"))?;
+        ensure_canonical(*tx_hash, tx.block_hash, &block, *block_hash)?;
         let BlockTransactions::Full(txs_in_block) = block.transactions else {
             return Err(eyre::eyre!("block transactions not found"));
         };
+        history::record_tx(*tx_hash);
+        let mut warnings = vec![];
+        if let Some(reason) = check_finality(Arc::clone(&provider), tx_block_number).await? {
+            warn!("{reason}");
+            warnings.push(Warning::new(WarningKind::UnfinalizedBlock, reason));
+        }
 
         // step 2. set enviroment and database
         // note that database should be set to tx_block_number - 1
@@ -114,7 +329,12 @@ impl ReplayArgs {
             cache_root.map(|p| p.join(format!("{}", tx_block_number - 1))),
         )
         .await?;
-        let mut env = setup_block_env(Arc::clone(&provider), Some(tx_block_number)).await?;
+        let mut env =
+            setup_block_env(Arc::clone(&provider), Some(tx_block_number), self.evm.spec_id())
+                .await?;
+        if *prestate {
+            apply_prestate(&mut db, Arc::clone(&provider), *tx_hash).await?;
+        }
 
         // step 3. replay all transactions before the target transaction
         // we use cumulative_gas_used as a quick validator for the correctness of the replay
@@ -126,9 +346,13 @@ impl ReplayArgs {
         };
         txs.push(tx.inner.clone());
 
+        let total = txs.len();
         let pb = init_progress!(txs, "Setting up the replay environment");
         pb.set_position(0);
         for (index, tx) in txs.into_iter().enumerate() {
+            if let Some(events) = &self.events {
+                let _ = events.send(EngineEvent::ReplayProgress { done: index, total });
+            }
             // System transactions such as on L2s don't contain any pricing info so
             // we skip them otherwise this would cause
             // reverts
@@ -143,6 +367,7 @@ impl ReplayArgs {
             trace!("Executing transaction: {:?}", tx.hash);
 
             fill_tx_env(&mut env, &tx)?;
+            let replay = span(if &tx.hash == tx_hash { Phase::Execute } else { Phase::Replay });
             let mut evm = new_evm_with_inspector(&mut db, env.clone(), NoOpInspector);
             let result = if &tx.hash == tx_hash {
                 // we don't commit the target transaction
@@ -151,15 +376,37 @@ impl ReplayArgs {
                 evm.transact_commit()?
             };
             drop(evm);
+            drop(replay);
 
             let tx_receipt = provider
                 .get_transaction_receipt(tx.hash)
                 .await?
                 .ok_or(eyre!("transaction receipt not found"))?;
 
+            if &tx.hash == tx_hash {
+                // the target transaction is always compared with its receipt, so that users know
+                // whether the replay is trustworthy
+                let receipt = &tx_receipt.inner;
+                let logs =
+                    receipt.inner.logs().iter().map(|log| log.inner.clone()).collect::<Vec<_>>();
+                let diff = ReceiptDiff::new(
+                    &result,
+                    receipt.inner.status(),
+                    receipt.gas_used,
+                    &logs,
+                    quirks.gas_matches(),
+                );
+                if diff.is_empty() {
+                    debug!("{diff}");
+                } else {
+                    warn!("{diff}");
+                    warnings.push(Warning::new(WarningKind::ReplayMismatch, diff.summary()));
+                }
+            }
+
             cumulative_gas_used += result.gas_used() as u128;
             ensure!(
-                *no_validation ||
+                no_validation ||
                     cumulative_gas_used ==
                         tx_receipt.inner.inner.inner.receipt.cumulative_gas_used,
                 "gas used mismatch ({:?}): {} vs {}",
@@ -170,22 +417,82 @@ impl ReplayArgs {
             update_progress!(pb, index);
         }
 
-        Ok((db, env))
+        Ok((db, env, warnings))
     }
 }
 
+/// Configures an Anvil node forking the state right before the given block, whose pool keeps
+/// the order the transactions are sent in rather than sorting them by fees.
+fn anvil_config(
+    fork_url: String,
+    port: u16,
+    tx_block_number: u64,
+    hardfork: Option<Hardfork>,
+) -> NodeConfig {
+    NodeConfig::default()
+        .with_port(port)
+        .with_eth_rpc_url(Some(fork_url))
+        .with_fork_block_number(Some(tx_block_number - 1))
+        .with_transaction_order(TransactionOrder::Fifo)
+        .with_hardfork(hardfork)
+        .silent()
+}
+
+/// Returns the transactions to mine into Anvil, i.e., the ones of the block up to (and
+/// including) the target transaction, in their original order, but for system transactions.
+fn anvil_replay_txs(
+    txs_in_block: Vec<Transaction>,
+    tx: Transaction,
+    tx_hash: TxHash,
+) -> Vec<Transaction> {
+    txs_in_block
+        .into_iter()
+        .take_while(|tx| tx.hash != tx_hash)
+        .chain(std::iter::once(tx))
+        .filter(|tx| {
+            !is_known_system_sender(tx.from) && tx.transaction_type != Some(SYSTEM_TRANSACTION_TYPE)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use std::{str::FromStr, time::Duration};
 
     use super::*;
+    use crate::utils::network_fixtures::{self, FixtureMode};
     use serial_test::serial;
 
+    /// Replays the RPC and explorer responses recorded under `testdata/fixtures`, if any, so that
+    /// the tests run offline. They are recorded anew, bypassing the caches under `testdata/cache`,
+    /// when `EDB_RECORD_FIXTURES` is set.
+    fn setup_fixtures() {
+        static SETUP: std::sync::Once = std::sync::Once::new();
+        SETUP.call_once(|| {
+            let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../testdata/fixtures");
+            let mode = if std::env::var_os("EDB_RECORD_FIXTURES").is_some() {
+                FixtureMode::Record
+            } else if dir.is_dir() {
+                FixtureMode::Replay
+            } else {
+                return;
+            };
+            network_fixtures::start(mode, &dir).unwrap();
+        });
+    }
+
     fn init_test(tx_hash: &str) -> Result<(ReplayArgs, PathBuf, PathBuf)> {
+        setup_fixtures();
         let args = ReplayArgs {
             tx_hash: TxHash::from_str(tx_hash)?,
             quick: false,
+            prestate: false,
             no_validation: false,
+            block_hash: None,
+            spawn_anvil: false,
+            anvil_port: 8545,
+            mev_context: false,
+            mev_window: 3,
             etherscan: EtherscanOpts::default(),
             rpc: RpcOpts {
                 url: Some("https://rpc.mevblocker.io".to_string()),
@@ -194,6 +501,9 @@ mod tests {
                 flashbots: false,
                 compute_units_per_second: None,
             },
+            evm: EvmOpts::default(),
+            ui: UiOpts::default(),
+            events: None,
         };
 
         let rpc_cache_root =
@@ -205,12 +515,47 @@ mod tests {
         Ok((args, rpc_cache_root, etherscan_cache_root))
     }
 
+    #[test]
+    fn test_anvil_config() {
+        let config = anvil_config("http://localhost:8545".to_string(), 8546, 100, None);
+        // the fork is at the state right before the block of the transaction
+        assert_eq!(config.fork_block_number, Some(99));
+        assert_eq!(config.transaction_order, TransactionOrder::Fifo);
+        assert_eq!(config.port, 8546);
+    }
+
+    #[test]
+    fn test_anvil_replay_txs() {
+        let tx = |n: u8| Transaction {
+            hash: TxHash::with_last_byte(n),
+            from: alloy_primitives::Address::with_last_byte(n),
+            ..Default::default()
+        };
+        let system = Transaction { transaction_type: Some(SYSTEM_TRANSACTION_TYPE), ..tx(2) };
+        // the fees of the transactions do not matter, only their position in the block
+        let block = vec![
+            Transaction { gas_price: Some(1), ..tx(1) },
+            system,
+            Transaction { gas_price: Some(100), ..tx(3) },
+            tx(4),
+            tx(5),
+        ];
+
+        let hashes = anvil_replay_txs(block, tx(4), TxHash::with_last_byte(4))
+            .into_iter()
+            .map(|tx| tx.hash)
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, [1, 3, 4].map(TxHash::with_last_byte));
+    }
+
     async fn run_e2e_test(tx_hash: &str) -> Result<()> {
         let (args, rpc_cache_root, etherscan_cache_root) = init_test(tx_hash)?;
-        let (db, env) = args.prepare(Some(rpc_cache_root)).await?;
-        let backend = DebugBackend::<ForkedDatabase>::builder()
-            .chain(args.etherscan.chain.unwrap_or_default())
-            .etherscan_api_key(args.etherscan.key().unwrap_or_default())
+        let (db, env, _) = args.prepare(Some(rpc_cache_root)).await?;
+        // the explorer is reached through the fixture server, and the caches are bypassed while
+        // recording
+        let backend = args
+            .etherscan
+            .backend_builder()?
             .cache_root(etherscan_cache_root)
             .cache_ttl(Duration::from_secs(u32::MAX as u64)) // we don't want the cache to expire
             .build::<ForkedDatabase>(&db, env)?;
@@ -0,0 +1,273 @@
+use std::{
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
+
+use alloy_primitives::{Address, TxHash, U256};
+use clap::Parser;
+use edb_debug_backend::{
+    analysis::funds::{Asset, FundsFlow},
+    artifact::debug::DebugArtifact,
+    export::calltree::{call_frames, FrameOutcome},
+};
+use eyre::{eyre, Result};
+use serde::Serialize;
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use super::replay::ReplayArgs;
+use crate::opts::{EtherscanOpts, EvmOpts, RpcOpts, UiOpts};
+
+/// CLI arguments for `edb scan`.
+#[derive(Clone, Debug, Parser)]
+pub struct ScanArgs {
+    /// The file listing the transactions to analyze, one hash per line. Empty lines and lines
+    /// starting with `#` are ignored.
+    ///
+    /// Reads from stdin if not given or `-`.
+    #[arg(value_name = "FILE")]
+    pub input: Option<PathBuf>,
+
+    /// The number of transactions analyzed in parallel.
+    #[arg(long, short, default_value_t = 4)]
+    pub jobs: usize,
+
+    /// Executes each transaction only with the state from the previous block. See `edb replay
+    /// --quick`.
+    #[arg(long, short)]
+    pub quick: bool,
+
+    /// Executes each transaction on top of its exact prestate. See `edb replay --prestate`.
+    #[arg(long)]
+    pub prestate: bool,
+
+    /// Skips validation of transactions replayed before each transaction.
+    #[arg(long, short)]
+    pub no_validation: bool,
+
+    /// Prints the summaries as JSON lines instead of a table.
+    #[arg(long)]
+    pub json: bool,
+
+    /// A JSON list of flagged addresses (e.g., known attackers and mixers), which are listed in
+    /// the summaries of the transactions touching them. May be given multiple times.
+    ///
+    /// `~/.edb/flags.json` is always loaded if it exists.
+    #[arg(long = "flags", value_name = "PATH")]
+    pub flag_files: Vec<PathBuf>,
+
+    #[command(flatten)]
+    pub etherscan: EtherscanOpts,
+
+    #[command(flatten)]
+    pub rpc: RpcOpts,
+
+    #[command(flatten)]
+    pub evm: EvmOpts,
+}
+
+/// The outcome of the analysis of one transaction.
+#[derive(Clone, Debug, Default, Serialize)]
+struct ScanSummary {
+    tx_hash: TxHash,
+    /// `success`, `reverted`, `halted`, or `error` if the transaction could not be analyzed.
+    status: &'static str,
+    gas_used: Option<u64>,
+    revert_reason: Option<String>,
+    /// The ether moved by the transaction, in wei.
+    ether_moved: U256,
+    /// The number of ether and token transfers.
+    transfers: usize,
+    /// The distinct tokens moved.
+    tokens: Vec<Address>,
+    /// The touched addresses which are flagged, e.g., known attackers.
+    flagged: Vec<Address>,
+    warnings: usize,
+    error: Option<String>,
+}
+
+impl ScanSummary {
+    fn new(tx_hash: TxHash, artifact: &DebugArtifact) -> Self {
+        let root = call_frames(artifact).into_iter().next();
+        let status = match root.as_ref().map(|frame| frame.outcome) {
+            Some(outcome) if outcome.is_success() => "success",
+            Some(FrameOutcome::Revert) => "reverted",
+            _ => "halted",
+        };
+
+        let flow = FundsFlow::new(artifact);
+        let ether_moved = flow
+            .transfers
+            .iter()
+            .filter(|transfer| transfer.asset == Asset::Ether)
+            .map(|transfer| transfer.amount)
+            .fold(U256::ZERO, U256::saturating_add);
+        let mut tokens = flow
+            .transfers
+            .iter()
+            .filter_map(|transfer| match transfer.asset {
+                Asset::Token(token) => Some(token),
+                Asset::Ether => None,
+            })
+            .collect::<Vec<_>>();
+        tokens.sort();
+        tokens.dedup();
+
+        let mut flagged = artifact
+            .touched_addresses()
+            .into_iter()
+            .filter(|address| artifact.flags.contains_key(address))
+            .collect::<Vec<_>>();
+        flagged.sort();
+
+        Self {
+            tx_hash,
+            status,
+            gas_used: root.as_ref().map(|frame| frame.gas_used),
+            revert_reason: root.as_ref().and_then(|frame| frame.revert_reason()),
+            ether_moved,
+            transfers: flow.transfers.len(),
+            tokens,
+            flagged,
+            warnings: artifact.warnings.len(),
+            error: None,
+        }
+    }
+
+    fn failed(tx_hash: TxHash, error: String) -> Self {
+        Self { tx_hash, status: "error", error: Some(error), ..Default::default() }
+    }
+}
+
+impl ScanArgs {
+    pub async fn run(self) -> Result<()> {
+        let hashes = match &self.input {
+            Some(path) if path.as_os_str() != "-" => {
+                parse_hashes(BufReader::new(std::fs::File::open(path)?))?
+            }
+            _ => parse_hashes(std::io::stdin().lock())?,
+        };
+        eprintln!("Scanning {} transactions with {} workers", hashes.len(), self.jobs.max(1));
+
+        // each worker replays its transaction on its own fork, so that they are independent
+        let semaphore = Arc::new(Semaphore::new(self.jobs.max(1)));
+        let mut workers = JoinSet::new();
+        for (index, tx_hash) in hashes.iter().copied().enumerate() {
+            let replay = self.replay_args(tx_hash);
+            let semaphore = Arc::clone(&semaphore);
+            workers.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let summary = match scan(&replay).await {
+                    Ok(artifact) => ScanSummary::new(tx_hash, &artifact),
+                    Err(e) => ScanSummary::failed(tx_hash, e.to_string()),
+                };
+                (index, summary)
+            });
+        }
+
+        let mut summaries = vec![None; hashes.len()];
+        while let Some(result) = workers.join_next().await {
+            let (index, summary) = result?;
+            summaries[index] = Some(summary);
+        }
+        let summaries = summaries.into_iter().flatten().collect::<Vec<_>>();
+
+        if self.json {
+            for summary in &summaries {
+                println!("{}", serde_json::to_string(summary)?);
+            }
+        } else {
+            print_table(&summaries);
+        }
+        Ok(())
+    }
+
+    fn replay_args(&self, tx_hash: TxHash) -> ReplayArgs {
+        ReplayArgs {
+            tx_hash,
+            quick: self.quick || self.prestate,
+            prestate: self.prestate,
+            no_validation: self.no_validation || self.quick || self.prestate,
+            block_hash: None,
+            spawn_anvil: false,
+            anvil_port: 8545,
+            mev_context: false,
+            mev_window: 3,
+            etherscan: self.etherscan.clone(),
+            rpc: self.rpc.clone(),
+            evm: self.evm.clone(),
+            ui: UiOpts { flag_files: self.flag_files.clone(), ..Default::default() },
+            events: None,
+        }
+    }
+}
+
+async fn scan(replay: &ReplayArgs) -> Result<DebugArtifact> {
+    let (db, env, warnings) = replay.prepare(None).await?;
+    let mut artifact = replay.analyze(&db, env).await?;
+    artifact.warnings.extend(warnings);
+    Ok(artifact)
+}
+
+/// Parses one transaction hash per line, skipping empty lines and `#` comments.
+fn parse_hashes(reader: impl BufRead) -> Result<Vec<TxHash>> {
+    let mut hashes = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let hash = TxHash::from_str(line)
+            .map_err(|e| eyre!("invalid transaction hash on line {}: {e}", number + 1))?;
+        hashes.push(hash);
+    }
+    Ok(hashes)
+}
+
+fn print_table(summaries: &[ScanSummary]) {
+    println!(
+        "{:<66}  {:<8}  {:>10}  {:>9}  {:>7}  details",
+        "tx", "status", "gas", "transfers", "flagged"
+    );
+    for summary in summaries {
+        let details = match (&summary.error, &summary.revert_reason) {
+            (Some(error), _) => error.clone(),
+            (None, Some(reason)) => format!("revert: {reason}"),
+            (None, None) if !summary.ether_moved.is_zero() => {
+                format!("{} wei moved", summary.ether_moved)
+            }
+            (None, None) => String::new(),
+        };
+        println!(
+            "{:<66}  {:<8}  {:>10}  {:>9}  {:>7}  {details}",
+            summary.tx_hash,
+            summary.status,
+            summary.gas_used.map(|gas| gas.to_string()).unwrap_or_default(),
+            summary.transfers,
+            summary.flagged.len(),
+        );
+    }
+
+    let failed = summaries.iter().filter(|summary| summary.status != "success").count();
+    println!("\n{} transactions scanned, {failed} did not succeed", summaries.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hashes() {
+        let input = "# incident 42\n\
+            0x1282e09bb5118f619da81b6a24c97999e7057ee9975628562c7cecbb4aa9f5af\n\
+            \n  0xd253e3b563bf7b8894da2a69db836a4e98e337157564483d8ac72117df355a9d  \n";
+        let hashes = parse_hashes(input.as_bytes()).unwrap();
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes[1].to_string()[..6], *"0xd253");
+
+        let err = parse_hashes("0x12\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+}
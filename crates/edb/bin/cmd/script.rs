@@ -1,20 +1,267 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use alloy_primitives::{Address, Bytes, TxKind, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types::{TransactionInput, TransactionRequest};
 use clap::Parser;
-use eyre::Result;
+use edb_debug_backend::artifact::debug::DebugArtifact;
+use edb_debug_frontend::DebugFrontend;
+use eyre::{ensure, eyre, Result};
+use foundry_evm::{fork::database::ForkedDatabase, utils::new_evm_with_inspector};
+use revm::{inspectors::NoOpInspector, primitives::EnvWithHandlerCfg};
+use serde::Deserialize;
 
-use crate::opts::{EtherscanOpts, RpcOpts};
+use crate::{
+    opts::{EtherscanOpts, EvmOpts, RpcOpts, UiOpts},
+    utils::evm::{fill_tx_env_with_request, setup_block_env, setup_fork_db},
+};
 
-/// CLI arguments for `edb replay`.
+/// CLI arguments for `edb script`.
 #[derive(Clone, Debug, Parser)]
 pub struct ScriptArgs {
+    /// The path of the Foundry script to run, e.g. `script/Deploy.s.sol`.
+    pub path: PathBuf,
+
+    /// The signature of the function to call in the script.
+    #[arg(long, short, default_value = "run()")]
+    pub sig: String,
+
+    /// The root of the Foundry project.
+    ///
+    /// Defaults to the current working directory.
+    #[arg(long, value_name = "PATH")]
+    pub root: Option<PathBuf>,
+
+    /// Uses an existing broadcast file (e.g. `run-latest.json`) instead of running the script.
+    #[arg(long, value_name = "PATH")]
+    pub broadcast_file: Option<PathBuf>,
+
+    /// The block number to fork from. Defaults to the latest block.
+    #[arg(long, value_name = "BLOCK")]
+    pub fork_block_number: Option<u64>,
+
+    /// Extra arguments passed to `forge script` as-is.
+    #[arg(last = true)]
+    pub forge_args: Vec<String>,
+
     #[command(flatten)]
     pub etherscan: EtherscanOpts,
 
     #[command(flatten)]
-    rpc: RpcOpts,
+    pub rpc: RpcOpts,
+
+    #[command(flatten)]
+    pub evm: EvmOpts,
+
+    #[command(flatten)]
+    pub ui: UiOpts,
+}
+
+/// A broadcast file produced by `forge script`.
+#[derive(Debug, Deserialize)]
+struct Broadcast {
+    transactions: Vec<BroadcastTransaction>,
+}
+
+/// A single transaction recorded in a broadcast file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BroadcastTransaction {
+    #[serde(default)]
+    contract_name: Option<String>,
+    #[serde(default)]
+    function: Option<String>,
+    transaction: BroadcastTransactionRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct BroadcastTransactionRequest {
+    from: Address,
+    #[serde(default)]
+    to: Option<Address>,
+    #[serde(default)]
+    gas: Option<U256>,
+    #[serde(default)]
+    value: Option<U256>,
+    // older versions of forge use `data` instead of `input`
+    #[serde(default, alias = "data")]
+    input: Bytes,
+}
+
+impl BroadcastTransactionRequest {
+    /// Converts the broadcasted transaction into a request. Its fees and nonce are left out: the
+    /// script is simulated at the base fee, and nonces are checked by forge when simulating it.
+    fn to_request(&self) -> TransactionRequest {
+        TransactionRequest {
+            from: Some(self.from),
+            to: Some(self.to.map_or(TxKind::Create, TxKind::Call)),
+            gas: self.gas.map(|gas| gas.saturating_to()),
+            value: self.value,
+            input: TransactionInput::new(self.input.clone()),
+            ..Default::default()
+        }
+    }
 }
 
 impl ScriptArgs {
     pub async fn run(self) -> Result<()> {
-        unimplemented!()
+        ensure!(
+            !self.ui.machine_interface && self.ui.follow.is_none(),
+            "the machine interface and followed sessions debug a single transaction, not a whole \
+             script"
+        );
+        let root = match &self.root {
+            Some(root) => root.clone(),
+            None => std::env::current_dir()?,
+        };
+        let fork_url = self.rpc.url(true)?.unwrap().to_string();
+        let provider = self.rpc.provider()?;
+        let chain_id = provider.get_chain_id().await?;
+        ensure!(
+            self.etherscan.chain.map_or(true, |chain| chain.id() == chain_id),
+            "inconsistent chain id"
+        );
+        let fork_block_number = match self.fork_block_number {
+            Some(bn) => bn,
+            None => provider.get_block_number().await?,
+        };
+
+        // step 1. simulate the script with forge, unless a broadcast file is given
+        let broadcast_file = match &self.broadcast_file {
+            Some(path) => path.clone(),
+            None => self.simulate(&root, &fork_url, fork_block_number, chain_id)?,
+        };
+        let broadcast: Broadcast = serde_json::from_str(&std::fs::read_to_string(&broadcast_file)?)
+            .map_err(|e| {
+                eyre!("failed to parse broadcast file {}: {e}", broadcast_file.display())
+            })?;
+        ensure!(
+            !broadcast.transactions.is_empty(),
+            "the script does not broadcast any transaction"
+        );
+
+        // step 2. set up the fork at the block the script was simulated against
+        let mut db =
+            setup_fork_db(provider.clone(), &fork_url, Some(fork_block_number), None).await?;
+        let mut env =
+            setup_block_env(provider, Some(fork_block_number), self.evm.spec_id()).await?;
+
+        // step 3. analyze each broadcasted transaction in sequence, committing its state changes
+        // before moving on to the next one
+        let total = broadcast.transactions.len();
+        let mut artifacts = Vec::with_capacity(total);
+        for (index, tx) in broadcast.transactions.iter().enumerate() {
+            let name = format!(
+                "{} {}",
+                tx.contract_name.as_deref().unwrap_or("<unknown>"),
+                tx.function.as_deref().unwrap_or("<create>"),
+            );
+            println!("[{}/{total}] {name}", index + 1);
+
+            fill_tx_env_with_request(&mut env, &tx.transaction.to_request());
+            artifacts.push((name, self.analyze(&db, env.clone()).await?));
+
+            let mut evm = new_evm_with_inspector(&mut db, env.clone(), NoOpInspector);
+            let result = evm.transact_commit()?;
+            drop(evm);
+            if !result.is_success() {
+                warn!("broadcast transaction #{index} did not succeed: {result:?}");
+            }
+        }
+
+        // step 4. debug all the transactions at once, each in its own session tab
+        let mut frontend = DebugFrontend::builder()
+            .theme(self.ui.theme())
+            .sync_dir(self.ui.sync_dir())
+            .step_into_getters(self.ui.step_into_getters)
+            .build_sessions(artifacts);
+        frontend.render().await?;
+
+        Ok(())
+    }
+
+    /// Analyze a single broadcasted transaction on top of the given database.
+    async fn analyze(&self, db: &ForkedDatabase, env: EnvWithHandlerCfg) -> Result<DebugArtifact> {
+        let builder = self.ui.configure(self.evm.configure(self.etherscan.backend_builder()?));
+        let backend = builder.build::<ForkedDatabase>(db, env)?;
+        backend.analyze().await
+    }
+
+    /// Run `forge script` against the fork without broadcasting, and return the path of the
+    /// resulting dry-run broadcast file.
+    fn simulate(
+        &self,
+        root: &Path,
+        fork_url: &str,
+        fork_block_number: u64,
+        chain_id: u64,
+    ) -> Result<PathBuf> {
+        let status = Command::new("forge")
+            .current_dir(root)
+            .arg("script")
+            .arg(&self.path)
+            .args(["--sig", &self.sig])
+            .args(["--fork-url", fork_url])
+            .args(["--fork-block-number", &fork_block_number.to_string()])
+            .args(&self.forge_args)
+            .status()
+            .map_err(|e| eyre!("failed to run forge, is it installed? ({e})"))?;
+        ensure!(status.success(), "forge script failed with {status}");
+
+        let script_name = self
+            .path
+            .file_name()
+            .ok_or_else(|| eyre!("invalid script path: {}", self.path.display()))?;
+        let broadcast_file = root
+            .join("broadcast")
+            .join(script_name)
+            .join(chain_id.to_string())
+            .join("dry-run")
+            .join("run-latest.json");
+        ensure!(
+            broadcast_file.exists(),
+            "cannot find the broadcast file at {}, please specify it with --broadcast-file",
+            broadcast_file.display()
+        );
+        Ok(broadcast_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::primitives::Env;
+
+    use super::*;
+
+    #[test]
+    fn test_fill_tx_env_from_broadcast() {
+        let tx: BroadcastTransactionRequest = serde_json::from_str(
+            r#"{
+                "from": "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+                "to": null,
+                "gas": "0x5208",
+                "value": "0x1",
+                "data": "0x1234"
+            }"#,
+        )
+        .unwrap();
+
+        let mut env = Env::default();
+        env.block.basefee = U256::from(7);
+        env.tx.gas_priority_fee = Some(U256::from(2));
+        env.tx.nonce = Some(3);
+        fill_tx_env_with_request(&mut env, &tx.to_request());
+
+        assert_eq!(env.tx.caller, tx.from);
+        assert_eq!(env.tx.transact_to, TxKind::Create);
+        assert_eq!(env.tx.gas_limit, 21_000);
+        assert_eq!(env.tx.value, U256::from(1));
+        assert_eq!(env.tx.data, tx.input);
+        assert_eq!(env.tx.gas_price, U256::from(7));
+        assert_eq!(env.tx.gas_priority_fee, None);
+        assert_eq!(env.tx.nonce, None);
     }
 }
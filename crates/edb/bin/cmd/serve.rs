@@ -0,0 +1,38 @@
+use std::net::SocketAddr;
+
+use clap::Parser;
+use eyre::Result;
+
+use super::replay::ReplayArgs;
+use crate::utils::{print_warnings, session_hash::env_hash, web};
+
+/// CLI arguments for `edb serve`.
+#[derive(Clone, Debug, Parser)]
+pub struct ServeArgs {
+    #[command(flatten)]
+    pub replay: ReplayArgs,
+
+    /// Serves the browser-based frontend, which renders the trace, source and storage panes and
+    /// is driven over a WebSocket API. This is the only frontend served for now.
+    #[arg(long, required = true)]
+    pub web: bool,
+
+    /// The address the frontend is served at.
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8547")]
+    pub listen: SocketAddr,
+}
+
+impl ServeArgs {
+    pub async fn run(self) -> Result<()> {
+        let (db, env, warnings) = self.replay.prepare(None).await?;
+        let id = env_hash(&env);
+        let mut artifact = self.replay.analyze(&db, env).await?;
+        artifact.warnings.extend(warnings);
+        print_warnings(&artifact.warnings);
+        let name = match artifact.debug_arena.first() {
+            Some(node) => artifact.address_label(&node.address),
+            None => self.replay.tx_hash.to_string(),
+        };
+        web::serve(self.listen, &name, &artifact, id).await
+    }
+}
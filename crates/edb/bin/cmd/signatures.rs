@@ -0,0 +1,54 @@
+use clap::Parser;
+use edb_debug_backend::{
+    analysis::{
+        eip712::TypeRegistry,
+        signature::{ecrecover_calls, verify_signature},
+    },
+    export::{calltree::call_frames, signature::signature_report},
+};
+use eyre::Result;
+
+use super::replay::ReplayArgs;
+use crate::utils::print_warnings;
+
+/// CLI arguments for `edb check-signatures`.
+#[derive(Clone, Debug, Parser)]
+pub struct CheckSignaturesArgs {
+    #[command(flatten)]
+    pub replay: ReplayArgs,
+
+    /// Only checks the signature verified by the `ecrecover` call at the given step of the whole
+    /// execution, rather than every signature.
+    #[arg(long, value_name = "STEP")]
+    pub step: Option<usize>,
+}
+
+impl CheckSignaturesArgs {
+    pub async fn run(self) -> Result<()> {
+        let (db, env, warnings) = self.replay.prepare(None).await?;
+        let mut artifact = self.replay.analyze(&db, env).await?;
+        artifact.warnings.extend(warnings);
+        print_warnings(&artifact.warnings);
+
+        let mut calls = ecrecover_calls(&artifact);
+        if let Some(step) = self.step {
+            calls.retain(|call| call.step == step);
+            eyre::ensure!(!calls.is_empty(), "no signature is checked at step {step}");
+        }
+        if calls.is_empty() {
+            println!("No signature is checked in the transaction.");
+            return Ok(());
+        }
+
+        let frames = call_frames(&artifact);
+        let registry = TypeRegistry::new(&artifact);
+        for (i, call) in calls.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            let report = verify_signature(&artifact, &frames, &registry, call);
+            print!("{}", signature_report(&artifact, &report));
+        }
+        Ok(())
+    }
+}
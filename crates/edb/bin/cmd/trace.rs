@@ -0,0 +1,216 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+};
+
+use alloy_primitives::Address;
+use clap::{Parser, ValueEnum};
+use edb_debug_backend::{
+    analysis::{
+        allowance::allowance_audit, funds::FundsFlow, layout::code_layouts, price::usd_prices,
+    },
+    export::{
+        allowance::allowance_report,
+        calltree::call_frames,
+        cast::write_cast_trace,
+        chrome::write_chrome_trace,
+        dependency::external_dependency_report,
+        flamegraph::write_flamegraph,
+        funds::write_funds_flow,
+        lcov::write_lcov,
+        matrix::{interaction_matrix_csv, interaction_matrix_table},
+        tenderly::write_tenderly_trace,
+    },
+};
+use eyre::Result;
+
+use super::replay::ReplayArgs;
+use crate::utils::{
+    fixture::{execute_touching, serialize_fixture, touched_prestate, FixtureFormat},
+    print_warnings,
+    session_hash::SessionHash,
+};
+
+/// The format of the interaction matrix.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum MatrixFormat {
+    /// Plain-text tables.
+    #[default]
+    Table,
+    /// One row per pair of accounts and kind of interaction.
+    Csv,
+}
+
+/// CLI arguments for `edb trace`.
+#[derive(Clone, Debug, Parser)]
+pub struct TraceArgs {
+    #[command(flatten)]
+    pub replay: ReplayArgs,
+
+    /// Exports the call timeline as a Chrome `trace_event` JSON file, which can be opened in
+    /// `chrome://tracing` or Perfetto. Gas is used as the duration metric.
+    #[arg(long, value_name = "PATH")]
+    pub chrome_trace: Option<PathBuf>,
+
+    /// Exports the call trace in the text format of `cast run --trace`.
+    #[arg(long, value_name = "PATH")]
+    pub cast_trace: Option<PathBuf>,
+
+    /// Exports the call trace in the JSON schema of Tenderly's call traces.
+    #[arg(long, value_name = "PATH")]
+    pub tenderly_trace: Option<PathBuf>,
+
+    /// Exports a flamegraph of the gas consumed by each call frame as an SVG image.
+    #[arg(long, value_name = "PATH")]
+    pub flamegraph: Option<PathBuf>,
+
+    /// Exports the source lines and branches exercised by the transaction as an lcov report.
+    #[arg(long, value_name = "PATH")]
+    pub lcov: Option<PathBuf>,
+
+    /// Exports the ether and ERC-20 tokens moved by the transaction as a plain-text report.
+    #[arg(long, value_name = "PATH")]
+    pub funds_flow: Option<PathBuf>,
+
+    /// Annotates the funds flow report with approximate USD values, read from Chainlink price
+    /// feeds at the replayed block (Ethereum mainnet only).
+    #[arg(long, requires = "funds_flow")]
+    pub prices: bool,
+
+    /// Exports which account wrote to which account's storage and sent value to whom,
+    /// aggregated over the transaction.
+    #[arg(long, value_name = "PATH")]
+    pub write_matrix: Option<PathBuf>,
+
+    /// The format of the interaction matrix.
+    #[arg(long, value_enum, default_value_t, requires = "write_matrix")]
+    pub matrix_format: MatrixFormat,
+
+    /// Exports the reads of the environment performed by the transaction, e.g., the block
+    /// timestamp, the balances of other accounts and oracle prices, as a plain-text report.
+    #[arg(long, value_name = "PATH")]
+    pub dependencies: Option<PathBuf>,
+
+    /// Exports the size and layout of the code of the touched contracts (runtime and init code
+    /// sizes, code, data and metadata bytes, and immutables) as JSON.
+    #[arg(long, value_name = "PATH")]
+    pub code_layout: Option<PathBuf>,
+
+    /// Exports the accounts, code and storage read by the transaction, as they were before it,
+    /// so that it can be reproduced locally without an archive node.
+    #[arg(long, value_name = "PATH")]
+    pub state_fixture: Option<PathBuf>,
+
+    /// The format of the state fixture.
+    #[arg(long, value_enum, default_value_t, requires = "state_fixture")]
+    pub fixture_format: FixtureFormat,
+
+    /// Lists who read, set, and spent the allowances of an owner on an ERC-20 token during the
+    /// transaction (`approve`, `permit`, `transferFrom`, and the allowance slots), with the
+    /// callers and amounts.
+    #[arg(long, num_args = 2, value_names = ["TOKEN", "OWNER"])]
+    pub allowance_audit: Option<Vec<Address>>,
+
+    /// Prints a hash of the replayed inputs (environment, state read, and transaction) and of the
+    /// results, so that others can check they replayed the identical execution.
+    #[arg(long)]
+    pub session_hash: bool,
+}
+
+impl TraceArgs {
+    pub async fn run(self) -> Result<()> {
+        let (db, env, warnings) = self.replay.prepare(None).await?;
+        let mut artifact = self.replay.analyze(&db, env.clone()).await?;
+        artifact.warnings.extend(warnings);
+        print_warnings(&artifact.warnings);
+
+        if let Some(path) = &self.chrome_trace {
+            write_chrome_trace(&artifact, BufWriter::new(File::create(path)?))?;
+            println!("Chrome trace written to {}", path.display());
+        }
+
+        if let Some(path) = &self.cast_trace {
+            write_cast_trace(&artifact, BufWriter::new(File::create(path)?))?;
+            println!("Cast trace written to {}", path.display());
+        }
+
+        if let Some(path) = &self.tenderly_trace {
+            write_tenderly_trace(&artifact, BufWriter::new(File::create(path)?))?;
+            println!("Tenderly trace written to {}", path.display());
+        }
+
+        if let Some(path) = &self.flamegraph {
+            let title = format!("Gas flamegraph of {}", self.replay.tx_hash);
+            write_flamegraph(&artifact, &title, BufWriter::new(File::create(path)?))?;
+            println!("Flamegraph written to {}", path.display());
+        }
+
+        if let Some(path) = &self.lcov {
+            write_lcov(&artifact, BufWriter::new(File::create(path)?))?;
+            println!("Coverage report written to {}", path.display());
+        }
+
+        if let Some(path) = &self.funds_flow {
+            let prices = if self.prices {
+                let flow = FundsFlow::new(&artifact);
+                let assets: BTreeSet<_> = flow.transfers.iter().map(|t| t.asset).collect();
+                usd_prices(&db, &env, assets)
+            } else {
+                HashMap::new()
+            };
+            write_funds_flow(&artifact, &prices, BufWriter::new(File::create(path)?))?;
+            println!("Funds flow report written to {}", path.display());
+        }
+
+        if let Some(path) = &self.write_matrix {
+            let matrix = match self.matrix_format {
+                MatrixFormat::Table => interaction_matrix_table(&artifact),
+                MatrixFormat::Csv => interaction_matrix_csv(&artifact),
+            };
+            std::fs::write(path, matrix)?;
+            println!("Interaction matrix written to {}", path.display());
+        }
+
+        if let Some(path) = &self.dependencies {
+            std::fs::write(path, external_dependency_report(&artifact))?;
+            println!("External dependency report written to {}", path.display());
+        }
+
+        if let Some(path) = &self.code_layout {
+            let layouts = code_layouts(&artifact, &call_frames(&artifact));
+            serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &layouts)?;
+            println!("Code layout of {} contracts written to {}", layouts.len(), path.display());
+        }
+
+        // the transaction is executed again to collect the state it reads, which only some of the
+        // outputs need
+        let touched = if self.state_fixture.is_some() || self.session_hash {
+            Some(execute_touching(&db, env.clone())?)
+        } else {
+            None
+        };
+
+        if let Some(path) = &self.state_fixture {
+            let (prestate, _) = touched.as_ref().expect("the touched state is collected");
+            let state = touched_prestate(prestate);
+            let fixture = serialize_fixture(&state, self.fixture_format);
+            serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &fixture)?;
+            println!("State fixture of {} accounts written to {}", state.len(), path.display());
+        }
+
+        if let Some([token, owner]) = self.allowance_audit.as_deref() {
+            let frames = call_frames(&artifact);
+            let uses = allowance_audit(&artifact, &frames, env.tx.caller, *token, *owner);
+            print!("{}", allowance_report(&artifact, *token, *owner, &uses));
+        }
+
+        if self.session_hash {
+            let (prestate, result) = touched.as_ref().expect("the touched state is collected");
+            println!("Session hash: {}", SessionHash::new(&env, prestate, result));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    path::PathBuf,
+    thread::JoinHandle,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use alloy_primitives::B256;
+use clap::Parser;
+use edb_utils::cache::CachePath;
+use eyre::{ensure, eyre, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The GitHub API endpoint of the latest release.
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/MedGa-eth/EDB/releases/latest";
+
+/// How often the startup check queries GitHub. The last result is cached in between.
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The timeout of the startup check, which must never hold up the debugger.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// CLI arguments for `edb update`.
+#[derive(Clone, Debug, Parser)]
+pub struct UpdateArgs {
+    /// Only checks whether a newer version is available, without installing it.
+    #[arg(long)]
+    pub check: bool,
+
+    /// Reinstalls the latest release even if the current version is up to date.
+    #[arg(long, conflicts_with = "check")]
+    pub force: bool,
+}
+
+/// A release published on GitHub.
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+    /// The digest of the asset computed by GitHub, e.g., `sha256:<hex>`, for the assets uploaded
+    /// since it is computed.
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+impl Release {
+    fn version(&self) -> Result<Version> {
+        Version::parse(self.tag_name.trim_start_matches('v'))
+            .map_err(|e| eyre!("invalid release tag {}: {e}", self.tag_name))
+    }
+
+    /// Returns the prebuilt binary for the current platform, named `edb-<os>-<arch>`.
+    fn asset(&self) -> Option<&ReleaseAsset> {
+        use std::env::consts::{ARCH, EXE_SUFFIX, OS};
+        let name = format!("edb-{OS}-{ARCH}{EXE_SUFFIX}");
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    /// Returns the expected SHA-256 of the asset, from its digest computed by GitHub, or else
+    /// from the `<name>.sha256` checksum file published along with it.
+    async fn sha256(&self, asset: &ReleaseAsset) -> Result<B256> {
+        if let Some(digest) = asset.digest.as_deref().and_then(|d| d.strip_prefix("sha256:")) {
+            return parse_sha256(digest);
+        }
+        let name = format!("{}.sha256", asset.name);
+        let checksum = self.assets.iter().find(|a| a.name == name).ok_or_else(|| {
+            eyre!(
+                "release {} publishes no checksum of {}, refusing to install it unverified: {}",
+                self.tag_name,
+                asset.name,
+                self.html_url
+            )
+        })?;
+        let content = client(None)?
+            .get(&checksum.browser_download_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        // the format of `sha256sum`, i.e., the digest followed by the file name
+        parse_sha256(content.split_whitespace().next().unwrap_or_default())
+    }
+}
+
+fn parse_sha256(hex: &str) -> Result<B256> {
+    hex.parse().map_err(|e| eyre!("invalid SHA-256 checksum {hex}: {e}"))
+}
+
+/// Checks the downloaded binary against its expected SHA-256.
+fn verify_sha256(binary: &[u8], expected: B256) -> Result<()> {
+    let actual = B256::from_slice(&Sha256::digest(binary));
+    ensure!(
+        actual == expected,
+        "the download is corrupted: expected SHA-256 {expected}, got {actual}"
+    );
+    Ok(())
+}
+
+impl UpdateArgs {
+    pub async fn run(self) -> Result<()> {
+        let current = current_version();
+        let release = fetch_latest_release(None).await?;
+        let latest = release.version()?;
+        // record the result, so that the startup check does not query again
+        VersionCheck::now(&latest).save();
+
+        if latest <= current && !self.force {
+            println!("edb {current} is up to date");
+            return Ok(());
+        }
+        if self.check {
+            println!("edb {latest} is available (current: {current}): {}", release.html_url);
+            return Ok(());
+        }
+
+        let asset = release.asset().ok_or_else(|| {
+            eyre!(
+                "no prebuilt binary for {}-{} in release {}, please update manually: {}",
+                std::env::consts::OS,
+                std::env::consts::ARCH,
+                release.tag_name,
+                release.html_url
+            )
+        })?;
+        let sha256 = release.sha256(asset).await?;
+        println!("Downloading {}...", asset.browser_download_url);
+        let binary = client(None)?
+            .get(&asset.browser_download_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        verify_sha256(&binary, sha256)?;
+        replace_current_exe(&binary)?;
+        println!("edb updated from {current} to {latest}");
+
+        Ok(())
+    }
+}
+
+/// The result of the last version check, cached in `~/.edb/version-check.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionCheck {
+    /// Seconds since the Unix epoch.
+    checked_at: u64,
+    latest: String,
+}
+
+impl VersionCheck {
+    fn now(latest: &Version) -> Self {
+        let checked_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        Self { checked_at, latest: latest.to_string() }
+    }
+
+    fn path() -> Option<PathBuf> {
+        Some(CachePath::edb_dir()?.join("version-check.json"))
+    }
+
+    fn load() -> Option<Self> {
+        serde_json::from_str(&std::fs::read_to_string(Self::path()?).ok()?).ok()
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else { return };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::from);
+        if let Err(e) = json.and_then(|json| std::fs::write(&path, json)) {
+            debug!("failed to save the version check: {e}");
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        now.saturating_sub(self.checked_at) < CHECK_INTERVAL.as_secs()
+    }
+}
+
+/// Starts checking for a newer release in the background, unless the check has been made
+/// recently. The handle returns the newer version, if any, so that the caller can tell the user
+/// once the debugger has exited.
+pub fn spawn_version_check() -> JoinHandle<Option<Version>> {
+    std::thread::spawn(|| {
+        let latest = match VersionCheck::load() {
+            Some(check) if check.is_fresh() => Version::parse(&check.latest).ok()?,
+            _ => {
+                let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().ok()?;
+                let release = rt.block_on(fetch_latest_release(Some(CHECK_TIMEOUT))).ok()?;
+                let latest = release.version().ok()?;
+                VersionCheck::now(&latest).save();
+                latest
+            }
+        };
+        (latest > current_version()).then_some(latest)
+    })
+}
+
+fn current_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("invalid package version")
+}
+
+fn client(timeout: Option<Duration>) -> Result<reqwest::Client> {
+    let mut builder =
+        reqwest::Client::builder().user_agent(concat!("edb/", env!("CARGO_PKG_VERSION")));
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    Ok(builder.build()?)
+}
+
+async fn fetch_latest_release(timeout: Option<Duration>) -> Result<Release> {
+    let release = client(timeout)?
+        .get(LATEST_RELEASE_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(release)
+}
+
+/// Replaces the running executable with the downloaded binary.
+///
+/// The binary is written next to the executable and then renamed over it, so that a failed
+/// download never leaves a broken installation behind.
+fn replace_current_exe(binary: &[u8]) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    let tmp = exe.with_extension("new");
+    std::fs::write(&tmp, binary).map_err(|e| {
+        eyre!("failed to write {}: {e}, is edb installed elsewhere?", tmp.display())
+    })?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // windows does not allow replacing a running executable, but allows renaming it
+    #[cfg(windows)]
+    std::fs::rename(&exe, exe.with_extension("old"))?;
+
+    std::fs::rename(&tmp, &exe)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_version() {
+        let release =
+            Release { tag_name: "v1.2.3".to_string(), html_url: String::new(), assets: vec![] };
+        assert_eq!(release.version().unwrap(), Version::new(1, 2, 3));
+        assert!(release.asset().is_none());
+    }
+
+    #[test]
+    fn test_verify_sha256() {
+        let expected =
+            parse_sha256("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+                .unwrap();
+        verify_sha256(b"hello", expected).unwrap();
+        assert!(verify_sha256(b"hello!", expected).is_err());
+        assert!(parse_sha256("not a checksum").is_err());
+    }
+}
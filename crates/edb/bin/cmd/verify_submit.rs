@@ -0,0 +1,344 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use alloy_primitives::{hex, keccak256, Address, Bytes, B256};
+use alloy_provider::Provider;
+use clap::{Parser, ValueEnum};
+use edb_debug_backend::{
+    artifact::verification::{replicate_settings, VerificationStatus},
+    solc::SolcManager,
+};
+use eyre::{eyre, Result};
+use foundry_block_explorers::verify::{CodeFormat, VerifyContract};
+use foundry_compilers::{
+    artifacts::{output_selection::OutputSelection, CompilerOutput, Settings, SolcInput, Source},
+    solc::SolcLanguage,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::opts::{EtherscanOpts, RpcOpts};
+
+/// The API of the public Sourcify instance.
+const SOURCIFY_URL: &str = "https://sourcify.dev/server";
+
+/// How often, and how many times, the status of an Etherscan submission is checked.
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+const STATUS_ATTEMPTS: usize = 12;
+
+/// The service the verification is submitted to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Verifier {
+    /// The explorer of the chain, as configured for `edb replay`.
+    #[default]
+    Etherscan,
+    Sourcify,
+    /// Both the explorer and Sourcify.
+    All,
+}
+
+/// CLI arguments for `edb verify-submit`.
+#[derive(Clone, Debug, Parser)]
+pub struct VerifySubmitArgs {
+    /// The address of the deployed contract.
+    pub address: Address,
+
+    /// The Foundry artifact of the contract, e.g. `out/Counter.sol/Counter.json`.
+    pub artifact: PathBuf,
+
+    /// The root of the Foundry project, which the source paths of the artifact are relative to.
+    ///
+    /// Defaults to the current working directory.
+    #[arg(long, value_name = "PATH")]
+    pub root: Option<PathBuf>,
+
+    /// The ABI-encoded arguments of the constructor, if it takes any.
+    #[arg(long, value_name = "HEX")]
+    pub constructor_args: Option<Bytes>,
+
+    /// The service to submit the verification to.
+    #[arg(long, value_enum, default_value_t)]
+    pub verifier: Verifier,
+
+    /// Only checks that the sources reproduce the deployed code, without submitting them.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    #[command(flatten)]
+    pub etherscan: EtherscanOpts,
+
+    #[command(flatten)]
+    pub rpc: RpcOpts,
+}
+
+/// The metadata of a compilation, as embedded by Forge in its artifacts (`rawMetadata`).
+#[derive(Debug, Deserialize)]
+struct SolcMetadata {
+    compiler: CompilerMetadata,
+    settings: Value,
+    sources: BTreeMap<String, SourceMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompilerMetadata {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceMetadata {
+    keccak256: B256,
+    /// The source code, only embedded with `use_literal_content`.
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForgeArtifact {
+    raw_metadata: Option<String>,
+}
+
+/// A compilation replicated from the metadata of a Forge artifact.
+#[derive(Debug)]
+struct Replication {
+    /// The source file of the contract, e.g., `src/Counter.sol`.
+    path: String,
+    name: String,
+    /// The full version of the compiler, e.g., `0.8.20+commit.a1b79de6`.
+    version: String,
+    input: SolcInput,
+    raw_metadata: String,
+}
+
+impl VerifySubmitArgs {
+    pub async fn run(self) -> Result<()> {
+        let root = match &self.root {
+            Some(root) => root.clone(),
+            None => std::env::current_dir()?,
+        };
+        let content = std::fs::read_to_string(&self.artifact)
+            .map_err(|e| eyre!("failed to read {}: {e}", self.artifact.display()))?;
+        let mut replication = Replication::from_artifact(&content, &root)?;
+
+        // the metadata of the artifact are checked to reproduce the deployed code, and the
+        // alternative settings tried otherwise, as for the contracts fetched from explorers
+        let provider = self.rpc.provider()?;
+        let onchain = provider.get_code_at(self.address).await?;
+        eyre::ensure!(!onchain.is_empty(), "there is no contract deployed at {}", self.address);
+        let version = replication.version.parse()?;
+        let compiler =
+            SolcManager::default().offline(self.etherscan.solc_offline).get(&version).await?;
+        let output = compiler.compile_exact(&replication.input)?;
+        let (input, output, status) =
+            replicate_settings(&compiler, replication.input, output, &onchain, &replication.name);
+        replication.input = input;
+        // Sourcify checks the metadata against the deployed code, so that the metadata are the
+        // ones of the settings which reproduce it, rather than the ones of the artifact
+        if let Some(metadata) = output_metadata(&output, &replication.path, &replication.name) {
+            replication.raw_metadata = metadata;
+        }
+        eyre::ensure!(
+            status != VerificationStatus::Mismatch,
+            "the sources of {} do not reproduce the code deployed at {}, are the artifacts up to \
+             date?",
+            replication.path,
+            self.address
+        );
+        println!("The sources of {} reproduce the deployed code ({status})", replication.path);
+        if self.dry_run {
+            return Ok(());
+        }
+
+        if matches!(self.verifier, Verifier::Etherscan | Verifier::All) {
+            self.submit_to_etherscan(&replication).await?;
+        }
+        if matches!(self.verifier, Verifier::Sourcify | Verifier::All) {
+            let chain_id = provider.get_chain_id().await?;
+            self.submit_to_sourcify(&replication, chain_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn submit_to_etherscan(&self, replication: &Replication) -> Result<()> {
+        let client = self.etherscan.client()?;
+        let verify = VerifyContract::new(
+            self.address,
+            format!("{}:{}", replication.path, replication.name),
+            serde_json::to_string(&replication.input)?,
+            format!("v{}", replication.version),
+        )
+        .code_format(CodeFormat::StandardJsonInput)
+        .constructor_arguments(self.constructor_args.as_ref().map(hex::encode));
+
+        let response = client.submit_contract_verification(&verify).await?;
+        if response.result.contains("already verified") {
+            println!("{} is already verified on the explorer", self.address);
+            return Ok(());
+        }
+        eyre::ensure!(
+            response.status == "1",
+            "the explorer rejected the verification: {} ({})",
+            response.result,
+            response.message
+        );
+
+        let guid = response.result;
+        println!("Submitted to the explorer (GUID {guid}), waiting for the result...");
+        for _ in 0..STATUS_ATTEMPTS {
+            tokio::time::sleep(STATUS_INTERVAL).await;
+            let status = client.check_contract_verification_status(&guid).await?;
+            if status.result.contains("Pending") {
+                continue;
+            }
+            eyre::ensure!(
+                status.status == "1" || status.result.contains("Already Verified"),
+                "the explorer failed to verify {}: {}",
+                self.address,
+                status.result
+            );
+            println!("{} is verified on the explorer: {}", self.address, status.result);
+            return Ok(());
+        }
+        println!(
+            "The explorer is still processing the verification, check it later with GUID {guid}"
+        );
+        Ok(())
+    }
+
+    async fn submit_to_sourcify(&self, replication: &Replication, chain_id: u64) -> Result<()> {
+        let mut files =
+            BTreeMap::from([("metadata.json".to_string(), replication.raw_metadata.clone())]);
+        for (path, source) in &replication.input.sources {
+            files.insert(path.display().to_string(), source.content.to_string());
+        }
+        let body = json!({
+            "address": self.address,
+            "chain": chain_id.to_string(),
+            "files": files,
+        });
+
+        let response = reqwest::Client::new()
+            .post(format!("{SOURCIFY_URL}/verify"))
+            .json(&body)
+            .send()
+            .await?;
+        let ok = response.status().is_success();
+        let response: Value = response.json().await?;
+        eyre::ensure!(
+            ok,
+            "Sourcify failed to verify {}: {}",
+            self.address,
+            response["error"].as_str().unwrap_or("unknown error")
+        );
+        let status = response["result"][0]["status"].as_str().unwrap_or("unknown");
+        println!("{} is verified on Sourcify ({status} match)", self.address);
+        Ok(())
+    }
+}
+
+impl Replication {
+    /// Replicates the compilation of a Forge artifact from its metadata, reading its sources
+    /// under the given root.
+    fn from_artifact(artifact: &str, root: &Path) -> Result<Self> {
+        let artifact: ForgeArtifact = serde_json::from_str(artifact)?;
+        let raw_metadata = artifact.raw_metadata.ok_or_else(|| {
+            eyre!("the artifact has no metadata, is `metadata` removed from `extra_output`?")
+        })?;
+        let mut metadata: SolcMetadata = serde_json::from_str(&raw_metadata)?;
+
+        let (path, name) = metadata.settings["compilationTarget"]
+            .as_object()
+            .and_then(|target| target.iter().next())
+            .and_then(|(path, name)| Some((path.clone(), name.as_str()?.to_string())))
+            .ok_or_else(|| eyre!("the metadata of the artifact has no compilation target"))?;
+        let settings = standard_json_settings(&mut metadata.settings)?;
+
+        let mut sources = BTreeMap::new();
+        for (source_path, source) in metadata.sources {
+            let content = match source.content {
+                Some(content) => content,
+                None => std::fs::read_to_string(root.join(&source_path))
+                    .map_err(|e| eyre!("failed to read {source_path}: {e}"))?,
+            };
+            eyre::ensure!(
+                keccak256(content.as_bytes()) == source.keccak256,
+                "{source_path} changed since the artifact was built, please rebuild it"
+            );
+            sources.insert(PathBuf::from(source_path), Source::new(content));
+        }
+
+        let input = SolcInput::new(SolcLanguage::Solidity, sources, settings);
+        Ok(Self { path, name, version: metadata.compiler.version, input, raw_metadata })
+    }
+}
+
+/// Returns the metadata of the contract in the given output, i.e., of the settings the output was
+/// compiled with.
+fn output_metadata(output: &CompilerOutput, path: &str, name: &str) -> Option<String> {
+    let (_, contracts) =
+        output.contracts.iter().find(|(file, _)| Path::new(file) == Path::new(path))?;
+    Some(contracts.get(name)?.metadata.as_ref()?.raw_metadata.clone())
+}
+
+/// Converts the settings of the metadata into the ones of the standard JSON input: the
+/// compilation target is dropped, and the libraries are nested by file.
+fn standard_json_settings(settings: &mut Value) -> Result<Settings> {
+    let settings =
+        settings.as_object_mut().ok_or_else(|| eyre!("invalid settings in the metadata"))?;
+    settings.remove("compilationTarget");
+
+    if let Some(Value::Object(libraries)) = settings.remove("libraries") {
+        let mut nested: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
+        for (qualified, address) in libraries {
+            let (file, library) = qualified.rsplit_once(':').unwrap_or(("", &qualified));
+            nested.entry(file.to_string()).or_default().insert(library.to_string(), address);
+        }
+        settings.insert("libraries".to_string(), json!(nested));
+    }
+
+    let mut settings: Settings = serde_json::from_value(Value::Object(settings.clone()))?;
+    settings.output_selection = OutputSelection::complete_output_selection();
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replication() {
+        let root = std::env::temp_dir().join(format!("edb-verify-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let source = "contract Counter {}";
+        std::fs::write(root.join("src/Counter.sol"), source).unwrap();
+
+        let metadata = json!({
+            "compiler": { "version": "0.8.20+commit.a1b79de6" },
+            "language": "Solidity",
+            "settings": {
+                "compilationTarget": { "src/Counter.sol": "Counter" },
+                "evmVersion": "paris",
+                "libraries": { "src/Math.sol:Math": "0x00000000000000000000000000000000000000aa" },
+                "optimizer": { "enabled": true, "runs": 200 },
+                "remappings": [],
+            },
+            "sources": { "src/Counter.sol": { "keccak256": keccak256(source) } },
+        });
+        let artifact = json!({ "rawMetadata": metadata.to_string() }).to_string();
+
+        let replication = Replication::from_artifact(&artifact, &root).unwrap();
+        assert_eq!(
+            (replication.path.as_str(), replication.name.as_str()),
+            ("src/Counter.sol", "Counter")
+        );
+        assert_eq!(replication.input.settings.optimizer.runs, Some(200));
+        assert_eq!(replication.input.sources.len(), 1);
+
+        // the sources must be the ones the artifact was built from
+        std::fs::write(root.join("src/Counter.sol"), "contract Counter { }").unwrap();
+        assert!(Replication::from_artifact(&artifact, &root).is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -9,6 +9,7 @@ mod utils;
 use args::{EDBArgs, EDBSubcommand};
 use clap::Parser;
 use eyre::Result;
+use utils::network_fixtures::FixtureMode;
 
 fn main() -> Result<()> {
     utils::install_error_handler();
@@ -16,10 +17,53 @@ fn main() -> Result<()> {
     utils::enable_paint();
 
     let opts = EDBArgs::parse();
+    if opts.profile {
+        edb_utils::profile::enable();
+    }
+    if let Some(dir) = &opts.record_fixtures {
+        utils::network_fixtures::start(FixtureMode::Record, dir)?;
+    } else if let Some(dir) = &opts.replay_fixtures {
+        utils::network_fixtures::start(FixtureMode::Replay, dir)?;
+    }
 
-    match opts.cmd {
+    // the check runs in the background while debugging, and is reported once the debugger exits
+    let version_check = match opts.cmd {
+        EDBSubcommand::Explain(_) |
+        EDBSubcommand::Update(_) |
+        EDBSubcommand::Completions(_) |
+        EDBSubcommand::Complete(_) => None,
+        _ if opts.no_update_check || opts.replay_fixtures.is_some() => None,
+        _ => Some(cmd::update::spawn_version_check()),
+    };
+
+    let result = match opts.cmd {
         EDBSubcommand::Replay(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::Trace(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::Scan(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::ExportTest(cmd) => utils::block_on(cmd.run()),
         EDBSubcommand::Script(cmd) => utils::block_on(cmd.run()),
         EDBSubcommand::Test(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::Call(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::Proxy(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::Serve(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::VerifySubmit(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::CheckSignatures(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::Explain(cmd) => cmd.run(),
+        EDBSubcommand::Update(cmd) => utils::block_on(cmd.run()),
+        EDBSubcommand::Completions(cmd) => cmd.run(),
+        EDBSubcommand::Complete(cmd) => cmd.run(),
+    };
+
+    if let Some(report) = edb_utils::profile::report() {
+        eprint!("{report}");
     }
+
+    if let Some(Ok(Some(latest))) = version_check.filter(|h| h.is_finished()).map(|h| h.join()) {
+        eprintln!(
+            "edb {latest} is available (current: {}), run `edb update` to install it",
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    result
 }
@@ -1,14 +1,19 @@
-use std::ffi::OsStr;
+use std::{collections::BTreeMap, ffi::OsStr};
 
 use alloy_chains::{Chain, NamedChain};
 use clap::{
     builder::{PossibleValuesParser, TypedValueParser},
     Parser,
 };
-use eyre::Result;
-use serde::Serialize;
+use edb_debug_backend::{solc::SolcManager, DebugBackendBuilder};
+use edb_utils::cache::CachePath;
+use eyre::{eyre, Result};
+use foundry_block_explorers::Client;
+use serde::{Deserialize, Serialize};
 use strum::VariantNames;
 
+use crate::utils::network_fixtures;
+
 /// Custom Clap value parser for [`Chain`]s.
 ///
 /// Displays all possible chains when an invalid chain is provided.
@@ -49,10 +54,50 @@ impl TypedValueParser for ChainValueParser {
     }
 }
 
+/// The explorer of a chain, as configured in `~/.edb/explorers.toml`, e.g.:
+///
+/// ```toml
+/// [mainnet]
+/// key = "..."
+///
+/// [bsc]
+/// key = "..."
+/// url = "https://api.bscscan.com/api"
+/// ```
+///
+/// Chains are given by name or by EIP-155 chain ID.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct ExplorerConfig {
+    /// The API key.
+    pub key: Option<String>,
+    /// The URL of the etherscan-compatible API, if not the chain's default one.
+    pub url: Option<String>,
+}
+
+impl ExplorerConfig {
+    /// Loads the config of the chain from `~/.edb/explorers.toml`, if any.
+    pub fn load(chain: Chain) -> Result<Option<Self>> {
+        let Some(path) = CachePath::edb_explorer_config_file().filter(|p| p.exists()) else {
+            return Ok(None);
+        };
+        let content = std::fs::read_to_string(&path)?;
+        Self::parse(&content, chain).map_err(|e| eyre!("invalid {}: {e}", path.display()))
+    }
+
+    fn parse(content: &str, chain: Chain) -> Result<Option<Self>> {
+        let mut configs: BTreeMap<String, Self> = toml::from_str(content)?;
+        let keys = [chain.to_string(), chain.id().to_string()];
+        Ok(keys.iter().find_map(|key| configs.remove(key)))
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize, Parser)]
 pub struct EtherscanOpts {
     /// The Etherscan (or equivalent) API key.
-    #[arg(short = 'e', long = "etherscan-api-key", alias = "api-key", env = "ETHERSCAN_API_KEY")]
+    ///
+    /// If not given, the key configured for the chain in `~/.edb/explorers.toml` is used, and
+    /// then the `ETHERSCAN_API_KEY` environment variable.
+    #[arg(short = 'e', long = "etherscan-api-key", alias = "api-key")]
     #[serde(rename = "etherscan_api_key", skip_serializing_if = "Option::is_none")]
     pub key: Option<String>,
 
@@ -66,6 +111,12 @@ pub struct EtherscanOpts {
     )]
     #[serde(rename = "chain_id", skip_serializing_if = "Option::is_none")]
     pub chain: Option<Chain>,
+
+    /// Only compiles with the solc versions downloaded already under `~/.edb/solc`, and never
+    /// downloads any.
+    #[arg(long, env = "EDB_SOLC_OFFLINE")]
+    #[serde(skip)]
+    pub solc_offline: bool,
 }
 
 impl EtherscanOpts {
@@ -74,8 +125,83 @@ impl EtherscanOpts {
         self.key.as_ref().filter(|key| !key.trim().is_empty()).is_some()
     }
 
-    /// Returns the Etherscan API key.
+    /// Returns the Etherscan API key given on the command line.
     pub fn key(&self) -> Option<String> {
         self.key.as_ref().filter(|key| !key.trim().is_empty()).cloned()
     }
+
+    /// Returns the chain, along with the explorer key and API URL configured for it.
+    fn explorer(&self) -> Result<(Chain, String, Option<String>)> {
+        let chain = self.chain.unwrap_or_default();
+        let config = ExplorerConfig::load(chain)?.unwrap_or_default();
+        let key = self
+            .key()
+            .or(config.key)
+            .or_else(|| std::env::var("ETHERSCAN_API_KEY").ok())
+            .filter(|key| !key.trim().is_empty())
+            .unwrap_or_default();
+        // when recording or replaying fixtures, the explorer is reached through the fixture
+        // server, at the default API of the chain unless another one is configured
+        let url = match (config.url, network_fixtures::is_enabled()) {
+            (url, false) => url,
+            (Some(url), true) => Some(network_fixtures::etherscan_url(url)),
+            (None, true) => {
+                let (api_url, _) = chain
+                    .etherscan_urls()
+                    .ok_or_else(|| eyre!("the explorer of {chain} is unknown"))?;
+                Some(network_fixtures::etherscan_url(api_url.to_string()))
+            }
+        };
+        Ok((chain, key, url))
+    }
+
+    /// Returns a builder of the debug backend for the chain, using the explorer key and API URL
+    /// configured for it.
+    pub fn backend_builder(&self) -> Result<DebugBackendBuilder> {
+        let (chain, key, url) = self.explorer()?;
+        let mut builder = DebugBackendBuilder::default()
+            .chain(chain)
+            .etherscan_api_key(key)
+            .solc_manager(SolcManager::default().offline(self.solc_offline));
+        if network_fixtures::is_recording() {
+            builder = builder.no_cache();
+        }
+        Ok(match url {
+            Some(url) => builder.etherscan_api_url(url),
+            None => builder,
+        })
+    }
+
+    /// Returns a client of the explorer of the chain.
+    pub fn client(&self) -> Result<Client> {
+        let (chain, key, url) = self.explorer()?;
+        let builder = Client::builder().chain(chain)?.with_api_key(key);
+        let builder = match url {
+            Some(url) => builder.with_api_url(url)?,
+            None => builder,
+        };
+        Ok(builder.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explorer_config() {
+        let content = r#"
+            [mainnet]
+            key = "mainnet-key"
+
+            [56]
+            key = "bsc-key"
+            url = "https://api.bscscan.com/api"
+        "#;
+        let mainnet = ExplorerConfig::parse(content, Chain::mainnet()).unwrap().unwrap();
+        assert_eq!(mainnet.key.as_deref(), Some("mainnet-key"));
+        let bsc = ExplorerConfig::parse(content, Chain::from_id(56)).unwrap().unwrap();
+        assert_eq!(bsc.url.as_deref(), Some("https://api.bscscan.com/api"));
+        assert!(ExplorerConfig::parse(content, Chain::from_id(137)).unwrap().is_none());
+    }
 }
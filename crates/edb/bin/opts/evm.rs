@@ -0,0 +1,38 @@
+use anvil::Hardfork;
+use clap::Parser;
+use edb_debug_backend::{snapshot::SnapshotGranularity, DebugBackendBuilder};
+use revm::primitives::SpecId;
+
+/// Options of the EVM executing the transactions.
+#[derive(Clone, Debug, Default, Parser)]
+pub struct EvmOpts {
+    /// Executes the transactions under the rules of the given hardfork, e.g., `shanghai`.
+    ///
+    /// By default, the hardfork active at the replayed block is selected on known chains, and
+    /// the latest one on the others.
+    #[arg(long, value_name = "HARDFORK")]
+    pub hardfork: Option<Hardfork>,
+
+    /// Records the stack and memory at every `opcode`, or only at the calls (`call`), the
+    /// storage accesses (`sstore`), or the jump destinations where statements usually start
+    /// (`line`).
+    ///
+    /// Coarser granularities take less memory and start faster on long transactions. The stack
+    /// and memory of the other steps are recorded by re-executing the transaction against the
+    /// state it read when their call is stepped into, without fetching anything again, while the
+    /// analyses of the whole execution only see the recorded ones.
+    #[arg(long, value_name = "GRANULARITY", default_value_t = SnapshotGranularity::Opcode)]
+    pub snapshot_granularity: SnapshotGranularity,
+}
+
+impl EvmOpts {
+    /// Returns the spec overriding the automatic selection, if any.
+    pub fn spec_id(&self) -> Option<SpecId> {
+        self.hardfork.map(Into::into)
+    }
+
+    /// Applies the options to the builder of the debug backend.
+    pub fn configure(&self, builder: DebugBackendBuilder) -> DebugBackendBuilder {
+        builder.snapshot_granularity(self.snapshot_granularity)
+    }
+}
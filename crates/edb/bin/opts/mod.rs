@@ -1,5 +1,9 @@
 mod etherscan;
+mod evm;
 mod rpc;
+mod ui;
 
 pub use etherscan::EtherscanOpts;
+pub use evm::EvmOpts;
 pub use rpc::RpcOpts;
+pub use ui::UiOpts;
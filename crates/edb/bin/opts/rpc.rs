@@ -1,7 +1,10 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Arc};
 
 use clap::Parser;
 use eyre::Result;
+use foundry_common::provider::{ProviderBuilder, RetryProvider};
+
+use crate::utils::network_fixtures;
 
 const FLASHBOTS_URL: &str = "https://rpc.flashbots.net/fast";
 const LOCALHOST_URL: &str = "http://localhost:8545";
@@ -58,11 +61,24 @@ impl RpcOpts {
             (false, None) if fallback_to_default => Some(Cow::Borrowed(LOCALHOST_URL)),
             _ => None,
         };
-        Ok(url)
+        Ok(url.map(network_fixtures::rpc_url))
     }
 
     /// Returns the JWT secret.
     pub fn jwt(&self) -> Result<Option<Cow<'_, str>>> {
         Ok(self.jwt_secret.as_deref().map(Cow::Borrowed))
     }
+
+    /// Builds a provider for the RPC endpoint, honoring the rate limit and JWT settings.
+    pub fn provider(&self) -> Result<Arc<RetryProvider>> {
+        let url = self.url(true)?.unwrap();
+        let compute_units_per_second =
+            if self.no_rate_limit { Some(u64::MAX) } else { self.compute_units_per_second };
+        let mut builder =
+            ProviderBuilder::new(&url).compute_units_per_second_opt(compute_units_per_second);
+        if let Some(jwt) = self.jwt()? {
+            builder = builder.jwt(jwt.as_ref());
+        }
+        Ok(Arc::new(builder.build()?))
+    }
 }
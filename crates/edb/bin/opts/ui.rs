@@ -0,0 +1,83 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use clap::Parser;
+use edb_debug_backend::DebugBackendBuilder;
+use edb_debug_frontend::{ColorMode, Theme};
+use edb_utils::cache::CachePath;
+
+/// Options of the terminal UI.
+#[derive(Clone, Debug, Default, Parser)]
+pub struct UiOpts {
+    /// Enables the accessibility mode: high-contrast colors, and ASCII-only borders and symbols.
+    #[arg(long)]
+    pub accessible: bool,
+
+    /// Disables colors. Also enabled by setting the `NO_COLOR` environment variable.
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Draws borders and symbols with ASCII characters only.
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Renders the panes as plain text without any style, which works best with screen readers.
+    #[arg(long)]
+    pub plain: bool,
+
+    /// Drives the debugger with line-based JSON commands on stdin instead of the terminal UI,
+    /// writing the current position to stdout, for editor plugins.
+    #[arg(long, conflicts_with = "follow")]
+    pub machine_interface: bool,
+
+    /// Writes the current position to `location.json` in the directory, and reads the commands
+    /// appended to its `commands` file, for editor plugins.
+    ///
+    /// Defaults to `~/.edb/sync` if no directory is given.
+    #[arg(long, value_name = "DIR", num_args = 0..=1)]
+    pub sync_dir: Option<Option<PathBuf>>,
+
+    /// Follows, read-only, the position of a session served by `edb serve`, or shared with
+    /// `--share`, for the same transaction, e.g., `http://127.0.0.1:8547/`.
+    #[arg(long, value_name = "URL")]
+    pub follow: Option<String>,
+
+    /// Shares the position of the session at the address, so that others can follow it read-only,
+    /// from a browser or with `--follow`, e.g., while pair-debugging an incident.
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["follow", "machine_interface"])]
+    pub share: Option<SocketAddr>,
+
+    /// Steps into the calls to simple getters, i.e., cheap `STATICCALL`s making no sub-call,
+    /// rather than over them. They can always be stepped into with `J`.
+    #[arg(long)]
+    pub step_into_getters: bool,
+
+    /// A JSON list of flagged addresses (e.g., known attackers and mixers) to mark in the
+    /// session and the funds flow report. May be given multiple times.
+    ///
+    /// `~/.edb/flags.json` is always loaded if it exists.
+    #[arg(long = "flags", value_name = "PATH")]
+    pub flag_files: Vec<PathBuf>,
+}
+
+impl UiOpts {
+    /// Returns the theme of the terminal UI.
+    pub fn theme(&self) -> Theme {
+        let mut theme = if self.accessible { Theme::accessible() } else { Theme::from_env() };
+        if self.no_color {
+            theme.colors = ColorMode::None;
+        }
+        theme.ascii |= self.ascii;
+        theme.plain |= self.plain;
+        theme
+    }
+
+    /// Returns the directory the position is synchronized with an editor through, if any.
+    pub fn sync_dir(&self) -> Option<PathBuf> {
+        self.sync_dir.clone()?.or_else(CachePath::edb_sync_dir)
+    }
+
+    /// Applies the options to the builder of the debug backend.
+    pub fn configure(&self, builder: DebugBackendBuilder) -> DebugBackendBuilder {
+        self.flag_files.iter().fold(builder, |builder, path| builder.flag_file(path.clone()))
+    }
+}
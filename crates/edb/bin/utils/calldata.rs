@@ -0,0 +1,214 @@
+//! Calldata builder, which encodes a function call from its signature and arguments, or
+//! interactively from the ABI of the callee.
+
+use std::io::{self, BufRead, Write};
+
+use alloy_dyn_abi::{DynSolType, DynSolValue, JsonAbiExt, Specifier};
+use alloy_json_abi::{Function, JsonAbi};
+use alloy_primitives::{Address, Bytes, U256};
+use eyre::{bail, eyre, Result};
+
+/// Encodes a call to the function with the given signature, e.g. `transfer(address,uint256)`.
+pub fn encode_call(sig: &str, args: &[String]) -> Result<Bytes> {
+    let function = Function::parse(sig).map_err(|e| eyre!("invalid function signature: {e}"))?;
+    if function.inputs.len() != args.len() {
+        bail!("{} expects {} argument(s), got {}", sig, function.inputs.len(), args.len());
+    }
+    let values = function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| {
+            parse_arg(&param.resolve()?, arg).map_err(|e| eyre!("invalid `{}`: {e}", param.name))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(function.abi_encode_input(&values)?.into())
+}
+
+/// Parses an argument of the given type, rejecting addresses with an invalid checksum and
+/// integers out of the range of their type, which are silently accepted by the ABI coder. Both
+/// are checked in arrays and tuples as well.
+pub fn parse_arg(ty: &DynSolType, input: &str) -> Result<DynSolValue> {
+    let input = input.trim();
+    let value = ty.coerce_str(input)?;
+    // the input is well-formed once parsed, so that its items can be split by the type
+    check_checksums(ty, input)?;
+    check_range(&value)?;
+    Ok(value)
+}
+
+/// Checks the checksums of the mixed-case addresses of the input, which the parsed values no
+/// longer tell.
+fn check_checksums(ty: &DynSolType, input: &str) -> Result<()> {
+    let input = input.trim();
+    match ty {
+        DynSolType::Address => {
+            let hex = input.strip_prefix("0x").unwrap_or(input);
+            let mixed_case = hex.chars().any(|c| c.is_ascii_uppercase()) &&
+                hex.chars().any(|c| c.is_ascii_lowercase());
+            if mixed_case {
+                Address::parse_checksummed(input, None)
+                    .map_err(|_| eyre!("invalid checksum of {input}"))?;
+            }
+            Ok(())
+        }
+        DynSolType::Array(inner) | DynSolType::FixedArray(inner, _) => {
+            let Some(items) = input.strip_prefix('[').and_then(|s| s.strip_suffix(']')) else {
+                return Ok(());
+            };
+            split_items(items).into_iter().try_for_each(|item| check_checksums(inner, item))
+        }
+        DynSolType::Tuple(types) => {
+            let Some(items) = input.strip_prefix('(').and_then(|s| s.strip_suffix(')')) else {
+                return Ok(());
+            };
+            types
+                .iter()
+                .zip(split_items(items))
+                .try_for_each(|(ty, item)| check_checksums(ty, item))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Splits the items of an array or a tuple at the commas which are not nested in another array,
+/// tuple or string.
+fn split_items(input: &str) -> Vec<&str> {
+    if input.trim().is_empty() {
+        return Vec::new();
+    }
+    let mut items = Vec::new();
+    let (mut depth, mut quoted, mut start) = (0usize, false, 0);
+    for (i, c) in input.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' | '(' if !quoted => depth += 1,
+            ']' | ')' if !quoted => depth = depth.saturating_sub(1),
+            ',' if !quoted && depth == 0 => {
+                items.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&input[start..]);
+    items
+}
+
+fn check_range(value: &DynSolValue) -> Result<()> {
+    match value {
+        DynSolValue::Uint(v, bits) if *bits < 256 && (*v >> *bits) != U256::ZERO => {
+            bail!("{v} does not fit in uint{bits}")
+        }
+        DynSolValue::Int(v, bits) if *bits < 256 => {
+            let (sign, abs) = v.into_sign_and_abs();
+            let limit = U256::from(1) << (*bits - 1);
+            let fits = if sign.is_negative() { abs <= limit } else { abs < limit };
+            if !fits {
+                bail!("{v} does not fit in int{bits}");
+            }
+            Ok(())
+        }
+        DynSolValue::Array(values) |
+        DynSolValue::FixedArray(values) |
+        DynSolValue::Tuple(values) => values.iter().try_for_each(check_range),
+        _ => Ok(()),
+    }
+}
+
+/// Builds the calldata interactively: the user picks a function of the ABI, and fills in its
+/// arguments one by one, which are asked again until they are valid.
+pub fn build_interactively(abi: &JsonAbi) -> Result<Bytes> {
+    let functions: Vec<_> = abi.functions().collect();
+    if functions.is_empty() {
+        bail!("the ABI has no function");
+    }
+
+    println!("Functions:");
+    for (i, function) in functions.iter().enumerate() {
+        println!("  [{}] {}", i + 1, function.signature());
+    }
+    let function = loop {
+        let input = prompt(&format!("Function [1-{}]: ", functions.len()))?;
+        match input.parse::<usize>() {
+            Ok(i) if (1..=functions.len()).contains(&i) => break functions[i - 1],
+            _ => println!("Please enter a number between 1 and {}", functions.len()),
+        }
+    };
+
+    let mut values = Vec::with_capacity(function.inputs.len());
+    for (i, param) in function.inputs.iter().enumerate() {
+        let ty = param.resolve()?;
+        let name = if param.name.is_empty() { format!("arg{i}") } else { param.name.clone() };
+        let value = loop {
+            let input = prompt(&format!("{name} ({}): ", param.selector_type()))?;
+            match parse_arg(&ty, &input) {
+                Ok(value) => break value,
+                Err(e) => println!("Invalid value: {e}"),
+            }
+        };
+        values.push(value);
+    }
+
+    let calldata: Bytes = function.abi_encode_input(&values)?.into();
+    println!("Calldata: {calldata}");
+    Ok(calldata)
+}
+
+fn prompt(message: &str) -> Result<String> {
+    print!("{message}");
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        bail!("aborted");
+    }
+    Ok(line.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::hex;
+
+    #[test]
+    fn test_encode_call() {
+        let args = ["0xdAC17F958D2ee523a2206206994597C13D831ec7".to_string(), "1000".to_string()];
+        let calldata = encode_call("transfer(address,uint256)", &args).unwrap();
+        assert_eq!(&calldata[..4], hex!("a9059cbb"));
+        assert_eq!(calldata.len(), 4 + 64);
+
+        // bad checksum
+        let args = ["0xdAC17F958D2ee523a2206206994597C13D831Ec7".to_string(), "1".to_string()];
+        assert!(encode_call("transfer(address,uint256)", &args).is_err());
+        // lowercase addresses have no checksum to check
+        assert!(
+            parse_arg(&DynSolType::Address, "0xdac17f958d2ee523a2206206994597c13d831ec7").is_ok()
+        );
+
+        assert!(parse_arg(&DynSolType::Uint(8), "255").is_ok());
+        assert!(parse_arg(&DynSolType::Uint(8), "256").is_err());
+        assert!(parse_arg(&DynSolType::Int(8), "-128").is_ok());
+        assert!(parse_arg(&DynSolType::Int(8), "128").is_err());
+        assert!(parse_arg(&DynSolType::Array(Box::new(DynSolType::Uint(8))), "[1, 300]").is_err());
+        assert!(encode_call("transfer(address,uint256)", &args[..1]).is_err());
+    }
+
+    #[test]
+    fn test_nested_checksums() {
+        let valid = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+        let invalid = "0xdAC17F958D2ee523a2206206994597C13D831Ec7";
+        let addresses = DynSolType::Array(Box::new(DynSolType::Address));
+        assert!(parse_arg(&addresses, &format!("[{valid}, {valid}]")).is_ok());
+        assert!(parse_arg(&addresses, &format!("[{valid}, {invalid}]")).is_err());
+        assert!(parse_arg(&addresses, "[]").is_ok());
+
+        let tuple = DynSolType::Tuple(vec![
+            DynSolType::String,
+            DynSolType::FixedArray(Box::new(DynSolType::Address), 1),
+        ]);
+        assert!(parse_arg(&tuple, &format!("(\"a, [b]\", [{valid}])")).is_ok());
+        assert!(parse_arg(&tuple, &format!("(\"a, [b]\", [{invalid}])")).is_err());
+
+        assert_eq!(split_items(" 1, [2, 3], (4, \"5,6\")"), vec![" 1", " [2, 3]", " (4, \"5,6\")"]);
+    }
+}
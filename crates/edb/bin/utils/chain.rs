@@ -0,0 +1,85 @@
+//! Differences of ZK-rollups from Ethereum, which affect how faithfully their transactions can be
+//! replayed in revm.
+
+use alloy_chains::{Chain, NamedChain};
+use serde_json::Value;
+
+/// How a chain deviates from the EVM executed by revm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainQuirks {
+    /// The chain executes the EVM, so that transactions are replayed exactly.
+    Evm,
+    /// Polygon zkEVM, which is EVM-equivalent up to gas accounting and a few opcodes (e.g.,
+    /// `SELFDESTRUCT` behaves as `SENDALL`), so that the replay cannot be validated against the
+    /// gas used on chain.
+    PolygonZkEvm,
+    /// zkSync Era, which compiles contracts to EraVM bytecode and has native account abstraction
+    /// and system contracts, so that its transactions cannot be re-executed by revm at all.
+    ZkSyncEra,
+}
+
+impl ChainQuirks {
+    pub fn of(chain: Chain) -> Self {
+        match chain.named() {
+            Some(NamedChain::PolygonZkEvm | NamedChain::PolygonZkEvmTestnet) => Self::PolygonZkEvm,
+            Some(NamedChain::ZkSync | NamedChain::ZkSyncTestnet) => Self::ZkSyncEra,
+            _ => Self::Evm,
+        }
+    }
+
+    /// Returns whether the transactions can be re-executed in revm.
+    pub fn can_reexecute(self) -> bool {
+        self != Self::ZkSyncEra
+    }
+
+    /// Returns whether the gas used by the replay is expected to match the receipts.
+    pub fn gas_matches(self) -> bool {
+        self == Self::Evm
+    }
+}
+
+/// Formats a call frame returned by the `callTracer` of `debug_traceTransaction` as an indented
+/// tree, which is all that can be shown for transactions which cannot be re-executed.
+pub fn format_call_trace(frame: &Value) -> String {
+    let mut out = String::new();
+    write_frame(frame, 0, &mut out);
+    out
+}
+
+fn write_frame(frame: &Value, depth: usize, out: &mut String) {
+    let field = |name: &str| frame.get(name).and_then(Value::as_str).unwrap_or("?");
+    out.push_str(&"  ".repeat(depth));
+    out.push_str(&format!("{} {} -> {}", field("type"), field("from"), field("to")));
+    if let Some(value) = frame.get("value").and_then(Value::as_str).filter(|v| *v != "0x0") {
+        out.push_str(&format!(" value={value}"));
+    }
+    if let Some(gas_used) = frame.get("gasUsed").and_then(Value::as_str) {
+        out.push_str(&format!(" gas={gas_used}"));
+    }
+    if let Some(error) = frame.get("error").and_then(Value::as_str) {
+        out.push_str(&format!(" [{error}]"));
+    }
+    out.push('\n');
+    for call in frame.get("calls").and_then(Value::as_array).into_iter().flatten() {
+        write_frame(call, depth + 1, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_call_trace() {
+        let frame = json!({
+            "type": "CALL", "from": "0xa", "to": "0xb", "value": "0x0", "gasUsed": "0x10",
+            "calls": [{ "type": "STATICCALL", "from": "0xb", "to": "0xc", "error": "execution reverted" }],
+        });
+        assert_eq!(
+            format_call_trace(&frame),
+            "CALL 0xa -> 0xb gas=0x10\n  STATICCALL 0xb -> 0xc [execution reverted]\n"
+        );
+        assert!(!ChainQuirks::of(Chain::from_named(NamedChain::ZkSync)).can_reexecute());
+    }
+}
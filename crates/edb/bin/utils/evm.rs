@@ -1,10 +1,14 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
 
 use alloy_chains::NamedChain;
 use alloy_consensus::TxType;
-use alloy_primitives::{TxKind, U256};
+use alloy_eips::{
+    eip1559::{calc_next_block_base_fee, BaseFeeParams},
+    eip4844::calc_excess_blob_gas,
+};
+use alloy_primitives::{keccak256, Address, Bytes, TxHash, TxKind, U256};
 use alloy_provider::{network::AnyNetwork, Provider};
-use alloy_rpc_types::{BlockNumberOrTag, Transaction};
+use alloy_rpc_types::{BlockNumberOrTag, Header, Transaction, TransactionRequest};
 use alloy_transport::{Transport, TransportError};
 use anvil::Hardfork;
 use eyre::{eyre, Result};
@@ -13,28 +17,30 @@ use foundry_evm::{
     fork::{database::ForkedDatabase, BlockchainDb, BlockchainDbMeta, SharedBackend},
     utils::apply_chain_and_block_specific_env_changes,
 };
-use revm::primitives::{BlobExcessGasAndPrice, BlockEnv, Env, EnvWithHandlerCfg};
+use revm::{
+    db::CacheDB,
+    primitives::{BlobExcessGasAndPrice, BlockEnv, Bytecode, Env, EnvWithHandlerCfg, SpecId},
+    DatabaseRef,
+};
+use serde::Deserialize;
+use serde_json::json;
 
 use edb_utils::cache::CachePath;
 
+use crate::utils::network_fixtures;
+
 pub async fn setup_block_env<
     T: Transport + Clone + Unpin,
     P: Provider<T, AnyNetwork> + Unpin + 'static + Clone,
 >(
     provider: Arc<P>,
     fork_block_number: Option<u64>,
+    spec_id: Option<SpecId>,
 ) -> Result<EnvWithHandlerCfg> {
     let mut env = EnvWithHandlerCfg::default();
 
     let (fork_block_number, fork_chain_id) = if let Some(fork_block_number) = fork_block_number {
-        // auto adjust hardfork if not specified
-        // but only if we're forking mainnet
         let chain_id = provider.get_chain_id().await?;
-        if NamedChain::Mainnet == chain_id {
-            let hardfork: Hardfork = fork_block_number.into();
-            env.handler_cfg.spec_id = hardfork.into();
-        }
-
         (fork_block_number, Some(U256::from(chain_id)))
     } else {
         // pick the last block number but also ensure it's not pending anymore
@@ -65,6 +71,18 @@ latest block number: {latest_block}"
         return Err(eyre!("failed to get block for block number: {fork_block_number}"));
     };
 
+    // use the hardfork active at the block if not specified, but only on chains whose schedule
+    // is known
+    if let Some(spec_id) = spec_id {
+        env.handler_cfg.spec_id = spec_id;
+    } else if let Some(chain_id) = fork_chain_id {
+        if let Some(hardfork) =
+            hardfork_at(chain_id.to(), fork_block_number, block.header.timestamp)
+        {
+            env.handler_cfg.spec_id = hardfork.into();
+        }
+    }
+
     // we only use the gas limit value of the block if it is non-zero and the block gas
     // limit is enabled, since there are networks where this is not used and is always
     // `0x0` which would inevitably result in `OutOfGas` errors as soon as the evm is about to record gas, See also <https://github.com/foundry-rs/foundry/issues/3247>
@@ -109,6 +127,56 @@ latest block number: {latest_block}"
     Ok(env)
 }
 
+/// The time between two blocks, which the pending block is assumed to be built after its parent.
+const BLOCK_TIME: u64 = 12;
+
+/// Turns the environment of a block into the one of the pending block built on top of it: the
+/// number is bumped, the timestamp is advanced by a slot, and the base fee and the excess blob gas
+/// follow from what the parent used.
+pub fn set_pending_block_env(env: &mut EnvWithHandlerCfg, parent: &Header) {
+    env.block.number = U256::from(parent.number.unwrap_or_default() + 1);
+    env.block.timestamp = U256::from(parent.timestamp + BLOCK_TIME);
+    if let Some(base_fee) = parent.base_fee_per_gas {
+        let base_fee = calc_next_block_base_fee(
+            parent.gas_used,
+            parent.gas_limit,
+            base_fee,
+            BaseFeeParams::ethereum(),
+        );
+        env.block.basefee = U256::from(base_fee);
+    }
+    if let (Some(excess_blob_gas), Some(blob_gas_used)) =
+        (parent.excess_blob_gas, parent.blob_gas_used)
+    {
+        let excess_blob_gas = calc_excess_blob_gas(excess_blob_gas, blob_gas_used);
+        env.block.blob_excess_gas_and_price =
+            Some(BlobExcessGasAndPrice::new(excess_blob_gas as u64));
+    }
+}
+
+/// Returns the hardfork active at the given block, or `None` if the schedule of the chain is not
+/// known. The schedules stop at Cancun, which is returned for the later blocks as well, since
+/// the EVM in use predates the final specification of Prague.
+fn hardfork_at(chain_id: u64, number: u64, timestamp: u64) -> Option<Hardfork> {
+    // activation of (Paris, Shanghai, Cancun), the latter two being scheduled by timestamp
+    let (paris_block, shanghai, cancun) = match NamedChain::try_from(chain_id).ok()? {
+        NamedChain::Mainnet => return Some(number.into()),
+        NamedChain::Sepolia => (1_735_371, 1_677_557_088, 1_706_655_072),
+        NamedChain::Holesky => (0, 1_696_000_704, 1_707_305_664),
+        _ => return None,
+    };
+    let hardfork = if timestamp >= cancun {
+        Hardfork::Cancun
+    } else if timestamp >= shanghai {
+        Hardfork::Shanghai
+    } else if number >= paris_block {
+        Hardfork::Paris
+    } else {
+        Hardfork::London
+    };
+    Some(hardfork)
+}
+
 pub async fn setup_fork_db<
     T: Transport + Clone + Unpin,
     P: Provider<T, AnyNetwork> + Unpin + 'static + Clone,
@@ -118,16 +186,17 @@ pub async fn setup_fork_db<
     fork_block_number: Option<u64>,
     cache_path: Option<PathBuf>,
 ) -> Result<ForkedDatabase> {
-    let env = setup_block_env(Arc::clone(&provider), fork_block_number).await?;
+    let env = setup_block_env(Arc::clone(&provider), fork_block_number, None).await?;
 
     let chain_id = env.cfg.chain_id;
     let fork_block_number = env.block.number.try_into()?;
 
     let meta = BlockchainDbMeta::new(*env.env.clone(), eth_rpc_url.to_string());
-    let block_chain_db = BlockchainDb::new_skip_check(
-        meta,
-        cache_path.or(CachePath::edb_block_cache_file(chain_id, fork_block_number)),
-    );
+    // the cache is bypassed while recording fixtures, so that every response is recorded
+    let cache_path = cache_path
+        .or(CachePath::edb_block_cache_file(chain_id, fork_block_number))
+        .filter(|_| !network_fixtures::is_recording());
+    let block_chain_db = BlockchainDb::new_skip_check(meta, cache_path);
 
     // This will spawn the background thread that will use the provider to fetch
     // blockchain data from the other client
@@ -140,6 +209,69 @@ pub async fn setup_fork_db<
     Ok(ForkedDatabase::new(backend, block_chain_db))
 }
 
+/// An account in the prestate returned by the `prestateTracer` of `debug_traceTransaction`.
+#[derive(Debug, Deserialize)]
+struct PrestateAccount {
+    #[serde(default)]
+    balance: Option<U256>,
+    #[serde(default)]
+    nonce: Option<u64>,
+    #[serde(default)]
+    code: Option<Bytes>,
+    #[serde(default)]
+    storage: BTreeMap<U256, U256>,
+}
+
+/// Overlays the exact prestate of the transaction, as traced by the node, on top of the forked
+/// database, so that the transaction can be executed without replaying the preceding
+/// transactions of its block.
+pub async fn apply_prestate<
+    T: Transport + Clone + Unpin,
+    P: Provider<T, AnyNetwork> + Unpin + 'static + Clone,
+>(
+    db: &mut ForkedDatabase,
+    provider: Arc<P>,
+    tx_hash: TxHash,
+) -> Result<()> {
+    let prestate: BTreeMap<Address, PrestateAccount> = provider
+        .raw_request(
+            "debug_traceTransaction".into(),
+            (tx_hash, json!({ "tracer": "prestateTracer" })),
+        )
+        .await
+        .map_err(|e| eyre!("failed to trace the prestate, is the debug API enabled? ({e})"))?;
+    overlay_prestate(db.database_mut(), prestate)
+}
+
+/// Overwrites the accounts and the slots listed in the prestate, which leaves the other slots
+/// of the accounts as they are in the underlying database.
+fn overlay_prestate<ExtDB: DatabaseRef>(
+    db: &mut CacheDB<ExtDB>,
+    prestate: BTreeMap<Address, PrestateAccount>,
+) -> Result<()>
+where
+    ExtDB::Error: std::error::Error + Send + Sync + 'static,
+{
+    for (address, account) in prestate {
+        let mut info = db.basic_ref(address)?.unwrap_or_default();
+        if let Some(balance) = account.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = account.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = account.code {
+            info.code_hash = keccak256(&code);
+            info.code = Some(Bytecode::new_raw(code));
+        }
+        db.insert_account_info(address, info);
+        for (slot, value) in account.storage {
+            db.insert_account_storage(address, slot, value)?;
+        }
+    }
+    Ok(())
+}
+
 /// Finds the latest appropriate block to fork
 ///
 /// This fetches the "latest" block and checks whether the `Block` is fully populated (`hash` field
@@ -271,9 +403,43 @@ pub fn fill_tx_env(env: &mut Env, tx: &Transaction) -> Result<()> {
     Ok(())
 }
 
+/// Fill transaction environment from a [TransactionRequest], e.g. a transaction which is about
+/// to be sent by a wallet. Missing fields are filled with values that let the transaction pass
+/// the pre-execution checks.
+pub fn fill_tx_env_with_request(env: &mut Env, tx: &TransactionRequest) {
+    env.tx.caller = tx.from.unwrap_or_default();
+    env.tx.transact_to = tx.to.unwrap_or(TxKind::Create);
+    env.tx.value = tx.value.unwrap_or_default();
+    env.tx.data = tx.input.input().cloned().unwrap_or_default();
+    env.tx.gas_limit = tx.gas.map(|gas| gas as u64).unwrap_or(env.block.gas_limit.saturating_to());
+    env.tx.gas_price =
+        U256::from(tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default()).max(env.block.basefee);
+    env.tx.gas_priority_fee = tx.max_priority_fee_per_gas.map(U256::from);
+    env.tx.chain_id = tx.chain_id;
+    env.tx.nonce = tx.nonce;
+    env.tx.access_list = tx
+        .access_list
+        .as_ref()
+        .map(|list| {
+            list.iter()
+                .map(|l| {
+                    (l.address, l.storage_keys.iter().map(|k| U256::from_be_bytes(k.0)).collect())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    env.tx.blob_hashes.clear();
+    env.tx.max_fee_per_blob_gas.take();
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Hardfork;
+    use revm::{
+        db::EmptyDB,
+        primitives::{AccountInfo, KECCAK_EMPTY},
+    };
+
+    use super::*;
 
     #[test]
     fn test_hardfork_blocks() {
@@ -286,4 +452,107 @@ mod tests {
         let hf: Hardfork = 12244000u64.into();
         assert_eq!(hf, Hardfork::Berlin);
     }
+
+    #[test]
+    fn test_hardfork_at() {
+        let sepolia = NamedChain::Sepolia as u64;
+        let (paris, shanghai, cancun) = (1_735_371, 1_677_557_088, 1_706_655_072);
+        // Paris is scheduled by block, before Shanghai
+        assert_eq!(hardfork_at(sepolia, paris - 1, shanghai - 1), Some(Hardfork::London));
+        assert_eq!(hardfork_at(sepolia, paris, shanghai - 1), Some(Hardfork::Paris));
+        assert_eq!(hardfork_at(sepolia, paris + 1_000_000, shanghai), Some(Hardfork::Shanghai));
+        assert_eq!(hardfork_at(sepolia, paris + 1_000_000, cancun - 1), Some(Hardfork::Shanghai));
+        assert_eq!(hardfork_at(sepolia, paris + 1_000_000, cancun), Some(Hardfork::Cancun));
+        // Prague is not scheduled
+        assert_eq!(hardfork_at(sepolia, paris + 6_000_000, 1_741_159_776), Some(Hardfork::Cancun));
+
+        // Holesky starts at Paris
+        let holesky = NamedChain::Holesky as u64;
+        let (shanghai, cancun) = (1_696_000_704, 1_707_305_664);
+        assert_eq!(hardfork_at(holesky, 0, shanghai - 1), Some(Hardfork::Paris));
+        assert_eq!(hardfork_at(holesky, 10, shanghai), Some(Hardfork::Shanghai));
+        assert_eq!(hardfork_at(holesky, 1_000_000, cancun - 1), Some(Hardfork::Shanghai));
+        assert_eq!(hardfork_at(holesky, 1_000_000, cancun), Some(Hardfork::Cancun));
+        assert_eq!(hardfork_at(holesky, 3_000_000, 1_740_434_112), Some(Hardfork::Cancun));
+
+        // the mainnet schedule is by block, whatever the timestamp
+        let mainnet = NamedChain::Mainnet as u64;
+        assert_ne!(hardfork_at(mainnet, 17_034_869, 0), Some(Hardfork::Shanghai));
+        assert_eq!(hardfork_at(mainnet, 17_034_870, 0), Some(Hardfork::Shanghai));
+        assert_eq!(hardfork_at(mainnet, 19_426_586, 0), Some(Hardfork::Shanghai));
+        assert_eq!(hardfork_at(mainnet, 19_426_587, 0), Some(Hardfork::Cancun));
+
+        // the schedules of other chains are not known
+        assert_eq!(hardfork_at(NamedChain::Optimism as u64, 1, 0), None);
+        assert_eq!(hardfork_at(NamedChain::Dev as u64, 1, 0), None);
+        assert_eq!(hardfork_at(123_456_789, 1, 0), None);
+    }
+
+    #[test]
+    fn test_overlay_prestate() {
+        let account = Address::repeat_byte(0xaa);
+        let fresh = Address::repeat_byte(0xbb);
+        // the forked state, before the preceding transactions of the block
+        let mut fork = CacheDB::new(EmptyDB::default());
+        let info = AccountInfo { balance: U256::from(1), nonce: 1, ..Default::default() };
+        fork.insert_account_info(account, info);
+        fork.insert_account_storage(account, U256::from(1), U256::from(10)).unwrap();
+        fork.insert_account_storage(account, U256::from(2), U256::from(20)).unwrap();
+
+        let prestate = json!({
+            account.to_string(): {
+                "balance": "0x64",
+                "nonce": 5,
+                "code": "0x6000",
+                "storage": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001":
+                        "0x000000000000000000000000000000000000000000000000000000000000000b",
+                },
+            },
+            fresh.to_string(): { "balance": "0x2" },
+        });
+        let mut db = CacheDB::new(fork);
+        overlay_prestate(&mut db, serde_json::from_value(prestate).unwrap()).unwrap();
+
+        let info = db.basic_ref(account).unwrap().unwrap();
+        assert_eq!((info.balance, info.nonce), (U256::from(100), 5));
+        assert_eq!(info.code_hash, keccak256([0x60, 0x00]));
+        assert_eq!(info.code.unwrap().original_bytes(), Bytes::from_static(&[0x60, 0x00]));
+        assert_eq!(db.storage_ref(account, U256::from(1)).unwrap(), U256::from(11));
+        // the slots left out of the prestate are read from the fork
+        assert_eq!(db.storage_ref(account, U256::from(2)).unwrap(), U256::from(20));
+        assert_eq!(db.storage_ref(account, U256::from(3)).unwrap(), U256::ZERO);
+
+        // the fields left out of the prestate keep their value, i.e., none for a new account
+        let info = db.basic_ref(fresh).unwrap().unwrap();
+        assert_eq!((info.balance, info.nonce, info.code_hash), (U256::from(2), 0, KECCAK_EMPTY));
+    }
+
+    #[test]
+    fn test_set_pending_block_env() {
+        let parent = Header {
+            number: Some(100),
+            timestamp: 1_700_000_000,
+            gas_limit: 30_000_000,
+            gas_used: 30_000_000,
+            base_fee_per_gas: Some(1_000_000_000),
+            blob_gas_used: Some(786_432),
+            excess_blob_gas: Some(0),
+            ..Default::default()
+        };
+        let mut env = EnvWithHandlerCfg::default();
+        set_pending_block_env(&mut env, &parent);
+        assert_eq!(env.block.number, U256::from(101));
+        assert_eq!(env.block.timestamp, U256::from(1_700_000_012));
+        // a full parent raises the base fee by an eighth, and blobs above the target add up
+        assert_eq!(env.block.basefee, U256::from(1_125_000_000));
+        assert_eq!(env.block.get_blob_excess_gas(), Some(393_216));
+
+        // a parent at the gas target keeps the base fee
+        let parent = Header { gas_used: 15_000_000, blob_gas_used: None, ..parent };
+        let mut env = EnvWithHandlerCfg::default();
+        set_pending_block_env(&mut env, &parent);
+        assert_eq!(env.block.basefee, U256::from(1_000_000_000));
+        assert_eq!(env.block.get_blob_excess_gas(), None);
+    }
 }
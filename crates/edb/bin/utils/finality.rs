@@ -0,0 +1,83 @@
+//! Canonicality of the replayed transaction, so that users do not debug history which was
+//! reorged away, or which may still be.
+
+use std::sync::Arc;
+
+use alloy_primitives::{BlockHash, TxHash};
+use alloy_provider::Provider;
+use alloy_rpc_types::{Block, BlockNumberOrTag, BlockTransactionsKind};
+use eyre::{ensure, eyre, Result};
+use foundry_common::provider::RetryProvider;
+
+/// The number of confirmations after which a block is considered safe from reorgs, when the node
+/// does not tell which blocks are finalized (e.g., before the merge or on some L2s).
+pub const SAFE_CONFIRMATIONS: u64 = 64;
+
+/// Ensures that the transaction is included in the canonical block at the RPC, and that this
+/// block is the pinned one, if any.
+pub fn ensure_canonical(
+    tx_hash: TxHash,
+    tx_block_hash: Option<BlockHash>,
+    block: &Block,
+    pinned: Option<BlockHash>,
+) -> Result<()> {
+    let number = block.header.number.unwrap_or_default();
+    let canonical = block.header.hash.ok_or(eyre!("block #{number} has no hash"))?;
+    if let Some(tx_block_hash) = tx_block_hash {
+        ensure!(
+            tx_block_hash == canonical,
+            "transaction {tx_hash} was included in block {tx_block_hash}, but the canonical \
+             block #{number} is {canonical}: it was likely reorged out"
+        );
+    }
+    if let Some(pinned) = pinned {
+        ensure!(
+            pinned == canonical,
+            "the canonical block #{number} is {canonical} at the RPC, not the pinned {pinned}"
+        );
+    }
+    Ok(())
+}
+
+/// Returns why the block may still be reorged, if it may: it is neither finalized nor buried
+/// under [`SAFE_CONFIRMATIONS`] blocks when the node does not report finality.
+pub fn unsafe_block_reason(number: u64, finalized: Option<u64>, latest: u64) -> Option<String> {
+    let confirmations = latest.saturating_sub(number);
+    match finalized {
+        Some(finalized) if number > finalized => Some(format!(
+            "block #{number} is not finalized yet ({confirmations} confirmations, finalized \
+             up to #{finalized}), the transaction may still be reorged"
+        )),
+        None if confirmations < SAFE_CONFIRMATIONS => Some(format!(
+            "block #{number} has only {confirmations} confirmations, the transaction may still \
+             be reorged"
+        )),
+        _ => None,
+    }
+}
+
+/// Checks whether the block may still be reorged at the RPC.
+pub async fn check_finality(provider: Arc<RetryProvider>, number: u64) -> Result<Option<String>> {
+    // nodes without a notion of finality reject the tag
+    let finalized = provider
+        .get_block(BlockNumberOrTag::Finalized.into(), BlockTransactionsKind::Hashes)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|block| block.header.number);
+    let latest = provider.get_block_number().await?;
+    Ok(unsafe_block_reason(number, finalized, latest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsafe_block_reason() {
+        assert!(unsafe_block_reason(100, Some(100), 120).is_none());
+        assert!(unsafe_block_reason(101, Some(100), 120).unwrap().contains("not finalized"));
+        assert!(unsafe_block_reason(100, None, 100 + SAFE_CONFIRMATIONS).is_none());
+        assert!(unsafe_block_reason(100, None, 110).unwrap().contains("10 confirmations"));
+    }
+}
@@ -0,0 +1,146 @@
+//! State fixtures, i.e., the pre-state touched by a transaction, which can be loaded locally to
+//! reproduce it without access to an archive node.
+
+use std::collections::BTreeMap;
+
+use alloy_primitives::{Address, Bytes, U256};
+use clap::ValueEnum;
+use eyre::Result;
+use foundry_evm::{fork::database::ForkedDatabase, utils::new_evm_with_inspector};
+use revm::{
+    db::CacheDB,
+    inspectors::NoOpInspector,
+    primitives::{EnvWithHandlerCfg, ExecutionResult, KECCAK_EMPTY},
+};
+use serde_json::{json, Map, Value};
+
+/// The format of a state fixture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum FixtureFormat {
+    /// The state dump loaded by `anvil --load-state`.
+    #[default]
+    Anvil,
+    /// A genesis alloc, which is loaded by `vm.loadAllocs` in Foundry tests (and by geth).
+    Alloc,
+}
+
+/// An account of the pre-state.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FixtureAccount {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Bytes,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// Returns the accounts and storage slots read by the transaction, as collected by
+/// [`execute_touching`], without the empty slots, which are the default and left out of the
+/// fixtures.
+pub fn touched_prestate(
+    state: &BTreeMap<Address, FixtureAccount>,
+) -> BTreeMap<Address, FixtureAccount> {
+    let mut state = state.clone();
+    for account in state.values_mut() {
+        account.storage.retain(|_, value| !value.is_zero());
+    }
+    state
+}
+
+/// Executes the transaction without committing it, and returns its result along with the
+/// accounts and storage slots it reads, as they were before the transaction, including the empty
+/// ones.
+pub fn execute_touching(
+    db: &ForkedDatabase,
+    env: EnvWithHandlerCfg,
+) -> Result<(BTreeMap<Address, FixtureAccount>, ExecutionResult)> {
+    // every account and slot read by the execution is cached with its original value, since
+    // nothing is committed
+    let mut cache = CacheDB::new(db);
+    let mut evm = new_evm_with_inspector(&mut cache, env, NoOpInspector);
+    let result = evm.transact()?.result;
+    drop(evm);
+
+    let mut state = BTreeMap::new();
+    for (address, account) in cache.accounts {
+        let info = account.info;
+        let code = match info.code {
+            Some(code) => code.original_bytes(),
+            None if info.code_hash != KECCAK_EMPTY => cache
+                .contracts
+                .get(&info.code_hash)
+                .map(|code| code.original_bytes())
+                .unwrap_or_default(),
+            None => Bytes::new(),
+        };
+        let storage = account.storage.into_iter().collect();
+        state.insert(
+            address,
+            FixtureAccount { balance: info.balance, nonce: info.nonce, code, storage },
+        );
+    }
+    Ok((state, result))
+}
+
+/// Serializes the state in the given format.
+pub fn serialize_fixture(
+    state: &BTreeMap<Address, FixtureAccount>,
+    format: FixtureFormat,
+) -> Value {
+    let accounts: Map<String, Value> = state
+        .iter()
+        .map(|(address, account)| {
+            let storage: Map<String, Value> = account
+                .storage
+                .iter()
+                .map(|(slot, value)| (format!("{slot:#x}"), json!(format!("{value:#x}"))))
+                .collect();
+            let account = match format {
+                FixtureFormat::Anvil => json!({
+                    "nonce": account.nonce,
+                    "balance": account.balance,
+                    "code": account.code,
+                    "storage": storage,
+                }),
+                FixtureFormat::Alloc => json!({
+                    "nonce": format!("{:#x}", account.nonce),
+                    "balance": account.balance,
+                    "code": account.code,
+                    "storage": storage,
+                }),
+            };
+            (address.to_checksum(None), account)
+        })
+        .collect();
+
+    match format {
+        FixtureFormat::Anvil => json!({ "block": null, "accounts": accounts }),
+        FixtureFormat::Alloc => Value::Object(accounts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_fixture() {
+        let account = FixtureAccount {
+            balance: U256::from(1000),
+            nonce: 2,
+            code: Bytes::from_static(&[0x60, 0x00]),
+            storage: BTreeMap::from([(U256::from(1), U256::from(255))]),
+        };
+        let state = BTreeMap::from([(Address::with_last_byte(1), account)]);
+        let address = "0x0000000000000000000000000000000000000001";
+
+        let anvil = serialize_fixture(&state, FixtureFormat::Anvil);
+        assert_eq!(anvil["accounts"][address]["nonce"], json!(2));
+        assert_eq!(anvil["accounts"][address]["balance"], json!("0x3e8"));
+        assert_eq!(anvil["accounts"][address]["code"], json!("0x6000"));
+        assert_eq!(anvil["accounts"][address]["storage"]["0x1"], json!("0xff"));
+
+        let alloc = serialize_fixture(&state, FixtureFormat::Alloc);
+        assert_eq!(alloc[address]["nonce"], json!("0x2"));
+        assert_eq!(alloc[address]["storage"]["0x1"], json!("0xff"));
+    }
+}
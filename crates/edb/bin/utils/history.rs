@@ -0,0 +1,50 @@
+//! The history of replayed transactions, which backs the shell completion of transaction hashes.
+
+use std::{
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+};
+
+use alloy_primitives::TxHash;
+use edb_utils::cache::CachePath;
+
+/// The maximum number of transactions suggested by the shell completion.
+const MAX_COMPLETIONS: usize = 50;
+
+/// Appends the transaction to the history. Failures are only logged, since the history is a
+/// convenience.
+pub fn record_tx(tx_hash: TxHash) {
+    let Some(path) = CachePath::edb_tx_history_file() else { return };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{tx_hash}"));
+    if let Err(e) = result {
+        debug!("failed to record {tx_hash} in {}: {e}", path.display());
+    }
+}
+
+/// Returns the most recently replayed transactions, newest first and without duplicates.
+pub fn recent_txs() -> Vec<TxHash> {
+    let Some(file) = CachePath::edb_tx_history_file().and_then(|p| std::fs::File::open(p).ok())
+    else {
+        return Vec::new();
+    };
+    let hashes: Vec<TxHash> =
+        BufReader::new(file).lines().map_while(Result::ok).filter_map(|l| l.parse().ok()).collect();
+
+    let mut recent = Vec::new();
+    for hash in hashes.into_iter().rev() {
+        if !recent.contains(&hash) {
+            recent.push(hash);
+        }
+        if recent.len() == MAX_COMPLETIONS {
+            break;
+        }
+    }
+    recent
+}
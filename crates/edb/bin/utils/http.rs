@@ -0,0 +1,121 @@
+//! The HTTP/1.1 server side shared by the local servers of EDB, i.e., the JSON-RPC proxy, the
+//! browser-based frontend and the fixture server. Each connection carries a single request, so
+//! that responses are always sent with `Connection: close`.
+
+use eyre::{ensure, eyre, Result};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// The maximum size of the head or of the body of a request.
+pub const MAX_REQUEST_SIZE: usize = 16 * 1024 * 1024;
+
+/// A request read from a connection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: String,
+    /// The path and query of the request, e.g., `/ws?token=00`.
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Parses the head of a request, i.e., everything up to the empty line ending the headers.
+    fn parse_head(head: &str) -> Result<Self> {
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+        let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+            return Err(eyre!("invalid HTTP request"));
+        };
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Ok(Self { method: method.to_string(), target: target.to_string(), headers, body: vec![] })
+    }
+
+    /// Returns the path of the target, without the query.
+    pub fn path(&self) -> &str {
+        self.target.split_once('?').map_or(&self.target, |(path, _)| path)
+    }
+
+    /// Returns the query of the target, without the leading `?`.
+    pub fn query(&self) -> &str {
+        self.target.split_once('?').map_or("", |(_, query)| query)
+    }
+
+    /// Returns the value of the first header with the given name, which is case-insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reads a request from the stream, up to the end of its body as given by `Content-Length`.
+pub async fn read_request(stream: &mut TcpStream) -> Result<HttpRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let head_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        ensure!(n > 0, "connection closed before the request was complete");
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        ensure!(buf.len() <= MAX_REQUEST_SIZE, "request is too large");
+    };
+
+    let mut request = HttpRequest::parse_head(&String::from_utf8_lossy(&buf[..head_end]))?;
+    let content_length =
+        request.header("content-length").map(str::parse::<usize>).transpose()?.unwrap_or_default();
+    ensure!(content_length <= MAX_REQUEST_SIZE, "request is too large");
+
+    let mut body = buf.split_off(head_end);
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await?;
+        ensure!(n > 0, "connection closed before the request was complete");
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(content_length);
+    request.body = body;
+    Ok(request)
+}
+
+/// Writes a response with the given status, e.g., `200 OK`, and extra headers, and closes the
+/// connection.
+pub async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Result<()> {
+    let mut head = format!("HTTP/1.1 {status}\r\n");
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", body.len()));
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_head() {
+        let head = "GET /ws?token=00 HTTP/1.1\r\nHost: 127.0.0.1:8547\r\norigin:  http://x\r\n\r\n";
+        let request = HttpRequest::parse_head(head).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!((request.path(), request.query()), ("/ws", "token=00"));
+        assert_eq!(request.header("Origin"), Some("http://x"));
+        assert_eq!(request.header("content-length"), None);
+        assert!(HttpRequest::parse_head("\r\n").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! Heuristic MEV context of a transaction, i.e., the transactions of its block which touched
+//! the same contracts shortly before or after it, e.g., the two legs of a sandwich.
+//!
+//! Contracts are considered touched when they emitted a log, which is the case of the pools of
+//! every major AMM on swaps. This is cheap (receipts only), but misses read-only interactions.
+
+use std::{collections::BTreeSet, fmt, sync::Arc};
+
+use alloy_primitives::{Address, TxHash};
+use alloy_provider::Provider;
+use alloy_rpc_types::{BlockTransactions, BlockTransactionsKind};
+use eyre::{eyre, Result};
+use foundry_common::provider::RetryProvider;
+use yansi::Paint;
+
+/// A transaction of the block, as far as the context is concerned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockTx {
+    pub index: usize,
+    pub hash: TxHash,
+    pub from: Address,
+    pub to: Option<Address>,
+    /// The contracts which emitted logs.
+    pub touched: BTreeSet<Address>,
+}
+
+/// A transaction related to the target one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelatedTx {
+    pub tx: BlockTx,
+    /// The contracts touched by both transactions.
+    pub shared: BTreeSet<Address>,
+}
+
+/// The MEV context of the target transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MevContext {
+    pub target: BlockTx,
+    /// The related transactions before the target, in block order.
+    pub before: Vec<RelatedTx>,
+    /// The related transactions after the target, in block order.
+    pub after: Vec<RelatedTx>,
+    /// The front-running and back-running legs of a suspected sandwich.
+    pub sandwich: Option<(TxHash, TxHash)>,
+}
+
+impl MevContext {
+    /// Relates the target to the transactions at most `window` positions away from it in the
+    /// block. A sandwich is suspected when the same sender (or bot contract) touches a contract
+    /// of the target both right before and right after it.
+    pub fn new(txs: &[BlockTx], target: usize, window: usize) -> Self {
+        let target_tx = txs[target].clone();
+        let related = |tx: &BlockTx| {
+            let shared: BTreeSet<_> =
+                tx.touched.intersection(&target_tx.touched).copied().collect();
+            (!shared.is_empty() && tx.from != target_tx.from)
+                .then(|| RelatedTx { tx: tx.clone(), shared })
+        };
+        let before: Vec<_> =
+            txs[target.saturating_sub(window)..target].iter().filter_map(related).collect();
+        let after: Vec<_> = txs[target + 1..txs.len().min(target + 1 + window)]
+            .iter()
+            .filter_map(related)
+            .collect();
+
+        let same_actor =
+            |a: &BlockTx, b: &BlockTx| a.from == b.from || (a.to.is_some() && a.to == b.to);
+        let sandwich = before.iter().rev().find_map(|front| {
+            after
+                .iter()
+                .find(|back| {
+                    same_actor(&front.tx, &back.tx) && !front.shared.is_disjoint(&back.shared)
+                })
+                .map(|back| (front.tx.hash, back.tx.hash))
+        });
+
+        Self { target: target_tx, before, after, sandwich }
+    }
+
+    /// Returns whether any transaction is related to the target.
+    pub fn is_empty(&self) -> bool {
+        self.before.is_empty() && self.after.is_empty()
+    }
+
+    /// Summarizes the context in a single line, e.g., for the warnings pane.
+    pub fn summary(&self) -> String {
+        match self.sandwich {
+            Some((front, back)) => format!("suspected sandwich between {front} and {back}"),
+            None => format!(
+                "{} related transactions before and {} after in the block",
+                self.before.len(),
+                self.after.len()
+            ),
+        }
+    }
+}
+
+impl fmt::Display for MevContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "MEV context of {} (index {}):", self.target.hash, self.target.index)?;
+        if self.is_empty() {
+            return writeln!(f, "  no nearby transaction touched the same contracts");
+        }
+        let line = |f: &mut fmt::Formatter<'_>, related: &RelatedTx, marker: &str| {
+            let shared = related.shared.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+            writeln!(
+                f,
+                "  {marker} #{:<4} {} from {} (shared: {})",
+                related.tx.index,
+                related.tx.hash,
+                related.tx.from,
+                shared.join(", ")
+            )
+        };
+        for related in &self.before {
+            line(f, related, "before")?;
+        }
+        writeln!(f, "  target #{:<4} {}", self.target.index, self.target.hash)?;
+        for related in &self.after {
+            line(f, related, "after ")?;
+        }
+        if let Some((front, back)) = self.sandwich {
+            writeln!(f, "  {} front-run by {front}, back-run by {back}", "sandwich?".red().bold())?;
+        }
+        Ok(())
+    }
+}
+
+/// Fetches the transactions around the target in its block, with their receipts, and builds
+/// its MEV context.
+pub async fn fetch_mev_context(
+    provider: Arc<RetryProvider>,
+    tx_hash: TxHash,
+    window: usize,
+) -> Result<MevContext> {
+    let tx =
+        provider.get_transaction_by_hash(tx_hash).await?.ok_or(eyre!("transaction not found"))?;
+    let block_number = tx.block_number.ok_or(eyre!("transaction may still be pending"))?;
+    let block = provider
+        .get_block(block_number.into(), BlockTransactionsKind::Full)
+        .await?
+        .ok_or(eyre!("block not found"))?;
+    let BlockTransactions::Full(txs_in_block) = block.transactions else {
+        return Err(eyre!("block transactions not found"));
+    };
+    let target = txs_in_block
+        .iter()
+        .position(|tx| tx.hash == tx_hash)
+        .ok_or(eyre!("transaction not found in its block"))?;
+
+    // only the receipts of the window are needed
+    let range = target.saturating_sub(window)..txs_in_block.len().min(target + 1 + window);
+    let mut txs = Vec::with_capacity(range.len());
+    for (index, tx) in txs_in_block.iter().enumerate().skip(range.start).take(range.len()) {
+        let receipt = provider
+            .get_transaction_receipt(tx.hash)
+            .await?
+            .ok_or(eyre!("transaction receipt not found"))?;
+        let touched = receipt.inner.inner.logs().iter().map(|log| log.inner.address).collect();
+        txs.push(BlockTx { index, hash: tx.hash, from: tx.from, to: tx.to, touched });
+    }
+
+    Ok(MevContext::new(&txs, target - range.start, window))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(index: u8, from: u8, touched: &[u8]) -> BlockTx {
+        BlockTx {
+            index: index as usize,
+            hash: TxHash::with_last_byte(index),
+            from: Address::with_last_byte(from),
+            to: None,
+            touched: touched.iter().map(|b| Address::with_last_byte(*b)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_sandwich() {
+        let pool = 0xaa;
+        let txs = [
+            tx(0, 1, &[0xbb]),
+            tx(1, 2, &[pool]),
+            tx(2, 3, &[pool, 0xcc]),
+            tx(3, 2, &[pool]),
+            tx(4, 4, &[pool]),
+        ];
+        let context = MevContext::new(&txs, 2, 2);
+        assert_eq!(context.before.len(), 1);
+        assert_eq!(context.after.len(), 2);
+        assert_eq!(context.sandwich, Some((txs[1].hash, txs[3].hash)));
+
+        // a back-run alone is not a sandwich
+        let context = MevContext::new(&txs[2..], 0, 2);
+        assert!(context.before.is_empty());
+        assert_eq!(context.sandwich, None);
+    }
+}
@@ -1,5 +1,18 @@
+pub mod calldata;
+pub mod chain;
 pub mod evm;
+pub mod finality;
+pub mod fixture;
+pub mod history;
+pub mod http;
+pub mod mev;
+pub mod network_fixtures;
+pub mod receipt;
+pub mod rerun;
+pub mod session_hash;
+pub mod web;
 
+use edb_debug_backend::artifact::warning::Warning;
 use eyre::EyreHandler;
 use std::{error::Error, future::Future};
 use tracing_error::ErrorLayer;
@@ -84,6 +97,18 @@ pub fn subscriber() {
         .init()
 }
 
+/// Prints the warnings of the session, for the commands which do not open the debugger, whose
+/// warnings pane would show them otherwise.
+pub fn print_warnings(warnings: &[Warning]) {
+    if warnings.is_empty() {
+        return;
+    }
+    eprintln!("{}", format!("{} warning(s):", warnings.len()).yellow().bold());
+    for warning in warnings {
+        eprintln!("{}", format!("- {warning}").yellow());
+    }
+}
+
 /// Sets the default [`yansi`] color output condition.
 pub fn enable_paint() {
     let enable = yansi::Condition::os_support() && yansi::Condition::tty_and_color_live();
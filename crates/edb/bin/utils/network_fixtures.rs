@@ -0,0 +1,321 @@
+//! Recorded RPC and explorer responses, so that sessions can be reproduced deterministically and
+//! offline, e.g., by the integration tests or to share the reproduction of an incident.
+//!
+//! When recording or replaying, the RPC endpoint and the explorer API are routed through a local
+//! HTTP server, which either forwards each request upstream and stores its response under the
+//! fixture directory, or serves the stored response without any network access:
+//!
+//! - `<dir>/rpc/<hash>.json`: the response to a JSON-RPC call, keyed by its method and params.
+//! - `<dir>/etherscan/<hash>.json`: the response to an explorer request, keyed by its query without
+//!   the API key.
+//!
+//! The local caches of the RPC and explorer responses are bypassed while recording, so that every
+//! response is recorded, and the responses telling that a rate limit is hit are never recorded.
+//! These are not to be confused with the state fixtures of [`super::fixture`].
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    thread,
+};
+
+use alloy_primitives::{hex, keccak256, B256};
+use eyre::{ensure, eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::utils::http;
+
+/// The base URL of the fixture server of the process, and whether it records or replays, once it
+/// is started.
+static SERVER: OnceLock<(String, FixtureMode)> = OnceLock::new();
+
+/// Whether the responses are recorded or replayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Forwards the requests upstream and records the responses.
+    Record,
+    /// Serves the recorded responses, and fails the requests which were not recorded.
+    Replay,
+}
+
+/// A recorded JSON-RPC response.
+#[derive(Debug, Serialize, Deserialize)]
+struct RpcFixture {
+    method: String,
+    params: Value,
+    /// The `result` or `error` member of the response.
+    response: Value,
+}
+
+/// A recorded explorer response.
+#[derive(Debug, Serialize, Deserialize)]
+struct EtherscanFixture {
+    query: String,
+    body: String,
+}
+
+#[derive(Debug)]
+struct FixtureServer {
+    mode: FixtureMode,
+    dir: PathBuf,
+    client: reqwest::Client,
+}
+
+/// Starts the fixture server of the process in a background thread, through which the RPC and
+/// explorer requests are routed from then on, see [`rpc_url`] and [`etherscan_url`].
+pub fn start(mode: FixtureMode, dir: &Path) -> Result<()> {
+    if mode == FixtureMode::Replay {
+        ensure!(dir.is_dir(), "there are no fixtures under {}", dir.display());
+    }
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    listener.set_nonblocking(true)?;
+
+    let server = Arc::new(FixtureServer {
+        mode,
+        dir: dir.to_path_buf(),
+        client: reqwest::Client::builder()
+            .user_agent(concat!("edb/", env!("CARGO_PKG_VERSION")))
+            .build()?,
+    });
+    thread::Builder::new().name("fixtures".into()).spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("could not start tokio rt");
+        runtime.block_on(server.serve(listener))
+    })?;
+
+    SERVER
+        .set((format!("http://{address}"), mode))
+        .map_err(|_| eyre!("fixtures are set up already"))
+}
+
+/// Returns whether the requests are routed through the fixture server.
+pub fn is_enabled() -> bool {
+    SERVER.get().is_some()
+}
+
+/// Returns whether the responses are being recorded, in which case the local caches are not to
+/// be used.
+pub fn is_recording() -> bool {
+    SERVER.get().is_some_and(|(_, mode)| *mode == FixtureMode::Record)
+}
+
+/// Returns the URL the given RPC endpoint is reached at, i.e., through the fixture server when
+/// recording or replaying.
+pub fn rpc_url(upstream: Cow<'_, str>) -> Cow<'_, str> {
+    route("rpc", upstream)
+}
+
+/// Returns the URL the given explorer API is reached at, see [`rpc_url`].
+pub fn etherscan_url(upstream: String) -> String {
+    route("etherscan", Cow::Owned(upstream)).into_owned()
+}
+
+/// Routes the upstream URL through the fixture server, encoding it in the path so that a single
+/// server handles every endpoint.
+fn route<'a>(service: &str, upstream: Cow<'a, str>) -> Cow<'a, str> {
+    match SERVER.get() {
+        Some((server, _)) => Cow::Owned(format!("{server}/{service}/{}", hex::encode(&*upstream))),
+        None => upstream,
+    }
+}
+
+impl FixtureServer {
+    async fn serve(self: Arc<Self>, listener: std::net::TcpListener) {
+        let listener = match TcpListener::from_std(listener) {
+            Ok(listener) => listener,
+            Err(e) => return error!("failed to start the fixture server: {e}"),
+        };
+        loop {
+            let Ok((stream, _)) = listener.accept().await else { continue };
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_connection(stream).await {
+                    warn!("fixture request failed: {e}");
+                }
+            });
+        }
+    }
+
+    async fn handle_connection(&self, mut stream: TcpStream) -> Result<()> {
+        let request = http::read_request(&mut stream).await?;
+        let target = &request.target;
+        let (service, upstream) = request
+            .path()
+            .trim_start_matches('/')
+            .split_once('/')
+            .ok_or_else(|| eyre!("invalid fixture request: {target}"))?;
+        let upstream = String::from_utf8(hex::decode(upstream)?)?;
+
+        let response = match service {
+            "rpc" => {
+                let response = match serde_json::from_slice(&request.body)? {
+                    Value::Array(calls) => {
+                        let mut responses = Vec::with_capacity(calls.len());
+                        for call in calls {
+                            responses.push(self.rpc_call(&upstream, call).await);
+                        }
+                        Value::Array(responses)
+                    }
+                    call => self.rpc_call(&upstream, call).await,
+                };
+                serde_json::to_string(&response)?
+            }
+            "etherscan" => self.etherscan_request(&upstream, request.query()).await?,
+            _ => return Err(eyre!("invalid fixture request: {target}")),
+        };
+        let headers = [("Content-Type", "application/json")];
+        http::write_response(&mut stream, "200 OK", &headers, response.as_bytes()).await
+    }
+
+    /// Answers a single JSON-RPC call, from its fixture or from the upstream endpoint.
+    async fn rpc_call(&self, upstream: &str, call: Value) -> Value {
+        let id = call.get("id").cloned().unwrap_or(Value::Null);
+        let method = call["method"].as_str().unwrap_or_default().to_string();
+        let params = call.get("params").cloned().unwrap_or(Value::Null);
+        let path = self.path("rpc", rpc_key(&method, &params));
+
+        let mut response = match self.mode {
+            FixtureMode::Replay => read_fixture::<RpcFixture>(&path)
+                .map(|fixture| fixture.response)
+                .unwrap_or_else(|e| rpc_error(format!("no fixture for {method}: {e}"))),
+            FixtureMode::Record => match self.forward_rpc(upstream, &call).await {
+                Ok(response) if is_rate_limited(&response["error"]["message"]) => response,
+                Ok(response) => {
+                    let fixture = RpcFixture { method, params, response };
+                    if let Err(e) = write_fixture(&path, &fixture) {
+                        warn!("failed to record {}: {e}", path.display());
+                    }
+                    fixture.response
+                }
+                Err(e) => rpc_error(e.to_string()),
+            },
+        };
+
+        if let Value::Object(members) = &mut response {
+            members.insert("jsonrpc".to_string(), json!("2.0"));
+            members.insert("id".to_string(), id);
+        }
+        response
+    }
+
+    /// Forwards a JSON-RPC call upstream, and returns the `result` or `error` member of the
+    /// response.
+    async fn forward_rpc(&self, upstream: &str, call: &Value) -> Result<Value> {
+        let response: Value =
+            self.client.post(upstream).json(call).send().await?.error_for_status()?.json().await?;
+        Ok(match (response.get("result"), response.get("error")) {
+            (_, Some(error)) => json!({ "error": error }),
+            (result, None) => json!({ "result": result.cloned().unwrap_or(Value::Null) }),
+        })
+    }
+
+    /// Answers an explorer request, from its fixture or from the upstream API.
+    async fn etherscan_request(&self, upstream: &str, query: &str) -> Result<String> {
+        let key = strip_api_key(query);
+        let path = self.path("etherscan", keccak256(key.as_bytes()));
+        match self.mode {
+            FixtureMode::Replay => Ok(read_fixture::<EtherscanFixture>(&path)
+                .map(|fixture| fixture.body)
+                .unwrap_or_else(|e| {
+                    json!({ "status": "0", "message": "NOTOK", "result": format!("no fixture: {e}") })
+                        .to_string()
+                })),
+            FixtureMode::Record => {
+                let separator = if upstream.contains('?') { '&' } else { '?' };
+                let url = format!("{upstream}{separator}{query}");
+                let body = self.client.get(url).send().await?.text().await?;
+                let result = serde_json::from_str::<Value>(&body).map(|body| body["result"].clone());
+                if result.is_ok_and(|result| is_rate_limited(&result)) {
+                    return Ok(body);
+                }
+                let fixture = EtherscanFixture { query: key, body };
+                write_fixture(&path, &fixture)?;
+                Ok(fixture.body)
+            }
+        }
+    }
+
+    fn path(&self, service: &str, key: B256) -> PathBuf {
+        self.dir.join(service).join(format!("{}.json", hex::encode(key)))
+    }
+}
+
+/// Keys a JSON-RPC call by its method and params, whichever its ID.
+fn rpc_key(method: &str, params: &Value) -> B256 {
+    keccak256(json!([method, params]).to_string())
+}
+
+/// Returns whether the error message of a response tells that a rate limit is hit, e.g.,
+/// `Max rate limit reached` from the explorer, in which case the response is not recorded.
+fn is_rate_limited(message: &Value) -> bool {
+    message.as_str().is_some_and(|message| {
+        let message = message.to_lowercase();
+        message.contains("rate limit") || message.contains("too many requests")
+    })
+}
+
+fn rpc_error(message: String) -> Value {
+    json!({ "error": { "code": -32000, "message": message } })
+}
+
+/// Removes the API key from the query, so that fixtures do not leak it and replay with any key.
+fn strip_api_key(query: &str) -> String {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.starts_with("apikey="))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn read_fixture<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    let content = std::fs::read_to_string(path).map_err(|_| eyre!("not recorded"))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn write_fixture<T: Serialize>(path: &Path, fixture: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(fixture)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fixtures() {
+        let dir = std::env::temp_dir().join(format!("edb-fixtures-test-{}", std::process::id()));
+        let call = json!({ "jsonrpc": "2.0", "id": 7, "method": "eth_chainId", "params": [] });
+        let server =
+            |mode| FixtureServer { mode, dir: dir.clone(), client: reqwest::Client::new() };
+
+        // a recorded response is replayed under the ID of the new call
+        let path = server(FixtureMode::Record).path("rpc", rpc_key("eth_chainId", &json!([])));
+        let fixture = RpcFixture {
+            method: "eth_chainId".to_string(),
+            params: json!([]),
+            response: json!({ "result": "0x1" }),
+        };
+        write_fixture(&path, &fixture).unwrap();
+        let response = server(FixtureMode::Replay).rpc_call("", call).await;
+        assert_eq!(response, json!({ "jsonrpc": "2.0", "id": 7, "result": "0x1" }));
+
+        // the calls which were not recorded fail in replay mode
+        let call = json!({ "jsonrpc": "2.0", "id": 8, "method": "eth_blockNumber", "params": [] });
+        let response = server(FixtureMode::Replay).rpc_call("", call).await;
+        assert!(response["error"]["message"].as_str().unwrap().contains("eth_blockNumber"));
+
+        assert!(is_rate_limited(&json!("Max rate limit reached, please use API Key")));
+        assert!(!is_rate_limited(&json!("execution reverted")));
+        assert!(!is_rate_limited(&Value::Null));
+
+        let query = strip_api_key("module=contract&apikey=secret&address=0x1");
+        assert_eq!(query, "module=contract&address=0x1");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
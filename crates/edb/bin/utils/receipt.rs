@@ -0,0 +1,143 @@
+//! Validation of the replayed target transaction against its on-chain receipt.
+
+use std::fmt;
+
+use alloy_primitives::Log;
+use revm::primitives::ExecutionResult;
+use yansi::Paint;
+
+/// The differences between the replayed transaction and its on-chain receipt.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReceiptDiff {
+    /// The on-chain and replayed status, if they differ.
+    pub status: Option<(bool, bool)>,
+    /// The on-chain and replayed gas used, if they differ.
+    pub gas_used: Option<(u128, u128)>,
+    /// The logs which differ, by index, as emitted on chain and in the replay.
+    pub logs: Vec<(usize, Option<Log>, Option<Log>)>,
+}
+
+impl ReceiptDiff {
+    /// Compares the result of the replay with the status, gas used and logs of the receipt.
+    ///
+    /// The gas used is only compared when `check_gas` is set, since some chains account for gas
+    /// differently from revm.
+    pub fn new(
+        result: &ExecutionResult,
+        status: bool,
+        gas_used: u128,
+        logs: &[Log],
+        check_gas: bool,
+    ) -> Self {
+        let mut diff = Self::default();
+        if result.is_success() != status {
+            diff.status = Some((status, result.is_success()));
+        }
+        let replayed_gas = result.gas_used() as u128;
+        if check_gas && replayed_gas != gas_used {
+            diff.gas_used = Some((gas_used, replayed_gas));
+        }
+        let replayed_logs = result.logs();
+        for index in 0..logs.len().max(replayed_logs.len()) {
+            let (expected, actual) = (logs.get(index), replayed_logs.get(index));
+            if expected != actual {
+                diff.logs.push((index, expected.cloned(), actual.cloned()));
+            }
+        }
+        diff
+    }
+
+    /// Returns whether the replay matches the receipt.
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none() && self.gas_used.is_none() && self.logs.is_empty()
+    }
+
+    /// Summarizes the differences in a single line, e.g., for the warnings pane.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![];
+        if self.status.is_some() {
+            parts.push("status differs".to_string());
+        }
+        if let Some((expected, actual)) = self.gas_used {
+            parts.push(format!("gas used differs ({expected} on chain, {actual} replayed)"));
+        }
+        if !self.logs.is_empty() {
+            parts.push(format!("{} log(s) differ", self.logs.len()));
+        }
+        parts.join(", ")
+    }
+}
+
+impl fmt::Display for ReceiptDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "{}", "The replay matches the on-chain receipt".green());
+        }
+        writeln!(f, "{}", "The replay does NOT match the on-chain receipt!".red().bold())?;
+        writeln!(f, "{}", "(- on chain, + replayed)".dim())?;
+        let status = |success: bool| if success { "success" } else { "reverted" };
+        if let Some((expected, actual)) = self.status {
+            writeln!(f, "status:")?;
+            writeln!(f, "{}", format!("- {}", status(expected)).red())?;
+            writeln!(f, "{}", format!("+ {}", status(actual)).green())?;
+        }
+        if let Some((expected, actual)) = self.gas_used {
+            writeln!(f, "gas used:")?;
+            writeln!(f, "{}", format!("- {expected}").red())?;
+            writeln!(f, "{}", format!("+ {actual}").green())?;
+        }
+        for (index, expected, actual) in &self.logs {
+            writeln!(f, "log #{index}:")?;
+            if let Some(log) = expected {
+                writeln!(f, "{}", format!("- {}", format_log(log)).red())?;
+            }
+            if let Some(log) = actual {
+                writeln!(f, "{}", format!("+ {}", format_log(log)).green())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn format_log(log: &Log) -> String {
+    let topics = log.topics().iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+    format!("{} topics=[{topics}] data={}", log.address, log.data.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, b256, Bytes, LogData};
+    use revm::primitives::{Output, SuccessReason};
+
+    #[test]
+    fn test_receipt_diff() {
+        let log = |data: &'static [u8]| Log {
+            address: address!("0000000000000000000000000000000000000001"),
+            data: LogData::new_unchecked(
+                vec![b256!("0000000000000000000000000000000000000000000000000000000000000002")],
+                Bytes::from_static(data),
+            ),
+        };
+        let result = ExecutionResult::Success {
+            reason: SuccessReason::Stop,
+            gas_used: 21000,
+            gas_refunded: 0,
+            logs: vec![log(b"a"), log(b"b")],
+            output: Output::Call(Bytes::new()),
+        };
+
+        assert!(ReceiptDiff::new(&result, true, 21000, &[log(b"a"), log(b"b")], true).is_empty());
+        // gas is ignored on chains with different accounting
+        assert!(ReceiptDiff::new(&result, true, 42, &[log(b"a"), log(b"b")], false).is_empty());
+
+        let diff = ReceiptDiff::new(&result, false, 42, &[log(b"a")], true);
+        assert_eq!(diff.status, Some((false, true)));
+        assert_eq!(diff.gas_used, Some((42, 21000)));
+        assert_eq!(diff.logs, vec![(1, None, Some(log(b"b")))]);
+        assert_eq!(
+            diff.summary(),
+            "status differs, gas used differs (42 on chain, 21000 replayed), 1 log(s) differ"
+        );
+    }
+}
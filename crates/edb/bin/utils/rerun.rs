@@ -0,0 +1,111 @@
+//! Re-running the debugged transaction with the changes made from the debugger.
+
+use std::future::Future;
+
+use edb_debug_backend::artifact::debug::DebugArtifact;
+use edb_debug_frontend::{BlackboxFile, DebugFrontend, ExitReason, MachineInterface, TxEdit};
+use edb_utils::cache::CachePath;
+use eyre::Result;
+use revm::primitives::EnvWithHandlerCfg;
+
+use crate::{
+    opts::UiOpts,
+    utils::{session_hash::env_hash, web},
+};
+
+/// Applies the changes to the transaction environment.
+pub fn apply_tx_edit(env: &mut EnvWithHandlerCfg, edit: &TxEdit) {
+    if let Some(data) = &edit.data {
+        env.tx.data = data.clone();
+    }
+    if let Some(value) = edit.value {
+        env.tx.value = value;
+    }
+    if let Some(gas) = edit.gas {
+        env.tx.gas_limit = gas;
+    }
+    if let Some(from) = edit.from {
+        env.tx.caller = from;
+        // the nonce of the original sender does not apply to the new one
+        env.tx.nonce = None;
+    }
+}
+
+/// Debugs the transaction, and whenever it is modified from the debugger, re-runs it with
+/// `analyze` and opens the result in a new session, compared with the one it was modified from.
+///
+/// Over the machine interface, or when following another session, the transaction is debugged
+/// as is.
+pub async fn debug_with_reruns<F, Fut>(
+    ui: &UiOpts,
+    artifact: DebugArtifact,
+    env: EnvWithHandlerCfg,
+    mut analyze: F,
+) -> Result<()>
+where
+    F: FnMut(EnvWithHandlerCfg) -> Fut,
+    Fut: Future<Output = Result<DebugArtifact>>,
+{
+    if ui.machine_interface {
+        return MachineInterface::new(&artifact)
+            .step_into_getters(ui.step_into_getters)
+            .blackbox(BlackboxFile::load(CachePath::edb_blackbox_file()))
+            .run(std::io::stdin().lock(), std::io::stdout());
+    }
+
+    let theme = ui.theme();
+    let sync_dir = ui.sync_dir();
+    let session = env_hash(&env);
+    if let Some(url) = &ui.follow {
+        let driver = web::follow(url, &artifact, session).await?;
+        let mut frontend = DebugFrontend::builder()
+            .theme(theme)
+            .sync_dir(sync_dir)
+            .step_into_getters(ui.step_into_getters)
+            .follow(driver)
+            .build(artifact);
+        frontend.render().await?;
+        return Ok(());
+    }
+
+    let followers = match ui.share {
+        Some(listen) => Some(web::share(listen, &artifact, session).await?),
+        None => None,
+    };
+    let mut frontend = DebugFrontend::builder()
+        .theme(theme)
+        .sync_dir(sync_dir.clone())
+        .share(followers)
+        .step_into_getters(ui.step_into_getters)
+        .rerunnable(true)
+        .build(artifact);
+    let mut envs = vec![env];
+    loop {
+        let ExitReason::Rerun { session, edit } = frontend.render().await? else {
+            return Ok(());
+        };
+
+        let mut env = envs[session].clone();
+        apply_tx_edit(&mut env, &edit);
+        println!("Re-running the transaction with the changes...");
+        let artifact = analyze(env.clone()).await?;
+
+        let mut artifacts = std::mem::take(&mut frontend.artifacts);
+        let name = format!("{} (edit #{})", artifacts[session].0, envs.len());
+        artifacts.push((name, artifact));
+        envs.push(env);
+
+        let index = artifacts.len() - 1;
+        let followers = frontend.share.take();
+        let state = frontend.state.take();
+        frontend = DebugFrontend::builder()
+            .theme(theme)
+            .sync_dir(sync_dir.clone())
+            .share(followers)
+            .step_into_getters(ui.step_into_getters)
+            .rerunnable(true)
+            .compare(index, session)
+            .state(state)
+            .build_sessions(artifacts);
+    }
+}
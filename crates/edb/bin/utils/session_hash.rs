@@ -0,0 +1,171 @@
+//! Content hashes of the replay of a transaction, so that two parties can check that they debugged
+//! the identical execution: the same block and transaction environment, the same pre-state, and
+//! the same outcome.
+//!
+//! The inputs and the results are hashed apart, so that a mismatch tells whether the replays
+//! started from different states or only diverged in their execution, e.g., with different
+//! versions of the EVM.
+
+use std::{collections::BTreeMap, fmt};
+
+use alloy_primitives::{keccak256, Address, B256};
+use revm::primitives::{EnvWithHandlerCfg, ExecutionResult, Output};
+use serde_json::{json, Value};
+
+use crate::utils::fixture::FixtureAccount;
+
+/// The content hashes of a replay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionHash {
+    /// The hash of the block and transaction environment, and of the pre-state read.
+    pub inputs: B256,
+    /// The hash of the outcome, i.e., the status, the gas used, the output, and the logs.
+    pub results: B256,
+}
+
+impl SessionHash {
+    pub fn new(
+        env: &EnvWithHandlerCfg,
+        prestate: &BTreeMap<Address, FixtureAccount>,
+        result: &ExecutionResult,
+    ) -> Self {
+        let inputs = json!({
+            "spec": format!("{:?}", env.handler_cfg.spec_id),
+            "chain_id": env.cfg.chain_id,
+            "block": block_env(env),
+            "tx": tx_env(env),
+            "prestate": prestate
+                .iter()
+                .map(|(address, account)| {
+                    let storage = account.storage.iter().collect::<Vec<_>>();
+                    json!([address, account.balance, account.nonce, account.code, storage])
+                })
+                .collect::<Vec<_>>(),
+        });
+        Self { inputs: hash(&inputs), results: hash(&execution_result(result)) }
+    }
+
+    /// Returns the hash of the whole session, i.e., of its inputs and results.
+    pub fn combined(&self) -> B256 {
+        keccak256([self.inputs.0, self.results.0].concat())
+    }
+}
+
+impl fmt::Display for SessionHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (inputs {}, results {})", self.combined(), self.inputs, self.results)
+    }
+}
+
+/// Returns the hash of the block and transaction environment, which tells apart the executions
+/// debugged by the clients of a shared session, without needing the pre-state.
+pub fn env_hash(env: &EnvWithHandlerCfg) -> B256 {
+    hash(&json!({
+        "spec": format!("{:?}", env.handler_cfg.spec_id),
+        "chain_id": env.cfg.chain_id,
+        "block": block_env(env),
+        "tx": tx_env(env),
+    }))
+}
+
+/// Hashes the compact JSON encoding of the value, whose members are always built in the same
+/// order.
+fn hash(value: &Value) -> B256 {
+    keccak256(value.to_string())
+}
+
+fn block_env(env: &EnvWithHandlerCfg) -> Value {
+    let block = &env.block;
+    let excess_blob_gas = block.blob_excess_gas_and_price.as_ref().map(|blob| blob.excess_blob_gas);
+    json!({
+        "number": block.number,
+        "coinbase": block.coinbase,
+        "timestamp": block.timestamp,
+        "gas_limit": block.gas_limit,
+        "basefee": block.basefee,
+        "difficulty": block.difficulty,
+        "prevrandao": block.prevrandao,
+        "excess_blob_gas": excess_blob_gas,
+    })
+}
+
+fn tx_env(env: &EnvWithHandlerCfg) -> Value {
+    let tx = &env.tx;
+    json!({
+        "caller": tx.caller,
+        "to": tx.transact_to.to(),
+        "value": tx.value,
+        "data": tx.data,
+        "nonce": tx.nonce,
+        "gas_limit": tx.gas_limit,
+        "gas_price": tx.gas_price,
+        "gas_priority_fee": tx.gas_priority_fee,
+        "access_list": tx.access_list,
+        "blob_hashes": tx.blob_hashes,
+        "max_fee_per_blob_gas": tx.max_fee_per_blob_gas,
+    })
+}
+
+fn execution_result(result: &ExecutionResult) -> Value {
+    let (status, output) = match result {
+        ExecutionResult::Success { reason, output, .. } => {
+            let output = match output {
+                Output::Call(output) => json!(output),
+                Output::Create(output, address) => json!([output, address]),
+            };
+            (format!("{reason:?}"), output)
+        }
+        ExecutionResult::Revert { output, .. } => ("Revert".to_string(), json!(output)),
+        ExecutionResult::Halt { reason, .. } => (format!("{reason:?}"), Value::Null),
+    };
+    let logs = result
+        .logs()
+        .iter()
+        .map(|log| json!([log.address, log.topics(), log.data.data]))
+        .collect::<Vec<_>>();
+    json!({
+        "status": status,
+        "gas_used": result.gas_used(),
+        "output": output,
+        "logs": logs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Bytes, U256};
+    use revm::primitives::SuccessReason;
+
+    use super::*;
+
+    #[test]
+    fn test_session_hash() {
+        let env = EnvWithHandlerCfg::default();
+        let account = FixtureAccount { balance: U256::from(1), ..Default::default() };
+        let prestate = BTreeMap::from([(Address::with_last_byte(1), account)]);
+        let result = ExecutionResult::Success {
+            reason: SuccessReason::Stop,
+            gas_used: 21000,
+            gas_refunded: 0,
+            logs: Vec::new(),
+            output: Output::Call(Bytes::new()),
+        };
+
+        let hash = SessionHash::new(&env, &prestate, &result);
+        assert_eq!(hash, SessionHash::new(&env, &prestate, &result));
+
+        // a different pre-state changes the inputs only
+        let other = SessionHash::new(&env, &BTreeMap::new(), &result);
+        assert_ne!(other.inputs, hash.inputs);
+        assert_eq!(other.results, hash.results);
+        assert_ne!(other.combined(), hash.combined());
+
+        let reverted = ExecutionResult::Revert { gas_used: 21000, output: Bytes::new() };
+        assert_ne!(SessionHash::new(&env, &prestate, &reverted).results, hash.results);
+
+        let mut other = env.clone();
+        other.tx.value = U256::from(1);
+        assert_eq!(env_hash(&env), env_hash(&env.clone()));
+        assert_ne!(env_hash(&other), env_hash(&env));
+    }
+}
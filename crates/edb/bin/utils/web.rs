@@ -0,0 +1,410 @@
+//! The browser-based frontend: a static page rendering the trace, source and storage panes, and a
+//! WebSocket API driving the session with the commands of the machine interface.
+//!
+//! When a client connects to `/ws`, it receives a `session` message with what does not change
+//! while stepping, i.e., the identifier of the execution, the call frames, the sources and the
+//! storage accesses, followed by a `location` message whenever the position changes. Every
+//! client follows the same position.
+//!
+//! Only the clients connecting with the token of the session drive it, e.g., from the URL printed
+//! by `edb serve`. The other clients are read-only observers, e.g., browsers or terminal UIs run
+//! with `--follow`, so that a session can be shared during an incident without handing over the
+//! controls. A session debugged in the terminal UI with `--share` is driven by the UI alone.
+//! WebSockets opened by the pages of other origins are refused.
+
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
+
+use alloy_primitives::{hex, B256};
+
+use edb_debug_backend::{
+    analysis::storage::{storage_accesses, StorageAccessKind},
+    artifact::debug::DebugArtifact,
+    export::calltree::{call_frames, CallFrame},
+};
+use edb_debug_frontend::{Command, Event, Location, MachineInterface};
+use eyre::{ensure, eyre, Result};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, watch},
+};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role, Message},
+    WebSocketStream,
+};
+
+use crate::utils::http::{self, HttpRequest};
+
+/// The page of the frontend, which has no other assets.
+const INDEX_HTML: &str = include_str!("../../assets/web/index.html");
+
+/// A command of a client, along with where to send the error it may result in.
+type ClientCommand = (Command, mpsc::UnboundedSender<String>);
+
+/// What the connections share with the task driving the session.
+#[derive(Clone)]
+struct Shared {
+    session: Arc<String>,
+    /// The secret the driving clients connect with, if the session can be driven from the web.
+    token: Option<Arc<String>>,
+    locations: watch::Receiver<String>,
+    commands: mpsc::UnboundedSender<ClientCommand>,
+}
+
+/// Serves the session at the given address until Ctrl-C is pressed. The session is identified
+/// by the hash of its environment, see [`env_hash`](crate::utils::session_hash::env_hash).
+pub async fn serve(
+    listen: SocketAddr,
+    name: &str,
+    artifact: &DebugArtifact,
+    id: B256,
+) -> Result<()> {
+    let frames = call_frames(artifact);
+    let session = Arc::new(session_message(name, artifact, &frames, id).to_string());
+    let mut interface = MachineInterface::new(artifact);
+    let (locations, _) = watch::channel(location_message(&frames, &interface.location()));
+
+    let token = Arc::new(hex::encode(&B256::random()[..16]));
+
+    let listener = TcpListener::bind(listen).await?;
+    println!("EDB is serving {name} (Ctrl-C to stop)");
+    println!("  drive it at   http://{listen}/?token={token}");
+    println!("  follow it at  http://{listen}/ (read-only)");
+
+    // the session is driven from this task, the connections only forward the commands
+    let (commands, mut received) = mpsc::unbounded_channel::<ClientCommand>();
+    let shared = Shared { session, token: Some(token), locations: locations.subscribe(), commands };
+    tokio::spawn(accept_connections(listener, shared));
+    loop {
+        tokio::select! {
+            Some((command, reply)) = received.recv() => match interface.execute(command) {
+                Event::Location(location) => {
+                    locations.send_replace(location_message(&frames, &location));
+                }
+                event => {
+                    let _ = reply.send(serde_json::to_string(&event)?);
+                }
+            },
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Shares, read-only, the session driven from the terminal UI at the given address. Returns
+/// where the UI sends its position, which is shared until the returned sender is dropped.
+pub async fn share(
+    listen: SocketAddr,
+    artifact: &DebugArtifact,
+    id: B256,
+) -> Result<std::sync::mpsc::Sender<Location>> {
+    let name = match artifact.debug_arena.first() {
+        Some(node) => artifact.address_label(&node.address),
+        None => "transaction".to_string(),
+    };
+    let frames = call_frames(artifact);
+    let session = Arc::new(session_message(&name, artifact, &frames, id).to_string());
+    let initial = MachineInterface::new(artifact).location();
+    let (locations, _) = watch::channel(location_message(&frames, &initial));
+
+    let listener = TcpListener::bind(listen).await?;
+    println!("EDB is sharing {name} at http://{listen}/ (read-only)");
+
+    // no client drives the session, so that the commands are never received
+    let (commands, _) = mpsc::unbounded_channel();
+    let shared = Shared { session, token: None, locations: locations.subscribe(), commands };
+    tokio::spawn(accept_connections(listener, shared));
+
+    let (sender, receiver) = std::sync::mpsc::channel::<Location>();
+    std::thread::Builder::new().name("share".into()).spawn(move || {
+        for location in receiver {
+            locations.send_replace(location_message(&frames, &location));
+        }
+    })?;
+    Ok(sender)
+}
+
+async fn accept_connections(listener: TcpListener, shared: Shared) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else { continue };
+        let shared = shared.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, shared).await {
+                debug!("web connection failed: {e}");
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, shared: Shared) -> Result<()> {
+    let request = http::read_request(&mut stream).await?;
+    let (status, content_type, body) = match request.path() {
+        "/ws" if !is_same_origin(&request) => {
+            ("403 Forbidden", "text/plain", "cross-origin connections are not allowed")
+        }
+        "/ws" => {
+            let Some(key) = request.header("sec-websocket-key") else {
+                let headers = [("Content-Type", "text/plain")];
+                let body = b"expected a WebSocket handshake";
+                return http::write_response(&mut stream, "400 Bad Request", &headers, body).await;
+            };
+            let response = format!(
+                "HTTP/1.1 101 Switching Protocols\r\n\
+Upgrade: websocket\r\n\
+Connection: Upgrade\r\n\
+Sec-WebSocket-Accept: {}\r\n\r\n",
+                derive_accept_key(key.as_bytes())
+            );
+            stream.write_all(response.as_bytes()).await?;
+            let socket = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+            let driver = shared.token.as_ref().is_some_and(|token| {
+                request.query().split('&').any(|pair| pair == format!("token={token}"))
+            });
+            return handle_websocket(socket, shared, driver).await;
+        }
+        "/" | "/index.html" => ("200 OK", "text/html; charset=utf-8", INDEX_HTML),
+        _ => ("404 Not Found", "text/plain", "not found"),
+    };
+    let headers = [("Content-Type", content_type)];
+    http::write_response(&mut stream, status, &headers, body.as_bytes()).await
+}
+
+/// Returns whether the WebSocket is opened by the page of the session, or by a client which is
+/// not a browser, e.g., a terminal UI following the session. Browsers always send the origin of
+/// the page opening a WebSocket, so that other sites cannot reach a session served locally.
+fn is_same_origin(request: &HttpRequest) -> bool {
+    let Some(origin) = request.header("origin") else { return true };
+    let host = origin.split_once("://").map_or(origin, |(_, host)| host);
+    request.header("host") == Some(host)
+}
+
+async fn handle_websocket(
+    socket: WebSocketStream<TcpStream>,
+    shared: Shared,
+    driver: bool,
+) -> Result<()> {
+    let Shared { session, mut locations, commands, .. } = shared;
+    let (mut sink, mut source) = socket.split();
+    let (replies, mut pending) = mpsc::unbounded_channel();
+    sink.send(Message::Text(session.to_string())).await?;
+    sink.send(Message::Text(json!({ "type": "role", "driver": driver }).to_string())).await?;
+    let location = locations.borrow_and_update().clone();
+    sink.send(Message::Text(location)).await?;
+
+    loop {
+        tokio::select! {
+            changed = locations.changed() => {
+                // the session is over
+                if changed.is_err() {
+                    return Ok(());
+                }
+                let location = locations.borrow_and_update().clone();
+                sink.send(Message::Text(location)).await?;
+            }
+            Some(reply) = pending.recv() => sink.send(Message::Text(reply)).await?,
+            message = source.next() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                    Ok(Command::Quit) => return Ok(()),
+                    Ok(command) if driver => {
+                        let _ = commands.send((command, replies.clone()));
+                    }
+                    Ok(_) => {
+                        let error = Event::Error {
+                            message: "this connection only follows the session".to_string(),
+                        };
+                        sink.send(Message::Text(serde_json::to_string(&error)?)).await?;
+                    }
+                    Err(e) => {
+                        let error = Event::Error { message: format!("invalid command: {e}") };
+                        sink.send(Message::Text(serde_json::to_string(&error)?)).await?;
+                    }
+                },
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+            },
+        }
+    }
+}
+
+/// Follows a session served by `edb serve`, or shared with `--share`, at the given URL, which
+/// must debug the execution with the same identifier. Returns the step of the driver, in the
+/// whole execution, each time it moves.
+pub async fn follow(
+    url: &str,
+    artifact: &DebugArtifact,
+    id: B256,
+) -> Result<std::sync::mpsc::Receiver<usize>> {
+    let (socket, _) = tokio_tungstenite::connect_async(websocket_url(url)).await?;
+    let (_, mut source) = socket.split();
+    let session = loop {
+        match source.next().await {
+            Some(Ok(Message::Text(text))) => break serde_json::from_str::<Value>(&text)?,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(e.into()),
+            None => return Err(eyre!("the session at {url} closed the connection")),
+        }
+    };
+    ensure!(
+        session["id"].as_str() == Some(id.to_string().as_str()),
+        "the session at {url} debugs another execution ({} rather than {id})",
+        session["id"]
+    );
+    let steps = artifact.steps().count();
+    ensure!(
+        session["steps"].as_u64() == Some(steps as u64),
+        "the session at {url} was recorded differently ({} steps rather than {steps})",
+        session["steps"]
+    );
+
+    let (driver, receiver) = std::sync::mpsc::channel();
+    tokio::spawn(async move {
+        while let Some(Ok(message)) = source.next().await {
+            let Message::Text(text) = message else { continue };
+            let Ok(message) = serde_json::from_str::<Value>(&text) else { continue };
+            if message["type"] != "location" {
+                continue;
+            }
+            let Some(step) = message["step"].as_u64() else { continue };
+            if driver.send(step as usize).is_err() {
+                // the debugger has exited
+                return;
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Returns the WebSocket endpoint of a session from the URL of its page, e.g.,
+/// `http://127.0.0.1:8547/` as printed by `edb serve`.
+fn websocket_url(url: &str) -> String {
+    let (scheme, rest) = match url.split_once("://") {
+        Some(("https" | "wss", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", url),
+    };
+    let host = rest.split(['/', '?']).next().unwrap_or(rest);
+    format!("{scheme}://{host}/ws")
+}
+
+/// Builds the message describing what does not change while stepping.
+fn session_message(name: &str, artifact: &DebugArtifact, frames: &[CallFrame], id: B256) -> Value {
+    // frames are listed before their sub-calls
+    let mut depths = vec![0; frames.len()];
+    for (index, frame) in frames.iter().enumerate() {
+        for child in &frame.children {
+            depths[*child] = depths[index] + 1;
+        }
+    }
+    let frames = frames
+        .iter()
+        .zip(depths)
+        .map(|(frame, depth)| {
+            json!({
+                "label": artifact.address_label(&frame.address),
+                "function": frame.function(artifact).map(|function| &function.name),
+                "kind": frame.kind.to_string(),
+                "depth": depth,
+                "steps": [frame.steps.start, frame.steps.end],
+                "outcome": format!("{:?}", frame.outcome),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let compilations = &artifact.compilation_artifacts;
+    let sources = compilations
+        .addresses()
+        .filter_map(|address| compilations.get(address))
+        .flat_map(|compilation| compilation.sources.values())
+        .map(|source| (source.path.display().to_string(), source.code.as_str()))
+        .collect::<BTreeMap<_, _>>();
+
+    let storage = storage_accesses(artifact)
+        .into_iter()
+        .map(|access| {
+            json!({
+                "step": access.step,
+                "address": access.address,
+                "slot": access.slot,
+                "kind": match access.kind {
+                    StorageAccessKind::Read => "read",
+                    StorageAccessKind::Write => "write",
+                },
+                "value": access.value,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "type": "session",
+        "id": id,
+        "name": name,
+        "steps": artifact.steps().count(),
+        "frames": frames,
+        "sources": sources,
+        "storage": storage,
+    })
+}
+
+/// Builds the message of the current position, along with the innermost frame containing it.
+fn location_message(frames: &[CallFrame], location: &Location) -> String {
+    let frame = frames.iter().rposition(|frame| frame.steps.contains(&location.step));
+    let mut message = json!(location);
+    message["type"] = json!("location");
+    message["frame"] = json!(frame);
+    message.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use edb_debug_backend::artifact::debug::{DebugNodeFlat, DebugStep};
+    use revm::interpreter::opcode::{SLOAD, STOP};
+
+    use super::*;
+
+    #[test]
+    fn test_web_messages() {
+        let steps = vec![
+            DebugStep { instruction: SLOAD, stack: vec![Default::default()], ..Default::default() },
+            DebugStep { instruction: STOP, pc: 1, ..Default::default() },
+        ];
+        let node = DebugNodeFlat { steps, ..Default::default() };
+        let artifact = DebugArtifact { debug_arena: vec![node], ..Default::default() };
+        let frames = call_frames(&artifact);
+
+        let session = session_message("tx", &artifact, &frames, B256::with_last_byte(1));
+        assert_eq!(session["id"], B256::with_last_byte(1).to_string());
+        assert_eq!(session["steps"], 2);
+        assert_eq!(session["frames"].as_array().unwrap().len(), 1);
+        assert_eq!(session["storage"][0]["kind"], "read");
+
+        let mut interface = MachineInterface::new(&artifact);
+        let Event::Location(location) = interface.execute(Command::StepInstruction) else {
+            panic!("expected a location");
+        };
+        let message: Value = serde_json::from_str(&location_message(&frames, &location)).unwrap();
+        assert_eq!(message["type"], "location");
+        assert_eq!((message["step"].as_u64(), message["frame"].as_u64()), (Some(1), Some(0)));
+    }
+
+    #[test]
+    fn test_is_same_origin() {
+        let request = |origin: Option<&str>| {
+            let mut headers = vec![("Host".to_string(), "127.0.0.1:8547".to_string())];
+            headers.extend(origin.map(|origin| ("Origin".to_string(), origin.to_string())));
+            HttpRequest { headers, ..Default::default() }
+        };
+        assert!(is_same_origin(&request(None)));
+        assert!(is_same_origin(&request(Some("http://127.0.0.1:8547"))));
+        assert!(!is_same_origin(&request(Some("https://evil.example.com"))));
+        assert!(!is_same_origin(&request(Some("null"))));
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(websocket_url("http://127.0.0.1:8547/"), "ws://127.0.0.1:8547/ws");
+        assert_eq!(websocket_url("https://edb.example.com/?token=00"), "wss://edb.example.com/ws");
+        assert_eq!(websocket_url("localhost:8547"), "ws://localhost:8547/ws");
+    }
+}
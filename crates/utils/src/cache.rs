@@ -5,9 +5,36 @@ use alloy_chains::Chain;
 pub struct CachePath {}
 
 impl CachePath {
+    /// Returns the path to edb's home dir: `~/.edb`, which can be overridden with the `EDB_HOME`
+    /// environment variable.
+    ///
+    /// The home dir is resolved per platform, e.g., `%USERPROFILE%` on Windows.
+    pub fn edb_dir() -> Option<PathBuf> {
+        match std::env::var_os("EDB_HOME") {
+            Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+            _ => dirs_next::home_dir().map(|p| p.join(".edb")),
+        }
+    }
+
+    /// Returns the path to edb's crash report dir: `~/.edb/crash`.
+    pub fn edb_crash_report_dir() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("crash"))
+    }
+
     /// Returns the path to edb's cache dir: `~/.edb/cache`.
     pub fn edb_cache_dir() -> Option<PathBuf> {
-        dirs_next::home_dir().map(|p| p.join(".edb").join("cache"))
+        Some(Self::edb_dir()?.join("cache"))
+    }
+
+    /// Returns the path to the solc binaries managed by edb: `~/.edb/solc`.
+    pub fn edb_solc_dir() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("solc"))
+    }
+
+    /// Returns the path to the list of replayed transactions, used for shell completion:
+    /// `~/.edb/cache/tx-history.txt`
+    pub fn edb_tx_history_file() -> Option<PathBuf> {
+        Some(Self::edb_cache_dir()?.join("tx-history.txt"))
     }
 
     /// Returns the path to edb rpc cache dir: `~/.edb/cache/rpc`.
@@ -41,4 +68,47 @@ impl CachePath {
     pub fn edb_etherscan_chain_cache_dir(chain_id: impl Into<Chain>) -> Option<PathBuf> {
         Some(Self::edb_etherscan_cache_dir()?.join(chain_id.into().to_string()))
     }
+
+    /// Returns the path to edb's token metadata cache file for `chain_id`:
+    /// `~/.edb/cache/tokens/<chain>.json`
+    pub fn edb_token_cache_file(chain_id: impl Into<Chain>) -> Option<PathBuf> {
+        Some(Self::edb_cache_dir()?.join("tokens").join(format!("{}.json", chain_id.into())))
+    }
+
+    /// Returns the path to the breakpoints kept across sessions: `~/.edb/breakpoints.json`
+    pub fn edb_breakpoints_file() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("breakpoints.json"))
+    }
+
+    /// Returns the path to the function breakpoints kept across sessions:
+    /// `~/.edb/function_breakpoints.json`
+    pub fn edb_function_breakpoints_file() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("function_breakpoints.json"))
+    }
+
+    /// Returns the path to the contracts stepped over when stepping: `~/.edb/blackbox.json`
+    pub fn edb_blackbox_file() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("blackbox.json"))
+    }
+
+    /// Returns the path to the directory shared with editor plugins: `~/.edb/sync`
+    pub fn edb_sync_dir() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("sync"))
+    }
+
+    /// Returns the path to the user-maintained list of flagged addresses: `~/.edb/flags.json`
+    pub fn edb_flags_file() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("flags.json"))
+    }
+
+    /// Returns the path to the per-chain block explorer config: `~/.edb/explorers.toml`
+    pub fn edb_explorer_config_file() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("explorers.toml"))
+    }
+
+    /// Returns the path to the user-maintained token metadata file, which overrides any fetched
+    /// metadata: `~/.edb/tokens.json`
+    pub fn edb_token_override_file() -> Option<PathBuf> {
+        Some(Self::edb_dir()?.join("tokens.json"))
+    }
 }
@@ -1,2 +1,3 @@
 pub mod cache;
+pub mod profile;
 pub mod progress_bar;
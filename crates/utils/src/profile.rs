@@ -0,0 +1,213 @@
+//! A lightweight profiler of EDB itself, enabled with `--profile`, which measures where the time
+//! of a session goes, e.g., to tell whether the RPC endpoint or the explorer rate limit is the
+//! bottleneck.
+//!
+//! Code is timed with [`span`], whose guard records the elapsed time when dropped. The time of
+//! nested spans is only counted in the innermost one, so that fetching state from the RPC
+//! endpoint while executing is not counted as execution.
+
+use std::{
+    cell::RefCell,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The time spent in each phase, and the number of spans, indexed by phase.
+static TOTALS: Mutex<[(Duration, u64); Phase::ALL.len()]> =
+    Mutex::new([(Duration::ZERO, 0); Phase::ALL.len()]);
+
+thread_local! {
+    /// The time spent in the nested spans of each open span of the thread, from the outermost
+    /// to the innermost.
+    static NESTED: RefCell<Vec<Duration>> = const { RefCell::new(Vec::new()) };
+}
+
+/// What the time is spent on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Fetching state from the RPC endpoint.
+    Rpc,
+    /// Replaying the transactions preceding the debugged one, including their RPC fetches.
+    Replay,
+    /// Fetching source code from the explorer, including the waits for its rate limit.
+    Explorer,
+    /// Compiling source code, including installing compilers.
+    Compile,
+    /// Executing the debugged transaction.
+    Execute,
+    /// Analyzing the execution, e.g., source maps, interfaces, and token metadata.
+    Analyze,
+    /// Drawing the terminal UI.
+    Render,
+}
+
+impl Phase {
+    pub const ALL: [Self; 7] = [
+        Self::Rpc,
+        Self::Replay,
+        Self::Explorer,
+        Self::Compile,
+        Self::Execute,
+        Self::Analyze,
+        Self::Render,
+    ];
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|phase| *phase == self).expect("all phases are listed")
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rpc => write!(f, "RPC fetches"),
+            Self::Replay => write!(f, "replaying preceding transactions"),
+            Self::Explorer => write!(f, "explorer fetches"),
+            Self::Compile => write!(f, "compilation"),
+            Self::Execute => write!(f, "execution"),
+            Self::Analyze => write!(f, "analysis"),
+            Self::Render => write!(f, "rendering"),
+        }
+    }
+}
+
+/// Enables the profiler. Spans started before are not recorded.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A timed span of code, which is recorded when dropped.
+#[derive(Debug)]
+#[must_use = "the span is recorded when dropped"]
+pub struct Span {
+    phase: Phase,
+    /// When the span started, or `None` if the profiler is disabled.
+    start: Option<Instant>,
+}
+
+/// Starts timing a span of code of the given phase, until the returned guard is dropped.
+pub fn span(phase: Phase) -> Span {
+    if !is_enabled() {
+        return Span { phase, start: None };
+    }
+    NESTED.with(|nested| nested.borrow_mut().push(Duration::ZERO));
+    Span { phase, start: Some(Instant::now()) }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some(start) = self.start else { return };
+        let elapsed = start.elapsed();
+        let nested = NESTED.with(|nested| {
+            let mut nested = nested.borrow_mut();
+            let time = nested.pop().unwrap_or_default();
+            if let Some(parent) = nested.last_mut() {
+                *parent += elapsed;
+            }
+            time
+        });
+
+        let mut totals = TOTALS.lock().unwrap_or_else(|e| e.into_inner());
+        let (time, count) = &mut totals[self.phase.index()];
+        *time += elapsed.saturating_sub(nested);
+        *count += 1;
+    }
+}
+
+/// A summary of where the time went.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// The time spent in each phase, and the number of spans, for the phases which were timed.
+    pub phases: Vec<(Phase, Duration, u64)>,
+}
+
+impl Report {
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, time, _)| *time).sum()
+    }
+
+    /// Returns the phase the most time was spent in.
+    pub fn bottleneck(&self) -> Option<Phase> {
+        self.phases.iter().max_by_key(|(_, time, _)| *time).map(|(phase, ..)| *phase)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        writeln!(f, "Time spent ({:.2?} in total):", total)?;
+        for (phase, time, count) in &self.phases {
+            let share = time.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON) * 100.0;
+            writeln!(
+                f,
+                "  {:<34} {:>10.2?} {share:>5.1}% ({count} spans)",
+                phase.to_string(),
+                time
+            )?;
+        }
+
+        match self.bottleneck() {
+            Some(Phase::Rpc | Phase::Replay) => writeln!(
+                f,
+                "Most time is spent on the RPC endpoint: a closer or local node, and keeping the \
+                 RPC cache, speed up the next sessions."
+            ),
+            Some(Phase::Explorer) => writeln!(
+                f,
+                "Most time is spent on the explorer: an API key raises its rate limit, and a \
+                 longer cache TTL avoids fetching the same sources again."
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the time spent so far, if the profiler is enabled.
+pub fn report() -> Option<Report> {
+    if !is_enabled() {
+        return None;
+    }
+    let totals = TOTALS.lock().unwrap_or_else(|e| e.into_inner());
+    let phases = Phase::ALL
+        .iter()
+        .zip(totals.iter())
+        .filter(|(_, (_, count))| *count > 0)
+        .map(|(phase, (time, count))| (*phase, *time, *count))
+        .collect();
+    Some(Report { phases })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+
+    use super::*;
+
+    #[test]
+    fn test_nested_spans() {
+        enable();
+        {
+            let _execute = span(Phase::Execute);
+            let _rpc = span(Phase::Rpc);
+            sleep(Duration::from_millis(20));
+        }
+
+        let report = report().unwrap();
+        assert_eq!(report.bottleneck(), Some(Phase::Rpc));
+        let (_, execution, count) =
+            report.phases.iter().find(|(p, ..)| *p == Phase::Execute).unwrap();
+        assert!(*execution < Duration::from_millis(20));
+        assert_eq!(*count, 1);
+        assert!(report.to_string().contains("RPC endpoint"));
+    }
+}